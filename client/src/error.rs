@@ -0,0 +1,68 @@
+/// Errors returned by the dealer API, mapped from its status codes and error
+/// messages.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("unauthorized")]
+    Unauthorized,
+    #[error("daily limit exceeded")]
+    DailyLimitExceeded,
+    #[error("amount below the minimum payout of the asset")]
+    BelowMinimumPayout,
+    #[error("asset not supported")]
+    UnsupportedAsset,
+    #[error("not enough liquidity for this amount")]
+    InsufficientLiquidity,
+    #[error("PIX temporarily unavailable")]
+    PixUnavailable,
+    #[error("dealer paused by its operators")]
+    Paused,
+    #[error("dealer returned {status}: {error} ({details})")]
+    Api {
+        status: u16,
+        error: String,
+        details: String,
+    },
+}
+
+impl Error {
+    /// Whether the same request may succeed later without changes.
+    pub fn is_temporary(&self) -> bool {
+        match self {
+            Error::Http(e) => e.is_timeout() || e.is_connect(),
+            Error::InsufficientLiquidity | Error::PixUnavailable | Error::Paused => true,
+            Error::Api { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn from_response(status: u16, body: &str) -> Self {
+        let body: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+        let field = |name: &str| {
+            body.get(name)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let error = field("error");
+
+        match (status, error.as_str()) {
+            (401, _) => Error::Unauthorized,
+            (403, "Daily limit exceeded") => Error::DailyLimitExceeded,
+            (422, "Amount below minimum payout") => Error::BelowMinimumPayout,
+            (501, "Invalid asset") => Error::UnsupportedAsset,
+            (503, "Insufficient liquidity") => Error::InsufficientLiquidity,
+            (503, "PIX temporarily unavailable") => Error::PixUnavailable,
+            (503, "Service paused") => Error::Paused,
+            (404, _) => Error::NotFound(error),
+            _ => Error::Api {
+                status,
+                error,
+                details: field("details"),
+            },
+        }
+    }
+}
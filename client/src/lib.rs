@@ -0,0 +1,467 @@
+//! Typed client for the Mooze Dealer HTTP API.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), mooze_dealer_client::Error> {
+//! use mooze_dealer_client::{DealerClient, NewDeposit};
+//!
+//! let client = DealerClient::new("https://dealer.example.com");
+//! let deposit = client
+//!     .create_deposit(&NewDeposit {
+//!         user_id: "user_uuid".to_string(),
+//!         address: "liquid_address".to_string(),
+//!         amount_in_cents: 10000,
+//!         asset: "asset_id".to_string(),
+//!         network: "liquid".to_string(),
+//!     })
+//!     .await?;
+//! let transaction = client.get_transaction(&deposit.id).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod error;
+mod models;
+
+use std::time::Duration;
+
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub use error::Error;
+pub use models::{Deposit, NewDeposit, NewUser, PartialPayout, Transaction, UserDetails};
+
+#[derive(Clone)]
+pub struct DealerClient {
+    base_url: String,
+    api_key: Option<String>,
+    client: reqwest::Client,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl DealerClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: None,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(15))
+                .build()
+                .expect("Could not build HTTP client."),
+            max_retries: 2,
+            retry_delay: Duration::from_millis(500),
+        }
+    }
+
+    /// Sent as a bearer token, for the merchant and admin routes.
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Retries of requests that fail with a timeout, a connection error or a
+    /// 502/503/504. Deposits are only retried when the request could not be
+    /// sent, since the dealer would create a second PIX charge.
+    pub fn with_retries(mut self, max_retries: u32, retry_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    pub async fn register_user(&self, user: &NewUser) -> Result<String, Error> {
+        let created: models::CreatedUser = self
+            .send(Method::POST, "/register", Some(user), false)
+            .await?;
+        Ok(created.user_id)
+    }
+
+    pub async fn create_deposit(&self, deposit: &NewDeposit) -> Result<Deposit, Error> {
+        self.send(Method::POST, "/deposit", Some(deposit), false)
+            .await
+    }
+
+    pub async fn get_transaction(&self, transaction_id: &str) -> Result<Transaction, Error> {
+        self.send::<(), _>(
+            Method::GET,
+            &format!("/transaction/{}", transaction_id),
+            None,
+            true,
+        )
+        .await
+    }
+
+    /// Cancels a deposit whose PIX charge wasn't paid yet. Not retried, as a
+    /// cancel that went through would be refused the second time.
+    pub async fn cancel_transaction(
+        &self,
+        transaction_id: &str,
+        user_id: &str,
+    ) -> Result<(), Error> {
+        let _: serde::de::IgnoredAny = self
+            .send(
+                Method::POST,
+                &format!("/transaction/{}/cancel", transaction_id),
+                Some(&serde_json::json!({ "user_id": user_id })),
+                false,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_user_details(&self, user_id: &str) -> Result<UserDetails, Error> {
+        self.send::<(), _>(Method::GET, &format!("/user/{}", user_id), None, true)
+            .await
+    }
+
+    async fn send<B: Serialize, T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+        idempotent: bool,
+    ) -> Result<T, Error> {
+        let mut attempt = 0;
+        loop {
+            let result = self.request(method.clone(), path, body).send().await;
+
+            let retryable = match &result {
+                Ok(response) => {
+                    idempotent
+                        && matches!(
+                            response.status(),
+                            StatusCode::BAD_GATEWAY
+                                | StatusCode::SERVICE_UNAVAILABLE
+                                | StatusCode::GATEWAY_TIMEOUT
+                        )
+                }
+                Err(e) => e.is_connect() || (idempotent && e.is_timeout()),
+            };
+            if retryable && attempt < self.max_retries {
+                attempt += 1;
+                tokio::time::sleep(self.retry_delay * attempt).await;
+                continue;
+            }
+
+            let response = result?;
+            let status = response.status();
+            if status.is_success() {
+                return Ok(response.json().await?);
+            }
+
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::from_response(status.as_u16(), &body));
+        }
+    }
+
+    fn request<B: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+    ) -> RequestBuilder {
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", self.base_url, path));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn client(server: &MockServer) -> DealerClient {
+        DealerClient::new(&server.uri()).with_retries(2, Duration::from_millis(1))
+    }
+
+    fn deposit() -> NewDeposit {
+        NewDeposit {
+            user_id: "user".to_string(),
+            address: "address".to_string(),
+            amount_in_cents: 10000,
+            asset: "asset".to_string(),
+            network: "liquid".to_string(),
+        }
+    }
+
+    fn transaction() -> serde_json::Value {
+        json!({
+            "id": "transaction",
+            "user_id": "user",
+            "amount_in_cents": 10000,
+            "asset": "asset",
+            "network": "liquid",
+            "status": "pending",
+            "created_at": "2025-06-01T12:00:00Z",
+            "updated_at": "2025-06-01T12:00:00Z"
+        })
+    }
+
+    #[tokio::test]
+    async fn retries_lookups_the_gateway_failed() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/transaction/transaction"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/transaction/transaction"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(transaction()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let transaction = client(&server)
+            .get_transaction("transaction")
+            .await
+            .unwrap();
+
+        assert_eq!(transaction.status, "pending");
+        assert!(transaction.partial_payouts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_last_retry() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/user/user"))
+            .respond_with(ResponseTemplate::new(502))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let error = client(&server).get_user_details("user").await.unwrap_err();
+
+        assert!(matches!(error, Error::Api { status: 502, .. }));
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_other_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/transaction/transaction"))
+            .respond_with(ResponseTemplate::new(500).set_body_json(json!({
+                "error": "Database error",
+                "details": "connection reset"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let error = client(&server)
+            .get_transaction("transaction")
+            .await
+            .unwrap_err();
+
+        match error {
+            Error::Api {
+                status,
+                error,
+                details,
+            } => {
+                assert_eq!(status, 500);
+                assert_eq!(error, "Database error");
+                assert_eq!(details, "connection reset");
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn never_retries_a_deposit_that_was_sent() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/deposit"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let error = client(&server)
+            .create_deposit(&deposit())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::Api { status: 503, .. }));
+    }
+
+    #[tokio::test]
+    async fn never_retries_a_cancel_that_was_sent() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/transaction/transaction/cancel"))
+            .and(body_json(json!({"user_id": "user"})))
+            .respond_with(ResponseTemplate::new(504))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let error = client(&server)
+            .cancel_transaction("transaction", "user")
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::Api { status: 504, .. }));
+    }
+
+    #[tokio::test]
+    async fn fails_temporarily_when_the_dealer_is_unreachable() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+
+        let error = DealerClient::new(&format!("http://{address}"))
+            .with_retries(2, Duration::from_millis(1))
+            .create_deposit(&deposit())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::Http(_)));
+        assert!(error.is_temporary());
+    }
+
+    #[tokio::test]
+    async fn sends_the_deposit_with_the_api_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/deposit"))
+            .and(header("authorization", "Bearer merchant-key"))
+            .and(body_json(json!({
+                "user_id": "user",
+                "address": "address",
+                "amount_in_cents": 10000,
+                "asset": "asset",
+                "network": "liquid"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "transaction",
+                "qr_copy_paste": "00020126",
+                "qr_image_url": "https://example.com/qr.png"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let deposit = client(&server)
+            .with_api_key("merchant-key")
+            .create_deposit(&deposit())
+            .await
+            .unwrap();
+
+        assert_eq!(deposit.id, "transaction");
+    }
+
+    #[tokio::test]
+    async fn sends_no_credentials_without_an_api_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/register"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "user_id": "user",
+                "api_key": "mzu_key"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let user_id = client(&server)
+            .register_user(&NewUser::default())
+            .await
+            .unwrap();
+
+        assert_eq!(user_id, "user");
+        let requests = server.received_requests().await.unwrap();
+        assert!(requests[0].headers.get("authorization").is_none());
+    }
+
+    #[tokio::test]
+    async fn maps_error_responses() {
+        // Status, body and whether the error is the expected one
+        type Case = (u16, serde_json::Value, fn(&Error) -> bool);
+        let cases: Vec<Case> = vec![
+            (401, json!({"error": "Unauthorized"}), |e| {
+                matches!(e, Error::Unauthorized)
+            }),
+            (403, json!({"error": "Daily limit exceeded"}), |e| {
+                matches!(e, Error::DailyLimitExceeded)
+            }),
+            (422, json!({"error": "Amount below minimum payout"}), |e| {
+                matches!(e, Error::BelowMinimumPayout)
+            }),
+            (501, json!({"error": "Invalid asset"}), |e| {
+                matches!(e, Error::UnsupportedAsset)
+            }),
+            (503, json!({"error": "Insufficient liquidity"}), |e| {
+                matches!(e, Error::InsufficientLiquidity)
+            }),
+            (503, json!({"error": "PIX temporarily unavailable"}), |e| {
+                matches!(e, Error::PixUnavailable)
+            }),
+            (503, json!({"error": "Service paused"}), |e| {
+                matches!(e, Error::Paused)
+            }),
+            (
+                404,
+                json!({"error": "Transaction not found"}),
+                |e| matches!(e, Error::NotFound(error) if error == "Transaction not found"),
+            ),
+            (
+                403,
+                json!({"error": "Forbidden"}),
+                |e| matches!(e, Error::Api { status: 403, error, .. } if error == "Forbidden"),
+            ),
+        ];
+
+        for (status, body, expected) in cases {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/deposit"))
+                .respond_with(ResponseTemplate::new(status).set_body_json(body.clone()))
+                .mount(&server)
+                .await;
+
+            let error = client(&server)
+                .create_deposit(&deposit())
+                .await
+                .unwrap_err();
+
+            assert!(expected(&error), "{status} {body} gave {error:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn maps_bodies_that_are_not_json() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/user/user"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("Bad Request"))
+            .mount(&server)
+            .await;
+
+        let error = client(&server).get_user_details("user").await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            Error::Api { status: 400, ref error, .. } if error.is_empty()
+        ));
+        assert!(!error.is_temporary());
+    }
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct NewUser {
+    pub referral_code: Option<String>,
+    /// Installation id of the app; accounts from the same installation share
+    /// their daily limits.
+    pub installation_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NewDeposit {
+    pub user_id: String,
+    /// Liquid address receiving the payout.
+    pub address: String,
+    pub amount_in_cents: i32,
+    /// Asset id of the payout.
+    pub asset: String,
+    pub network: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Deposit {
+    /// Transaction id.
+    pub id: String,
+    pub qr_copy_paste: String,
+    pub qr_image_url: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Transaction {
+    pub id: String,
+    pub user_id: String,
+    pub amount_in_cents: i32,
+    pub asset: String,
+    pub network: String,
+    pub status: String,
+    /// Parts of the payout sent while the dealer covered only some of it.
+    #[serde(default)]
+    pub partial_payouts: Vec<PartialPayout>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PartialPayout {
+    /// Base units of the transaction's asset.
+    pub amount: i64,
+    pub txid: Option<String>,
+    pub sent_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct UserDetails {
+    pub user_id: String,
+    pub daily_spending: i64,
+    pub allowed_spending: i64,
+    pub verified: bool,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct CreatedUser {
+    pub user_id: String,
+}
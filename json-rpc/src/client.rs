@@ -0,0 +1,589 @@
+use crate::transport::{Connection, Frame, Transport};
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
+use uuid::Uuid;
+
+type PendingRequests = Arc<Mutex<HashMap<String, PendingRequest>>>;
+
+/// Notifications kept for a slow consumer; past this the oldest are dropped.
+const NOTIFICATION_CAPACITY: usize = 1024;
+
+struct PendingRequest {
+    sender: oneshot::Sender<Value>,
+    sent_at: Instant,
+}
+
+/// The server did not answer a call in time.
+#[derive(Debug, thiserror::Error)]
+#[error("JSON-RPC call {method} timed out after {}s", timeout.as_secs())]
+pub struct Timeout {
+    pub method: String,
+    pub timeout: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// How long a call waits for its response.
+    pub call_timeout: Duration,
+    /// A ping is sent every interval; without any frame from the server for
+    /// two intervals the connection counts as lost.
+    pub ping_interval: Duration,
+    /// Attempts to connect before giving up, waiting `connect_backoff`
+    /// after the first failure and twice as long after each next one.
+    pub connect_attempts: u32,
+    pub connect_backoff: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            call_timeout: Duration::from_secs(30),
+            ping_interval: Duration::from_secs(30),
+            connect_attempts: 3,
+            connect_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// What happens on a client, e.g. to export it as metrics. Does nothing by
+/// default.
+pub trait Observer: Send + Sync + 'static {
+    fn call_timed_out(&self, _method: &str) {}
+    /// Notifications waiting to be read.
+    fn notification_queue(&self, _depth: usize) {}
+    fn notifications_dropped(&self, _dropped: u64) {}
+    /// Calls waiting for a response, after `orphaned` ones nobody waited
+    /// for anymore were dropped.
+    fn pending_requests(&self, _pending: usize, _orphaned: usize) {}
+    /// The server stopped answering pings.
+    fn connection_lost(&self) {}
+}
+
+pub struct NoObserver;
+
+impl Observer for NoObserver {}
+
+pub struct JsonRpcClient {
+    /// Frames to send, written by a task of their own
+    sender: mpsc::UnboundedSender<Frame>,
+    /// Calls waiting for their response, by id
+    pending_requests: PendingRequests,
+    call_timeout: Duration,
+    /// False once the connection closed or stopped answering pings
+    connected: watch::Receiver<bool>,
+    observer: Arc<dyn Observer>,
+}
+
+/// Notifications of a client, oldest first. Only kept while this is alive,
+/// and dropped oldest first when it falls behind.
+pub struct Notifications {
+    receiver: broadcast::Receiver<Value>,
+    observer: Arc<dyn Observer>,
+}
+
+impl Notifications {
+    /// Next notification, or none once the connection is lost and every
+    /// notification was taken.
+    pub async fn next(&mut self) -> Option<Value> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(notification) => {
+                    self.observer.notification_queue(self.receiver.len());
+                    return Some(notification);
+                }
+                Err(RecvError::Lagged(dropped)) => {
+                    log::warn!("Dropped {} JSON-RPC notifications nobody read", dropped);
+                    self.observer.notifications_dropped(dropped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl JsonRpcClient {
+    /// Connects through `transport`, retrying as `config` allows.
+    /// Notifications go to the returned queue, so they are read or dropped
+    /// with it.
+    ///
+    /// A lost connection is not opened again: whatever the session set up,
+    /// such as a login or subscriptions, is gone with it, so the caller
+    /// connects a new client and sets it up again.
+    pub async fn connect(
+        transport: &dyn Transport,
+        config: Config,
+        observer: Arc<dyn Observer>,
+    ) -> Result<(Self, Notifications), anyhow::Error> {
+        let connection = connect_with_retries(transport, &config).await?;
+        Ok(Self::start(connection, config, observer))
+    }
+
+    fn start(
+        connection: Connection,
+        config: Config,
+        observer: Arc<dyn Observer>,
+    ) -> (Self, Notifications) {
+        let Connection {
+            sender: mut write,
+            receiver: mut read,
+        } = connection;
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let pending_requests: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
+        let (notifications, receiver) = broadcast::channel(NOTIFICATION_CAPACITY);
+        let (connected_tx, connected) = watch::channel(true);
+        let connected_tx = Arc::new(connected_tx);
+        let last_seen = Arc::new(std::sync::Mutex::new(Instant::now()));
+
+        tokio::spawn(async move {
+            while let Some(frame) = rx.recv().await {
+                if let Err(e) = write.send(frame).await {
+                    log::error!("Could not send on the JSON-RPC connection: {}", e);
+                    break;
+                }
+            }
+        });
+
+        let read_pending_requests = pending_requests.clone();
+        let read_connected = connected_tx.clone();
+        let read_last_seen = last_seen.clone();
+        let read_observer = observer.clone();
+
+        tokio::spawn(async move {
+            while let Some(frame) = read.next().await {
+                let frame = match frame {
+                    Ok(frame) => frame,
+                    Err(e) => {
+                        log::error!("Could not read from the JSON-RPC connection: {}", e);
+                        break;
+                    }
+                };
+                if let Ok(mut last_seen) = read_last_seen.lock() {
+                    *last_seen = Instant::now();
+                }
+
+                let text = match frame {
+                    Frame::Text(text) => text,
+                    _ => continue,
+                };
+
+                let value: Value = match serde_json::from_str(&text) {
+                    Ok(value) => value,
+                    Err(e) => {
+                        log::error!("Could not parse JSON-RPC message {}: {}", text, e);
+                        continue;
+                    }
+                };
+                log::trace!("JSON-RPC received: {}", value);
+
+                // Responses carry the id of their call, notifications none
+                match value
+                    .get("id")
+                    .and_then(|id| id.as_str())
+                    .map(str::to_string)
+                {
+                    Some(id) => {
+                        let pending = read_pending_requests.lock().await.remove(&id);
+                        match pending {
+                            Some(pending) => {
+                                log::debug!(
+                                    "JSON-RPC call {} answered in {}ms",
+                                    id,
+                                    pending.sent_at.elapsed().as_millis()
+                                );
+                                if pending.sender.send(value).is_err() {
+                                    log::warn!("Nobody waits for JSON-RPC call {} anymore", id);
+                                }
+                            }
+                            None => log::warn!("JSON-RPC response to unknown call {}", id),
+                        }
+                    }
+                    None => {
+                        if notifications.send(value).is_err() {
+                            read_observer.notifications_dropped(1);
+                        }
+                        read_observer.notification_queue(notifications.len());
+                    }
+                }
+            }
+
+            log::warn!("JSON-RPC connection closed");
+            read_connected.send_replace(false);
+        });
+
+        tokio::spawn(keep_alive(
+            tx.downgrade(),
+            connected_tx,
+            last_seen,
+            config.ping_interval,
+            observer.clone(),
+        ));
+
+        tokio::spawn(sweep_pending_requests(
+            Arc::downgrade(&pending_requests),
+            config.call_timeout,
+            observer.clone(),
+        ));
+
+        (
+            Self {
+                sender: tx,
+                pending_requests,
+                call_timeout: config.call_timeout,
+                connected,
+                observer: observer.clone(),
+            },
+            Notifications { receiver, observer },
+        )
+    }
+
+    /// Changes to false once the connection is lost, for good: a new client
+    /// has to be connected.
+    pub fn connection(&self) -> watch::Receiver<bool> {
+        self.connected.clone()
+    }
+
+    pub async fn call_method(
+        &self,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Value, anyhow::Error> {
+        let id = Uuid::new_v4().to_string();
+        let request = json!({
+            "id": id,
+            "method": method,
+            "params": params
+        });
+        log::debug!("JSON-RPC call {} to {}", id, method);
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.pending_requests.lock().await.insert(
+            id.clone(),
+            PendingRequest {
+                sender: resp_tx,
+                sent_at: Instant::now(),
+            },
+        );
+        if let Err(e) = self.sender.send(Frame::Text(request.to_string())) {
+            self.pending_requests.lock().await.remove(&id);
+            return Err(e.into());
+        }
+
+        match tokio::time::timeout(self.call_timeout, resp_rx).await {
+            Ok(response) => Ok(response?),
+            Err(_) => {
+                self.pending_requests.lock().await.remove(&id);
+                log::warn!("JSON-RPC call {} to {} timed out", id, method);
+                self.observer.call_timed_out(method);
+                Err(Timeout {
+                    method: method.to_string(),
+                    timeout: self.call_timeout,
+                }
+                .into())
+            }
+        }
+    }
+}
+
+async fn connect_with_retries(
+    transport: &dyn Transport,
+    config: &Config,
+) -> Result<Connection, anyhow::Error> {
+    let mut backoff = config.connect_backoff;
+    let mut attempt = 1;
+
+    loop {
+        match transport.connect().await {
+            Ok(connection) => return Ok(connection),
+            Err(e) if attempt < config.connect_attempts => {
+                log::warn!(
+                    "Could not connect to the JSON-RPC server (attempt {} of {}), retrying in {}ms: {}",
+                    attempt,
+                    config.connect_attempts,
+                    backoff.as_millis(),
+                    e
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Drops pending requests nobody waits for anymore, e.g. calls whose future
+/// was dropped before the response came in. Stops with the client.
+async fn sweep_pending_requests(
+    pending_requests: Weak<Mutex<HashMap<String, PendingRequest>>>,
+    call_timeout: Duration,
+    observer: Arc<dyn Observer>,
+) {
+    let mut interval = tokio::time::interval(call_timeout);
+
+    loop {
+        interval.tick().await;
+        let Some(pending_requests) = pending_requests.upgrade() else {
+            break;
+        };
+
+        let mut pending_requests = pending_requests.lock().await;
+        let before = pending_requests.len();
+        pending_requests.retain(|_, pending| {
+            !pending.sender.is_closed() && pending.sent_at.elapsed() <= call_timeout * 2
+        });
+
+        let orphaned = before - pending_requests.len();
+        if orphaned > 0 {
+            log::warn!("Dropped {} orphaned JSON-RPC requests", orphaned);
+        }
+        observer.pending_requests(pending_requests.len(), orphaned);
+    }
+}
+
+/// Pings the server, and marks the connection lost when nothing came back
+/// for two intervals, so callers reconnect before the next call fails.
+/// Stops with the client.
+async fn keep_alive(
+    sender: mpsc::WeakUnboundedSender<Frame>,
+    connected: Arc<watch::Sender<bool>>,
+    last_seen: Arc<std::sync::Mutex<Instant>>,
+    ping_interval: Duration,
+    observer: Arc<dyn Observer>,
+) {
+    let mut interval = tokio::time::interval(ping_interval);
+
+    loop {
+        interval.tick().await;
+        if !*connected.borrow() {
+            break;
+        }
+
+        let silent_for = match last_seen.lock() {
+            Ok(last_seen) => last_seen.elapsed(),
+            Err(_) => break,
+        };
+        if silent_for > ping_interval * 2 {
+            log::warn!(
+                "No answer on the JSON-RPC connection for {}s, closing it",
+                silent_for.as_secs()
+            );
+            observer.connection_lost();
+            connected.send_replace(false);
+            break;
+        }
+
+        let Some(sender) = sender.upgrade() else {
+            break;
+        };
+        if sender.send(Frame::Ping).is_err() {
+            connected.send_replace(false);
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use futures_util::{sink, stream};
+    use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+    /// The far end of a mock connection.
+    struct Server {
+        received: mpsc::UnboundedReceiver<Frame>,
+        send: mpsc::UnboundedSender<Result<Frame, anyhow::Error>>,
+    }
+
+    impl Server {
+        /// Answers the next call with `result`.
+        async fn answer(&mut self, result: Value) -> Value {
+            loop {
+                match self.received.recv().await.expect("client hung up") {
+                    Frame::Text(text) => {
+                        let request: Value = serde_json::from_str(&text).unwrap();
+                        let response = json!({"id": request["id"], "result": result});
+                        self.send
+                            .send(Ok(Frame::Text(response.to_string())))
+                            .unwrap();
+                        return request;
+                    }
+                    _ => continue,
+                }
+            }
+        }
+
+        fn notify(&self, notification: Value) {
+            self.send
+                .send(Ok(Frame::Text(notification.to_string())))
+                .unwrap();
+        }
+    }
+
+    fn mock_connection() -> (Connection, Server) {
+        let (client_tx, received) = mpsc::unbounded_channel();
+        let (send, client_rx) = mpsc::unbounded_channel();
+
+        let sender = sink::unfold(
+            client_tx,
+            |tx: mpsc::UnboundedSender<Frame>, frame| async move {
+                tx.send(frame)?;
+                Ok::<_, anyhow::Error>(tx)
+            },
+        );
+        let receiver = stream::unfold(client_rx, |mut rx| async move {
+            rx.recv().await.map(|frame| (frame, rx))
+        });
+
+        (
+            Connection {
+                sender: Box::pin(sender),
+                receiver: Box::pin(receiver),
+            },
+            Server { received, send },
+        )
+    }
+
+    /// Fails the first `failures` attempts, then hands out its connection.
+    struct MockTransport {
+        failures: AtomicU32,
+        attempts: AtomicU32,
+        connection: std::sync::Mutex<Option<Connection>>,
+    }
+
+    #[async_trait]
+    impl Transport for MockTransport {
+        async fn connect(&self) -> Result<Connection, anyhow::Error> {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            if self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                    left.checked_sub(1)
+                })
+                .is_ok()
+            {
+                anyhow::bail!("connection refused");
+            }
+
+            self.connection
+                .lock()
+                .unwrap()
+                .take()
+                .ok_or_else(|| anyhow::anyhow!("already connected"))
+        }
+    }
+
+    #[derive(Default)]
+    struct CountingObserver {
+        timeouts: AtomicUsize,
+    }
+
+    impl Observer for CountingObserver {
+        fn call_timed_out(&self, _method: &str) {
+            self.timeouts.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            call_timeout: Duration::from_millis(200),
+            ping_interval: Duration::from_secs(60),
+            connect_attempts: 3,
+            connect_backoff: Duration::from_millis(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn matches_responses_to_calls() {
+        let (connection, mut server) = mock_connection();
+        let (client, _) = JsonRpcClient::start(connection, config(), Arc::new(NoObserver));
+
+        let (response, request) = tokio::join!(
+            client.call_method("market", Some(json!({"list_markets": {}}))),
+            server.answer(json!({"list_markets": {"markets": []}}))
+        );
+
+        assert_eq!(request["method"], "market");
+        assert_eq!(request["params"], json!({"list_markets": {}}));
+        let response = response.unwrap();
+        assert_eq!(response["id"], request["id"]);
+        assert_eq!(response["result"]["list_markets"]["markets"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn queues_notifications_apart() {
+        let (connection, server) = mock_connection();
+        let (_client, mut notifications) =
+            JsonRpcClient::start(connection, config(), Arc::new(NoObserver));
+
+        server.notify(json!({"method": "server_status", "params": {}}));
+        server.notify(json!({"method": "market", "params": {"quote": {}}}));
+
+        let first = notifications.next().await.unwrap();
+        assert_eq!(first["method"], "server_status");
+        let second = notifications.next().await.unwrap();
+        assert_eq!(second["method"], "market");
+    }
+
+    #[tokio::test]
+    async fn calls_time_out() {
+        let (connection, _server) = mock_connection();
+        let observer = Arc::new(CountingObserver::default());
+        let (client, _) = JsonRpcClient::start(connection, config(), observer.clone());
+
+        let error = client.call_method("market", None).await.unwrap_err();
+
+        let timeout = error
+            .downcast_ref::<Timeout>()
+            .expect("should be a Timeout");
+        assert_eq!(timeout.method, "market");
+        assert_eq!(observer.timeouts.load(Ordering::SeqCst), 1);
+        assert!(client.pending_requests.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn closed_connections_are_lost() {
+        let (connection, server) = mock_connection();
+        let (client, _) = JsonRpcClient::start(connection, config(), Arc::new(NoObserver));
+        let mut connected = client.connection();
+        assert!(*connected.borrow());
+
+        drop(server);
+
+        connected.wait_for(|connected| !connected).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn retries_connecting() {
+        let (connection, _server) = mock_connection();
+        let transport = MockTransport {
+            failures: AtomicU32::new(2),
+            attempts: AtomicU32::new(0),
+            connection: std::sync::Mutex::new(Some(connection)),
+        };
+
+        let connected = JsonRpcClient::connect(&transport, config(), Arc::new(NoObserver)).await;
+        assert!(connected.is_ok());
+        assert_eq!(transport.attempts.load(Ordering::SeqCst), 3);
+
+        let (connection, _server) = mock_connection();
+        let transport = MockTransport {
+            failures: AtomicU32::new(3),
+            attempts: AtomicU32::new(0),
+            connection: std::sync::Mutex::new(Some(connection)),
+        };
+
+        let connected = JsonRpcClient::connect(&transport, config(), Arc::new(NoObserver)).await;
+        assert!(connected.is_err());
+        assert_eq!(transport.attempts.load(Ordering::SeqCst), 3);
+    }
+}
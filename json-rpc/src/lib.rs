@@ -0,0 +1,11 @@
+//! JSON-RPC over a websocket, as spoken to Sideswap by the dealer and
+//! mooze-swap: calls matched to their responses by id, with a timeout,
+//! notifications queued apart, and pings to notice a dead connection. The
+//! connection comes from a [`Transport`], a websocket in production and
+//! anything that passes frames around in tests.
+
+mod client;
+mod transport;
+
+pub use client::{Config, JsonRpcClient, NoObserver, Notifications, Observer, Timeout};
+pub use transport::{Connection, Frame, Transport, WebSocket};
@@ -0,0 +1,74 @@
+use async_trait::async_trait;
+use futures_util::{future, Sink, SinkExt, Stream, StreamExt};
+use std::pin::Pin;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+/// A websocket frame, as far as JSON-RPC cares.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Frame {
+    Text(String),
+    Ping,
+    Pong,
+    Close,
+}
+
+/// Both ends of an open connection.
+pub struct Connection {
+    pub sender: Pin<Box<dyn Sink<Frame, Error = anyhow::Error> + Send>>,
+    /// Ends when the connection closes.
+    pub receiver: Pin<Box<dyn Stream<Item = Result<Frame, anyhow::Error>> + Send>>,
+}
+
+/// Opens connections to a JSON-RPC server.
+#[async_trait]
+pub trait Transport: Send + Sync + 'static {
+    async fn connect(&self) -> Result<Connection, anyhow::Error>;
+}
+
+/// A websocket at `url`, `ws://` or `wss://`.
+pub struct WebSocket {
+    url: String,
+}
+
+impl WebSocket {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for WebSocket {
+    async fn connect(&self) -> Result<Connection, anyhow::Error> {
+        let (stream, _) = connect_async(self.url.as_str()).await?;
+        let (write, read) = stream.split();
+
+        let sender = write
+            .sink_map_err(anyhow::Error::from)
+            .with(|frame: Frame| {
+                future::ok::<_, anyhow::Error>(match frame {
+                    Frame::Text(text) => Message::Text(text.into()),
+                    Frame::Ping => Message::Ping(Default::default()),
+                    Frame::Pong => Message::Pong(Default::default()),
+                    Frame::Close => Message::Close(None),
+                })
+            });
+        // Binary frames mean nothing to JSON-RPC
+        let receiver = read.filter_map(|message| {
+            future::ready(match message {
+                Ok(Message::Text(text)) => Some(Ok(Frame::Text(text.to_string()))),
+                Ok(Message::Ping(_)) => Some(Ok(Frame::Ping)),
+                Ok(Message::Pong(_)) => Some(Ok(Frame::Pong)),
+                Ok(Message::Close(_)) => Some(Ok(Frame::Close)),
+                Ok(Message::Binary(_) | Message::Frame(_)) => None,
+                Err(e) => Some(Err(e.into())),
+            })
+        });
+
+        Ok(Connection {
+            sender: Box::pin(sender),
+            receiver: Box::pin(receiver),
+        })
+    }
+}
@@ -0,0 +1,10 @@
+-- Installation ids bound to accounts at registration, stored as SHA-256
+-- fingerprints. Accounts sharing a fingerprint get combined limits.
+CREATE TABLE IF NOT EXISTS user_devices (
+    user_id TEXT NOT NULL REFERENCES users (id),
+    device_fingerprint TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, device_fingerprint)
+);
+
+CREATE INDEX IF NOT EXISTS user_devices_fingerprint_idx ON user_devices (device_fingerprint);
@@ -0,0 +1,17 @@
+-- Payout addresses that must never receive funds, maintained by compliance.
+CREATE TABLE IF NOT EXISTS address_denylist (
+    address TEXT PRIMARY KEY,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Screening outcomes other than a clean allow, one per transaction and source.
+CREATE TABLE IF NOT EXISTS compliance_flags (
+    transaction_id TEXT NOT NULL REFERENCES transactions (id),
+    address TEXT NOT NULL,
+    source TEXT NOT NULL,
+    action TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (transaction_id, source)
+);
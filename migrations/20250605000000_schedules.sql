@@ -0,0 +1,22 @@
+-- Recurring buy orders. The scheduler creates a PIX charge through the
+-- regular deposit flow every time next_run_at is reached.
+CREATE TABLE IF NOT EXISTS schedules (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES users (id),
+    address TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    network TEXT NOT NULL,
+    amount_in_cents INTEGER NOT NULL,
+    frequency TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'active',
+    next_run_at TIMESTAMPTZ NOT NULL,
+    last_transaction_id TEXT,
+    last_qr_copy_paste TEXT,
+    last_qr_image_url TEXT,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS schedules_due_idx ON schedules (next_run_at) WHERE status = 'active';
+CREATE INDEX IF NOT EXISTS schedules_user_id_idx ON schedules (user_id);
@@ -0,0 +1,28 @@
+-- Merchants receiving PIX payments through payment links. Each merchant is
+-- backed by a user account so link payments go through the regular
+-- transactions table; the API key is stored as a SHA-256 hash.
+CREATE TABLE IF NOT EXISTS merchants (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES users (id),
+    name TEXT NOT NULL,
+    api_key_hash TEXT NOT NULL UNIQUE,
+    payout_address TEXT NOT NULL,
+    payout_asset TEXT NOT NULL,
+    network TEXT NOT NULL,
+    fee_bps INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- One PIX charge per link, paid out to the merchant on settlement.
+CREATE TABLE IF NOT EXISTS payment_links (
+    token TEXT PRIMARY KEY,
+    merchant_id TEXT NOT NULL REFERENCES merchants (id),
+    transaction_id TEXT NOT NULL UNIQUE REFERENCES transactions (id),
+    amount_in_cents INTEGER NOT NULL,
+    description TEXT,
+    qr_copy_paste TEXT NOT NULL,
+    qr_image_url TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS payment_links_merchant_id_idx ON payment_links (merchant_id);
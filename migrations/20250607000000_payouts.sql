@@ -0,0 +1,10 @@
+-- Broadcast attempts of payouts. A row is written before the payout is
+-- broadcast and completed with its txid afterwards, so a crash in between
+-- can be told apart from a payout that never started.
+CREATE TABLE IF NOT EXISTS payouts (
+    transaction_id TEXT PRIMARY KEY REFERENCES transactions (id),
+    txid TEXT,
+    status TEXT NOT NULL DEFAULT 'broadcasting',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
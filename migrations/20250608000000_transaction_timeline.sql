@@ -0,0 +1,37 @@
+-- Every status a transaction went through, whichever code path changed it.
+CREATE TABLE IF NOT EXISTS transaction_status_history (
+    id BIGSERIAL PRIMARY KEY,
+    transaction_id TEXT NOT NULL REFERENCES transactions (id),
+    status TEXT NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS transaction_status_history_transaction_id_idx
+    ON transaction_status_history (transaction_id);
+
+CREATE OR REPLACE FUNCTION record_transaction_status() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' OR NEW.status IS DISTINCT FROM OLD.status THEN
+        INSERT INTO transaction_status_history (transaction_id, status)
+        VALUES (NEW.id, NEW.status);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS transactions_status_history ON transactions;
+CREATE TRIGGER transactions_status_history
+    AFTER INSERT OR UPDATE OF status ON transactions
+    FOR EACH ROW EXECUTE FUNCTION record_transaction_status();
+
+-- Liquidity swaps started to fund a payout.
+CREATE TABLE IF NOT EXISTS payout_swaps (
+    transaction_id TEXT NOT NULL REFERENCES transactions (id),
+    quote_sub_id BIGINT NOT NULL,
+    txid TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMPTZ,
+    PRIMARY KEY (transaction_id, quote_sub_id)
+);
+
+CREATE INDEX IF NOT EXISTS payout_swaps_quote_sub_id_idx ON payout_swaps (quote_sub_id);
@@ -0,0 +1,16 @@
+-- Every webhook received from Eulen, stored as received before it is
+-- processed, so disputed statuses can be checked against what was sent.
+CREATE TABLE IF NOT EXISTS webhook_events (
+    id TEXT PRIMARY KEY,
+    source TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    headers TEXT NOT NULL DEFAULT '{}',
+    bank_tx_ids TEXT[] NOT NULL DEFAULT '{}',
+    qr_ids TEXT[] NOT NULL DEFAULT '{}',
+    outcome TEXT,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    processed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS webhook_events_bank_tx_ids_idx ON webhook_events USING GIN (bank_tx_ids);
+CREATE INDEX IF NOT EXISTS webhook_events_qr_ids_idx ON webhook_events USING GIN (qr_ids);
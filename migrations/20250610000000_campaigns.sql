@@ -0,0 +1,23 @@
+-- Time-boxed fee campaigns and their redemptions. A user redeems a
+-- campaign at most once.
+CREATE TABLE IF NOT EXISTS campaigns (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    fee_discount_bps INTEGER NOT NULL CHECK (fee_discount_bps BETWEEN 0 AND 10000),
+    first_purchase_only BOOLEAN NOT NULL DEFAULT FALSE,
+    max_amount_in_cents INTEGER,
+    asset TEXT,
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS campaign_redemptions (
+    campaign_id TEXT NOT NULL REFERENCES campaigns (id),
+    user_id TEXT NOT NULL,
+    transaction_id TEXT NOT NULL UNIQUE REFERENCES transactions (id),
+    fee_discount BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (campaign_id, user_id)
+);
@@ -0,0 +1,7 @@
+-- Switches flipped by operators at runtime, kept across restarts.
+CREATE TABLE IF NOT EXISTS operator_flags (
+    name TEXT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL,
+    updated_by TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
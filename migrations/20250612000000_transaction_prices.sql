@@ -0,0 +1,9 @@
+-- Price each payout was computed with, and how the provider quotes were
+-- combined into it. Rewritten when a payout is retried at a new price.
+CREATE TABLE IF NOT EXISTS transaction_prices (
+    transaction_id TEXT PRIMARY KEY REFERENCES transactions (id),
+    quote_currency TEXT NOT NULL,
+    price_in_cents BIGINT NOT NULL,
+    aggregation TEXT,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
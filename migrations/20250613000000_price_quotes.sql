@@ -0,0 +1,17 @@
+-- Prices locked for a short time between the app's preview and the deposit.
+-- A quote is used by at most one transaction.
+CREATE TABLE IF NOT EXISTS price_quotes (
+    id TEXT PRIMARY KEY,
+    asset TEXT NOT NULL,
+    quote_currency TEXT NOT NULL,
+    price_in_cents BIGINT NOT NULL,
+    aggregation TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS transaction_quotes (
+    transaction_id TEXT PRIMARY KEY REFERENCES transactions (id),
+    quote_id TEXT NOT NULL UNIQUE REFERENCES price_quotes (id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
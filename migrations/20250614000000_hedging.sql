@@ -0,0 +1,30 @@
+-- Price exposure ledger of the hedging module. Payouts sell an asset for
+-- BRL, hedges buy it back with DEPIX. asset_amount is negative when sold;
+-- brl_cents is the BRL basis of the open position, received on payouts and
+-- released at the average price on hedges.
+CREATE TABLE IF NOT EXISTS exposure_ledger (
+    id BIGSERIAL PRIMARY KEY,
+    asset TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    reference TEXT NOT NULL,
+    asset_amount BIGINT NOT NULL,
+    brl_cents BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (kind, reference)
+);
+
+CREATE INDEX IF NOT EXISTS exposure_ledger_asset_idx ON exposure_ledger (asset);
+
+-- Offsetting swaps placed on Sideswap. Open hedges count against the
+-- exposure until they fill or expire.
+CREATE TABLE IF NOT EXISTS hedges (
+    quote_sub_id BIGINT PRIMARY KEY,
+    asset TEXT NOT NULL,
+    expected_amount BIGINT NOT NULL,
+    depix_amount BIGINT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'open',
+    received_amount BIGINT,
+    realized_pnl_cents BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    closed_at TIMESTAMPTZ
+);
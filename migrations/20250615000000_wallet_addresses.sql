@@ -0,0 +1,13 @@
+-- Addresses handed out by the hot wallet and the derivation index they were
+-- taken from, so a restored wallet neither reuses nor misses them. chain is
+-- external for receive addresses and internal for change.
+CREATE TABLE IF NOT EXISTS wallet_addresses (
+    address TEXT PRIMARY KEY,
+    chain TEXT NOT NULL,
+    derivation_index INTEGER NOT NULL,
+    transaction_id TEXT REFERENCES transactions (id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (chain, derivation_index)
+);
+
+CREATE INDEX IF NOT EXISTS wallet_addresses_transaction_idx ON wallet_addresses (transaction_id);
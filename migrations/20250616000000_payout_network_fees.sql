@@ -0,0 +1,2 @@
+-- L-BTC paid to the network by each payout, in sats.
+ALTER TABLE payouts ADD COLUMN IF NOT EXISTS network_fee BIGINT;
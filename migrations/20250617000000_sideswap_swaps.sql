@@ -0,0 +1,21 @@
+-- Every swap executed on Sideswap, with the fees it charged and the oracle
+-- prices at execution, for the treasury report. Prices are BRL cents per
+-- whole unit and empty when the price service had none.
+CREATE TABLE IF NOT EXISTS sideswap_swaps (
+    quote_sub_id BIGINT PRIMARY KEY,
+    quote_id BIGINT NOT NULL,
+    txid TEXT NOT NULL,
+    sell_asset TEXT NOT NULL,
+    receive_asset TEXT NOT NULL,
+    sold_amount BIGINT NOT NULL,
+    received_amount BIGINT NOT NULL,
+    fee_asset TEXT,
+    server_fee BIGINT NOT NULL,
+    fixed_fee BIGINT NOT NULL,
+    sell_price_in_cents BIGINT,
+    receive_price_in_cents BIGINT,
+    fee_price_in_cents BIGINT,
+    executed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS sideswap_swaps_executed_at_idx ON sideswap_swaps (executed_at);
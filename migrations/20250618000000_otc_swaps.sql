@@ -0,0 +1,20 @@
+-- Swaps executed with the OTC desk. Ids share the namespace of Sideswap
+-- quote_sub_ids in payout_swaps and hedges, so they start far above them.
+CREATE SEQUENCE IF NOT EXISTS otc_swaps_id_seq START WITH 1000000000000;
+
+CREATE TABLE IF NOT EXISTS otc_swaps (
+    id BIGINT PRIMARY KEY DEFAULT nextval('otc_swaps_id_seq'),
+    external_id TEXT NOT NULL UNIQUE,
+    sell_asset TEXT NOT NULL,
+    receive_asset TEXT NOT NULL,
+    sold_amount BIGINT NOT NULL,
+    expected_amount BIGINT NOT NULL,
+    deposit_txid TEXT,
+    -- accepted, deposited, completed or failed
+    status TEXT NOT NULL DEFAULT 'accepted',
+    settlement_txid TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    closed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS otc_swaps_status_idx ON otc_swaps (status);
@@ -0,0 +1,11 @@
+-- Admin searches page through transactions and PIX charges newest first,
+-- filtered by status, user and amount.
+CREATE INDEX IF NOT EXISTS transactions_created_at_idx ON transactions (created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS transactions_user_id_created_at_idx ON transactions (user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS transactions_status_created_at_idx ON transactions (status, created_at DESC);
+CREATE INDEX IF NOT EXISTS transactions_amount_idx ON transactions (amount_in_cents);
+
+CREATE INDEX IF NOT EXISTS pix_transactions_created_at_idx ON pix_transactions (created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS pix_transactions_status_created_at_idx ON pix_transactions (status, created_at DESC);
+CREATE INDEX IF NOT EXISTS pix_transactions_amount_idx ON pix_transactions (amount_in_cents);
+CREATE INDEX IF NOT EXISTS pix_transactions_transaction_id_idx ON pix_transactions (transaction_id);
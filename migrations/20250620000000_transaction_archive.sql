@@ -0,0 +1,40 @@
+-- Settled transactions and their PIX charges older than `archive.after_months`
+-- are moved here by the archival job. Rows keep their ids, so history,
+-- prices and payouts recorded against them still join through the
+-- all_transactions and all_pix_transactions views. The archives get the
+-- columns and indexes of the live tables.
+CREATE TABLE IF NOT EXISTS transactions_archive (LIKE transactions INCLUDING DEFAULTS INCLUDING INDEXES);
+CREATE TABLE IF NOT EXISTS pix_transactions_archive (LIKE pix_transactions INCLUDING DEFAULTS INCLUDING INDEXES);
+
+-- A column added to transactions or pix_transactions later must be added to
+-- its archive too, and the view recreated.
+CREATE OR REPLACE VIEW all_transactions AS
+    SELECT * FROM transactions
+    UNION ALL
+    SELECT * FROM transactions_archive;
+
+CREATE OR REPLACE VIEW all_pix_transactions AS
+    SELECT * FROM pix_transactions
+    UNION ALL
+    SELECT * FROM pix_transactions_archive;
+
+-- Archived transactions leave the table these keys point to
+ALTER TABLE transaction_quotes DROP CONSTRAINT IF EXISTS transaction_quotes_transaction_id_fkey;
+ALTER TABLE payment_links DROP CONSTRAINT IF EXISTS payment_links_transaction_id_fkey;
+ALTER TABLE campaign_redemptions DROP CONSTRAINT IF EXISTS campaign_redemptions_transaction_id_fkey;
+ALTER TABLE payouts DROP CONSTRAINT IF EXISTS payouts_transaction_id_fkey;
+ALTER TABLE compliance_flags DROP CONSTRAINT IF EXISTS compliance_flags_transaction_id_fkey;
+ALTER TABLE transaction_status_history DROP CONSTRAINT IF EXISTS transaction_status_history_transaction_id_fkey;
+ALTER TABLE payout_swaps DROP CONSTRAINT IF EXISTS payout_swaps_transaction_id_fkey;
+ALTER TABLE transaction_prices DROP CONSTRAINT IF EXISTS transaction_prices_transaction_id_fkey;
+ALTER TABLE wallet_addresses DROP CONSTRAINT IF EXISTS wallet_addresses_transaction_id_fkey;
+ALTER TABLE review_queue DROP CONSTRAINT IF EXISTS review_queue_transaction_id_fkey;
+ALTER TABLE pix_transactions DROP CONSTRAINT IF EXISTS pix_transactions_transaction_id_fkey;
+
+CREATE TABLE IF NOT EXISTS archive_runs (
+    id BIGSERIAL PRIMARY KEY,
+    archived_before TIMESTAMPTZ NOT NULL,
+    transactions INTEGER NOT NULL,
+    pix_transactions INTEGER NOT NULL,
+    ran_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,46 @@
+-- Amount and count of a user's transactions per creation day and status,
+-- kept by a trigger so limit checks read a few rows instead of summing
+-- transactions. Archival deletes rows from transactions without touching
+-- these, so they cover archived transactions too.
+CREATE TABLE IF NOT EXISTS user_daily_spending (
+    user_id TEXT NOT NULL,
+    day DATE NOT NULL,
+    status TEXT NOT NULL,
+    amount_in_cents BIGINT NOT NULL DEFAULT 0,
+    transactions INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id, day, status)
+);
+
+CREATE OR REPLACE FUNCTION project_user_daily_spending() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' THEN
+        IF NEW.status IS NOT DISTINCT FROM OLD.status THEN
+            RETURN NEW;
+        END IF;
+
+        UPDATE user_daily_spending
+        SET amount_in_cents = amount_in_cents - OLD.amount_in_cents,
+            transactions = transactions - 1
+        WHERE user_id = OLD.user_id AND day = DATE(OLD.created_at) AND status = OLD.status;
+    END IF;
+
+    INSERT INTO user_daily_spending (user_id, day, status, amount_in_cents, transactions)
+    VALUES (NEW.user_id, DATE(NEW.created_at), NEW.status, NEW.amount_in_cents, 1)
+    ON CONFLICT (user_id, day, status) DO UPDATE
+    SET amount_in_cents = user_daily_spending.amount_in_cents + EXCLUDED.amount_in_cents,
+        transactions = user_daily_spending.transactions + 1;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS transactions_daily_spending ON transactions;
+CREATE TRIGGER transactions_daily_spending
+    AFTER INSERT OR UPDATE OF status ON transactions
+    FOR EACH ROW EXECUTE FUNCTION project_user_daily_spending();
+
+INSERT INTO user_daily_spending (user_id, day, status, amount_in_cents, transactions)
+SELECT user_id, DATE(created_at), status, SUM(amount_in_cents), COUNT(*)
+FROM all_transactions
+GROUP BY user_id, DATE(created_at), status
+ON CONFLICT (user_id, day, status) DO NOTHING;
@@ -0,0 +1,28 @@
+-- Where each user wants payout receipts sent. Receipts only go to the
+-- channels the user opted into.
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id TEXT PRIMARY KEY REFERENCES users (id),
+    email TEXT,
+    phone TEXT,
+    email_receipts BOOLEAN NOT NULL DEFAULT FALSE,
+    sms_receipts BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- One receipt per payout and channel, written before it is handed to the
+-- provider so a retried payout is not receipted twice. No foreign key to
+-- transactions, so payouts can still be archived.
+CREATE TABLE IF NOT EXISTS receipts (
+    id BIGSERIAL PRIMARY KEY,
+    transaction_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'sending',
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (transaction_id, channel)
+);
+
+CREATE INDEX IF NOT EXISTS receipts_user_id_idx ON receipts (user_id);
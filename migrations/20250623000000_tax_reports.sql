@@ -0,0 +1,12 @@
+-- Monthly reports filed with Receita Federal, kept as generated so what was
+-- filed for a month can be retrieved again. `month` is its first day.
+CREATE TABLE IF NOT EXISTS tax_reports (
+    layout TEXT NOT NULL,
+    month DATE NOT NULL,
+    content TEXT NOT NULL,
+    operations INTEGER NOT NULL,
+    generated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (layout, month)
+);
+
+CREATE INDEX IF NOT EXISTS payouts_updated_at_idx ON payouts (updated_at) WHERE txid IS NOT NULL;
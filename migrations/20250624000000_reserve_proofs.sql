@@ -0,0 +1,7 @@
+-- Proofs of reserves as published. Anyone holding a hash can fetch the proof
+-- and check it against it.
+CREATE TABLE IF NOT EXISTS reserve_proofs (
+    hash TEXT PRIMARY KEY,
+    content TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,12 @@
+-- Points at which a dealer adopted the wallet and database of another one,
+-- written by the `bootstrap` command once the imported wallet state agrees
+-- with the ledger.
+CREATE TABLE IF NOT EXISTS handovers (
+    id TEXT PRIMARY KEY,
+    wallet_tip_height INTEGER NOT NULL,
+    wallet_transactions INTEGER NOT NULL,
+    last_webhook_event_id TEXT,
+    last_webhook_received_at TIMESTAMPTZ,
+    forced BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
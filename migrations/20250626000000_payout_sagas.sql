@@ -0,0 +1,13 @@
+-- Steps of each payout attempt, appended as they complete, fail or are
+-- compensated. A payout that stopped halfway is resumed or compensated from
+-- the last step of its latest attempt, which starts with `price_locked`.
+CREATE TABLE IF NOT EXISTS payout_saga_steps (
+    id BIGSERIAL PRIMARY KEY,
+    transaction_id TEXT NOT NULL,
+    step TEXT NOT NULL,
+    status TEXT NOT NULL,
+    details TEXT NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS payout_saga_steps_transaction_id_idx ON payout_saga_steps (transaction_id, id);
@@ -0,0 +1,15 @@
+-- Parts of a payout sent while the wallet covered only some of it. A row is
+-- written before the part is broadcast and completed with its txid after;
+-- the final payout sends what is owed minus every part recorded.
+CREATE TABLE IF NOT EXISTS partial_payouts (
+    id BIGSERIAL PRIMARY KEY,
+    transaction_id TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    txid TEXT,
+    network_fee BIGINT,
+    status TEXT NOT NULL DEFAULT 'broadcasting',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS partial_payouts_transaction_id_idx ON partial_payouts (transaction_id);
@@ -0,0 +1,22 @@
+-- Service invoices for the fee of each paid payment link, issued through the
+-- invoicing API once the merchant's payout finishes. Failed requests are
+-- retried with a growing delay until the API returns an invoice id or
+-- max_attempts is reached. No foreign key to transactions, so payouts can
+-- still be archived.
+CREATE TABLE IF NOT EXISTS merchant_invoices (
+    transaction_id TEXT PRIMARY KEY,
+    merchant_id TEXT NOT NULL REFERENCES merchants (id),
+    amount_in_cents INTEGER NOT NULL,
+    fee_bps INTEGER NOT NULL,
+    fee_in_cents BIGINT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    invoice_id TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS merchant_invoices_due_idx
+    ON merchant_invoices (next_attempt_at) WHERE status = 'pending';
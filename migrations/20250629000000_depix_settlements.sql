@@ -0,0 +1,19 @@
+-- DEPIX Eulen reported minting for a paid PIX charge, and the wallet
+-- transaction it was matched with. Unmatched settlements past the grace
+-- period are flagged `missing`, underpaid ones `short`.
+CREATE TABLE IF NOT EXISTS depix_settlements (
+    eulen_id TEXT PRIMARY KEY,
+    transaction_id TEXT NOT NULL,
+    bank_tx_id TEXT NOT NULL,
+    blockchain_tx_id TEXT,
+    value_in_cents INT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'unmatched',
+    matched_txid TEXT,
+    matched_by TEXT,
+    received_amount BIGINT,
+    reported_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    checked_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS depix_settlements_status_idx ON depix_settlements (status, reported_at);
@@ -0,0 +1,21 @@
+-- Quotes Sideswap made for our swaps. A swap quoted with the amount in
+-- either asset keeps both quotes; `chosen` marks the one taken.
+CREATE TABLE IF NOT EXISTS sideswap_quotes (
+    id BIGSERIAL PRIMARY KEY,
+    swap_id BIGINT NOT NULL,
+    quote_sub_id BIGINT NOT NULL,
+    quote_id BIGINT NOT NULL,
+    sell_asset TEXT NOT NULL,
+    receive_asset TEXT NOT NULL,
+    quoted_in TEXT NOT NULL,
+    requested_amount BIGINT NOT NULL,
+    sold_amount BIGINT NOT NULL,
+    received_amount BIGINT NOT NULL,
+    server_fee BIGINT NOT NULL,
+    fixed_fee BIGINT NOT NULL,
+    price DOUBLE PRECISION NOT NULL,
+    chosen BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS sideswap_quotes_swap_id_idx ON sideswap_quotes (swap_id);
@@ -0,0 +1,21 @@
+-- Surplus of PIX payments above the charged amount, owed back to the payer.
+-- Eulen offers no refund API, so operators pay it back and mark the refund
+-- `refunded`, or `dismissed` when the payer is not owed it.
+CREATE TABLE IF NOT EXISTS pix_refunds (
+    id TEXT PRIMARY KEY,
+    transaction_id TEXT NOT NULL,
+    eulen_id TEXT NOT NULL,
+    bank_tx_id TEXT NOT NULL UNIQUE,
+    payer_name TEXT NOT NULL,
+    payer_tax_number TEXT NOT NULL,
+    charged_in_cents INT NOT NULL,
+    paid_in_cents INT NOT NULL,
+    amount_in_cents INT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'due',
+    resolved_by TEXT,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS pix_refunds_status_idx ON pix_refunds (status, created_at);
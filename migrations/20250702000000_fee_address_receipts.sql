@@ -0,0 +1,24 @@
+-- A fee address belongs to one transaction only.
+CREATE UNIQUE INDEX IF NOT EXISTS wallet_addresses_transaction_unique_idx
+    ON wallet_addresses (transaction_id) WHERE transaction_id IS NOT NULL;
+
+-- DEPIX outputs received on receive addresses, attributed to the transaction
+-- the address was handed out for. status is `attributed` for the first
+-- DEPIX a transaction's address received in time, `late` when it arrived
+-- past the window, `repeat` when the address had already received DEPIX,
+-- and `unassigned` for an address handed out to no transaction.
+CREATE TABLE IF NOT EXISTS fee_address_receipts (
+    txid TEXT NOT NULL,
+    vout INTEGER NOT NULL,
+    address TEXT,
+    derivation_index INTEGER NOT NULL,
+    transaction_id TEXT,
+    amount BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    received_at TIMESTAMPTZ,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (txid, vout)
+);
+
+CREATE INDEX IF NOT EXISTS fee_address_receipts_status_idx ON fee_address_receipts (status, recorded_at);
+CREATE INDEX IF NOT EXISTS fee_address_receipts_transaction_idx ON fee_address_receipts (transaction_id);
@@ -0,0 +1,12 @@
+-- Network, Electrum server and chain tip every transaction was broadcast
+-- with, payouts and swaps alike, to look into transfers reported as not
+-- received.
+CREATE TABLE IF NOT EXISTS broadcasts (
+    txid TEXT PRIMARY KEY,
+    network TEXT NOT NULL,
+    electrum_server TEXT NOT NULL,
+    tip_height INTEGER NOT NULL,
+    tip_time TIMESTAMPTZ,
+    network_fee BIGINT NOT NULL,
+    broadcast_at TIMESTAMPTZ NOT NULL
+);
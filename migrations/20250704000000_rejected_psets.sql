@@ -0,0 +1,16 @@
+-- PSETs refused before signing because they did not do to the wallet what
+-- was agreed, kept to look into what the counterparty or the builder sent.
+CREATE TABLE IF NOT EXISTS rejected_psets (
+    id BIGSERIAL PRIMARY KEY,
+    -- swap or payout
+    kind TEXT NOT NULL,
+    -- Quote id of a swap, transaction id of a payout
+    reference TEXT NOT NULL,
+    pset TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    -- Net change per asset id the PSET made to the wallet, as JSON
+    balances TEXT NOT NULL,
+    rejected_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS rejected_psets_rejected_at_idx ON rejected_psets (rejected_at);
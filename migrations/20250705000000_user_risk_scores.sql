@@ -0,0 +1,11 @@
+-- Last risk score of each user, from 0 (most trusted) to 100 (riskiest).
+-- The band scales the consumer limits the user gets.
+CREATE TABLE IF NOT EXISTS user_risk_scores (
+    user_id TEXT PRIMARY KEY REFERENCES users (id),
+    score INTEGER NOT NULL,
+    -- trusted, standard or risky
+    band TEXT NOT NULL,
+    -- Signals that moved the score, as JSON
+    factors TEXT NOT NULL,
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
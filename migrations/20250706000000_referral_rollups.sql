@@ -0,0 +1,46 @@
+-- Referral bonuses of consumer payouts, recorded when the payout is
+-- computed and marked paid once it is broadcast. There's no foreign key to
+-- transactions so archived ones keep their bonus.
+CREATE TABLE IF NOT EXISTS referral_bonuses (
+    transaction_id TEXT PRIMARY KEY,
+    referrer_id TEXT NOT NULL REFERENCES users (id),
+    referred_user_id TEXT NOT NULL,
+    asset TEXT NOT NULL,
+    -- Base units of the asset
+    amount BIGINT NOT NULL,
+    -- What the bonus is worth in cents, at the share of the deposit it is
+    amount_in_cents BIGINT NOT NULL,
+    -- earned or paid
+    status TEXT NOT NULL DEFAULT 'earned',
+    txid TEXT,
+    earned_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    paid_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS referral_bonuses_referrer_idx ON referral_bonuses (referrer_id, earned_at);
+
+-- Bonuses of each referrer per month and asset: those earned in the month,
+-- and those paid in it. Rebuilt by the referral_rollups job.
+CREATE TABLE IF NOT EXISTS referral_rollups (
+    referrer_id TEXT NOT NULL,
+    month DATE NOT NULL,
+    asset TEXT NOT NULL,
+    earned_count INTEGER NOT NULL,
+    earned BIGINT NOT NULL,
+    earned_in_cents BIGINT NOT NULL,
+    paid_count INTEGER NOT NULL,
+    paid BIGINT NOT NULL,
+    paid_in_cents BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (referrer_id, month, asset)
+);
+
+CREATE INDEX IF NOT EXISTS referral_rollups_month_idx ON referral_rollups (month);
+
+-- API keys referrers read their statements with, stored as SHA-256 hashes.
+-- Issuing another replaces it.
+CREATE TABLE IF NOT EXISTS referrer_api_keys (
+    user_id TEXT PRIMARY KEY REFERENCES users (id),
+    api_key_hash TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,14 @@
+-- Payouts the user asked to receive later. Once paid, the transaction waits
+-- in `scheduled` until `deliver_at`, when the delivery is `released` for
+-- payout, unless the user cancelled it first. There is no foreign key, so
+-- archival moves the transaction without it.
+CREATE TABLE IF NOT EXISTS scheduled_deliveries (
+    transaction_id TEXT PRIMARY KEY,
+    deliver_at TIMESTAMPTZ NOT NULL,
+    status TEXT NOT NULL DEFAULT 'scheduled',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    settled_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS scheduled_deliveries_due_idx
+    ON scheduled_deliveries (deliver_at) WHERE status = 'scheduled';
@@ -0,0 +1,23 @@
+-- PIX payments contested through MED, the Central Bank's special return
+-- mechanism. The payer's bank may claw the money back after the dealer paid
+-- out, so open disputes are money at risk. Linked to the transaction of the
+-- contested charge when one matches, and to its user.
+CREATE TABLE IF NOT EXISTS pix_disputes (
+    id TEXT PRIMARY KEY,
+    external_id TEXT NOT NULL UNIQUE,
+    bank_tx_id TEXT NOT NULL,
+    transaction_id TEXT,
+    user_id TEXT,
+    amount_in_cents INT NOT NULL,
+    reason TEXT NOT NULL DEFAULT '',
+    status TEXT NOT NULL DEFAULT 'open',
+    reported_by TEXT NOT NULL,
+    resolved_by TEXT,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS pix_disputes_status_idx ON pix_disputes (status, created_at);
+CREATE INDEX IF NOT EXISTS pix_disputes_user_idx ON pix_disputes (user_id) WHERE status = 'open';
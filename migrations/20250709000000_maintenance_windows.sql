@@ -0,0 +1,15 @@
+-- Periods operators close new deposits for, such as a provider's
+-- maintenance. Webhooks and payouts of paid transactions go on during them.
+CREATE TABLE IF NOT EXISTS maintenance_windows (
+    id TEXT PRIMARY KEY,
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    reason TEXT NOT NULL,
+    scheduled_by TEXT NOT NULL,
+    cancelled_by TEXT,
+    cancelled_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS maintenance_windows_ends_idx ON maintenance_windows (ends_at)
+    WHERE cancelled_at IS NULL;
@@ -0,0 +1,7 @@
+-- One row per transaction ever paid out. The worker paying a transaction
+-- keeps its row locked until it is done, so the recovery scan and other
+-- workers skip it; a crashed worker's lock goes with its connection.
+CREATE TABLE IF NOT EXISTS payout_claims (
+    transaction_id TEXT PRIMARY KEY REFERENCES transactions (id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,5 @@
+-- Only one part is sent per transaction. The row is inserted before the part
+-- is built, so two workers can't both send one.
+CREATE UNIQUE INDEX IF NOT EXISTS partial_payouts_transaction_id_key ON partial_payouts (transaction_id);
+
+DROP INDEX IF EXISTS partial_payouts_transaction_id_idx;
@@ -0,0 +1,8 @@
+-- Hedges are recorded as pending before their swap is requested, so a swap
+-- is never sent without a row to book its fill on. The quote id is only
+-- known once the swap was accepted.
+ALTER TABLE hedges DROP CONSTRAINT IF EXISTS hedges_pkey;
+ALTER TABLE hedges ADD COLUMN IF NOT EXISTS id BIGSERIAL PRIMARY KEY;
+ALTER TABLE hedges ALTER COLUMN quote_sub_id DROP NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS hedges_quote_sub_id_key ON hedges (quote_sub_id);
@@ -0,0 +1,8 @@
+-- Archived transactions leave the table this key points to
+ALTER TABLE payout_claims DROP CONSTRAINT IF EXISTS payout_claims_transaction_id_fkey;
+
+-- A claim is a lease committed with the row instead of a lock held by an
+-- open transaction, so a payout in flight doesn't keep a connection. It is
+-- released by its holder, or taken over once it expires after a crash.
+ALTER TABLE payout_claims ADD COLUMN IF NOT EXISTS claim_token TEXT;
+ALTER TABLE payout_claims ADD COLUMN IF NOT EXISTS claimed_until TIMESTAMPTZ;
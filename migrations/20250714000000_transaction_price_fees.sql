@@ -0,0 +1,4 @@
+-- Fee each payout was computed with, in base units of the asset, so the
+-- payout can be checked against it before it is signed. fee_collected on
+-- transactions is too narrow to hold every fee.
+ALTER TABLE transaction_prices ADD COLUMN IF NOT EXISTS fee_in_asset BIGINT;
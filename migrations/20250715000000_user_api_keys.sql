@@ -0,0 +1,8 @@
+-- API keys users authenticate their own routes with, such as their
+-- notification preferences, stored as SHA-256 hashes. Handed out at
+-- registration; issuing another replaces it.
+CREATE TABLE IF NOT EXISTS user_api_keys (
+    user_id TEXT PRIMARY KEY REFERENCES users (id),
+    api_key_hash TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,115 @@
+//! Sideswap adds fields to its payloads now and then. They are ignored when
+//! parsing, so older models keep working, but logged once so the models can
+//! catch up.
+
+use anyhow::anyhow;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+
+/// Fields already warned about, by path.
+static WARNED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+/// Parses `value` as `what`, warning about the fields the model leaves out.
+pub(crate) fn parse<T: DeserializeOwned + Serialize>(
+    value: Value,
+    what: &str,
+) -> Result<T, anyhow::Error> {
+    let parsed: T = serde_json::from_value(value.clone())
+        .map_err(|e| anyhow!("Failed to deserialize the {}: {}", what, e))?;
+
+    for field in unknown_fields(&value, &parsed) {
+        let path = format!("{}.{}", what, field);
+        let mut warned = WARNED
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if warned.insert(path.clone()) {
+            log::warn!("Sideswap sent {}, which the models do not know", path);
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Paths of the fields of `received` that `parsed` has no place for.
+pub fn unknown_fields<T: Serialize>(received: &Value, parsed: &T) -> Vec<String> {
+    let mut unknown = Vec::new();
+    if let Ok(modelled) = serde_json::to_value(parsed) {
+        collect_unknown(received, &modelled, "", &mut unknown);
+    }
+
+    unknown
+}
+
+fn collect_unknown(received: &Value, modelled: &Value, path: &str, unknown: &mut Vec<String>) {
+    match (received, modelled) {
+        (Value::Object(received), Value::Object(modelled)) => {
+            for (key, value) in received {
+                let field = match path {
+                    "" => key.clone(),
+                    path => format!("{}.{}", path, key),
+                };
+                match modelled.get(key) {
+                    Some(modelled) => collect_unknown(value, modelled, &field, unknown),
+                    // Optional fields may be left out when empty
+                    None if !value.is_null() => unknown.push(field),
+                    None => {}
+                }
+            }
+        }
+        (Value::Array(received), Value::Array(modelled)) => {
+            for (item, modelled) in received.iter().zip(modelled) {
+                collect_unknown(item, modelled, &format!("{}[]", path), unknown);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AssetPair, ListMarkets, Quote};
+    use serde_json::json;
+
+    #[test]
+    fn finds_fields_the_model_leaves_out() {
+        let received = json!({"pset": "cHNldP8=", "ttl": 30000, "expires_at": 1700000000});
+        let quote: Quote = serde_json::from_value(received.clone()).unwrap();
+
+        assert_eq!(unknown_fields(&received, &quote), vec!["expires_at"]);
+    }
+
+    #[test]
+    fn looks_into_nested_objects_and_arrays() {
+        let received = json!({"markets": [{
+            "asset_pair": {"base": "b", "quote": "q", "precision": 8},
+            "fee_asset": "Base",
+            "type": "Stablecoin"
+        }]});
+        let markets: ListMarkets = serde_json::from_value(received.clone()).unwrap();
+
+        assert_eq!(
+            unknown_fields(&received, &markets),
+            vec!["markets[].asset_pair.precision"]
+        );
+    }
+
+    #[test]
+    fn known_and_null_fields_are_fine() {
+        let received = json!({"base": "b", "quote": "q", "extra": null});
+        let pair: AssetPair = serde_json::from_value(received.clone()).unwrap();
+
+        assert!(unknown_fields(&received, &pair).is_empty());
+    }
+
+    #[test]
+    fn parses_despite_unknown_fields() {
+        let received = json!({"pset": "cHNldP8=", "ttl": 30000, "new_field": {"a": 1}});
+
+        let quote: Quote = parse(received, "get_quote result").unwrap();
+        assert_eq!(quote.ttl, 30000);
+    }
+}
@@ -0,0 +1,21 @@
+//! Sideswap's JSON-RPC protocol, spoken by the dealer and mooze-swap alike:
+//! the requests, the results they answer with and the notifications the
+//! server pushes. The websocket is left to the caller, which sends
+//! [`Request::method`] with [`Request::params`] and hands the response to
+//! [`Request::parse`].
+//!
+//! Payloads are parsed leniently: fields the models do not know are logged
+//! and ignored. The version the server announces at login is read with
+//! [`ApiVersion::from_login`]; `tests/contract.rs` checks the models against
+//! fixtures of every supported version.
+
+mod fields;
+pub mod models;
+mod notifications;
+mod requests;
+mod version;
+
+pub use fields::unknown_fields;
+pub use notifications::{parse_notification, Notification};
+pub use requests::{check_response, response_result, Request, SideswapError};
+pub use version::ApiVersion;
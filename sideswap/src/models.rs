@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum AssetType {
+    Base,
+    Quote,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub enum TradeDir {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Asset {
+    pub always_show: Option<bool>,
+    pub asset_id: String,
+    pub contract: Option<Contract>,
+    pub domain: Option<String>,
+    pub icon: Option<String>,
+    pub icon_url: Option<String>,
+    pub instant_swaps: Option<bool>,
+    pub issuance_prevout: Option<IssuancePrevout>,
+    pub issuer_pubkey: Option<String>,
+    pub market_type: Option<String>,
+    pub name: String,
+    pub payjoin: Option<bool>,
+    pub precision: u8,
+    pub ticker: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Contract {
+    pub entity: Option<Entity>,
+    pub issuer_pubkey: Option<String>,
+    pub name: String,
+    pub precision: u8,
+    pub ticker: Option<String>,
+    pub version: Option<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entity {
+    pub domain: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssuancePrevout {
+    pub txid: String,
+    pub vout: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Assets {
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AssetPair {
+    pub base: String,
+    pub quote: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Market {
+    pub asset_pair: AssetPair,
+    pub fee_asset: String,
+    #[serde(rename = "type")]
+    pub asset_type: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListMarkets {
+    pub markets: Vec<Market>,
+}
+
+/// Candle of a Sideswap market chart, priced in quote asset per base asset.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChartPoint {
+    pub time: String,
+    pub open: f64,
+    pub close: f64,
+    pub high: f64,
+    pub low: f64,
+    pub volume: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ChartSub {
+    pub asset_pair: AssetPair,
+    pub data: Vec<ChartPoint>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ChartUpdate {
+    pub asset_pair: AssetPair,
+    pub update: ChartPoint,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SideswapUtxo {
+    #[serde(rename = "txid")]
+    pub txid: String,
+    #[serde(rename = "vout")]
+    pub vout: u32,
+    #[serde(rename = "asset")]
+    pub asset: String,
+    #[serde(rename = "asset_bf")]
+    pub asset_bf: String,
+    #[serde(rename = "value")]
+    pub value: u64,
+    #[serde(rename = "value_bf")]
+    pub value_bf: String,
+    #[serde(rename = "redeem_script", skip_serializing_if = "Option::is_none")]
+    pub redeem_script: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct QuoteRequest {
+    #[serde(rename = "asset_pair")]
+    pub asset_pair: AssetPair,
+    #[serde(rename = "asset_type")]
+    pub asset_type: AssetType,
+    #[serde(rename = "trade_dir")]
+    pub trade_dir: TradeDir,
+    #[serde(rename = "amount")]
+    pub amount: i64,
+    #[serde(rename = "utxos")]
+    pub utxos: Vec<SideswapUtxo>,
+    #[serde(rename = "receive_address")]
+    pub receive_address: String,
+    #[serde(rename = "change_address")]
+    pub change_address: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StartQuotes {
+    pub fee_asset: String,
+    pub quote_sub_id: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Quote {
+    pub pset: String,
+    pub ttl: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TakerSign {
+    pub txid: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub enum QuoteStatus {
+    Success {
+        quote_id: u64,
+        base_amount: u64,
+        quote_amount: u64,
+        server_fee: u64,
+        fixed_fee: u64,
+        ttl: u64,
+    },
+    LowBalance {
+        base_amount: u64,
+        quote_amount: u64,
+        server_fee: u64,
+        fixed_fee: u64,
+        available: u64,
+    },
+    Error {
+        error_msg: String,
+    },
+}
+
+/// Sideswap's `server_status` notification, sent after login and whenever
+/// fees change or maintenance is announced.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ServerStatus {
+    /// Network fee rate of Liquid transactions, in sat/vbyte.
+    #[serde(default)]
+    pub elements_fee_rate: Option<f64>,
+    #[serde(default)]
+    pub server_fee_percent_peg_in: Option<f64>,
+    #[serde(default)]
+    pub server_fee_percent_peg_out: Option<f64>,
+    #[serde(default)]
+    pub maintenance: Option<Maintenance>,
+}
+
+/// Announced maintenance window, in milliseconds since the epoch. Without a
+/// start it runs from the announcement, without an end until a status
+/// without it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Maintenance {
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub start_time: Option<i64>,
+    #[serde(default)]
+    pub end_time: Option<i64>,
+}
+
+impl Maintenance {
+    pub fn is_active(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        let now = now.timestamp_millis();
+        self.start_time.is_none_or(|start| start <= now)
+            && self.end_time.is_none_or(|end| now < end)
+    }
+}
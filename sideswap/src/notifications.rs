@@ -0,0 +1,210 @@
+use crate::fields;
+use crate::models::{AssetPair, ChartUpdate, QuoteStatus, ServerStatus};
+
+use serde_json::Value;
+
+/// What a notification pushed by Sideswap tells us.
+#[derive(Clone, Debug)]
+pub enum Notification {
+    /// A quote of a subscription started with `start_quotes`.
+    Quote {
+        quote_sub_id: i64,
+        status: QuoteStatus,
+    },
+    /// Close of the latest candle of a subscribed chart.
+    MarketPrice {
+        asset_pair: AssetPair,
+        price: f64,
+    },
+    ServerStatus(ServerStatus),
+}
+
+/// Notifications carried by a pushed message. A market message may carry a
+/// quote and a chart update at once; messages of unknown methods carry none.
+pub fn parse_notification(notification: &Value) -> Result<Vec<Notification>, anyhow::Error> {
+    match notification.get("method") {
+        Some(method) => match method.as_str() {
+            Some("market") => parse_market_notification(&notification["params"]),
+            Some("server_status") => {
+                let status: ServerStatus =
+                    fields::parse(notification["params"].clone(), "server_status")?;
+
+                Ok(vec![Notification::ServerStatus(status)])
+            }
+            _ => {
+                log::warn!("Received unknown notification type: {}", method);
+                Ok(Vec::new())
+            }
+        },
+        None => {
+            log::warn!("Received notification without method.");
+            Ok(Vec::new())
+        }
+    }
+}
+
+fn parse_market_notification(params: &Value) -> Result<Vec<Notification>, anyhow::Error> {
+    let mut notifications = Vec::new();
+    if let Some(quote) = params.get("quote") {
+        notifications.extend(parse_quote(quote));
+    }
+
+    if let Some(chart_update) = params.get("chart_update") {
+        let chart_update: ChartUpdate = fields::parse(chart_update.clone(), "chart_update")?;
+
+        notifications.push(Notification::MarketPrice {
+            asset_pair: chart_update.asset_pair,
+            price: chart_update.update.close,
+        });
+    }
+
+    Ok(notifications)
+}
+
+fn parse_quote(quote: &Value) -> Vec<Notification> {
+    let quote_sub_id = quote["quote_sub_id"].as_i64().unwrap_or(0);
+    let Some(status) = quote.get("status") else {
+        log::warn!("Received quote without status.");
+        return Vec::new();
+    };
+
+    let mut statuses = Vec::new();
+    if let Some(low_balance) = status.get("LowBalance") {
+        statuses.push(QuoteStatus::LowBalance {
+            base_amount: low_balance["base_amount"].as_u64().unwrap_or(0),
+            quote_amount: low_balance["quote_amount"].as_u64().unwrap_or(0),
+            server_fee: low_balance["server_fee"].as_u64().unwrap_or(0),
+            fixed_fee: low_balance["fixed_fee"].as_u64().unwrap_or(0),
+            available: low_balance["available"].as_u64().unwrap_or(0),
+        });
+    }
+
+    if let Some(error) = status.get("Error") {
+        statuses.push(QuoteStatus::Error {
+            error_msg: error["error_msg"]
+                .as_str()
+                .unwrap_or("Unknown error")
+                .to_owned(),
+        });
+    }
+
+    if let Some(success) = status.get("Success") {
+        statuses.push(QuoteStatus::Success {
+            quote_id: success["quote_id"].as_u64().unwrap_or(0),
+            base_amount: success["base_amount"].as_u64().unwrap_or(0),
+            quote_amount: success["quote_amount"].as_u64().unwrap_or(0),
+            server_fee: success["server_fee"].as_u64().unwrap_or(0),
+            fixed_fee: success["fixed_fee"].as_u64().unwrap_or(0),
+            ttl: success["ttl"].as_u64().unwrap_or(0),
+        });
+    }
+
+    if statuses.is_empty() {
+        log::warn!("Received quote with an unknown status: {}", status);
+    }
+
+    statuses
+        .into_iter()
+        .map(|status| Notification::Quote {
+            quote_sub_id,
+            status,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_quotes() {
+        let notification = json!({
+            "method": "market",
+            "params": {"quote": {
+                "quote_sub_id": 12,
+                "status": {"Success": {
+                    "quote_id": 34,
+                    "base_amount": 1000,
+                    "quote_amount": 5000,
+                    "server_fee": 10,
+                    "fixed_fee": 20,
+                    "ttl": 30000
+                }}
+            }}
+        });
+
+        let notifications = parse_notification(&notification).unwrap();
+        match notifications.as_slice() {
+            [Notification::Quote {
+                quote_sub_id: 12,
+                status:
+                    QuoteStatus::Success {
+                        quote_id: 34,
+                        base_amount: 1000,
+                        ttl: 30000,
+                        ..
+                    },
+            }] => {}
+            other => panic!("unexpected notifications: {:?}", other),
+        }
+
+        let notification = json!({
+            "method": "market",
+            "params": {"quote": {"quote_sub_id": 12, "status": {"LowBalance": {"available": 70}}}}
+        });
+        match parse_notification(&notification).unwrap().as_slice() {
+            [Notification::Quote {
+                status: QuoteStatus::LowBalance { available: 70, .. },
+                ..
+            }] => {}
+            other => panic!("unexpected notifications: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_chart_updates_and_server_status() {
+        let notification = json!({
+            "method": "market",
+            "params": {"chart_update": {
+                "asset_pair": {"base": "base", "quote": "quote"},
+                "update": {"time": "t", "open": 1.0, "close": 2.5, "high": 3.0, "low": 0.5, "volume": 9.0}
+            }}
+        });
+        match parse_notification(&notification).unwrap().as_slice() {
+            [Notification::MarketPrice { asset_pair, price }] => {
+                assert_eq!(asset_pair.base, "base");
+                assert_eq!(*price, 2.5);
+            }
+            other => panic!("unexpected notifications: {:?}", other),
+        }
+
+        let notification = json!({
+            "method": "server_status",
+            "params": {"elements_fee_rate": 0.1}
+        });
+        match parse_notification(&notification).unwrap().as_slice() {
+            [Notification::ServerStatus(status)] => {
+                assert_eq!(status.elements_fee_rate, Some(0.1));
+            }
+            other => panic!("unexpected notifications: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ignores_unknown_notifications() {
+        assert!(
+            parse_notification(&json!({"method": "peg_status", "params": {}}))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(parse_notification(&json!({"params": {}}))
+            .unwrap()
+            .is_empty());
+        assert!(
+            parse_notification(&json!({"method": "market", "params": {"quote": {}}}))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
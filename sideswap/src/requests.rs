@@ -0,0 +1,272 @@
+use crate::fields;
+use crate::models::{AssetPair, QuoteRequest};
+
+use anyhow::anyhow;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Error object of a Sideswap JSON-RPC response.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, thiserror::Error)]
+#[error("Sideswap error {code}: {message}")]
+pub struct SideswapError {
+    pub code: i64,
+    pub message: String,
+}
+
+/// A call to Sideswap. Everything but the login goes through the `market`
+/// method, as an object keyed by the name of the request; the result comes
+/// back under the same key.
+#[derive(Clone, Debug)]
+pub enum Request {
+    Login {
+        api_key: String,
+        user_agent: String,
+        version: String,
+    },
+    ListMarkets,
+    /// Latest candles of a market; updates follow as `chart_update`
+    /// notifications.
+    ChartSub {
+        asset_pair: AssetPair,
+    },
+    /// Subscribes to quotes, which arrive as `quote` notifications.
+    StartQuotes(QuoteRequest),
+    StopQuotes,
+    GetQuote {
+        quote_id: u64,
+    },
+    TakerSign {
+        quote_id: u64,
+        pset: String,
+    },
+}
+
+impl Request {
+    pub fn method(&self) -> &'static str {
+        match self {
+            Request::Login { .. } => "login",
+            _ => "market",
+        }
+    }
+
+    /// Key of the request in the params, and of its result.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Request::Login { .. } => "login",
+            Request::ListMarkets => "list_markets",
+            Request::ChartSub { .. } => "chart_sub",
+            Request::StartQuotes(_) => "start_quotes",
+            Request::StopQuotes => "stop_quotes",
+            Request::GetQuote { .. } => "get_quote",
+            Request::TakerSign { .. } => "taker_sign",
+        }
+    }
+
+    pub fn params(&self) -> Value {
+        let request = match self {
+            Request::Login {
+                api_key,
+                user_agent,
+                version,
+            } => {
+                return json!({
+                    "api_key": api_key,
+                    "user-agent": user_agent,
+                    "version": version
+                })
+            }
+            Request::ListMarkets | Request::StopQuotes => json!({}),
+            Request::ChartSub { asset_pair } => json!({ "asset_pair": asset_pair }),
+            Request::StartQuotes(quote_request) => json!(quote_request),
+            Request::GetQuote { quote_id } => json!({ "quote_id": quote_id }),
+            Request::TakerSign { quote_id, pset } => json!({
+                "quote_id": quote_id,
+                "pset": pset
+            }),
+        };
+
+        json!({ self.name(): request })
+    }
+
+    /// The result of this request in `response`, or the error Sideswap
+    /// answered with.
+    pub fn parse<T: DeserializeOwned + Serialize>(
+        &self,
+        response: &Value,
+    ) -> Result<T, anyhow::Error> {
+        let result = response_result(response, self.name())?;
+        fields::parse(result, &format!("{} result", self.name()))
+    }
+}
+
+/// The error Sideswap answered with, as a [`SideswapError`].
+pub fn check_response(response: &Value) -> Result<(), anyhow::Error> {
+    match response.get("error") {
+        Some(error) if !error.is_null() => {
+            let error: SideswapError = serde_json::from_value(error.clone())
+                .map_err(|_| anyhow!("Malformed Sideswap error: {}", error))?;
+            Err(error.into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `result_key` of the result of a response, or the error Sideswap answered
+/// with.
+pub fn response_result(response: &Value, result_key: &str) -> Result<Value, anyhow::Error> {
+    check_response(response)?;
+
+    let result = response
+        .get("result")
+        .ok_or_else(|| anyhow!("Sideswap response without result: {}", response))?;
+
+    result
+        .get(result_key)
+        .cloned()
+        .ok_or_else(|| anyhow!("Missing result key: {}", result_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AssetType, Quote, TradeDir};
+    use anyhow::Context;
+
+    fn sideswap_error(e: anyhow::Error) -> SideswapError {
+        e.downcast::<SideswapError>()
+            .expect("error should be a SideswapError")
+    }
+
+    #[test]
+    fn market_requests_are_keyed_by_name() {
+        let quote_request = QuoteRequest {
+            asset_pair: AssetPair {
+                base: "base".to_string(),
+                quote: "quote".to_string(),
+            },
+            asset_type: AssetType::Base,
+            trade_dir: TradeDir::Sell,
+            amount: 1000,
+            utxos: Vec::new(),
+            receive_address: "receive".to_string(),
+            change_address: "change".to_string(),
+        };
+
+        let request = Request::StartQuotes(quote_request);
+        assert_eq!(request.method(), "market");
+        let params = request.params();
+        assert_eq!(params["start_quotes"]["asset_type"], "Base");
+        assert_eq!(params["start_quotes"]["trade_dir"], "Sell");
+        assert!(params.get("quote").is_none());
+
+        assert_eq!(Request::ListMarkets.params(), json!({"list_markets": {}}));
+        assert_eq!(
+            Request::TakerSign {
+                quote_id: 7,
+                pset: "cHNldP8=".to_string()
+            }
+            .params(),
+            json!({"taker_sign": {"quote_id": 7, "pset": "cHNldP8="}})
+        );
+    }
+
+    #[test]
+    fn login_is_its_own_method() {
+        let request = Request::Login {
+            api_key: "key".to_string(),
+            user_agent: "mooze-dealer".to_string(),
+            version: "0.1.0".to_string(),
+        };
+
+        assert_eq!(request.method(), "login");
+        assert_eq!(
+            request.params(),
+            json!({"api_key": "key", "user-agent": "mooze-dealer", "version": "0.1.0"})
+        );
+    }
+
+    #[test]
+    fn parses_the_result_of_the_request() {
+        let response = json!({
+            "id": 3,
+            "result": {"get_quote": {"pset": "cHNldP8BAgQCAAAA", "ttl": 30000}},
+        });
+
+        let quote: Quote = Request::GetQuote { quote_id: 3 }.parse(&response).unwrap();
+        assert_eq!(quote.pset, "cHNldP8BAgQCAAAA");
+        assert_eq!(quote.ttl, 30000);
+    }
+
+    #[test]
+    fn returns_the_result_key() {
+        let response = json!({
+            "id": 3,
+            "result": {"get_quote": {"pset": "cHNldP8BAgQCAAAA", "ttl": 30000}},
+        });
+
+        let result = response_result(&response, "get_quote").unwrap();
+        assert_eq!(result["pset"], "cHNldP8BAgQCAAAA");
+    }
+
+    #[test]
+    fn null_error_is_not_an_error() {
+        let response = json!({"id": 4, "error": null, "result": {"taker_sign": {"txid": "ab12"}}});
+
+        let result = response_result(&response, "taker_sign").unwrap();
+        assert_eq!(result["txid"], "ab12");
+    }
+
+    #[test]
+    fn parses_error_payloads() {
+        let response = json!({
+            "id": 5,
+            "error": {"code": -32000, "message": "quote expired"},
+        });
+
+        let error = sideswap_error(response_result(&response, "get_quote").unwrap_err());
+        assert_eq!(
+            error,
+            SideswapError {
+                code: -32000,
+                message: "quote expired".to_string(),
+            }
+        );
+
+        let response = json!({
+            "id": 6,
+            "error": {"code": -32602, "message": "invalid params", "data": {"field": "pset"}},
+        });
+
+        let error = sideswap_error(check_response(&response).unwrap_err());
+        assert_eq!(error.code, -32602);
+        assert_eq!(error.message, "invalid params");
+    }
+
+    #[test]
+    fn login_errors_keep_the_sideswap_error() {
+        let response = json!({"id": 1, "error": {"code": 1, "message": "invalid api key"}});
+
+        let error = check_response(&response)
+            .context("Failed to log in to Sideswap")
+            .unwrap_err();
+        assert_eq!(sideswap_error(error).message, "invalid api key");
+    }
+
+    #[test]
+    fn malformed_responses_fail_without_panicking() {
+        let response = json!({"id": 7, "error": "something went wrong"});
+        let error = response_result(&response, "get_quote").unwrap_err();
+        assert!(error.downcast_ref::<SideswapError>().is_none());
+        assert!(error.to_string().starts_with("Malformed Sideswap error"));
+
+        let response = json!({"id": 8});
+        let error = response_result(&response, "get_quote").unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Sideswap response without result"));
+
+        let response = json!({"id": 9, "result": {"start_quotes": {}}});
+        let error = response_result(&response, "get_quote").unwrap_err();
+        assert_eq!(error.to_string(), "Missing result key: get_quote");
+    }
+}
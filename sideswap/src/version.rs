@@ -0,0 +1,60 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// Version of the API a Sideswap server speaks, as announced in the result
+/// of the login. Servers that announce none speak version 1, the protocol
+/// before versions were announced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ApiVersion(pub u32);
+
+impl ApiVersion {
+    pub const V1: ApiVersion = ApiVersion(1);
+
+    /// Versions the models were checked against, each with its fixtures
+    /// under `tests/fixtures`.
+    pub const SUPPORTED: [ApiVersion; 1] = [ApiVersion::V1];
+
+    /// Version announced in a login response.
+    pub fn from_login(response: &Value) -> ApiVersion {
+        response["result"]["login"]["api_version"]
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .map(ApiVersion)
+            .unwrap_or(ApiVersion::V1)
+    }
+
+    pub fn is_supported(&self) -> bool {
+        Self::SUPPORTED.contains(self)
+    }
+}
+
+impl std::fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_the_announced_version() {
+        let response = json!({"id": "1", "result": {"login": {"api_version": 2}}});
+        assert_eq!(ApiVersion::from_login(&response), ApiVersion(2));
+        assert!(!ApiVersion(2).is_supported());
+    }
+
+    #[test]
+    fn servers_announcing_none_speak_v1() {
+        for response in [
+            json!({"id": "1", "result": {}}),
+            json!({"id": "1", "result": {"login": {}}}),
+            json!({"id": "1", "result": {"login": {"api_version": "two"}}}),
+        ] {
+            assert_eq!(ApiVersion::from_login(&response), ApiVersion::V1);
+        }
+        assert!(ApiVersion::V1.is_supported());
+    }
+}
@@ -0,0 +1,143 @@
+//! Contract tests against the fixtures of every supported API version, in
+//! `tests/fixtures/<version>`. Each request fixture holds the call as it goes
+//! on the wire and the response Sideswap answers with; the requests must be
+//! built exactly so and the responses parsed without fields left over.
+
+use mooze_sideswap::models::{
+    AssetPair, ChartSub, ChartUpdate, ListMarkets, Quote, ServerStatus, StartQuotes, TakerSign,
+};
+use mooze_sideswap::{parse_notification, unknown_fields, ApiVersion, Request};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+
+fn fixture(version: ApiVersion, name: &str) -> Value {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(version.to_string())
+        .join(format!("{}.json", name));
+    let contents = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Missing fixture {}: {}", path.display(), e));
+    serde_json::from_str(&contents).unwrap()
+}
+
+/// Checks `request` against the fixture of its name and returns the parsed
+/// response.
+fn exchange<T: DeserializeOwned + Serialize>(version: ApiVersion, request: Request) -> T {
+    let fixture = fixture(version, request.name());
+    assert_eq!(
+        request.method(),
+        fixture["request"]["method"],
+        "{}",
+        request.name()
+    );
+    assert_eq!(
+        request.params(),
+        fixture["request"]["params"],
+        "{}",
+        request.name()
+    );
+
+    let response = &fixture["response"];
+    let result: T = request.parse(response).unwrap();
+    assert_eq!(
+        unknown_fields(&response["result"][request.name()], &result),
+        Vec::<String>::new(),
+        "{} result of {}",
+        request.name(),
+        version
+    );
+    result
+}
+
+/// Params of the request in the fixture of `name`.
+fn params<T: DeserializeOwned>(version: ApiVersion, name: &str) -> T {
+    serde_json::from_value(fixture(version, name)["request"]["params"][name].clone()).unwrap()
+}
+
+#[derive(Deserialize)]
+struct ChartSubParams {
+    asset_pair: AssetPair,
+}
+
+#[test]
+fn requests_and_responses_match_the_fixtures() {
+    for version in ApiVersion::SUPPORTED {
+        let login = Request::Login {
+            api_key: "test-api-key".to_string(),
+            user_agent: "mooze-dealer".to_string(),
+            version: "0.1.0".to_string(),
+        };
+        let fixture = fixture(version, "login");
+        assert_eq!(login.method(), fixture["request"]["method"]);
+        assert_eq!(login.params(), fixture["request"]["params"]);
+        assert_eq!(ApiVersion::from_login(&fixture["response"]), version);
+
+        let markets: ListMarkets = exchange(version, Request::ListMarkets);
+        assert!(!markets.markets.is_empty());
+
+        let chart: ChartSub = exchange(
+            version,
+            Request::ChartSub {
+                asset_pair: params::<ChartSubParams>(version, "chart_sub").asset_pair,
+            },
+        );
+        assert!(!chart.data.is_empty());
+
+        let started: StartQuotes = exchange(
+            version,
+            Request::StartQuotes(params(version, "start_quotes")),
+        );
+        assert!(started.quote_sub_id > 0);
+
+        let quote: Quote = exchange(version, Request::GetQuote { quote_id: 42 });
+        let signed: TakerSign = exchange(
+            version,
+            Request::TakerSign {
+                quote_id: 42,
+                pset: quote.pset,
+            },
+        );
+        assert_eq!(signed.txid.len(), 64);
+    }
+}
+
+#[test]
+fn notifications_match_the_fixtures() {
+    for version in ApiVersion::SUPPORTED {
+        let fixtures = fixture(version, "notifications");
+        for fixture in fixtures.as_array().unwrap() {
+            let notification = &fixture["notification"];
+            let parsed = parse_notification(notification).unwrap();
+            assert_eq!(
+                parsed.len() as u64,
+                fixture["notifications"].as_u64().unwrap(),
+                "{} of {}",
+                notification,
+                version
+            );
+
+            let params = &notification["params"];
+            let unknown = match notification["method"].as_str() {
+                Some("server_status") => {
+                    let status: ServerStatus = serde_json::from_value(params.clone()).unwrap();
+                    unknown_fields(params, &status)
+                }
+                _ => match params.get("chart_update") {
+                    Some(update) => {
+                        let update: ChartUpdate = serde_json::from_value(update.clone()).unwrap();
+                        unknown_fields(&params["chart_update"], &update)
+                    }
+                    None => Vec::new(),
+                },
+            };
+            assert_eq!(
+                unknown,
+                Vec::<String>::new(),
+                "{} of {}",
+                notification,
+                version
+            );
+        }
+    }
+}
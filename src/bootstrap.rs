@@ -0,0 +1,86 @@
+//! Adopts the wallet and database of another dealer when moving servers:
+//! imports its persisted wallet state so the new dealer doesn't scan from
+//! genesis, checks it is the same wallet and agrees with the ledger, and
+//! records the handover point.
+
+use anyhow::bail;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::models::handovers::Handover;
+use crate::repositories::{handovers::HandoverRepository, liquid::LiquidRepository};
+use crate::settings::Settings;
+
+/// Imports the wallet state under `wallet_state` and records the handover.
+/// `descriptor` is the previous dealer's CT descriptor, as listed by
+/// `/admin/wallet/descriptor`. Reconciliation issues stop the handover
+/// unless `force` is set; the imported state is kept either way.
+pub async fn bootstrap(
+    config: &Settings,
+    conn: PgPool,
+    wallet_state: &str,
+    descriptor: Option<&str>,
+    force: bool,
+) -> Result<Handover, anyhow::Error> {
+    let wallet = LiquidRepository::import_wallet_state(
+        &config.wallet.mnemonic,
+        config.network.is_mainnet(),
+        Path::new(wallet_state),
+    )?;
+
+    println!(
+        "[*] Imported {} wallet updates, tip at height {}, {} transactions",
+        wallet.updates,
+        wallet.tip_height,
+        wallet.txids.len()
+    );
+    for (asset, amount) in &wallet.balances {
+        println!("    balance {}: {}", asset, amount);
+    }
+
+    if let Some(descriptor) = descriptor {
+        if descriptor.trim() != wallet.descriptor {
+            bail!(
+                "Descriptor mismatch, this dealer's mnemonic gives {}",
+                wallet.descriptor
+            );
+        }
+        println!("[*] Descriptor matches");
+    }
+
+    let repository = HandoverRepository::new(conn);
+    let txids: HashSet<String> = wallet.txids.iter().cloned().collect();
+    let reconciliation = repository.reconcile(&txids).await?;
+
+    for txid in &reconciliation.missing_payouts {
+        println!("    payout {} is not in the wallet state", txid);
+    }
+    if reconciliation.unfinished_payouts > 0 {
+        println!(
+            "    {} payouts were started without a txid",
+            reconciliation.unfinished_payouts
+        );
+    }
+    if reconciliation.unprocessed_webhooks > 0 {
+        println!(
+            "    {} webhooks were stored and never processed",
+            reconciliation.unprocessed_webhooks
+        );
+    }
+
+    if !reconciliation.is_clean() {
+        if !force {
+            bail!("Wallet state and database disagree, stop the previous dealer and copy its state again, or pass --force");
+        }
+        println!("[*] Recording the handover despite the issues above");
+    }
+
+    repository
+        .create_handover(
+            wallet.tip_height,
+            wallet.txids.len(),
+            !reconciliation.is_clean(),
+        )
+        .await
+}
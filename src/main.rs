@@ -1,11 +1,14 @@
-use clap::Parser;
-use log::{debug, info};
+use clap::{Parser, Subcommand};
+use log::{debug, info, warn};
 use log4rs;
 use sqlx::postgres::PgPoolOptions;
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
+mod bootstrap;
 mod models;
+mod replay;
 mod repositories;
 pub mod services;
 pub mod settings;
@@ -20,6 +23,54 @@ struct Args {
     log4rs: String,
     #[arg(short, long, default_value = "info")]
     log_level: String,
+    /// Builds and signs transactions without broadcasting them.
+    #[arg(long)]
+    dry_run: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Decrypts a disaster recovery snapshot and checks its integrity.
+    VerifySnapshot { path: String },
+    /// Adopts the wallet state and database of a previous dealer when
+    /// moving servers, then records the handover point.
+    Bootstrap {
+        /// Wallet store directory of the previous dealer.
+        #[arg(long)]
+        wallet_state: String,
+        /// CT descriptor of the previous dealer, checked against ours.
+        #[arg(long)]
+        descriptor: Option<String>,
+        /// Record the handover even if the wallet state and database
+        /// disagree.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Replays captured webhooks and Sideswap notifications against a dealer
+    /// running in dry-run mode.
+    Replay {
+        /// Base URL of the dry-run dealer.
+        #[arg(long, default_value = "http://localhost:8080")]
+        target: String,
+        /// JSON array of `{"source", "payload"}` to replay instead of the
+        /// `webhook_events` table.
+        #[arg(long)]
+        file: Option<String>,
+        /// Only replay events from this source, e.g. `eulen_status`.
+        #[arg(long)]
+        source: Option<String>,
+        /// Only replay events received at or after this time (RFC 3339).
+        #[arg(long)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        /// Only replay events received before this time (RFC 3339).
+        #[arg(long)]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        /// Pause between events, in milliseconds.
+        #[arg(long, default_value_t = 0)]
+        delay_ms: u64,
+    },
 }
 
 #[tokio::main]
@@ -31,17 +82,94 @@ async fn main() {
     info!("Starting Mooze dealer service.");
     debug!("Loading configuration");
 
-    let config = settings::Settings::new(&args.config).expect("Could not load config file.");
+    let mut config = settings::Settings::new(&args.config).expect("Could not load config file.");
+    if args.dry_run {
+        config.dry_run = true;
+    }
+
+    match &args.command {
+        Some(Command::VerifySnapshot { path }) => {
+            verify_snapshot(path, &config.snapshots.encryption_key);
+            return;
+        }
+        Some(Command::Bootstrap {
+            wallet_state,
+            descriptor,
+            force,
+        }) => {
+            let conn = connect_database(&config.postgres).await;
+
+            match bootstrap::bootstrap(&config, conn, wallet_state, descriptor.as_deref(), *force)
+                .await
+            {
+                Ok(handover) => {
+                    println!("[*] Handover {} recorded", handover.id);
+                    if let Some(received_at) = handover.last_webhook_received_at {
+                        println!("    last webhook received at {}", received_at);
+                    }
+                }
+                Err(e) => {
+                    println!("[ERROR] Bootstrap failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(Command::Replay {
+            target,
+            file,
+            source,
+            since,
+            until,
+            delay_ms,
+        }) => {
+            let events = match file {
+                Some(path) => replay::read_file(path).expect("Could not read replay file."),
+                None => {
+                    let conn = connect_database(&config.postgres).await;
+                    repositories::webhooks::WebhookRepository::new(conn)
+                        .list_events(source.as_deref(), *since, *until)
+                        .await
+                        .expect("Could not load webhook events.")
+                        .into_iter()
+                        .map(Into::into)
+                        .collect()
+                }
+            };
+            let events = events
+                .into_iter()
+                .filter(|event| source.as_ref().is_none_or(|s| *s == event.source))
+                .collect();
+
+            if let Err(e) = replay::replay(
+                target,
+                &config.admin.api_key,
+                &config.disputes.webhook_secret,
+                events,
+                Duration::from_millis(*delay_ms),
+            )
+            .await
+            {
+                println!("[ERROR] Replay failed: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        None => {}
+    }
+
+    if config.dry_run {
+        warn!("Running in dry-run mode, transactions will not be broadcast");
+    }
+
+    info!("Running on {:?}", config.network);
+    models::transactions::Assets::init(config.asset_ids());
 
     info!(
         "Connecting to PostgreSQL database at {}",
         &config.postgres.url
     );
-    let conn = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&config.postgres.url)
-        .await
-        .expect("Could not connect to database.");
+    let conn = connect_database(&config.postgres).await;
 
     info!("Starting services.");
     services::start_services(conn, config)
@@ -56,6 +184,58 @@ async fn main() {
     info!("Service shutting down");
 }
 
+/// Keeps retrying until the database answers, so the dealer comes up on its
+/// own once Postgres does instead of exiting.
+async fn connect_database(postgres: &settings::Postgres) -> sqlx::PgPool {
+    let mut backoff = Duration::from_secs(1);
+
+    loop {
+        match PgPoolOptions::new()
+            .max_connections(postgres.max_connections.unwrap_or(5))
+            .connect(&postgres.url)
+            .await
+        {
+            Ok(conn) => return conn,
+            Err(e) => {
+                warn!(
+                    "Could not connect to database, retrying in {:?}: {}",
+                    backoff, e
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(Duration::from_secs(60));
+            }
+        }
+    }
+}
+
+fn verify_snapshot(path: &str, encryption_key: &str) {
+    let data = fs::read(path).expect("Could not read snapshot file.");
+
+    match repositories::snapshots::open_bundle(encryption_key, &data) {
+        Ok(bundle) => {
+            println!("[*] Snapshot OK: {}", bundle.checksum);
+            println!("    created at: {}", bundle.snapshot.created_at);
+            println!(
+                "    pending transactions: {}",
+                bundle.snapshot.pending_transactions.len()
+            );
+            println!(
+                "    unreconciled transactions: {}",
+                bundle.snapshot.unreconciled_transactions.len()
+            );
+            println!("    active quotes: {}", bundle.snapshot.active_quotes.len());
+            println!("    utxos: {}", bundle.snapshot.utxos.len());
+            for (asset, amount) in &bundle.snapshot.reserved_in_cents {
+                println!("    reserved {}: {} cents", asset, amount);
+            }
+        }
+        Err(e) => {
+            println!("[ERROR] Snapshot verification failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 fn init_logging(path: &str) -> Result<(), anyhow::Error> {
     if !Path::new("logs").exists() {
         fs::create_dir("logs")?;
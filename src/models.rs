@@ -1,6 +1,36 @@
+pub mod analytics;
+pub mod audit;
+pub mod campaigns;
+pub mod compliance;
+pub mod disputes;
+pub mod events;
+pub mod handovers;
+pub mod health;
+pub mod hedging;
+pub mod jobs;
+pub mod merchants;
+pub mod operator;
+pub mod otc;
+pub mod payouts;
 pub mod pix;
+pub mod price;
+pub mod quotes;
+pub mod receipts;
+pub mod reconciliation;
+pub mod refunds;
 pub mod referrals;
+pub mod reports;
+pub mod reserves;
+pub mod reviews;
+pub mod risk;
+pub mod sagas;
+pub mod schedules;
 pub mod server;
 pub mod sideswap;
+pub mod snapshots;
+pub mod timeline;
 pub mod transactions;
+pub mod treasury;
 pub mod users;
+pub mod wallet;
+pub mod webhooks;
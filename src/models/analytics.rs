@@ -0,0 +1,30 @@
+use serde::Serialize;
+
+/// Upper bounds, in cents, of the tiers amounts are reported in.
+const AMOUNT_TIERS: [(i32, &str); 4] = [
+    (50 * 100, "0-50"),
+    (200 * 100, "50-200"),
+    (1000 * 100, "200-1000"),
+    (5000 * 100, "1000-5000"),
+];
+
+/// BRL range an amount falls in. Analytics never get exact amounts.
+pub fn amount_tier(amount_in_cents: i32) -> &'static str {
+    AMOUNT_TIERS
+        .iter()
+        .find(|(upper, _)| amount_in_cents < *upper)
+        .map(|(_, tier)| *tier)
+        .unwrap_or("5000+")
+}
+
+/// Product analytics event. Ids are hashed before the event is built.
+#[derive(Clone, Debug, Serialize)]
+pub struct AnalyticsEvent {
+    /// Lets the sink deduplicate events sent again after a failed export.
+    pub id: String,
+    pub name: &'static str,
+    /// Hashed id of the user.
+    pub distinct_id: String,
+    pub properties: serde_json::Value,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct AuditEvent {
+    pub id: String,
+    pub actor: String,
+    pub action: String,
+    pub subject_id: String,
+    /// JSON encoded context of the operation.
+    pub details: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::validation::{self, FieldError, Validate};
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct Campaign {
+    pub id: String,
+    pub name: String,
+    /// Share of the fee waived, in basis points. 10000 waives the whole fee.
+    pub fee_discount_bps: i32,
+    /// Only the user's first paid purchase is eligible.
+    pub first_purchase_only: bool,
+    /// Purchases above this amount are not eligible.
+    pub max_amount_in_cents: Option<i32>,
+    /// Restricts the campaign to one payout asset.
+    pub asset: Option<String>,
+    pub starts_at: chrono::DateTime<chrono::Utc>,
+    pub ends_at: chrono::DateTime<chrono::Utc>,
+    pub active: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Campaign {
+    /// Eligibility rules that only depend on the purchase itself.
+    pub fn applies_to(&self, amount_in_cents: i32, asset: &str) -> bool {
+        self.max_amount_in_cents
+            .is_none_or(|max| amount_in_cents <= max)
+            && self.asset.as_deref().is_none_or(|a| a == asset)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NewCampaign {
+    pub name: String,
+    pub fee_discount_bps: i32,
+    #[serde(default)]
+    pub first_purchase_only: bool,
+    pub max_amount_in_cents: Option<i32>,
+    pub asset: Option<String>,
+    pub starts_at: chrono::DateTime<chrono::Utc>,
+    pub ends_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Validate for NewCampaign {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let ends_before_start = (self.ends_at <= self.starts_at).then_some(FieldError {
+            field: "ends_at",
+            message: "A data final deve ser posterior à inicial.",
+        });
+
+        validation::collect([
+            validation::required("name", &self.name),
+            validation::bps("fee_discount_bps", self.fee_discount_bps),
+            self.max_amount_in_cents
+                .and_then(|max| validation::amount("max_amount_in_cents", max)),
+            self.asset
+                .as_deref()
+                .and_then(|asset| validation::asset("asset", asset)),
+            ends_before_start,
+        ])
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct CampaignRedemption {
+    pub campaign_id: String,
+    pub user_id: String,
+    pub transaction_id: String,
+    /// Fee not charged, in base units of the payout asset.
+    pub fee_discount: i64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
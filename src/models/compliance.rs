@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreeningAction {
+    Allow,
+    /// Pay out, but keep a compliance flag on the transaction.
+    Flag,
+    /// Stop the payout until someone reviews the transaction.
+    Hold,
+    Block,
+}
+
+impl ScreeningAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScreeningAction::Allow => "allow",
+            ScreeningAction::Flag => "flag",
+            ScreeningAction::Hold => "hold",
+            ScreeningAction::Block => "block",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScreeningResult {
+    pub action: ScreeningAction,
+    /// `denylist` or `provider`.
+    pub source: Option<String>,
+    pub reason: Option<String>,
+}
+
+impl ScreeningResult {
+    pub fn allow() -> Self {
+        Self {
+            action: ScreeningAction::Allow,
+            source: None,
+            reason: None,
+        }
+    }
+}
+
+/// Address risk as reported by Chainalysis-style screening APIs.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProviderRisk {
+    pub risk: String,
+}
+
+pub fn risk_rank(risk: &str) -> u8 {
+    match risk.to_lowercase().as_str() {
+        "low" => 0,
+        "medium" => 1,
+        "high" => 2,
+        "severe" => 3,
+        _ => 1,
+    }
+}
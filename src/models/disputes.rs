@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::validation::{self, FieldError, Validate};
+
+/// PIX payment contested through MED, the Central Bank's special return
+/// mechanism, after which the payer's bank may claw the money back.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct PixDispute {
+    pub id: String,
+    /// Id of the dispute at the provider, or given by the operator who
+    /// reported it.
+    pub external_id: String,
+    pub bank_tx_id: String,
+    /// Transaction of the contested charge, when one matched.
+    pub transaction_id: Option<String>,
+    pub user_id: Option<String>,
+    /// Amount contested, which may be less than was paid.
+    pub amount_in_cents: i32,
+    pub reason: String,
+    /// `open`, `clawed_back` once the money was returned to the payer, or
+    /// `rejected` when it stays with the dealer.
+    pub status: String,
+    /// `eulen`, or the operator who reported it.
+    pub reported_by: String,
+    pub resolved_by: Option<String>,
+    pub note: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub resolved_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Dispute notification sent by Eulen, once when the dispute opens and again
+/// when it closes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EulenDisputeNotice {
+    pub id: String,
+    pub bank_tx_id: String,
+    /// Charge the contested payment paid.
+    #[serde(default)]
+    pub qr_id: String,
+    pub status: String,
+    pub value_in_cents: i32,
+    #[serde(default)]
+    pub reason: String,
+}
+
+impl EulenDisputeNotice {
+    /// Status of the dispute in our terms. Statuses we don't know keep it
+    /// open.
+    pub fn dispute_status(&self) -> &'static str {
+        match self.status.as_str() {
+            "accepted" | "refunded" => "clawed_back",
+            "rejected" | "canceled" | "cancelled" => "rejected",
+            _ => "open",
+        }
+    }
+}
+
+/// A dispute to record, from a notification or an operator.
+#[derive(Clone, Debug)]
+pub struct NewDispute {
+    pub external_id: String,
+    pub bank_tx_id: String,
+    pub qr_id: Option<String>,
+    pub amount_in_cents: i32,
+    pub reason: String,
+    pub status: String,
+    pub reported_by: String,
+}
+
+impl From<EulenDisputeNotice> for NewDispute {
+    fn from(notice: EulenDisputeNotice) -> Self {
+        Self {
+            status: notice.dispute_status().to_string(),
+            external_id: notice.id,
+            bank_tx_id: notice.bank_tx_id,
+            qr_id: Some(notice.qr_id).filter(|qr_id| !qr_id.is_empty()),
+            amount_in_cents: notice.value_in_cents,
+            reason: notice.reason,
+            reported_by: "eulen".to_string(),
+        }
+    }
+}
+
+/// Dispute an operator learned of outside the webhook, such as from the
+/// provider's support.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReportDisputeRequest {
+    pub external_id: String,
+    pub bank_tx_id: String,
+    pub qr_id: Option<String>,
+    pub amount_in_cents: i32,
+    #[serde(default)]
+    pub reason: String,
+    pub reported_by: String,
+}
+
+impl Validate for ReportDisputeRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([
+            validation::required("external_id", &self.external_id),
+            validation::required("bank_tx_id", &self.bank_tx_id),
+            validation::optional("qr_id", self.qr_id.as_deref()),
+            validation::amount("amount_in_cents", self.amount_in_cents),
+            validation::optional("reason", Some(&self.reason)),
+            validation::required("reported_by", &self.reported_by),
+        ])
+    }
+}
+
+impl From<ReportDisputeRequest> for NewDispute {
+    fn from(req: ReportDisputeRequest) -> Self {
+        Self {
+            external_id: req.external_id,
+            bank_tx_id: req.bank_tx_id,
+            qr_id: req.qr_id,
+            amount_in_cents: req.amount_in_cents,
+            reason: req.reason,
+            status: "open".to_string(),
+            reported_by: req.reported_by,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DisputeQuery {
+    /// Disputes still `open` by default.
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisputeDecision {
+    ClawedBack,
+    Rejected,
+}
+
+impl DisputeDecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisputeDecision::ClawedBack => "clawed_back",
+            DisputeDecision::Rejected => "rejected",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DisputeDecisionRequest {
+    pub decided_by: String,
+    pub note: Option<String>,
+}
+
+impl Validate for DisputeDecisionRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([
+            validation::required("decided_by", &self.decided_by),
+            validation::optional("note", self.note.as_deref()),
+        ])
+    }
+}
+
+/// Disputes of a status, and how much of what they contest was already paid
+/// out.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct DisputeExposure {
+    pub status: String,
+    pub disputes: i64,
+    pub amount_in_cents: i64,
+    /// Contested in disputes whose transaction was paid out.
+    pub paid_out_in_cents: i64,
+    /// Disputes no transaction matched.
+    pub unlinked: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eulen_statuses_map_to_dispute_statuses() {
+        let notice = |status: &str| {
+            serde_json::from_value::<EulenDisputeNotice>(serde_json::json!({
+                "id": "med-1",
+                "bankTxId": "E123",
+                "status": status,
+                "valueInCents": 5000,
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(notice("open").dispute_status(), "open");
+        assert_eq!(notice("under_review").dispute_status(), "open");
+        assert_eq!(notice("refunded").dispute_status(), "clawed_back");
+        assert_eq!(notice("canceled").dispute_status(), "rejected");
+
+        let dispute = NewDispute::from(notice("accepted"));
+        assert_eq!(dispute.status, "clawed_back");
+        assert_eq!(dispute.qr_id, None);
+        assert_eq!(dispute.reported_by, "eulen");
+    }
+}
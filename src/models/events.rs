@@ -0,0 +1,42 @@
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    TransactionCreated,
+    PixPaid,
+    PayoutSent,
+    SwapCompleted,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::TransactionCreated => "transaction_created",
+            EventKind::PixPaid => "pix_paid",
+            EventKind::PayoutSent => "payout_sent",
+            EventKind::SwapCompleted => "swap_completed",
+        }
+    }
+}
+
+/// Event published to the message broker. Delivery is at least once, so
+/// consumers deduplicate on `id`.
+#[derive(Clone, Debug, Serialize)]
+pub struct DomainEvent {
+    pub id: String,
+    pub kind: EventKind,
+    pub occurred_at: chrono::DateTime<chrono::Utc>,
+    pub data: serde_json::Value,
+}
+
+impl DomainEvent {
+    pub fn new(kind: EventKind, data: serde_json::Value) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            occurred_at: chrono::Utc::now(),
+            data,
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use serde::Serialize;
+
+/// Point at which this dealer took over a wallet and database from another
+/// one. Webhooks received up to `last_webhook_received_at` were processed by
+/// the previous dealer.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct Handover {
+    pub id: String,
+    pub wallet_tip_height: i32,
+    pub wallet_transactions: i32,
+    pub last_webhook_event_id: Option<String>,
+    pub last_webhook_received_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Whether the handover was recorded despite reconciliation issues.
+    pub forced: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Disagreements between an imported wallet state and Postgres.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Reconciliation {
+    /// Payout txids recorded in Postgres that the wallet doesn't know about,
+    /// i.e. the state was copied before the previous dealer stopped.
+    pub missing_payouts: Vec<String>,
+    /// Payouts started and never given a txid.
+    pub unfinished_payouts: i64,
+    /// Webhooks stored and never processed.
+    pub unprocessed_webhooks: i64,
+}
+
+impl Reconciliation {
+    pub fn is_clean(&self) -> bool {
+        self.missing_payouts.is_empty()
+            && self.unfinished_payouts == 0
+            && self.unprocessed_webhooks == 0
+    }
+}
@@ -0,0 +1,63 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::operator::AssetAvailability;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ServiceState {
+    /// `starting` until its dependencies answered, `running`, `restarting`
+    /// after the service crashed, or `standby` for a service that only runs
+    /// on the leader.
+    pub status: &'static str,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    pub since: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HealthReport {
+    /// `ok`, `starting` while services wait for their dependencies, or
+    /// `degraded` once a service crashed.
+    pub status: &'static str,
+    /// `leader`, or `follower` while another instance moves money.
+    pub role: &'static str,
+    /// Every service is running, or on standby on a follower.
+    pub ready: bool,
+    pub services: BTreeMap<&'static str, ServiceState>,
+    /// Whether each long-lived connection, such as the Sideswap websocket,
+    /// is up.
+    pub connections: BTreeMap<&'static str, bool>,
+    /// Functionality depending on a service that is down.
+    pub degraded: Vec<&'static str>,
+}
+
+/// Requests to a dependency over the latency window, against its objective.
+#[derive(Clone, Debug, Serialize)]
+pub struct DependencyLatency {
+    pub dependency: &'static str,
+    pub requests: usize,
+    pub success_rate: Option<f64>,
+    pub p50_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub max_ms: Option<u64>,
+    pub p95_target_ms: Option<u64>,
+    /// Whether the success rate and p95 meet the objective. None without
+    /// requests in the window.
+    pub within_slo: Option<bool>,
+}
+
+/// Public summary of the dealer, for a status page.
+#[derive(Clone, Debug, Serialize)]
+pub struct PublicStatus {
+    pub accepting_deposits: bool,
+    /// While deposits are closed for maintenance or outside business hours.
+    pub deposits_reopen_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+    pub assets: Vec<AssetAvailability>,
+    /// `operational`, `degraded` while Eulen keeps failing requests, or
+    /// `unavailable` while the PIX service is down.
+    pub pix: &'static str,
+    /// Average time from the PIX payment to the payout over the last hour.
+    /// None without payouts in that time.
+    pub average_delivery_secs: Option<u64>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
@@ -0,0 +1,41 @@
+use serde::Serialize;
+
+/// Offsetting swap of DEPIX into an asset the dealer is short of.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct Hedge {
+    pub id: i64,
+    /// None until the swap was accepted.
+    pub quote_sub_id: Option<i64>,
+    pub asset: String,
+    /// Units the hedge was sized to buy back.
+    pub expected_amount: i64,
+    /// DEPIX sold, in base units.
+    pub depix_amount: i64,
+    /// pending while its swap is requested, then open, filled, failed or
+    /// expired. An expired hedge may still fill.
+    pub status: String,
+    pub received_amount: Option<i64>,
+    pub realized_pnl_cents: Option<i64>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub closed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Open position of an asset, summed from the exposure ledger.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct AssetExposure {
+    pub asset: String,
+    /// Units sold and not bought back yet. Negative when over-hedged.
+    pub open_amount: i64,
+    /// BRL received for the open units.
+    pub open_brl_cents: i64,
+    /// Units expected from open hedges.
+    pub pending_amount: i64,
+    pub realized_pnl_cents: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HedgingReport {
+    pub enabled: bool,
+    pub exposures: Vec<AssetExposure>,
+    pub hedges: Vec<Hedge>,
+}
@@ -0,0 +1,20 @@
+use serde::Serialize;
+
+/// Schedule and outcome of the runs of a background job.
+#[derive(Clone, Debug, Serialize)]
+pub struct JobStatus {
+    pub name: &'static str,
+    /// Cron expression or interval, e.g. `0 3 * * *` or `60s`.
+    pub schedule: String,
+    pub running: bool,
+    pub next_run_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_duration_ms: Option<u64>,
+    /// Error of the last run, `None` when it succeeded.
+    pub last_error: Option<String>,
+    pub runs: u64,
+    pub failures: u64,
+    /// Failed runs since the last success.
+    pub consecutive_failures: u64,
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::validation::{self, FieldError, Validate};
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct Merchant {
+    pub id: String,
+    /// Account the link payments are recorded under.
+    pub user_id: String,
+    pub name: String,
+    pub payout_address: String,
+    pub payout_asset: String,
+    pub network: String,
+    /// Fee charged on each link payment, in basis points of the BRL amount.
+    pub fee_bps: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NewMerchant {
+    pub name: String,
+    pub payout_address: String,
+    pub payout_asset: String,
+    pub network: String,
+    /// Defaults to `merchants.default_fee_bps`.
+    pub fee_bps: Option<i32>,
+}
+
+impl Validate for NewMerchant {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([
+            validation::required("name", &self.name),
+            validation::address("payout_address", &self.payout_address),
+            validation::asset("payout_asset", &self.payout_asset),
+            validation::network("network", &self.network),
+            self.fee_bps
+                .and_then(|fee_bps| validation::bps("fee_bps", fee_bps)),
+        ])
+    }
+}
+
+/// Returned once on registration; only the hash of the key is stored.
+#[derive(Clone, Debug, Serialize)]
+pub struct MerchantCredentials {
+    pub merchant: Merchant,
+    pub api_key: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct PaymentLink {
+    pub token: String,
+    pub merchant_id: String,
+    pub merchant_name: String,
+    pub transaction_id: String,
+    pub amount_in_cents: i32,
+    pub description: Option<String>,
+    pub qr_copy_paste: String,
+    pub qr_image_url: String,
+    /// Status of the underlying transaction.
+    pub transaction_status: String,
+    /// Service invoice issued for the fee, once the payout finished.
+    pub invoice_id: Option<String>,
+    #[sqlx(skip)]
+    pub url: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl PaymentLink {
+    /// Status shown to the payer: `open` until the PIX payment settles,
+    /// then `paid`, whatever happens to the merchant's payout afterwards.
+    pub fn status(&self) -> &'static str {
+        match self.transaction_status.as_str() {
+            "eulen_depix_sent"
+            | "scheduled"
+            | "pricing_unavailable"
+            | "finished"
+            | "held"
+            | "blocked" => "paid",
+            "refund_requested" | "eulen_refunded" | "failed" => "refunded",
+            "eulen_canceled" | "eulen_expired" | "eulen_error" | "cancelled" => "expired",
+            _ => "open",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NewPaymentLink {
+    pub amount_in_cents: i32,
+    pub description: Option<String>,
+}
+
+impl Validate for NewPaymentLink {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([
+            validation::amount("amount_in_cents", self.amount_in_cents),
+            validation::optional("description", self.description.as_deref()),
+        ])
+    }
+}
+
+/// Service invoice for the fee of a paid payment link.
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct FeeInvoice {
+    pub transaction_id: String,
+    pub merchant_id: String,
+    /// Amount the payer paid.
+    pub amount_in_cents: i32,
+    pub fee_bps: i32,
+    /// Value of the invoiced service.
+    pub fee_in_cents: i64,
+    /// `pending`, `issued` or `failed`.
+    pub status: String,
+    pub invoice_id: Option<String>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
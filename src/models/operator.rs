@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::validation::{self, FieldError, Validate};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct KillSwitchRequest {
+    /// Operator flipping the switch, recorded in the audit trail.
+    pub requested_by: String,
+    pub reason: Option<String>,
+}
+
+impl Validate for KillSwitchRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([
+            validation::required("requested_by", &self.requested_by),
+            validation::optional("reason", self.reason.as_deref()),
+        ])
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AssetAvailability {
+    pub asset_id: String,
+    pub ticker: String,
+    /// Whether deposits for the asset are taken and its payouts sent.
+    pub enabled: bool,
+}
+
+/// Period new deposits are closed for, such as a provider's maintenance.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct MaintenanceWindow {
+    pub id: String,
+    pub starts_at: chrono::DateTime<chrono::Utc>,
+    pub ends_at: chrono::DateTime<chrono::Utc>,
+    pub reason: String,
+    /// Operator who scheduled it, or `settings` for the windows configured.
+    pub scheduled_by: String,
+}
+
+impl MaintenanceWindow {
+    pub fn contains(&self, at: chrono::DateTime<chrono::Utc>) -> bool {
+        self.starts_at <= at && at < self.ends_at
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NewMaintenanceWindow {
+    pub starts_at: chrono::DateTime<chrono::Utc>,
+    pub ends_at: chrono::DateTime<chrono::Utc>,
+    /// Recorded in the audit trail, e.g. the provider's maintenance notice.
+    pub reason: String,
+    pub scheduled_by: String,
+}
+
+impl Validate for NewMaintenanceWindow {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([
+            validation::period("ends_at", self.starts_at, self.ends_at),
+            validation::required("reason", &self.reason),
+            validation::required("scheduled_by", &self.scheduled_by),
+        ])
+    }
+}
+
+/// Why new deposits are not taken right now, and when they are again.
+#[derive(Clone, Debug, Serialize)]
+pub struct DepositClosure {
+    /// `maintenance` or `business_hours`.
+    pub closed_for: &'static str,
+    /// Reason of the maintenance window.
+    pub note: Option<String>,
+    /// In local time. None when nothing reopens them.
+    pub reopens_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PendingRetryRequest {
+    pub requested_by: String,
+}
+
+impl Validate for PendingRetryRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([validation::required("requested_by", &self.requested_by)])
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PendingDropRequest {
+    pub requested_by: String,
+    /// Why the payout is given up on, recorded in the audit trail.
+    pub reason: String,
+}
+
+impl Validate for PendingDropRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([
+            validation::required("requested_by", &self.requested_by),
+            validation::required("reason", &self.reason),
+        ])
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RecentTransactionsQuery {
+    pub limit: Option<i64>,
+}
+
+/// Filters of the admin transaction and PIX searches, all optional. Results
+/// are newest first; `cursor` is the `next_cursor` of the previous page.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct TransactionSearch {
+    pub status: Option<String>,
+    pub asset: Option<String>,
+    pub user_id: Option<String>,
+    pub min_amount_in_cents: Option<i32>,
+    pub max_amount_in_cents: Option<i32>,
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Range of the archive export, by creation time. `cursor` is the
+/// `next_cursor` of the previous page.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ArchiveExportQuery {
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Position after the last row of a page: its creation time and id.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        format!("{}_{}", self.created_at.timestamp_micros(), self.id)
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let (micros, id) = cursor.split_once('_')?;
+        let created_at = chrono::DateTime::from_timestamp_micros(micros.parse().ok()?)?;
+
+        Some(Self {
+            created_at,
+            id: id.to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// None on the last page.
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Page of the first `limit` rows; fetching one more row tells whether
+    /// another page follows.
+    pub fn from_rows(mut rows: Vec<T>, limit: i64, cursor: impl Fn(&T) -> Cursor) -> Self {
+        let next_cursor = if rows.len() as i64 > limit {
+            rows.truncate(limit as usize);
+            rows.last().map(|row| cursor(row).encode())
+        } else {
+            None
+        };
+
+        Self {
+            items: rows,
+            next_cursor,
+        }
+    }
+}
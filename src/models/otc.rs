@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+pub struct OtcQuoteRequest<'a> {
+    pub sell_asset: &'a str,
+    pub receive_asset: &'a str,
+    pub amount: i64,
+}
+
+/// Firm quote of the desk, net of its fees.
+#[derive(Debug, Deserialize)]
+pub struct OtcQuote {
+    pub quote_id: String,
+    pub receive_amount: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OtcAcceptRequest<'a> {
+    pub receive_address: &'a str,
+}
+
+/// Where the sold asset has to be sent for an accepted quote.
+#[derive(Debug, Deserialize)]
+pub struct OtcAcceptance {
+    pub swap_id: String,
+    pub deposit_address: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OtcSwapStatus {
+    /// pending, completed or failed.
+    pub status: String,
+    /// Payout of the desk, once completed.
+    pub txid: Option<String>,
+    pub received_amount: Option<u64>,
+}
+
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct OtcSwap {
+    pub id: i64,
+    pub external_id: String,
+    pub sell_asset: String,
+    pub receive_asset: String,
+    pub sold_amount: i64,
+    pub expected_amount: i64,
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct Payout {
+    pub transaction_id: String,
+    pub txid: Option<String>,
+    /// L-BTC paid to the network, in sats, once broadcast.
+    pub network_fee: Option<i64>,
+    /// `broadcasting` until the txid is known, then `broadcast`.
+    pub status: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Part of a payout sent before the wallet covered all of it.
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct PartialPayout {
+    pub id: i64,
+    pub transaction_id: String,
+    /// Base units of the transaction's asset.
+    pub amount: i64,
+    pub txid: Option<String>,
+    pub network_fee: Option<i64>,
+    /// `broadcasting` until the txid is known, then `broadcast`.
+    pub status: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct PixTransaction {
     pub id: String,
     pub transaction_id: String,
@@ -37,6 +37,51 @@ pub struct EulenDepositStatus {
     pub value_in_cents: i32,
 }
 
+impl EulenDepositStatus {
+    /// Position of the status in the Eulen deposit lifecycle, used to apply
+    /// backfilled updates in causal order.
+    pub fn status_rank(&self) -> u8 {
+        status_rank(&self.status)
+    }
+}
+
+/// Position of an Eulen deposit status in its lifecycle. Statuses of our
+/// own, such as "cancelled", rank with "under_review".
+pub fn status_rank(status: &str) -> u8 {
+    match status {
+        "pending" => 0,
+        "under_review" => 1,
+        "depix_sent" => 2,
+        "canceled" | "error" | "expired" => 3,
+        "refunded" => 4,
+        _ => 1,
+    }
+}
+
+/// Outcome of applying an Eulen status to the charge it refers to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DepositStatusUpdate {
+    /// Applied to the charge of this transaction.
+    Applied(String),
+    /// The charge of this transaction was already at or past the status.
+    Stale(String),
+    /// No charge matches, e.g. a chat deposit.
+    Unmatched,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EulenBatchItemResult {
+    pub index: usize,
+    pub bank_tx_id: String,
+    pub qr_id: String,
+    pub status: String,
+    /// One of "applied", "duplicate", "superseded", "stale", "ignored" or
+    /// "failed".
+    pub result: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Deposit {
     pub id: String,
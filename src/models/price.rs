@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Fiat currency prices are quoted in. Transaction amounts are minor units
+/// (cents) of the quote currency of the rail they came from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteCurrency {
+    #[default]
+    Brl,
+    Usd,
+    Eur,
+}
+
+impl QuoteCurrency {
+    pub const ALL: [QuoteCurrency; 3] =
+        [QuoteCurrency::Brl, QuoteCurrency::Usd, QuoteCurrency::Eur];
+
+    /// Lowercase ISO 4217 code, as used by the price providers.
+    pub fn code(&self) -> &'static str {
+        match self {
+            QuoteCurrency::Brl => "brl",
+            QuoteCurrency::Usd => "usd",
+            QuoteCurrency::Eur => "eur",
+        }
+    }
+}
+
+/// How quotes from several price providers are combined into one price.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceAggregation {
+    /// Middle quote, or the mean of the two middle ones.
+    #[default]
+    Median,
+    /// Median weighted by each provider's 24h traded volume.
+    VolumeWeighted,
+    /// Mean of the quotes without the highest and the lowest one.
+    TrimmedMean,
+}
+
+impl PriceAggregation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriceAggregation::Median => "median",
+            PriceAggregation::VolumeWeighted => "volume_weighted",
+            PriceAggregation::TrimmedMean => "trimmed_mean",
+        }
+    }
+}
+
+/// Price of an asset as served by the price service.
+#[derive(Clone, Debug, Serialize)]
+pub struct AssetPrice {
+    /// Per whole unit of the asset, spread included.
+    pub price: f64,
+    /// How the provider quotes were combined; none for pegged prices.
+    pub aggregation: Option<PriceAggregation>,
+    pub updated_at: DateTime<Utc>,
+    /// When the next fetch replaces this price.
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PriceQuery {
+    /// Asset id, as in deposits.
+    pub asset: String,
+    #[serde(default)]
+    pub currency: QuoteCurrency,
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::validation::{self, FieldError, Validate};
+
+/// Price locked for a deposit created before `expires_at`.
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct PriceQuote {
+    pub id: String,
+    pub asset: String,
+    pub quote_currency: String,
+    /// Per whole unit of the asset, spread included.
+    pub price_in_cents: i64,
+    pub aggregation: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl PriceQuote {
+    pub fn is_valid_for(&self, asset: &str, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.asset == asset && self.expires_at > now
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct QuoteRequest {
+    pub asset: String,
+}
+
+impl Validate for QuoteRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([validation::asset("asset", &self.asset)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::clock::{Clock, MockClock};
+    use chrono::TimeZone;
+
+    fn quote(clock: &MockClock, ttl_secs: i64) -> PriceQuote {
+        PriceQuote {
+            id: "quote".to_string(),
+            asset: "depix".to_string(),
+            quote_currency: "BRL".to_string(),
+            price_in_cents: 100,
+            aggregation: None,
+            created_at: clock.now(),
+            expires_at: clock.now() + chrono::Duration::seconds(ttl_secs),
+        }
+    }
+
+    #[test]
+    fn quote_expires_after_its_ttl() {
+        let clock = MockClock::at(chrono::Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap());
+        let quote = quote(&clock, 60);
+        assert!(quote.is_valid_for("depix", clock.now()));
+
+        clock.advance(chrono::Duration::seconds(59));
+        assert!(quote.is_valid_for("depix", clock.now()));
+
+        clock.advance(chrono::Duration::seconds(1));
+        assert!(!quote.is_valid_for("depix", clock.now()));
+    }
+
+    #[test]
+    fn quote_is_only_valid_for_its_asset() {
+        let clock = MockClock::at(chrono::Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap());
+        let quote = quote(&clock, 60);
+        assert!(!quote.is_valid_for("lbtc", clock.now()));
+    }
+}
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+
+use super::transactions::Assets;
+use crate::utils::money;
+use crate::utils::validation::{self, FieldError, Validate};
+
+pub const RECEIPT_SUBJECT: &str = "Comprovante do seu pagamento Mooze";
+
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct NotificationPreferences {
+    pub user_id: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub email_receipts: bool,
+    pub sms_receipts: bool,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Preferences sent by the app, replacing the stored ones.
+#[derive(Clone, Debug, Deserialize)]
+pub struct NotificationPreferencesUpdate {
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub email_receipts: bool,
+    #[serde(default)]
+    pub sms_receipts: bool,
+}
+
+impl Validate for NotificationPreferencesUpdate {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let email = match self.email.as_deref() {
+            Some(email) => validation::email("email", email),
+            None if self.email_receipts => validation::required("email", ""),
+            None => None,
+        };
+        let phone = match self.phone.as_deref() {
+            Some(phone) => validation::phone("phone", phone),
+            None if self.sms_receipts => validation::required("phone", ""),
+            None => None,
+        };
+
+        validation::collect([email, phone])
+    }
+}
+
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct Receipt {
+    pub id: i64,
+    pub transaction_id: String,
+    pub user_id: String,
+    /// `email` or `sms`.
+    pub channel: String,
+    pub recipient: String,
+    /// `sending` until the provider answers, then `sent` or `failed`.
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// What a receipt tells about a broadcast payout, with where the user wants
+/// it sent.
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct ReceiptDetails {
+    pub transaction_id: String,
+    pub user_id: String,
+    pub amount_in_cents: i32,
+    pub asset: String,
+    /// Dealer fee, in base units of `asset`.
+    pub fee_collected: Option<i32>,
+    pub txid: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub email_receipts: bool,
+    pub sms_receipts: bool,
+}
+
+impl ReceiptDetails {
+    /// Channels the user opted into, with their recipient.
+    pub fn channels(&self) -> Vec<(&'static str, String)> {
+        let mut channels = Vec::new();
+        if let (true, Some(email)) = (self.email_receipts, &self.email) {
+            channels.push(("email", email.clone()));
+        }
+        if let (true, Some(phone)) = (self.sms_receipts, &self.phone) {
+            channels.push(("sms", phone.clone()));
+        }
+        channels
+    }
+
+    /// Receipt text, linking the payout at `explorer_url` followed by the txid.
+    pub fn text(&self, explorer_url: &str) -> String {
+        let ticker = Assets::from_hex(&self.asset)
+            .map(|asset| asset.ticker())
+            .unwrap_or("");
+        let fee = money::decimal(
+            self.fee_collected.unwrap_or(0).into(),
+            Assets::precision_of(&self.asset).into(),
+        );
+
+        format!(
+            "Seu pagamento de {} foi enviado.\nTaxa: {} {}\nTransação: {}{}",
+            money::format_brl(self.amount_in_cents.into()),
+            fee,
+            ticker,
+            explorer_url,
+            self.txid
+        )
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use super::transactions::Assets;
+
+/// DEPIX Eulen reported minting for a paid charge, and what the wallet got.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct DepixSettlement {
+    pub eulen_id: String,
+    pub transaction_id: String,
+    pub bank_tx_id: String,
+    /// Txid Eulen reported, empty reports are stored as none.
+    pub blockchain_tx_id: Option<String>,
+    pub value_in_cents: i32,
+    /// `unmatched`, `matched`, `short` when less arrived than reported, or
+    /// `missing` when nothing arrived within the grace period.
+    pub status: String,
+    pub matched_txid: Option<String>,
+    /// `txid` or `amount`.
+    pub matched_by: Option<String>,
+    /// DEPIX received in the matched transaction, in base units.
+    pub received_amount: Option<i64>,
+    pub reported_at: chrono::DateTime<chrono::Utc>,
+    pub checked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl DepixSettlement {
+    /// DEPIX base units Eulen should have sent, at one real per DEPIX.
+    pub fn expected_amount(&self) -> i64 {
+        i64::from(self.value_in_cents) * Assets::DEPIX.unit() as i64 / 100
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DepixSettlementQuery {
+    /// Flagged settlements, `short` and `missing`, by default.
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
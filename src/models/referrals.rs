@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct Referral {
     pub id: String,
     pub user_id: String,
@@ -9,3 +9,60 @@ pub struct Referral {
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
+
+/// Bonus a referrer earned on a payout of a user they referred.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct ReferralBonus {
+    pub transaction_id: String,
+    pub referrer_id: String,
+    pub referred_user_id: String,
+    pub asset: String,
+    /// Base units of `asset`.
+    pub amount: i64,
+    pub amount_in_cents: i64,
+    /// `earned` until the payout carrying it is broadcast, then `paid`.
+    pub status: String,
+    pub txid: Option<String>,
+    pub earned_at: chrono::DateTime<chrono::Utc>,
+    pub paid_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Bonuses of a referrer in a month and asset: the ones earned in the
+/// month, and the ones paid in it.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct ReferralRollup {
+    pub referrer_id: String,
+    pub month: chrono::NaiveDate,
+    pub asset: String,
+    pub earned_count: i32,
+    pub earned: i64,
+    pub earned_in_cents: i64,
+    pub paid_count: i32,
+    pub paid: i64,
+    pub paid_in_cents: i64,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// What a referrer earned and was paid in a month.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReferrerStatement {
+    pub referrer_id: String,
+    pub month: String,
+    pub rollups: Vec<ReferralRollup>,
+    /// Bonuses earned in the month, in the order they were.
+    pub bonuses: Vec<ReferralBonus>,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Returned once when a key is issued; only its hash is stored.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReferrerCredentials {
+    pub user_id: String,
+    pub api_key: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReferralReportQuery {
+    /// Month of the rollups, as `YYYY-MM`.
+    pub month: String,
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::validation::{self, FieldError, Validate};
+
+/// Amount a payer sent above the PIX charge, owed back to them.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct PixRefund {
+    pub id: String,
+    pub transaction_id: String,
+    pub eulen_id: String,
+    pub bank_tx_id: String,
+    pub payer_name: String,
+    pub payer_tax_number: String,
+    pub charged_in_cents: i32,
+    pub paid_in_cents: i32,
+    /// What is owed, the paid amount less the charge.
+    pub amount_in_cents: i32,
+    /// `due`, `refunded` once paid back, or `dismissed`.
+    pub status: String,
+    pub resolved_by: Option<String>,
+    pub note: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub resolved_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RefundQuery {
+    /// Refunds still `due` by default.
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefundDecision {
+    Refunded,
+    Dismissed,
+}
+
+impl RefundDecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RefundDecision::Refunded => "refunded",
+            RefundDecision::Dismissed => "dismissed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RefundDecisionRequest {
+    pub decided_by: String,
+    /// Reference of the PIX paid back, or why the refund was dismissed.
+    pub note: Option<String>,
+}
+
+impl Validate for RefundDecisionRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([
+            validation::required("decided_by", &self.decided_by),
+            validation::optional("note", self.note.as_deref()),
+        ])
+    }
+}
@@ -0,0 +1,54 @@
+use serde::Deserialize;
+
+use super::transactions::Assets;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TaxReportQuery {
+    /// Month of the report, as `YYYY-MM`.
+    pub month: String,
+    /// Generates the report again instead of returning the stored one.
+    #[serde(default)]
+    pub regenerate: bool,
+}
+
+/// Asset sold to a user: a deposit whose payout was broadcast.
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct TaxableSale {
+    pub transaction_id: String,
+    pub paid_at: chrono::DateTime<chrono::Utc>,
+    pub amount_in_cents: i32,
+    pub asset: String,
+    /// Dealer fee, in base units of `asset`.
+    pub fee_collected: Option<i32>,
+    /// BRL cents per whole unit of `asset` the payout was computed with.
+    pub price_in_cents: Option<i64>,
+    /// Eulen ids of the PIX charges that paid the deposit.
+    pub eulen_ids: Vec<String>,
+}
+
+impl TaxableSale {
+    /// Dealer fee in BRL cents, at the payout price.
+    pub fn fee_in_cents(&self) -> i64 {
+        let fee = i128::from(self.fee_collected.unwrap_or(0));
+        let price = i128::from(self.price_in_cents.unwrap_or(0));
+        (fee * price / i128::from(Assets::unit_of(&self.asset))) as i64
+    }
+
+    /// Base units of the asset delivered, net of the fee. Zero when the
+    /// price was not recorded.
+    pub fn quantity(&self) -> i64 {
+        let Some(price) = self.price_in_cents.filter(|price| *price > 0) else {
+            return 0;
+        };
+        let unit = i128::from(Assets::unit_of(&self.asset));
+        let gross = i128::from(self.amount_in_cents) * unit / i128::from(price);
+        (gross - i128::from(self.fee_collected.unwrap_or(0))) as i64
+    }
+}
+
+/// Payer of a PIX charge, as reported by Eulen.
+#[derive(Clone, Debug, Default)]
+pub struct Payer {
+    pub name: String,
+    pub tax_number: String,
+}
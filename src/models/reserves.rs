@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::utils::validation::{self, FieldError, Validate};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReserveProofRequest {
+    /// Text chosen by the auditor, included in the signed message so the
+    /// signatures can't have been made in advance.
+    pub challenge: Option<String>,
+}
+
+impl Validate for ReserveProofRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([validation::optional("challenge", self.challenge.as_deref())])
+    }
+}
+
+/// Wallet address holding unspent outputs, with a signature of the proof
+/// message by its key.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReserveAddress {
+    /// Unconfidential address.
+    pub address: String,
+    /// external for receive addresses, internal for change.
+    pub chain: String,
+    pub derivation_index: u32,
+    /// Unspent amount per asset id, in base units.
+    pub balances: BTreeMap<String, u64>,
+    /// BIP-137 signature of the message (Bitcoin signed message format,
+    /// segwit header byte), base64 encoded.
+    pub signature: String,
+}
+
+/// What the dealer owes users in one status and asset: deposits paid by
+/// PIX and not paid out yet, or to be refunded.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct Obligation {
+    pub status: String,
+    pub asset: String,
+    pub transactions: i64,
+    pub amount_in_cents: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProofOfReserves {
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+    /// Message signed by every address.
+    pub message: String,
+    pub addresses: Vec<ReserveAddress>,
+    /// Unspent amount per asset id over every address, in base units.
+    pub totals: BTreeMap<String, u64>,
+    pub obligations: Vec<Obligation>,
+}
+
+/// Proof as published: its JSON and the SHA-256 of exactly that JSON.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct ReserveProof {
+    pub hash: String,
+    pub content: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
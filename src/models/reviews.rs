@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use super::transactions::Transaction;
+use crate::utils::validation::{self, FieldError, Validate};
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct Review {
+    pub id: String,
+    pub transaction_id: String,
+    /// What put the transaction on hold: `compliance`, `amount_mismatch`, ...
+    pub source: String,
+    pub reason: String,
+    /// `pending`, `approved` or `rejected`.
+    pub status: String,
+    pub decided_by: Option<String>,
+    pub note: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub decided_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HeldTransaction {
+    pub review: Review,
+    pub transaction: Transaction,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReviewDecision {
+    Approve,
+    Reject,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReviewDecisionRequest {
+    pub decided_by: String,
+    pub note: Option<String>,
+}
+
+impl Validate for ReviewDecisionRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([
+            validation::required("decided_by", &self.decided_by),
+            validation::optional("note", self.note.as_deref()),
+        ])
+    }
+}
@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+
+/// Score every account starts from, before its signals are weighed.
+const BASE_SCORE: i32 = 40;
+/// Highest score a band grants trust to, and lowest it treats as risky.
+const TRUSTED_UP_TO: i32 = 25;
+const RISKY_FROM: i32 = 75;
+
+/// How much of the consumer limits a user gets, by how risky the account
+/// looks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskBand {
+    Trusted,
+    Standard,
+    Risky,
+}
+
+impl RiskBand {
+    pub fn of(score: i32) -> Self {
+        if score <= TRUSTED_UP_TO {
+            RiskBand::Trusted
+        } else if score >= RISKY_FROM {
+            RiskBand::Risky
+        } else {
+            RiskBand::Standard
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskBand::Trusted => "trusted",
+            RiskBand::Standard => "standard",
+            RiskBand::Risky => "risky",
+        }
+    }
+
+    pub fn parse(band: &str) -> Option<Self> {
+        match band {
+            "trusted" => Some(RiskBand::Trusted),
+            "standard" => Some(RiskBand::Standard),
+            "risky" => Some(RiskBand::Risky),
+            _ => None,
+        }
+    }
+
+    /// Share of the consumer limits granted, in basis points.
+    pub fn limit_bps(&self) -> i64 {
+        match self {
+            RiskBand::Trusted => 20_000,
+            RiskBand::Standard => 10_000,
+            RiskBand::Risky => 5_000,
+        }
+    }
+
+    /// `limit` scaled to the band.
+    pub fn scale(&self, limit: i64) -> i64 {
+        limit.saturating_mul(self.limit_bps()) / 10_000
+    }
+}
+
+/// What is known about an account when it is scored.
+#[derive(Clone, Debug)]
+pub struct RiskSignals {
+    pub account_age_days: i64,
+    pub verified: bool,
+    /// Deposits the user completed, in cents.
+    pub total_volume_cents: i64,
+    /// Deposits whose payment errored, was cancelled at Eulen or arrived
+    /// after the user cancelled.
+    pub failed_payments: i64,
+    /// Other accounts bound to a device of the user.
+    pub shared_device_accounts: i64,
+}
+
+/// A signal that moved the score, kept so operators can tell why a user got
+/// the limits they have.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RiskFactor {
+    pub signal: String,
+    /// Added to the score, negative when the signal builds trust.
+    pub points: i32,
+    pub detail: String,
+}
+
+impl RiskSignals {
+    /// Score from 0, most trusted, to 100, riskiest, with the factors that
+    /// moved it away from the base.
+    pub fn score(&self) -> (i32, Vec<RiskFactor>) {
+        let mut factors = Vec::new();
+        let mut add = |signal: &str, points: i32, detail: String| {
+            if points != 0 {
+                factors.push(RiskFactor {
+                    signal: signal.to_string(),
+                    points,
+                    detail,
+                });
+            }
+        };
+
+        let age_points = match self.account_age_days {
+            days if days < 7 => 15,
+            days if days < 30 => 5,
+            days if days >= 180 => -10,
+            _ => 0,
+        };
+        add(
+            "account_age",
+            age_points,
+            format!("Account created {} days ago", self.account_age_days),
+        );
+
+        if self.verified {
+            add("verified", -20, "Account is verified".to_string());
+        }
+
+        let volume_points = match self.total_volume_cents {
+            0 => 10,
+            cents if cents >= 10_000 * 100 => -20,
+            cents if cents >= 1_500 * 100 => -10,
+            _ => 0,
+        };
+        add(
+            "volume",
+            volume_points,
+            format!("Completed {} cents in deposits", self.total_volume_cents),
+        );
+
+        add(
+            "failed_payments",
+            (self.failed_payments.min(3) * 10) as i32,
+            format!("{} failed payments", self.failed_payments),
+        );
+
+        add(
+            "device_sharing",
+            (self.shared_device_accounts.min(3) * 15) as i32,
+            format!(
+                "Shares a device with {} other accounts",
+                self.shared_device_accounts
+            ),
+        );
+
+        let score = BASE_SCORE + factors.iter().map(|factor| factor.points).sum::<i32>();
+        (score.clamp(0, 100), factors)
+    }
+}
+
+/// Last score computed for a user.
+#[derive(Clone, Debug, Serialize)]
+pub struct RiskScore {
+    pub user_id: String,
+    pub score: i32,
+    pub band: RiskBand,
+    pub factors: Vec<RiskFactor>,
+    pub computed_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signals() -> RiskSignals {
+        RiskSignals {
+            account_age_days: 60,
+            verified: false,
+            total_volume_cents: 500 * 100,
+            failed_payments: 0,
+            shared_device_accounts: 0,
+        }
+    }
+
+    #[test]
+    fn unremarkable_account_keeps_the_base_score() {
+        let (score, factors) = signals().score();
+        assert_eq!(score, BASE_SCORE);
+        assert!(factors.is_empty());
+        assert_eq!(RiskBand::of(score), RiskBand::Standard);
+    }
+
+    #[test]
+    fn new_accounts_get_the_standard_limits() {
+        let (score, _) = RiskSignals {
+            account_age_days: 0,
+            total_volume_cents: 0,
+            ..signals()
+        }
+        .score();
+        assert_eq!(RiskBand::of(score), RiskBand::Standard);
+    }
+
+    #[test]
+    fn established_verified_accounts_are_trusted() {
+        let (score, factors) = RiskSignals {
+            account_age_days: 365,
+            verified: true,
+            total_volume_cents: 20_000 * 100,
+            ..signals()
+        }
+        .score();
+        assert_eq!(score, 0);
+        assert_eq!(RiskBand::of(score), RiskBand::Trusted);
+        assert_eq!(
+            factors
+                .iter()
+                .map(|factor| factor.signal.as_str())
+                .collect::<Vec<_>>(),
+            ["account_age", "verified", "volume"]
+        );
+    }
+
+    #[test]
+    fn shared_devices_and_failed_payments_are_risky() {
+        let (score, _) = RiskSignals {
+            account_age_days: 2,
+            total_volume_cents: 0,
+            shared_device_accounts: 1,
+            ..signals()
+        }
+        .score();
+        assert_eq!(RiskBand::of(score), RiskBand::Risky);
+
+        let (score, factors) = RiskSignals {
+            failed_payments: 10,
+            shared_device_accounts: 10,
+            ..signals()
+        }
+        .score();
+        assert_eq!(score, 100);
+        // Each signal is capped
+        assert_eq!(
+            factors
+                .iter()
+                .map(|factor| factor.points)
+                .collect::<Vec<_>>(),
+            [30, 45]
+        );
+    }
+
+    #[test]
+    fn bands_scale_the_limits() {
+        assert_eq!(RiskBand::Trusted.scale(5000 * 100), 10_000 * 100);
+        assert_eq!(RiskBand::Standard.scale(5000 * 100), 5000 * 100);
+        assert_eq!(RiskBand::Risky.scale(5000 * 100), 2500 * 100);
+        assert_eq!(RiskBand::parse("risky"), Some(RiskBand::Risky));
+    }
+}
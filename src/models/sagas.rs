@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+/// Steps of a payout, in the order they run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayoutStep {
+    /// Wallet was short on the asset and a swap was started to refill it.
+    SwapRequested,
+    PriceLocked,
+    FeeComputed,
+    PsetBuilt,
+    /// The PSET was checked against the transaction.
+    PsetVerified,
+    Signed,
+    Broadcast,
+}
+
+impl PayoutStep {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PayoutStep::SwapRequested => "swap_requested",
+            PayoutStep::PriceLocked => "price_locked",
+            PayoutStep::FeeComputed => "fee_computed",
+            PayoutStep::PsetBuilt => "pset_built",
+            PayoutStep::PsetVerified => "pset_verified",
+            PayoutStep::Signed => "signed",
+            PayoutStep::Broadcast => "broadcast",
+        }
+    }
+
+    pub fn parse(step: &str) -> Option<Self> {
+        match step {
+            "swap_requested" => Some(PayoutStep::SwapRequested),
+            "price_locked" => Some(PayoutStep::PriceLocked),
+            "fee_computed" => Some(PayoutStep::FeeComputed),
+            "pset_built" => Some(PayoutStep::PsetBuilt),
+            "pset_verified" => Some(PayoutStep::PsetVerified),
+            "signed" => Some(PayoutStep::Signed),
+            "broadcast" => Some(PayoutStep::Broadcast),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepStatus {
+    Completed,
+    Failed,
+    /// What the failed step left behind was undone.
+    Compensated,
+}
+
+impl StepStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepStatus::Completed => "completed",
+            StepStatus::Failed => "failed",
+            StepStatus::Compensated => "compensated",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct SagaStep {
+    pub id: i64,
+    pub transaction_id: String,
+    pub step: String,
+    /// `completed`, `failed` or `compensated`.
+    pub status: String,
+    /// JSON encoded outcome of the step, or the error it failed with.
+    pub details: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
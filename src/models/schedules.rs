@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::validation::{self, FieldError, Validate};
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct Schedule {
+    pub id: String,
+    pub user_id: String,
+    pub address: String,
+    pub asset: String,
+    pub network: String,
+    pub amount_in_cents: i32,
+    /// `weekly` or `monthly`.
+    pub frequency: String,
+    /// `active`, `paused` or `cancelled`.
+    pub status: String,
+    pub next_run_at: chrono::DateTime<chrono::Utc>,
+    pub last_transaction_id: Option<String>,
+    pub last_qr_copy_paste: Option<String>,
+    pub last_qr_image_url: Option<String>,
+    pub last_error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Schedule {
+    /// Next run after `from`, or None for an unknown frequency.
+    pub fn next_run_after(
+        &self,
+        from: chrono::DateTime<chrono::Utc>,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        match self.frequency.as_str() {
+            "weekly" => Some(from + chrono::Duration::weeks(1)),
+            "monthly" => from.checked_add_months(chrono::Months::new(1)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NewSchedule {
+    pub user_id: String,
+    pub address: String,
+    pub asset: String,
+    pub network: String,
+    pub amount_in_cents: i32,
+    pub frequency: String,
+    /// First run; defaults to now.
+    pub start_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Validate for NewSchedule {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([
+            validation::required("user_id", &self.user_id),
+            validation::address("address", &self.address),
+            validation::asset_or_ticker("asset", &self.asset),
+            validation::network("network", &self.network),
+            validation::amount("amount_in_cents", self.amount_in_cents),
+            validation::required("frequency", &self.frequency),
+        ])
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScheduleAction {
+    pub user_id: String,
+}
+
+impl Validate for ScheduleAction {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([validation::required("user_id", &self.user_id)])
+    }
+}
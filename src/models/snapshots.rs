@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::sideswap::ActiveQuote;
+use super::transactions::{PendingEntry, Transaction};
+use crate::utils::validation::{self, FieldError, Validate};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UtxoSnapshot {
+    pub txid: String,
+    pub vout: u32,
+    pub asset: String,
+    pub value: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DealerSnapshot {
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub pending_transactions: Vec<PendingEntry>,
+    pub unreconciled_transactions: Vec<Transaction>,
+    pub active_quotes: Vec<ActiveQuote>,
+    pub utxos: Vec<UtxoSnapshot>,
+    /// Amount in cents already paid by users but not yet delivered, per asset.
+    pub reserved_in_cents: BTreeMap<String, i64>,
+}
+
+/// What is actually encrypted and written to storage.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SnapshotBundle {
+    pub version: u32,
+    pub checksum: String,
+    pub snapshot: DealerSnapshot,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SnapshotSummary {
+    pub name: String,
+    pub checksum: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub pending_transactions: usize,
+    pub unreconciled_transactions: usize,
+    pub active_quotes: usize,
+    pub utxos: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RestoreSnapshot {
+    pub name: String,
+}
+
+impl Validate for RestoreSnapshot {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([validation::required("name", &self.name)])
+    }
+}
@@ -0,0 +1,20 @@
+use serde::Serialize;
+
+use super::transactions::Transaction;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TimelineEvent {
+    pub at: chrono::DateTime<chrono::Utc>,
+    /// Where the event comes from: `transaction`, `pix`, `swap`, `payout`,
+    /// `saga`, `review`, `compliance` or `audit`.
+    pub source: String,
+    pub event: String,
+    pub details: serde_json::Value,
+}
+
+/// Everything known about a transaction, oldest event first.
+#[derive(Clone, Debug, Serialize)]
+pub struct TransactionTimeline {
+    pub transaction: Transaction,
+    pub events: Vec<TimelineEvent>,
+}
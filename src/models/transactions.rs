@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+use super::pix::PixTransaction;
+use crate::utils::validation::{self, FieldError, Validate};
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct Transaction {
     pub id: String,
     pub user_id: String,
@@ -14,6 +19,41 @@ pub struct Transaction {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Transaction moved out of the live tables, with its PIX charges.
+#[derive(Clone, Debug, Serialize)]
+pub struct ArchivedTransaction {
+    #[serde(flatten)]
+    pub transaction: Transaction,
+    pub pix_transactions: Vec<PixTransaction>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PendingEntry {
+    pub transaction_id: String,
+    pub user_id: String,
+    pub asset: String,
+    pub amount_in_cents: i32,
+    pub attempts: u32,
+    pub last_attempt: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub swap_id: Option<i64>,
+    /// Why the last attempt did not pay out.
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+/// Deposit about to be inserted, with the fee address its PIX charge is
+/// paid to.
+#[derive(Clone, Debug)]
+pub struct NewTransactionRow {
+    pub user_id: String,
+    pub address: String,
+    pub fee_address: String,
+    pub amount_in_cents: i32,
+    pub asset: String,
+    pub network: String,
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct NewTransaction {
     pub user_id: String,
@@ -21,8 +61,91 @@ pub struct NewTransaction {
     pub amount_in_cents: i32,
     pub asset: String,
     pub network: String,
+    /// Quote from POST /quote locking the price of the payout.
+    pub quote_id: Option<String>,
+    /// When to pay out, if not as soon as the deposit is paid.
+    #[serde(default)]
+    pub scheduled_delivery_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Validate for NewTransaction {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let deposit = validation::deposit(
+            &self.address,
+            self.amount_in_cents,
+            &self.asset,
+            &self.network,
+        );
+
+        validation::collect(deposit.into_iter().chain([
+            validation::required("user_id", &self.user_id),
+            validation::optional("quote_id", self.quote_id.as_deref()),
+        ]))
+    }
+}
+
+/// Payout the user asked to receive later. `scheduled` until it is
+/// `released` for payout or `cancelled` by the user.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct ScheduledDelivery {
+    pub transaction_id: String,
+    pub deliver_at: chrono::DateTime<chrono::Utc>,
+    pub status: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub settled_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Body of POST /transaction/{id}/cancel.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CancelTransaction {
+    pub user_id: String,
+}
+
+impl Validate for CancelTransaction {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([validation::required("user_id", &self.user_id)])
+    }
+}
+
+/// What to do with a payout that fees pushed below the dust threshold.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DustPolicy {
+    /// Hold the transaction for manual review.
+    #[default]
+    Hold,
+    /// Pay the full amount without charging the fee.
+    WaiveFee,
+}
+
+/// Decimals of an asset missing from the configured precisions.
+pub const DEFAULT_PRECISION: u8 = 8;
+/// Most decimals the Liquid asset registry allows.
+pub const MAX_PRECISION: u8 = 8;
+
+/// Asset ids of the network the dealer runs on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetIds {
+    pub depix: String,
+    pub usdt: String,
+    pub lbtc: String,
+    /// Registry precision of assets that do not use 8 decimals, by asset id.
+    pub precisions: HashMap<String, u8>,
+}
+
+impl AssetIds {
+    pub fn mainnet() -> Self {
+        Self {
+            depix: "02f22f8d9c76ab41661a2729e4752e2c5d1a263012141b86ea98af5472df5189".to_string(),
+            usdt: "ce091c998b83c78bb71a632313ba3760f1763d9cfcffae02258ffa9865a37bd2".to_string(),
+            lbtc: "6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d".to_string(),
+            precisions: HashMap::new(),
+        }
+    }
 }
 
+static ASSET_IDS: OnceLock<AssetIds> = OnceLock::new();
+
 #[derive(Debug)]
 pub enum Assets {
     DEPIX,
@@ -31,26 +154,91 @@ pub enum Assets {
 }
 
 impl Assets {
+    /// Selects the asset ids of the configured network. Called once at
+    /// startup, before any service runs; mainnet ids are used otherwise.
+    pub fn init(asset_ids: AssetIds) {
+        let _ = ASSET_IDS.set(asset_ids);
+    }
+
+    fn ids() -> &'static AssetIds {
+        ASSET_IDS.get_or_init(AssetIds::mainnet)
+    }
+
     pub fn hex(&self) -> String {
+        let ids = Self::ids();
         match self {
-            Assets::DEPIX => {
-                "02f22f8d9c76ab41661a2729e4752e2c5d1a263012141b86ea98af5472df5189".to_string()
-            }
-            Assets::USDT => {
-                "ce091c998b83c78bb71a632313ba3760f1763d9cfcffae02258ffa9865a37bd2".to_string()
-            }
-            Assets::LBTC => {
-                "6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d".to_string()
-            }
+            Assets::DEPIX => ids.depix.clone(),
+            Assets::USDT => ids.usdt.clone(),
+            Assets::LBTC => ids.lbtc.clone(),
         }
     }
 
+    /// Base units in one whole unit of the asset.
+    pub fn unit(&self) -> u64 {
+        Self::unit_of(&self.hex())
+    }
+
+    /// Decimals of `asset_id`, 8 unless configured otherwise.
+    pub fn precision_of(asset_id: &str) -> u8 {
+        Self::ids()
+            .precisions
+            .get(asset_id)
+            .copied()
+            .unwrap_or(DEFAULT_PRECISION)
+    }
+
+    /// Base units in one whole unit of `asset_id`.
+    pub fn unit_of(asset_id: &str) -> u64 {
+        10u64.pow(u32::from(Self::precision_of(asset_id)))
+    }
+
+    pub fn ticker(&self) -> &'static str {
+        match self {
+            Assets::DEPIX => "DePix",
+            Assets::USDT => "USDt",
+            Assets::LBTC => "L-BTC",
+        }
+    }
+
+    /// Asset of a ticker such as `USDt` or `L-BTC`, in any case and with or
+    /// without the dash.
+    pub fn from_ticker(ticker: &str) -> Result<Self, String> {
+        let ticker = ticker.replace('-', "");
+        [Assets::DEPIX, Assets::USDT, Assets::LBTC]
+            .into_iter()
+            .find(|asset| {
+                asset
+                    .ticker()
+                    .replace('-', "")
+                    .eq_ignore_ascii_case(&ticker)
+            })
+            .ok_or_else(|| "Invalid asset ticker".to_string())
+    }
+
     pub fn from_hex(hex: &str) -> Result<Self, String> {
-        match hex {
-            "02f22f8d9c76ab41661a2729e4752e2c5d1a263012141b86ea98af5472df5189" => Ok(Assets::DEPIX),
-            "ce091c998b83c78bb71a632313ba3760f1763d9cfcffae02258ffa9865a37bd2" => Ok(Assets::USDT),
-            "6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d" => Ok(Assets::LBTC),
-            _ => Err("Invalid asset hex".to_string()),
+        let ids = Self::ids();
+        if hex == ids.depix {
+            Ok(Assets::DEPIX)
+        } else if hex == ids.usdt {
+            Ok(Assets::USDT)
+        } else if hex == ids.lbtc {
+            Ok(Assets::LBTC)
+        } else {
+            Err("Invalid asset hex".to_string())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tickers_resolve_in_any_case() {
+        assert!(matches!(Assets::from_ticker("USDt"), Ok(Assets::USDT)));
+        assert!(matches!(Assets::from_ticker("lbtc"), Ok(Assets::LBTC)));
+        assert!(matches!(Assets::from_ticker("L-BTC"), Ok(Assets::LBTC)));
+        assert!(matches!(Assets::from_ticker("DEPIX"), Ok(Assets::DEPIX)));
+        assert!(Assets::from_ticker("BTC").is_err());
+    }
+}
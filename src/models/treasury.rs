@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// A swap executed on Sideswap, with the oracle prices it is compared to.
+#[derive(Clone, Debug)]
+pub struct SwapExecution {
+    pub quote_sub_id: i64,
+    pub quote_id: i64,
+    pub txid: String,
+    pub sell_asset: String,
+    pub receive_asset: String,
+    pub sold_amount: i64,
+    pub received_amount: i64,
+    pub fee_asset: Option<String>,
+    pub server_fee: i64,
+    pub fixed_fee: i64,
+    /// BRL cents per whole unit, when the price service had one.
+    pub sell_price_in_cents: Option<i64>,
+    pub receive_price_in_cents: Option<i64>,
+    pub fee_price_in_cents: Option<i64>,
+}
+
+/// Quote Sideswap made for a swap. Swaps quoted both ways keep the quote
+/// that was not taken too.
+#[derive(Clone, Debug)]
+pub struct SwapQuote {
+    pub swap_id: i64,
+    pub quote_sub_id: i64,
+    pub quote_id: i64,
+    pub sell_asset: String,
+    pub receive_asset: String,
+    /// `sell` or `receive`: which asset the requested amount was of.
+    pub quoted_in: &'static str,
+    pub requested_amount: i64,
+    pub sold_amount: i64,
+    pub received_amount: i64,
+    pub server_fee: i64,
+    pub fixed_fee: i64,
+    /// Received base units per sold base unit.
+    pub price: f64,
+    pub chosen: bool,
+}
+
+/// Swaps of one pair on one day (UTC).
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct DailySwapSummary {
+    pub day: chrono::NaiveDate,
+    pub sell_asset: String,
+    pub receive_asset: String,
+    pub fee_asset: Option<String>,
+    pub swaps: i64,
+    pub sold_amount: i64,
+    pub received_amount: i64,
+    /// In base units of the fee asset.
+    pub server_fees: i64,
+    pub fixed_fees: i64,
+    pub fees_brl_cents: Option<i64>,
+    /// Units received per unit sold.
+    pub realized_rate: Option<f64>,
+    /// Units the oracle prices would have given per unit sold.
+    pub oracle_rate: Option<f64>,
+    /// Value received minus value sold at oracle prices, fees excluded.
+    pub pnl_cents: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TreasuryReport {
+    pub days: i64,
+    pub total_fees_brl_cents: i64,
+    pub total_pnl_cents: i64,
+    pub daily: Vec<DailySwapSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TreasuryQuery {
+    pub days: Option<i64>,
+}
@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+use super::{audit, pix, receipts, referrals, transactions};
+use crate::utils::signing::DocumentSignature;
+use crate::utils::validation::{self, FieldError, Validate};
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct User {
     pub id: String,
     pub verified: bool,
@@ -12,6 +16,24 @@ pub struct User {
 #[derive(Clone, Debug, Deserialize)]
 pub struct NewUser {
     pub referral_code: Option<String>,
+    /// Per-installation id generated by the app, used to bind accounts to a device.
+    pub installation_id: Option<String>,
+}
+
+impl Validate for NewUser {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([
+            validation::optional("referral_code", self.referral_code.as_deref()),
+            validation::optional("installation_id", self.installation_id.as_deref()),
+        ])
+    }
+}
+
+/// Returned once when a key is issued; only its hash is stored.
+#[derive(Clone, Debug, Serialize)]
+pub struct UserCredentials {
+    pub user_id: String,
+    pub api_key: String,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -21,3 +43,86 @@ pub struct UserDetails {
     pub allowed_spending: i64,
     pub is_verified: bool, // reserved field
 }
+
+/// Spending of every account sharing a device with a user. Limits are applied
+/// to the accounts combined so new accounts can't reset them.
+#[derive(Clone, Debug, Serialize)]
+pub struct DeviceLimits {
+    pub linked_accounts: Vec<String>,
+    pub daily_spending: i64,
+    pub allowed_spending: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UserDataExport {
+    pub user: User,
+    pub referral: Option<referrals::Referral>,
+    pub device_fingerprints: Vec<String>,
+    pub transactions: Vec<transactions::Transaction>,
+    pub pix_transactions: Vec<pix::PixTransaction>,
+    pub notification_preferences: Option<receipts::NotificationPreferences>,
+    pub receipts: Vec<receipts::Receipt>,
+    pub audit_trail: Vec<audit::AuditEvent>,
+    pub exported_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DataSubjectRequest {
+    /// Operator handling the request, recorded in the audit trail.
+    pub requested_by: String,
+    /// Ticket or protocol number of the data subject request.
+    pub reference: Option<String>,
+}
+
+impl Validate for DataSubjectRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        validation::collect([
+            validation::required("requested_by", &self.requested_by),
+            validation::optional("reference", self.reference.as_deref()),
+        ])
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct StatementQuery {
+    /// Month of the statement, as `YYYY-MM`.
+    pub month: String,
+}
+
+/// A user's deposits in a month with what was paid out for each, in the
+/// order they were made.
+#[derive(Clone, Debug, Serialize)]
+pub struct Statement {
+    pub user_id: String,
+    pub month: String,
+    pub entries: Vec<StatementEntry>,
+    /// Sum of the deposits that were paid, in BRL cents.
+    pub total_paid_in_cents: i64,
+    /// The same total as shown to the user, e.g. `R$ 1.234,56`.
+    pub total_paid: String,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct StatementEntry {
+    pub transaction_id: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub status: String,
+    pub amount_in_cents: i32,
+    pub asset: String,
+    /// Dealer fee, in base units of `asset`.
+    pub fee_collected: Option<i32>,
+    /// Price the payout was computed with, in cents of `quote_currency`.
+    pub price_in_cents: Option<i64>,
+    pub quote_currency: Option<String>,
+    pub txid: Option<String>,
+    /// L-BTC paid to the network, in sats.
+    pub network_fee: Option<i64>,
+}
+
+/// Statement serialized as it is handed out, with the signature of exactly
+/// those bytes.
+pub struct SignedStatement {
+    pub body: String,
+    pub signature: DocumentSignature,
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+
+/// What an external watch-only wallet needs to follow the hot wallet. The CT
+/// descriptor carries the public keys and the SLIP-77 blinding key: enough to
+/// see every address and amount, not to spend.
+#[derive(Clone, Debug, Serialize)]
+pub struct WatchOnlyDescriptor {
+    pub descriptor: String,
+    pub network: String,
+    /// First external address not used yet.
+    pub next_address_index: u32,
+    /// First change address not used yet.
+    pub next_change_index: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DerivedAddress {
+    pub index: u32,
+    pub address: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DerivedAddressQuery {
+    /// List change addresses instead of external ones.
+    #[serde(default)]
+    pub change: bool,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DerivedAddressPage {
+    pub change: bool,
+    pub offset: u32,
+    pub limit: u32,
+    pub addresses: Vec<DerivedAddress>,
+}
+
+/// Address handed out by the hot wallet, as recorded in Postgres.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct WalletAddress {
+    pub address: String,
+    /// external for receive addresses, internal for change.
+    pub chain: String,
+    pub derivation_index: i32,
+    pub transaction_id: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Recorded address of a transaction checked against the one derived again
+/// from the descriptor at the same index.
+#[derive(Clone, Debug, Serialize)]
+pub struct AddressVerification {
+    pub transaction_id: String,
+    pub address: String,
+    pub chain: String,
+    pub derivation_index: i32,
+    pub derived_address: String,
+    pub matches: bool,
+}
+
+/// Fee rate a transaction is built with, from the `[network_fees]` section.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeePriority {
+    Normal,
+    High,
+}
+
+impl FeePriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeePriority::Normal => "normal",
+            FeePriority::High => "high",
+        }
+    }
+}
+
+/// Broadcast transaction and the L-BTC it paid to the network.
+#[derive(Clone, Debug, Serialize)]
+pub struct BroadcastTransaction {
+    pub txid: String,
+    pub network_fee: u64,
+    pub context: BroadcastContext,
+}
+
+/// Network, server and chain tip a transaction was broadcast with, to tell
+/// a payout that did not propagate from one sent to the wrong chain.
+#[derive(Clone, Debug, Serialize)]
+pub struct BroadcastContext {
+    /// `liquid` or `liquid-testnet`.
+    pub network: String,
+    pub electrum_server: String,
+    /// Tip the wallet had synced to.
+    pub tip_height: u32,
+    /// Block time of the tip, when the server reported it.
+    pub tip_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub broadcast_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A recorded broadcast, as stored with its context.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct BroadcastRecord {
+    pub txid: String,
+    pub network: String,
+    pub electrum_server: String,
+    pub tip_height: i32,
+    pub tip_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub network_fee: i64,
+    pub broadcast_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// An asset the hot wallet received in a transaction.
+#[derive(Clone, Debug, Serialize)]
+pub struct IncomingTransfer {
+    pub txid: String,
+    /// Net amount received, in base units.
+    pub amount: u64,
+    /// Block time, none while unconfirmed.
+    pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// An output of an asset the hot wallet received on a receive address.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReceivedOutput {
+    pub txid: String,
+    pub vout: u32,
+    /// Index of the receive address on the external chain.
+    pub derivation_index: u32,
+    /// In base units.
+    pub amount: u64,
+    /// Block time, none while unconfirmed.
+    pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// DEPIX received on a receive address and the transaction it was
+/// attributed to.
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+pub struct FeeAddressReceipt {
+    pub txid: String,
+    pub vout: i32,
+    /// None when the address was never recorded as handed out.
+    pub address: Option<String>,
+    pub derivation_index: i32,
+    pub transaction_id: Option<String>,
+    pub amount: i64,
+    /// `attributed`, `late`, `repeat` or `unassigned`.
+    pub status: String,
+    pub received_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub recorded_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FeeAddressReceiptQuery {
+    /// Unexpected receipts, all but `attributed`, by default.
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// What a PSET does to the hot wallet, decoded with its blinding keys before
+/// signing.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PsetBalance {
+    /// Net change per asset id, in base units: negative for what the wallet
+    /// pays.
+    pub balances: std::collections::BTreeMap<String, i64>,
+    /// Network fee of the transaction, in L-BTC.
+    pub fee: u64,
+    /// Outputs paying anyone but the wallet.
+    pub recipients: Vec<PsetRecipient>,
+}
+
+/// An output of a PSET paying outside the wallet. Asset and value are
+/// missing when the output is blinded without them.
+#[derive(Clone, Debug, Serialize)]
+pub struct PsetRecipient {
+    pub vout: u32,
+    pub script_pubkey: String,
+    pub asset_id: Option<String>,
+    pub value: Option<u64>,
+}
+
+/// Wallet state adopted from another dealer's store, as it opened here.
+#[derive(Clone, Debug)]
+pub struct ImportedWallet {
+    pub descriptor: String,
+    /// Persisted updates copied over.
+    pub updates: usize,
+    pub tip_height: u32,
+    pub txids: Vec<String>,
+    /// Balance per asset id, in base units.
+    pub balances: std::collections::BTreeMap<String, u64>,
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
+pub struct WebhookEvent {
+    pub id: String,
+    /// Endpoint the webhook was received on, e.g. `eulen_status`.
+    pub source: String,
+    /// Request body exactly as received.
+    pub payload: String,
+    /// JSON encoded request headers, without credentials.
+    pub headers: String,
+    pub bank_tx_ids: Vec<String>,
+    pub qr_ids: Vec<String>,
+    /// Processing result, empty while the webhook is being processed.
+    pub outcome: Option<String>,
+    pub received_at: chrono::DateTime<chrono::Utc>,
+    pub processed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// A webhook request before it is parsed.
+#[derive(Clone, Debug)]
+pub struct RawWebhook {
+    pub payload: String,
+    pub headers: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookEventQuery {
+    pub bank_tx_id: Option<String>,
+    pub transaction_id: Option<String>,
+}
@@ -0,0 +1,111 @@
+//! Replays captured Eulen webhooks and Sideswap notifications against a
+//! dealer running in dry-run mode, to reproduce incidents and test changes
+//! to the processing logic with real traffic.
+
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::models::webhooks::WebhookEvent;
+
+/// A captured request, as stored in `webhook_events` or listed in a replay
+/// file.
+#[derive(Debug, Deserialize)]
+pub struct CapturedEvent {
+    pub source: String,
+    pub payload: String,
+}
+
+impl From<WebhookEvent> for CapturedEvent {
+    fn from(event: WebhookEvent) -> Self {
+        Self {
+            source: event.source,
+            payload: event.payload,
+        }
+    }
+}
+
+pub fn read_file(path: &str) -> Result<Vec<CapturedEvent>, anyhow::Error> {
+    let data = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+/// What an endpoint is authenticated with.
+enum Credential {
+    None,
+    AdminKey,
+    /// Eulen's shared secret, left out of the captured headers.
+    WebhookSecret,
+}
+
+/// Endpoint a captured event is posted to, and the credential it needs.
+fn endpoint(source: &str) -> Option<(&'static str, Credential)> {
+    match source {
+        "eulen_status" => Some(("/webhook/eulen_status", Credential::None)),
+        "eulen_status_batch" => Some(("/webhook/eulen_status/batch", Credential::WebhookSecret)),
+        "sideswap" => Some(("/admin/replay/sideswap", Credential::AdminKey)),
+        _ => None,
+    }
+}
+
+pub async fn replay(
+    target: &str,
+    admin_api_key: &str,
+    webhook_secret: &str,
+    events: Vec<CapturedEvent>,
+    delay: Duration,
+) -> Result<(), anyhow::Error> {
+    let client = reqwest::Client::new();
+    let target = target.trim_end_matches('/');
+
+    // Never replay against a dealer that would broadcast
+    let mode: serde_json::Value = client
+        .get(format!("{}/admin/replay", target))
+        .bearer_auth(admin_api_key)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if mode["dry_run"].as_bool() != Some(true) {
+        return Err(anyhow::anyhow!(
+            "{} is not running in dry-run mode, refusing to replay",
+            target
+        ));
+    }
+
+    println!("[*] Replaying {} events against {}", events.len(), target);
+
+    for (i, event) in events.into_iter().enumerate() {
+        let Some((path, credential)) = endpoint(&event.source) else {
+            println!("[{}] {}: skipped, unknown source", i, event.source);
+            continue;
+        };
+
+        let mut request = client
+            .post(format!("{}{}", target, path))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(event.payload);
+        match credential {
+            Credential::None => {}
+            Credential::AdminKey => request = request.bearer_auth(admin_api_key),
+            Credential::WebhookSecret => {
+                request = request.header("x-webhook-secret", webhook_secret)
+            }
+        }
+
+        match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                println!("[{}] {}: {} {}", i, event.source, status, body);
+            }
+            Err(e) => println!("[{}] {}: request failed: {}", i, event.source, e),
+        }
+
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    Ok(())
+}
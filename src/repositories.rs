@@ -1,7 +1,39 @@
+pub mod addresses;
+pub mod analytics;
+pub mod archive;
+pub mod audit;
+pub mod broadcasts;
+pub mod campaigns;
+pub mod compliance;
+pub mod deliveries;
+pub mod disputes;
+pub mod events;
+pub mod handovers;
+pub mod hedging;
+pub mod invoicing;
 pub mod liquid;
+pub mod merchants;
+pub mod notifications;
+pub mod operator;
+pub mod otc;
+pub mod payouts;
 pub mod pix;
 pub mod price;
-//pub mod sideswap;
-//pub mod swap;
+pub mod psets;
+pub mod quotes;
+pub mod receipts;
+pub mod reconciliation;
+pub mod referrals;
+pub mod refunds;
+pub mod reports;
+pub mod reviews;
+pub mod risk;
+pub mod sagas;
+pub mod schedules;
+pub mod snapshots;
+pub mod spending;
+pub mod timeline;
 pub mod transactions;
+pub mod treasury;
 pub mod users;
+pub mod webhooks;
@@ -0,0 +1,152 @@
+use crate::models::wallet::{FeeAddressReceipt, ReceivedOutput, WalletAddress};
+
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct AddressRepository {
+    conn: PgPool,
+}
+
+impl AddressRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Highest index handed out on the chain, if any.
+    pub async fn get_max_index(&self, chain: &str) -> Result<Option<u32>, anyhow::Error> {
+        let index: Option<i32> = sqlx::query_scalar(
+            "SELECT MAX(derivation_index) FROM wallet_addresses WHERE chain = $1",
+        )
+        .bind(chain)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(index.map(|index| index as u32))
+    }
+
+    pub async fn insert_address(
+        &self,
+        address: &str,
+        chain: &str,
+        index: u32,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "INSERT INTO wallet_addresses (address, chain, derivation_index) VALUES ($1, $2, $3)",
+        )
+        .bind(address)
+        .bind(chain)
+        .bind(index as i32)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_transaction_address(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<WalletAddress>, anyhow::Error> {
+        let address = sqlx::query_as::<_, WalletAddress>(
+            "SELECT * FROM wallet_addresses WHERE transaction_id = $1",
+        )
+        .bind(transaction_id)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(address)
+    }
+
+    pub async fn get_address_at(
+        &self,
+        chain: &str,
+        index: u32,
+    ) -> Result<Option<WalletAddress>, anyhow::Error> {
+        let address = sqlx::query_as::<_, WalletAddress>(
+            "SELECT * FROM wallet_addresses WHERE chain = $1 AND derivation_index = $2",
+        )
+        .bind(chain)
+        .bind(index as i32)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(address)
+    }
+
+    /// Outputs of `txids` already recorded, as `(txid, vout)`.
+    pub async fn get_recorded_receipts(
+        &self,
+        txids: &[String],
+    ) -> Result<Vec<(String, i32)>, anyhow::Error> {
+        let recorded =
+            sqlx::query_as("SELECT txid, vout FROM fee_address_receipts WHERE txid = ANY($1)")
+                .bind(txids)
+                .fetch_all(&self.conn)
+                .await?;
+
+        Ok(recorded)
+    }
+
+    /// Whether the address received DEPIX before.
+    pub async fn has_receipt(&self, address: &str) -> Result<bool, anyhow::Error> {
+        let received: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM fee_address_receipts WHERE address = $1)",
+        )
+        .bind(address)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(received)
+    }
+
+    /// Returns None when the output was already recorded.
+    pub async fn insert_receipt(
+        &self,
+        output: &ReceivedOutput,
+        address: Option<&WalletAddress>,
+        status: &str,
+    ) -> Result<Option<FeeAddressReceipt>, anyhow::Error> {
+        let receipt = sqlx::query_as::<_, FeeAddressReceipt>(
+            r#"
+                INSERT INTO fee_address_receipts
+                (txid, vout, address, derivation_index, transaction_id, amount, status, received_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (txid, vout) DO NOTHING
+                RETURNING *
+            "#,
+        )
+        .bind(&output.txid)
+        .bind(output.vout as i32)
+        .bind(address.map(|address| &address.address))
+        .bind(output.derivation_index as i32)
+        .bind(address.and_then(|address| address.transaction_id.as_ref()))
+        .bind(output.amount as i64)
+        .bind(status)
+        .bind(output.timestamp)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(receipt)
+    }
+
+    /// Receipts in `statuses`, newest first.
+    pub async fn get_receipts(
+        &self,
+        statuses: &[String],
+        limit: i64,
+    ) -> Result<Vec<FeeAddressReceipt>, anyhow::Error> {
+        let receipts = sqlx::query_as::<_, FeeAddressReceipt>(
+            r#"
+                SELECT * FROM fee_address_receipts
+                WHERE status = ANY($1)
+                ORDER BY recorded_at DESC
+                LIMIT $2
+            "#,
+        )
+        .bind(statuses)
+        .bind(limit)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(receipts)
+    }
+}
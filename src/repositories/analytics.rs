@@ -0,0 +1,69 @@
+use anyhow::bail;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::models::analytics::AnalyticsEvent;
+use crate::settings::{Analytics, AnalyticsSink};
+
+const EXPORT_TIMEOUT_SECS: u64 = 10;
+
+/// Exports events through the batch API of PostHog (`/batch/`) or Segment
+/// (`/v1/batch`). Self-hosted and compatible services work the same way.
+pub struct AnalyticsRepository {
+    sink: AnalyticsSink,
+    url: String,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl AnalyticsRepository {
+    pub fn new(sink: AnalyticsSink, settings: &Analytics) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(EXPORT_TIMEOUT_SECS))
+            .build()
+            .expect("Could not build analytics HTTP client.");
+
+        Self {
+            sink,
+            url: settings.url.clone(),
+            api_key: settings.api_key.clone(),
+            client,
+        }
+    }
+
+    pub async fn export(&self, events: &[AnalyticsEvent]) -> Result<(), anyhow::Error> {
+        let request = match self.sink {
+            AnalyticsSink::Posthog => self.client.post(&self.url).json(&json!({
+                "api_key": self.api_key,
+                "batch": events.iter().map(|event| json!({
+                    "uuid": event.id,
+                    "event": event.name,
+                    "distinct_id": event.distinct_id,
+                    "properties": event.properties,
+                    "timestamp": event.timestamp,
+                })).collect::<Vec<_>>(),
+            })),
+            AnalyticsSink::Segment => self
+                .client
+                .post(&self.url)
+                .basic_auth(&self.api_key, Some(""))
+                .json(&json!({
+                    "batch": events.iter().map(|event| json!({
+                        "type": "track",
+                        "messageId": event.id,
+                        "event": event.name,
+                        "anonymousId": event.distinct_id,
+                        "properties": event.properties,
+                        "timestamp": event.timestamp,
+                    })).collect::<Vec<_>>(),
+                })),
+        };
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            bail!("Analytics sink answered {}", response.status());
+        }
+
+        Ok(())
+    }
+}
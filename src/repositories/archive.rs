@@ -0,0 +1,170 @@
+use crate::models::operator::{Cursor, Page};
+use crate::models::pix::PixTransaction;
+use crate::models::transactions::{ArchivedTransaction, Transaction};
+
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+/// Statuses a transaction never leaves. Unpaid deposits stay `pending` once
+/// their charge expires; held and paid ones wait for an operator or a payout.
+/// A cancelled deposit paid late moves to `refund_requested`; a payout an
+/// operator dropped from the queue is `failed`.
+pub(crate) const ARCHIVED_STATUSES: [&str; 6] = [
+    "finished",
+    "blocked",
+    "refund_requested",
+    "pending",
+    "cancelled",
+    "failed",
+];
+
+#[derive(Clone)]
+pub struct ArchiveRepository {
+    conn: PgPool,
+}
+
+impl ArchiveRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Moves up to `batch_size` settled transactions created before
+    /// `created_before`, with their PIX charges, into the archive tables.
+    /// Returns how many transactions and charges were moved.
+    pub async fn archive_batch(
+        &self,
+        created_before: chrono::DateTime<chrono::Utc>,
+        batch_size: i64,
+    ) -> Result<(u64, u64), anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        // A pending deposit whose charge was paid is left for reconciliation
+        let ids: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT t.id FROM transactions t
+            WHERE t.created_at < $1
+              AND t.status = ANY($2)
+              AND NOT (t.status = 'pending' AND EXISTS (
+                  SELECT 1 FROM pix_transactions p
+                  WHERE p.transaction_id = t.id AND p.status = 'depix_sent'
+              ))
+            ORDER BY t.created_at
+            LIMIT $3
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(created_before)
+        .bind(&ARCHIVED_STATUSES[..])
+        .bind(batch_size)
+        .fetch_all(&mut *tx)
+        .await?;
+        if ids.is_empty() {
+            return Ok((0, 0));
+        }
+
+        let pix_transactions = sqlx::query(
+            r#"
+            WITH moved AS (
+                DELETE FROM pix_transactions WHERE transaction_id = ANY($1) RETURNING *
+            )
+            INSERT INTO pix_transactions_archive SELECT * FROM moved
+            "#,
+        )
+        .bind(&ids)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let transactions = sqlx::query(
+            r#"
+            WITH moved AS (
+                DELETE FROM transactions WHERE id = ANY($1) RETURNING *
+            )
+            INSERT INTO transactions_archive SELECT * FROM moved
+            "#,
+        )
+        .bind(&ids)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+
+        Ok((transactions, pix_transactions))
+    }
+
+    pub async fn record_run(
+        &self,
+        archived_before: chrono::DateTime<chrono::Utc>,
+        transactions: u64,
+        pix_transactions: u64,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "INSERT INTO archive_runs (archived_before, transactions, pix_transactions) VALUES ($1, $2, $3)",
+        )
+        .bind(archived_before)
+        .bind(transactions as i32)
+        .bind(pix_transactions as i32)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Page of archived transactions created in `[since, until)`, oldest
+    /// first, starting after `after`.
+    pub async fn get_archived(
+        &self,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        after: Option<&Cursor>,
+        limit: i64,
+    ) -> Result<Page<ArchivedTransaction>, anyhow::Error> {
+        let transactions = sqlx::query_as::<_, Transaction>(
+            r#"
+            SELECT * FROM transactions_archive
+            WHERE ($1::TIMESTAMPTZ IS NULL OR created_at >= $1)
+              AND ($2::TIMESTAMPTZ IS NULL OR created_at < $2)
+              AND ($3::TIMESTAMPTZ IS NULL OR (created_at, id) > ($3, $4))
+            ORDER BY created_at, id
+            LIMIT $5
+            "#,
+        )
+        .bind(since)
+        .bind(until)
+        .bind(after.map(|cursor| cursor.created_at))
+        .bind(after.map(|cursor| cursor.id.as_str()))
+        .bind(limit + 1)
+        .fetch_all(&self.conn)
+        .await?;
+
+        let ids: Vec<&str> = transactions.iter().map(|t| t.id.as_str()).collect();
+        let pix_transactions = sqlx::query_as::<_, PixTransaction>(
+            "SELECT * FROM pix_transactions_archive WHERE transaction_id = ANY($1) ORDER BY created_at",
+        )
+        .bind(&ids)
+        .fetch_all(&self.conn)
+        .await?;
+
+        let mut charges: HashMap<String, Vec<PixTransaction>> = HashMap::new();
+        for pix in pix_transactions {
+            charges
+                .entry(pix.transaction_id.clone())
+                .or_default()
+                .push(pix);
+        }
+
+        let archived = transactions
+            .into_iter()
+            .map(|transaction| ArchivedTransaction {
+                pix_transactions: charges.remove(&transaction.id).unwrap_or_default(),
+                transaction,
+            })
+            .collect();
+
+        Ok(Page::from_rows(archived, limit, |archived| Cursor {
+            created_at: archived.transaction.created_at,
+            id: archived.transaction.id.clone(),
+        }))
+    }
+}
@@ -0,0 +1,51 @@
+use crate::models::audit::AuditEvent;
+
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+/// Inserts an audit event through any executor, so it can be written in the
+/// same database transaction as the operation it records.
+pub async fn record_audit_event<'e, E>(
+    executor: E,
+    actor: &str,
+    action: &str,
+    subject_id: &str,
+    details: serde_json::Value,
+) -> Result<AuditEvent, anyhow::Error>
+where
+    E: PgExecutor<'e>,
+{
+    let event = sqlx::query_as::<_, AuditEvent>(
+        r#"
+            INSERT INTO audit_log (id, actor, action, subject_id, details)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+        "#,
+    )
+    .bind(Uuid::new_v4().hyphenated().to_string())
+    .bind(actor)
+    .bind(action)
+    .bind(subject_id)
+    .bind(details.to_string())
+    .fetch_one(executor)
+    .await?;
+
+    Ok(event)
+}
+
+pub async fn get_audit_events<'e, E>(
+    executor: E,
+    subject_id: &str,
+) -> Result<Vec<AuditEvent>, anyhow::Error>
+where
+    E: PgExecutor<'e>,
+{
+    let events = sqlx::query_as::<_, AuditEvent>(
+        "SELECT * FROM audit_log WHERE subject_id = $1 ORDER BY created_at",
+    )
+    .bind(subject_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(events)
+}
@@ -0,0 +1,54 @@
+use crate::models::wallet::{BroadcastRecord, BroadcastTransaction};
+
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct BroadcastRepository {
+    conn: PgPool,
+}
+
+impl BroadcastRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Keeps the first broadcast of a txid; a rebroadcast does not replace it.
+    pub async fn insert_broadcast(
+        &self,
+        broadcast: &BroadcastTransaction,
+    ) -> Result<(), anyhow::Error> {
+        let context = &broadcast.context;
+        sqlx::query(
+            r#"
+                INSERT INTO broadcasts
+                (txid, network, electrum_server, tip_height, tip_time, network_fee, broadcast_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (txid) DO NOTHING
+            "#,
+        )
+        .bind(&broadcast.txid)
+        .bind(&context.network)
+        .bind(&context.electrum_server)
+        .bind(context.tip_height as i32)
+        .bind(context.tip_time)
+        .bind(broadcast.network_fee as i64)
+        .bind(context.broadcast_at)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_broadcast(
+        &self,
+        txid: &str,
+    ) -> Result<Option<BroadcastRecord>, anyhow::Error> {
+        let broadcast =
+            sqlx::query_as::<_, BroadcastRecord>("SELECT * FROM broadcasts WHERE txid = $1")
+                .bind(txid)
+                .fetch_optional(&self.conn)
+                .await?;
+
+        Ok(broadcast)
+    }
+}
@@ -0,0 +1,156 @@
+use crate::models::campaigns::{Campaign, CampaignRedemption, NewCampaign};
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct CampaignRepository {
+    conn: PgPool,
+}
+
+impl CampaignRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    pub async fn insert_campaign(&self, campaign: &NewCampaign) -> Result<Campaign, anyhow::Error> {
+        let campaign = sqlx::query_as::<_, Campaign>(
+            r#"
+            INSERT INTO campaigns
+            (id, name, fee_discount_bps, first_purchase_only, max_amount_in_cents, asset, starts_at, ends_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().hyphenated().to_string())
+        .bind(&campaign.name)
+        .bind(campaign.fee_discount_bps)
+        .bind(campaign.first_purchase_only)
+        .bind(campaign.max_amount_in_cents)
+        .bind(&campaign.asset)
+        .bind(campaign.starts_at)
+        .bind(campaign.ends_at)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(campaign)
+    }
+
+    pub async fn get_campaigns(&self) -> Result<Vec<Campaign>, anyhow::Error> {
+        let campaigns =
+            sqlx::query_as::<_, Campaign>("SELECT * FROM campaigns ORDER BY starts_at DESC")
+                .fetch_all(&self.conn)
+                .await?;
+
+        Ok(campaigns)
+    }
+
+    pub async fn set_active(&self, id: &str, active: bool) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query("UPDATE campaigns SET active = $1 WHERE id = $2")
+            .bind(active)
+            .bind(id)
+            .execute(&self.conn)
+            .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Running campaigns the user has not redeemed yet, largest discount
+    /// first.
+    pub async fn get_available_campaigns(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<Campaign>, anyhow::Error> {
+        let campaigns = sqlx::query_as::<_, Campaign>(
+            r#"
+            SELECT * FROM campaigns c
+            WHERE c.active
+              AND c.starts_at <= CURRENT_TIMESTAMP
+              AND c.ends_at > CURRENT_TIMESTAMP
+              AND NOT EXISTS (
+                  SELECT 1 FROM campaign_redemptions r
+                  WHERE r.campaign_id = c.id AND r.user_id = $1
+              )
+            ORDER BY c.fee_discount_bps DESC, c.starts_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(campaigns)
+    }
+
+    /// Whether the user had a purchase paid out before this one.
+    pub async fn has_previous_purchase(
+        &self,
+        user_id: &str,
+        transaction_id: &str,
+    ) -> Result<bool, anyhow::Error> {
+        let exists: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM all_transactions
+                WHERE user_id = $1 AND id <> $2 AND status = 'finished'
+            )
+            "#,
+        )
+        .bind(user_id)
+        .bind(transaction_id)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(exists)
+    }
+
+    pub async fn get_redemption(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<CampaignRedemption>, anyhow::Error> {
+        let redemption = sqlx::query_as::<_, CampaignRedemption>(
+            "SELECT * FROM campaign_redemptions WHERE transaction_id = $1",
+        )
+        .bind(transaction_id)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(redemption)
+    }
+
+    /// Records the redemption. Returns false when the user already redeemed
+    /// the campaign.
+    pub async fn redeem(
+        &self,
+        campaign_id: &str,
+        user_id: &str,
+        transaction_id: &str,
+        fee_discount: u64,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO campaign_redemptions (campaign_id, user_id, transaction_id, fee_discount)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(campaign_id)
+        .bind(user_id)
+        .bind(transaction_id)
+        .bind(fee_discount as i64)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Drops the redemption of a payout that wasn't sent, so the campaign
+    /// is left for a later one. Returns false when there was none.
+    pub async fn release(&self, transaction_id: &str) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query("DELETE FROM campaign_redemptions WHERE transaction_id = $1")
+            .bind(transaction_id)
+            .execute(&self.conn)
+            .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
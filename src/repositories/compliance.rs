@@ -0,0 +1,81 @@
+use crate::models::compliance::{ProviderRisk, ScreeningResult};
+
+use anyhow::bail;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct ComplianceRepository {
+    conn: PgPool,
+    provider_url: String,
+    provider_api_key: String,
+    client: reqwest::Client,
+}
+
+impl ComplianceRepository {
+    pub fn new(conn: PgPool, provider_url: String, provider_api_key: String) -> Self {
+        Self {
+            conn,
+            provider_url,
+            provider_api_key,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn has_provider(&self) -> bool {
+        !self.provider_url.is_empty()
+    }
+
+    /// Reason the address was denylisted, if it is.
+    pub async fn get_denylist_entry(&self, address: &str) -> Result<Option<String>, anyhow::Error> {
+        let reason: Option<String> =
+            sqlx::query_scalar("SELECT reason FROM address_denylist WHERE address = $1")
+                .bind(address)
+                .fetch_optional(&self.conn)
+                .await?;
+
+        Ok(reason)
+    }
+
+    pub async fn get_provider_risk(&self, address: &str) -> Result<ProviderRisk, anyhow::Error> {
+        let response = self
+            .client
+            .get(format!(
+                "{}/api/risk/v2/entities/{}",
+                self.provider_url.trim_end_matches('/'),
+                address
+            ))
+            .header("Token", &self.provider_api_key)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            bail!("Screening provider answered {}", response.status());
+        }
+
+        Ok(response.json::<ProviderRisk>().await?)
+    }
+
+    pub async fn record_flag(
+        &self,
+        transaction_id: &str,
+        address: &str,
+        result: &ScreeningResult,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+                INSERT INTO compliance_flags (transaction_id, address, source, action, reason)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(address)
+        .bind(result.source.as_deref().unwrap_or("unknown"))
+        .bind(result.action.as_str())
+        .bind(result.reason.as_deref().unwrap_or(""))
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+}
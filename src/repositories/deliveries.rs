@@ -0,0 +1,153 @@
+use crate::models::transactions::{ScheduledDelivery, Transaction};
+use crate::repositories::audit::record_audit_event;
+
+use sqlx::PgPool;
+
+/// Most deliveries released in one run.
+const RELEASE_BATCH: i64 = 100;
+
+#[derive(Clone)]
+pub struct DeliveryRepository {
+    conn: PgPool,
+}
+
+impl DeliveryRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    pub async fn schedule(
+        &self,
+        transaction_id: &str,
+        deliver_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<ScheduledDelivery, anyhow::Error> {
+        let delivery = sqlx::query_as::<_, ScheduledDelivery>(
+            r#"
+                INSERT INTO scheduled_deliveries (transaction_id, deliver_at)
+                VALUES ($1, $2)
+                RETURNING *
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(deliver_at)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(delivery)
+    }
+
+    /// Delivery of a transaction that hasn't been released or cancelled yet.
+    pub async fn get_scheduled(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<ScheduledDelivery>, anyhow::Error> {
+        let delivery = sqlx::query_as::<_, ScheduledDelivery>(
+            "SELECT * FROM scheduled_deliveries WHERE transaction_id = $1 AND status = 'scheduled'",
+        )
+        .bind(transaction_id)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(delivery)
+    }
+
+    /// Transactions waiting in `scheduled` whose delivery time came, the
+    /// longest due first.
+    pub async fn get_due(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<String>, anyhow::Error> {
+        let due: Vec<String> = sqlx::query_scalar(
+            r#"
+                SELECT d.transaction_id FROM scheduled_deliveries d
+                JOIN transactions t ON t.id = d.transaction_id
+                WHERE d.status = 'scheduled' AND t.status = 'scheduled' AND d.deliver_at <= $1
+                ORDER BY d.deliver_at
+                LIMIT $2
+            "#,
+        )
+        .bind(now)
+        .bind(RELEASE_BATCH)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(due)
+    }
+
+    /// Releases the delivery, moving its transaction back to
+    /// `eulen_depix_sent` if it waited in `scheduled`. False when it was
+    /// released or cancelled already.
+    pub async fn release(&self, transaction_id: &str) -> Result<bool, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let released = sqlx::query(
+            "UPDATE scheduled_deliveries SET status = 'released', settled_at = CURRENT_TIMESTAMP WHERE transaction_id = $1 AND status = 'scheduled'",
+        )
+        .bind(transaction_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !released {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "UPDATE transactions SET status = 'eulen_depix_sent', updated_at = CURRENT_TIMESTAMP WHERE id = $1 AND status = 'scheduled'",
+        )
+        .bind(transaction_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Cancels the purchase of a user whose payout waits in `scheduled`,
+    /// leaving the transaction to be refunded. None when there's no such
+    /// transaction waiting.
+    pub async fn cancel(
+        &self,
+        transaction_id: &str,
+        user_id: &str,
+    ) -> Result<Option<Transaction>, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let transaction = sqlx::query_as::<_, Transaction>(
+            r#"
+                UPDATE transactions SET status = 'refund_requested', updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND user_id = $2 AND status = 'scheduled'
+                RETURNING *
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(transaction) = transaction else {
+            return Ok(None);
+        };
+
+        sqlx::query(
+            "UPDATE scheduled_deliveries SET status = 'cancelled', settled_at = CURRENT_TIMESTAMP WHERE transaction_id = $1 AND status = 'scheduled'",
+        )
+        .bind(transaction_id)
+        .execute(&mut *tx)
+        .await?;
+
+        record_audit_event(
+            &mut *tx,
+            user_id,
+            "scheduled_delivery_cancelled",
+            transaction_id,
+            serde_json::json!({ "amount_in_cents": transaction.amount_in_cents }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(transaction))
+    }
+}
@@ -0,0 +1,205 @@
+use crate::models::disputes::{DisputeDecision, DisputeExposure, NewDispute, PixDispute};
+use crate::repositories::audit::record_audit_event;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct DisputeRepository {
+    conn: PgPool,
+}
+
+impl DisputeRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Records a dispute, linked to the transaction of the contested charge,
+    /// found by its charge or its bank transaction, and to its user. A
+    /// dispute recorded already takes the new status unless it was closed.
+    /// Returns the dispute and the status it had, None when it is new.
+    pub async fn record_dispute(
+        &self,
+        dispute: &NewDispute,
+    ) -> Result<(PixDispute, Option<String>), anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let previous: Option<String> =
+            sqlx::query_scalar("SELECT status FROM pix_disputes WHERE external_id = $1 FOR UPDATE")
+                .bind(&dispute.external_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+        let recorded = sqlx::query_as::<_, PixDispute>(
+            r#"
+                WITH linked AS (
+                    SELECT id, user_id FROM all_transactions
+                    WHERE id = COALESCE(
+                        (SELECT transaction_id FROM all_pix_transactions WHERE eulen_id = $4 LIMIT 1),
+                        (SELECT transaction_id FROM depix_settlements WHERE bank_tx_id = $3 LIMIT 1)
+                    )
+                )
+                INSERT INTO pix_disputes
+                    (id, external_id, bank_tx_id, transaction_id, user_id, amount_in_cents,
+                     reason, status, reported_by, resolved_by, resolved_at)
+                SELECT $1, $2, $3, linked.id, linked.user_id, $5, $6, $7, $8,
+                       CASE WHEN $7 = 'open' THEN NULL ELSE $8 END,
+                       CASE WHEN $7 = 'open' THEN NULL ELSE CURRENT_TIMESTAMP END
+                FROM (SELECT 1) AS one LEFT JOIN linked ON TRUE
+                ON CONFLICT (external_id) DO UPDATE
+                SET amount_in_cents = EXCLUDED.amount_in_cents,
+                    status = EXCLUDED.status,
+                    resolved_by = EXCLUDED.resolved_by,
+                    resolved_at = EXCLUDED.resolved_at,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE pix_disputes.status = 'open'
+                RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().hyphenated().to_string())
+        .bind(&dispute.external_id)
+        .bind(&dispute.bank_tx_id)
+        .bind(dispute.qr_id.as_deref())
+        .bind(dispute.amount_in_cents)
+        .bind(&dispute.reason)
+        .bind(&dispute.status)
+        .bind(&dispute.reported_by)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        // A closed dispute is left as it was
+        let Some(recorded) = recorded else {
+            let closed = sqlx::query_as::<_, PixDispute>(
+                "SELECT * FROM pix_disputes WHERE external_id = $1",
+            )
+            .bind(&dispute.external_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            return Ok((closed, previous));
+        };
+
+        if previous.as_deref() != Some(recorded.status.as_str()) {
+            record_audit_event(
+                &mut *tx,
+                &dispute.reported_by,
+                &format!("pix_dispute_{}", recorded.status),
+                recorded.transaction_id.as_deref().unwrap_or(&recorded.id),
+                serde_json::json!({
+                    "dispute_id": recorded.id,
+                    "external_id": recorded.external_id,
+                    "amount_in_cents": recorded.amount_in_cents,
+                    "reason": recorded.reason,
+                }),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok((recorded, previous))
+    }
+
+    /// Disputes in `statuses`, newest first.
+    pub async fn get_disputes(
+        &self,
+        statuses: &[String],
+        limit: i64,
+    ) -> Result<Vec<PixDispute>, anyhow::Error> {
+        let disputes = sqlx::query_as::<_, PixDispute>(
+            r#"
+                SELECT * FROM pix_disputes
+                WHERE status = ANY($1)
+                ORDER BY created_at DESC
+                LIMIT $2
+            "#,
+        )
+        .bind(statuses)
+        .bind(limit)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(disputes)
+    }
+
+    /// Closes an open dispute. Returns None when there is no open dispute
+    /// with this id.
+    pub async fn resolve_dispute(
+        &self,
+        id: &str,
+        decision: DisputeDecision,
+        decided_by: &str,
+        note: Option<&str>,
+    ) -> Result<Option<PixDispute>, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let dispute = sqlx::query_as::<_, PixDispute>(
+            r#"
+                UPDATE pix_disputes
+                SET status = $2, resolved_by = $3, note = $4, resolved_at = CURRENT_TIMESTAMP,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND status = 'open'
+                RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(decision.as_str())
+        .bind(decided_by)
+        .bind(note)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(dispute) = dispute else {
+            return Ok(None);
+        };
+
+        record_audit_event(
+            &mut *tx,
+            decided_by,
+            &format!("pix_dispute_{}", decision.as_str()),
+            dispute.transaction_id.as_deref().unwrap_or(&dispute.id),
+            serde_json::json!({
+                "dispute_id": dispute.id,
+                "amount_in_cents": dispute.amount_in_cents,
+                "note": note,
+            }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(dispute))
+    }
+
+    pub async fn count_open(&self, user_id: &str) -> Result<i64, anyhow::Error> {
+        let open: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pix_disputes WHERE user_id = $1 AND status = 'open'",
+        )
+        .bind(user_id)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(open)
+    }
+
+    /// Disputes by status, with what they contest of paid out transactions.
+    pub async fn get_exposure(&self) -> Result<Vec<DisputeExposure>, anyhow::Error> {
+        let exposure = sqlx::query_as::<_, DisputeExposure>(
+            r#"
+                SELECT d.status, COUNT(*) AS disputes,
+                       COALESCE(SUM(d.amount_in_cents), 0)::BIGINT AS amount_in_cents,
+                       COALESCE(SUM(d.amount_in_cents) FILTER (WHERE t.status = 'finished'), 0)::BIGINT
+                           AS paid_out_in_cents,
+                       COUNT(*) FILTER (WHERE d.transaction_id IS NULL) AS unlinked
+                FROM pix_disputes d
+                LEFT JOIN all_transactions t ON t.id = d.transaction_id
+                GROUP BY d.status
+                ORDER BY d.status
+            "#,
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(exposure)
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::{anyhow, bail};
+use lapin::options::{BasicPublishOptions, ConfirmSelectOptions};
+use lapin::publisher_confirm::Confirmation;
+use lapin::tcp::OwnedTLSConfig;
+use lapin::uri::AMQPUri;
+use lapin::{BasicProperties, Channel, Connection, ConnectionProperties};
+
+use crate::models::events::DomainEvent;
+use crate::settings::{BrokerKind, Events};
+
+const PUBLISH_TIMEOUT_SECS: u64 = 10;
+
+/// Publishes events to NATS, or to a RabbitMQ exchange over AMQP with
+/// publisher confirms.
+pub struct EventRepository {
+    broker: BrokerKind,
+    url: String,
+    username: Option<String>,
+    password: Option<String>,
+    tls: bool,
+    tls_ca_path: Option<String>,
+    subject_prefix: String,
+    exchange: String,
+    vhost: String,
+    nats: Option<async_nats::Client>,
+    /// The channel lives as long as its connection is kept.
+    rabbitmq: Option<(Connection, Channel)>,
+}
+
+impl EventRepository {
+    pub fn new(broker: BrokerKind, settings: &Events) -> Self {
+        Self {
+            broker,
+            url: settings.url.clone(),
+            username: settings.username.clone(),
+            password: settings.password.clone(),
+            tls: settings.tls,
+            tls_ca_path: settings.tls_ca_path.clone(),
+            subject_prefix: settings.subject_prefix.clone(),
+            exchange: settings.exchange.clone(),
+            vhost: settings.vhost.clone(),
+            nats: None,
+            rabbitmq: None,
+        }
+    }
+
+    fn subject(&self, event: &DomainEvent) -> String {
+        format!("{}.{}", self.subject_prefix, event.kind.as_str())
+    }
+
+    /// Publishes the events in order. When it fails some of them may have
+    /// been published already.
+    pub async fn publish(&mut self, events: &[DomainEvent]) -> Result<(), anyhow::Error> {
+        let timeout = tokio::time::Duration::from_secs(PUBLISH_TIMEOUT_SECS);
+
+        let result = match self.broker {
+            BrokerKind::Nats => tokio::time::timeout(timeout, self.publish_nats(events)).await,
+            BrokerKind::Rabbitmq => {
+                tokio::time::timeout(timeout, self.publish_rabbitmq(events)).await
+            }
+        };
+        let result = match result {
+            Ok(result) => result,
+            Err(_) => Err(anyhow!("Broker did not answer in time")),
+        };
+
+        if result.is_err() {
+            // Reconnect on the next attempt
+            self.nats = None;
+            self.rabbitmq = None;
+        }
+
+        result
+    }
+
+    async fn connect_nats(&self) -> Result<async_nats::Client, anyhow::Error> {
+        let mut options = async_nats::ConnectOptions::new()
+            .name("mooze-dealer")
+            .require_tls(self.tls);
+        if let Some(path) = &self.tls_ca_path {
+            options = options.add_root_certificates(path.into());
+        }
+        if let Some(username) = &self.username {
+            options = options
+                .user_and_password(username.clone(), self.password.clone().unwrap_or_default());
+        }
+
+        Ok(options.connect(self.url.as_str()).await?)
+    }
+
+    async fn publish_nats(&mut self, events: &[DomainEvent]) -> Result<(), anyhow::Error> {
+        let client = match &self.nats {
+            Some(client) => client.clone(),
+            None => self.nats.insert(self.connect_nats().await?).clone(),
+        };
+
+        for event in events {
+            client
+                .publish(self.subject(event), serde_json::to_vec(event)?.into())
+                .await?;
+        }
+        // Returns once the server processed everything before it
+        client.flush().await?;
+
+        Ok(())
+    }
+
+    async fn connect_rabbitmq(&self) -> Result<(Connection, Channel), anyhow::Error> {
+        let mut uri: AMQPUri = self.url.parse().map_err(|e: String| anyhow!(e))?;
+        uri.vhost = self.vhost.clone();
+        if let Some(username) = &self.username {
+            uri.authority.userinfo.username = username.clone();
+            uri.authority.userinfo.password = self.password.clone().unwrap_or_default();
+        }
+
+        let tls = OwnedTLSConfig {
+            identity: None,
+            cert_chain: match &self.tls_ca_path {
+                Some(path) => Some(tokio::fs::read_to_string(path).await?),
+                None => None,
+            },
+        };
+        let connection =
+            Connection::connect_uri_with_config(uri, ConnectionProperties::default(), tls).await?;
+        let channel = connection.create_channel().await?;
+        channel
+            .confirm_select(ConfirmSelectOptions::default())
+            .await?;
+
+        Ok((connection, channel))
+    }
+
+    async fn publish_rabbitmq(&mut self, events: &[DomainEvent]) -> Result<(), anyhow::Error> {
+        let channel = match &self.rabbitmq {
+            Some((_, channel)) => channel.clone(),
+            None => self
+                .rabbitmq
+                .insert(self.connect_rabbitmq().await?)
+                .1
+                .clone(),
+        };
+
+        for event in events {
+            let subject = self.subject(event);
+            let properties = BasicProperties::default()
+                .with_content_type("application/json".into())
+                .with_message_id(event.id.as_str().into())
+                .with_delivery_mode(2);
+            let confirmation = channel
+                .basic_publish(
+                    &self.exchange,
+                    &subject,
+                    BasicPublishOptions {
+                        mandatory: true,
+                        ..BasicPublishOptions::default()
+                    },
+                    &serde_json::to_vec(event)?,
+                    properties,
+                )
+                .await?
+                .await?;
+
+            match confirmation {
+                Confirmation::Ack(Some(_)) => log::warn!(
+                    "No queue is bound to {}, event {} is lost",
+                    subject,
+                    event.id
+                ),
+                Confirmation::Ack(None) | Confirmation::NotRequested => {}
+                Confirmation::Nack(_) => bail!("RabbitMQ refused event {}", event.id),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use crate::models::handovers::{Handover, Reconciliation};
+use crate::repositories::audit::record_audit_event;
+
+use sqlx::PgPool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+pub struct HandoverRepository {
+    conn: PgPool,
+}
+
+impl HandoverRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Checks the ledger against the txids of an imported wallet.
+    pub async fn reconcile(
+        &self,
+        txids: &HashSet<String>,
+    ) -> Result<Reconciliation, anyhow::Error> {
+        let payout_txids = sqlx::query_scalar::<_, String>(
+            "SELECT txid FROM payouts WHERE txid IS NOT NULL ORDER BY created_at",
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        let unfinished_payouts =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM payouts WHERE txid IS NULL")
+                .fetch_one(&self.conn)
+                .await?;
+
+        let unprocessed_webhooks = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM webhook_events WHERE processed_at IS NULL",
+        )
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(Reconciliation {
+            missing_payouts: payout_txids
+                .into_iter()
+                .filter(|txid| !txids.contains(txid))
+                .collect(),
+            unfinished_payouts,
+            unprocessed_webhooks,
+        })
+    }
+
+    /// Records the handover, and who made it in the audit log, as of the
+    /// last webhook stored.
+    pub async fn create_handover(
+        &self,
+        wallet_tip_height: u32,
+        wallet_transactions: usize,
+        forced: bool,
+    ) -> Result<Handover, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let handover = sqlx::query_as::<_, Handover>(
+            r#"
+            INSERT INTO handovers (
+                id, wallet_tip_height, wallet_transactions,
+                last_webhook_event_id, last_webhook_received_at, forced
+            )
+            SELECT $1, $2, $3, last.id, last.received_at, $4
+            FROM (SELECT NULL) AS none
+            LEFT JOIN (
+                SELECT id, received_at FROM webhook_events
+                ORDER BY received_at DESC LIMIT 1
+            ) AS last ON TRUE
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().hyphenated().to_string())
+        .bind(wallet_tip_height as i32)
+        .bind(wallet_transactions as i32)
+        .bind(forced)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        record_audit_event(
+            &mut *tx,
+            "bootstrap",
+            "handover",
+            &handover.id,
+            serde_json::json!({
+                "wallet_tip_height": handover.wallet_tip_height,
+                "wallet_transactions": handover.wallet_transactions,
+                "last_webhook_event_id": handover.last_webhook_event_id,
+                "forced": handover.forced,
+            }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(handover)
+    }
+}
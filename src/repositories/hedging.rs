@@ -0,0 +1,251 @@
+use crate::models::hedging::{AssetExposure, Hedge};
+use crate::models::transactions::Assets;
+
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct HedgingRepository {
+    conn: PgPool,
+}
+
+impl HedgingRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Books the units a finished payout sent out, at the price it was
+    /// computed with. The fee stays in the wallet and is not sold.
+    pub async fn record_payout(
+        &self,
+        transaction_id: &str,
+        hedged_assets: &[String],
+    ) -> Result<bool, anyhow::Error> {
+        let units: Vec<i64> = hedged_assets
+            .iter()
+            .map(|asset| Assets::unit_of(asset) as i64)
+            .collect();
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO exposure_ledger (asset, kind, reference, asset_amount, brl_cents)
+            SELECT t.asset, 'payout', t.id,
+                   -(FLOOR(t.amount_in_cents::NUMERIC * a.unit / p.price_in_cents)
+                     - COALESCE(t.fee_collected, 0))::BIGINT,
+                   t.amount_in_cents
+            FROM transactions t
+            JOIN transaction_prices p ON p.transaction_id = t.id
+            JOIN UNNEST($2::TEXT[], $3::BIGINT[]) AS a(asset, unit) ON a.asset = t.asset
+            WHERE t.id = $1
+              AND t.status = 'finished'
+              AND p.quote_currency = 'brl'
+            ON CONFLICT (kind, reference) DO NOTHING
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(hedged_assets)
+        .bind(&units)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn get_exposures(&self) -> Result<Vec<AssetExposure>, anyhow::Error> {
+        let exposures = sqlx::query_as::<_, AssetExposure>(
+            r#"
+            SELECT l.asset,
+                   (-SUM(l.asset_amount))::BIGINT AS open_amount,
+                   SUM(l.brl_cents)::BIGINT AS open_brl_cents,
+                   COALESCE((
+                       SELECT SUM(h.expected_amount) FROM hedges h
+                       WHERE h.asset = l.asset AND h.status IN ('pending', 'open')
+                   ), 0)::BIGINT AS pending_amount,
+                   COALESCE((
+                       SELECT SUM(h.realized_pnl_cents) FROM hedges h
+                       WHERE h.asset = l.asset AND h.status = 'filled'
+                   ), 0)::BIGINT AS realized_pnl_cents
+            FROM exposure_ledger l
+            GROUP BY l.asset
+            ORDER BY l.asset
+            "#,
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(exposures)
+    }
+
+    /// Records a hedge about to be requested. It counts against the exposure
+    /// from now on, so a crash before its swap is booked doesn't hedge twice.
+    pub async fn insert_hedge(
+        &self,
+        asset: &str,
+        expected_amount: i64,
+        depix_amount: i64,
+    ) -> Result<i64, anyhow::Error> {
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO hedges (asset, expected_amount, depix_amount, status)
+            VALUES ($1, $2, $3, 'pending')
+            RETURNING id
+            "#,
+        )
+        .bind(asset)
+        .bind(expected_amount)
+        .bind(depix_amount)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Links a pending hedge to the swap it was placed as.
+    pub async fn open_hedge(&self, id: i64, quote_sub_id: i64) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "UPDATE hedges SET quote_sub_id = $2, status = 'open' WHERE id = $1 AND status = 'pending'",
+        )
+        .bind(id)
+        .bind(quote_sub_id)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Closes a pending hedge whose swap was refused.
+    pub async fn fail_hedge(&self, id: i64) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "UPDATE hedges SET status = 'failed', closed_at = CURRENT_TIMESTAMP WHERE id = $1 AND status = 'pending'",
+        )
+        .bind(id)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Books a filled hedge against the open position of its asset at the
+    /// position's average price, and returns it with its realized P&L.
+    /// Hedges that expired are booked too, since their swap went through
+    /// after all. Nothing is booked for swaps that are not hedges.
+    pub async fn fill_hedge(
+        &self,
+        quote_sub_id: i64,
+        depix_sold: i64,
+        received_amount: i64,
+    ) -> Result<Option<Hedge>, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let hedge = sqlx::query_as::<_, Hedge>(
+            "SELECT * FROM hedges WHERE quote_sub_id = $1 AND status IN ('open', 'expired') FOR UPDATE",
+        )
+        .bind(quote_sub_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(hedge) = hedge else {
+            return Ok(None);
+        };
+
+        // Serializes fills of the same asset while the position is read
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(&hedge.asset)
+            .execute(&mut *tx)
+            .await?;
+
+        let (open_amount, open_brl_cents): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(-SUM(asset_amount), 0)::BIGINT, COALESCE(SUM(brl_cents), 0)::BIGINT
+            FROM exposure_ledger WHERE asset = $1
+            "#,
+        )
+        .bind(&hedge.asset)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let cost_cents = depix_to_cents(depix_sold);
+        let (brl_cents, realized_pnl_cents) =
+            realize(open_amount, open_brl_cents, received_amount, cost_cents);
+
+        sqlx::query(
+            r#"
+            INSERT INTO exposure_ledger (asset, kind, reference, asset_amount, brl_cents)
+            VALUES ($1, 'hedge', $2, $3, $4)
+            "#,
+        )
+        .bind(&hedge.asset)
+        .bind(quote_sub_id.to_string())
+        .bind(received_amount)
+        .bind(brl_cents)
+        .execute(&mut *tx)
+        .await?;
+
+        let hedge = sqlx::query_as::<_, Hedge>(
+            r#"
+            UPDATE hedges
+            SET status = 'filled', depix_amount = $2, received_amount = $3,
+                realized_pnl_cents = $4, closed_at = CURRENT_TIMESTAMP
+            WHERE quote_sub_id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(quote_sub_id)
+        .bind(depix_sold)
+        .bind(received_amount)
+        .bind(realized_pnl_cents)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(hedge))
+    }
+
+    /// Hedges that did not fill in time stop counting against the exposure.
+    /// A late fill is still booked.
+    pub async fn expire_hedges(
+        &self,
+        created_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, anyhow::Error> {
+        let result = sqlx::query(
+            "UPDATE hedges SET status = 'expired', closed_at = CURRENT_TIMESTAMP WHERE status IN ('pending', 'open') AND created_at < $1",
+        )
+        .bind(created_before)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_recent_hedges(&self, limit: i64) -> Result<Vec<Hedge>, anyhow::Error> {
+        let hedges =
+            sqlx::query_as::<_, Hedge>("SELECT * FROM hedges ORDER BY created_at DESC LIMIT $1")
+                .bind(limit)
+                .fetch_all(&self.conn)
+                .await?;
+
+        Ok(hedges)
+    }
+}
+
+/// DEPIX base units to BRL cents, at one real per DEPIX.
+fn depix_to_cents(depix_amount: i64) -> i64 {
+    (i128::from(depix_amount) * 100 / i128::from(Assets::DEPIX.unit())) as i64
+}
+
+/// Ledger BRL entry and realized P&L of buying back `received` units for
+/// `cost_cents`. The covered units release their share of the position's
+/// basis; units bought beyond the open position are carried at cost.
+fn realize(open_amount: i64, open_brl_cents: i64, received: i64, cost_cents: i64) -> (i64, i64) {
+    if received <= 0 {
+        return (0, 0);
+    }
+
+    let covered = received.min(open_amount.max(0));
+    let covered_basis = match open_amount {
+        0 => 0,
+        _ => (open_brl_cents as i128 * covered as i128 / open_amount as i128) as i64,
+    };
+    let covered_cost = (cost_cents as i128 * covered as i128 / received as i128) as i64;
+    let extra_cost = cost_cents - covered_cost;
+
+    (-(covered_basis + extra_cost), covered_basis - covered_cost)
+}
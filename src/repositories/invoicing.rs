@@ -0,0 +1,69 @@
+use crate::models::merchants::{FeeInvoice, Merchant};
+use crate::settings::Invoicing;
+
+use anyhow::bail;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+#[derive(Deserialize)]
+struct IssuedInvoice {
+    id: String,
+}
+
+/// Posts fee invoices to the invoicing API, which answers with the id of the
+/// issued invoice. The transaction id is sent as the idempotency key, so a
+/// retried request does not issue the invoice twice.
+#[derive(Clone)]
+pub struct InvoicingRepository {
+    url: String,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl InvoicingRepository {
+    pub fn new(settings: &Invoicing) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(settings.timeout_secs))
+            .build()
+            .expect("Could not build invoicing HTTP client.");
+
+        Self {
+            url: settings.url.clone(),
+            api_key: settings.api_key.clone(),
+            client,
+        }
+    }
+
+    pub async fn issue_invoice(
+        &self,
+        invoice: &FeeInvoice,
+        merchant: &Merchant,
+    ) -> Result<String, anyhow::Error> {
+        let response = self
+            .client
+            .post(&self.url)
+            .bearer_auth(&self.api_key)
+            .header("Idempotency-Key", &invoice.transaction_id)
+            .json(&json!({
+                "reference": invoice.transaction_id,
+                "merchant_id": merchant.id,
+                "merchant_name": merchant.name,
+                "service_amount_in_cents": invoice.fee_in_cents,
+                "fee_bps": invoice.fee_bps,
+                "payment_amount_in_cents": invoice.amount_in_cents,
+                "paid_at": invoice.created_at,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!("Invoicing API answered {}: {}", status, body);
+        }
+
+        let issued: IssuedInvoice = response.json().await?;
+        Ok(issued.id)
+    }
+}
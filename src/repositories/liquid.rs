@@ -1,18 +1,36 @@
 use directories::ProjectDirs;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use anyhow::{anyhow, bail};
+use base64::Engine;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::models::reserves::ReserveAddress;
+use crate::models::wallet::{
+    BroadcastContext, BroadcastTransaction, DerivedAddress, ImportedWallet, IncomingTransfer,
+    PsetBalance, PsetRecipient, ReceivedOutput, WatchOnlyDescriptor,
+};
+use crate::utils::latency::{self, Dependency};
 use lwk_common::Signer;
 use lwk_signer::SwSigner;
+use lwk_wollet::elements::bitcoin::{bip32::DerivationPath, hashes::Hash, secp256k1, sign_message};
 use lwk_wollet::{
     self,
     blocking::BlockchainBackend,
-    elements::{pset::{serialize::Serialize, PartiallySignedTransaction}, OutPoint, Transaction, TxOut},
-    full_scan_with_electrum_client, ElectrumClient, ElectrumUrl, ElementsNetwork, FsPersister,
-    NoPersist, WalletTxOut, Wollet,
+    elements::{pset::PartiallySignedTransaction, OutPoint, Txid},
+    full_scan_to_index_with_electrum_client, ElectrumClient, ElectrumUrl, ElementsNetwork,
+    FsPersister, Persister, WalletTxOut, Wollet,
 };
 
+mod selection;
+mod snapshot;
+
+use snapshot::{SnapshotCell, UtxoSnapshot};
+
 trait SignerExt {
     fn wpkh_slip77_descriptor(&self) -> Result<lwk_wollet::WolletDescriptor, anyhow::Error>;
 }
@@ -36,12 +54,35 @@ impl SignerExt for SwSigner {
     }
 }
 
+/// Where the wallet's updates are persisted, so a restart picks up from the
+/// last scan.
+pub fn store_dir() -> Result<PathBuf, anyhow::Error> {
+    let proj_dirs = ProjectDirs::from("com", "mooze", "dealer")
+        .ok_or_else(|| anyhow!("Could not find the config directory"))?;
+
+    Ok(proj_dirs.config_dir().to_path_buf())
+}
+
+// Sizes in vbytes used to estimate the fee when choosing coins, generous so
+// the builder's exact fee is covered. Confidential outputs are discounted.
+const PAYOUT_BASE_VSIZE: u64 = 20;
+const OUTPUT_VSIZE: u64 = 200;
+const INPUT_VSIZE: u64 = 100;
+
 #[derive(Debug)]
 pub struct LiquidRepository {
     signer: SwSigner,
     wallet: RwLock<Wollet>,
     electrum_client: RwLock<ElectrumClient>,
     network: ElementsNetwork,
+    /// As configured, recorded with every broadcast.
+    electrum_server: String,
+    /// Highest index handed out, scanned even past the gap limit.
+    scan_to_index: AtomicU32,
+    /// Finalized transactions are not broadcast.
+    dry_run: bool,
+    /// Unspent outputs as of the last update, read without the wallet lock.
+    snapshot: SnapshotCell,
 }
 
 impl LiquidRepository {
@@ -49,56 +90,134 @@ impl LiquidRepository {
         mnemonic: &str,
         electrum_url: String,
         is_mainnet: bool,
+        scan_to_index: u32,
+        dry_run: bool,
     ) -> Result<Arc<LiquidRepository>, anyhow::Error> {
         let network = match is_mainnet {
             true => ElementsNetwork::Liquid,
             false => ElementsNetwork::LiquidTestnet,
         };
 
-        // using expect here to stop at startup if wallet load fails
         let signer = SwSigner::new(mnemonic, is_mainnet)
-            .expect("Could not build signer. Maybe mnemonic is invalid?");
-        let descriptor = signer.wpkh_slip77_descriptor().unwrap();
+            .map_err(|e| anyhow!("Could not build signer. Maybe mnemonic is invalid? {}", e))?;
+        let descriptor = signer.wpkh_slip77_descriptor()?;
 
-        let proj_dirs = ProjectDirs::from("com", "mooze", "dealer").unwrap();
-        let persister = FsPersister::new(proj_dirs.config_dir(), network, &descriptor).unwrap();
+        let persister = FsPersister::new(store_dir()?, network, &descriptor)
+            .map_err(|e| anyhow!("Could not open wallet store: {}", e))?;
 
-        let electrum_url =
-            ElectrumUrl::new(&electrum_url, true, true).expect("Invalid Electrum URL.");
-        let mut wallet = Wollet::new(network, NoPersist::new(), descriptor)
-            .expect("Could not initialize wallet.");
-        let mut electrum_client =
-            ElectrumClient::new(&electrum_url).expect("Could not connect to Electrum server.");
+        let electrum_server = electrum_url;
+        let electrum_url = ElectrumUrl::new(&electrum_server, true, true)
+            .map_err(|e| anyhow!("Invalid Electrum URL: {}", e))?;
+        let mut wallet = Wollet::new(network, persister, descriptor)
+            .map_err(|e| anyhow!("Could not initialize wallet: {}", e))?;
+        let mut electrum_client = ElectrumClient::new(&electrum_url)
+            .map_err(|e| anyhow!("Could not connect to Electrum server: {}", e))?;
 
-        full_scan_with_electrum_client(&mut wallet, &mut electrum_client)?;
+        full_scan_to_index_with_electrum_client(&mut wallet, scan_to_index, &mut electrum_client)?;
 
-        let balances = wallet.balance().expect("Could not get balances.");
+        let snapshot = UtxoSnapshot::from_wallet(&wallet)?;
 
         Ok(Arc::new(LiquidRepository {
             signer,
             wallet: RwLock::new(wallet),
             electrum_client: RwLock::new(electrum_client),
             network,
+            electrum_server,
+            scan_to_index: AtomicU32::new(scan_to_index),
+            dry_run,
+            snapshot: SnapshotCell::new(snapshot),
         }))
     }
 
+    /// Copies the wallet updates persisted by another dealer under `source`
+    /// (its wallet store directory) into this one's store and opens the
+    /// wallet from them, without touching Electrum. Updates are encrypted
+    /// with a key derived from the descriptor, so nothing is found for a
+    /// different mnemonic.
+    pub fn import_wallet_state(
+        mnemonic: &str,
+        is_mainnet: bool,
+        source: &Path,
+    ) -> Result<ImportedWallet, anyhow::Error> {
+        let network = match is_mainnet {
+            true => ElementsNetwork::Liquid,
+            false => ElementsNetwork::LiquidTestnet,
+        };
+
+        let signer = SwSigner::new(mnemonic, is_mainnet)
+            .map_err(|e| anyhow!("Could not build signer. Maybe mnemonic is invalid? {}", e))?;
+        let descriptor = signer.wpkh_slip77_descriptor()?;
+
+        let target_dir = store_dir()?;
+        let source_persister = FsPersister::new(source, network, &descriptor)
+            .map_err(|e| anyhow!("Could not open wallet state: {}", e))?;
+        let target_persister = FsPersister::new(&target_dir, network, &descriptor)
+            .map_err(|e| anyhow!("Could not open wallet store: {}", e))?;
+
+        if target_persister.get(0)?.is_some() {
+            bail!(
+                "{} already holds state for this wallet, move it aside first",
+                target_dir.display()
+            );
+        }
+
+        let mut updates = 0;
+        while let Some(update) = source_persister.get(updates)? {
+            target_persister.push(update)?;
+            updates += 1;
+        }
+        if updates == 0 {
+            bail!("No state for this wallet under {}", source.display());
+        }
+
+        let wallet = Wollet::new(network, target_persister, descriptor.clone())
+            .map_err(|e| anyhow!("Could not open imported wallet: {}", e))?;
+        let txids = wallet
+            .transactions()
+            .map_err(|e| anyhow!("Could not list wallet transactions: {}", e))?
+            .iter()
+            .map(|tx| tx.txid.to_string())
+            .collect();
+        let balances = wallet
+            .balance()
+            .map_err(|e| anyhow!("Could not get balances: {}", e))?
+            .into_iter()
+            .map(|(asset, amount)| (asset.to_string(), amount))
+            .collect();
+
+        Ok(ImportedWallet {
+            descriptor: descriptor.to_string(),
+            updates,
+            tip_height: wallet.tip().height(),
+            txids,
+            balances,
+        })
+    }
+
     pub async fn update_wallet(&self) -> Result<(), anyhow::Error> {
         let mut wallet = self.wallet.write().await;
         let mut electrum_client = self.electrum_client.write().await;
 
-        let update = electrum_client.full_scan(&*wallet)?;
+        let update = latency::track_blocking(Dependency::Electrum, || {
+            electrum_client.full_scan_to_index(&*wallet, self.scan_to_index.load(Ordering::Relaxed))
+        })?;
         match update {
             Some(update) => {
                 wallet.apply_update(update)?;
+                self.snapshot.store(UtxoSnapshot::from_wallet(&wallet)?);
                 Ok(())
             }
             None => return Ok(()),
         }
     }
 
+    /// With `avoid_change`, a payout in L-BTC only spends the coins leaving
+    /// the least change rather than all of them.
     pub async fn build_transaction(
         &self,
         recipients: Vec<lwk_wollet::UnvalidatedRecipient>,
+        fee_rate_sat_kvb: f32,
+        avoid_change: bool,
     ) -> Result<PartiallySignedTransaction, anyhow::Error> {
         let validated_recipients = recipients
             .into_iter()
@@ -106,13 +225,32 @@ impl LiquidRepository {
             .collect::<Result<Vec<_>, _>>()?;
 
         let wallet_guard = self.wallet.read().await;
-        let mut tx_builder = wallet_guard.tx_builder();
-
-        for recipient in validated_recipients {
-            tx_builder = tx_builder.add_validated_recipient(recipient);
-        }
+        let coins = match avoid_change {
+            true => {
+                self.exact_change_coins(&wallet_guard, &validated_recipients, fee_rate_sat_kvb)?
+            }
+            false => None,
+        };
+        let build = |coins: Option<Vec<OutPoint>>| {
+            let mut tx_builder = wallet_guard.tx_builder().fee_rate(Some(fee_rate_sat_kvb));
+            for recipient in validated_recipients.iter().cloned() {
+                tx_builder = tx_builder.add_validated_recipient(recipient);
+            }
+            if let Some(coins) = coins {
+                tx_builder = tx_builder.set_wallet_utxos(coins);
+            }
+            tx_builder.finish()
+        };
 
-        let tx = tx_builder.finish().map_err(|e| {
+        let built = match coins {
+            // The fee was estimated, the builder knows it exactly
+            Some(coins) => build(Some(coins)).or_else(|e| {
+                log::warn!("Could not pay with the closest coins, spending all of them: {e}");
+                build(None)
+            }),
+            None => build(None),
+        };
+        let tx = built.map_err(|e| {
             log::error!("{:?}", e.to_string());
             anyhow!("Failed to finish transaction build: {e}")
         })?;
@@ -127,6 +265,41 @@ impl LiquidRepository {
         Ok(tx)
     }
 
+    /// L-BTC coins paying the recipients with the least change, when they are
+    /// all paid in L-BTC. The builder only takes chosen coins then, and
+    /// otherwise spends every L-BTC coin of the wallet.
+    fn exact_change_coins(
+        &self,
+        wallet: &Wollet,
+        recipients: &[lwk_wollet::Recipient],
+        fee_rate_sat_kvb: f32,
+    ) -> Result<Option<Vec<OutPoint>>, anyhow::Error> {
+        let policy_asset = self.network.policy_asset();
+        if recipients
+            .iter()
+            .any(|recipient| recipient.asset != policy_asset)
+        {
+            return Ok(None);
+        }
+
+        let amount = recipients.iter().map(|recipient| recipient.satoshi).sum();
+        let coins: Vec<(OutPoint, u64)> = wallet
+            .utxos()
+            .map_err(|e| anyhow!("Failed to fetch UTXOs: {e}"))?
+            .into_iter()
+            .filter(|utxo| utxo.unblinded.asset == policy_asset)
+            .map(|utxo| (utxo.outpoint, utxo.unblinded.value))
+            .collect();
+        // Recipients, change and fee outputs
+        let outputs = recipients.len() as u64 + 2;
+        let fee = |inputs: usize| {
+            let vsize = PAYOUT_BASE_VSIZE + outputs * OUTPUT_VSIZE + inputs as u64 * INPUT_VSIZE;
+            (vsize as f32 * fee_rate_sat_kvb / 1000.0).ceil() as u64
+        };
+
+        Ok(selection::exact_change(&coins, amount, fee))
+    }
+
     pub fn sign_transaction(
         &self,
         mut pset: PartiallySignedTransaction,
@@ -155,17 +328,8 @@ impl LiquidRepository {
         Ok(txout.clone())
     }
 
-    // Taken from Bull Bitcoin definition
-    pub async fn sign_with_extra_details(
-        &self,
-        mut pset: PartiallySignedTransaction,
-    ) -> Result<String, anyhow::Error> {
-        let wallet = self.wallet.read().await;
-        let _ = self.signer.sign(&mut pset).map_err(|e| {
-            log::error!("{}", e.to_string());
-            anyhow!("Could not sign transaction: {e}")
-        })?;
-
+    /// Sets the previous outputs of the inputs spending the wallet's coins.
+    async fn add_witness_utxos(&self, pset: &mut PartiallySignedTransaction) {
         for input in pset.inputs_mut().iter_mut() {
             let res = self.get_txout(&OutPoint {
                 txid: input.previous_txid,
@@ -177,6 +341,59 @@ impl LiquidRepository {
                 input.witness_utxo = Some(txout);
             }
         }
+    }
+
+    /// What `pset` does to the wallet. Fails when an input lacks its
+    /// previous output or an output of the wallet cannot be unblinded.
+    pub async fn pset_balance(
+        &self,
+        pset: &PartiallySignedTransaction,
+    ) -> Result<PsetBalance, anyhow::Error> {
+        let mut pset = pset.clone();
+        self.add_witness_utxos(&mut pset).await;
+
+        let wallet = self.wallet.read().await;
+        let details = wallet
+            .get_details(&pset)
+            .map_err(|e| anyhow!("Could not decode the PSET: {e}"))?;
+
+        Ok(PsetBalance {
+            balances: details
+                .balance
+                .balances
+                .into_iter()
+                .map(|(asset_id, amount)| (asset_id.to_string(), amount))
+                .collect(),
+            fee: details.balance.fee,
+            recipients: details
+                .balance
+                .recipients
+                .into_iter()
+                .map(|recipient| PsetRecipient {
+                    vout: recipient.vout,
+                    script_pubkey: format!(
+                        "{:x}",
+                        pset.outputs()[recipient.vout as usize].script_pubkey
+                    ),
+                    asset_id: recipient.asset.map(|asset_id| asset_id.to_string()),
+                    value: recipient.value,
+                })
+                .collect(),
+        })
+    }
+
+    // Taken from Bull Bitcoin definition
+    pub async fn sign_with_extra_details(
+        &self,
+        mut pset: PartiallySignedTransaction,
+    ) -> Result<String, anyhow::Error> {
+        let wallet = self.wallet.read().await;
+        let _ = self.signer.sign(&mut pset).map_err(|e| {
+            log::error!("{}", e);
+            anyhow!("Could not sign transaction: {e}")
+        })?;
+
+        self.add_witness_utxos(&mut pset).await;
 
         wallet.add_details(&mut pset)?;
         let _ = self.signer.sign(&mut pset).map_err(|e| {
@@ -196,7 +413,7 @@ impl LiquidRepository {
     pub async fn finalize_and_broadcast_transaction(
         &self,
         mut pset: PartiallySignedTransaction,
-    ) -> Result<String, anyhow::Error> {
+    ) -> Result<BroadcastTransaction, anyhow::Error> {
         let wallet = self.wallet.read().await;
         let client = self.electrum_client.read().await;
 
@@ -205,73 +422,272 @@ impl LiquidRepository {
             anyhow!("Could not finalize transaction: {e}")
         })?;
 
-        let txid = client.broadcast(&tx).map_err(|e| {
-            log::error!("{}", e.to_string());
-            anyhow!("Could not broadcast transaction: {e}")
-        })?;
+        if self.dry_run {
+            let txid_string = tx.txid().to_string();
+            log::info!("Dry run, not broadcasting TXID: {}", txid_string);
+
+            return Ok(BroadcastTransaction {
+                txid: txid_string,
+                network_fee: tx.fee_in(wallet.policy_asset()),
+                context: self.broadcast_context(&wallet),
+            });
+        }
+
+        let txid = latency::track_blocking(Dependency::Electrum, || client.broadcast(&tx))
+            .map_err(|e| {
+                log::error!("{}", e);
+                anyhow!("Could not broadcast transaction: {e}")
+            })?;
 
         let txid_string = txid.to_string();
         log::info!("TXID: {}", txid_string);
 
-        Ok(txid_string)
+        Ok(BroadcastTransaction {
+            txid: txid_string,
+            network_fee: tx.fee_in(wallet.policy_asset()),
+            context: self.broadcast_context(&wallet),
+        })
     }
 
-    pub async fn generate_address(&self) -> Result<String, anyhow::Error> {
+    fn broadcast_context(&self, wallet: &Wollet) -> BroadcastContext {
+        let tip = wallet.tip();
+        BroadcastContext {
+            network: self.network.as_str().to_string(),
+            electrum_server: self.electrum_server.clone(),
+            tip_height: tip.height(),
+            tip_time: tip
+                .timestamp()
+                .and_then(|timestamp| chrono::DateTime::from_timestamp(timestamp.into(), 0)),
+            broadcast_at: chrono::Utc::now(),
+        }
+    }
+
+    /// Whether a transaction is known to the wallet, in the mempool or confirmed.
+    pub async fn has_transaction(&self, txid: &str) -> Result<bool, anyhow::Error> {
+        let txid = Txid::from_str(txid)?;
         let wallet = self.wallet.read().await;
-        let address = wallet
-            .address(None)
-            .map_err(|e| anyhow!(e.to_string()))?
-            .address()
-            .to_string();
 
-        Ok(address)
+        Ok(wallet.transaction(&txid)?.is_some())
     }
 
-    pub async fn generate_change_address(&self) -> Result<String, anyhow::Error> {
+    /// Transactions that added `asset_id` to the wallet, unconfirmed ones
+    /// and those confirmed at or after `since`.
+    pub async fn incoming_transfers(
+        &self,
+        asset_id: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<IncomingTransfer>, anyhow::Error> {
         let wallet = self.wallet.read().await;
-        let address = wallet
-            .change(None)
-            .map_err(|e| anyhow!(e.to_string()))?
-            .address()
-            .to_string();
+        let transactions = wallet
+            .transactions()
+            .map_err(|e| anyhow!("Failed to fetch transactions: {e}"))?;
 
-        Ok(address)
+        let transfers = transactions
+            .into_iter()
+            .filter_map(|tx| {
+                let amount = tx
+                    .balance
+                    .iter()
+                    .find(|(asset, _)| asset.to_string() == asset_id)
+                    .map(|(_, amount)| *amount)
+                    .filter(|amount| *amount > 0)?;
+                let timestamp = tx.timestamp.and_then(|timestamp| {
+                    chrono::DateTime::from_timestamp(i64::from(timestamp), 0)
+                });
+
+                Some(IncomingTransfer {
+                    txid: tx.txid.to_string(),
+                    amount: amount as u64,
+                    timestamp,
+                })
+            })
+            .filter(|transfer| {
+                transfer
+                    .timestamp
+                    .is_none_or(|timestamp| timestamp >= since)
+            })
+            .collect();
+
+        Ok(transfers)
     }
 
-    pub async fn get_utxos(
+    /// Outputs of the asset received on receive addresses since `since`,
+    /// whether spent or not. Change outputs are left out.
+    pub async fn received_outputs(
         &self,
-        asset: Option<String>,
-    ) -> Result<Vec<WalletTxOut>, anyhow::Error> {
+        asset_id: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<ReceivedOutput>, anyhow::Error> {
         let wallet = self.wallet.read().await;
-        let utxos = wallet
-            .utxos()
-            .map_err(|e| anyhow!("Failed to fetch UTXOs: {e}"))?;
+        let transactions = wallet
+            .transactions()
+            .map_err(|e| anyhow!("Failed to fetch transactions: {e}"))?;
 
-        if let Some(asset) = asset {
-            let filtered_utxos = utxos
-                .into_iter()
-                .filter(|utxo| utxo.unblinded.asset.to_string() == asset)
-                .collect();
+        let outputs = transactions
+            .into_iter()
+            .flat_map(|tx| {
+                let timestamp = tx.timestamp.and_then(|timestamp| {
+                    chrono::DateTime::from_timestamp(i64::from(timestamp), 0)
+                });
+                tx.outputs
+                    .into_iter()
+                    .flatten()
+                    .filter(|txo| txo.ext_int == lwk_wollet::Chain::External)
+                    .filter(|txo| txo.unblinded.asset.to_string() == asset_id)
+                    .map(move |txo| ReceivedOutput {
+                        txid: txo.outpoint.txid.to_string(),
+                        vout: txo.outpoint.vout,
+                        derivation_index: txo.wildcard_index,
+                        amount: txo.unblinded.value,
+                        timestamp,
+                    })
+            })
+            .filter(|output| output.timestamp.is_none_or(|timestamp| timestamp >= since))
+            .collect();
+
+        Ok(outputs)
+    }
+
+    pub async fn watch_only_descriptor(&self) -> Result<WatchOnlyDescriptor, anyhow::Error> {
+        let wallet = self.wallet.read().await;
+
+        let network = match self.network {
+            ElementsNetwork::Liquid => "liquid",
+            ElementsNetwork::LiquidTestnet => "liquid-testnet",
+            ElementsNetwork::ElementsRegtest { .. } => "elements-regtest",
+        };
 
-            return Ok(filtered_utxos);
+        let next_address = wallet.address(None).map_err(|e| anyhow!(e.to_string()))?;
+        let next_change = wallet.change(None).map_err(|e| anyhow!(e.to_string()))?;
+
+        Ok(WatchOnlyDescriptor {
+            descriptor: wallet.wollet_descriptor().to_string(),
+            network: network.to_string(),
+            next_address_index: next_address.index(),
+            next_change_index: next_change.index(),
+        })
+    }
+
+    /// Derives `limit` addresses starting at `offset`, without marking them
+    /// as used.
+    pub async fn derive_addresses(
+        &self,
+        change: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DerivedAddress>, anyhow::Error> {
+        let wallet = self.wallet.read().await;
+
+        (offset..offset.saturating_add(limit))
+            .map(|index| {
+                Ok(DerivedAddress {
+                    index,
+                    address: Self::address_at(&wallet, change, index)?,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn derive_address(&self, change: bool, index: u32) -> Result<String, anyhow::Error> {
+        let wallet = self.wallet.read().await;
+        Self::address_at(&wallet, change, index)
+    }
+
+    fn address_at(wallet: &Wollet, change: bool, index: u32) -> Result<String, anyhow::Error> {
+        let derived = match change {
+            true => wallet.change(Some(index)),
+            false => wallet.address(Some(index)),
         }
+        .map_err(|e| anyhow!(e.to_string()))?;
 
-        Ok(utxos)
+        Ok(derived.address().to_string())
     }
 
-    pub async fn get_asset_balance(&self, asset_id: &str) -> Result<u64, anyhow::Error> {
+    /// First index after the last one seen on chain.
+    pub async fn last_unused_index(&self, change: bool) -> Result<u32, anyhow::Error> {
         let wallet = self.wallet.read().await;
-        let balances = wallet
-            .balance()
-            .map_err(|e| anyhow!("Failed to fetch balances: {e}"))?;
+        let derived = match change {
+            true => wallet.change(None),
+            false => wallet.address(None),
+        }
+        .map_err(|e| anyhow!(e.to_string()))?;
 
-        for (asset, balance) in balances {
-            if asset.to_string() == asset_id {
-                return Ok(balance);
-            }
+        Ok(derived.index())
+    }
+
+    /// Makes wallet updates scan at least up to `index`.
+    pub fn extend_scan_to(&self, index: u32) {
+        self.scan_to_index.fetch_max(index, Ordering::Relaxed);
+    }
+
+    /// Signs `message` with the key of every address holding unspent outputs,
+    /// BIP-137 style: a compact recoverable signature of the Bitcoin signed
+    /// message hash, with the header byte of P2WPKH addresses.
+    pub async fn sign_reserves(&self, message: &str) -> Result<Vec<ReserveAddress>, anyhow::Error> {
+        let utxos = self.get_utxos(None).await?;
+        let mut addresses: BTreeMap<(u32, u32), ReserveAddress> = BTreeMap::new();
+        for utxo in utxos {
+            let (chain, chain_index) = match utxo.ext_int {
+                lwk_wollet::Chain::External => ("external", 0),
+                lwk_wollet::Chain::Internal => ("internal", 1),
+            };
+
+            let address = addresses
+                .entry((chain_index, utxo.wildcard_index))
+                .or_insert_with(|| ReserveAddress {
+                    address: utxo.address.to_unconfidential().to_string(),
+                    chain: chain.to_string(),
+                    derivation_index: utxo.wildcard_index,
+                    balances: BTreeMap::new(),
+                    signature: String::new(),
+                });
+            *address
+                .balances
+                .entry(utxo.unblinded.asset.to_string())
+                .or_default() += utxo.unblinded.value;
         }
 
-        // If the asset is not found, return 0 balance
-        Ok(0)
+        let coin_type = match self.network {
+            ElementsNetwork::Liquid => 1776,
+            _ => 1,
+        };
+        let secp = secp256k1::Secp256k1::new();
+        let digest = sign_message::signed_msg_hash(message);
+        let digest = secp256k1::Message::from_digest(digest.to_byte_array());
+
+        for ((chain_index, index), address) in addresses.iter_mut() {
+            let path = DerivationPath::from_str(&format!(
+                "m/84'/{}'/0'/{}/{}",
+                coin_type, chain_index, index
+            ))?;
+            let xprv = self
+                .signer
+                .derive_xprv(&path)
+                .map_err(|e| anyhow!("Could not derive key of {}: {}", address.address, e))?;
+
+            let (recovery_id, signature) = secp
+                .sign_ecdsa_recoverable(&digest, &xprv.private_key)
+                .serialize_compact();
+            let mut serialized = [0u8; 65];
+            serialized[0] = 39 + recovery_id.to_i32() as u8;
+            serialized[1..].copy_from_slice(&signature);
+
+            address.signature = base64::engine::general_purpose::STANDARD.encode(serialized);
+        }
+
+        Ok(addresses.into_values().collect())
+    }
+
+    /// Served from the snapshot of the last update.
+    pub async fn get_utxos(
+        &self,
+        asset: Option<String>,
+    ) -> Result<Vec<WalletTxOut>, anyhow::Error> {
+        Ok(self.snapshot.load().utxos(asset.as_deref()))
+    }
+
+    /// Served from the snapshot of the last update.
+    pub async fn get_asset_balance(&self, asset_id: &str) -> Result<u64, anyhow::Error> {
+        Ok(self.snapshot.load().balance(asset_id))
     }
 }
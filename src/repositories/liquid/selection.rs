@@ -0,0 +1,120 @@
+//! Picks the L-BTC coins a payout spends so that the least comes back as
+//! change, instead of spending every coin of the wallet as the builder does
+//! on its own.
+
+use lwk_wollet::elements::OutPoint;
+
+/// Coins covering `amount` plus `fee` of spending them, by the number of
+/// inputs, with the least left over. `None` when the coins can't cover it.
+///
+/// Tries the smallest coin covering it alone, and the largest of the coins
+/// too small to, added until they cover it, keeping whichever leaves less.
+pub fn exact_change(
+    coins: &[(OutPoint, u64)],
+    amount: u64,
+    fee: impl Fn(usize) -> u64,
+) -> Option<Vec<OutPoint>> {
+    let mut sorted = coins.to_vec();
+    sorted.sort_by_key(|(_, value)| std::cmp::Reverse(*value));
+    let (large, small): (Vec<_>, Vec<_>) = sorted
+        .into_iter()
+        .partition(|(_, value)| *value >= amount + fee(1));
+
+    let single = large.last().map(|coin| vec![*coin]);
+
+    let mut combined = Vec::new();
+    let mut total = 0;
+    for coin in small {
+        if total >= amount + fee(combined.len()) && !combined.is_empty() {
+            break;
+        }
+        combined.push(coin);
+        total += coin.1;
+    }
+    let combined =
+        (!combined.is_empty() && total >= amount + fee(combined.len())).then_some(combined);
+
+    let left_over = |selected: &Vec<(OutPoint, u64)>| {
+        selected.iter().map(|(_, value)| value).sum::<u64>() - fee(selected.len())
+    };
+    let best = match (single, combined) {
+        (Some(single), Some(combined)) => {
+            if left_over(&combined) < left_over(&single) {
+                combined
+            } else {
+                single
+            }
+        }
+        (Some(selected), None) | (None, Some(selected)) => selected,
+        (None, None) => return None,
+    };
+
+    Some(best.into_iter().map(|(outpoint, _)| outpoint).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lwk_wollet::elements::hashes::Hash;
+    use lwk_wollet::elements::Txid;
+
+    fn coins(values: &[u64]) -> Vec<(OutPoint, u64)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(vout, value)| (OutPoint::new(Txid::all_zeros(), vout as u32), *value))
+            .collect()
+    }
+
+    fn values(coins: &[(OutPoint, u64)], selected: &[OutPoint]) -> Vec<u64> {
+        let mut values: Vec<u64> = selected
+            .iter()
+            .map(|outpoint| coins.iter().find(|(o, _)| o == outpoint).unwrap().1)
+            .collect();
+        values.sort();
+        values
+    }
+
+    fn fee(inputs: usize) -> u64 {
+        10 + 5 * inputs as u64
+    }
+
+    #[test]
+    fn prefers_the_smallest_coin_covering_the_payout() {
+        let coins = coins(&[100_000, 5_020, 5_100, 2_000]);
+        let selected = exact_change(&coins, 5_000, fee).unwrap();
+
+        assert_eq!(values(&coins, &selected), vec![5_020]);
+    }
+
+    #[test]
+    fn combines_coins_when_none_covers_the_payout() {
+        let coins = coins(&[3_000, 2_500, 1_000, 400]);
+        let selected = exact_change(&coins, 5_000, fee).unwrap();
+
+        // The 1,000 and 400 aren't needed once 3,000 and 2,500 cover it
+        assert_eq!(values(&coins, &selected), vec![2_500, 3_000]);
+    }
+
+    #[test]
+    fn combines_smaller_coins_when_they_leave_less() {
+        let coins = coins(&[50_000, 3_000, 2_030]);
+        let selected = exact_change(&coins, 5_000, fee).unwrap();
+
+        assert_eq!(values(&coins, &selected), vec![2_030, 3_000]);
+    }
+
+    #[test]
+    fn counts_the_fee_of_each_input() {
+        let coins = coins(&[5_014, 5_015]);
+        let selected = exact_change(&coins, 5_000, fee).unwrap();
+
+        assert_eq!(values(&coins, &selected), vec![5_015]);
+    }
+
+    #[test]
+    fn nothing_when_the_wallet_cannot_cover_it() {
+        assert!(exact_change(&coins(&[1_000, 2_000]), 5_000, fee).is_none());
+        assert!(exact_change(&[], 1, fee).is_none());
+    }
+}
@@ -0,0 +1,177 @@
+//! Unspent outputs and balances of the wallet as of its last update. Taken
+//! while the update still holds the wallet, so reading them never waits on
+//! the wallet lock or a running scan.
+
+use lwk_wollet::{WalletTxOut, Wollet};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug, Default)]
+pub struct UtxoSnapshot {
+    utxos: Vec<WalletTxOut>,
+    /// By asset id, in base units.
+    balances: BTreeMap<String, u64>,
+}
+
+impl UtxoSnapshot {
+    pub fn new(utxos: Vec<WalletTxOut>) -> Self {
+        let mut balances = BTreeMap::new();
+        for utxo in &utxos {
+            *balances
+                .entry(utxo.unblinded.asset.to_string())
+                .or_default() += utxo.unblinded.value;
+        }
+
+        Self { utxos, balances }
+    }
+
+    pub fn from_wallet(wallet: &Wollet) -> Result<Self, anyhow::Error> {
+        let utxos = wallet
+            .utxos()
+            .map_err(|e| anyhow::anyhow!("Failed to fetch UTXOs: {e}"))?;
+
+        Ok(Self::new(utxos))
+    }
+
+    /// Unspent outputs, of `asset` only when given.
+    pub fn utxos(&self, asset: Option<&str>) -> Vec<WalletTxOut> {
+        self.utxos
+            .iter()
+            .filter(|utxo| asset.is_none_or(|asset| utxo.unblinded.asset.to_string() == asset))
+            .cloned()
+            .collect()
+    }
+
+    pub fn balance(&self, asset_id: &str) -> u64 {
+        self.balances.get(asset_id).copied().unwrap_or(0)
+    }
+}
+
+/// Latest snapshot. The lock is only held to swap or clone the `Arc`.
+#[derive(Debug, Default)]
+pub struct SnapshotCell {
+    current: RwLock<Arc<UtxoSnapshot>>,
+}
+
+impl SnapshotCell {
+    pub fn new(snapshot: UtxoSnapshot) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(snapshot)),
+        }
+    }
+
+    pub fn load(&self) -> Arc<UtxoSnapshot> {
+        self.current.read().unwrap().clone()
+    }
+
+    pub fn store(&self, snapshot: UtxoSnapshot) {
+        *self.current.write().unwrap() = Arc::new(snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::liquid::SignerExt;
+    use lwk_signer::SwSigner;
+    use lwk_wollet::elements::confidential::{AssetBlindingFactor, ValueBlindingFactor};
+    use lwk_wollet::elements::hashes::Hash;
+    use lwk_wollet::elements::{Address, AssetId, OutPoint, TxOutSecrets, Txid};
+    use lwk_wollet::{Chain, ElementsNetwork, NoPersist};
+    use proptest::prelude::*;
+    use std::sync::OnceLock;
+
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn wallet() -> Wollet {
+        let signer = SwSigner::new(MNEMONIC, false).unwrap();
+        let descriptor = signer.wpkh_slip77_descriptor().unwrap();
+        Wollet::new(ElementsNetwork::LiquidTestnet, NoPersist::new(), descriptor).unwrap()
+    }
+
+    /// Deriving addresses is slow, outputs all pay to the first one.
+    fn address() -> &'static Address {
+        static ADDRESS: OnceLock<Address> = OnceLock::new();
+        ADDRESS.get_or_init(|| wallet().address(Some(0)).unwrap().address().clone())
+    }
+
+    fn asset(id: u8) -> AssetId {
+        AssetId::from_slice(&[id; 32]).unwrap()
+    }
+
+    fn utxo(vout: u32, asset_id: u8, value: u64) -> WalletTxOut {
+        WalletTxOut {
+            outpoint: OutPoint::new(Txid::all_zeros(), vout),
+            script_pubkey: address().script_pubkey(),
+            height: Some(1),
+            unblinded: TxOutSecrets::new(
+                asset(asset_id),
+                AssetBlindingFactor::zero(),
+                value,
+                ValueBlindingFactor::zero(),
+            ),
+            wildcard_index: 0,
+            ext_int: Chain::External,
+            is_spent: false,
+            address: address().clone(),
+        }
+    }
+
+    #[test]
+    fn matches_the_wallet_it_was_taken_from() {
+        let wallet = wallet();
+        let snapshot = UtxoSnapshot::from_wallet(&wallet).unwrap();
+
+        assert_eq!(snapshot.utxos(None), wallet.utxos().unwrap());
+        for (asset, balance) in wallet.balance().unwrap() {
+            assert_eq!(snapshot.balance(&asset.to_string()), balance);
+        }
+    }
+
+    #[test]
+    fn filters_outputs_by_asset() {
+        let snapshot = UtxoSnapshot::new(vec![utxo(0, 1, 100), utxo(1, 2, 200), utxo(2, 1, 300)]);
+
+        let outputs = snapshot.utxos(Some(&asset(1).to_string()));
+        let vouts: Vec<u32> = outputs.iter().map(|utxo| utxo.outpoint.vout).collect();
+        assert_eq!(vouts, [0, 2]);
+        assert_eq!(snapshot.utxos(None).len(), 3);
+        assert_eq!(snapshot.balance(&asset(3).to_string()), 0);
+    }
+
+    #[test]
+    fn readers_keep_the_snapshot_they_loaded() {
+        let cell = SnapshotCell::new(UtxoSnapshot::new(vec![utxo(0, 1, 100)]));
+        let before = cell.load();
+
+        cell.store(UtxoSnapshot::new(vec![utxo(1, 1, 250)]));
+
+        assert_eq!(before.balance(&asset(1).to_string()), 100);
+        assert_eq!(cell.load().balance(&asset(1).to_string()), 250);
+    }
+
+    proptest! {
+        #[test]
+        fn balances_are_the_sum_of_the_outputs(
+            outputs in prop::collection::vec((1..=3u8, 0..=2_100_000_000_000_000u64), 0..20),
+        ) {
+            let utxos: Vec<WalletTxOut> = outputs
+                .iter()
+                .enumerate()
+                .map(|(vout, (asset_id, value))| utxo(vout as u32, *asset_id, *value))
+                .collect();
+            let snapshot = UtxoSnapshot::new(utxos);
+
+            for asset_id in 1..=3u8 {
+                let asset_id = asset(asset_id).to_string();
+                let sum: u64 = snapshot
+                    .utxos(Some(&asset_id))
+                    .iter()
+                    .map(|utxo| utxo.unblinded.value)
+                    .sum();
+                prop_assert_eq!(snapshot.balance(&asset_id), sum);
+            }
+        }
+    }
+}
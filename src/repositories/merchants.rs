@@ -0,0 +1,266 @@
+use crate::models::{merchants, pix::Deposit};
+
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+fn hash_api_key(api_key: &str) -> String {
+    format!("{:x}", Sha256::digest(api_key.trim().as_bytes()))
+}
+
+const PAYMENT_LINK_QUERY: &str = r#"
+    SELECT l.token, l.merchant_id, m.name AS merchant_name, l.transaction_id,
+        l.amount_in_cents, l.description, l.qr_copy_paste, l.qr_image_url,
+        t.status AS transaction_status, i.invoice_id, l.created_at
+    FROM payment_links l
+    JOIN merchants m ON m.id = l.merchant_id
+    JOIN all_transactions t ON t.id = l.transaction_id
+    LEFT JOIN merchant_invoices i ON i.transaction_id = l.transaction_id
+"#;
+
+#[derive(Clone)]
+pub struct MerchantRepository {
+    conn: PgPool,
+}
+
+impl MerchantRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    pub async fn insert_merchant(
+        &self,
+        user_id: &str,
+        merchant: &merchants::NewMerchant,
+        fee_bps: i32,
+        api_key: &str,
+    ) -> Result<merchants::Merchant, anyhow::Error> {
+        let merchant = sqlx::query_as::<_, merchants::Merchant>(
+            r#"
+                INSERT INTO merchants
+                (id, user_id, name, api_key_hash, payout_address, payout_asset, network, fee_bps)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().hyphenated().to_string())
+        .bind(user_id)
+        .bind(&merchant.name)
+        .bind(hash_api_key(api_key))
+        .bind(&merchant.payout_address)
+        .bind(&merchant.payout_asset)
+        .bind(&merchant.network)
+        .bind(fee_bps)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(merchant)
+    }
+
+    pub async fn get_merchant_by_api_key(
+        &self,
+        api_key: &str,
+    ) -> Result<Option<merchants::Merchant>, anyhow::Error> {
+        let merchant = sqlx::query_as::<_, merchants::Merchant>(
+            "SELECT * FROM merchants WHERE api_key_hash = $1",
+        )
+        .bind(hash_api_key(api_key))
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(merchant)
+    }
+
+    pub async fn insert_payment_link(
+        &self,
+        merchant_id: &str,
+        description: Option<&str>,
+        deposit: &Deposit,
+    ) -> Result<String, anyhow::Error> {
+        let token = Uuid::new_v4().simple().to_string();
+
+        sqlx::query(
+            r#"
+                INSERT INTO payment_links
+                (token, merchant_id, transaction_id, amount_in_cents, description, qr_copy_paste, qr_image_url)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(&token)
+        .bind(merchant_id)
+        .bind(&deposit.transaction_id)
+        .bind(deposit.amount_in_cents)
+        .bind(description)
+        .bind(&deposit.qr_copy_paste)
+        .bind(&deposit.qr_image_url)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(token)
+    }
+
+    pub async fn get_payment_link(
+        &self,
+        token: &str,
+    ) -> Result<Option<merchants::PaymentLink>, anyhow::Error> {
+        let link = sqlx::query_as::<_, merchants::PaymentLink>(&format!(
+            "{} WHERE l.token = $1",
+            PAYMENT_LINK_QUERY
+        ))
+        .bind(token)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(link)
+    }
+
+    pub async fn get_merchant_payment_links(
+        &self,
+        merchant_id: &str,
+    ) -> Result<Vec<merchants::PaymentLink>, anyhow::Error> {
+        let links = sqlx::query_as::<_, merchants::PaymentLink>(&format!(
+            "{} WHERE l.merchant_id = $1 ORDER BY l.created_at DESC",
+            PAYMENT_LINK_QUERY
+        ))
+        .bind(merchant_id)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(links)
+    }
+
+    /// Fee of the merchant a transaction was paid to, or None for consumer
+    /// transactions.
+    pub async fn get_fee_bps_for_transaction(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<i32>, anyhow::Error> {
+        let fee_bps: Option<i32> = sqlx::query_scalar(
+            r#"
+                SELECT m.fee_bps FROM payment_links l
+                JOIN merchants m ON m.id = l.merchant_id
+                WHERE l.transaction_id = $1
+            "#,
+        )
+        .bind(transaction_id)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(fee_bps)
+    }
+
+    /// Queues the invoice of the fee of a link payment. False for consumer
+    /// transactions, fee-free merchants and invoices already queued.
+    pub async fn create_fee_invoice(&self, transaction_id: &str) -> Result<bool, anyhow::Error> {
+        let created = sqlx::query(
+            r#"
+                INSERT INTO merchant_invoices
+                (transaction_id, merchant_id, amount_in_cents, fee_bps, fee_in_cents)
+                SELECT l.transaction_id, m.id, l.amount_in_cents, m.fee_bps,
+                    l.amount_in_cents::BIGINT * m.fee_bps / 10000
+                FROM payment_links l
+                JOIN merchants m ON m.id = l.merchant_id
+                WHERE l.transaction_id = $1 AND m.fee_bps > 0
+                ON CONFLICT (transaction_id) DO NOTHING
+            "#,
+        )
+        .bind(transaction_id)
+        .execute(&self.conn)
+        .await?
+        .rows_affected();
+
+        Ok(created > 0)
+    }
+
+    /// Takes up to `limit` invoices due for an attempt and pushes their next
+    /// attempt back, so other instances skip them while they are sent.
+    pub async fn claim_due_invoices(
+        &self,
+        limit: i64,
+        retry_base_secs: u64,
+    ) -> Result<Vec<merchants::FeeInvoice>, anyhow::Error> {
+        let invoices = sqlx::query_as::<_, merchants::FeeInvoice>(
+            r#"
+                UPDATE merchant_invoices
+                SET attempts = attempts + 1,
+                    next_attempt_at = CURRENT_TIMESTAMP
+                        + make_interval(secs => LEAST($2 * power(2, attempts), 86400)),
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE transaction_id IN (
+                    SELECT transaction_id FROM merchant_invoices
+                    WHERE status = 'pending' AND next_attempt_at <= CURRENT_TIMESTAMP
+                    ORDER BY next_attempt_at
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING *
+            "#,
+        )
+        .bind(limit)
+        .bind(retry_base_secs as f64)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(invoices)
+    }
+
+    pub async fn record_invoice_issued(
+        &self,
+        transaction_id: &str,
+        invoice_id: &str,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+                UPDATE merchant_invoices
+                SET status = 'issued', invoice_id = $2, last_error = NULL,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE transaction_id = $1
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(invoice_id)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records a failed attempt. True when it was the last one and the
+    /// invoice is now `failed`.
+    pub async fn record_invoice_failure(
+        &self,
+        transaction_id: &str,
+        error: &str,
+        max_attempts: i32,
+    ) -> Result<bool, anyhow::Error> {
+        let status: String = sqlx::query_scalar(
+            r#"
+                UPDATE merchant_invoices
+                SET status = CASE WHEN attempts >= $3 THEN 'failed' ELSE 'pending' END,
+                    last_error = $2, updated_at = CURRENT_TIMESTAMP
+                WHERE transaction_id = $1
+                RETURNING status
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(error)
+        .bind(max_attempts)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(status == "failed")
+    }
+
+    pub async fn get_merchant(
+        &self,
+        merchant_id: &str,
+    ) -> Result<Option<merchants::Merchant>, anyhow::Error> {
+        let merchant =
+            sqlx::query_as::<_, merchants::Merchant>("SELECT * FROM merchants WHERE id = $1")
+                .bind(merchant_id)
+                .fetch_optional(&self.conn)
+                .await?;
+
+        Ok(merchant)
+    }
+}
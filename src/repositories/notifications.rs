@@ -0,0 +1,124 @@
+use crate::settings::{Notifications, ReceiptProvider};
+
+use anyhow::bail;
+use serde_json::json;
+
+/// Delivers operator alerts to a chat webhook (Slack/Mattermost compatible
+/// `{"text": ...}` payload), user notices to the app's push relay and
+/// receipts to the e-mail and SMS providers. Messages are only logged when
+/// the respective webhook is not set.
+#[derive(Clone)]
+pub struct NotificationRepository {
+    webhook_url: String,
+    user_webhook_url: String,
+    email: ReceiptProvider,
+    sms: ReceiptProvider,
+    client: reqwest::Client,
+}
+
+impl NotificationRepository {
+    pub fn new(settings: Notifications) -> Self {
+        Self {
+            webhook_url: settings.webhook_url,
+            user_webhook_url: settings.user_webhook_url,
+            email: settings.email,
+            sms: settings.sms,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Whether receipts can be sent on `channel`.
+    pub fn has_provider(&self, channel: &str) -> bool {
+        match channel {
+            "email" => !self.email.url.is_empty(),
+            "sms" => !self.sms.url.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Hands a receipt to the provider of `channel` as `{from, to, subject, text}`.
+    pub async fn send_receipt(
+        &self,
+        channel: &str,
+        to: &str,
+        subject: &str,
+        text: &str,
+    ) -> Result<(), anyhow::Error> {
+        let provider = match channel {
+            "email" => &self.email,
+            "sms" => &self.sms,
+            _ => bail!("Unknown receipt channel {}", channel),
+        };
+        if provider.url.is_empty() {
+            bail!("No {} provider configured", channel);
+        }
+
+        let response = self
+            .client
+            .post(&provider.url)
+            .bearer_auth(&provider.api_key)
+            .json(&json!({
+                "from": provider.from,
+                "to": to,
+                "subject": subject,
+                "text": text,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            bail!("{} provider answered {}", channel, response.status());
+        }
+
+        Ok(())
+    }
+
+    pub async fn send_user_notice(
+        &self,
+        user_id: &str,
+        title: &str,
+        message: &str,
+        data: &serde_json::Value,
+    ) -> Result<(), anyhow::Error> {
+        if self.user_webhook_url.is_empty() {
+            return Ok(());
+        }
+
+        let response = self
+            .client
+            .post(&self.user_webhook_url)
+            .json(&json!({
+                "user_id": user_id,
+                "title": title,
+                "message": message,
+                "data": data,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            bail!("Push relay answered {}", response.status());
+        }
+
+        Ok(())
+    }
+
+    pub async fn send_alert(&self, title: &str, message: &str) -> Result<(), anyhow::Error> {
+        if self.webhook_url.is_empty() {
+            return Ok(());
+        }
+
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .json(&json!({ "text": format!("*{}*\n{}", title, message) }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            bail!("Alert webhook answered {}", response.status());
+        }
+
+        Ok(())
+    }
+}
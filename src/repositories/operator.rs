@@ -0,0 +1,167 @@
+use crate::models::operator::{MaintenanceWindow, NewMaintenanceWindow};
+use crate::repositories::audit::record_audit_event;
+
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct OperatorRepository {
+    conn: PgPool,
+}
+
+impl OperatorRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    pub async fn get_flag(&self, name: &str) -> Result<bool, anyhow::Error> {
+        Ok(self.find_flag(name).await?.unwrap_or(false))
+    }
+
+    /// The flag, or None if it was never set.
+    pub async fn find_flag(&self, name: &str) -> Result<Option<bool>, anyhow::Error> {
+        let enabled: Option<bool> =
+            sqlx::query_scalar("SELECT enabled FROM operator_flags WHERE name = $1")
+                .bind(name)
+                .fetch_optional(&self.conn)
+                .await?;
+
+        Ok(enabled)
+    }
+
+    /// Sets the flag and records who changed it in the audit log.
+    pub async fn set_flag(
+        &self,
+        name: &str,
+        enabled: bool,
+        updated_by: &str,
+        reason: Option<&str>,
+    ) -> Result<(), anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO operator_flags (name, enabled, updated_by)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (name) DO UPDATE
+            SET enabled = EXCLUDED.enabled, updated_by = EXCLUDED.updated_by, updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(name)
+        .bind(enabled)
+        .bind(updated_by)
+        .execute(&mut *tx)
+        .await?;
+
+        record_audit_event(
+            &mut *tx,
+            updated_by,
+            if enabled { "flag_enabled" } else { "flag_disabled" },
+            name,
+            json!({ "reason": reason }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Windows not cancelled that end after `since`, soonest first.
+    pub async fn get_maintenance_windows(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<MaintenanceWindow>, anyhow::Error> {
+        let windows = sqlx::query_as::<_, MaintenanceWindow>(
+            r#"
+            SELECT id, starts_at, ends_at, reason, scheduled_by FROM maintenance_windows
+            WHERE cancelled_at IS NULL AND ends_at > $1
+            ORDER BY starts_at
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(windows)
+    }
+
+    pub async fn schedule_maintenance(
+        &self,
+        window: &NewMaintenanceWindow,
+    ) -> Result<MaintenanceWindow, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let scheduled = sqlx::query_as::<_, MaintenanceWindow>(
+            r#"
+            INSERT INTO maintenance_windows (id, starts_at, ends_at, reason, scheduled_by)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, starts_at, ends_at, reason, scheduled_by
+            "#,
+        )
+        .bind(Uuid::new_v4().hyphenated().to_string())
+        .bind(window.starts_at)
+        .bind(window.ends_at)
+        .bind(&window.reason)
+        .bind(&window.scheduled_by)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        record_audit_event(
+            &mut *tx,
+            &window.scheduled_by,
+            "maintenance_scheduled",
+            &scheduled.id,
+            json!({
+                "starts_at": scheduled.starts_at,
+                "ends_at": scheduled.ends_at,
+                "reason": scheduled.reason,
+            }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(scheduled)
+    }
+
+    /// False when there is no window with this id left to cancel.
+    pub async fn cancel_maintenance(
+        &self,
+        id: &str,
+        cancelled_by: &str,
+        reason: Option<&str>,
+    ) -> Result<bool, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let cancelled = sqlx::query(
+            r#"
+            UPDATE maintenance_windows
+            SET cancelled_by = $2, cancelled_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND cancelled_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(cancelled_by)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+
+        if cancelled {
+            record_audit_event(
+                &mut *tx,
+                cancelled_by,
+                "maintenance_cancelled",
+                id,
+                json!({ "reason": reason }),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(cancelled)
+    }
+}
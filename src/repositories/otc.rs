@@ -0,0 +1,159 @@
+use crate::models::otc::{
+    OtcAcceptRequest, OtcAcceptance, OtcQuote, OtcQuoteRequest, OtcSwap, OtcSwapStatus,
+};
+
+use anyhow::bail;
+use serde::de::DeserializeOwned;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct OtcRepository {
+    conn: PgPool,
+    desk_url: String,
+    desk_api_key: String,
+    client: reqwest::Client,
+}
+
+impl OtcRepository {
+    pub fn new(conn: PgPool, desk_url: String, desk_api_key: String) -> Self {
+        Self {
+            conn,
+            desk_url,
+            desk_api_key,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn request_quote(
+        &self,
+        sell_asset: &str,
+        receive_asset: &str,
+        amount: i64,
+    ) -> Result<OtcQuote, anyhow::Error> {
+        let request = self
+            .client
+            .post(self.url("/quotes"))
+            .json(&OtcQuoteRequest {
+                sell_asset,
+                receive_asset,
+                amount,
+            });
+
+        self.send(request).await
+    }
+
+    pub async fn accept_quote(
+        &self,
+        quote_id: &str,
+        receive_address: &str,
+    ) -> Result<OtcAcceptance, anyhow::Error> {
+        let request = self
+            .client
+            .post(self.url(&format!("/quotes/{}/accept", quote_id)))
+            .json(&OtcAcceptRequest { receive_address });
+
+        self.send(request).await
+    }
+
+    pub async fn get_swap_status(&self, external_id: &str) -> Result<OtcSwapStatus, anyhow::Error> {
+        let request = self
+            .client
+            .get(self.url(&format!("/swaps/{}", external_id)));
+
+        self.send(request).await
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.desk_url.trim_end_matches('/'), path)
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T, anyhow::Error> {
+        let response = request.bearer_auth(&self.desk_api_key).send().await?;
+
+        if !response.status().is_success() {
+            bail!("OTC desk answered {}", response.status());
+        }
+
+        Ok(response.json::<T>().await?)
+    }
+
+    /// Returns the id the swap is known by to the rest of the dealer.
+    pub async fn insert_swap(
+        &self,
+        external_id: &str,
+        sell_asset: &str,
+        receive_asset: &str,
+        sold_amount: i64,
+        expected_amount: i64,
+    ) -> Result<i64, anyhow::Error> {
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO otc_swaps (external_id, sell_asset, receive_asset, sold_amount, expected_amount)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id
+            "#,
+        )
+        .bind(external_id)
+        .bind(sell_asset)
+        .bind(receive_asset)
+        .bind(sold_amount)
+        .bind(expected_amount)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn record_deposit(&self, id: i64, txid: &str) -> Result<(), anyhow::Error> {
+        sqlx::query("UPDATE otc_swaps SET deposit_txid = $2, status = 'deposited' WHERE id = $1")
+            .bind(id)
+            .bind(txid)
+            .execute(&self.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Closes a swap as completed or failed. Returns false when it was
+    /// already closed.
+    pub async fn close_swap(
+        &self,
+        id: i64,
+        status: &str,
+        settlement_txid: Option<&str>,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE otc_swaps
+            SET status = $2, settlement_txid = $3, closed_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND closed_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(status)
+        .bind(settlement_txid)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Swaps whose deposit was sent and that the desk has not settled yet.
+    pub async fn get_deposited_swaps(&self) -> Result<Vec<OtcSwap>, anyhow::Error> {
+        let swaps = sqlx::query_as::<_, OtcSwap>(
+            r#"
+            SELECT id, external_id, sell_asset, receive_asset, sold_amount, expected_amount
+            FROM otc_swaps
+            WHERE status = 'deposited'
+            ORDER BY id
+            "#,
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(swaps)
+    }
+}
@@ -0,0 +1,270 @@
+use crate::models::payouts;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// How long a claim on a payout lasts when its holder never releases it,
+/// e.g. because the process crashed.
+const CLAIM_LEASE_SECS: f64 = 900.0;
+
+#[derive(Clone)]
+pub struct PayoutRepository {
+    conn: PgPool,
+}
+
+/// Lease on the payout of a transaction, released when dropped. No
+/// connection is held while it lasts.
+pub struct PayoutClaim {
+    conn: PgPool,
+    transaction_id: String,
+    token: String,
+}
+
+impl Drop for PayoutClaim {
+    fn drop(&mut self) {
+        // Left to expire when there's no runtime to release it on
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let conn = self.conn.clone();
+        let transaction_id = std::mem::take(&mut self.transaction_id);
+        let token = std::mem::take(&mut self.token);
+
+        runtime.spawn(async move {
+            let released = sqlx::query(
+                "UPDATE payout_claims SET claim_token = NULL, claimed_until = NULL WHERE transaction_id = $1 AND claim_token = $2",
+            )
+            .bind(&transaction_id)
+            .bind(&token)
+            .execute(&conn)
+            .await;
+            if let Err(e) = released {
+                log::error!("Could not release payout claim of {}: {}", transaction_id, e);
+            }
+        });
+    }
+}
+
+impl PayoutRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Claims the payout of a transaction for the caller. None while someone
+    /// else holds an unexpired claim on it.
+    pub async fn claim(&self, transaction_id: &str) -> Result<Option<PayoutClaim>, anyhow::Error> {
+        let token = Uuid::new_v4().simple().to_string();
+        let claimed = sqlx::query_scalar::<_, String>(
+            r#"
+                INSERT INTO payout_claims (transaction_id, claim_token, claimed_until)
+                VALUES ($1, $2, CURRENT_TIMESTAMP + make_interval(secs => $3))
+                ON CONFLICT (transaction_id) DO UPDATE
+                SET claim_token = EXCLUDED.claim_token, claimed_until = EXCLUDED.claimed_until
+                WHERE payout_claims.claimed_until IS NULL
+                   OR payout_claims.claimed_until < CURRENT_TIMESTAMP
+                RETURNING transaction_id
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(&token)
+        .bind(CLAIM_LEASE_SECS)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(claimed.map(|transaction_id| PayoutClaim {
+            conn: self.conn.clone(),
+            transaction_id,
+            token,
+        }))
+    }
+
+    /// Records that the payout of a transaction is about to be broadcast.
+    pub async fn start_broadcast(&self, transaction_id: &str) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+                INSERT INTO payouts (transaction_id, status) VALUES ($1, 'broadcasting')
+                ON CONFLICT (transaction_id) DO UPDATE
+                SET txid = NULL, status = 'broadcasting', updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(transaction_id)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_txid(
+        &self,
+        transaction_id: &str,
+        txid: &str,
+        network_fee: u64,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "UPDATE payouts SET txid = $2, network_fee = $3, status = 'broadcast', updated_at = CURRENT_TIMESTAMP WHERE transaction_id = $1",
+        )
+        .bind(transaction_id)
+        .bind(txid)
+        .bind(network_fee as i64)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Forgets a broadcast a reviewer found did not go out, so the payout can
+    /// be retried.
+    pub async fn clear_broadcast(&self, transaction_id: &str) -> Result<(), anyhow::Error> {
+        sqlx::query("DELETE FROM payouts WHERE transaction_id = $1 AND txid IS NULL")
+            .bind(transaction_id)
+            .execute(&self.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_payout(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<payouts::Payout>, anyhow::Error> {
+        let payout = sqlx::query_as::<_, payouts::Payout>(
+            "SELECT * FROM payouts WHERE transaction_id = $1",
+        )
+        .bind(transaction_id)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(payout)
+    }
+
+    pub async fn record_swap(
+        &self,
+        transaction_id: &str,
+        quote_sub_id: i64,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "INSERT INTO payout_swaps (transaction_id, quote_sub_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        )
+        .bind(transaction_id)
+        .bind(quote_sub_id)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn complete_swap(&self, quote_sub_id: i64, txid: &str) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "UPDATE payout_swaps SET txid = $2, completed_at = CURRENT_TIMESTAMP WHERE quote_sub_id = $1 AND completed_at IS NULL",
+        )
+        .bind(quote_sub_id)
+        .bind(txid)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records a part of the payout about to be built and broadcast. None
+    /// when the transaction already has one.
+    pub async fn start_partial(
+        &self,
+        transaction_id: &str,
+        amount: u64,
+    ) -> Result<Option<i64>, anyhow::Error> {
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+                INSERT INTO partial_payouts (transaction_id, amount) VALUES ($1, $2)
+                ON CONFLICT (transaction_id) DO NOTHING
+                RETURNING id
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(amount as i64)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn record_partial_txid(
+        &self,
+        id: i64,
+        txid: &str,
+        network_fee: u64,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "UPDATE partial_payouts SET txid = $2, network_fee = $3, status = 'broadcast', updated_at = CURRENT_TIMESTAMP WHERE id = $1",
+        )
+        .bind(id)
+        .bind(txid)
+        .bind(network_fee as i64)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Forgets a part that failed before it was broadcast.
+    pub async fn clear_partial(&self, id: i64) -> Result<(), anyhow::Error> {
+        sqlx::query("DELETE FROM partial_payouts WHERE id = $1 AND txid IS NULL")
+            .bind(id)
+            .execute(&self.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_partial_payouts(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Vec<payouts::PartialPayout>, anyhow::Error> {
+        let partials = sqlx::query_as::<_, payouts::PartialPayout>(
+            "SELECT * FROM partial_payouts WHERE transaction_id = $1 ORDER BY id",
+        )
+        .bind(transaction_id)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(partials)
+    }
+
+    /// Base units sent in parts so far. Parts whose broadcast was interrupted
+    /// count as sent, so they are never paid twice.
+    pub async fn get_partial_total(&self, transaction_id: &str) -> Result<u64, anyhow::Error> {
+        let total = sqlx::query_scalar::<_, i64>(
+            "SELECT COALESCE(SUM(amount), 0)::BIGINT FROM partial_payouts WHERE transaction_id = $1",
+        )
+        .bind(transaction_id)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(total.max(0) as u64)
+    }
+
+    /// Cents of the other transactions whose payout, or a part of it,
+    /// started since `since`.
+    pub async fn get_paid_since(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+        excluding_transaction_id: &str,
+    ) -> Result<i64, anyhow::Error> {
+        let total = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COALESCE(SUM(amount_in_cents), 0)::BIGINT FROM all_transactions
+            WHERE id IN (
+                SELECT transaction_id FROM payouts WHERE created_at > $1
+                UNION
+                SELECT transaction_id FROM partial_payouts WHERE created_at > $1
+            )
+            AND id <> $2
+            "#,
+        )
+        .bind(since)
+        .bind(excluding_transaction_id)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(total)
+    }
+}
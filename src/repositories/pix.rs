@@ -1,17 +1,26 @@
+use crate::models::operator::{Cursor, Page, TransactionSearch};
 use crate::models::pix;
+use crate::settings::Eulen;
 use sqlx;
 use sqlx::PgPool;
 use uuid::Uuid;
 mod eulen;
 
+pub use eulen::EulenUnavailable;
+
 pub struct PixRepository {
     eulen_api: eulen::EulenApi,
     conn: PgPool,
 }
 
 impl PixRepository {
-    pub fn new(eulen_auth_token: String, eulen_url: String, conn: PgPool) -> Self {
-        let eulen_api = eulen::EulenApi::new(eulen_auth_token, eulen_url);
+    pub fn new(
+        eulen_auth_token: String,
+        eulen_url: String,
+        eulen_settings: Eulen,
+        conn: PgPool,
+    ) -> Self {
+        let eulen_api = eulen::EulenApi::new(eulen_auth_token, eulen_url, eulen_settings);
 
         PixRepository { eulen_api, conn }
     }
@@ -52,22 +61,98 @@ impl PixRepository {
         Ok(deposit)
     }
 
+    /// Moves the charge to the Eulen status unless it is already at or past
+    /// it, so replayed or late updates can't take a paid charge back.
     pub async fn update_eulen_deposit_status(
         &self,
         eulen_deposit_status: &pix::EulenDepositStatus,
-    ) -> Result<Option<String>, anyhow::Error> {
-        let transaction = sqlx::query_as!(
-            pix::PixTransaction,
-            "UPDATE pix_transactions SET status = $1, updated_at = CURRENT_TIMESTAMP WHERE eulen_id = $2 returning *",
-            eulen_deposit_status.status,
-            eulen_deposit_status.qr_id
+    ) -> Result<pix::DepositStatusUpdate, anyhow::Error> {
+        let current: Option<(String, String)> = sqlx::query_as(
+            "SELECT transaction_id, status FROM pix_transactions WHERE eulen_id = $1",
         )
+        .bind(&eulen_deposit_status.qr_id)
         .fetch_optional(&self.conn)
         .await?;
 
-        match transaction {
-            Some(transaction) => Ok(Some(transaction.transaction_id)),
-            None => Ok(None),
+        let Some((transaction_id, status)) = current else {
+            return Ok(pix::DepositStatusUpdate::Unmatched);
+        };
+        if pix::status_rank(&status) >= eulen_deposit_status.status_rank() {
+            return Ok(pix::DepositStatusUpdate::Stale(transaction_id));
+        }
+
+        // Only from the status read above, a concurrent update wins
+        let updated = sqlx::query(
+            "UPDATE pix_transactions SET status = $1, updated_at = CURRENT_TIMESTAMP WHERE eulen_id = $2 AND status = $3",
+        )
+        .bind(&eulen_deposit_status.status)
+        .bind(&eulen_deposit_status.qr_id)
+        .bind(&status)
+        .execute(&self.conn)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Ok(pix::DepositStatusUpdate::Stale(transaction_id));
         }
+
+        Ok(pix::DepositStatusUpdate::Applied(transaction_id))
+    }
+
+    /// Amount the PIX charge was created for.
+    pub async fn get_expected_amount(&self, eulen_id: &str) -> Result<Option<i32>, anyhow::Error> {
+        let amount: Option<i32> =
+            sqlx::query_scalar("SELECT amount_in_cents FROM pix_transactions WHERE eulen_id = $1")
+                .bind(eulen_id)
+                .fetch_optional(&self.conn)
+                .await?;
+
+        Ok(amount)
+    }
+
+    /// Page of the admin search of PIX charges, newest first, starting after
+    /// `after`, archived ones included. Asset and user are those of the
+    /// deposit the charge pays.
+    pub async fn search_pix_transactions(
+        &self,
+        search: &TransactionSearch,
+        after: Option<&Cursor>,
+        limit: i64,
+    ) -> Result<Page<pix::PixTransaction>, anyhow::Error> {
+        let pix_transactions = sqlx::query_as::<_, pix::PixTransaction>(
+            r#"
+            SELECT * FROM all_pix_transactions p
+            WHERE ($1::TEXT IS NULL OR p.status = $1)
+              AND (($2::TEXT IS NULL AND $3::TEXT IS NULL) OR EXISTS (
+                  SELECT 1 FROM all_transactions t
+                  WHERE t.id = p.transaction_id
+                    AND ($2::TEXT IS NULL OR t.asset = $2)
+                    AND ($3::TEXT IS NULL OR t.user_id = $3)
+              ))
+              AND ($4::INT IS NULL OR p.amount_in_cents >= $4)
+              AND ($5::INT IS NULL OR p.amount_in_cents <= $5)
+              AND ($6::TIMESTAMPTZ IS NULL OR p.created_at >= $6)
+              AND ($7::TIMESTAMPTZ IS NULL OR p.created_at < $7)
+              AND ($8::TIMESTAMPTZ IS NULL OR (p.created_at, p.id) < ($8, $9))
+            ORDER BY p.created_at DESC, p.id DESC
+            LIMIT $10
+            "#,
+        )
+        .bind(&search.status)
+        .bind(&search.asset)
+        .bind(&search.user_id)
+        .bind(search.min_amount_in_cents)
+        .bind(search.max_amount_in_cents)
+        .bind(search.since)
+        .bind(search.until)
+        .bind(after.map(|cursor| cursor.created_at))
+        .bind(after.map(|cursor| cursor.id.as_str()))
+        .bind(limit + 1)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(Page::from_rows(pix_transactions, limit, |pix_transaction| Cursor {
+            created_at: pix_transaction.created_at,
+            id: pix_transaction.id.clone(),
+        }))
     }
 }
@@ -1,21 +1,53 @@
 use crate::models::pix;
+use crate::settings;
+use crate::utils::latency::{self, Dependency};
+use crate::utils::metrics;
 use anyhow::bail;
 use reqwest;
+use reqwest::StatusCode;
 use serde_json::json;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+/// Returned without calling Eulen while the circuit breaker is open.
+#[derive(Debug, thiserror::Error)]
+#[error("Eulen: API unavailable, not sending requests for now")]
+pub struct EulenUnavailable;
+
+/// A failure worth retrying: the request may not have reached Eulen, or
+/// Eulen failed on its side.
+#[derive(Debug, thiserror::Error)]
+#[error("Eulen: {0}")]
+struct Transient(String);
+
+#[derive(Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
 pub struct EulenApi {
     auth_token: String,
     url: String,
     client: reqwest::Client,
+    settings: settings::Eulen,
+    breaker: Mutex<Breaker>,
 }
 
 impl EulenApi {
-    pub fn new(auth_token: String, url: String) -> Self {
+    pub fn new(auth_token: String, url: String, settings: settings::Eulen) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(settings.timeout_secs))
+            .build()
+            .expect("Could not build Eulen HTTP client.");
+
         Self {
             auth_token,
             url,
-            client: reqwest::Client::new(),
+            client,
+            settings,
+            breaker: Mutex::new(Breaker::default()),
         }
     }
 
@@ -24,20 +56,38 @@ impl EulenApi {
         amount_in_cents: i32,
         address: &String,
     ) -> Result<pix::EulenDeposit, anyhow::Error> {
+        self.check_breaker()?;
+
+        // The nonce is kept across retries so Eulen deduplicates the charge
         let uuid = Uuid::new_v4().hyphenated().to_string();
         let payload = json!({
             "amountInCents": amount_in_cents,
             "depixAddress": address
         });
 
-        let payload_json = self
-            .client
-            .post(format!("{}/api/deposit", self.url))
-            .bearer_auth(&self.auth_token)
-            .header("X-Nonce", uuid)
-            .json(&payload);
-
-        let response = payload_json.send().await?.text().await?;
+        let mut attempt = 0;
+        let response = loop {
+            match latency::track(Dependency::Eulen, self.try_deposit(&uuid, &payload)).await {
+                Ok(response) => break response,
+                Err(e) if attempt < self.settings.max_retries => {
+                    let delay = self.backoff(attempt);
+                    log::warn!(
+                        "{} (attempt {}), retrying in {}ms",
+                        e,
+                        attempt + 1,
+                        delay.as_millis()
+                    );
+                    metrics::increment("eulen_retries_total", &[]);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    self.record_failure();
+                    return Err(e.into());
+                }
+            }
+        };
+        self.record_success();
 
         let response_json: serde_json::Value = serde_json::from_str(&response)?;
         match response_json.get("response") {
@@ -50,4 +100,76 @@ impl EulenApi {
             }
         }
     }
+
+    /// Sends the deposit request once. Client errors are returned as the
+    /// response body, since retrying them would not help.
+    async fn try_deposit(
+        &self,
+        nonce: &str,
+        payload: &serde_json::Value,
+    ) -> Result<String, Transient> {
+        let response = self
+            .client
+            .post(format!("{}/api/deposit", self.url))
+            .bearer_auth(&self.auth_token)
+            .header("X-Nonce", nonce)
+            .json(payload)
+            .send()
+            .await
+            .map_err(|e| Transient(e.to_string()))?;
+
+        let status = response.status();
+        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(Transient(format!("deposit failed with status {}", status)));
+        }
+
+        response.text().await.map_err(|e| Transient(e.to_string()))
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .settings
+            .retry_base_ms
+            .saturating_mul(1 << attempt.min(16));
+        let jitter = (Uuid::new_v4().as_u128() % (delay as u128 / 2 + 1)) as u64;
+
+        Duration::from_millis(delay / 2 + jitter)
+    }
+
+    /// Fails fast while the breaker is open. Once the cooldown is over
+    /// requests go through again, and a single failure reopens it.
+    fn check_breaker(&self) -> Result<(), EulenUnavailable> {
+        let breaker = self.breaker.lock().unwrap();
+        match breaker.open_until {
+            Some(open_until) if Instant::now() < open_until => Err(EulenUnavailable),
+            _ => Ok(()),
+        }
+    }
+
+    fn record_failure(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.consecutive_failures += 1;
+
+        if breaker.consecutive_failures >= self.settings.breaker_threshold {
+            if breaker.open_until.is_none() {
+                log::error!(
+                    "Eulen failed {} requests in a row, pausing requests for {}s",
+                    breaker.consecutive_failures,
+                    self.settings.breaker_cooldown_secs
+                );
+            }
+            breaker.open_until =
+                Some(Instant::now() + Duration::from_secs(self.settings.breaker_cooldown_secs));
+            metrics::set_gauge("eulen_circuit_open", &[], 1.0);
+        }
+    }
+
+    fn record_success(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        if breaker.open_until.is_some() {
+            log::info!("Eulen is responding again, resuming requests");
+        }
+        *breaker = Breaker::default();
+        metrics::set_gauge("eulen_circuit_open", &[], 0.0);
+    }
 }
@@ -1,32 +1,54 @@
+use chrono::{DateTime, Utc};
 use serde_json;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::models::price::{AssetPrice, PriceAggregation, QuoteCurrency};
 use crate::models::transactions::Assets;
+use crate::utils::latency::{self, Dependency};
 
-#[derive(Clone)]
+mod aggregation;
+
+use aggregation::Quote;
+
+/// Prices are refetched this often, and are valid until the next fetch.
+pub const FETCH_INTERVAL_SECS: i64 = 60;
+
+#[derive(Clone, Default)]
 struct PriceCache {
     bitcoin: Option<f64>,
     usdt: Option<f64>,
+    updated_at: Option<DateTime<Utc>>,
 }
 
+type Prices = HashMap<QuoteCurrency, PriceCache>;
+
+/// Quotes of a single provider.
+#[derive(Default)]
+struct ProviderQuotes {
+    bitcoin: Option<Quote>,
+    usdt: Option<Quote>,
+}
+
+type ProviderPrices = HashMap<QuoteCurrency, ProviderQuotes>;
+
 #[derive(Clone)]
 pub struct PriceRepository {
     binance_url: String,
     coingecko_url: String,
-    price_cache: Arc<RwLock<PriceCache>>,
+    aggregation: PriceAggregation,
+    price_cache: Arc<RwLock<Prices>>,
 }
 
 impl PriceRepository {
-    pub fn new(binance_url: String, coingecko_url: String) -> Self {
-        let price_cache = Arc::new(RwLock::new(PriceCache {
-            bitcoin: None,
-            usdt: None,
-        }));
+    pub fn new(binance_url: String, coingecko_url: String, aggregation: PriceAggregation) -> Self {
+        let price_cache = Arc::new(RwLock::new(HashMap::new()));
 
         Self {
             binance_url,
             coingecko_url,
+            aggregation,
             price_cache,
         }
     }
@@ -34,83 +56,115 @@ impl PriceRepository {
     pub async fn get_asset_price_with_spread(
         &self,
         asset: Assets,
-    ) -> Result<Option<f64>, anyhow::Error> {
+        currency: QuoteCurrency,
+    ) -> Result<Option<AssetPrice>, anyhow::Error> {
+        let prices = self.get_price_cache().await?;
+        let quote = prices.get(&currency).cloned().unwrap_or_default();
+        let validity = chrono::Duration::seconds(FETCH_INTERVAL_SECS);
+
+        // DEPIX is pegged to BRL, other currencies go through the USDT cross rate
+        if asset.hex() == Assets::DEPIX.hex() && currency == QuoteCurrency::Brl {
+            let now = Utc::now();
+            return Ok(Some(AssetPrice {
+                price: 1.0,
+                aggregation: None,
+                updated_at: now,
+                expires_at: now + validity,
+            }));
+        }
+
+        let Some(updated_at) = quote.updated_at else {
+            return Ok(None);
+        };
+        let aggregated = |price| AssetPrice {
+            price,
+            aggregation: Some(self.aggregation),
+            updated_at,
+            expires_at: updated_at + validity,
+        };
+
         if asset.hex() == Assets::DEPIX.hex() {
-            return Ok(Some(1.0));
+            let brl_usdt = prices.get(&QuoteCurrency::Brl).and_then(|p| p.usdt);
+            return match (quote.usdt, brl_usdt) {
+                (Some(usdt), Some(brl_usdt)) if brl_usdt > 0.0 => {
+                    Ok(Some(aggregated(usdt / brl_usdt)))
+                }
+                _ => Ok(None),
+            };
         }
 
-        let prices = self.get_price_cache().await?;
         let price = match asset {
-            Assets::LBTC => Ok(prices.bitcoin),
-            Assets::USDT => Ok(prices.usdt),
+            Assets::LBTC => Ok(quote.bitcoin),
+            Assets::USDT => Ok(quote.usdt),
             _ => Err(anyhow::anyhow!("Unsupported asset")),
         };
 
-        match price {
-            Ok(Some(price)) => Ok(Some(price * 1.02)),
-            Ok(None) => Err(anyhow::anyhow!("Price not found")),
-            Err(e) => Err(e),
-        }
+        Ok(price?.map(|price| aggregated(price * 1.02)))
     }
 
-    async fn get_price_cache(&self) -> Result<PriceCache, anyhow::Error> {
+    async fn get_price_cache(&self) -> Result<Prices, anyhow::Error> {
         let cache = self.price_cache.read().await;
         Ok(cache.clone())
     }
 
-    pub async fn start_price_fetch_task(&self) {
-        let repository = self.clone();
-
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+    pub async fn fetch_best_prices(&self) -> Result<(), anyhow::Error> {
+        let (coingecko, binance) = tokio::join!(
+            latency::track(
+                Dependency::PriceProviders,
+                self.fetch_prices_from_coingecko()
+            ),
+            latency::track(Dependency::PriceProviders, self.fetch_prices_from_binance())
+        );
 
-            loop {
-                interval.tick().await;
-
-                match repository.fetch_best_prices().await {
-                    Ok(()) => {
-                        log::info!("Fetched prices.")
-                    }
-                    Err(e) => {
-                        log::error!("Error updating price cache: {}", e);
-                    }
+        let providers: Vec<ProviderPrices> = [("Coingecko", coingecko), ("Binance", binance)]
+            .into_iter()
+            .filter_map(|(provider, prices)| match prices {
+                Ok(prices) => Some(prices),
+                Err(e) => {
+                    log::warn!("Could not fetch prices from {}: {}", provider, e);
+                    None
                 }
-            }
-        });
-
-        log::info!("Price fetch task started");
-    }
-
-    async fn fetch_best_prices(&self) -> Result<(), anyhow::Error> {
-        let coingecko_prices = self.fetch_prices_from_coingecko().await?;
-        //let binance_prices = self.fetch_prices_from_binance().await?;
+            })
+            .collect();
+        if providers.is_empty() {
+            anyhow::bail!("No price provider responded");
+        }
 
-        /*
-        let bitcoin = match (coingecko_prices.bitcoin, binance_prices.bitcoin) {
-            (Some(cg), Some(bn)) => Some(cg.max(bn)),
-            (Some(cg), None) => Some(cg),
-            (None, Some(bn)) => Some(bn),
-            (None, None) => None,
-        };
+        let updated_at = Some(Utc::now());
+        let prices = QuoteCurrency::ALL
+            .into_iter()
+            .map(|currency| {
+                let quotes = |asset: fn(&ProviderQuotes) -> Option<Quote>| -> Vec<Quote> {
+                    providers
+                        .iter()
+                        .filter_map(|prices| prices.get(&currency).and_then(asset))
+                        .collect()
+                };
+                let bitcoin = aggregation::aggregate(self.aggregation, &quotes(|p| p.bitcoin));
+                let usdt = aggregation::aggregate(self.aggregation, &quotes(|p| p.usdt));
 
-        let usdt = match (coingecko_prices.usdt, binance_prices.usdt) {
-            (Some(cg), Some(bn)) => Some(cg.max(bn)),
-            (Some(cg), None) => Some(cg),
-            (None, Some(bn)) => Some(bn),
-            (None, None) => None,
-        };
-        */
+                (
+                    currency,
+                    PriceCache {
+                        bitcoin,
+                        usdt,
+                        updated_at,
+                    },
+                )
+            })
+            .collect();
 
         let mut cache = self.price_cache.write().await;
-        *cache = PriceCache { bitcoin: coingecko_prices.bitcoin, usdt: coingecko_prices.usdt };
+        *cache = prices;
 
         Ok(())
     }
 
-    async fn fetch_prices_from_coingecko(&self) -> Result<PriceCache, anyhow::Error> {
+    async fn fetch_prices_from_coingecko(&self) -> Result<ProviderPrices, anyhow::Error> {
+        let vs_currencies = QuoteCurrency::ALL.map(|c| c.code()).join(",");
         let prices: serde_json::Value = reqwest::get(format!(
-            "{}/api/v3/simple/price?ids=bitcoin,tether&vs_currencies=brl",
-            self.coingecko_url
+            "{}/api/v3/simple/price?ids=bitcoin,tether&vs_currencies={}&include_24hr_vol=true",
+            self.coingecko_url, vs_currencies
         ))
         .await?
         .json()
@@ -118,32 +172,88 @@ impl PriceRepository {
 
         log::info!("Fetched prices from Coingecko: {:?}", prices);
 
-        let bitcoin = prices["bitcoin"]["brl"].as_f64().map(|v| v);
-        let usdt = prices["tether"]["brl"].as_f64().map(|v| v);
+        let quote = |id: &str, currency: QuoteCurrency| {
+            let price = prices[id][currency.code()].as_f64()?;
+            let volume = prices[id][format!("{}_24h_vol", currency.code())]
+                .as_f64()
+                .unwrap_or(0.0);
+
+            Some(Quote { price, volume })
+        };
+
+        let prices = QuoteCurrency::ALL
+            .into_iter()
+            .map(|currency| {
+                let bitcoin = quote("bitcoin", currency);
+                let usdt = quote("tether", currency);
+
+                (currency, ProviderQuotes { bitcoin, usdt })
+            })
+            .collect();
 
-        Ok(PriceCache { bitcoin, usdt })
+        Ok(prices)
     }
 
-    async fn fetch_prices_from_binance(&self) -> Result<PriceCache, anyhow::Error> {
-        let prices: Vec<serde_json::Value> = reqwest::get(format!(
-            "{}/api/v3/ticker/price?symbols=[\"BTCBRL\",\"USDTBRL\"]",
-            self.binance_url
+    /// Binance tickers for bitcoin and tether in `currency`, and whether the
+    /// tether ticker is quoted the other way around. There are no USD pairs.
+    fn binance_symbols(currency: QuoteCurrency) -> Option<(&'static str, &'static str, bool)> {
+        match currency {
+            QuoteCurrency::Brl => Some(("BTCBRL", "USDTBRL", false)),
+            QuoteCurrency::Eur => Some(("BTCEUR", "EURUSDT", true)),
+            QuoteCurrency::Usd => None,
+        }
+    }
+
+    async fn fetch_prices_from_binance(&self) -> Result<ProviderPrices, anyhow::Error> {
+        let symbols: Vec<String> = QuoteCurrency::ALL
+            .into_iter()
+            .filter_map(Self::binance_symbols)
+            .flat_map(|(bitcoin, usdt, _)| [format!("\"{}\"", bitcoin), format!("\"{}\"", usdt)])
+            .collect();
+
+        let tickers: Vec<serde_json::Value> = reqwest::get(format!(
+            "{}/api/v3/ticker/24hr?symbols=[{}]",
+            self.binance_url,
+            symbols.join(",")
         ))
         .await?
         .json()
         .await?;
 
-        log::info!("Fetched prices from Binance: {:?}", prices);
+        log::info!("Fetched prices from Binance: {:?}", tickers);
+
+        let field = |ticker: &serde_json::Value, name: &str| {
+            ticker[name].as_str().and_then(|v| v.parse::<f64>().ok())
+        };
+        // Volumes are taken in the fiat side of the pair
+        let quote = |symbol: &str, inverted: bool| {
+            let ticker = tickers.iter().find(|t| t["symbol"] == symbol)?;
+            let price = field(ticker, "lastPrice")?;
+
+            match inverted {
+                false => Some(Quote {
+                    price,
+                    volume: field(ticker, "quoteVolume").unwrap_or(0.0),
+                }),
+                true if price > 0.0 => Some(Quote {
+                    price: 1.0 / price,
+                    volume: field(ticker, "volume").unwrap_or(0.0),
+                }),
+                true => None,
+            }
+        };
+
+        let prices = QuoteCurrency::ALL
+            .into_iter()
+            .filter_map(|currency| {
+                let (bitcoin, usdt, inverted) = Self::binance_symbols(currency)?;
+                let bitcoin = quote(bitcoin, false);
+                let usdt = quote(usdt, inverted);
 
-        let bitcoin = prices
-            .iter()
-            .find(|p| p["symbol"] == "BTCBRL")
-            .map(|p| p["price"].as_str().unwrap().parse::<f64>().unwrap());
-        let usdt = prices
-            .iter()
-            .find(|p| p["symbol"] == "USDTBRL")
-            .map(|p| p["price"].as_str().unwrap().parse::<f64>().unwrap());
+                Some((currency, ProviderQuotes { bitcoin, usdt }))
+            })
+            .collect();
 
-        Ok(PriceCache { bitcoin, usdt })
+        Ok(prices)
     }
 }
@@ -0,0 +1,190 @@
+//! Combines the quotes of several price providers into a single price, so
+//! one stale or broken source does not move the price on its own.
+
+use crate::models::price::PriceAggregation;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quote {
+    pub price: f64,
+    /// 24h traded volume, in the quote currency.
+    pub volume: f64,
+}
+
+/// Aggregated price of the valid quotes, none when there are none.
+pub fn aggregate(strategy: PriceAggregation, quotes: &[Quote]) -> Option<f64> {
+    let mut quotes: Vec<Quote> = quotes
+        .iter()
+        .copied()
+        .filter(|q| q.price.is_finite() && q.price > 0.0)
+        .collect();
+    if quotes.is_empty() {
+        return None;
+    }
+    quotes.sort_by(|a, b| a.price.total_cmp(&b.price));
+
+    let price = match strategy {
+        PriceAggregation::Median => median(&quotes),
+        PriceAggregation::VolumeWeighted => weighted_median(&quotes),
+        PriceAggregation::TrimmedMean => trimmed_mean(&quotes),
+    };
+
+    Some(price)
+}
+
+fn median(sorted: &[Quote]) -> f64 {
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1].price + sorted[mid].price) / 2.0
+    } else {
+        sorted[mid].price
+    }
+}
+
+fn volume(quote: &Quote) -> f64 {
+    if quote.volume.is_finite() && quote.volume > 0.0 {
+        quote.volume
+    } else {
+        0.0
+    }
+}
+
+/// Price below which half of the volume trades. Providers without a volume
+/// count for nothing, and without any volume this is the plain median.
+fn weighted_median(sorted: &[Quote]) -> f64 {
+    let total: f64 = sorted.iter().map(volume).sum();
+    if total <= 0.0 {
+        return median(sorted);
+    }
+
+    let mut cumulative = 0.0;
+    for (i, quote) in sorted.iter().enumerate() {
+        cumulative += volume(quote);
+        if cumulative * 2.0 > total {
+            return quote.price;
+        }
+        // Exactly half the volume on each side, like an even median
+        if cumulative * 2.0 == total {
+            if let Some(next) = sorted[i + 1..].iter().find(|q| volume(q) > 0.0) {
+                return (quote.price + next.price) / 2.0;
+            }
+            return quote.price;
+        }
+    }
+
+    sorted[sorted.len() - 1].price
+}
+
+/// Drops the highest and the lowest quote when there are at least three.
+fn trimmed_mean(sorted: &[Quote]) -> f64 {
+    let kept = if sorted.len() >= 3 {
+        &sorted[1..sorted.len() - 1]
+    } else {
+        sorted
+    };
+
+    kept.iter().map(|q| q.price).sum::<f64>() / kept.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quotes(prices: &[(f64, f64)]) -> Vec<Quote> {
+        prices
+            .iter()
+            .map(|&(price, volume)| Quote { price, volume })
+            .collect()
+    }
+
+    #[test]
+    fn no_valid_quote_has_no_price() {
+        for strategy in [
+            PriceAggregation::Median,
+            PriceAggregation::VolumeWeighted,
+            PriceAggregation::TrimmedMean,
+        ] {
+            assert_eq!(aggregate(strategy, &[]), None);
+            assert_eq!(
+                aggregate(
+                    strategy,
+                    &quotes(&[(0.0, 1.0), (f64::NAN, 1.0), (-5.0, 1.0)])
+                ),
+                None
+            );
+            assert_eq!(aggregate(strategy, &quotes(&[(5.0, 1.0)])), Some(5.0));
+        }
+    }
+
+    #[test]
+    fn median_ignores_a_single_outlier() {
+        let prices = quotes(&[(101.0, 1.0), (1_000.0, 1.0), (100.0, 1.0)]);
+        assert_eq!(aggregate(PriceAggregation::Median, &prices), Some(101.0));
+    }
+
+    #[test]
+    fn median_of_an_even_count_is_the_middle_mean() {
+        let prices = quotes(&[(100.0, 1.0), (104.0, 1.0)]);
+        assert_eq!(aggregate(PriceAggregation::Median, &prices), Some(102.0));
+
+        let prices = quotes(&[(100.0, 1.0), (90.0, 1.0), (104.0, 1.0), (500.0, 1.0)]);
+        assert_eq!(aggregate(PriceAggregation::Median, &prices), Some(102.0));
+    }
+
+    #[test]
+    fn volume_weighted_follows_the_volume() {
+        let prices = quotes(&[(100.0, 10.0), (104.0, 1.0), (90.0, 1.0)]);
+        assert_eq!(
+            aggregate(PriceAggregation::VolumeWeighted, &prices),
+            Some(100.0)
+        );
+
+        let prices = quotes(&[(100.0, 1.0), (104.0, 10.0), (90.0, 1.0)]);
+        assert_eq!(
+            aggregate(PriceAggregation::VolumeWeighted, &prices),
+            Some(104.0)
+        );
+    }
+
+    #[test]
+    fn volume_weighted_splits_an_even_volume() {
+        let prices = quotes(&[(100.0, 5.0), (104.0, 5.0)]);
+        assert_eq!(
+            aggregate(PriceAggregation::VolumeWeighted, &prices),
+            Some(102.0)
+        );
+    }
+
+    #[test]
+    fn volume_weighted_without_volume_is_the_median() {
+        let prices = quotes(&[(100.0, 0.0), (104.0, f64::NAN), (1_000.0, -1.0)]);
+        assert_eq!(
+            aggregate(PriceAggregation::VolumeWeighted, &prices),
+            Some(104.0)
+        );
+
+        // A provider without volume does not count
+        let prices = quotes(&[(100.0, 0.0), (104.0, 3.0)]);
+        assert_eq!(
+            aggregate(PriceAggregation::VolumeWeighted, &prices),
+            Some(104.0)
+        );
+    }
+
+    #[test]
+    fn trimmed_mean_drops_the_extremes() {
+        let prices = quotes(&[(90.0, 1.0), (1_000.0, 1.0), (100.0, 1.0), (104.0, 1.0)]);
+        assert_eq!(
+            aggregate(PriceAggregation::TrimmedMean, &prices),
+            Some(102.0)
+        );
+    }
+
+    #[test]
+    fn trimmed_mean_of_two_quotes_is_their_mean() {
+        let prices = quotes(&[(100.0, 1.0), (104.0, 1.0)]);
+        assert_eq!(
+            aggregate(PriceAggregation::TrimmedMean, &prices),
+            Some(102.0)
+        );
+    }
+}
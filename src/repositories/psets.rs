@@ -0,0 +1,41 @@
+use crate::models::wallet::PsetBalance;
+
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct PsetRepository {
+    conn: PgPool,
+}
+
+impl PsetRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Records a PSET refused before signing. `reference` is what it was
+    /// built for, the quote id of a swap or the transaction id of a payout.
+    pub async fn insert_rejected(
+        &self,
+        kind: &str,
+        reference: &str,
+        pset: &str,
+        reason: &str,
+        balance: &PsetBalance,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+                INSERT INTO rejected_psets (kind, reference, pset, reason, balances)
+                VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(kind)
+        .bind(reference)
+        .bind(pset)
+        .bind(reason)
+        .bind(serde_json::to_string(&balance.balances)?)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use crate::models::quotes::PriceQuote;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct QuoteRepository {
+    conn: PgPool,
+}
+
+impl QuoteRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    pub async fn insert_quote(
+        &self,
+        asset: &str,
+        quote_currency: &str,
+        price_in_cents: i64,
+        aggregation: Option<&str>,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<PriceQuote, anyhow::Error> {
+        let quote = sqlx::query_as::<_, PriceQuote>(
+            r#"
+            INSERT INTO price_quotes (id, asset, quote_currency, price_in_cents, aggregation, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().hyphenated().to_string())
+        .bind(asset)
+        .bind(quote_currency)
+        .bind(price_in_cents)
+        .bind(aggregation)
+        .bind(expires_at)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(quote)
+    }
+
+    /// The quote, unless a transaction already used it.
+    pub async fn get_unused_quote(&self, id: &str) -> Result<Option<PriceQuote>, anyhow::Error> {
+        let quote = sqlx::query_as::<_, PriceQuote>(
+            r#"
+            SELECT q.* FROM price_quotes q
+            WHERE q.id = $1
+              AND NOT EXISTS (SELECT 1 FROM transaction_quotes t WHERE t.quote_id = q.id)
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(quote)
+    }
+
+    /// Links the quote to the transaction. False when another transaction
+    /// took it first.
+    pub async fn attach_to_transaction(
+        &self,
+        transaction_id: &str,
+        quote_id: &str,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(
+            "INSERT INTO transaction_quotes (transaction_id, quote_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        )
+        .bind(transaction_id)
+        .bind(quote_id)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn get_transaction_quote(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<PriceQuote>, anyhow::Error> {
+        let quote = sqlx::query_as::<_, PriceQuote>(
+            r#"
+            SELECT q.* FROM price_quotes q
+            JOIN transaction_quotes t ON t.quote_id = q.id
+            WHERE t.transaction_id = $1
+            "#,
+        )
+        .bind(transaction_id)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(quote)
+    }
+}
@@ -0,0 +1,87 @@
+use crate::models::receipts::ReceiptDetails;
+
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct ReceiptRepository {
+    conn: PgPool,
+}
+
+impl ReceiptRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Details of a broadcast payout, None until its txid is recorded.
+    pub async fn get_receipt_details(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<ReceiptDetails>, anyhow::Error> {
+        let details = sqlx::query_as::<_, ReceiptDetails>(
+            r#"
+                SELECT t.id AS transaction_id, t.user_id, t.amount_in_cents, t.asset, t.fee_collected,
+                       p.txid, n.email, n.phone,
+                       COALESCE(n.email_receipts, FALSE) AS email_receipts,
+                       COALESCE(n.sms_receipts, FALSE) AS sms_receipts
+                FROM all_transactions t
+                JOIN payouts p ON p.transaction_id = t.id AND p.txid IS NOT NULL
+                LEFT JOIN notification_preferences n ON n.user_id = t.user_id
+                WHERE t.id = $1
+            "#,
+        )
+        .bind(transaction_id)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(details)
+    }
+
+    /// Records a receipt about to be sent. False when the payout was already
+    /// receipted on that channel.
+    pub async fn claim_receipt(
+        &self,
+        details: &ReceiptDetails,
+        channel: &str,
+        recipient: &str,
+    ) -> Result<bool, anyhow::Error> {
+        let claimed = sqlx::query(
+            r#"
+                INSERT INTO receipts (transaction_id, user_id, channel, recipient)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (transaction_id, channel) DO NOTHING
+            "#,
+        )
+        .bind(&details.transaction_id)
+        .bind(&details.user_id)
+        .bind(channel)
+        .bind(recipient)
+        .execute(&self.conn)
+        .await?
+        .rows_affected();
+
+        Ok(claimed > 0)
+    }
+
+    pub async fn record_delivery(
+        &self,
+        transaction_id: &str,
+        channel: &str,
+        error: Option<String>,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+                UPDATE receipts
+                SET status = CASE WHEN $3::TEXT IS NULL THEN 'sent' ELSE 'failed' END,
+                    error = $3, updated_at = CURRENT_TIMESTAMP
+                WHERE transaction_id = $1 AND channel = $2
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(channel)
+        .bind(error)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+}
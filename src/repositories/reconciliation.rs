@@ -0,0 +1,147 @@
+use crate::models::pix::EulenDepositStatus;
+use crate::models::reconciliation::DepixSettlement;
+
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct ReconciliationRepository {
+    conn: PgPool,
+}
+
+impl ReconciliationRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Records the DEPIX Eulen reported sending for a charge. A repeated
+    /// webhook keeps the first report.
+    pub async fn record_settlement(
+        &self,
+        transaction_id: &str,
+        status: &EulenDepositStatus,
+    ) -> Result<(), anyhow::Error> {
+        let blockchain_tx_id = Some(status.blockchain_tx_id.trim()).filter(|id| !id.is_empty());
+
+        sqlx::query(
+            r#"
+                INSERT INTO depix_settlements
+                (eulen_id, transaction_id, bank_tx_id, blockchain_tx_id, value_in_cents)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (eulen_id) DO NOTHING
+            "#,
+        )
+        .bind(&status.qr_id)
+        .bind(transaction_id)
+        .bind(&status.bank_tx_id)
+        .bind(blockchain_tx_id)
+        .bind(status.value_in_cents)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Settlements still waiting for their DEPIX, oldest first. Missing ones
+    /// keep being looked for until `lookback_days` passed.
+    pub async fn get_open_settlements(
+        &self,
+        lookback_days: i32,
+    ) -> Result<Vec<DepixSettlement>, anyhow::Error> {
+        let settlements = sqlx::query_as::<_, DepixSettlement>(
+            r#"
+                SELECT * FROM depix_settlements
+                WHERE status IN ('unmatched', 'missing')
+                  AND reported_at >= CURRENT_TIMESTAMP - make_interval(days => $1)
+                ORDER BY reported_at, eulen_id
+            "#,
+        )
+        .bind(lookback_days)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(settlements)
+    }
+
+    /// Wallet transactions already matched to a settlement.
+    pub async fn get_matched_txids(&self) -> Result<Vec<String>, anyhow::Error> {
+        let txids = sqlx::query_scalar(
+            "SELECT matched_txid FROM depix_settlements WHERE matched_txid IS NOT NULL",
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(txids)
+    }
+
+    pub async fn record_match(
+        &self,
+        eulen_id: &str,
+        status: &str,
+        txid: &str,
+        matched_by: &str,
+        received_amount: i64,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+                UPDATE depix_settlements
+                SET status = $2, matched_txid = $3, matched_by = $4, received_amount = $5,
+                    checked_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+                WHERE eulen_id = $1
+            "#,
+        )
+        .bind(eulen_id)
+        .bind(status)
+        .bind(txid)
+        .bind(matched_by)
+        .bind(received_amount)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Settlements checked without a match, `missing` once past the grace
+    /// period.
+    pub async fn record_unmatched(
+        &self,
+        eulen_id: &str,
+        status: &str,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+                UPDATE depix_settlements
+                SET status = $2, checked_at = CURRENT_TIMESTAMP,
+                    updated_at = CASE WHEN status = $2 THEN updated_at ELSE CURRENT_TIMESTAMP END
+                WHERE eulen_id = $1
+            "#,
+        )
+        .bind(eulen_id)
+        .bind(status)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Settlements in `statuses`, newest first.
+    pub async fn get_settlements(
+        &self,
+        statuses: &[String],
+        limit: i64,
+    ) -> Result<Vec<DepixSettlement>, anyhow::Error> {
+        let settlements = sqlx::query_as::<_, DepixSettlement>(
+            r#"
+                SELECT * FROM depix_settlements
+                WHERE status = ANY($1)
+                ORDER BY reported_at DESC, eulen_id
+                LIMIT $2
+            "#,
+        )
+        .bind(statuses)
+        .bind(limit)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(settlements)
+    }
+}
@@ -0,0 +1,237 @@
+use crate::models::referrals::{ReferralBonus, ReferralRollup};
+
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+fn hash_api_key(api_key: &str) -> String {
+    format!("{:x}", Sha256::digest(api_key.trim().as_bytes()))
+}
+
+#[derive(Clone)]
+pub struct ReferralRepository {
+    conn: PgPool,
+}
+
+impl ReferralRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Records the bonus a payout of the user pays their referrer, replacing
+    /// the one of an earlier attempt at it. Nothing when the user wasn't
+    /// referred.
+    pub async fn record_bonus(
+        &self,
+        transaction_id: &str,
+        user_id: &str,
+        asset: &str,
+        amount: u64,
+        amount_in_cents: i64,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+                INSERT INTO referral_bonuses
+                    (transaction_id, referrer_id, referred_user_id, asset, amount, amount_in_cents)
+                SELECT $1, referred_by, id, $3, $4, $5 FROM users
+                WHERE id = $2 AND referred_by IS NOT NULL
+                ON CONFLICT (transaction_id) DO UPDATE
+                SET asset = EXCLUDED.asset, amount = EXCLUDED.amount,
+                    amount_in_cents = EXCLUDED.amount_in_cents
+                WHERE referral_bonuses.status = 'earned'
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(user_id)
+        .bind(asset)
+        .bind(amount as i64)
+        .bind(amount_in_cents)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Drops the bonus of an earlier attempt at a payout that no longer pays
+    /// one, such as a bonus that became dust.
+    pub async fn discard_bonus(&self, transaction_id: &str) -> Result<(), anyhow::Error> {
+        sqlx::query("DELETE FROM referral_bonuses WHERE transaction_id = $1 AND status = 'earned'")
+            .bind(transaction_id)
+            .execute(&self.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Bonus recorded for the payout of a transaction, in base units.
+    pub async fn get_bonus_amount(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<u64>, anyhow::Error> {
+        let amount: Option<i64> =
+            sqlx::query_scalar("SELECT amount FROM referral_bonuses WHERE transaction_id = $1")
+                .bind(transaction_id)
+                .fetch_optional(&self.conn)
+                .await?;
+
+        Ok(amount.map(|amount| amount.max(0) as u64))
+    }
+
+    pub async fn mark_paid(&self, transaction_id: &str, txid: &str) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "UPDATE referral_bonuses SET status = 'paid', txid = $2, paid_at = NOW() WHERE transaction_id = $1 AND status = 'earned'",
+        )
+        .bind(transaction_id)
+        .bind(txid)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Rebuilds the rollups of `since` and the months after it from the
+    /// bonuses. Returns the number of rollups written.
+    pub async fn rebuild_rollups(&self, since: chrono::NaiveDate) -> Result<u64, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        sqlx::query("DELETE FROM referral_rollups WHERE month >= $1")
+            .bind(since)
+            .execute(&mut *tx)
+            .await?;
+
+        let written = sqlx::query(
+            r#"
+            WITH earned AS (
+                SELECT referrer_id, DATE_TRUNC('month', earned_at)::DATE AS month, asset,
+                       COUNT(*)::INTEGER AS earned_count, SUM(amount)::BIGINT AS earned,
+                       SUM(amount_in_cents)::BIGINT AS earned_in_cents
+                FROM referral_bonuses
+                WHERE earned_at >= $1
+                GROUP BY 1, 2, 3
+            ),
+            paid AS (
+                SELECT referrer_id, DATE_TRUNC('month', paid_at)::DATE AS month, asset,
+                       COUNT(*)::INTEGER AS paid_count, SUM(amount)::BIGINT AS paid,
+                       SUM(amount_in_cents)::BIGINT AS paid_in_cents
+                FROM referral_bonuses
+                WHERE status = 'paid' AND paid_at >= $1
+                GROUP BY 1, 2, 3
+            )
+            INSERT INTO referral_rollups
+                (referrer_id, month, asset, earned_count, earned, earned_in_cents,
+                 paid_count, paid, paid_in_cents)
+            SELECT referrer_id, month, asset,
+                   COALESCE(e.earned_count, 0), COALESCE(e.earned, 0), COALESCE(e.earned_in_cents, 0),
+                   COALESCE(p.paid_count, 0), COALESCE(p.paid, 0), COALESCE(p.paid_in_cents, 0)
+            FROM earned e
+            FULL JOIN paid p USING (referrer_id, month, asset)
+            "#,
+        )
+        .bind(since)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+
+        Ok(written)
+    }
+
+    /// Rollups of a month, of one referrer or of all of them.
+    pub async fn get_rollups(
+        &self,
+        month: chrono::NaiveDate,
+        referrer_id: Option<&str>,
+    ) -> Result<Vec<ReferralRollup>, anyhow::Error> {
+        let rollups = sqlx::query_as::<_, ReferralRollup>(
+            r#"
+                SELECT * FROM referral_rollups
+                WHERE month = $1 AND ($2::TEXT IS NULL OR referrer_id = $2)
+                ORDER BY referrer_id, asset
+            "#,
+        )
+        .bind(month)
+        .bind(referrer_id)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(rollups)
+    }
+
+    /// Bonuses a referrer earned in `[since, until)`.
+    pub async fn get_bonuses(
+        &self,
+        referrer_id: &str,
+        since: chrono::DateTime<chrono::Utc>,
+        until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<ReferralBonus>, anyhow::Error> {
+        let bonuses = sqlx::query_as::<_, ReferralBonus>(
+            r#"
+                SELECT * FROM referral_bonuses
+                WHERE referrer_id = $1 AND earned_at >= $2 AND earned_at < $3
+                ORDER BY earned_at, transaction_id
+            "#,
+        )
+        .bind(referrer_id)
+        .bind(since)
+        .bind(until)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(bonuses)
+    }
+
+    /// Bonuses paid to a referrer, newest first.
+    pub async fn get_payments(
+        &self,
+        referrer_id: &str,
+        limit: i64,
+    ) -> Result<Vec<ReferralBonus>, anyhow::Error> {
+        let payments = sqlx::query_as::<_, ReferralBonus>(
+            r#"
+                SELECT * FROM referral_bonuses
+                WHERE referrer_id = $1 AND status = 'paid'
+                ORDER BY paid_at DESC, transaction_id
+                LIMIT $2
+            "#,
+        )
+        .bind(referrer_id)
+        .bind(limit)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(payments)
+    }
+
+    /// Stores the key of a referrer, replacing the one they had. False when
+    /// the user has no referral code.
+    pub async fn set_api_key(&self, user_id: &str, api_key: &str) -> Result<bool, anyhow::Error> {
+        let stored = sqlx::query(
+            r#"
+                INSERT INTO referrer_api_keys (user_id, api_key_hash)
+                SELECT user_id, $2 FROM referrals WHERE user_id = $1
+                ON CONFLICT (user_id) DO UPDATE
+                SET api_key_hash = EXCLUDED.api_key_hash, created_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(hash_api_key(api_key))
+        .execute(&self.conn)
+        .await?
+        .rows_affected();
+
+        Ok(stored > 0)
+    }
+
+    pub async fn get_referrer_by_api_key(
+        &self,
+        api_key: &str,
+    ) -> Result<Option<String>, anyhow::Error> {
+        let user_id: Option<String> =
+            sqlx::query_scalar("SELECT user_id FROM referrer_api_keys WHERE api_key_hash = $1")
+                .bind(hash_api_key(api_key))
+                .fetch_optional(&self.conn)
+                .await?;
+
+        Ok(user_id)
+    }
+}
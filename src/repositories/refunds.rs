@@ -0,0 +1,119 @@
+use crate::models::pix::EulenDepositStatus;
+use crate::models::refunds::{PixRefund, RefundDecision};
+use crate::repositories::audit::record_audit_event;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct RefundRepository {
+    conn: PgPool,
+}
+
+impl RefundRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Records what the payer sent above the charge as due. Returns None when
+    /// the payment was already recorded, as for a repeated webhook.
+    pub async fn record_overpayment(
+        &self,
+        transaction_id: &str,
+        status: &EulenDepositStatus,
+        charged_in_cents: i32,
+    ) -> Result<Option<PixRefund>, anyhow::Error> {
+        let refund = sqlx::query_as::<_, PixRefund>(
+            r#"
+                INSERT INTO pix_refunds
+                (id, transaction_id, eulen_id, bank_tx_id, payer_name, payer_tax_number,
+                 charged_in_cents, paid_in_cents, amount_in_cents)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8 - $7)
+                ON CONFLICT (bank_tx_id) DO NOTHING
+                RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().hyphenated().to_string())
+        .bind(transaction_id)
+        .bind(&status.qr_id)
+        .bind(&status.bank_tx_id)
+        .bind(&status.payer_name)
+        .bind(&status.payer_tax_number)
+        .bind(charged_in_cents)
+        .bind(status.value_in_cents)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(refund)
+    }
+
+    /// Refunds in `statuses`, newest first.
+    pub async fn get_refunds(
+        &self,
+        statuses: &[String],
+        limit: i64,
+    ) -> Result<Vec<PixRefund>, anyhow::Error> {
+        let refunds = sqlx::query_as::<_, PixRefund>(
+            r#"
+                SELECT * FROM pix_refunds
+                WHERE status = ANY($1)
+                ORDER BY created_at DESC
+                LIMIT $2
+            "#,
+        )
+        .bind(statuses)
+        .bind(limit)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(refunds)
+    }
+
+    /// Closes a due refund. Returns None when there is no due refund with
+    /// this id.
+    pub async fn resolve_refund(
+        &self,
+        id: &str,
+        decision: RefundDecision,
+        decided_by: &str,
+        note: Option<&str>,
+    ) -> Result<Option<PixRefund>, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let refund = sqlx::query_as::<_, PixRefund>(
+            r#"
+                UPDATE pix_refunds
+                SET status = $2, resolved_by = $3, note = $4, resolved_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND status = 'due'
+                RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(decision.as_str())
+        .bind(decided_by)
+        .bind(note)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(refund) = refund else {
+            return Ok(None);
+        };
+
+        record_audit_event(
+            &mut *tx,
+            decided_by,
+            &format!("pix_refund_{}", decision.as_str()),
+            &refund.transaction_id,
+            serde_json::json!({
+                "refund_id": refund.id,
+                "amount_in_cents": refund.amount_in_cents,
+                "note": note,
+            }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(refund))
+    }
+}
@@ -0,0 +1,148 @@
+use crate::models::reports::TaxableSale;
+use crate::models::reserves::{Obligation, ReserveProof};
+
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct ReportRepository {
+    conn: PgPool,
+}
+
+impl ReportRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Deposits whose payout was broadcast in `[since, until)`, in that order.
+    pub async fn get_taxable_sales(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+        until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<TaxableSale>, anyhow::Error> {
+        let sales = sqlx::query_as::<_, TaxableSale>(
+            r#"
+                SELECT t.id AS transaction_id, p.updated_at AS paid_at, t.amount_in_cents, t.asset,
+                       t.fee_collected, tp.price_in_cents,
+                       ARRAY(
+                           SELECT eulen_id FROM all_pix_transactions
+                           WHERE transaction_id = t.id
+                       ) AS eulen_ids
+                FROM payouts p
+                JOIN all_transactions t ON t.id = p.transaction_id
+                LEFT JOIN transaction_prices tp ON tp.transaction_id = t.id
+                WHERE p.txid IS NOT NULL AND p.updated_at >= $1 AND p.updated_at < $2
+                ORDER BY p.updated_at, t.id
+            "#,
+        )
+        .bind(since)
+        .bind(until)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(sales)
+    }
+
+    /// Payloads of the Eulen webhooks about any of `eulen_ids`.
+    pub async fn get_webhook_payloads(
+        &self,
+        eulen_ids: &[String],
+    ) -> Result<Vec<String>, anyhow::Error> {
+        let payloads = sqlx::query_scalar(
+            "SELECT payload FROM webhook_events WHERE qr_ids && $1 ORDER BY received_at",
+        )
+        .bind(eulen_ids)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(payloads)
+    }
+
+    pub async fn get_report(
+        &self,
+        layout: &str,
+        month: chrono::NaiveDate,
+    ) -> Result<Option<String>, anyhow::Error> {
+        let content =
+            sqlx::query_scalar("SELECT content FROM tax_reports WHERE layout = $1 AND month = $2")
+                .bind(layout)
+                .bind(month)
+                .fetch_optional(&self.conn)
+                .await?;
+
+        Ok(content)
+    }
+
+    pub async fn save_report(
+        &self,
+        layout: &str,
+        month: chrono::NaiveDate,
+        content: &str,
+        operations: usize,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+                INSERT INTO tax_reports (layout, month, content, operations)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (layout, month) DO UPDATE
+                SET content = EXCLUDED.content, operations = EXCLUDED.operations,
+                    generated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(layout)
+        .bind(month)
+        .bind(content)
+        .bind(i32::try_from(operations)?)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Deposits paid by PIX that were not paid out, by status and asset.
+    pub async fn get_obligations(&self) -> Result<Vec<Obligation>, anyhow::Error> {
+        let obligations = sqlx::query_as::<_, Obligation>(
+            r#"
+                SELECT t.status, t.asset, COUNT(*) AS transactions,
+                       SUM(t.amount_in_cents)::BIGINT AS amount_in_cents
+                FROM transactions t
+                WHERE t.status IN ('eulen_depix_sent', 'scheduled', 'pricing_unavailable', 'held', 'refund_requested')
+                  AND EXISTS (
+                      SELECT 1 FROM pix_transactions p
+                      WHERE p.transaction_id = t.id AND p.status = 'depix_sent'
+                  )
+                GROUP BY t.status, t.asset
+                ORDER BY t.status, t.asset
+            "#,
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(obligations)
+    }
+
+    pub async fn save_proof(
+        &self,
+        hash: &str,
+        content: &str,
+    ) -> Result<ReserveProof, anyhow::Error> {
+        let proof = sqlx::query_as::<_, ReserveProof>(
+            "INSERT INTO reserve_proofs (hash, content) VALUES ($1, $2) RETURNING *",
+        )
+        .bind(hash)
+        .bind(content)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(proof)
+    }
+
+    pub async fn get_proof(&self, hash: &str) -> Result<Option<ReserveProof>, anyhow::Error> {
+        let proof =
+            sqlx::query_as::<_, ReserveProof>("SELECT * FROM reserve_proofs WHERE hash = $1")
+                .bind(hash)
+                .fetch_optional(&self.conn)
+                .await?;
+
+        Ok(proof)
+    }
+}
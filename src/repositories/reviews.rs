@@ -0,0 +1,169 @@
+use crate::models::reviews::{HeldTransaction, Review, ReviewDecision};
+use crate::models::transactions::Transaction;
+use crate::repositories::audit::record_audit_event;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct ReviewRepository {
+    conn: PgPool,
+}
+
+impl ReviewRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Moves the transaction to `held` and opens a review for it. Returns
+    /// None when the transaction already has a pending review.
+    pub async fn hold(
+        &self,
+        transaction_id: &str,
+        source: &str,
+        reason: &str,
+    ) -> Result<Option<Review>, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let review = sqlx::query_as::<_, Review>(
+            r#"
+                INSERT INTO review_queue (id, transaction_id, source, reason)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT DO NOTHING
+                RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().hyphenated().to_string())
+        .bind(transaction_id)
+        .bind(source)
+        .bind(reason)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE transactions SET status = 'held', updated_at = CURRENT_TIMESTAMP WHERE id = $1",
+        )
+        .bind(transaction_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if review.is_some() {
+            record_audit_event(
+                &mut *tx,
+                source,
+                "transaction_held",
+                transaction_id,
+                serde_json::json!({ "reason": reason }),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(review)
+    }
+
+    pub async fn get_held_transactions(&self) -> Result<Vec<HeldTransaction>, anyhow::Error> {
+        let reviews = sqlx::query_as::<_, Review>(
+            "SELECT * FROM review_queue WHERE status = 'pending' ORDER BY created_at",
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        let transaction_ids: Vec<String> = reviews
+            .iter()
+            .map(|review| review.transaction_id.clone())
+            .collect();
+        let mut transactions =
+            sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = ANY($1)")
+                .bind(&transaction_ids)
+                .fetch_all(&self.conn)
+                .await?;
+
+        Ok(reviews
+            .into_iter()
+            .filter_map(|review| {
+                let index = transactions
+                    .iter()
+                    .position(|transaction| transaction.id == review.transaction_id)?;
+                Some(HeldTransaction {
+                    review,
+                    transaction: transactions.swap_remove(index),
+                })
+            })
+            .collect())
+    }
+
+    /// Closes the pending review of a transaction. Rejected transactions move
+    /// to `refund_requested`; approved ones keep `held` until the payout resumes.
+    pub async fn resolve(
+        &self,
+        transaction_id: &str,
+        decision: ReviewDecision,
+        decided_by: &str,
+        note: Option<&str>,
+    ) -> Result<Option<Review>, anyhow::Error> {
+        let status = match decision {
+            ReviewDecision::Approve => "approved",
+            ReviewDecision::Reject => "rejected",
+        };
+
+        let mut tx = self.conn.begin().await?;
+
+        let review = sqlx::query_as::<_, Review>(
+            r#"
+                UPDATE review_queue
+                SET status = $2, decided_by = $3, note = $4, decided_at = CURRENT_TIMESTAMP
+                WHERE transaction_id = $1 AND status = 'pending'
+                RETURNING *
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(status)
+        .bind(decided_by)
+        .bind(note)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(review) = review else {
+            return Ok(None);
+        };
+
+        if decision == ReviewDecision::Reject {
+            sqlx::query(
+                "UPDATE transactions SET status = 'refund_requested', updated_at = CURRENT_TIMESTAMP WHERE id = $1",
+            )
+            .bind(transaction_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        record_audit_event(
+            &mut *tx,
+            decided_by,
+            &format!("review_{}", status),
+            transaction_id,
+            serde_json::json!({
+                "review_id": review.id,
+                "source": review.source,
+                "note": note,
+            }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(review))
+    }
+
+    pub async fn is_approved(&self, transaction_id: &str) -> Result<bool, anyhow::Error> {
+        let approved: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM review_queue WHERE transaction_id = $1 AND status = 'approved')",
+        )
+        .bind(transaction_id)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(approved)
+    }
+}
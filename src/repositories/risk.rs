@@ -0,0 +1,112 @@
+use crate::models::risk::{RiskBand, RiskScore, RiskSignals};
+use crate::utils::clock::SharedClock;
+
+use anyhow::anyhow;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct RiskRepository {
+    conn: PgPool,
+    clock: SharedClock,
+}
+
+#[derive(sqlx::FromRow)]
+struct RiskScoreRow {
+    user_id: String,
+    score: i32,
+    band: String,
+    factors: String,
+    computed_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl RiskRepository {
+    pub fn new(conn: PgPool, clock: SharedClock) -> Self {
+        Self { conn, clock }
+    }
+
+    /// Signals of a user as they are now. None when there's no such user.
+    pub async fn get_signals(&self, user_id: &str) -> Result<Option<RiskSignals>, anyhow::Error> {
+        let user: Option<(chrono::DateTime<chrono::Utc>, bool)> =
+            sqlx::query_as("SELECT created_at, verified FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_optional(&self.conn)
+                .await?;
+        let Some((created_at, verified)) = user else {
+            return Ok(None);
+        };
+
+        let total_volume_cents: i64 = sqlx::query_scalar(
+            r#"SELECT COALESCE(SUM(amount_in_cents), 0)::BIGINT FROM user_daily_spending WHERE user_id = $1 AND status = 'eulen_depix_sent'"#,
+        )
+        .bind(user_id)
+        .fetch_one(&self.conn)
+        .await?;
+
+        let failed_payments: i64 = sqlx::query_scalar(
+            r#"SELECT COUNT(*) FROM all_transactions WHERE user_id = $1 AND status IN ('eulen_error', 'eulen_canceled', 'refund_requested')"#,
+        )
+        .bind(user_id)
+        .fetch_one(&self.conn)
+        .await?;
+
+        let shared_device_accounts: i64 = sqlx::query_scalar(
+            r#"
+                SELECT COUNT(DISTINCT linked.user_id) FROM user_devices own
+                JOIN user_devices linked ON linked.device_fingerprint = own.device_fingerprint
+                WHERE own.user_id = $1 AND linked.user_id <> $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(Some(RiskSignals {
+            account_age_days: (self.clock.now() - created_at).num_days(),
+            verified,
+            total_volume_cents,
+            failed_payments,
+            shared_device_accounts,
+        }))
+    }
+
+    pub async fn save_score(&self, score: &RiskScore) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+                INSERT INTO user_risk_scores (user_id, score, band, factors, computed_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (user_id) DO UPDATE
+                SET score = EXCLUDED.score, band = EXCLUDED.band, factors = EXCLUDED.factors,
+                    computed_at = EXCLUDED.computed_at
+            "#,
+        )
+        .bind(&score.user_id)
+        .bind(score.score)
+        .bind(score.band.as_str())
+        .bind(serde_json::to_string(&score.factors)?)
+        .bind(score.computed_at)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_score(&self, user_id: &str) -> Result<Option<RiskScore>, anyhow::Error> {
+        let row =
+            sqlx::query_as::<_, RiskScoreRow>("SELECT * FROM user_risk_scores WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_optional(&self.conn)
+                .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        Ok(Some(RiskScore {
+            user_id: row.user_id,
+            score: row.score,
+            band: RiskBand::parse(&row.band)
+                .ok_or_else(|| anyhow!("Unknown risk band {}", row.band))?,
+            factors: serde_json::from_str(&row.factors)?,
+            computed_at: row.computed_at,
+        }))
+    }
+}
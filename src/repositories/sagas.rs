@@ -0,0 +1,64 @@
+use crate::models::sagas::{PayoutStep, SagaStep, StepStatus};
+
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct SagaRepository {
+    conn: PgPool,
+}
+
+impl SagaRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    pub async fn record_step(
+        &self,
+        transaction_id: &str,
+        step: PayoutStep,
+        status: StepStatus,
+        details: serde_json::Value,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "INSERT INTO payout_saga_steps (transaction_id, step, status, details) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(transaction_id)
+        .bind(step.as_str())
+        .bind(status.as_str())
+        .bind(details.to_string())
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Every step recorded for the payout, oldest first.
+    pub async fn get_steps(&self, transaction_id: &str) -> Result<Vec<SagaStep>, anyhow::Error> {
+        let steps = sqlx::query_as::<_, SagaStep>(
+            "SELECT * FROM payout_saga_steps WHERE transaction_id = $1 ORDER BY id",
+        )
+        .bind(transaction_id)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(steps)
+    }
+
+    /// Steps of the latest payout attempt, oldest first.
+    pub async fn get_latest_attempt(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Vec<SagaStep>, anyhow::Error> {
+        let mut steps = self.get_steps(transaction_id).await?;
+
+        let started_at = steps.iter().rposition(|step| {
+            step.step == PayoutStep::PriceLocked.as_str()
+                && step.status == StepStatus::Completed.as_str()
+        });
+        if let Some(started_at) = started_at {
+            steps.drain(..started_at);
+        }
+
+        Ok(steps)
+    }
+}
@@ -0,0 +1,148 @@
+use crate::models::{pix::Deposit, schedules};
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct ScheduleRepository {
+    conn: PgPool,
+}
+
+impl ScheduleRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    pub async fn insert_schedule(
+        &self,
+        schedule: &schedules::NewSchedule,
+    ) -> Result<schedules::Schedule, anyhow::Error> {
+        let schedule = sqlx::query_as::<_, schedules::Schedule>(
+            r#"
+                INSERT INTO schedules
+                (id, user_id, address, asset, network, amount_in_cents, frequency, next_run_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().hyphenated().to_string())
+        .bind(&schedule.user_id)
+        .bind(&schedule.address)
+        .bind(&schedule.asset)
+        .bind(&schedule.network)
+        .bind(schedule.amount_in_cents)
+        .bind(&schedule.frequency)
+        .bind(schedule.start_at.unwrap_or_else(chrono::Utc::now))
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    pub async fn get_user_schedules(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<schedules::Schedule>, anyhow::Error> {
+        let schedules = sqlx::query_as::<_, schedules::Schedule>(
+            "SELECT * FROM schedules WHERE user_id = $1 AND status <> 'cancelled' ORDER BY created_at",
+        )
+        .bind(user_id)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(schedules)
+    }
+
+    /// Changes the status of a schedule owned by the user. Cancelled
+    /// schedules are final.
+    pub async fn set_status(
+        &self,
+        schedule_id: &str,
+        user_id: &str,
+        status: &str,
+    ) -> Result<Option<schedules::Schedule>, anyhow::Error> {
+        let schedule = sqlx::query_as::<_, schedules::Schedule>(
+            r#"
+                UPDATE schedules SET status = $3, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND user_id = $2 AND status <> 'cancelled'
+                RETURNING *
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(user_id)
+        .bind(status)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    pub async fn get_due_schedules(&self) -> Result<Vec<schedules::Schedule>, anyhow::Error> {
+        let schedules = sqlx::query_as::<_, schedules::Schedule>(
+            "SELECT * FROM schedules WHERE status = 'active' AND next_run_at <= CURRENT_TIMESTAMP ORDER BY next_run_at",
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(schedules)
+    }
+
+    /// Moves next_run_at forward only if nobody else did it first, so a run
+    /// is never executed twice.
+    pub async fn claim_run(
+        &self,
+        schedule: &schedules::Schedule,
+        next_run_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool, anyhow::Error> {
+        let claimed = sqlx::query(
+            r#"
+                UPDATE schedules SET next_run_at = $3, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND next_run_at = $2 AND status = 'active'
+            "#,
+        )
+        .bind(&schedule.id)
+        .bind(schedule.next_run_at)
+        .bind(next_run_at)
+        .execute(&self.conn)
+        .await?
+        .rows_affected();
+
+        Ok(claimed == 1)
+    }
+
+    pub async fn record_run(
+        &self,
+        schedule_id: &str,
+        result: &Result<Deposit, String>,
+    ) -> Result<(), anyhow::Error> {
+        match result {
+            Ok(deposit) => {
+                sqlx::query(
+                    r#"
+                        UPDATE schedules
+                        SET last_transaction_id = $2, last_qr_copy_paste = $3, last_qr_image_url = $4,
+                            last_error = NULL, updated_at = CURRENT_TIMESTAMP
+                        WHERE id = $1
+                    "#,
+                )
+                .bind(schedule_id)
+                .bind(&deposit.transaction_id)
+                .bind(&deposit.qr_copy_paste)
+                .bind(&deposit.qr_image_url)
+                .execute(&self.conn)
+                .await?;
+            }
+            Err(error) => {
+                sqlx::query(
+                    "UPDATE schedules SET last_error = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $1",
+                )
+                .bind(schedule_id)
+                .bind(error)
+                .execute(&self.conn)
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
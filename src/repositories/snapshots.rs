@@ -0,0 +1,192 @@
+use crate::models::snapshots::{DealerSnapshot, SnapshotBundle};
+
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::{anyhow, bail};
+use argon2::{Algorithm, Argon2, Params, Version};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+const SNAPSHOT_VERSION: u32 = 1;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+/// Memory Argon2id uses to derive the key from the passphrase, in KiB. With
+/// `KDF_ITERATIONS` passes this is the OWASP recommended minimum.
+const KDF_MEMORY_KIB: u32 = 19 * 1024;
+const KDF_ITERATIONS: u32 = 2;
+
+/// Stores encrypted snapshot bundles either on a local/mounted directory
+/// (`file://` or plain path) or on an object storage bucket accepting
+/// authenticated `PUT`/`GET` requests (`http://`, `https://`).
+pub struct SnapshotRepository {
+    storage_url: String,
+    storage_token: Option<String>,
+    encryption_key: String,
+    client: reqwest::Client,
+}
+
+impl SnapshotRepository {
+    pub fn new(storage_url: String, storage_token: Option<String>, encryption_key: String) -> Self {
+        Self {
+            storage_url,
+            storage_token,
+            encryption_key,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn seal(&self, snapshot: DealerSnapshot) -> Result<(SnapshotBundle, Vec<u8>), anyhow::Error> {
+        let checksum = checksum(&snapshot)?;
+        let bundle = SnapshotBundle {
+            version: SNAPSHOT_VERSION,
+            checksum,
+            snapshot,
+        };
+
+        let plaintext = serde_json::to_vec(&bundle)?;
+        let sealed = encrypt(&self.encryption_key, &plaintext)?;
+
+        Ok((bundle, sealed))
+    }
+
+    pub fn open(&self, data: &[u8]) -> Result<SnapshotBundle, anyhow::Error> {
+        open_bundle(&self.encryption_key, data)
+    }
+
+    pub async fn store(&self, name: &str, data: Vec<u8>) -> Result<(), anyhow::Error> {
+        if self.is_remote() {
+            let mut request = self
+                .client
+                .put(format!("{}/{}", self.storage_url.trim_end_matches('/'), name))
+                .body(data);
+            if let Some(token) = &self.storage_token {
+                request = request.bearer_auth(token);
+            }
+
+            request.send().await?.error_for_status()?;
+        } else {
+            let dir = self.local_dir();
+            tokio::fs::create_dir_all(dir).await?;
+            tokio::fs::write(Path::new(dir).join(name), data).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn load(&self, name: &str) -> Result<Vec<u8>, anyhow::Error> {
+        if name.contains('/') || name.contains("..") {
+            bail!("Invalid snapshot name: {}", name);
+        }
+
+        if self.is_remote() {
+            let mut request = self
+                .client
+                .get(format!("{}/{}", self.storage_url.trim_end_matches('/'), name));
+            if let Some(token) = &self.storage_token {
+                request = request.bearer_auth(token);
+            }
+
+            let data = request.send().await?.error_for_status()?.bytes().await?;
+            Ok(data.to_vec())
+        } else {
+            Ok(tokio::fs::read(Path::new(self.local_dir()).join(name)).await?)
+        }
+    }
+
+    fn is_remote(&self) -> bool {
+        self.storage_url.starts_with("http://") || self.storage_url.starts_with("https://")
+    }
+
+    fn local_dir(&self) -> &str {
+        self.storage_url
+            .strip_prefix("file://")
+            .unwrap_or(self.storage_url.as_str())
+    }
+}
+
+/// Decrypts a bundle and checks its checksum. Used both by the restore path
+/// and by the offline `verify-snapshot` command.
+pub fn open_bundle(encryption_key: &str, data: &[u8]) -> Result<SnapshotBundle, anyhow::Error> {
+    let plaintext = decrypt(encryption_key, data)?;
+    let bundle: SnapshotBundle = serde_json::from_slice(&plaintext)?;
+
+    if bundle.version != SNAPSHOT_VERSION {
+        bail!("Unsupported snapshot version: {}", bundle.version);
+    }
+
+    if checksum(&bundle.snapshot)? != bundle.checksum {
+        bail!("Snapshot checksum mismatch");
+    }
+
+    Ok(bundle)
+}
+
+fn checksum(snapshot: &DealerSnapshot) -> Result<String, anyhow::Error> {
+    let serialized = serde_json::to_vec(snapshot)?;
+    Ok(format!("{:x}", Sha256::digest(&serialized)))
+}
+
+/// Cipher keyed with Argon2id of the passphrase and the snapshot's salt, so
+/// a stolen snapshot can't be brute forced cheaply.
+fn cipher(encryption_key: &str, salt: &[u8]) -> Result<Aes256Gcm, anyhow::Error> {
+    if encryption_key.is_empty() {
+        bail!("Snapshot encryption key is not configured");
+    }
+
+    let params = Params::new(KDF_MEMORY_KIB, KDF_ITERATIONS, 1, Some(32))
+        .map_err(|e| anyhow!("Invalid snapshot key parameters: {}", e))?;
+    let mut key = Key::<Aes256Gcm>::default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(encryption_key.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Could not derive the snapshot key: {}", e))?;
+    Ok(Aes256Gcm::new(&key))
+}
+
+/// Sealed snapshots are the salt, the nonce and the ciphertext, in order.
+fn encrypt(encryption_key: &str, plaintext: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    let mut salt = [0u8; SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = cipher(encryption_key, &salt)?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow!("Could not encrypt snapshot"))?;
+
+    let mut sealed = salt.to_vec();
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+
+    Ok(sealed)
+}
+
+fn decrypt(encryption_key: &str, data: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    if data.len() <= SALT_LENGTH + NONCE_LENGTH {
+        bail!("Snapshot is too short");
+    }
+
+    let (salt, data) = data.split_at(SALT_LENGTH);
+    let cipher = cipher(encryption_key, salt)?;
+    let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Could not decrypt snapshot. Wrong key or corrupted data?"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_snapshot_is_sealed_with_its_own_salt() {
+        let first = encrypt("passphrase", b"state").unwrap();
+        let second = encrypt("passphrase", b"state").unwrap();
+
+        assert_ne!(first[..SALT_LENGTH], second[..SALT_LENGTH]);
+        assert_eq!(decrypt("passphrase", &first).unwrap(), b"state");
+        assert_eq!(decrypt("passphrase", &second).unwrap(), b"state");
+        assert!(decrypt("other passphrase", &first).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct SpendingRepository {
+    conn: PgPool,
+}
+
+impl SpendingRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    /// Recomputes `user_daily_spending` from the transactions created on or
+    /// after `since`, the whole history when none, and fixes the rows that
+    /// drifted. Writes to transactions wait meanwhile. Returns the number of
+    /// rows fixed.
+    pub async fn rebuild_daily_spending(
+        &self,
+        since: Option<chrono::NaiveDate>,
+    ) -> Result<u64, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        sqlx::query("LOCK TABLE transactions IN SHARE MODE")
+            .execute(&mut *tx)
+            .await?;
+
+        let repaired = sqlx::query(
+            r#"
+            WITH expected AS (
+                SELECT user_id, DATE(created_at) AS day, status,
+                       SUM(amount_in_cents)::BIGINT AS amount_in_cents,
+                       COUNT(*)::INTEGER AS transactions
+                FROM all_transactions
+                WHERE $1::DATE IS NULL OR DATE(created_at) >= $1
+                GROUP BY user_id, DATE(created_at), status
+            ),
+            projected AS (
+                SELECT user_id, day, status, amount_in_cents, transactions
+                FROM user_daily_spending
+                WHERE $1::DATE IS NULL OR day >= $1
+            )
+            INSERT INTO user_daily_spending (user_id, day, status, amount_in_cents, transactions)
+            SELECT user_id, day, status,
+                   COALESCE(e.amount_in_cents, 0), COALESCE(e.transactions, 0)
+            FROM expected e
+            FULL JOIN projected p USING (user_id, day, status)
+            WHERE COALESCE(e.amount_in_cents, 0) <> COALESCE(p.amount_in_cents, 0)
+               OR COALESCE(e.transactions, 0) <> COALESCE(p.transactions, 0)
+            ON CONFLICT (user_id, day, status) DO UPDATE
+            SET amount_in_cents = EXCLUDED.amount_in_cents,
+                transactions = EXCLUDED.transactions
+            "#,
+        )
+        .bind(since)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+
+        Ok(repaired)
+    }
+}
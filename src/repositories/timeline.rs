@@ -0,0 +1,240 @@
+use crate::models::{
+    payouts::PartialPayout,
+    pix::PixTransaction,
+    reviews::Review,
+    sagas::SagaStep,
+    timeline::{TimelineEvent, TransactionTimeline},
+    transactions::Transaction,
+};
+use crate::repositories::audit::get_audit_events;
+
+use serde_json::json;
+use sqlx::PgPool;
+
+type Timestamp = chrono::DateTime<chrono::Utc>;
+
+fn event(at: Timestamp, source: &str, event: &str, details: serde_json::Value) -> TimelineEvent {
+    TimelineEvent {
+        at,
+        source: source.to_string(),
+        event: event.to_string(),
+        details,
+    }
+}
+
+#[derive(Clone)]
+pub struct TimelineRepository {
+    conn: PgPool,
+}
+
+impl TimelineRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    pub async fn get_transaction_timeline(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<TransactionTimeline>, anyhow::Error> {
+        let transaction =
+            sqlx::query_as::<_, Transaction>("SELECT * FROM all_transactions WHERE id = $1")
+                .bind(transaction_id)
+                .fetch_optional(&self.conn)
+                .await?;
+        let Some(transaction) = transaction else {
+            return Ok(None);
+        };
+
+        let mut events = vec![event(
+            transaction.created_at,
+            "transaction",
+            "created",
+            json!({
+                "amount_in_cents": transaction.amount_in_cents,
+                "asset": transaction.asset,
+                "network": transaction.network,
+                "address": transaction.address,
+            }),
+        )];
+
+        let history = sqlx::query_as::<_, (String, Timestamp)>(
+            "SELECT status, changed_at FROM transaction_status_history WHERE transaction_id = $1 ORDER BY id",
+        )
+        .bind(transaction_id)
+        .fetch_all(&self.conn)
+        .await?;
+        if history.is_empty() {
+            // Transactions older than the status history only have their current status
+            events.push(event(
+                transaction.updated_at,
+                "transaction",
+                "status_changed",
+                json!({ "status": transaction.status }),
+            ));
+        }
+        for (status, changed_at) in history {
+            events.push(event(
+                changed_at,
+                "transaction",
+                "status_changed",
+                json!({ "status": status }),
+            ));
+        }
+
+        let pix_transactions = sqlx::query_as::<_, PixTransaction>(
+            "SELECT * FROM all_pix_transactions WHERE transaction_id = $1",
+        )
+        .bind(transaction_id)
+        .fetch_all(&self.conn)
+        .await?;
+        for pix in pix_transactions {
+            events.push(event(
+                pix.created_at,
+                "pix",
+                "charge_created",
+                json!({ "eulen_id": pix.eulen_id, "amount_in_cents": pix.amount_in_cents }),
+            ));
+            if pix.updated_at > pix.created_at {
+                events.push(event(
+                    pix.updated_at,
+                    "pix",
+                    "status_updated",
+                    json!({ "eulen_id": pix.eulen_id, "status": pix.status }),
+                ));
+            }
+        }
+
+        let swaps = sqlx::query_as::<_, (i64, Option<String>, Timestamp, Option<Timestamp>)>(
+            "SELECT quote_sub_id, txid, created_at, completed_at FROM payout_swaps WHERE transaction_id = $1",
+        )
+        .bind(transaction_id)
+        .fetch_all(&self.conn)
+        .await?;
+        for (quote_sub_id, txid, created_at, completed_at) in swaps {
+            events.push(event(
+                created_at,
+                "swap",
+                "swap_started",
+                json!({ "quote_sub_id": quote_sub_id }),
+            ));
+            if let Some(completed_at) = completed_at {
+                events.push(event(
+                    completed_at,
+                    "swap",
+                    "swap_executed",
+                    json!({ "quote_sub_id": quote_sub_id, "txid": txid }),
+                ));
+            }
+        }
+
+        let payout = sqlx::query_as::<_, (Option<String>, Option<i64>, Timestamp, Timestamp)>(
+            "SELECT txid, network_fee, created_at, updated_at FROM payouts WHERE transaction_id = $1",
+        )
+        .bind(transaction_id)
+        .fetch_optional(&self.conn)
+        .await?;
+        if let Some((txid, network_fee, created_at, updated_at)) = payout {
+            events.push(event(created_at, "payout", "broadcasting", json!({})));
+            if let Some(txid) = txid {
+                events.push(event(
+                    updated_at,
+                    "payout",
+                    "broadcast",
+                    json!({ "txid": txid, "network_fee": network_fee }),
+                ));
+            }
+        }
+
+        let partials = sqlx::query_as::<_, PartialPayout>(
+            "SELECT * FROM partial_payouts WHERE transaction_id = $1 ORDER BY id",
+        )
+        .bind(transaction_id)
+        .fetch_all(&self.conn)
+        .await?;
+        for partial in partials {
+            events.push(event(
+                partial.created_at,
+                "payout",
+                "partial_broadcast",
+                json!({
+                    "amount": partial.amount,
+                    "txid": partial.txid,
+                    "network_fee": partial.network_fee,
+                }),
+            ));
+        }
+
+        let steps = sqlx::query_as::<_, SagaStep>(
+            "SELECT * FROM payout_saga_steps WHERE transaction_id = $1 ORDER BY id",
+        )
+        .bind(transaction_id)
+        .fetch_all(&self.conn)
+        .await?;
+        for step in steps {
+            let details = serde_json::from_str::<serde_json::Value>(&step.details)
+                .unwrap_or(json!(step.details));
+            events.push(event(
+                step.created_at,
+                "saga",
+                &step.step,
+                json!({ "status": step.status, "details": details }),
+            ));
+        }
+
+        let reviews = sqlx::query_as::<_, Review>(
+            "SELECT * FROM review_queue WHERE transaction_id = $1",
+        )
+        .bind(transaction_id)
+        .fetch_all(&self.conn)
+        .await?;
+        for review in reviews {
+            events.push(event(
+                review.created_at,
+                "review",
+                "held",
+                json!({ "source": review.source, "reason": review.reason }),
+            ));
+            if let Some(decided_at) = review.decided_at {
+                events.push(event(
+                    decided_at,
+                    "review",
+                    &review.status,
+                    json!({ "decided_by": review.decided_by, "note": review.note }),
+                ));
+            }
+        }
+
+        let flags = sqlx::query_as::<_, (String, String, String, Timestamp)>(
+            "SELECT source, action, reason, created_at FROM compliance_flags WHERE transaction_id = $1",
+        )
+        .bind(transaction_id)
+        .fetch_all(&self.conn)
+        .await?;
+        for (source, action, reason, created_at) in flags {
+            events.push(event(
+                created_at,
+                "compliance",
+                &action,
+                json!({ "source": source, "reason": reason }),
+            ));
+        }
+
+        for audit_event in get_audit_events(&self.conn, transaction_id).await? {
+            let details = serde_json::from_str::<serde_json::Value>(&audit_event.details)
+                .unwrap_or(json!(audit_event.details));
+            events.push(event(
+                audit_event.created_at,
+                "audit",
+                &audit_event.action,
+                json!({ "actor": audit_event.actor, "details": details }),
+            ));
+        }
+
+        events.sort_by_key(|event| event.at);
+
+        Ok(Some(TransactionTimeline {
+            transaction,
+            events,
+        }))
+    }
+}
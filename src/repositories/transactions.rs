@@ -1,43 +1,76 @@
+use crate::models::operator::{Cursor, Page, TransactionSearch};
+use crate::models::price::QuoteCurrency;
+use crate::models::risk::RiskBand;
 use crate::models::transactions;
+use crate::repositories::audit::record_audit_event;
+use crate::utils::clock::SharedClock;
 use anyhow::bail;
 use sqlx::PgPool;
 use uuid::Uuid;
 
+/// Daily cap of a consumer, in cents.
+pub const DAILY_LIMIT_CENTS: i64 = 5000 * 100;
+/// Caps of a consumer's first deposits, in cents, by how many they completed.
+pub const FIRST_DEPOSIT_LIMITS_CENTS: [i64; 3] = [250 * 100, 750 * 100, 1500 * 100];
+
+/// Consumer limits of a new deposit, given the deposits the user completed
+/// and what they spent today, scaled to the user's risk band.
+pub fn check_deposit_limits(
+    transaction_count: i64,
+    daily_spending: i64,
+    amount_in_cents: i32,
+    band: RiskBand,
+) -> Result<(), anyhow::Error> {
+    let amount = amount_in_cents as i64;
+
+    let first_deposit_limit = usize::try_from(transaction_count)
+        .ok()
+        .and_then(|count| FIRST_DEPOSIT_LIMITS_CENTS.get(count));
+    if let Some(limit) = first_deposit_limit {
+        if amount > band.scale(*limit) {
+            bail!("ExceededAllowedTransactionAmount")
+        }
+    }
+
+    if amount.saturating_add(daily_spending) > band.scale(DAILY_LIMIT_CENTS) {
+        bail!("ExceededDailyAmount")
+    }
+
+    Ok(())
+}
+
 #[derive(Clone)]
 pub struct TransactionRepository {
     conn: PgPool,
+    clock: SharedClock,
 }
 
 impl TransactionRepository {
-    pub fn new(conn: PgPool) -> Self {
-        TransactionRepository { conn }
+    pub fn new(conn: PgPool, clock: SharedClock) -> Self {
+        TransactionRepository { conn, clock }
     }
 
     pub async fn new_transaction(
         &self,
-        user_id: &String,
-        address: &String,
-        fee_address: &String,
-        amount_in_cents: i32,
-        asset: &String,
-        network: &String,
+        row: &transactions::NewTransactionRow,
+        band: RiskBand,
     ) -> Result<transactions::Transaction, anyhow::Error> {
-        let transaction_count = self.get_transaction_count(user_id).await?;
-        let daily_spending = self.get_daily_spending(user_id).await?;
+        let transaction_count = self.get_transaction_count(&row.user_id).await?;
+        let daily_spending = self.get_daily_spending(&row.user_id).await?;
 
-        if ((transaction_count == 0 && amount_in_cents > 250 * 100)
-            || (transaction_count == 1 && amount_in_cents > 750 * 100)
-            || (transaction_count == 2 && amount_in_cents > 1500 * 100))
-        {
-            bail!("ExceededAllowedTransactionAmount")
-        }
+        check_deposit_limits(transaction_count, daily_spending, row.amount_in_cents, band)?;
 
-        if (amount_in_cents + daily_spending) > 5000 * 100 {
-            bail!("ExceededDailyAmount")
-        }
+        self.insert_transaction(row).await
+    }
 
+    /// Inserts a transaction without the consumer limits, for merchant
+    /// payment links.
+    pub async fn insert_transaction(
+        &self,
+        row: &transactions::NewTransactionRow,
+    ) -> Result<transactions::Transaction, anyhow::Error> {
         let transaction_id = Uuid::new_v4().hyphenated().to_string();
-        let tx = self.conn.begin().await?;
+        let mut tx = self.conn.begin().await?;
 
         let transaction = sqlx::query_as!(
             transactions::Transaction,
@@ -47,15 +80,30 @@ impl TransactionRepository {
             RETURNING *
             "#,
             transaction_id,
-            user_id,
-            address,
-            amount_in_cents as i32,
-            asset,
-            network
+            row.user_id,
+            row.address,
+            row.amount_in_cents,
+            row.asset,
+            row.network
         )
-        .fetch_one(&self.conn)
+        .fetch_one(&mut *tx)
         .await?;
 
+        // A fee address is handed out once and never reused
+        let assigned = sqlx::query(
+            "UPDATE wallet_addresses SET transaction_id = $1 WHERE address = $2 AND transaction_id IS NULL",
+        )
+        .bind(&transaction.id)
+        .bind(&row.fee_address)
+        .execute(&mut *tx)
+        .await?;
+        if assigned.rows_affected() != 1 {
+            bail!(
+                "Fee address {} was not handed out or already belongs to a transaction",
+                row.fee_address
+            );
+        }
+
         tx.commit().await?;
 
         Ok(transaction)
@@ -65,19 +113,103 @@ impl TransactionRepository {
         &self,
         id: &String,
     ) -> Result<Option<transactions::Transaction>, anyhow::Error> {
-        let transaction = sqlx::query_as!(
-            transactions::Transaction,
-            r#"SELECT
-            *
-            FROM transactions WHERE id = $1"#,
-            id
+        let transaction = sqlx::query_as::<_, transactions::Transaction>(
+            "SELECT * FROM all_transactions WHERE id = $1",
         )
+        .bind(id)
         .fetch_optional(&self.conn)
         .await?;
 
         Ok(transaction)
     }
 
+    pub async fn get_transactions_by_status(
+        &self,
+        status: &str,
+    ) -> Result<Vec<transactions::Transaction>, anyhow::Error> {
+        let transactions = sqlx::query_as::<_, transactions::Transaction>(
+            "SELECT * FROM transactions WHERE status = $1 ORDER BY created_at",
+        )
+        .bind(status)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(transactions)
+    }
+
+    pub async fn get_recent_transactions(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<transactions::Transaction>, anyhow::Error> {
+        let transactions = sqlx::query_as::<_, transactions::Transaction>(
+            "SELECT * FROM transactions ORDER BY created_at DESC LIMIT $1",
+        )
+        .bind(limit)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(transactions)
+    }
+
+    /// Page of the admin search, newest first, starting after `after`.
+    /// Archived transactions are included.
+    pub async fn search_transactions(
+        &self,
+        search: &TransactionSearch,
+        after: Option<&Cursor>,
+        limit: i64,
+    ) -> Result<Page<transactions::Transaction>, anyhow::Error> {
+        let transactions = sqlx::query_as::<_, transactions::Transaction>(
+            r#"
+            SELECT * FROM all_transactions
+            WHERE ($1::TEXT IS NULL OR status = $1)
+              AND ($2::TEXT IS NULL OR asset = $2)
+              AND ($3::TEXT IS NULL OR user_id = $3)
+              AND ($4::INT IS NULL OR amount_in_cents >= $4)
+              AND ($5::INT IS NULL OR amount_in_cents <= $5)
+              AND ($6::TIMESTAMPTZ IS NULL OR created_at >= $6)
+              AND ($7::TIMESTAMPTZ IS NULL OR created_at < $7)
+              AND ($8::TIMESTAMPTZ IS NULL OR (created_at, id) < ($8, $9))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $10
+            "#,
+        )
+        .bind(&search.status)
+        .bind(&search.asset)
+        .bind(&search.user_id)
+        .bind(search.min_amount_in_cents)
+        .bind(search.max_amount_in_cents)
+        .bind(search.since)
+        .bind(search.until)
+        .bind(after.map(|cursor| cursor.created_at))
+        .bind(after.map(|cursor| cursor.id.as_str()))
+        .bind(limit + 1)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(Page::from_rows(transactions, limit, |transaction| Cursor {
+            created_at: transaction.created_at,
+            id: transaction.id.clone(),
+        }))
+    }
+
+    /// Transactions in a status that haven't changed since `updated_before`.
+    pub async fn get_stale_transactions(
+        &self,
+        status: &str,
+        updated_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<transactions::Transaction>, anyhow::Error> {
+        let transactions = sqlx::query_as::<_, transactions::Transaction>(
+            "SELECT * FROM transactions WHERE status = $1 AND updated_at < $2 ORDER BY created_at",
+        )
+        .bind(status)
+        .bind(updated_before)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(transactions)
+    }
+
     pub async fn get_allowed_spending(&self, user_id: &String) -> Result<i32, anyhow::Error> {
         let transaction_count = self.get_transaction_count(user_id).await?;
 
@@ -85,7 +217,7 @@ impl TransactionRepository {
             0 => 250 * 100,
             1 => 750 * 100,
             2 => 1500 * 100,
-            _ => self.get_daily_spending(user_id).await?,
+            _ => self.get_daily_spending(user_id).await? as i32,
         };
 
         Ok(allowed_spending)
@@ -93,7 +225,7 @@ impl TransactionRepository {
 
     async fn get_transaction_count(&self, user_id: &String) -> Result<i64, anyhow::Error> {
         let count: i64 = sqlx::query_scalar(
-            "SELECT COUNT(1) FROM transactions WHERE user_id = $1 AND status = 'eulen_depix_sent'",
+            "SELECT COALESCE(SUM(transactions), 0) FROM user_daily_spending WHERE user_id = $1 AND status IN ('eulen_depix_sent', 'scheduled', 'pricing_unavailable')",
         )
         .bind(user_id)
         .fetch_one(&self.conn)
@@ -102,22 +234,23 @@ impl TransactionRepository {
         Ok(count)
     }
 
-    async fn get_daily_spending(&self, user_id: &String) -> Result<i32, anyhow::Error> {
+    async fn get_daily_spending(&self, user_id: &String) -> Result<i64, anyhow::Error> {
         let amount: i64 = sqlx::query_scalar(
-            r#"SELECT COALESCE(SUM(amount_in_cents), 0) FROM transactions WHERE user_id = $1 AND DATE(created_at) = CURRENT_DATE AND status = 'eulen_depix_sent'"#,
+            r#"SELECT COALESCE(SUM(amount_in_cents), 0)::BIGINT FROM user_daily_spending WHERE user_id = $1 AND day = $2::TIMESTAMPTZ::DATE AND status IN ('eulen_depix_sent', 'scheduled', 'pricing_unavailable')"#,
         )
         .bind(user_id)
+        .bind(self.clock.now())
         .fetch_one(&self.conn)
         .await?;
 
-        Ok(amount as i32)
+        Ok(amount)
     }
 
     pub async fn update_transaction_status(
         &self,
         id: &String,
         status: &String,
-    ) -> Result<String, anyhow::Error> {
+    ) -> Result<transactions::Transaction, anyhow::Error> {
         let transaction = sqlx::query_as!(
             transactions::Transaction,
             "UPDATE transactions SET status = $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2 RETURNING *",
@@ -127,7 +260,172 @@ impl TransactionRepository {
         .fetch_one(&self.conn)
         .await?;
 
-        Ok(transaction.id)
+        Ok(transaction)
+    }
+
+    /// Moves a transaction from any of the `from` statuses to `to`. None
+    /// when it was in none of them.
+    pub async fn transition_status(
+        &self,
+        id: &str,
+        from: &[&str],
+        to: &str,
+    ) -> Result<Option<transactions::Transaction>, anyhow::Error> {
+        let transaction = sqlx::query_as::<_, transactions::Transaction>(
+            "UPDATE transactions SET status = $3, updated_at = CURRENT_TIMESTAMP WHERE id = $1 AND status = ANY($2) RETURNING *",
+        )
+        .bind(id)
+        .bind(from)
+        .bind(to)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(transaction)
+    }
+
+    /// Cancels a deposit of the user that wasn't paid yet, along with its
+    /// PIX charges. None when there's no such deposit, or its charge was
+    /// already paid.
+    pub async fn cancel_transaction(
+        &self,
+        id: &str,
+        user_id: &str,
+    ) -> Result<Option<transactions::Transaction>, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let transaction = sqlx::query_as::<_, transactions::Transaction>(
+            r#"
+            UPDATE transactions SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND user_id = $2 AND status = 'pending'
+              AND NOT EXISTS (
+                  SELECT 1 FROM pix_transactions
+                  WHERE transaction_id = $1 AND status = 'depix_sent'
+              )
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(transaction) = transaction else {
+            return Ok(None);
+        };
+
+        sqlx::query(
+            "UPDATE pix_transactions SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP WHERE transaction_id = $1 AND status = 'pending'",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        record_audit_event(
+            &mut *tx,
+            user_id,
+            "transaction_cancelled",
+            id,
+            serde_json::json!({ "amount_in_cents": transaction.amount_in_cents }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(transaction))
+    }
+
+    /// Cancels a pending deposit nobody waited for, along with its PIX
+    /// charges. False when it's no longer pending.
+    pub async fn abandon_transaction(&self, id: &str, stage: &str) -> Result<bool, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let abandoned = sqlx::query(
+            "UPDATE transactions SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP WHERE id = $1 AND status = 'pending'",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !abandoned {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "UPDATE pix_transactions SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP WHERE transaction_id = $1 AND status = 'pending'",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        record_audit_event(
+            &mut *tx,
+            "system",
+            "deposit_abandoned",
+            id,
+            serde_json::json!({ "stage": stage }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Gives up on the payout of a paid transaction, recording who did and
+    /// why. None when it is no longer awaiting payout.
+    pub async fn fail_transaction(
+        &self,
+        id: &str,
+        actor: &str,
+        reason: &str,
+    ) -> Result<Option<transactions::Transaction>, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let transaction = sqlx::query_as::<_, transactions::Transaction>(
+            r#"
+            UPDATE transactions SET status = 'failed', updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND status = 'eulen_depix_sent'
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(transaction) = transaction else {
+            return Ok(None);
+        };
+
+        record_audit_event(
+            &mut *tx,
+            actor,
+            "pending_payout_dropped",
+            id,
+            serde_json::json!({
+                "reason": reason,
+                "amount_in_cents": transaction.amount_in_cents,
+            }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(transaction))
+    }
+
+    /// Records an operator forcing a queued payout to be retried.
+    pub async fn record_payout_retry(&self, id: &str, actor: &str) -> Result<(), anyhow::Error> {
+        record_audit_event(
+            &self.conn,
+            actor,
+            "pending_payout_retried",
+            id,
+            serde_json::json!({}),
+        )
+        .await?;
+
+        Ok(())
     }
 
     pub async fn update_fee_collected(
@@ -146,4 +444,50 @@ impl TransactionRepository {
 
         Ok(transaction.id)
     }
+
+    /// Records the price a payout was computed with.
+    pub async fn record_price(
+        &self,
+        id: &String,
+        currency: QuoteCurrency,
+        price_in_cents: u64,
+        aggregation: Option<&str>,
+        fee_in_asset: u64,
+    ) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "INSERT INTO transaction_prices (transaction_id, quote_currency, price_in_cents, aggregation, fee_in_asset)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (transaction_id) DO UPDATE SET
+                quote_currency = EXCLUDED.quote_currency,
+                price_in_cents = EXCLUDED.price_in_cents,
+                aggregation = EXCLUDED.aggregation,
+                fee_in_asset = EXCLUDED.fee_in_asset,
+                recorded_at = CURRENT_TIMESTAMP",
+        )
+        .bind(id)
+        .bind(currency.code())
+        .bind(i64::try_from(price_in_cents)?)
+        .bind(aggregation)
+        .bind(i64::try_from(fee_in_asset)?)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Price and fee the payout of a transaction was last computed with.
+    /// None when it wasn't computed, or before fees were recorded.
+    pub async fn get_recorded_price(&self, id: &str) -> Result<Option<(u64, u64)>, anyhow::Error> {
+        let recorded: Option<(i64, i64)> = sqlx::query_as(
+            "SELECT price_in_cents, fee_in_asset FROM transaction_prices WHERE transaction_id = $1 AND fee_in_asset IS NOT NULL",
+        )
+        .bind(id)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        match recorded {
+            Some((price, fee)) => Ok(Some((u64::try_from(price)?, u64::try_from(fee)?))),
+            None => Ok(None),
+        }
+    }
 }
@@ -0,0 +1,117 @@
+use crate::models::treasury::{DailySwapSummary, SwapExecution, SwapQuote};
+
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct TreasuryRepository {
+    conn: PgPool,
+}
+
+impl TreasuryRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    pub async fn record_swap(&self, swap: &SwapExecution) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO sideswap_swaps (
+                quote_sub_id, quote_id, txid, sell_asset, receive_asset, sold_amount,
+                received_amount, fee_asset, server_fee, fixed_fee, sell_price_in_cents,
+                receive_price_in_cents, fee_price_in_cents
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            ON CONFLICT (quote_sub_id) DO NOTHING
+            "#,
+        )
+        .bind(swap.quote_sub_id)
+        .bind(swap.quote_id)
+        .bind(&swap.txid)
+        .bind(&swap.sell_asset)
+        .bind(&swap.receive_asset)
+        .bind(swap.sold_amount)
+        .bind(swap.received_amount)
+        .bind(&swap.fee_asset)
+        .bind(swap.server_fee)
+        .bind(swap.fixed_fee)
+        .bind(swap.sell_price_in_cents)
+        .bind(swap.receive_price_in_cents)
+        .bind(swap.fee_price_in_cents)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_quote(&self, quote: &SwapQuote) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO sideswap_quotes (
+                swap_id, quote_sub_id, quote_id, sell_asset, receive_asset, quoted_in,
+                requested_amount, sold_amount, received_amount, server_fee, fixed_fee, price,
+                chosen
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            "#,
+        )
+        .bind(quote.swap_id)
+        .bind(quote.quote_sub_id)
+        .bind(quote.quote_id)
+        .bind(&quote.sell_asset)
+        .bind(&quote.receive_asset)
+        .bind(quote.quoted_in)
+        .bind(quote.requested_amount)
+        .bind(quote.sold_amount)
+        .bind(quote.received_amount)
+        .bind(quote.server_fee)
+        .bind(quote.fixed_fee)
+        .bind(quote.price)
+        .bind(quote.chosen)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Swaps of the last `days` days per day and pair, newest first. Oracle
+    /// rates and P&L only count swaps priced by the price service.
+    pub async fn get_daily_summaries(
+        &self,
+        days: i64,
+    ) -> Result<Vec<DailySwapSummary>, anyhow::Error> {
+        let summaries = sqlx::query_as::<_, DailySwapSummary>(
+            r#"
+            SELECT (executed_at AT TIME ZONE 'UTC')::DATE AS day,
+                   sell_asset,
+                   receive_asset,
+                   fee_asset,
+                   COUNT(*) AS swaps,
+                   SUM(sold_amount)::BIGINT AS sold_amount,
+                   SUM(received_amount)::BIGINT AS received_amount,
+                   SUM(server_fee)::BIGINT AS server_fees,
+                   SUM(fixed_fee)::BIGINT AS fixed_fees,
+                   SUM((server_fee + fixed_fee)::NUMERIC * fee_price_in_cents / 100000000)::BIGINT
+                       AS fees_brl_cents,
+                   (SUM(received_amount)::NUMERIC / NULLIF(SUM(sold_amount), 0))::DOUBLE PRECISION
+                       AS realized_rate,
+                   (SUM(sold_amount::NUMERIC * sell_price_in_cents / NULLIF(receive_price_in_cents, 0))
+                       / NULLIF(SUM(sold_amount) FILTER (
+                           WHERE sell_price_in_cents IS NOT NULL AND receive_price_in_cents > 0
+                       ), 0))::DOUBLE PRECISION AS oracle_rate,
+                   SUM((received_amount::NUMERIC * receive_price_in_cents
+                       - sold_amount::NUMERIC * sell_price_in_cents) / 100000000)::BIGINT
+                       AS pnl_cents
+            FROM sideswap_swaps
+            WHERE executed_at >= ((CURRENT_TIMESTAMP AT TIME ZONE 'UTC')::DATE - ($1::INT - 1))
+                  ::TIMESTAMP AT TIME ZONE 'UTC'
+            GROUP BY 1, sell_asset, receive_asset, fee_asset
+            ORDER BY day DESC, sell_asset, receive_asset
+            "#,
+        )
+        .bind(days as i32)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(summaries)
+    }
+}
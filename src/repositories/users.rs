@@ -1,22 +1,55 @@
-use crate::models::{referrals, users};
+use crate::models::risk::RiskBand;
+use crate::models::{pix, receipts, referrals, transactions, users};
+use crate::repositories::archive::ARCHIVED_STATUSES;
+use crate::repositories::audit::{get_audit_events, record_audit_event};
+use crate::utils::clock::SharedClock;
 
 use anyhow::bail;
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+/// Anonymization is refused while the user has transactions that are paid
+/// but not settled, since their payout still needs the address.
+#[derive(Debug, thiserror::Error)]
+#[error("User has {0} transactions not settled yet")]
+pub struct UnsettledTransactions(pub usize);
+
+fn allowed_spending_for(total_spending: i64) -> i64 {
+    if total_spending < 250 * 100 {
+        250 * 100
+    } else if total_spending < 750 * 100 {
+        750 * 100
+    } else if total_spending < 1500 * 100 {
+        1500 * 100
+    } else {
+        5000 * 100
+    }
+}
+
+fn device_fingerprint(installation_id: &str) -> String {
+    format!("{:x}", Sha256::digest(installation_id.trim().as_bytes()))
+}
+
+fn hash_api_key(api_key: &str) -> String {
+    format!("{:x}", Sha256::digest(api_key.trim().as_bytes()))
+}
+
 #[derive(Clone)]
 pub struct UserRepository {
     conn: PgPool,
+    clock: SharedClock,
 }
 
 impl UserRepository {
-    pub fn new(conn: PgPool) -> Self {
-        Self { conn }
+    pub fn new(conn: PgPool, clock: SharedClock) -> Self {
+        Self { conn, clock }
     }
 
     pub async fn insert_user(
         &self,
         referral_code: Option<String>,
+        installation_id: Option<String>,
     ) -> Result<users::User, anyhow::Error> {
         let user_id = Uuid::new_v4().hyphenated().to_string();
 
@@ -51,9 +84,76 @@ impl UserRepository {
         .fetch_one(&self.conn)
         .await?;
 
+        if let Some(installation_id) = installation_id.filter(|id| !id.trim().is_empty()) {
+            self.bind_device(&user.id, &installation_id).await?;
+        }
+
         Ok(user)
     }
 
+    pub async fn bind_device(&self, user_id: &str, installation_id: &str) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"
+                INSERT INTO user_devices (user_id, device_fingerprint)
+                VALUES ($1, $2)
+                ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(device_fingerprint(installation_id))
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Accounts sharing at least one device with the user, the user included.
+    pub async fn get_device_linked_users(&self, user_id: &str) -> Result<Vec<String>, anyhow::Error> {
+        let users: Vec<String> = sqlx::query_scalar(
+            r#"
+                SELECT DISTINCT linked.user_id FROM user_devices own
+                JOIN user_devices linked ON linked.device_fingerprint = own.device_fingerprint
+                WHERE own.user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(users)
+    }
+
+    pub async fn get_device_limits(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<users::DeviceLimits>, anyhow::Error> {
+        let linked_accounts = self.get_device_linked_users(user_id).await?;
+        if linked_accounts.len() < 2 {
+            return Ok(None);
+        }
+
+        let daily_spending: i64 = sqlx::query_scalar(
+            r#"SELECT COALESCE(SUM(amount_in_cents), 0)::BIGINT FROM user_daily_spending WHERE user_id = ANY($1) AND day = $2::TIMESTAMPTZ::DATE AND status IN ('eulen_depix_sent', 'scheduled', 'pricing_unavailable', 'finished')"#,
+        )
+        .bind(&linked_accounts)
+        .bind(self.clock.now())
+        .fetch_one(&self.conn)
+        .await?;
+
+        let total_spending: i64 = sqlx::query_scalar(
+            r#"SELECT COALESCE(SUM(amount_in_cents), 0)::BIGINT FROM user_daily_spending WHERE user_id = ANY($1) AND status = 'eulen_depix_sent'"#,
+        )
+        .bind(&linked_accounts)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(Some(users::DeviceLimits {
+            linked_accounts,
+            daily_spending,
+            allowed_spending: allowed_spending_for(total_spending),
+        }))
+    }
+
     pub async fn get_user_by_id(
         &self,
         user_id: &str,
@@ -64,9 +164,367 @@ impl UserRepository {
 
         log::debug!("Got user: {:?}", user);
 
+        if user.is_some() && self.is_user_anonymized(user_id).await? {
+            return Ok(None);
+        }
+
         Ok(user)
     }
 
+    pub async fn is_user_anonymized(&self, user_id: &str) -> Result<bool, anyhow::Error> {
+        let anonymized: bool = sqlx::query_scalar(
+            r#"SELECT EXISTS(SELECT 1 FROM audit_log WHERE subject_id = $1 AND action = 'user_anonymized')"#,
+        )
+        .bind(user_id)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(anonymized)
+    }
+
+    /// Collects everything held about a user and records the export in the
+    /// audit trail.
+    pub async fn export_user_data(
+        &self,
+        user_id: &str,
+        actor: &str,
+        reference: Option<&str>,
+    ) -> Result<Option<users::UserDataExport>, anyhow::Error> {
+        let user = sqlx::query_as::<_, users::User>("SELECT * FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.conn)
+            .await?;
+        let Some(user) = user else {
+            return Ok(None);
+        };
+
+        let referral =
+            sqlx::query_as::<_, referrals::Referral>("SELECT * FROM referrals WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_optional(&self.conn)
+                .await?;
+
+        let device_fingerprints: Vec<String> = sqlx::query_scalar(
+            "SELECT device_fingerprint FROM user_devices WHERE user_id = $1 ORDER BY created_at",
+        )
+        .bind(user_id)
+        .fetch_all(&self.conn)
+        .await?;
+
+        let transactions = sqlx::query_as::<_, transactions::Transaction>(
+            "SELECT * FROM all_transactions WHERE user_id = $1 ORDER BY created_at",
+        )
+        .bind(user_id)
+        .fetch_all(&self.conn)
+        .await?;
+
+        let pix_transactions = sqlx::query_as::<_, pix::PixTransaction>(
+            r#"
+                SELECT p.* FROM all_pix_transactions p
+                JOIN all_transactions t ON t.id = p.transaction_id
+                WHERE t.user_id = $1
+                ORDER BY p.created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.conn)
+        .await?;
+
+        let notification_preferences = sqlx::query_as::<_, receipts::NotificationPreferences>(
+            "SELECT * FROM notification_preferences WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        let receipts = sqlx::query_as::<_, receipts::Receipt>(
+            "SELECT * FROM receipts WHERE user_id = $1 ORDER BY created_at",
+        )
+        .bind(user_id)
+        .fetch_all(&self.conn)
+        .await?;
+
+        record_audit_event(
+            &self.conn,
+            actor,
+            "user_data_exported",
+            user_id,
+            serde_json::json!({ "reference": reference }),
+        )
+        .await?;
+
+        let audit_trail = get_audit_events(&self.conn, user_id).await?;
+
+        Ok(Some(users::UserDataExport {
+            user,
+            referral,
+            device_fingerprints,
+            transactions,
+            pix_transactions,
+            notification_preferences,
+            receipts,
+            audit_trail,
+            exported_at: self.clock.now(),
+        }))
+    }
+
+    /// Removes personal data of a user while keeping the financial records
+    /// (amounts, assets, statuses and dates) that must be retained by law.
+    /// Destination addresses are scrubbed, the referral code and payment
+    /// address are deleted, device bindings and notification preferences are
+    /// dropped, receipt recipients are scrubbed and users referred by them
+    /// are detached. Archived transactions are scrubbed too. Unpaid deposits
+    /// are cancelled first; a user with other transactions not settled yet
+    /// gets `UnsettledTransactions`.
+    pub async fn anonymize_user(
+        &self,
+        user_id: &str,
+        actor: &str,
+        reference: Option<&str>,
+    ) -> Result<bool, anyhow::Error> {
+        let mut tx = self.conn.begin().await?;
+
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !exists {
+            return Ok(false);
+        }
+
+        // Settled as the archive sees them: a pending deposit is unless its
+        // charge was paid
+        let unsettled: Vec<String> = sqlx::query_scalar(
+            r#"
+                SELECT t.id FROM transactions t
+                WHERE t.user_id = $1
+                  AND (t.status <> ALL($2) OR (t.status = 'pending' AND EXISTS (
+                      SELECT 1 FROM pix_transactions p
+                      WHERE p.transaction_id = t.id AND p.status = 'depix_sent'
+                  )))
+                FOR UPDATE
+            "#,
+        )
+        .bind(user_id)
+        .bind(&ARCHIVED_STATUSES[..])
+        .fetch_all(&mut *tx)
+        .await?;
+        if !unsettled.is_empty() {
+            return Err(UnsettledTransactions(unsettled.len()).into());
+        }
+
+        // A charge paid after this is refunded like any cancelled deposit's
+        let cancelled: Vec<String> = sqlx::query_scalar(
+            r#"
+                UPDATE transactions SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP
+                WHERE user_id = $1 AND status = 'pending'
+                RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        sqlx::query(
+            "UPDATE pix_transactions SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP WHERE transaction_id = ANY($1) AND status = 'pending'",
+        )
+        .bind(&cancelled)
+        .execute(&mut *tx)
+        .await?;
+
+        let mut transactions = 0;
+        let mut pix_transactions = 0;
+        for (transaction_table, pix_table) in [
+            ("transactions", "pix_transactions"),
+            ("transactions_archive", "pix_transactions_archive"),
+        ] {
+            transactions += sqlx::query(&format!(
+                "UPDATE {transaction_table} SET address = 'anonymized', updated_at = CURRENT_TIMESTAMP WHERE user_id = $1"
+            ))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            pix_transactions += sqlx::query(&format!(
+                r#"
+                    UPDATE {pix_table} SET address = 'anonymized', updated_at = CURRENT_TIMESTAMP
+                    WHERE transaction_id IN (SELECT id FROM {transaction_table} WHERE user_id = $1)
+                "#
+            ))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        let referrals = sqlx::query("DELETE FROM referrals WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        let devices = sqlx::query("DELETE FROM user_devices WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        let notification_preferences =
+            sqlx::query("DELETE FROM notification_preferences WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+        let receipts = sqlx::query(
+            "UPDATE receipts SET recipient = 'anonymized', updated_at = CURRENT_TIMESTAMP WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let referred_users = sqlx::query(
+            "UPDATE users SET referred_by = NULL, updated_at = CURRENT_TIMESTAMP WHERE referred_by = $1",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        sqlx::query(
+            "UPDATE users SET verified = false, referred_by = NULL, updated_at = CURRENT_TIMESTAMP WHERE id = $1",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        record_audit_event(
+            &mut *tx,
+            actor,
+            "user_anonymized",
+            user_id,
+            serde_json::json!({
+                "reference": reference,
+                "cancelled_deposits": cancelled.len(),
+                "transactions": transactions,
+                "pix_transactions": pix_transactions,
+                "referrals": referrals,
+                "devices": devices,
+                "notification_preferences": notification_preferences,
+                "receipts": receipts,
+                "referred_users": referred_users,
+            }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Transactions of a user created in `[since, until)`, with their price
+    /// and payout.
+    pub async fn get_statement_entries(
+        &self,
+        user_id: &str,
+        since: chrono::DateTime<chrono::Utc>,
+        until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<users::StatementEntry>, anyhow::Error> {
+        let entries = sqlx::query_as::<_, users::StatementEntry>(
+            r#"
+                SELECT t.id AS transaction_id, t.created_at, t.status, t.amount_in_cents, t.asset,
+                       t.fee_collected, tp.price_in_cents, tp.quote_currency, p.txid, p.network_fee
+                FROM all_transactions t
+                LEFT JOIN transaction_prices tp ON tp.transaction_id = t.id
+                LEFT JOIN payouts p ON p.transaction_id = t.id
+                WHERE t.user_id = $1 AND t.created_at >= $2 AND t.created_at < $3
+                ORDER BY t.created_at, t.id
+            "#,
+        )
+        .bind(user_id)
+        .bind(since)
+        .bind(until)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Stores the key of a user, replacing the one they had. False when
+    /// there's no such user.
+    pub async fn set_api_key(&self, user_id: &str, api_key: &str) -> Result<bool, anyhow::Error> {
+        let stored = sqlx::query(
+            r#"
+                INSERT INTO user_api_keys (user_id, api_key_hash)
+                SELECT id, $2 FROM users WHERE id = $1
+                ON CONFLICT (user_id) DO UPDATE
+                SET api_key_hash = EXCLUDED.api_key_hash, created_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(hash_api_key(api_key))
+        .execute(&self.conn)
+        .await?
+        .rows_affected();
+
+        Ok(stored > 0)
+    }
+
+    pub async fn get_user_by_api_key(
+        &self,
+        api_key: &str,
+    ) -> Result<Option<String>, anyhow::Error> {
+        let user_id: Option<String> =
+            sqlx::query_scalar("SELECT user_id FROM user_api_keys WHERE api_key_hash = $1")
+                .bind(hash_api_key(api_key))
+                .fetch_optional(&self.conn)
+                .await?;
+
+        Ok(user_id)
+    }
+
+    pub async fn get_notification_preferences(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<receipts::NotificationPreferences>, anyhow::Error> {
+        let preferences = sqlx::query_as::<_, receipts::NotificationPreferences>(
+            "SELECT * FROM notification_preferences WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(preferences)
+    }
+
+    pub async fn set_notification_preferences(
+        &self,
+        user_id: &str,
+        preferences: &receipts::NotificationPreferencesUpdate,
+    ) -> Result<receipts::NotificationPreferences, anyhow::Error> {
+        let preferences = sqlx::query_as::<_, receipts::NotificationPreferences>(
+            r#"
+                INSERT INTO notification_preferences (user_id, email, phone, email_receipts, sms_receipts)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (user_id) DO UPDATE
+                SET email = EXCLUDED.email, phone = EXCLUDED.phone,
+                    email_receipts = EXCLUDED.email_receipts, sms_receipts = EXCLUDED.sms_receipts,
+                    updated_at = CURRENT_TIMESTAMP
+                RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(&preferences.email)
+        .bind(&preferences.phone)
+        .bind(preferences.email_receipts)
+        .bind(preferences.sms_receipts)
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(preferences)
+    }
+
     pub async fn verify_user(&self, user_id: &str) -> Result<(), anyhow::Error> {
         let user = self.get_user_by_id(user_id).await?;
 
@@ -86,9 +544,10 @@ impl UserRepository {
 
     pub async fn get_user_daily_spending(&self, user_id: &str) -> Result<i64, anyhow::Error> {
         let amount: i64 = sqlx::query_scalar(
-            r#"SELECT COALESCE(SUM(amount_in_cents), 0) FROM transactions WHERE user_id = $1 AND DATE(created_at) = CURRENT_DATE AND (status = 'eulen_depix_sent' OR status = 'finished')"#,
+            r#"SELECT COALESCE(SUM(amount_in_cents), 0)::BIGINT FROM user_daily_spending WHERE user_id = $1 AND day = $2::TIMESTAMPTZ::DATE AND status IN ('eulen_depix_sent', 'scheduled', 'pricing_unavailable', 'finished')"#,
         )
         .bind(user_id)
+        .bind(self.clock.now())
         .fetch_one(&self.conn)
         .await?;
 
@@ -97,7 +556,7 @@ impl UserRepository {
 
     async fn get_user_spending(&self, user_id: &str) -> Result<i64, anyhow::Error> {
         let amount: i64 = sqlx::query_scalar(
-            r#"SELECT COALESCE(SUM(amount_in_cents), 0) FROM transactions WHERE user_id = $1 AND status = 'eulen_depix_sent'"#,
+            r#"SELECT COALESCE(SUM(amount_in_cents), 0)::BIGINT FROM user_daily_spending WHERE user_id = $1 AND status = 'eulen_depix_sent'"#,
         )
         .bind(user_id)
         .fetch_one(&self.conn)
@@ -106,26 +565,21 @@ impl UserRepository {
         Ok(amount)
     }
 
-    pub async fn get_user_allowed_spending(&self, user_id: &str) -> Result<i64, anyhow::Error> {
+    /// Limit of the user's deposits, scaled to their risk band.
+    pub async fn get_user_allowed_spending(
+        &self,
+        user_id: &str,
+        band: RiskBand,
+    ) -> Result<i64, anyhow::Error> {
         let user_spending = self.get_user_spending(user_id).await?;
         let user_daily_spending = self.get_user_daily_spending(user_id).await?;
 
-        let allowed_spending = if user_spending < 250 * 100 {
-            250 * 100
-        } else if user_spending < 750 * 100 {
-            750 * 100
-        } else if user_spending < 1500 * 100 {
-            1500 * 100
-        } else {
-            5000 * 100
-        };
-
-        Ok(allowed_spending)
+        Ok(band.scale(allowed_spending_for(user_spending)))
     }
 
     pub async fn get_transaction_count(&self, user_id: &str) -> Result<i64, anyhow::Error> {
         let tx_count: i64 =
-            sqlx::query_scalar(r#"SELECT COUNT(1) FROM transactions WHERE user_id = $1 AND status = 'eulen_depix_sent'"#)
+            sqlx::query_scalar(r#"SELECT COALESCE(SUM(transactions), 0) FROM user_daily_spending WHERE user_id = $1 AND status = 'eulen_depix_sent'"#)
                 .bind(user_id)
                 .fetch_one(&self.conn)
                 .await?;
@@ -0,0 +1,104 @@
+use crate::models::webhooks::{RawWebhook, WebhookEvent};
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct WebhookRepository {
+    conn: PgPool,
+}
+
+impl WebhookRepository {
+    pub fn new(conn: PgPool) -> Self {
+        Self { conn }
+    }
+
+    pub async fn insert_event(
+        &self,
+        source: &str,
+        webhook: &RawWebhook,
+        bank_tx_ids: &[String],
+        qr_ids: &[String],
+    ) -> Result<String, anyhow::Error> {
+        let id = Uuid::new_v4().hyphenated().to_string();
+
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_events (id, source, payload, headers, bank_tx_ids, qr_ids)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(&id)
+        .bind(source)
+        .bind(&webhook.payload)
+        .bind(&webhook.headers)
+        .bind(bank_tx_ids)
+        .bind(qr_ids)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn record_outcome(&self, id: &str, outcome: &str) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            "UPDATE webhook_events SET outcome = $1, processed_at = CURRENT_TIMESTAMP WHERE id = $2",
+        )
+        .bind(outcome)
+        .bind(id)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Webhooks mentioning a bank transaction, or any PIX charge of a
+    /// transaction, newest first.
+    pub async fn search_events(
+        &self,
+        bank_tx_id: Option<&str>,
+        transaction_id: Option<&str>,
+    ) -> Result<Vec<WebhookEvent>, anyhow::Error> {
+        let events = sqlx::query_as::<_, WebhookEvent>(
+            r#"
+            SELECT * FROM webhook_events
+            WHERE ($1::TEXT IS NULL OR $1 = ANY(bank_tx_ids))
+              AND ($2::TEXT IS NULL OR qr_ids && ARRAY(
+                  SELECT eulen_id FROM all_pix_transactions WHERE transaction_id = $2
+              ))
+            ORDER BY received_at DESC
+            LIMIT 500
+            "#,
+        )
+        .bind(bank_tx_id)
+        .bind(transaction_id)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Stored events in the order they were received, for replays.
+    pub async fn list_events(
+        &self,
+        source: Option<&str>,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        until: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<WebhookEvent>, anyhow::Error> {
+        let events = sqlx::query_as::<_, WebhookEvent>(
+            r#"
+            SELECT * FROM webhook_events
+            WHERE ($1::TEXT IS NULL OR source = $1)
+              AND ($2::TIMESTAMPTZ IS NULL OR received_at >= $2)
+              AND ($3::TIMESTAMPTZ IS NULL OR received_at < $3)
+            ORDER BY received_at ASC
+            "#,
+        )
+        .bind(source)
+        .bind(since)
+        .bind(until)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(events)
+    }
+}
@@ -1,18 +1,46 @@
 use async_trait::async_trait;
 use sqlx::PgPool;
-use tokio::sync::mpsc;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex, Semaphore};
+use tokio::task::{JoinError, JoinSet};
 
+use crate::models::price::QuoteCurrency;
+use crate::repositories::otc::OtcRepository;
 use crate::settings::Settings;
+use crate::utils::clock;
+use crate::utils::latency;
+use crate::utils::metrics;
+use crate::utils::signing::DocumentSigner;
 
+mod analytics;
+mod archive;
+mod compliance;
 mod database;
+mod events;
+mod fee_addresses;
+mod hedging;
 mod http;
+mod jobs;
+mod leader;
 mod liquid;
 mod liquidity;
+mod merchants;
+mod notifications;
 mod pix;
 mod price;
+mod reconciliation;
+mod referrals;
+mod reports;
+mod scheduler;
 mod sideswap;
+mod snapshots;
+mod spending;
+mod supervisor;
 mod transactions;
 mod users;
+mod venues;
 
 #[derive(Debug, thiserror::Error)]
 enum ServiceError {
@@ -26,6 +54,9 @@ enum ServiceError {
     Communication(String, String),
     #[error("External service error: {0} -> {1} => {2}")]
     ExternalService(String, String, String),
+    /// Error object Sideswap answered a call with.
+    #[error("{0}")]
+    Sideswap(sideswap::SideswapError),
 }
 
 #[async_trait]
@@ -36,152 +67,807 @@ where
     async fn handle_request(&self, request: T);
 }
 
+/// Bound on the requests a service handles at once.
+#[derive(Clone, Copy, Debug)]
+pub struct WorkerPool {
+    pub name: &'static str,
+    pub size: usize,
+}
+
 #[async_trait]
 pub trait Service<T, H>: Send + Sync + 'static
 where
     T: Send + 'static,
     H: RequestHandler<T> + Clone + Send,
 {
+    /// Without a pool every request gets its own task.
+    fn worker_pool(&self) -> Option<WorkerPool> {
+        None
+    }
+
     async fn run(&mut self, handler: H, receiver: &mut mpsc::Receiver<T>) {
-        while let Some(request) = receiver.recv().await {
-            let handler = handler.clone();
+        if let Some(pool) = self.worker_pool() {
+            return run_pooled(pool, handler, receiver).await;
+        }
+
+        let mut handlers = JoinSet::new();
+        loop {
+            tokio::select! {
+                request = receiver.recv() => {
+                    let Some(request) = request else {
+                        break;
+                    };
+                    let handler = handler.clone();
 
-            tokio::spawn(async move {
-                handler.handle_request(request).await;
-            });
+                    handlers.spawn(async move {
+                        handler.handle_request(request).await;
+                    });
+                }
+                Some(handled) = handlers.join_next() => raise_panic(handled, &mut handlers),
+            }
         }
+
+        handlers.detach_all();
+    }
+}
+
+/// Panics the service when a request handler panicked, so the supervisor
+/// restarts it. The other requests still being handled run to the end.
+fn raise_panic(handled: Result<(), JoinError>, handlers: &mut JoinSet<()>) {
+    if let Err(e) = handled {
+        if e.is_panic() {
+            handlers.detach_all();
+            std::panic::resume_unwind(e.into_panic());
+        }
+    }
+}
+
+/// Handles at most `pool.size` requests at once; the rest wait in the
+/// channel, so a burst can't take every database connection.
+async fn run_pooled<T, H>(pool: WorkerPool, handler: H, receiver: &mut mpsc::Receiver<T>)
+where
+    T: Send + 'static,
+    H: RequestHandler<T> + Clone + Send,
+{
+    let size = pool.size.max(1);
+    let workers = Arc::new(Semaphore::new(size));
+    let labels = [("service", pool.name)];
+    metrics::set_gauge("service_workers", &labels, size as f64);
+
+    let mut handlers = JoinSet::new();
+    loop {
+        let request = tokio::select! {
+            request = receiver.recv() => request,
+            Some(handled) = handlers.join_next() => {
+                raise_panic(handled, &mut handlers);
+                continue;
+            }
+        };
+        let Some(request) = request else {
+            break;
+        };
+
+        let permit = match workers.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                metrics::increment("service_saturated_total", &labels);
+                let waiting_since = tokio::time::Instant::now();
+                let Ok(permit) = workers.clone().acquire_owned().await else {
+                    return;
+                };
+                metrics::observe(
+                    "service_worker_wait_seconds",
+                    &labels,
+                    waiting_since.elapsed().as_secs_f64(),
+                );
+                permit
+            }
+        };
+        metrics::set_gauge(
+            "service_workers_busy",
+            &labels,
+            (size - workers.available_permits()) as f64,
+        );
+
+        let handler = handler.clone();
+        let workers = workers.clone();
+        let name = pool.name;
+        handlers.spawn(async move {
+            handler.handle_request(request).await;
+
+            drop(permit);
+            metrics::set_gauge(
+                "service_workers_busy",
+                &[("service", name)],
+                (size - workers.available_permits()) as f64,
+            );
+        });
     }
+
+    handlers.detach_all();
+}
+
+/// Runs a request service under the supervisor. The receiver is kept across
+/// restarts so the senders held by the other services stay connected;
+/// `handler` builds a new handler on every start, and the service is ready
+/// once it succeeded.
+fn supervise_service<T, H, S, N, F, Fut>(
+    health: &supervisor::ServiceHealth,
+    name: &'static str,
+    affects: &'static [&'static str],
+    receiver: mpsc::Receiver<T>,
+    new_service: N,
+    handler: F,
+) where
+    T: Send + 'static,
+    H: RequestHandler<T> + Clone + Send,
+    S: Service<T, H>,
+    N: Fn() -> S + Send + Sync + 'static,
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<H, anyhow::Error>> + Send + 'static,
+{
+    let receiver = Arc::new(Mutex::new(receiver));
+    let new_service = Arc::new(new_service);
+    let handler = Arc::new(handler);
+
+    supervisor::supervise(health, name, affects, move |readiness| {
+        let receiver = receiver.clone();
+        let new_service = new_service.clone();
+        let handler = handler.clone();
+
+        // Built inside the supervised task, so a panicking constructor is
+        // retried like one returning an error
+        async move {
+            let handler = handler().await?;
+            readiness.set_ready();
+
+            let mut receiver = receiver.lock().await;
+            new_service().run(handler, &mut receiver).await;
+            Ok(())
+        }
+    });
+}
+
+/// Like `supervise_service`, for services that move money: they only start
+/// once this instance is the leader, and are on standby until then.
+fn supervise_leader_service<T, H, S, N, F, Fut>(
+    health: &supervisor::ServiceHealth,
+    leadership: &leader::Leadership,
+    name: &'static str,
+    affects: &'static [&'static str],
+    receiver: mpsc::Receiver<T>,
+    new_service: N,
+    handler: F,
+) where
+    T: Send + 'static,
+    H: RequestHandler<T> + Clone + Send,
+    S: Service<T, H>,
+    N: Fn() -> S + Send + Sync + 'static,
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<H, anyhow::Error>> + Send + 'static,
+{
+    let standby_health = health.clone();
+    let leadership = leadership.clone();
+    let handler = Arc::new(handler);
+
+    supervise_service(health, name, affects, receiver, new_service, move || {
+        let health = standby_health.clone();
+        let leadership = leadership.clone();
+        let handler = handler.clone();
+
+        async move {
+            if !leadership.is_leader() {
+                health.set_standby(name);
+                leadership.acquired().await;
+            }
+            handler().await
+        }
+    });
 }
 
 pub async fn start_services(pool: PgPool, settings: Settings) -> Result<(), anyhow::Error> {
-    let (transaction_tx, mut transaction_rx) = mpsc::channel(512);
-    let (liquid_tx, mut liquid_rx) = mpsc::channel(512);
-    let (liquidity_tx, mut liquidity_rx) = mpsc::channel(512);
-    let (pix_tx, mut pix_rx) = mpsc::channel(512);
-    let (price_tx, mut price_rx) = mpsc::channel(512);
-    let (sideswap_tx, mut sideswap_rx) = mpsc::channel(512);
-    let (user_tx, mut user_rx) = mpsc::channel(512);
-
-    let mut transaction_service = transactions::TransactionService::new();
-    let mut liquid_service = liquid::LiquidService::new();
-    let mut liquidity_service = liquidity::LiquidityService::new();
-    let mut price_service = price::PriceService::new();
-    let mut pix_service = pix::PixService::new();
-    let mut sideswap_service = sideswap::SideswapService::new();
-    let mut user_service = users::UserService::new();
+    let clock = clock::system();
+    let (transaction_tx, transaction_rx) = mpsc::channel(512);
+    let (liquid_tx, liquid_rx) = mpsc::channel(512);
+    let (liquidity_tx, liquidity_rx) = mpsc::channel(512);
+    let (notification_tx, notification_rx) = mpsc::channel(512);
+    let (compliance_tx, compliance_rx) = mpsc::channel(512);
+    let (hedging_tx, hedging_rx) = mpsc::channel(512);
+    let (event_tx, event_rx) = mpsc::channel(512);
+    let (analytics_tx, analytics_rx) = mpsc::channel(512);
+    let (merchant_tx, merchant_rx) = mpsc::channel(512);
+    let (pix_tx, pix_rx) = mpsc::channel(512);
+    let (price_tx, price_rx) = mpsc::channel(512);
+    let (sideswap_tx, sideswap_rx) = mpsc::channel(512);
+    let (snapshot_tx, snapshot_rx) = mpsc::channel(16);
+    let (scheduler_tx, scheduler_rx) = mpsc::channel(512);
+    let (user_tx, user_rx) = mpsc::channel(512);
+    let (report_tx, report_rx) = mpsc::channel(16);
+
+    jobs::configure(&settings.jobs.schedules)?;
+    latency::configure(settings.latency.clone());
+    let leadership = leader::start_election(pool.clone(), settings.leader.clone());
+    let health = supervisor::ServiceHealth::new(leadership.clone());
+    database::start_pool_metrics(pool.clone());
+
+    // Sideswap first, so it is preferred when no venue can estimate
+    let mut venues: Vec<Arc<dyn venues::SwapVenue>> =
+        vec![Arc::new(venues::SideswapVenue::new(sideswap_tx.clone()))];
+    if !settings.otc.url.is_empty() {
+        let otc = venues::otc::OtcVenue::new(
+            OtcRepository::new(
+                pool.clone(),
+                settings.otc.url.clone(),
+                settings.otc.api_key.clone(),
+            ),
+            liquid_tx.clone(),
+            transaction_tx.clone(),
+            hedging_tx.clone(),
+            settings.dry_run,
+        );
+        let settlement_check = otc.clone();
+        let settlement_interval = Duration::from_secs(settings.otc.poll_interval_secs);
+        let settlement_leadership = leadership.clone();
+        tokio::spawn(async move {
+            settlement_leadership.acquired().await;
+            settlement_check.start_settlement_check(settlement_interval);
+        });
+        venues.push(Arc::new(otc));
+    }
+    let swap_router = venues::SwapRouter::new(venues);
+
+    println!("[*] Starting notification service.");
+    let notification_settings = settings.notifications.clone();
+    let notification_pool = pool.clone();
+    supervise_service(
+        &health,
+        "notification",
+        &["alerts", "user_notices", "receipts"],
+        notification_rx,
+        notifications::NotificationService::new,
+        move || {
+            let settings = notification_settings.clone();
+            let pool = notification_pool.clone();
+            async move {
+                Ok(notifications::NotificationRequestHandler::new(
+                    pool, settings,
+                ))
+            }
+        },
+    );
+
+    println!("[*] Starting event service.");
+    let event_settings = settings.events.clone();
+    supervise_leader_service(
+        &health,
+        &leadership,
+        "event",
+        &["event_publishing"],
+        event_rx,
+        events::EventService::new,
+        move || {
+            let settings = event_settings.clone();
+            async move { Ok(events::EventRequestHandler::new(settings)) }
+        },
+    );
+
+    // Every instance exports the events of the requests it serves
+    println!("[*] Starting analytics service.");
+    let analytics_settings = settings.analytics.clone();
+    supervise_service(
+        &health,
+        "analytics",
+        &["analytics"],
+        analytics_rx,
+        analytics::AnalyticsService::new,
+        move || {
+            let settings = analytics_settings.clone();
+            async move { Ok(analytics::AnalyticsRequestHandler::new(settings)) }
+        },
+    );
+
+    println!("[*] Starting compliance service.");
+    let compliance_pool_clone = pool.clone();
+    let compliance_notification_tx = notification_tx.clone();
+    let compliance_settings = settings.compliance.clone();
+    supervise_service(
+        &health,
+        "compliance",
+        &["payouts"],
+        compliance_rx,
+        compliance::ComplianceService::new,
+        move || {
+            let pool = compliance_pool_clone.clone();
+            let settings = compliance_settings.clone();
+            let notification_tx = compliance_notification_tx.clone();
+            async move {
+                Ok(compliance::ComplianceRequestHandler::new(
+                    pool,
+                    settings,
+                    notification_tx,
+                ))
+            }
+        },
+    );
 
     println!("[*] Starting transaction service.");
     let tx_pool_clone = pool.clone();
-    let transaction_liquid_tx = liquid_tx.clone();
-    let transaction_pix_tx = pix_tx.clone();
-    let transaction_price_tx = price_tx.clone();
-    let transaction_sideswap_tx = sideswap_tx.clone();
-    let transaction_user_tx = user_tx.clone();
-    tokio::spawn(async move {
-        transaction_service
-            .run(
-                transactions::TransactionRequestHandler::new(
-                    tx_pool_clone.clone(),
-                    transaction_liquid_tx.clone(),
-                    transaction_pix_tx,
-                    transaction_price_tx,
-                    transaction_user_tx,
-                    transaction_sideswap_tx,
-                ),
-                &mut transaction_rx,
-            )
-            .await;
-    });
+    let transaction_channels = transactions::TransactionChannels {
+        liquid: liquid_tx.clone(),
+        pix: pix_tx.clone(),
+        price: price_tx.clone(),
+        user: user_tx.clone(),
+        swap_router: swap_router.clone(),
+        compliance: compliance_tx.clone(),
+        notification: notification_tx.clone(),
+        hedging: hedging_tx.clone(),
+        event: event_tx.clone(),
+        merchant: merchant_tx.clone(),
+        analytics: analytics_tx.clone(),
+    };
+    let transaction_config = transactions::TransactionConfig {
+        priority_policy: transactions::PayoutPriorityPolicy::new(
+            settings.payouts.small_payout_cents,
+            settings.payouts.starvation_after_secs,
+        ),
+        floor_policy: transactions::PayoutFloorPolicy::new(
+            settings.payouts.min_payouts,
+            settings.payouts.dust_threshold,
+            settings.payouts.below_dust,
+        ),
+        exposure_policy: transactions::PayoutExposurePolicy::new(
+            settings.payouts.max_payout_cents,
+            settings.payouts.max_hourly_payout_cents,
+        ),
+        swap_buffer_bps: settings.payouts.swap_buffer_bps,
+        block_unfundable_deposits: settings.liquidity.block_unfundable_deposits,
+        partial_payouts: settings.payouts.partial_payouts,
+        avoid_change: settings.payouts.avoid_change,
+        disabled_assets: settings.payouts.disabled_assets.clone(),
+        deposit_hours: transactions::DepositHours::new(&settings.availability),
+        stale_payout_after_secs: settings.payouts.stale_payout_after_secs,
+        pricing_watchdog: transactions::PricingWatchdog::new(
+            settings.payouts.max_price_age_secs,
+            health.clone(),
+        ),
+        disputes: settings.disputes.clone(),
+        quotes: settings.quotes.clone(),
+        // PIX deposits settle in BRL
+        quote_currency: QuoteCurrency::Brl,
+    };
+    let transaction_clock = clock.clone();
+    let transaction_workers = settings.workers.transactions;
+    supervise_leader_service(
+        &health,
+        &leadership,
+        "transaction",
+        &["deposits", "payouts", "quotes"],
+        transaction_rx,
+        move || transactions::TransactionService::new(transaction_workers),
+        move || {
+            let handler = transactions::TransactionRequestHandler::new(
+                tx_pool_clone.clone(),
+                transaction_channels.clone(),
+                transaction_config.clone(),
+                transaction_clock.clone(),
+            );
+            async move { Ok(handler.await) }
+        },
+    );
 
     println!("[*] Starting Liquid service.");
     let liquidity_liquid_tx = liquidity_tx.clone();
-    tokio::spawn(async move {
-        let handler = liquid::LiquidRequestHandler::new(
-            liquidity_liquid_tx,
-            settings.wallet.mnemonic,
-            settings.electrum.url,
-            settings.wallet.mainnet,
-        );
+    let liquid_pool_clone = pool.clone();
+    let wallet_mnemonic = settings.wallet.mnemonic.clone();
+    let electrum_url = settings.electrum.url.clone();
+    let is_mainnet = settings.network.is_mainnet();
+    let network_fees = settings.network_fees.clone();
+    let dry_run = settings.dry_run;
+    supervise_leader_service(
+        &health,
+        &leadership,
+        "liquid",
+        &["deposits", "payouts"],
+        liquid_rx,
+        liquid::LiquidService::new,
+        move || {
+            let pool = liquid_pool_clone.clone();
+            let liquidity_tx = liquidity_liquid_tx.clone();
+            let mnemonic = wallet_mnemonic.clone();
+            let electrum_url = electrum_url.clone();
+            let network_fees = network_fees.clone();
+            async move {
+                let handler = liquid::LiquidRequestHandler::new(
+                    pool,
+                    liquidity_tx,
+                    mnemonic,
+                    electrum_url,
+                    is_mainnet,
+                    network_fees,
+                    dry_run,
+                )
+                .await?;
 
-        handler.start().await;
-        liquid_service.run(handler, &mut liquid_rx).await;
-    });
+                handler.start();
+                Ok(handler)
+            }
+        },
+    );
 
     log::info!("Starting liquidity service.");
-    let sideswap_liquidity_tx = sideswap_tx.clone();
-    tokio::spawn(async move {
-        let handler = liquidity::LiquidityHandler::new(
-            settings.liquidity.max_depix_amount,
-            sideswap_liquidity_tx,
-        );
-
-        liquidity_service.run(handler, &mut liquidity_rx).await;
-    });
+    let liquidity_swap_router = swap_router.clone();
+    let notification_liquidity_tx = notification_tx.clone();
+    let liquidity_settings = settings.liquidity.clone();
+    supervise_leader_service(
+        &health,
+        &leadership,
+        "liquidity",
+        &["liquidity_refills"],
+        liquidity_rx,
+        liquidity::LiquidityService::new,
+        move || {
+            let settings = liquidity_settings.clone();
+            let swap_router = liquidity_swap_router.clone();
+            let notification_tx = notification_liquidity_tx.clone();
+            async move {
+                Ok(liquidity::LiquidityHandler::new(
+                    settings.max_depix_amount,
+                    settings.swaps,
+                    settings.low_water_marks,
+                    swap_router,
+                    notification_tx,
+                ))
+            }
+        },
+    );
 
     println!("[*] Starting Pix service.");
     let pix_pool_clone = pool.clone();
     let transaction_tx_clone = transaction_tx.clone();
-    tokio::spawn(async move {
-        pix_service
-            .run(
-                pix::PixRequestHandler::new(
-                    settings.depix.auth_token,
-                    settings.depix.url,
-                    pix_pool_clone,
-                    transaction_tx_clone,
-                ),
-                &mut pix_rx,
-            )
-            .await;
-    });
+    let pix_notification_tx = notification_tx.clone();
+    let depix_settings = settings.depix.clone();
+    let eulen_settings = settings.eulen.clone();
+    supervise_leader_service(
+        &health,
+        &leadership,
+        "pix",
+        &["deposits", "eulen_webhooks"],
+        pix_rx,
+        pix::PixService::new,
+        move || {
+            let depix = depix_settings.clone();
+            let eulen = eulen_settings.clone();
+            let pool = pix_pool_clone.clone();
+            let transaction_tx = transaction_tx_clone.clone();
+            let notification_tx = pix_notification_tx.clone();
+            async move {
+                Ok(pix::PixRequestHandler::new(
+                    depix.auth_token,
+                    depix.url,
+                    eulen,
+                    pool,
+                    transaction_tx,
+                    notification_tx,
+                ))
+            }
+        },
+    );
 
     println!("[*] Starting price service.");
-    tokio::spawn(async move {
-        let handler = price::PriceRequestHandler::new(
-            settings.price_providers.binance_url,
-            settings.price_providers.coingecko_url,
-        );
-        handler.start_price_fetch_task().await;
+    let price_settings = settings.price_providers.clone();
+    supervise_service(
+        &health,
+        "price",
+        &["deposits", "prices", "quotes"],
+        price_rx,
+        price::PriceService::new,
+        move || {
+            let settings = price_settings.clone();
+            async move {
+                let handler = price::PriceRequestHandler::new(
+                    settings.binance_url,
+                    settings.coingecko_url,
+                    settings.aggregation,
+                );
+                handler.start_price_fetch_task();
 
-        price_service.run(handler, &mut price_rx).await;
-    });
+                Ok(handler)
+            }
+        },
+    );
 
     log::info!("Starting Sideswap service.");
-    let sideswap_liquid_tx = liquid_tx.clone();
-    let sideswap_client_tx = sideswap_tx.clone();
-    tokio::spawn(async move {
-        let handler = sideswap::SideswapRequestHandler::new(
-            &settings.sideswap.url,
-            &settings.sideswap.api_key,
-            sideswap_liquid_tx,
-            sideswap_client_tx,
-        )
-        .await;
-
-        sideswap_service.run(handler, &mut sideswap_rx).await;
-    });
+    let sideswap_channels = sideswap::SideswapChannels {
+        liquid: liquid_tx.clone(),
+        transaction: transaction_tx.clone(),
+        hedging: hedging_tx.clone(),
+        price: price_tx.clone(),
+        client: sideswap_tx.clone(),
+    };
+    let sideswap_settings = settings.sideswap.clone();
+    let sideswap_pool_clone = pool.clone();
+    let sideswap_health = health.clone();
+    supervise_leader_service(
+        &health,
+        &leadership,
+        "sideswap",
+        &["swaps", "hedging"],
+        sideswap_rx,
+        move || sideswap::SideswapService::new(sideswap_health.clone()),
+        move || {
+            let settings = sideswap_settings.clone();
+            let channels = sideswap_channels.clone();
+            let pool = sideswap_pool_clone.clone();
+            async move {
+                sideswap::SideswapRequestHandler::new(pool, &settings, channels, dry_run).await
+            }
+        },
+    );
+
+    log::info!("Starting snapshot service.");
+    let snapshot_transaction_tx = transaction_tx.clone();
+    let snapshot_liquid_tx = liquid_tx.clone();
+    let snapshot_sideswap_tx = sideswap_tx.clone();
+    let snapshot_settings = settings.snapshots.clone();
+    supervise_leader_service(
+        &health,
+        &leadership,
+        "snapshot",
+        &["snapshots"],
+        snapshot_rx,
+        snapshots::SnapshotService::new,
+        move || {
+            let settings = snapshot_settings.clone();
+            let transaction_tx = snapshot_transaction_tx.clone();
+            let liquid_tx = snapshot_liquid_tx.clone();
+            let sideswap_tx = snapshot_sideswap_tx.clone();
+            async move {
+                Ok(snapshots::SnapshotRequestHandler::new(
+                    settings.storage_url,
+                    settings.storage_token,
+                    settings.encryption_key,
+                    transaction_tx,
+                    liquid_tx,
+                    sideswap_tx,
+                ))
+            }
+        },
+    );
+
+    println!("[*] Starting scheduler service.");
+    let scheduler_pool_clone = pool.clone();
+    let scheduler_transaction_tx = transaction_tx.clone();
+    let scheduler_notification_tx = notification_tx.clone();
+    supervise_leader_service(
+        &health,
+        &leadership,
+        "scheduler",
+        &["scheduled_buys"],
+        scheduler_rx,
+        scheduler::SchedulerService::new,
+        move || {
+            let handler = scheduler::SchedulerRequestHandler::new(
+                scheduler_pool_clone.clone(),
+                scheduler_transaction_tx.clone(),
+                scheduler_notification_tx.clone(),
+            );
+            async move { Ok(handler) }
+        },
+    );
+
+    println!("[*] Starting hedging service.");
+    let hedging_pool_clone = pool.clone();
+    let hedging_price_tx = price_tx.clone();
+    let hedging_swap_router = swap_router.clone();
+    let hedging_settings = settings.hedging.clone();
+    supervise_leader_service(
+        &health,
+        &leadership,
+        "hedging",
+        &["hedging"],
+        hedging_rx,
+        hedging::HedgingService::new,
+        move || {
+            let handler = hedging::HedgingRequestHandler::new(
+                hedging_pool_clone.clone(),
+                hedging_settings.clone(),
+                hedging_price_tx.clone(),
+                hedging_swap_router.clone(),
+            );
+            async move { Ok(handler) }
+        },
+    );
+
+    println!("[*] Starting merchant service.");
+    let merchant_pool_clone = pool.clone();
+    let merchant_user_tx = user_tx.clone();
+    let merchant_transaction_tx = transaction_tx.clone();
+    let merchant_notification_tx = notification_tx.clone();
+    let merchant_settings = settings.merchants.clone();
+    supervise_service(
+        &health,
+        "merchant",
+        &["merchants"],
+        merchant_rx,
+        merchants::MerchantService::new,
+        move || {
+            let handler = merchants::MerchantRequestHandler::new(
+                merchant_pool_clone.clone(),
+                merchant_settings.default_fee_bps,
+                merchant_settings.public_url.clone(),
+                merchant_settings.invoicing.clone(),
+                merchant_user_tx.clone(),
+                merchant_transaction_tx.clone(),
+                merchant_notification_tx.clone(),
+            );
+            async move { Ok(handler) }
+        },
+    );
 
     println!("[*] Starting user service.");
     let user_pool_clone = pool.clone();
+    let user_workers = settings.workers.users;
+    let user_clock = clock.clone();
+    let statement_signer = match settings.statements.signing_key.as_str() {
+        "" => None,
+        key => Some(DocumentSigner::from_hex(key)?),
+    };
+    supervise_service(
+        &health,
+        "user",
+        &["registration", "deposits"],
+        user_rx,
+        move || users::UserService::new(user_workers),
+        move || {
+            let handler = users::UserRequestHandler::new(
+                user_pool_clone.clone(),
+                statement_signer.clone(),
+                user_clock.clone(),
+            );
+            async move { Ok(handler) }
+        },
+    );
+
+    println!("[*] Starting report service.");
+    let report_pool_clone = pool.clone();
+    let report_liquid_tx = liquid_tx.clone();
+    let report_settings = settings.tax_reports.clone();
+    supervise_service(
+        &health,
+        "report",
+        &["tax_reports", "reserve_proofs"],
+        report_rx,
+        reports::ReportService::new,
+        move || {
+            let handler = reports::ReportRequestHandler::new(
+                report_pool_clone.clone(),
+                report_liquid_tx.clone(),
+                report_settings.clone(),
+            );
+            async move { Ok(handler) }
+        },
+    );
+
+    // Jobs writing to the ledger run on the leader only
+    let job_pool = pool.clone();
+    let job_report_tx = report_tx.clone();
+    let archive_settings = settings.archive.clone();
+    let spending_settings = settings.spending.clone();
+    let tax_report_settings = settings.tax_reports.clone();
+    let reconciliation_liquid_tx = liquid_tx.clone();
+    let reconciliation_notification_tx = notification_tx.clone();
+    let reconciliation_settings = settings.reconciliation.clone();
+    let fee_address_liquid_tx = liquid_tx.clone();
+    let fee_address_notification_tx = notification_tx.clone();
+    let fee_address_settings = settings.fee_addresses.clone();
+    let job_leadership = leadership.clone();
     tokio::spawn(async move {
-        user_service
-            .run(
-                users::UserRequestHandler::new(user_pool_clone),
-                &mut user_rx,
-            )
-            .await;
+        job_leadership.acquired().await;
+        archive::start_archival(job_pool.clone(), archive_settings);
+        spending::start_verification(job_pool.clone(), spending_settings);
+        referrals::start_rollups(job_pool.clone());
+        reconciliation::start_depix_reconciliation(
+            job_pool.clone(),
+            reconciliation_liquid_tx,
+            reconciliation_notification_tx,
+            reconciliation_settings,
+        );
+        fee_addresses::start_fee_address_watch(
+            job_pool,
+            fee_address_liquid_tx,
+            fee_address_notification_tx,
+            fee_address_settings,
+        );
+        reports::start_monthly_reports(job_report_tx, tax_report_settings);
     });
 
     println!("[*] Starting HTTP server.");
-    let http_transaction_tx = transaction_tx.clone();
-    let http_pix_tx = pix_tx.clone();
-    let http_user_tx = user_tx.clone();
-    tokio::spawn(async move {
-        http::start_http_server(http_transaction_tx, http_pix_tx, http_user_tx)
-            .await
-            .expect("Could not start HTTP server.");
+    let http_channels = http::HttpChannels {
+        transaction: transaction_tx.clone(),
+        pix: pix_tx.clone(),
+        user: user_tx.clone(),
+        snapshot: snapshot_tx.clone(),
+        scheduler: scheduler_tx.clone(),
+        merchant: merchant_tx.clone(),
+        price: price_tx.clone(),
+        hedging: hedging_tx.clone(),
+        liquid: liquid_tx.clone(),
+        sideswap: sideswap_tx.clone(),
+        report: report_tx.clone(),
+        analytics: analytics_tx.clone(),
+    };
+    let http_health = health.clone();
+    let admin_api_key = settings.admin.api_key.clone();
+    let dispute_webhook_secret = settings.disputes.webhook_secret.clone();
+    let http_settings = settings.http.clone();
+    supervisor::supervise(&health, "http", &["api"], move |readiness| {
+        let server = http::start_http_server(
+            http_channels.clone(),
+            http_health.clone(),
+            admin_api_key.clone(),
+            dispute_webhook_secret.clone(),
+            http_settings.clone(),
+            dry_run,
+        );
+
+        // Up from the start: money operations are refused until the services
+        // they depend on are ready
+        readiness.set_ready();
+        server
     });
 
     println!("[SUCCESS] Started services.");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct PanickingHandler;
+
+    #[async_trait]
+    impl RequestHandler<bool> for PanickingHandler {
+        async fn handle_request(&self, panic: bool) {
+            if panic {
+                panic!("handler panicked");
+            }
+        }
+    }
+
+    struct PanickingService;
+
+    impl Service<bool, PanickingHandler> for PanickingService {}
+
+    #[tokio::test]
+    async fn a_panicking_handler_restarts_its_service() {
+        let health = supervisor::ServiceHealth::new(leader::Leadership::sole());
+        let (sender, receiver) = mpsc::channel(8);
+        supervise_service(
+            &health,
+            "panicking",
+            &[],
+            receiver,
+            || PanickingService,
+            || async { Ok(PanickingHandler) },
+        );
+
+        sender.send(false).await.unwrap();
+        sender.send(true).await.unwrap();
+
+        let mut state = None;
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            state = health.report().services.get("panicking").cloned();
+            if state.as_ref().is_some_and(|state| state.restarts > 0) {
+                break;
+            }
+        }
+
+        let state = state.unwrap();
+        assert_eq!(state.restarts, 1);
+        assert_eq!(state.status, "restarting");
+        assert_eq!(state.last_error.as_deref(), Some("handler panicked"));
+    }
+}
@@ -0,0 +1,189 @@
+use super::{RequestHandler, Service};
+use crate::models::analytics::AnalyticsEvent;
+use crate::repositories::analytics::AnalyticsRepository;
+use crate::settings::Analytics;
+use crate::utils::metrics;
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Events exported to the sink per call.
+const BATCH_SIZE: usize = 100;
+
+pub enum AnalyticsRequest {
+    /// Step of a user's funnel. `transaction_id` is sent hashed as
+    /// `deposit_id`, so steps of the same deposit can be joined.
+    Track {
+        name: &'static str,
+        user_id: String,
+        transaction_id: Option<String>,
+        properties: serde_json::Value,
+    },
+}
+
+/// Hashes ids, samples users and exports events in the background, so the
+/// sink never sees who a user is nor delays a deposit.
+#[derive(Clone)]
+pub struct AnalyticsRequestHandler {
+    /// None when no sink is configured.
+    buffer: Option<Arc<Mutex<VecDeque<AnalyticsEvent>>>>,
+    buffer_size: usize,
+    id_salt: Arc<String>,
+    sample_rate: f64,
+}
+
+impl AnalyticsRequestHandler {
+    pub fn new(settings: Analytics) -> Self {
+        let mut handler = Self {
+            buffer: None,
+            buffer_size: settings.buffer_size.max(1),
+            id_salt: Arc::new(settings.id_salt.clone()),
+            sample_rate: settings.sample_rate.clamp(0.0, 1.0),
+        };
+
+        let Some(sink) = settings.sink else {
+            return handler;
+        };
+        if settings.id_salt.is_empty() {
+            log::warn!("analytics.id_salt is empty, hashed ids can be matched to user ids");
+        }
+
+        handler.buffer = Some(Arc::new(Mutex::new(VecDeque::new())));
+        handler.start_exporter(
+            AnalyticsRepository::new(sink, &settings),
+            settings.flush_interval_secs.max(1),
+        );
+
+        handler
+    }
+
+    fn start_exporter(&self, repository: AnalyticsRepository, flush_interval_secs: u64) {
+        let Some(buffer) = self.buffer.clone() else {
+            return;
+        };
+
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(tokio::time::Duration::from_secs(flush_interval_secs));
+
+            loop {
+                interval.tick().await;
+                flush(&buffer, &repository).await;
+            }
+        });
+    }
+
+    fn hash_id(&self, id: &str) -> String {
+        let digest = Sha256::digest(format!("{}:{}", self.id_salt, id).as_bytes());
+        format!("{:x}", digest)[..32].to_string()
+    }
+
+    /// Whether the user falls in the sampled share, the same on every call.
+    fn is_sampled(&self, distinct_id: &str) -> bool {
+        let bucket = u32::from_str_radix(&distinct_id[..8], 16).unwrap_or(0);
+        (bucket as f64) < self.sample_rate * u32::MAX as f64
+    }
+
+    async fn track(
+        &self,
+        name: &'static str,
+        user_id: &str,
+        transaction_id: Option<&str>,
+        mut properties: serde_json::Value,
+    ) {
+        let Some(buffer) = &self.buffer else {
+            return;
+        };
+
+        let distinct_id = self.hash_id(user_id);
+        if !self.is_sampled(&distinct_id) {
+            return;
+        }
+        if let Some(transaction_id) = transaction_id {
+            properties["deposit_id"] = serde_json::json!(self.hash_id(transaction_id));
+        }
+
+        let event = AnalyticsEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            distinct_id,
+            properties,
+            timestamp: chrono::Utc::now(),
+        };
+
+        let mut buffer = buffer.lock().await;
+        if buffer.len() >= self.buffer_size {
+            if let Some(dropped) = buffer.pop_front() {
+                metrics::increment("analytics_events_dropped_total", &[("event", dropped.name)]);
+            }
+        }
+        buffer.push_back(event);
+        metrics::set_gauge("analytics_events_buffered", &[], buffer.len() as f64);
+    }
+}
+
+/// Exports the buffered events in order, stopping at the first failure so
+/// the rest are retried on the next tick.
+async fn flush(buffer: &Mutex<VecDeque<AnalyticsEvent>>, repository: &AnalyticsRepository) {
+    loop {
+        let batch: Vec<AnalyticsEvent> = buffer
+            .lock()
+            .await
+            .iter()
+            .take(BATCH_SIZE)
+            .cloned()
+            .collect();
+        if batch.is_empty() {
+            return;
+        }
+
+        if let Err(e) = repository.export(&batch).await {
+            log::warn!("Could not export {} analytics events: {}", batch.len(), e);
+            metrics::increment("analytics_export_failures_total", &[]);
+            return;
+        }
+        metrics::add("analytics_events_exported_total", &[], batch.len() as u64);
+
+        // Events may have been dropped from the front while exporting
+        let exported: HashSet<&str> = batch.iter().map(|event| event.id.as_str()).collect();
+        let mut buffer = buffer.lock().await;
+        while buffer
+            .front()
+            .is_some_and(|event| exported.contains(event.id.as_str()))
+        {
+            buffer.pop_front();
+        }
+        metrics::set_gauge("analytics_events_buffered", &[], buffer.len() as f64);
+    }
+}
+
+#[async_trait]
+impl RequestHandler<AnalyticsRequest> for AnalyticsRequestHandler {
+    async fn handle_request(&self, request: AnalyticsRequest) {
+        match request {
+            AnalyticsRequest::Track {
+                name,
+                user_id,
+                transaction_id,
+                properties,
+            } => {
+                self.track(name, &user_id, transaction_id.as_deref(), properties)
+                    .await
+            }
+        }
+    }
+}
+
+pub struct AnalyticsService;
+
+impl AnalyticsService {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[async_trait]
+impl Service<AnalyticsRequest, AnalyticsRequestHandler> for AnalyticsService {}
@@ -0,0 +1,84 @@
+use super::jobs::{self, JobSchedule};
+use crate::repositories::archive::ArchiveRepository;
+use crate::settings::Archive;
+use crate::utils::metrics;
+
+use anyhow::anyhow;
+use sqlx::PgPool;
+use std::sync::Arc;
+
+/// Moves settled transactions older than `after_months` out of the live
+/// tables, in batches, every `interval_secs`.
+pub fn start_archival(pool: PgPool, settings: Archive) {
+    if !settings.enabled {
+        return;
+    }
+
+    let repository = Arc::new(ArchiveRepository::new(pool));
+    jobs::register(
+        "archive",
+        JobSchedule::every_secs(settings.interval_secs),
+        move || {
+            let repository = repository.clone();
+            let settings = settings.clone();
+            async move {
+                let created_before = chrono::Utc::now()
+                    .checked_sub_months(chrono::Months::new(settings.after_months))
+                    .ok_or_else(|| {
+                        anyhow!("Invalid archive.after_months: {}", settings.after_months)
+                    })?;
+                archive_before(&repository, created_before, settings.batch_size).await
+            }
+        },
+    );
+}
+
+/// Stops at the first failed batch; the batches moved before it stay moved.
+async fn archive_before(
+    repository: &ArchiveRepository,
+    created_before: chrono::DateTime<chrono::Utc>,
+    batch_size: i64,
+) -> Result<(), anyhow::Error> {
+    let (mut transactions, mut pix_transactions) = (0, 0);
+
+    let result = loop {
+        match repository.archive_batch(created_before, batch_size).await {
+            Ok((0, _)) => break Ok(()),
+            Ok((moved, moved_pix)) => {
+                transactions += moved;
+                pix_transactions += moved_pix;
+            }
+            Err(e) => break Err(anyhow!("Archival of transactions failed: {}", e)),
+        }
+    };
+
+    if transactions == 0 {
+        return result;
+    }
+
+    log::info!(
+        "Archived {} transactions and {} PIX charges created before {}",
+        transactions,
+        pix_transactions,
+        created_before
+    );
+    metrics::add(
+        "archived_rows_total",
+        &[("table", "transactions")],
+        transactions,
+    );
+    metrics::add(
+        "archived_rows_total",
+        &[("table", "pix_transactions")],
+        pix_transactions,
+    );
+
+    if let Err(e) = repository
+        .record_run(created_before, transactions, pix_transactions)
+        .await
+    {
+        log::error!("Could not record archival run: {}", e);
+    }
+
+    result
+}
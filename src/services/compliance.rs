@@ -0,0 +1,170 @@
+use super::{notifications::NotificationRequest, RequestHandler, Service, ServiceError};
+use crate::models::compliance::{risk_rank, ScreeningAction, ScreeningResult};
+use crate::repositories::compliance::ComplianceRepository;
+use crate::settings::Compliance;
+use crate::utils::metrics;
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+use tokio::sync::{mpsc, oneshot};
+
+pub enum ComplianceRequest {
+    ScreenAddress {
+        transaction_id: String,
+        address: String,
+        response: oneshot::Sender<Result<ScreeningResult, ServiceError>>,
+    },
+}
+
+#[derive(Clone)]
+pub struct ComplianceRequestHandler {
+    repository: ComplianceRepository,
+    notification_channel: mpsc::Sender<NotificationRequest>,
+    enabled: bool,
+    risk_threshold: u8,
+    denylist_action: ScreeningAction,
+    provider_action: ScreeningAction,
+    fail_closed: bool,
+}
+
+impl ComplianceRequestHandler {
+    pub fn new(
+        sql_conn: PgPool,
+        settings: Compliance,
+        notification_channel: mpsc::Sender<NotificationRequest>,
+    ) -> Self {
+        let repository = ComplianceRepository::new(
+            sql_conn,
+            settings.provider_url,
+            settings.provider_api_key,
+        );
+
+        Self {
+            repository,
+            notification_channel,
+            enabled: settings.enabled,
+            risk_threshold: risk_rank(&settings.risk_threshold),
+            denylist_action: settings.denylist_action,
+            provider_action: settings.provider_action,
+            fail_closed: settings.fail_closed,
+        }
+    }
+
+    async fn screen_address(
+        &self,
+        transaction_id: &str,
+        address: &str,
+    ) -> Result<ScreeningResult, ServiceError> {
+        if !self.enabled {
+            return Ok(ScreeningResult::allow());
+        }
+
+        let result = self.evaluate(address).await?;
+        metrics::increment(
+            "compliance_screenings_total",
+            &[("action", result.action.as_str())],
+        );
+
+        if result.action != ScreeningAction::Allow {
+            log::warn!(
+                "Screening of {} for transaction {}: {:?} ({:?})",
+                address,
+                transaction_id,
+                result.action,
+                result.reason
+            );
+
+            self.repository
+                .record_flag(transaction_id, address, &result)
+                .await
+                .map_err(|e| ServiceError::Repository("Compliance".to_string(), e.to_string()))?;
+        }
+
+        if result.action >= ScreeningAction::Hold {
+            let _ = self
+                .notification_channel
+                .send(NotificationRequest::Alert {
+                    title: format!("Payout {}", result.action.as_str()),
+                    message: format!(
+                        "Transaction {} to {}: {}",
+                        transaction_id,
+                        address,
+                        result.reason.as_deref().unwrap_or("no reason given")
+                    ),
+                })
+                .await;
+        }
+
+        Ok(result)
+    }
+
+    /// The denylist takes precedence; the provider is only asked about
+    /// addresses that are not listed.
+    async fn evaluate(&self, address: &str) -> Result<ScreeningResult, ServiceError> {
+        let denylisted = self
+            .repository
+            .get_denylist_entry(address)
+            .await
+            .map_err(|e| ServiceError::Repository("Compliance".to_string(), e.to_string()))?;
+
+        if let Some(reason) = denylisted {
+            return Ok(ScreeningResult {
+                action: self.denylist_action,
+                source: Some("denylist".to_string()),
+                reason: Some(reason),
+            });
+        }
+
+        if !self.repository.has_provider() {
+            return Ok(ScreeningResult::allow());
+        }
+
+        match self.repository.get_provider_risk(address).await {
+            Ok(risk) if risk_rank(&risk.risk) >= self.risk_threshold => Ok(ScreeningResult {
+                action: self.provider_action,
+                source: Some("provider".to_string()),
+                reason: Some(format!("Risk rated {}", risk.risk)),
+            }),
+            Ok(_) => Ok(ScreeningResult::allow()),
+            Err(e) => {
+                log::error!("Screening provider failed for {}: {}", address, e);
+                Ok(ScreeningResult {
+                    action: if self.fail_closed {
+                        ScreeningAction::Hold
+                    } else {
+                        ScreeningAction::Flag
+                    },
+                    source: Some("provider".to_string()),
+                    reason: Some(format!("Provider unavailable: {}", e)),
+                })
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl RequestHandler<ComplianceRequest> for ComplianceRequestHandler {
+    async fn handle_request(&self, request: ComplianceRequest) {
+        match request {
+            ComplianceRequest::ScreenAddress {
+                transaction_id,
+                address,
+                response,
+            } => {
+                let result = self.screen_address(&transaction_id, &address).await;
+                let _ = response.send(result);
+            }
+        }
+    }
+}
+
+pub struct ComplianceService;
+
+impl ComplianceService {
+    pub fn new() -> Self {
+        ComplianceService {}
+    }
+}
+
+#[async_trait]
+impl Service<ComplianceRequest, ComplianceRequestHandler> for ComplianceService {}
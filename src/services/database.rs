@@ -1 +1,38 @@
+use crate::utils::latency::{self, Dependency};
+use crate::utils::metrics;
 
+use sqlx::PgPool;
+
+const SAMPLE_INTERVAL_SECS: u64 = 10;
+
+/// Samples the connection pool, so saturation shows in the metrics before it
+/// shows as timeouts, and times a round trip to the database.
+pub fn start_pool_metrics(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(tokio::time::Duration::from_secs(SAMPLE_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+
+            let size = pool.size();
+            let idle = pool.num_idle() as u32;
+            metrics::set_gauge(
+                "db_pool_max_connections",
+                &[],
+                pool.options().get_max_connections() as f64,
+            );
+            metrics::set_gauge("db_pool_connections", &[("state", "idle")], idle as f64);
+            metrics::set_gauge(
+                "db_pool_connections",
+                &[("state", "in_use")],
+                size.saturating_sub(idle) as f64,
+            );
+
+            let probe = sqlx::query("SELECT 1").execute(&pool);
+            if let Err(e) = latency::track(Dependency::Postgres, probe).await {
+                log::warn!("Database did not answer the latency probe: {}", e);
+            }
+        }
+    });
+}
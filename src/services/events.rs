@@ -0,0 +1,138 @@
+use super::{RequestHandler, Service};
+use crate::models::events::DomainEvent;
+use crate::repositories::events::EventRepository;
+use crate::settings::Events;
+use crate::utils::metrics;
+
+use async_trait::async_trait;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Events published to the broker per call.
+const BATCH_SIZE: usize = 100;
+
+pub enum EventRequest {
+    Publish { event: DomainEvent },
+}
+
+/// Buffers events in memory and publishes them in the background, so a slow
+/// or unreachable broker never delays a payout.
+#[derive(Clone)]
+pub struct EventRequestHandler {
+    /// None when no broker is configured.
+    buffer: Option<Arc<Mutex<VecDeque<DomainEvent>>>>,
+    buffer_size: usize,
+}
+
+impl EventRequestHandler {
+    pub fn new(settings: Events) -> Self {
+        let Some(broker) = settings.broker else {
+            return Self {
+                buffer: None,
+                buffer_size: 0,
+            };
+        };
+
+        let handler = Self {
+            buffer: Some(Arc::new(Mutex::new(VecDeque::new()))),
+            buffer_size: settings.buffer_size.max(1),
+        };
+        handler.start_publisher(
+            EventRepository::new(broker, &settings),
+            settings.flush_interval_secs.max(1),
+        );
+
+        handler
+    }
+
+    fn start_publisher(&self, mut repository: EventRepository, flush_interval_secs: u64) {
+        let Some(buffer) = self.buffer.clone() else {
+            return;
+        };
+
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(tokio::time::Duration::from_secs(flush_interval_secs));
+
+            loop {
+                interval.tick().await;
+                flush(&buffer, &mut repository).await;
+            }
+        });
+    }
+
+    async fn publish(&self, event: DomainEvent) {
+        let Some(buffer) = &self.buffer else {
+            return;
+        };
+
+        let mut buffer = buffer.lock().await;
+        if buffer.len() >= self.buffer_size {
+            if let Some(dropped) = buffer.pop_front() {
+                log::warn!("Event buffer full, dropping event {}", dropped.id);
+                metrics::increment("events_dropped_total", &[("kind", dropped.kind.as_str())]);
+            }
+        }
+        buffer.push_back(event);
+        metrics::set_gauge("events_buffered", &[], buffer.len() as f64);
+    }
+}
+
+/// Publishes the buffered events in order, stopping at the first failure so
+/// the rest are retried on the next tick.
+async fn flush(buffer: &Mutex<VecDeque<DomainEvent>>, repository: &mut EventRepository) {
+    loop {
+        let batch: Vec<DomainEvent> = buffer
+            .lock()
+            .await
+            .iter()
+            .take(BATCH_SIZE)
+            .cloned()
+            .collect();
+        if batch.is_empty() {
+            return;
+        }
+
+        if let Err(e) = repository.publish(&batch).await {
+            log::warn!("Could not publish {} events: {}", batch.len(), e);
+            metrics::increment("events_publish_failures_total", &[]);
+            return;
+        }
+
+        for event in &batch {
+            metrics::increment("events_published_total", &[("kind", event.kind.as_str())]);
+        }
+
+        // Events may have been dropped from the front while publishing
+        let published: HashSet<&str> = batch.iter().map(|event| event.id.as_str()).collect();
+        let mut buffer = buffer.lock().await;
+        while buffer
+            .front()
+            .is_some_and(|event| published.contains(event.id.as_str()))
+        {
+            buffer.pop_front();
+        }
+        metrics::set_gauge("events_buffered", &[], buffer.len() as f64);
+    }
+}
+
+#[async_trait]
+impl RequestHandler<EventRequest> for EventRequestHandler {
+    async fn handle_request(&self, request: EventRequest) {
+        match request {
+            EventRequest::Publish { event } => self.publish(event).await,
+        }
+    }
+}
+
+pub struct EventService;
+
+impl EventService {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[async_trait]
+impl Service<EventRequest, EventRequestHandler> for EventService {}
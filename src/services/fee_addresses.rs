@@ -0,0 +1,156 @@
+use super::jobs::{self, JobSchedule};
+use super::liquid::LiquidRequest;
+use super::notifications::NotificationRequest;
+use crate::models::transactions::Assets;
+use crate::models::wallet::{ReceivedOutput, WalletAddress};
+use crate::repositories::addresses::AddressRepository;
+use crate::settings::FeeAddresses;
+use crate::utils::metrics;
+
+use anyhow::anyhow;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+/// Every `interval_secs`, attributes the DEPIX that arrived on receive
+/// addresses to the transaction each address was handed out for, and flags
+/// DEPIX arriving where none was expected.
+pub fn start_fee_address_watch(
+    pool: PgPool,
+    liquid_channel: mpsc::Sender<LiquidRequest>,
+    notification_channel: mpsc::Sender<NotificationRequest>,
+    settings: FeeAddresses,
+) {
+    let repository = Arc::new(AddressRepository::new(pool));
+    jobs::register(
+        "fee_address_watch",
+        JobSchedule::every_secs(settings.interval_secs),
+        move || {
+            let repository = repository.clone();
+            let liquid_channel = liquid_channel.clone();
+            let notification_channel = notification_channel.clone();
+            let settings = settings.clone();
+            async move {
+                watch(
+                    &repository,
+                    &liquid_channel,
+                    &notification_channel,
+                    &settings,
+                )
+                .await
+            }
+        },
+    );
+}
+
+async fn watch(
+    repository: &AddressRepository,
+    liquid_channel: &mpsc::Sender<LiquidRequest>,
+    notification_channel: &mpsc::Sender<NotificationRequest>,
+    settings: &FeeAddresses,
+) -> Result<(), anyhow::Error> {
+    let since = chrono::Utc::now() - chrono::Duration::days(settings.lookback_days);
+    let mut outputs = received_depix(liquid_channel, since).await?;
+    if outputs.is_empty() {
+        return Ok(());
+    }
+
+    let txids: Vec<String> = outputs.iter().map(|output| output.txid.clone()).collect();
+    let recorded: HashSet<(String, i32)> = repository
+        .get_recorded_receipts(&txids)
+        .await?
+        .into_iter()
+        .collect();
+    outputs.retain(|output| !recorded.contains(&(output.txid.clone(), output.vout as i32)));
+    // Oldest first, so the first DEPIX on an address is the expected one
+    outputs.sort_by_key(|output| (output.timestamp.is_none(), output.timestamp));
+
+    for output in outputs {
+        let address = repository
+            .get_address_at("external", output.derivation_index)
+            .await?;
+        let status = classify(repository, &output, address.as_ref(), settings).await?;
+
+        let Some(receipt) = repository
+            .insert_receipt(&output, address.as_ref(), status)
+            .await?
+        else {
+            continue;
+        };
+        metrics::increment("fee_address_receipts_total", &[("status", status)]);
+
+        if status != "attributed" {
+            alert(
+                notification_channel,
+                format!(
+                    "{} DEPIX base units arrived in {}:{} on address {} (index {}, transaction {}): {}",
+                    receipt.amount,
+                    receipt.txid,
+                    receipt.vout,
+                    receipt.address.as_deref().unwrap_or("not recorded"),
+                    receipt.derivation_index,
+                    receipt.transaction_id.as_deref().unwrap_or("none"),
+                    status
+                ),
+            )
+            .await;
+        }
+    }
+
+    Ok(())
+}
+
+/// `unassigned` when the address belongs to no transaction, `repeat` when
+/// it already received DEPIX, `late` past `expected_within_secs` after it
+/// was handed out, `attributed` otherwise.
+async fn classify(
+    repository: &AddressRepository,
+    output: &ReceivedOutput,
+    address: Option<&WalletAddress>,
+    settings: &FeeAddresses,
+) -> Result<&'static str, anyhow::Error> {
+    let Some(address) = address.filter(|address| address.transaction_id.is_some()) else {
+        return Ok("unassigned");
+    };
+    if repository.has_receipt(&address.address).await? {
+        return Ok("repeat");
+    }
+
+    let received_at = output.timestamp.unwrap_or_else(chrono::Utc::now);
+    if received_at - address.created_at > chrono::Duration::seconds(settings.expected_within_secs) {
+        return Ok("late");
+    }
+
+    Ok("attributed")
+}
+
+async fn received_depix(
+    liquid_channel: &mpsc::Sender<LiquidRequest>,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<ReceivedOutput>, anyhow::Error> {
+    let (liquid_tx, liquid_rx) = oneshot::channel();
+    liquid_channel
+        .send(LiquidRequest::GetReceivedOutputs {
+            asset_id: Assets::DEPIX.hex(),
+            since,
+            response: liquid_tx,
+        })
+        .await
+        .map_err(|e| anyhow!("Could not reach the Liquid service: {}", e))?;
+
+    liquid_rx
+        .await
+        .map_err(|e| anyhow!("Liquid service dropped the request: {}", e))?
+        .map_err(|e| anyhow!("Could not list received DEPIX: {}", e))
+}
+
+async fn alert(notification_channel: &mpsc::Sender<NotificationRequest>, message: String) {
+    log::warn!("{}", message);
+    let _ = notification_channel
+        .send(NotificationRequest::Alert {
+            title: "Unexpected DEPIX on a fee address".to_string(),
+            message,
+        })
+        .await;
+}
@@ -0,0 +1,303 @@
+use super::jobs::{self, JobSchedule};
+use super::{price::PriceRequest, venues::SwapRouter, RequestHandler, Service, ServiceError};
+use crate::models::hedging::HedgingReport;
+use crate::models::price::QuoteCurrency;
+use crate::models::transactions::Assets;
+use crate::repositories::hedging::HedgingRepository;
+use crate::settings::Hedging;
+use crate::utils::metrics;
+use crate::utils::money;
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+pub enum HedgingRequest {
+    /// A payout was sent; the units it sold join the open exposure.
+    RecordPayout { transaction_id: String },
+    /// A swap settled on any venue. Only swaps placed as hedges are booked.
+    SwapFilled {
+        quote_sub_id: i64,
+        sold_amount: u64,
+        received_amount: u64,
+    },
+    GetReport {
+        response: oneshot::Sender<Result<HedgingReport, ServiceError>>,
+    },
+}
+
+#[derive(Clone)]
+pub struct HedgingRequestHandler {
+    repository: HedgingRepository,
+    settings: Arc<Hedging>,
+    hedged_assets: Arc<Vec<String>>,
+    price_channel: mpsc::Sender<PriceRequest>,
+    swap_router: SwapRouter,
+}
+
+impl HedgingRequestHandler {
+    pub fn new(
+        sql_conn: PgPool,
+        settings: Hedging,
+        price_channel: mpsc::Sender<PriceRequest>,
+        swap_router: SwapRouter,
+    ) -> Self {
+        // DEPIX is what hedges are paid with, it carries no exposure
+        let hedged_assets = settings
+            .thresholds
+            .keys()
+            .filter(|asset| **asset != Assets::DEPIX.hex())
+            .cloned()
+            .collect();
+
+        let handler = Self {
+            repository: HedgingRepository::new(sql_conn),
+            settings: Arc::new(settings),
+            hedged_assets: Arc::new(hedged_assets),
+            price_channel,
+            swap_router,
+        };
+
+        if handler.settings.enabled {
+            handler.start_hedging();
+        }
+
+        handler
+    }
+
+    fn start_hedging(&self) {
+        let handler = self.clone();
+
+        jobs::register(
+            "hedging",
+            JobSchedule::every_secs(self.settings.check_interval_secs),
+            move || {
+                let handler = handler.clone();
+                async move {
+                    handler.check_exposures().await;
+                    Ok(())
+                }
+            },
+        );
+    }
+
+    async fn record_payout(&self, transaction_id: &str) {
+        if !self.settings.enabled {
+            return;
+        }
+
+        if let Err(e) = self
+            .repository
+            .record_payout(transaction_id, &self.hedged_assets)
+            .await
+        {
+            log::error!(
+                "Could not book payout of transaction {} in the exposure ledger: {}",
+                transaction_id,
+                e
+            );
+        }
+    }
+
+    /// Places a hedge for every asset whose exposure, net of the open
+    /// hedges, is above its threshold.
+    async fn check_exposures(&self) {
+        let timeout =
+            chrono::Utc::now() - chrono::Duration::seconds(self.settings.hedge_timeout_secs);
+        match self.repository.expire_hedges(timeout).await {
+            Ok(0) => {}
+            Ok(expired) => {
+                log::warn!("{} hedges did not fill in time", expired);
+                metrics::add("hedges_total", &[("outcome", "expired")], expired);
+            }
+            Err(e) => log::error!("Could not expire stale hedges: {}", e),
+        }
+
+        let exposures = match self.repository.get_exposures().await {
+            Ok(exposures) => exposures,
+            Err(e) => {
+                log::error!("Could not read the exposure ledger: {}", e);
+                return;
+            }
+        };
+
+        for exposure in exposures {
+            metrics::set_gauge(
+                "hedging_open_exposure",
+                &[("asset", &exposure.asset)],
+                exposure.open_amount as f64,
+            );
+
+            let Some(threshold) = self.settings.thresholds.get(&exposure.asset) else {
+                continue;
+            };
+            let unhedged = exposure.open_amount - exposure.pending_amount;
+            if unhedged <= *threshold as i64 {
+                continue;
+            }
+
+            if let Err(e) = self.place_hedge(&exposure.asset, unhedged).await {
+                log::error!(
+                    "Could not hedge {} units of {}: {}",
+                    unhedged,
+                    exposure.asset,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Sells DEPIX for `amount` units of the asset. The hedge is sized at the
+    /// dealer's price, spread included, so it buys slightly more than the
+    /// exposure; the surplus offsets later payouts.
+    async fn place_hedge(&self, asset: &str, amount: i64) -> Result<(), ServiceError> {
+        let price_in_cents = self.request_price_in_cents(asset).await?;
+        // Through cents, at one real per DEPIX
+        let depix_amount = (amount as i128 * price_in_cents as i128 * Assets::DEPIX.unit() as i128
+            / Assets::unit_of(asset) as i128
+            / 100) as i64;
+
+        let map_err =
+            |e: anyhow::Error| ServiceError::Repository("Hedging".to_string(), e.to_string());
+
+        // Recorded first, so the fill of a swap sent always finds its hedge
+        let hedge_id = self
+            .repository
+            .insert_hedge(asset, amount, depix_amount)
+            .await
+            .map_err(map_err)?;
+
+        let quote_sub_id = match self
+            .swap_router
+            .swap(&Assets::DEPIX.hex(), asset, depix_amount)
+            .await
+        {
+            Ok(quote_sub_id) => quote_sub_id,
+            Err(e) => {
+                if let Err(fail_error) = self.repository.fail_hedge(hedge_id).await {
+                    log::error!("Could not close failed hedge {}: {}", hedge_id, fail_error);
+                }
+                metrics::increment("hedges_total", &[("outcome", "failed")]);
+                return Err(e);
+            }
+        };
+
+        if let Err(e) = self.repository.open_hedge(hedge_id, quote_sub_id).await {
+            log::error!(
+                "Hedge {} was placed as swap {} but could not be linked to it: {}",
+                hedge_id,
+                quote_sub_id,
+                e
+            );
+            return Err(map_err(e));
+        }
+
+        log::info!(
+            "Placed hedge {}: selling {} DEPIX for {} units of {}",
+            quote_sub_id,
+            depix_amount,
+            amount,
+            asset
+        );
+        metrics::increment("hedges_total", &[("outcome", "placed")]);
+
+        Ok(())
+    }
+
+    async fn request_price_in_cents(&self, asset: &str) -> Result<u64, ServiceError> {
+        let asset = Assets::from_hex(asset).map_err(ServiceError::Internal)?;
+
+        let (price_tx, price_rx) = oneshot::channel();
+        self.price_channel
+            .send(PriceRequest::GetPrice {
+                asset,
+                currency: QuoteCurrency::Brl,
+                response: price_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Hedging => Price".to_string(), e.to_string())
+            })?;
+
+        let price = price_rx.await.map_err(|e| {
+            ServiceError::Communication("Price => Hedging".to_string(), e.to_string())
+        })??;
+
+        match price {
+            Some(price) if price.price > 0.0 => Ok(money::to_cents(price.price) as u64),
+            _ => Err(ServiceError::Internal("Asset price not found".to_string())),
+        }
+    }
+
+    async fn fill_hedge(&self, quote_sub_id: i64, sold_amount: u64, received_amount: u64) {
+        let hedge = self
+            .repository
+            .fill_hedge(quote_sub_id, sold_amount as i64, received_amount as i64)
+            .await;
+
+        match hedge {
+            Ok(Some(hedge)) => {
+                log::info!(
+                    "Hedge {} filled: {} units of {} for {} DEPIX, realized P&L of {} cents",
+                    quote_sub_id,
+                    received_amount,
+                    hedge.asset,
+                    sold_amount,
+                    hedge.realized_pnl_cents.unwrap_or_default()
+                );
+                metrics::increment("hedges_total", &[("outcome", "filled")]);
+            }
+            Ok(None) => {}
+            Err(e) => log::error!("Could not book fill of hedge {}: {}", quote_sub_id, e),
+        }
+    }
+
+    async fn get_report(&self) -> Result<HedgingReport, ServiceError> {
+        let map_err =
+            |e: anyhow::Error| ServiceError::Repository("Hedging".to_string(), e.to_string());
+
+        Ok(HedgingReport {
+            enabled: self.settings.enabled,
+            exposures: self.repository.get_exposures().await.map_err(map_err)?,
+            hedges: self
+                .repository
+                .get_recent_hedges(50)
+                .await
+                .map_err(map_err)?,
+        })
+    }
+}
+
+#[async_trait]
+impl RequestHandler<HedgingRequest> for HedgingRequestHandler {
+    async fn handle_request(&self, request: HedgingRequest) {
+        match request {
+            HedgingRequest::RecordPayout { transaction_id } => {
+                self.record_payout(&transaction_id).await;
+            }
+            HedgingRequest::SwapFilled {
+                quote_sub_id,
+                sold_amount,
+                received_amount,
+            } => {
+                self.fill_hedge(quote_sub_id, sold_amount, received_amount)
+                    .await;
+            }
+            HedgingRequest::GetReport { response } => {
+                let _ = response.send(self.get_report().await);
+            }
+        }
+    }
+}
+
+pub struct HedgingService;
+
+impl HedgingService {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[async_trait]
+impl Service<HedgingRequest, HedgingRequestHandler> for HedgingService {}
@@ -1,29 +1,155 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    body::Bytes,
+    extract::{Path, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
 use serde::Serialize;
 use serde_json::json;
-use tokio::sync::{mpsc, oneshot};
-use tower_http::trace::TraceLayer;
+use std::sync::Arc;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use tokio::sync::{
+    mpsc::{self, error::SendTimeoutError},
+    oneshot,
+};
 
-use super::{pix::PixServiceRequest, transactions::TransactionServiceRequest, users::UserRequest};
+use super::{
+    analytics::AnalyticsRequest, hedging::HedgingRequest, liquid::LiquidRequest,
+    merchants::MerchantRequest, pix::PixServiceRequest, price::PriceRequest,
+    reports::ReportRequest, scheduler::SchedulerRequest, sideswap::SideswapRequest,
+    snapshots::SnapshotRequest, supervisor::ServiceHealth, transactions::TransactionServiceRequest,
+    users::UserRequest, ServiceError,
+};
 use crate::models::{
-    pix,
-    transactions::{Assets, NewTransaction},
+    analytics::amount_tier,
+    operator::DepositClosure,
+    transactions::{Assets, CancelTransaction, NewTransaction},
     users::NewUser,
+    webhooks::RawWebhook,
 };
+use crate::settings;
+use crate::utils::metrics;
+use validation::ValidJson;
 
+mod admin;
+mod campaigns;
+mod dashboard;
+mod disputes;
+mod jobs;
+mod listeners;
+mod logging;
+mod maintenance;
+mod merchants;
+mod prices;
+mod referrals;
+mod refunds;
+mod replay;
+mod reports;
+mod reviews;
+mod schedules;
+mod search;
+mod security;
+mod status;
 mod users;
+mod validation;
+mod wallet;
+
+/// Services the HTTP API forwards requests to.
+#[derive(Clone)]
+pub struct HttpChannels {
+    pub transaction: mpsc::Sender<TransactionServiceRequest>,
+    pub pix: mpsc::Sender<PixServiceRequest>,
+    pub user: mpsc::Sender<UserRequest>,
+    pub snapshot: mpsc::Sender<SnapshotRequest>,
+    pub scheduler: mpsc::Sender<SchedulerRequest>,
+    pub merchant: mpsc::Sender<MerchantRequest>,
+    pub price: mpsc::Sender<PriceRequest>,
+    pub hedging: mpsc::Sender<HedgingRequest>,
+    pub liquid: mpsc::Sender<LiquidRequest>,
+    pub sideswap: mpsc::Sender<SideswapRequest>,
+    pub report: mpsc::Sender<ReportRequest>,
+    pub analytics: mpsc::Sender<AnalyticsRequest>,
+}
 
 #[derive(Clone)]
 struct AppState {
     transaction_channel: mpsc::Sender<TransactionServiceRequest>,
     pix_channel: mpsc::Sender<PixServiceRequest>,
     user_channel: mpsc::Sender<UserRequest>,
+    snapshot_channel: mpsc::Sender<SnapshotRequest>,
+    scheduler_channel: mpsc::Sender<SchedulerRequest>,
+    merchant_channel: mpsc::Sender<MerchantRequest>,
+    price_channel: mpsc::Sender<PriceRequest>,
+    hedging_channel: mpsc::Sender<HedgingRequest>,
+    liquid_channel: mpsc::Sender<LiquidRequest>,
+    sideswap_channel: mpsc::Sender<SideswapRequest>,
+    report_channel: mpsc::Sender<ReportRequest>,
+    analytics_channel: mpsc::Sender<AnalyticsRequest>,
+    health: ServiceHealth,
+    status_cache: Arc<status::StatusCache>,
+    admin_api_key: Arc<String>,
+    dispute_webhook_secret: Arc<String>,
+    dry_run: bool,
+}
+
+/// Header carrying Eulen's shared secret, on the dispute and batch status
+/// webhooks.
+const WEBHOOK_SECRET: &str = "x-webhook-secret";
+/// How long a handler waits for room in a service's queue before shedding the
+/// request, instead of holding the connection until the service catches up.
+const QUEUE_TIMEOUT: Duration = Duration::from_millis(500);
+/// Seconds shed clients are asked to wait before retrying.
+const RETRY_AFTER_SECS: &str = "2";
+
+enum QueueError {
+    /// The queue stayed full for `QUEUE_TIMEOUT`.
+    Saturated,
+    Closed,
+}
+
+impl IntoResponse for QueueError {
+    fn into_response(self) -> Response {
+        match self {
+            QueueError::Saturated => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(header::RETRY_AFTER, RETRY_AFTER_SECS)],
+                Json(json!({
+                    "error": "Service overloaded",
+                    "details": "Serviço sobrecarregado, tente novamente em instantes."
+                })),
+            )
+                .into_response(),
+            QueueError::Closed => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Internal server error",
+                    "details": "channel closed"
+                })),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Hands a request to a service, giving up when its queue stays full.
+async fn enqueue<T>(
+    channel: &mpsc::Sender<T>,
+    service: &'static str,
+    request: T,
+) -> Result<(), QueueError> {
+    match channel.send_timeout(request, QUEUE_TIMEOUT).await {
+        Ok(()) => Ok(()),
+        Err(SendTimeoutError::Timeout(_)) => {
+            log::warn!("{} queue is full, shedding request", service);
+            metrics::increment("http_requests_shed_total", &[("service", service)]);
+            Err(QueueError::Saturated)
+        }
+        Err(SendTimeoutError::Closed(_)) => Err(QueueError::Closed),
+    }
 }
 
 #[derive(Serialize)]
@@ -35,7 +161,7 @@ struct DepositResponse {
 
 async fn create_new_user(
     State(state): State<AppState>,
-    Json(req): Json<NewUser>,
+    ValidJson(req): ValidJson<NewUser>,
 ) -> impl IntoResponse {
     log::debug!("[DEBUG] Received new user registration request");
     let (user_tx, user_rx) = oneshot::channel();
@@ -44,6 +170,7 @@ async fn create_new_user(
         .user_channel
         .send(UserRequest::CreateUser {
             referral_code: req.referral_code,
+            installation_id: req.installation_id,
             response: user_tx,
         })
         .await;
@@ -61,7 +188,8 @@ async fn create_new_user(
     match user_rx.await {
         Ok(Ok(user)) => {
             dbg!(&user.id);
-            return (StatusCode::CREATED, Json(json!({"user_id": user.id})));
+            // Returned once, for the user's own routes
+            return users::new_user_key(&state, user.id).await;
         }
         Ok(Err(service_error)) => {
             log::error!("Database error: {}", service_error);
@@ -142,8 +270,8 @@ async fn get_user_daily_spending(
 
 async fn request_new_deposit(
     State(state): State<AppState>,
-    Json(req): Json<NewTransaction>,
-) -> impl IntoResponse {
+    ValidJson(req): ValidJson<NewTransaction>,
+) -> Response {
     log::debug!("Received new deposit request: {:?}", req);
     let (transaction_tx, transaction_rx) = oneshot::channel();
 
@@ -154,29 +282,71 @@ async fn request_new_deposit(
                 "error": "Invalid asset",
                 "details": "Em breve!"
             })),
-        );
+        )
+            .into_response();
     }
 
-    let tx_result = state
-        .transaction_channel
-        .send(TransactionServiceRequest::NewTransaction {
-            user_id: req.user_id,
-            address: req.address,
-            amount_in_cents: req.amount_in_cents,
-            asset: req.asset,
-            network: req.network,
-            response: transaction_tx,
-        })
-        .await;
+    let user_id = req.user_id.clone();
+    let mut properties = json!({
+        "asset": req.asset,
+        "network": req.network,
+        "amount_tier": amount_tier(req.amount_in_cents),
+        "quoted": req.quote_id.is_some(),
+        "scheduled": req.scheduled_delivery_at.is_some(),
+    });
 
-    if let Err(e) = tx_result {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"description": format!("Failed to process request: {}", e)})),
+    let request = TransactionServiceRequest::NewTransaction {
+        user_id: req.user_id,
+        address: req.address,
+        amount_in_cents: req.amount_in_cents,
+        asset: req.asset,
+        network: req.network,
+        quote_id: req.quote_id,
+        scheduled_delivery_at: req.scheduled_delivery_at,
+        response: transaction_tx,
+    };
+    if let Err(e) = enqueue(&state.transaction_channel, "transactions", request).await {
+        return e.into_response();
+    }
+
+    let result = transaction_rx.await;
+
+    // Refusals are funnel drop-offs too
+    properties["outcome"] = match &result {
+        Ok(Ok(_)) => json!("created"),
+        Ok(Err(ServiceError::Internal(reason)))
+            if reason.chars().all(|c| c.is_ascii_alphanumeric()) =>
+        {
+            json!(reason)
+        }
+        _ => json!("error"),
+    };
+    let analytics = AnalyticsRequest::Track {
+        name: "deposit_requested",
+        user_id,
+        transaction_id: result
+            .as_ref()
+            .ok()
+            .and_then(|result| result.as_ref().ok())
+            .map(|deposit| deposit.id.clone()),
+        properties,
+    };
+    if state.analytics_channel.try_send(analytics).is_err() {
+        metrics::increment(
+            "analytics_events_dropped_total",
+            &[("event", "deposit_requested")],
         );
     }
 
-    match transaction_rx.await {
+    // Closed deposits are answered with when they reopen
+    let closure = match &result {
+        Ok(Err(ServiceError::Internal(reason))) if reason == "DepositsClosed" => {
+            deposit_closure(&state).await
+        }
+        _ => None,
+    };
+
+    let response = match result {
         Ok(Ok(deposit)) => {
             log::debug!("Deposit created: {:?}", deposit);
             let response = DepositResponse {
@@ -186,6 +356,86 @@ async fn request_new_deposit(
             };
             (StatusCode::CREATED, Json(json!(response)))
         }
+        Ok(Err(ServiceError::Internal(reason))) if reason == "DeviceLimitExceeded" => (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "Daily limit exceeded",
+                "details": "Limite diário excedido."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "OpenDispute" => (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "Open PIX dispute",
+                "details": "Há uma contestação de PIX (MED) em aberto na sua conta, entre em contato com o suporte."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "PayoutBelowMinimum" => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": "Amount below minimum payout",
+                "details": "Valor abaixo do mínimo para este ativo, tente um valor maior."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "InvalidRequest" => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": "Invalid request",
+                "details": "Dados inválidos, verifique os campos informados."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "PaymentAmountMismatch" => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": "Payment URI amount mismatch",
+                "details": "O valor da URI de pagamento não corresponde ao valor do depósito."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "InvalidDeliveryTime" => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": "Invalid delivery time",
+                "details": "Escolha um horário de entrega futuro, em até 7 dias."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "InvalidQuote" => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": "Invalid or expired quote",
+                "details": "Cotação expirada ou inválida, atualize a cotação e tente novamente."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "DealerPaused" => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "Service paused",
+                "details": "Serviço pausado para manutenção, tente novamente mais tarde."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "DepositsClosed" => {
+            deposits_closed(closure)
+        }
+        Ok(Err(ServiceError::Internal(reason))) if reason == "AssetUnavailable" => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "Asset temporarily unavailable",
+                "details": "Ativo temporariamente indisponível, escolha outro ativo ou tente novamente mais tarde."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "PixUnavailable" => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "PIX temporarily unavailable",
+                "details": "PIX temporariamente indisponível, tente novamente em alguns minutos."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "InsufficientLiquidity" => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "Insufficient liquidity",
+                "details": "Valor indisponível no momento, tente um valor menor."
+            })),
+        ),
         Ok(Err(service_error)) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(
@@ -198,38 +448,275 @@ async fn request_new_deposit(
                 json!({"error": format!("Failed to receive response: {}", e), "details": e.to_string()}),
             ),
         ),
+    };
+
+    response.into_response()
+}
+
+async fn deposit_closure(state: &AppState) -> Option<DepositClosure> {
+    let (closure_tx, closure_rx) = oneshot::channel();
+    state
+        .transaction_channel
+        .send(TransactionServiceRequest::GetDepositClosure {
+            response: closure_tx,
+        })
+        .await
+        .ok()?;
+
+    closure_rx.await.ok().flatten()
+}
+
+/// Scheduled unavailability, with the reopening time in local time.
+fn deposits_closed(closure: Option<DepositClosure>) -> (StatusCode, Json<serde_json::Value>) {
+    let closed_for = closure
+        .as_ref()
+        .map_or("maintenance", |closure| closure.closed_for);
+    let reopens_at = closure.as_ref().and_then(|closure| closure.reopens_at);
+    let details = match closed_for {
+        "business_hours" => "Depósitos disponíveis apenas no horário de atendimento.",
+        _ => "Depósitos pausados para manutenção programada.",
+    };
+    let details = match reopens_at {
+        Some(reopens_at) => format!(
+            "{} Voltamos em {}.",
+            details,
+            reopens_at.format("%d/%m às %H:%M")
+        ),
+        None => format!("{} Tente novamente mais tarde.", details),
+    };
+
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({
+            "error": "Deposits closed",
+            "closed_for": closed_for,
+            "reopens_at": reopens_at,
+            "details": details
+        })),
+    )
+}
+
+async fn get_transaction(
+    State(state): State<AppState>,
+    Path(transaction_id): Path<String>,
+) -> Response {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let request = TransactionServiceRequest::GetTransaction {
+        transaction_id: transaction_id.clone(),
+        response: transaction_tx,
+    };
+    if let Err(e) = enqueue(&state.transaction_channel, "transactions", request).await {
+        return e.into_response();
     }
+
+    let response = match transaction_rx.await {
+        Ok(Ok(Some(transaction))) => {
+            let partial_payouts = match get_partial_payouts(&state, transaction_id).await {
+                Ok(partial_payouts) => partial_payouts,
+                Err(response) => return response,
+            };
+
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "id": transaction.id,
+                    "user_id": transaction.user_id,
+                    "amount_in_cents": transaction.amount_in_cents,
+                    "asset": transaction.asset,
+                    "network": transaction.network,
+                    "status": transaction.status,
+                    "partial_payouts": partial_payouts,
+                    "created_at": transaction.created_at,
+                    "updated_at": transaction.updated_at
+                })),
+            )
+        }
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Transaction not found"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Database error",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    };
+
+    response.into_response()
 }
 
-async fn eulen_update_status(
+/// Cancels a deposit before its PIX charge is paid.
+async fn cancel_transaction(
     State(state): State<AppState>,
-    Json(req): Json<pix::EulenDepositStatus>,
-) -> impl IntoResponse {
-    println!("Received request: {:?}", req);
-    let (pix_tx, pix_rx) = oneshot::channel();
+    Path(transaction_id): Path<String>,
+    ValidJson(req): ValidJson<CancelTransaction>,
+) -> Response {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
 
-    dbg!("Initialized oneshot channel");
-    let pix_result = state
-        .pix_channel
-        .send(PixServiceRequest::UpdateEulenStatus {
-            eulen_status: req,
-            response: pix_tx,
-        })
-        .await;
+    let request = TransactionServiceRequest::CancelTransaction {
+        transaction_id,
+        user_id: req.user_id,
+        response: transaction_tx,
+    };
+    if let Err(e) = enqueue(&state.transaction_channel, "transactions", request).await {
+        return e.into_response();
+    }
 
-    if let Err(e) = pix_result {
-        dbg!("Failed to send to PIX channel");
-        return (
+    let response = match transaction_rx.await {
+        Ok(Ok(Some(transaction))) => (
+            StatusCode::OK,
+            Json(json!({
+                "id": transaction.id,
+                "status": transaction.status,
+                "updated_at": transaction.updated_at
+            })),
+        ),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Transaction not found"
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "TransactionNotCancellable" => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Transaction not cancellable",
+                "details": "O PIX deste depósito já foi pago ou o depósito não está mais pendente."
+            })),
+        ),
+        Ok(Err(service_error)) => (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"description": format!("Failed to process request: {}", e)})),
-        );
+            Json(json!({
+                "error": "Database error",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    };
+
+    response.into_response()
+}
+
+/// Parts of the payout already sent, for transactions paid out in parts.
+async fn get_partial_payouts(
+    state: &AppState,
+    transaction_id: String,
+) -> Result<Vec<serde_json::Value>, Response> {
+    let (partials_tx, partials_rx) = oneshot::channel();
+
+    let request = TransactionServiceRequest::GetPartialPayouts {
+        transaction_id,
+        response: partials_tx,
+    };
+    if let Err(e) = enqueue(&state.transaction_channel, "transactions", request).await {
+        return Err(e.into_response());
+    }
+
+    match partials_rx.await {
+        Ok(Ok(partials)) => Ok(partials
+            .into_iter()
+            .map(|partial| {
+                json!({
+                    "amount": partial.amount,
+                    "txid": partial.txid,
+                    "sent_at": partial.created_at,
+                })
+            })
+            .collect()),
+        Ok(Err(service_error)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Database error",
+                "details": service_error.to_string()
+            })),
+        )
+            .into_response()),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response()),
+    }
+}
+
+/// Whether the webhook carries Eulen's shared secret. Never while none is
+/// configured.
+fn has_webhook_secret(state: &AppState, headers: &HeaderMap) -> bool {
+    let secret = state.dispute_webhook_secret.as_bytes();
+    !secret.is_empty()
+        && headers
+            .get(WEBHOOK_SECRET)
+            .is_some_and(|value| bool::from(value.as_bytes().ct_eq(secret)))
+}
+
+/// Keeps the webhook as received. Credentials are left out of the stored
+/// headers.
+fn raw_webhook(headers: &HeaderMap, body: &Bytes) -> RawWebhook {
+    let headers: serde_json::Map<String, serde_json::Value> = headers
+        .iter()
+        .filter(|(name, _)| {
+            *name != header::AUTHORIZATION && *name != header::COOKIE && *name != WEBHOOK_SECRET
+        })
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                json!(String::from_utf8_lossy(value.as_bytes())),
+            )
+        })
+        .collect();
+
+    RawWebhook {
+        payload: String::from_utf8_lossy(body).into_owned(),
+        headers: serde_json::Value::Object(headers).to_string(),
+    }
+}
+
+async fn eulen_update_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let webhook = raw_webhook(&headers, &body);
+    let (pix_tx, pix_rx) = oneshot::channel();
+
+    let request = PixServiceRequest::UpdateEulenStatus {
+        webhook,
+        response: pix_tx,
     };
+    if let Err(e) = enqueue(&state.pix_channel, "pix", request).await {
+        return e.into_response();
+    }
 
-    match pix_rx.await {
-        Ok(Ok(update)) => (
+    let response = match pix_rx.await {
+        Ok(Ok(())) => (
             StatusCode::OK,
             Json(json!({"description": "Status updated successfully"})),
         ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "InvalidWebhookPayload" => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"description": "Invalid payload."})),
+        ),
         Ok(Err(service_error)) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({"description": format!("Internal server error.")})),
@@ -238,34 +725,304 @@ async fn eulen_update_status(
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({"description": format!("Failed to receive response: {}", e)})),
         ),
+    };
+
+    response.into_response()
+}
+
+/// Backfills after an outage. Only accepted with the shared secret, since
+/// each update can mark a deposit paid.
+async fn eulen_update_status_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if !has_webhook_secret(&state, &headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"description": "Unauthorized"})),
+        )
+            .into_response();
+    }
+
+    let (pix_tx, pix_rx) = oneshot::channel();
+
+    let request = PixServiceRequest::UpdateEulenStatusBatch {
+        webhook: raw_webhook(&headers, &body),
+        response: pix_tx,
+    };
+    if let Err(e) = enqueue(&state.pix_channel, "pix", request).await {
+        return e.into_response();
+    }
+
+    let response = match pix_rx.await {
+        Ok(Ok(results)) => (StatusCode::OK, Json(json!({"results": results}))),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "InvalidWebhookPayload" => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"description": "Invalid payload."})),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"description": format!("Failed to process request: {}", service_error)})),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"description": format!("Failed to receive response: {}", e)})),
+        ),
+    };
+
+    response.into_response()
+}
+
+/// Disputes of paid charges, sent by Eulen when the payer's bank opens a MED
+/// and again when it is decided. Only accepted with the shared secret, since
+/// a dispute holds the user's payouts.
+async fn eulen_dispute(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> Response {
+    if !has_webhook_secret(&state, &headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"description": "Unauthorized"})),
+        )
+            .into_response();
+    }
+
+    let (pix_tx, pix_rx) = oneshot::channel();
+
+    let request = PixServiceRequest::ReceiveDisputeNotice {
+        webhook: raw_webhook(&headers, &body),
+        response: pix_tx,
+    };
+    if let Err(e) = enqueue(&state.pix_channel, "pix", request).await {
+        return e.into_response();
+    }
+
+    let response = match pix_rx.await {
+        Ok(Ok(dispute)) => (
+            StatusCode::OK,
+            Json(json!({"description": "Dispute recorded", "id": dispute.id})),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "InvalidWebhookPayload" => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"description": "Invalid payload."})),
+        ),
+        // The error is kept with the stored webhook
+        Ok(Err(_)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"description": "Internal server error."})),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"description": format!("Failed to receive response: {}", e)})),
+        ),
+    };
+
+    response.into_response()
+}
+
+/// Services are restarted when they crash or cannot start; while one is down
+/// the functionality depending on it is reported as degraded and the
+/// endpoint answers 503 so load balancers hold traffic back.
+async fn get_health(State(state): State<AppState>) -> impl IntoResponse {
+    let report = state.health.report();
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(json!(report)))
+}
+
+/// Refuses operations that move money until the services behind deposits
+/// are up, instead of accepting requests that would fail halfway through.
+async fn require_ready(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    if !state.health.is_available("deposits") {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "Service unavailable",
+                "details": "Serviço temporariamente indisponível, tente novamente em instantes."
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(req).await
+}
+
+/// Leaves requests that reach the services moving money to the leader. A
+/// follower answers 503 so the load balancer tries the leader, instead of
+/// queueing them for services that don't run here.
+async fn require_leader(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    if !state.health.is_leader() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, RETRY_AFTER_SECS)],
+            Json(json!({
+                "error": "Not the leader",
+                "details": "Serviço temporariamente indisponível, tente novamente em instantes."
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(req).await
+}
+
+/// 200 on the leader only, for load balancers routing money operations.
+async fn get_leader_health(State(state): State<AppState>) -> StatusCode {
+    if state.health.is_leader() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
     }
 }
 
 pub async fn start_http_server(
-    transaction_channel: mpsc::Sender<TransactionServiceRequest>,
-    pix_channel: mpsc::Sender<PixServiceRequest>,
-    user_channel: mpsc::Sender<UserRequest>,
+    channels: HttpChannels,
+    health: ServiceHealth,
+    admin_api_key: String,
+    dispute_webhook_secret: String,
+    http_settings: settings::Http,
+    dry_run: bool,
 ) -> Result<(), anyhow::Error> {
     let app_state = AppState {
-        transaction_channel,
-        pix_channel,
-        user_channel,
+        transaction_channel: channels.transaction,
+        pix_channel: channels.pix,
+        user_channel: channels.user,
+        snapshot_channel: channels.snapshot,
+        scheduler_channel: channels.scheduler,
+        merchant_channel: channels.merchant,
+        price_channel: channels.price,
+        hedging_channel: channels.hedging,
+        liquid_channel: channels.liquid,
+        sideswap_channel: channels.sideswap,
+        report_channel: channels.report,
+        analytics_channel: channels.analytics,
+        health,
+        status_cache: status::StatusCache::start(),
+        admin_api_key: Arc::new(admin_api_key),
+        dispute_webhook_secret: Arc::new(dispute_webhook_secret),
+        dry_run,
     };
 
-    let app = Router::new()
+    let ready = || middleware::from_fn_with_state(app_state.clone(), require_ready);
+    let leader = || middleware::from_fn_with_state(app_state.clone(), require_leader);
+
+    // Metrics are scraped from every instance
+    let admin_router = admin::router()
+        .route_layer(leader())
+        .route("/metrics", get(admin::get_metrics))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            admin::require_admin,
+        ));
+
+    // Routes called from the web clients, the only ones answering CORS
+    let mut public_router = Router::new()
         .route("/register", post(create_new_user))
-        .route("/deposit", post(request_new_deposit))
-        .route("/webhook/eulen_status", post(eulen_update_status))
+        .route("/deposit", post(request_new_deposit).route_layer(ready()))
+        .route(
+            "/transaction/{transaction_id}",
+            get(get_transaction).route_layer(leader()),
+        )
+        .route(
+            "/transaction/{transaction_id}/cancel",
+            post(cancel_transaction).route_layer(leader()),
+        )
+        .route("/price", get(prices::get_price))
+        .route("/quote", post(prices::create_quote).route_layer(ready()))
         .route("/user/{user_id}", get(users::get_user_details))
+        .route(
+            "/user/{user_id}/schedules",
+            get(schedules::get_user_schedules).route_layer(leader()),
+        )
+        .route("/user/{user_id}/statement", get(users::get_statement))
+        .route(
+            "/referrer/statement",
+            get(referrals::get_referrer_statement),
+        )
+        .route("/referrer/payments", get(referrals::get_referrer_payments))
+        .route(
+            "/user/{user_id}/notifications",
+            get(users::get_notification_preferences).put(users::set_notification_preferences),
+        )
+        .route(
+            "/schedules",
+            post(schedules::create_schedule).route_layer(leader()),
+        )
+        .route(
+            "/schedules/{schedule_id}/pause",
+            post(schedules::pause_schedule).route_layer(leader()),
+        )
+        .route(
+            "/schedules/{schedule_id}/resume",
+            post(schedules::resume_schedule).route_layer(leader()),
+        )
+        .route(
+            "/schedules/{schedule_id}/cancel",
+            post(schedules::cancel_schedule).route_layer(leader()),
+        )
+        .route(
+            "/merchant/links",
+            post(merchants::create_payment_link)
+                .route_layer(ready())
+                .get(merchants::get_payment_links),
+        )
+        .route("/pay/{token}", get(merchants::get_payment_page))
+        .route("/pay/{token}/qr", get(merchants::get_payment_link))
+        .route("/reserves/{hash}", get(reports::get_reserve_proof))
         .route("/hello", get(|| async { "Hello, World!" }))
-        .route("/health", get(|| async { "OK" }))
-        .with_state(app_state)
-        .layer(TraceLayer::new_for_http());
+        .route("/health", get(get_health))
+        .route("/health/leader", get(get_leader_health))
+        .route("/status", get(status::get_status).route_layer(leader()));
+    if let Some(cors) = security::cors_layer(&http_settings) {
+        public_router = public_router.layer(cors);
+    }
+
+    let public_app = public_router
+        .route(
+            "/webhook/eulen_status",
+            post(eulen_update_status).route_layer(leader()),
+        )
+        .route(
+            "/webhook/eulen_status/batch",
+            post(eulen_update_status_batch).route_layer(leader()),
+        )
+        .route(
+            "/webhook/eulen_dispute",
+            post(eulen_dispute).route_layer(leader()),
+        );
+    let admin_app = Router::new()
+        .route("/admin/ui", get(dashboard::get_dashboard))
+        .nest("/admin", admin_router);
+
+    let security_headers = security::SecurityHeaders::new(&http_settings);
+    let request_logger = logging::RequestLogger::new(&http_settings);
+    let finish = |router: Router<AppState>| {
+        router
+            .with_state(app_state.clone())
+            .layer(middleware::from_fn_with_state(
+                security_headers.clone(),
+                security::add_security_headers,
+            ))
+            .layer(middleware::from_fn_with_state(
+                request_logger.clone(),
+                logging::log_requests,
+            ))
+    };
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
-    println!("[INFO] Listening on {}", listener.local_addr()?);
+    let tls = listeners::tls_acceptor(&http_settings)?;
+    if http_settings.admin_listen.is_empty() {
+        let app = finish(public_app.merge(admin_app));
+        return listeners::serve(&http_settings.listen, tls.as_ref(), app).await;
+    }
 
-    axum::serve(listener, app).await?;
+    // The admin API is only reachable on its own listeners
+    tokio::try_join!(
+        listeners::serve(&http_settings.listen, tls.as_ref(), finish(public_app)),
+        listeners::serve(&http_settings.admin_listen, tls.as_ref(), finish(admin_app)),
+    )?;
 
     Ok(())
 }
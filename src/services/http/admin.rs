@@ -0,0 +1,527 @@
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use subtle::ConstantTimeEq;
+use tokio::sync::oneshot;
+
+use super::validation::ValidJson;
+use super::{
+    campaigns, dashboard, disputes, jobs, maintenance, merchants, referrals, refunds, replay,
+    reports, reviews, search, users, wallet, AppState,
+};
+use crate::models::reconciliation::DepixSettlementQuery;
+use crate::models::snapshots::RestoreSnapshot;
+use crate::models::treasury::TreasuryQuery;
+use crate::models::webhooks::WebhookEventQuery;
+use crate::services::hedging::HedgingRequest;
+use crate::services::pix::PixServiceRequest;
+use crate::services::sideswap::SideswapRequest;
+use crate::services::snapshots::SnapshotRequest;
+use crate::services::transactions::TransactionServiceRequest;
+use crate::utils::{latency, metrics};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/status", get(get_status))
+        .route("/snapshots", post(create_snapshot))
+        .route("/snapshots/restore", post(restore_snapshot))
+        .route("/pending", get(dashboard::get_pending_transactions))
+        .route(
+            "/pending/{transaction_id}/retry",
+            post(dashboard::retry_pending_transaction),
+        )
+        .route(
+            "/pending/{transaction_id}/drop",
+            post(dashboard::drop_pending_transaction),
+        )
+        .route("/transactions", get(dashboard::get_recent_transactions))
+        .route("/transactions/search", get(search::search_transactions))
+        .route("/pix/search", get(search::search_pix_transactions))
+        .route("/archive/export", get(search::export_archive))
+        .route("/jobs", get(jobs::get_jobs))
+        .route("/jobs/{name}/run", post(jobs::run_job))
+        .route("/kill-switch", get(dashboard::get_kill_switch))
+        .route("/kill-switch/on", post(dashboard::enable_kill_switch))
+        .route("/kill-switch/off", post(dashboard::disable_kill_switch))
+        .route("/assets", get(dashboard::get_asset_availability))
+        .route("/assets/{asset_id}/enable", post(dashboard::enable_asset))
+        .route("/assets/{asset_id}/disable", post(dashboard::disable_asset))
+        .route(
+            "/maintenance",
+            get(maintenance::get_maintenance_windows).post(maintenance::schedule_maintenance),
+        )
+        .route(
+            "/maintenance/{window_id}/cancel",
+            post(maintenance::cancel_maintenance),
+        )
+        .route(
+            "/transaction/{transaction_id}/timeline",
+            get(get_transaction_timeline),
+        )
+        .route("/webhooks", get(search_webhook_events))
+        .route("/replay", get(replay::get_replay_mode))
+        .route(
+            "/replay/sideswap",
+            post(replay::replay_sideswap_notification),
+        )
+        .route("/hedging", get(get_hedging_report))
+        .route("/treasury", get(get_treasury_report))
+        .route("/venues/sideswap", get(get_sideswap_status))
+        .route("/reconciliation/depix", get(get_depix_settlements))
+        .route("/wallet/descriptor", get(wallet::get_wallet_descriptor))
+        .route("/wallet/addresses", get(wallet::get_derived_addresses))
+        .route("/wallet/receipts", get(wallet::get_fee_address_receipts))
+        .route("/wallet/broadcasts/{txid}", get(wallet::get_broadcast))
+        .route(
+            "/transaction/{transaction_id}/address",
+            get(wallet::verify_transaction_address),
+        )
+        .route("/merchants", post(merchants::register_merchant))
+        .route(
+            "/campaigns",
+            post(campaigns::create_campaign).get(campaigns::get_campaigns),
+        )
+        .route(
+            "/campaigns/{campaign_id}/activate",
+            post(campaigns::activate_campaign),
+        )
+        .route(
+            "/campaigns/{campaign_id}/deactivate",
+            post(campaigns::deactivate_campaign),
+        )
+        .route("/users/{user_id}/export", post(users::export_user_data))
+        .route("/users/{user_id}/anonymize", post(users::anonymize_user))
+        .route("/users/{user_id}/risk", get(users::get_risk_score))
+        .route("/users/{user_id}/key", post(users::issue_user_key))
+        .route("/reports/in1888", get(reports::get_in1888_report))
+        .route("/reports/referrals", get(referrals::get_referral_report))
+        .route("/reports/disputes", get(disputes::get_dispute_exposure))
+        .route(
+            "/referrers/{user_id}/key",
+            post(referrals::issue_referrer_key),
+        )
+        .route("/reserves/proofs", post(reports::create_reserve_proof))
+        .route("/reviews", get(reviews::get_held_transactions))
+        .route(
+            "/reviews/{transaction_id}/approve",
+            post(reviews::approve_transaction),
+        )
+        .route(
+            "/reviews/{transaction_id}/reject",
+            post(reviews::reject_transaction),
+        )
+        .route("/refunds", get(refunds::get_refunds))
+        .route(
+            "/refunds/{refund_id}/refunded",
+            post(refunds::mark_refunded),
+        )
+        .route(
+            "/refunds/{refund_id}/dismiss",
+            post(refunds::dismiss_refund),
+        )
+        .route(
+            "/disputes",
+            get(disputes::get_disputes).post(disputes::report_dispute),
+        )
+        .route(
+            "/disputes/{dispute_id}/clawed_back",
+            post(disputes::mark_clawed_back),
+        )
+        .route(
+            "/disputes/{dispute_id}/rejected",
+            post(disputes::mark_rejected),
+        )
+}
+
+pub async fn require_admin(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    if state.admin_api_key.is_empty() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Admin API disabled"})),
+        )
+            .into_response();
+    }
+
+    let authorized = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| bool::from(token.as_bytes().ct_eq(state.admin_api_key.as_bytes())));
+
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"error": "Unauthorized"})),
+        )
+            .into_response();
+    }
+
+    next.run(req).await
+}
+
+pub async fn get_metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(),
+    )
+}
+
+/// Health of the instance with the latency of each dependency, to tell
+/// which one slows deposits down.
+async fn get_status(State(state): State<AppState>) -> impl IntoResponse {
+    Json(json!({
+        "health": state.health.report(),
+        "dependencies": latency::summaries(),
+    }))
+}
+
+async fn create_snapshot(State(state): State<AppState>) -> impl IntoResponse {
+    let (snapshot_tx, snapshot_rx) = oneshot::channel();
+
+    let snapshot_result = state
+        .snapshot_channel
+        .send(SnapshotRequest::CreateSnapshot {
+            response: snapshot_tx,
+        })
+        .await;
+    if let Err(e) = snapshot_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match snapshot_rx.await {
+        Ok(Ok(summary)) => (StatusCode::CREATED, Json(json!(summary))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not create snapshot",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+async fn restore_snapshot(
+    State(state): State<AppState>,
+    ValidJson(req): ValidJson<RestoreSnapshot>,
+) -> impl IntoResponse {
+    let (snapshot_tx, snapshot_rx) = oneshot::channel();
+
+    let snapshot_result = state
+        .snapshot_channel
+        .send(SnapshotRequest::RestoreSnapshot {
+            name: req.name,
+            response: snapshot_tx,
+        })
+        .await;
+    if let Err(e) = snapshot_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match snapshot_rx.await {
+        Ok(Ok(restored)) => (StatusCode::OK, Json(json!({"restored": restored}))),
+        Ok(Err(service_error)) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": "Could not restore snapshot",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+async fn get_transaction_timeline(
+    State(state): State<AppState>,
+    Path(transaction_id): Path<String>,
+) -> impl IntoResponse {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::GetTimeline {
+            transaction_id,
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(Some(timeline))) => (StatusCode::OK, Json(json!(timeline))),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Transaction not found"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not build timeline",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+async fn search_webhook_events(
+    State(state): State<AppState>,
+    Query(query): Query<WebhookEventQuery>,
+) -> impl IntoResponse {
+    if query.bank_tx_id.is_none() && query.transaction_id.is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Missing filter",
+                "details": "Pass bank_tx_id or transaction_id"
+            })),
+        );
+    }
+
+    let (pix_tx, pix_rx) = oneshot::channel();
+    let pix_result = state
+        .pix_channel
+        .send(PixServiceRequest::SearchWebhookEvents {
+            bank_tx_id: query.bank_tx_id,
+            transaction_id: query.transaction_id,
+            response: pix_tx,
+        })
+        .await;
+    if let Err(e) = pix_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match pix_rx.await {
+        Ok(Ok(events)) => (StatusCode::OK, Json(json!({"events": events}))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not search webhooks",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+async fn get_hedging_report(State(state): State<AppState>) -> impl IntoResponse {
+    let (hedging_tx, hedging_rx) = oneshot::channel();
+    let hedging_result = state
+        .hedging_channel
+        .send(HedgingRequest::GetReport {
+            response: hedging_tx,
+        })
+        .await;
+    if let Err(e) = hedging_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match hedging_rx.await {
+        Ok(Ok(report)) => (StatusCode::OK, Json(json!(report))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not build hedging report",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// DEPIX settlements of paid charges, newest first: the flagged ones, `short`
+/// and `missing`, unless `status` asks for another.
+async fn get_depix_settlements(
+    State(state): State<AppState>,
+    Query(query): Query<DepixSettlementQuery>,
+) -> impl IntoResponse {
+    let statuses = match query.status {
+        Some(status) => vec![status],
+        None => vec!["short".to_string(), "missing".to_string()],
+    };
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+
+    let (pix_tx, pix_rx) = oneshot::channel();
+    let pix_result = state
+        .pix_channel
+        .send(PixServiceRequest::GetDepixSettlements {
+            statuses,
+            limit,
+            response: pix_tx,
+        })
+        .await;
+    if let Err(e) = pix_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match pix_rx.await {
+        Ok(Ok(settlements)) => (StatusCode::OK, Json(json!(settlements))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not list DEPIX settlements",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Whether Sideswap is connected and taking swaps, with the fees and
+/// maintenance of its last server status.
+async fn get_sideswap_status(State(state): State<AppState>) -> impl IntoResponse {
+    let (sideswap_tx, sideswap_rx) = oneshot::channel();
+    let sideswap_result = state
+        .sideswap_channel
+        .send(SideswapRequest::GetVenueStatus {
+            response: sideswap_tx,
+        })
+        .await;
+    if let Err(e) = sideswap_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match sideswap_rx.await {
+        Ok(status) => (StatusCode::OK, Json(json!(status))),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Sideswap fees and realized conversion rates against oracle prices per day
+/// and pair, over the last `days` days (30 by default).
+async fn get_treasury_report(
+    State(state): State<AppState>,
+    Query(query): Query<TreasuryQuery>,
+) -> impl IntoResponse {
+    let days = query.days.unwrap_or(30).clamp(1, 366);
+
+    let (sideswap_tx, sideswap_rx) = oneshot::channel();
+    let sideswap_result = state
+        .sideswap_channel
+        .send(SideswapRequest::GetTreasuryReport {
+            days,
+            response: sideswap_tx,
+        })
+        .await;
+    if let Err(e) = sideswap_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match sideswap_rx.await {
+        Ok(Ok(report)) => (StatusCode::OK, Json(json!(report))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not build treasury report",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
@@ -0,0 +1,156 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use super::validation::ValidJson;
+use crate::models::campaigns::NewCampaign;
+use crate::services::transactions::TransactionServiceRequest;
+
+pub async fn create_campaign(
+    State(state): State<super::AppState>,
+    ValidJson(req): ValidJson<NewCampaign>,
+) -> impl IntoResponse {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::CreateCampaign {
+            campaign: req,
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(campaign)) => (StatusCode::CREATED, Json(json!(campaign))),
+        Ok(Err(service_error)) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Could not create campaign",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn get_campaigns(State(state): State<super::AppState>) -> impl IntoResponse {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::GetCampaigns {
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(campaigns)) => (StatusCode::OK, Json(json!(campaigns))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not list campaigns",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn activate_campaign(
+    State(state): State<super::AppState>,
+    Path(campaign_id): Path<String>,
+) -> impl IntoResponse {
+    set_campaign_active(state, campaign_id, true).await
+}
+
+pub async fn deactivate_campaign(
+    State(state): State<super::AppState>,
+    Path(campaign_id): Path<String>,
+) -> impl IntoResponse {
+    set_campaign_active(state, campaign_id, false).await
+}
+
+async fn set_campaign_active(
+    state: super::AppState,
+    campaign_id: String,
+    active: bool,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::SetCampaignActive {
+            campaign_id,
+            active,
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(true)) => (StatusCode::OK, Json(json!({"active": active}))),
+        Ok(Ok(false)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Campaign not found"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not update campaign",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
@@ -0,0 +1,419 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{Html, IntoResponse},
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use super::validation::ValidJson;
+use crate::models::operator::{
+    KillSwitchRequest, PendingDropRequest, PendingRetryRequest, RecentTransactionsQuery,
+};
+use crate::services::transactions::TransactionServiceRequest;
+use crate::services::ServiceError;
+
+const DASHBOARD: &str = include_str!("dashboard.html");
+
+/// The page holds no data: it asks for the admin API key and calls the admin
+/// API with it, so it is served without authentication.
+pub async fn get_dashboard() -> impl IntoResponse {
+    Html(DASHBOARD)
+}
+
+pub async fn get_pending_transactions(State(state): State<super::AppState>) -> impl IntoResponse {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::GetPendingTransactions {
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(pending)) => (StatusCode::OK, Json(json!(pending))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not list pending transactions",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn retry_pending_transaction(
+    State(state): State<super::AppState>,
+    Path(transaction_id): Path<String>,
+    ValidJson(req): ValidJson<PendingRetryRequest>,
+) -> impl IntoResponse {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::RetryPending {
+            transaction_id,
+            requested_by: req.requested_by,
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(Some(still_queued))) => (
+            StatusCode::OK,
+            Json(json!({
+                "retried": true,
+                "still_queued": still_queued
+            })),
+        ),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Transaction is not in the pending queue"
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "DealerPaused" => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Payouts are paused",
+                "details": "Turn the kill switch off before retrying payouts."
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not retry pending transaction",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn drop_pending_transaction(
+    State(state): State<super::AppState>,
+    Path(transaction_id): Path<String>,
+    ValidJson(req): ValidJson<PendingDropRequest>,
+) -> impl IntoResponse {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::DropPending {
+            transaction_id,
+            requested_by: req.requested_by,
+            reason: req.reason,
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(true)) => (StatusCode::OK, Json(json!({"dropped": true}))),
+        Ok(Ok(false)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Transaction is not in the pending queue"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not drop pending transaction",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn get_recent_transactions(
+    State(state): State<super::AppState>,
+    Query(query): Query<RecentTransactionsQuery>,
+) -> impl IntoResponse {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::GetRecentTransactions {
+            limit: query.limit.unwrap_or(50).clamp(1, 500),
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(transactions)) => (StatusCode::OK, Json(json!(transactions))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not list transactions",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn get_kill_switch(State(state): State<super::AppState>) -> impl IntoResponse {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::IsPaused {
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(paused) => (StatusCode::OK, Json(json!({"paused": paused}))),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn enable_kill_switch(
+    State(state): State<super::AppState>,
+    ValidJson(req): ValidJson<KillSwitchRequest>,
+) -> impl IntoResponse {
+    set_kill_switch(state, true, req).await
+}
+
+pub async fn disable_kill_switch(
+    State(state): State<super::AppState>,
+    ValidJson(req): ValidJson<KillSwitchRequest>,
+) -> impl IntoResponse {
+    set_kill_switch(state, false, req).await
+}
+
+async fn set_kill_switch(
+    state: super::AppState,
+    paused: bool,
+    req: KillSwitchRequest,
+) -> (StatusCode, Json<serde_json::Value>) {
+    if req.requested_by.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Missing requested_by"
+            })),
+        );
+    }
+
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::SetPaused {
+            paused,
+            requested_by: req.requested_by,
+            reason: req.reason,
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(())) => (StatusCode::OK, Json(json!({"paused": paused}))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not change kill switch",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn get_asset_availability(State(state): State<super::AppState>) -> impl IntoResponse {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::GetAssetAvailability {
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(assets) => (StatusCode::OK, Json(json!(assets))),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn enable_asset(
+    State(state): State<super::AppState>,
+    Path(asset_id): Path<String>,
+    ValidJson(req): ValidJson<KillSwitchRequest>,
+) -> impl IntoResponse {
+    set_asset_enabled(state, asset_id, true, req).await
+}
+
+pub async fn disable_asset(
+    State(state): State<super::AppState>,
+    Path(asset_id): Path<String>,
+    ValidJson(req): ValidJson<KillSwitchRequest>,
+) -> impl IntoResponse {
+    set_asset_enabled(state, asset_id, false, req).await
+}
+
+async fn set_asset_enabled(
+    state: super::AppState,
+    asset_id: String,
+    enabled: bool,
+    req: KillSwitchRequest,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::SetAssetEnabled {
+            asset_id: asset_id.clone(),
+            enabled,
+            requested_by: req.requested_by,
+            reason: req.reason,
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(true)) => (
+            StatusCode::OK,
+            Json(json!({"asset_id": asset_id, "enabled": enabled})),
+        ),
+        Ok(Ok(false)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Unknown asset"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not update asset",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
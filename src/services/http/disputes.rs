@@ -0,0 +1,212 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use super::validation::ValidJson;
+use crate::models::disputes::{
+    DisputeDecision, DisputeDecisionRequest, DisputeQuery, ReportDisputeRequest,
+};
+use crate::services::pix::PixServiceRequest;
+
+/// MED disputes of PIX payments, newest first: the ones still `open` unless
+/// `status` asks for another.
+pub async fn get_disputes(
+    State(state): State<super::AppState>,
+    Query(query): Query<DisputeQuery>,
+) -> impl IntoResponse {
+    let statuses = vec![query.status.unwrap_or_else(|| "open".to_string())];
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+
+    let (pix_tx, pix_rx) = oneshot::channel();
+    let pix_result = state
+        .pix_channel
+        .send(PixServiceRequest::GetDisputes {
+            statuses,
+            limit,
+            response: pix_tx,
+        })
+        .await;
+    if let Err(e) = pix_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match pix_rx.await {
+        Ok(Ok(disputes)) => (StatusCode::OK, Json(json!(disputes))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not list disputes",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Records a dispute learned of outside the webhook. Reporting one recorded
+/// already leaves it as it is.
+pub async fn report_dispute(
+    State(state): State<super::AppState>,
+    ValidJson(req): ValidJson<ReportDisputeRequest>,
+) -> impl IntoResponse {
+    let (pix_tx, pix_rx) = oneshot::channel();
+
+    let pix_result = state
+        .pix_channel
+        .send(PixServiceRequest::ReportDispute {
+            dispute: req.into(),
+            response: pix_tx,
+        })
+        .await;
+    if let Err(e) = pix_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match pix_rx.await {
+        Ok(Ok(dispute)) => (StatusCode::CREATED, Json(json!(dispute))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not record dispute",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Marks a dispute lost: the payer's bank took the money back.
+pub async fn mark_clawed_back(
+    State(state): State<super::AppState>,
+    Path(dispute_id): Path<String>,
+    ValidJson(req): ValidJson<DisputeDecisionRequest>,
+) -> impl IntoResponse {
+    resolve_dispute(state, dispute_id, DisputeDecision::ClawedBack, req).await
+}
+
+pub async fn mark_rejected(
+    State(state): State<super::AppState>,
+    Path(dispute_id): Path<String>,
+    ValidJson(req): ValidJson<DisputeDecisionRequest>,
+) -> impl IntoResponse {
+    resolve_dispute(state, dispute_id, DisputeDecision::Rejected, req).await
+}
+
+async fn resolve_dispute(
+    state: super::AppState,
+    id: String,
+    decision: DisputeDecision,
+    req: DisputeDecisionRequest,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let (pix_tx, pix_rx) = oneshot::channel();
+
+    let pix_result = state
+        .pix_channel
+        .send(PixServiceRequest::ResolveDispute {
+            id,
+            decision,
+            decided_by: req.decided_by,
+            note: req.note,
+            response: pix_tx,
+        })
+        .await;
+    if let Err(e) = pix_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match pix_rx.await {
+        Ok(Ok(Some(dispute))) => (StatusCode::OK, Json(json!(dispute))),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "No open dispute with this id"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not resolve dispute",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Disputes by status, with how much of what they contest was paid out.
+pub async fn get_dispute_exposure(State(state): State<super::AppState>) -> impl IntoResponse {
+    let (pix_tx, pix_rx) = oneshot::channel();
+
+    let pix_result = state
+        .pix_channel
+        .send(PixServiceRequest::GetDisputeExposure { response: pix_tx })
+        .await;
+    if let Err(e) = pix_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match pix_rx.await {
+        Ok(Ok(exposure)) => (StatusCode::OK, Json(json!(exposure))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not compute dispute exposure",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
@@ -0,0 +1,24 @@
+use axum::{extract::Path, http::StatusCode, response::IntoResponse, Json};
+use serde_json::json;
+
+use crate::services::jobs;
+
+/// Schedule, last run and failures of every background job.
+pub async fn get_jobs() -> impl IntoResponse {
+    Json(json!(jobs::statuses()))
+}
+
+/// Runs a job now, or right after the run in progress.
+pub async fn run_job(Path(name): Path<String>) -> impl IntoResponse {
+    if jobs::trigger(&name) {
+        (StatusCode::ACCEPTED, Json(json!({ "job": name })))
+    } else {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Job not found",
+                "details": format!("No job named {}", name)
+            })),
+        )
+    }
+}
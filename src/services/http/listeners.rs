@@ -0,0 +1,159 @@
+//! Listeners the API is served on: TCP addresses, optionally behind TLS, and
+//! Unix sockets, as configured in `[http]`.
+
+use axum::{serve::Listener, Router};
+use futures_util::future::{self, BoxFuture};
+use std::future::IntoFuture;
+use std::net::SocketAddr;
+use std::os::unix::fs::FileTypeExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream, UnixListener};
+use tokio::sync::mpsc;
+use tokio_rustls::{
+    rustls::{
+        self,
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    },
+    server::TlsStream,
+    TlsAcceptor,
+};
+
+use crate::settings;
+
+/// A client that has not finished the handshake by then is dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Connections past the handshake waiting to be served.
+const HANDSHAKE_BACKLOG: usize = 128;
+
+/// TLS with the configured certificate, or None to serve plain HTTP.
+pub fn tls_acceptor(settings: &settings::Http) -> Result<Option<TlsAcceptor>, anyhow::Error> {
+    let (Some(cert_path), Some(key_path)) = (&settings.tls_cert_path, &settings.tls_key_path)
+    else {
+        return Ok(None);
+    };
+
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| anyhow::anyhow!("Could not read certificate {}: {}", cert_path, e))?;
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| anyhow::anyhow!("Could not read private key {}: {}", key_path, e))?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(Some(TlsAcceptor::from(Arc::new(config))))
+}
+
+/// Binds every listener in `binds`, then serves `app` on all of them until
+/// one fails. TLS applies to the TCP listeners only.
+pub async fn serve(
+    binds: &[String],
+    tls: Option<&TlsAcceptor>,
+    app: Router,
+) -> Result<(), anyhow::Error> {
+    let mut servers: Vec<BoxFuture<'static, std::io::Result<()>>> = Vec::new();
+    for bind in binds {
+        match bind.strip_prefix("unix:") {
+            Some(path) => {
+                remove_stale_socket(path)?;
+                let listener = UnixListener::bind(path)?;
+                println!("[INFO] Listening on {}", bind);
+                servers.push(Box::pin(axum::serve(listener, app.clone()).into_future()));
+            }
+            None => {
+                let listener = TcpListener::bind(bind).await?;
+                println!("[INFO] Listening on {}", listener.local_addr()?);
+                match tls {
+                    Some(acceptor) => {
+                        let listener = TlsListener::new(listener, acceptor.clone())?;
+                        servers.push(Box::pin(axum::serve(listener, app.clone()).into_future()));
+                    }
+                    None => {
+                        servers.push(Box::pin(axum::serve(listener, app.clone()).into_future()));
+                    }
+                }
+            }
+        }
+    }
+
+    future::try_join_all(servers).await?;
+    Ok(())
+}
+
+/// A socket left behind by a previous run would fail the bind. Anything
+/// other than a socket is left alone.
+fn remove_stale_socket(path: &str) -> std::io::Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path),
+        _ => Ok(()),
+    }
+}
+
+/// TCP listener handing out connections once their TLS handshake is done.
+/// Handshakes run apart so a slow client does not hold up the others.
+struct TlsListener {
+    local_addr: SocketAddr,
+    streams: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+}
+
+impl TlsListener {
+    fn new(listener: TcpListener, acceptor: TlsAcceptor) -> std::io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (streams_tx, streams_rx) = mpsc::channel(HANDSHAKE_BACKLOG);
+        tokio::spawn(accept_tls(listener, acceptor, streams_tx));
+
+        Ok(Self {
+            local_addr,
+            streams: streams_rx,
+        })
+    }
+}
+
+/// Accepts connections until the listener is dropped, so the address is
+/// free again when the server restarts.
+async fn accept_tls(
+    mut listener: TcpListener,
+    acceptor: TlsAcceptor,
+    streams: mpsc::Sender<(TlsStream<TcpStream>, SocketAddr)>,
+) {
+    loop {
+        let (stream, addr) = tokio::select! {
+            accepted = Listener::accept(&mut listener) => accepted,
+            _ = streams.closed() => return,
+        };
+
+        let acceptor = acceptor.clone();
+        let streams = streams.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => {
+                    let _ = streams.send((stream, addr)).await;
+                }
+                Ok(Err(e)) => log::debug!("TLS handshake with {} failed: {}", addr, e),
+                Err(_) => log::debug!("TLS handshake with {} timed out", addr),
+            }
+        });
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.streams.recv().await {
+            Some(accepted) => accepted,
+            // The accepting task only stops once this listener is dropped
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
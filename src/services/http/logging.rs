@@ -0,0 +1,339 @@
+use axum::{
+    body::{self, Body, HttpBody},
+    extract::{FromRequestParts, MatchedPath, Query, RawPathParams, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::settings::{self, Redaction};
+
+/// Target the requests are logged under, which log4rs sends to their own
+/// file.
+const TARGET: &str = "http_requests";
+
+/// Logs the requests served as one JSON line each. Headers are never logged,
+/// and bodies only when they are JSON, so nothing escapes redaction.
+#[derive(Clone, Default)]
+pub struct RequestLogger(Option<Arc<Logger>>);
+
+struct Logger {
+    settings: settings::RequestLogging,
+    rules: Rules,
+}
+
+impl RequestLogger {
+    pub fn new(settings: &settings::Http) -> Self {
+        if !settings.logging.enabled {
+            return Self::default();
+        }
+
+        Self(Some(Arc::new(Logger {
+            settings: settings.logging.clone(),
+            rules: Rules::new(&settings.logging.redact),
+        })))
+    }
+}
+
+/// Redactions by field name, compared without case or underscores, so
+/// `pix_key` also covers Eulen's `pixKey`.
+struct Rules {
+    names: HashMap<String, Redaction>,
+    suffixes: Vec<(String, Redaction)>,
+}
+
+impl Rules {
+    fn new(redact: &HashMap<String, Redaction>) -> Self {
+        let mut names = HashMap::new();
+        let mut suffixes = Vec::new();
+        for (name, redaction) in redact {
+            let name = normalize(name);
+            match name.strip_prefix('*') {
+                Some(suffix) => suffixes.push((suffix.to_string(), *redaction)),
+                None => {
+                    names.insert(name, *redaction);
+                }
+            }
+        }
+
+        Self { names, suffixes }
+    }
+
+    fn get(&self, name: &str) -> Option<Redaction> {
+        let name = normalize(name);
+        self.names.get(&name).copied().or_else(|| {
+            self.suffixes
+                .iter()
+                .find(|(suffix, _)| name.ends_with(suffix.as_str()))
+                .map(|(_, redaction)| *redaction)
+        })
+    }
+
+    fn pairs<'a>(&self, pairs: impl Iterator<Item = (&'a str, &'a str)>) -> Value {
+        let fields = pairs
+            .map(|(name, value)| {
+                let value = match self.get(name) {
+                    Some(redaction) => redact(value, redaction),
+                    None => value.to_string(),
+                };
+                (name.to_string(), Value::String(value))
+            })
+            .collect::<Map<_, _>>();
+        Value::Object(fields)
+    }
+
+    fn json(&self, value: &mut Value) {
+        match value {
+            Value::Object(fields) => {
+                for (name, field) in fields.iter_mut() {
+                    match self.get(name) {
+                        Some(redaction) => redact_json(field, redaction),
+                        None => self.json(field),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.json(item)),
+            _ => {}
+        }
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.replace('_', "").to_lowercase()
+}
+
+fn redact(value: &str, redaction: Redaction) -> String {
+    match redaction {
+        Redaction::Hide => "[redacted]".to_string(),
+        Redaction::Mask => {
+            let chars: Vec<char> = value.chars().collect();
+            // The last characters of short values give away too much of them
+            if chars.len() < 8 {
+                return "****".to_string();
+            }
+            let last: String = chars[chars.len() - 4..].iter().collect();
+            format!("****{}", last)
+        }
+        Redaction::Hash => {
+            let hash = format!("{:x}", Sha256::digest(value.as_bytes()));
+            format!("sha256:{}", &hash[..12])
+        }
+    }
+}
+
+fn redact_json(value: &mut Value, redaction: Redaction) {
+    match value {
+        Value::Null => {}
+        Value::String(text) => *text = redact(text, redaction),
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| redact_json(item, redaction)),
+        other => *other = Value::String(redact(&other.to_string(), redaction)),
+    }
+}
+
+impl Logger {
+    /// Draws from the random bits of the request id.
+    fn sampled(&self, route: &str, request_id: &uuid::Uuid) -> bool {
+        let rate = self
+            .settings
+            .route_sample_rates
+            .get(route)
+            .copied()
+            .unwrap_or(self.settings.sample_rate);
+        let draw = (request_id.as_u128() as u64 & ((1 << 53) - 1)) as f64 / (1u64 << 53) as f64;
+        draw < rate
+    }
+
+    /// The body redacted when it is JSON of a known length no longer than
+    /// `max_body_bytes`, and the body to pass on in place of the one read.
+    async fn capture(&self, is_json: bool, body: Body) -> (Option<Value>, Body) {
+        let length = body.size_hint().exact();
+        if !self.settings.bodies
+            || !is_json
+            || length.is_none_or(|length| length as usize > self.settings.max_body_bytes)
+        {
+            return (None, body);
+        }
+
+        match body::to_bytes(body, self.settings.max_body_bytes).await {
+            Ok(bytes) => {
+                // Bodies that aren't valid JSON can't be redacted
+                let logged = serde_json::from_slice::<Value>(&bytes)
+                    .ok()
+                    .map(|mut value| {
+                        self.rules.json(&mut value);
+                        value
+                    });
+                (logged, Body::from(bytes))
+            }
+            Err(e) => {
+                log::warn!("Could not read body to log: {}", e);
+                (None, Body::empty())
+            }
+        }
+    }
+}
+
+fn is_json(headers: &axum::http::HeaderMap) -> bool {
+    headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"))
+}
+
+/// Logs a share of the requests, and every failed one, with their route,
+/// parameters, status, duration and bodies, redacted.
+pub async fn log_requests(
+    State(logger): State<RequestLogger>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(logger) = logger.0 else {
+        return next.run(req).await;
+    };
+
+    let started = Instant::now();
+    let request_id = uuid::Uuid::new_v4();
+    let (mut parts, body) = req.into_parts();
+    let route = parts
+        .extensions
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+    let sampled = logger.sampled(route.as_deref().unwrap_or_default(), &request_id);
+    let method = parts.method.to_string();
+    let params = RawPathParams::from_request_parts(&mut parts, &())
+        .await
+        .ok()
+        .filter(|params| params.iter().next().is_some())
+        .map(|params| logger.rules.pairs(params.iter()));
+    let query = Query::<Vec<(String, String)>>::try_from_uri(&parts.uri)
+        .ok()
+        .filter(|query| !query.is_empty())
+        .map(|query| {
+            let pairs = query
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()));
+            logger.rules.pairs(pairs)
+        });
+    let (request_body, body) = if sampled {
+        logger.capture(is_json(&parts.headers), body).await
+    } else {
+        (None, body)
+    };
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+    let status = response.status();
+    if !sampled && !status.is_client_error() && !status.is_server_error() {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let (response_body, body) = logger.capture(is_json(&parts.headers), body).await;
+
+    let mut line = json!({
+        "ts": chrono::Utc::now().to_rfc3339(),
+        "request_id": request_id.to_string(),
+        "method": method,
+        "route": route,
+        "status": status.as_u16(),
+        "duration_ms": started.elapsed().as_millis() as u64,
+        "sampled": sampled,
+    });
+    for (name, value) in [
+        ("params", params),
+        ("query", query),
+        ("request_body", request_body),
+        ("response_body", response_body),
+    ] {
+        if let Some(value) = value {
+            line[name] = value;
+        }
+    }
+    log::info!(target: TARGET, "{}", line);
+
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> Rules {
+        Rules::new(&settings::RequestLogging::default().redact)
+    }
+
+    #[test]
+    fn fields_are_redacted_at_any_depth() {
+        let mut body = json!({
+            "pix_key": "user@example.com",
+            "address": "lq1qqf8er278e6nyvuwtgf39e6ewvdcnjupn9a86rzpx655y5lhkt0walu3djf9cklkxd3ryld97hu8h3xepw7sh2rlu7q45dcew5",
+            "amount_in_cents": 5000,
+            "payouts": [{"payout_address": "ex1qshort", "amount": 10}],
+            "payer_name": "Fulano de Tal",
+            "Email": null,
+        });
+        rules().json(&mut body);
+
+        assert_eq!(body["pix_key"], "[redacted]");
+        assert_eq!(body["address"], "****cew5");
+        assert_eq!(body["amount_in_cents"], 5000);
+        assert_eq!(body["payouts"][0]["payout_address"], "****hort");
+        assert_eq!(body["payouts"][0]["amount"], 10);
+        assert!(body["payer_name"]
+            .as_str()
+            .is_some_and(|hash| hash.starts_with("sha256:") && !hash.contains("Fulano")));
+        assert_eq!(body["Email"], Value::Null);
+    }
+
+    #[test]
+    fn camel_case_eulen_fields_are_redacted() {
+        let mut body = json!({
+            "bankTxId": "fitbank_E0000000020250710123456789012345",
+            "blockchainTxID": "",
+            "customerMessage": "",
+            "payerName": "Fulano de Tal",
+            "payerTaxNumber": "12345678901",
+            "expiration": "2025-07-11T12:00:00Z",
+            "pixKey": "user@example.com",
+            "qrId": "0197f1c2-4b5e-7a3d-9c1e-2f8a6b4d0e17",
+            "status": "depix_sent",
+            "valueInCents": 5000,
+        });
+        rules().json(&mut body);
+
+        assert_eq!(body["pixKey"], "[redacted]");
+        assert_eq!(body["payerTaxNumber"], "****8901");
+        assert!(body["payerName"]
+            .as_str()
+            .is_some_and(|hash| hash.starts_with("sha256:")));
+        assert_eq!(body["qrId"], "0197f1c2-4b5e-7a3d-9c1e-2f8a6b4d0e17");
+        assert_eq!(body["valueInCents"], 5000);
+
+        let mut deposit =
+            json!({"id": "q1", "qrCopyPaste": "00020101021226", "userAddress": "lq1qqexample"});
+        rules().json(&mut deposit);
+        assert_eq!(deposit["qrCopyPaste"], "[redacted]");
+        assert_eq!(deposit["userAddress"], "****mple");
+    }
+
+    #[test]
+    fn short_values_are_masked_whole() {
+        assert_eq!(redact("1234567", Redaction::Mask), "****");
+        assert_eq!(redact("12345678", Redaction::Mask), "****5678");
+        assert_eq!(
+            redact("abc", Redaction::Hash),
+            redact("abc", Redaction::Hash)
+        );
+    }
+
+    #[test]
+    fn pairs_are_redacted_by_name() {
+        let pairs = rules().pairs([("token", "secret"), ("month", "2025-07")].into_iter());
+        assert_eq!(pairs, json!({"token": "[redacted]", "month": "2025-07"}));
+    }
+}
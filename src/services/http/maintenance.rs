@@ -0,0 +1,148 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use super::validation::ValidJson;
+use crate::models::operator::{KillSwitchRequest, NewMaintenanceWindow};
+use crate::services::transactions::TransactionServiceRequest;
+
+/// Maintenance windows not over yet, soonest first, with whether deposits
+/// are closed right now.
+pub async fn get_maintenance_windows(State(state): State<super::AppState>) -> impl IntoResponse {
+    let (windows_tx, windows_rx) = oneshot::channel();
+    let (closure_tx, closure_rx) = oneshot::channel();
+
+    for request in [
+        TransactionServiceRequest::GetMaintenanceWindows {
+            response: windows_tx,
+        },
+        TransactionServiceRequest::GetDepositClosure {
+            response: closure_tx,
+        },
+    ] {
+        if let Err(e) = state.transaction_channel.send(request).await {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Internal server error",
+                    "details": e.to_string()
+                })),
+            );
+        }
+    }
+
+    match (windows_rx.await, closure_rx.await) {
+        (Ok(windows), Ok(closure)) => (
+            StatusCode::OK,
+            Json(json!({"windows": windows, "closure": closure})),
+        ),
+        (Err(e), _) | (_, Err(e)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Closes new deposits from `starts_at` to `ends_at`.
+pub async fn schedule_maintenance(
+    State(state): State<super::AppState>,
+    ValidJson(req): ValidJson<NewMaintenanceWindow>,
+) -> impl IntoResponse {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::ScheduleMaintenance {
+            window: req,
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(window)) => (StatusCode::CREATED, Json(json!(window))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not schedule maintenance",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Cancels a scheduled window, reopening deposits if it was under way.
+/// Windows from the settings can't be cancelled.
+pub async fn cancel_maintenance(
+    State(state): State<super::AppState>,
+    Path(window_id): Path<String>,
+    ValidJson(req): ValidJson<KillSwitchRequest>,
+) -> impl IntoResponse {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::CancelMaintenance {
+            id: window_id,
+            requested_by: req.requested_by,
+            reason: req.reason,
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(true)) => (StatusCode::OK, Json(json!({"cancelled": true}))),
+        Ok(Ok(false)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "No scheduled maintenance window with this id"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not cancel maintenance",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
@@ -0,0 +1,364 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use super::validation::ValidJson;
+use crate::models::merchants::{Merchant, NewMerchant, NewPaymentLink, PaymentLink};
+use crate::services::merchants::MerchantRequest;
+use crate::services::ServiceError;
+use crate::utils::money;
+
+pub async fn register_merchant(
+    State(state): State<super::AppState>,
+    ValidJson(req): ValidJson<NewMerchant>,
+) -> impl IntoResponse {
+    let (merchant_tx, merchant_rx) = oneshot::channel();
+
+    let merchant_result = state
+        .merchant_channel
+        .send(MerchantRequest::RegisterMerchant {
+            merchant: req,
+            response: merchant_tx,
+        })
+        .await;
+    if let Err(e) = merchant_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match merchant_rx.await {
+        Ok(Ok(credentials)) => (StatusCode::CREATED, Json(json!(credentials))),
+        Ok(Err(service_error)) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Could not register merchant",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Resolves the merchant from its `Authorization: Bearer` API key.
+async fn authenticate(
+    state: &super::AppState,
+    headers: &HeaderMap,
+) -> Result<Merchant, (StatusCode, Json<serde_json::Value>)> {
+    let unauthorized = || {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"error": "Unauthorized"})),
+        )
+    };
+
+    let api_key = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|token| !token.is_empty())
+        .ok_or_else(unauthorized)?;
+
+    let (merchant_tx, merchant_rx) = oneshot::channel();
+    state
+        .merchant_channel
+        .send(MerchantRequest::AuthenticateMerchant {
+            api_key: api_key.to_string(),
+            response: merchant_tx,
+        })
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Internal server error",
+                    "details": e.to_string()
+                })),
+            )
+        })?;
+
+    match merchant_rx.await {
+        Ok(Ok(Some(merchant))) => Ok(merchant),
+        Ok(Ok(None)) => Err(unauthorized()),
+        Ok(Err(service_error)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Database error",
+                "details": service_error.to_string()
+            })),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )),
+    }
+}
+
+pub async fn create_payment_link(
+    State(state): State<super::AppState>,
+    headers: HeaderMap,
+    ValidJson(req): ValidJson<NewPaymentLink>,
+) -> impl IntoResponse {
+    let merchant = match authenticate(&state, &headers).await {
+        Ok(merchant) => merchant,
+        Err(response) => return response,
+    };
+
+    let (merchant_tx, merchant_rx) = oneshot::channel();
+    let merchant_result = state
+        .merchant_channel
+        .send(MerchantRequest::CreatePaymentLink {
+            merchant,
+            link: req,
+            response: merchant_tx,
+        })
+        .await;
+    if let Err(e) = merchant_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match merchant_rx.await {
+        Ok(Ok(link)) => (StatusCode::CREATED, Json(link_json(&link))),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "DealerPaused" => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "Service paused",
+                "details": "Serviço pausado para manutenção, tente novamente mais tarde."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "AssetUnavailable" => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "Asset temporarily unavailable",
+                "details": "Ativo temporariamente indisponível, escolha outro ativo ou tente novamente mais tarde."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "PixUnavailable" => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "PIX temporarily unavailable",
+                "details": "PIX temporariamente indisponível, tente novamente em alguns minutos."
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Could not create payment link",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn get_payment_links(
+    State(state): State<super::AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let merchant = match authenticate(&state, &headers).await {
+        Ok(merchant) => merchant,
+        Err(response) => return response,
+    };
+
+    let (merchant_tx, merchant_rx) = oneshot::channel();
+    let merchant_result = state
+        .merchant_channel
+        .send(MerchantRequest::GetMerchantPaymentLinks {
+            merchant_id: merchant.id,
+            response: merchant_tx,
+        })
+        .await;
+    if let Err(e) = merchant_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match merchant_rx.await {
+        Ok(Ok(links)) => (
+            StatusCode::OK,
+            Json(json!(links.iter().map(link_json).collect::<Vec<_>>())),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Database error",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// QR payload of a payment link, for merchants rendering their own checkout.
+pub async fn get_payment_link(
+    State(state): State<super::AppState>,
+    Path(token): Path<String>,
+) -> impl IntoResponse {
+    match fetch_payment_link(&state, token).await {
+        Ok(link) => (StatusCode::OK, Json(public_link_json(&link))),
+        Err(response) => response,
+    }
+}
+
+/// Payment page hosted by the dealer.
+pub async fn get_payment_page(
+    State(state): State<super::AppState>,
+    Path(token): Path<String>,
+) -> Response {
+    let link = match fetch_payment_link(&state, token).await {
+        Ok(link) => link,
+        Err(response) => return response.into_response(),
+    };
+
+    let body = match link.status() {
+        "open" => format!(
+            r#"<img src="{}" alt="QR Code PIX" width="280" height="280">
+<p>Ou use o PIX Copia e Cola:</p>
+<textarea readonly rows="4" cols="40">{}</textarea>"#,
+            escape_html(&link.qr_image_url),
+            escape_html(&link.qr_copy_paste)
+        ),
+        "paid" => "<p>Pagamento confirmado. Obrigado!</p>".to_string(),
+        "refunded" => "<p>Este pagamento foi estornado.</p>".to_string(),
+        _ => "<p>Este link de pagamento expirou.</p>".to_string(),
+    };
+
+    Html(format!(
+        r#"<!DOCTYPE html>
+<html lang="pt-BR">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Pagamento - {merchant}</title>
+</head>
+<body style="font-family: sans-serif; text-align: center; padding: 24px;">
+<h1>{merchant}</h1>
+<p>{description}</p>
+<h2>{amount}</h2>
+{body}
+</body>
+</html>"#,
+        merchant = escape_html(&link.merchant_name),
+        description = escape_html(link.description.as_deref().unwrap_or("")),
+        amount = money::format_brl(link.amount_in_cents.into()),
+        body = body
+    ))
+    .into_response()
+}
+
+async fn fetch_payment_link(
+    state: &super::AppState,
+    token: String,
+) -> Result<PaymentLink, (StatusCode, Json<serde_json::Value>)> {
+    let (merchant_tx, merchant_rx) = oneshot::channel();
+    state
+        .merchant_channel
+        .send(MerchantRequest::GetPaymentLink {
+            token,
+            response: merchant_tx,
+        })
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Internal server error",
+                    "details": e.to_string()
+                })),
+            )
+        })?;
+
+    match merchant_rx.await {
+        Ok(Ok(Some(link))) => Ok(link),
+        Ok(Ok(None)) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Payment link not found",
+                "details": "Link de pagamento não encontrado."
+            })),
+        )),
+        Ok(Err(service_error)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Database error",
+                "details": service_error.to_string()
+            })),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )),
+    }
+}
+
+fn link_json(link: &PaymentLink) -> serde_json::Value {
+    let mut value = json!(link);
+    value["status"] = json!(link.status());
+    value
+}
+
+/// Link fields safe to show to the payer.
+fn public_link_json(link: &PaymentLink) -> serde_json::Value {
+    json!({
+        "token": link.token,
+        "merchant_name": link.merchant_name,
+        "amount_in_cents": link.amount_in_cents,
+        "description": link.description,
+        "status": link.status(),
+        "qr_copy_paste": link.qr_copy_paste,
+        "qr_image_url": link.qr_image_url,
+    })
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
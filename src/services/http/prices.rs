@@ -0,0 +1,153 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use super::validation::ValidJson;
+use crate::models::price::PriceQuery;
+use crate::models::quotes::QuoteRequest;
+use crate::models::transactions::Assets;
+use crate::services::price::PriceRequest;
+use crate::services::transactions::TransactionServiceRequest;
+use crate::services::ServiceError;
+
+/// Current buy price of an asset, as used for new deposits.
+pub async fn get_price(
+    State(state): State<super::AppState>,
+    Query(query): Query<PriceQuery>,
+) -> impl IntoResponse {
+    let asset = match Assets::from_hex(&query.asset) {
+        Ok(asset) => asset,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": "Invalid asset",
+                    "details": e
+                })),
+            )
+        }
+    };
+
+    let (price_tx, price_rx) = oneshot::channel();
+    let price_result = state
+        .price_channel
+        .send(PriceRequest::GetPrice {
+            asset,
+            currency: query.currency,
+            response: price_tx,
+        })
+        .await;
+    if let Err(e) = price_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match price_rx.await {
+        Ok(Ok(Some(price))) => {
+            let ttl_secs = (price.expires_at - chrono::Utc::now()).num_seconds().max(0);
+
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "asset": query.asset,
+                    "currency": query.currency,
+                    "price": price.price,
+                    "aggregation": price.aggregation,
+                    "updated_at": price.updated_at,
+                    "expires_at": price.expires_at,
+                    "ttl_secs": ttl_secs
+                })),
+            )
+        }
+        Ok(Ok(None)) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "Price unavailable",
+                "details": "Cotação indisponível no momento, tente novamente em instantes."
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Locks the current price of an asset for a deposit created shortly after.
+pub async fn create_quote(
+    State(state): State<super::AppState>,
+    ValidJson(req): ValidJson<QuoteRequest>,
+) -> Response {
+    let (quote_tx, quote_rx) = oneshot::channel();
+
+    let request = TransactionServiceRequest::CreateQuote {
+        asset: req.asset,
+        response: quote_tx,
+    };
+    if let Err(e) = super::enqueue(&state.transaction_channel, "transactions", request).await {
+        return e.into_response();
+    }
+
+    let response = match quote_rx.await {
+        Ok(Ok(quote)) => (
+            StatusCode::CREATED,
+            Json(json!({
+                "quote_id": quote.id,
+                "asset": quote.asset,
+                "currency": quote.quote_currency,
+                "price_in_cents": quote.price_in_cents,
+                "expires_at": quote.expires_at
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "Invalid asset" => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Invalid asset",
+                "details": "Ativo inválido."
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "Asset price not found" => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "Price unavailable",
+                "details": "Cotação indisponível no momento, tente novamente em instantes."
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    };
+
+    response.into_response()
+}
@@ -0,0 +1,248 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use crate::models::referrals::ReferralReportQuery;
+use crate::models::users::StatementQuery;
+use crate::services::reports::ReportRequest;
+use crate::services::users::UserRequest;
+
+fn parse_month(month: &str) -> Option<chrono::NaiveDate> {
+    chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").ok()
+}
+
+fn invalid_month() -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "Invalid month",
+            "details": "Informe o mês no formato AAAA-MM."
+        })),
+    )
+        .into_response()
+}
+
+fn internal_error(e: impl ToString) -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({
+            "error": "Internal server error",
+            "details": e.to_string()
+        })),
+    )
+        .into_response()
+}
+
+/// Resolves the referrer from their `Authorization: Bearer` API key.
+async fn authenticate(state: &super::AppState, headers: &HeaderMap) -> Result<String, Response> {
+    let unauthorized = || {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"error": "Unauthorized"})),
+        )
+            .into_response()
+    };
+
+    let api_key = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|token| !token.is_empty())
+        .ok_or_else(unauthorized)?;
+
+    let (user_tx, user_rx) = oneshot::channel();
+    state
+        .user_channel
+        .send(UserRequest::AuthenticateReferrer {
+            api_key: api_key.to_string(),
+            response: user_tx,
+        })
+        .await
+        .map_err(internal_error)?;
+
+    match user_rx.await {
+        Ok(Ok(Some(referrer_id))) => Ok(referrer_id),
+        Ok(Ok(None)) => Err(unauthorized()),
+        Ok(Err(service_error)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Database error",
+                "details": service_error.to_string()
+            })),
+        )
+            .into_response()),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+/// Bonuses the referrer earned and was paid in a month.
+pub async fn get_referrer_statement(
+    State(state): State<super::AppState>,
+    headers: HeaderMap,
+    Query(query): Query<StatementQuery>,
+) -> Response {
+    let referrer_id = match authenticate(&state, &headers).await {
+        Ok(referrer_id) => referrer_id,
+        Err(response) => return response,
+    };
+    let Some(month) = parse_month(&query.month) else {
+        return invalid_month();
+    };
+
+    let (user_tx, user_rx) = oneshot::channel();
+    let user_result = state
+        .user_channel
+        .send(UserRequest::GetReferrerStatement {
+            id: referrer_id,
+            month,
+            response: user_tx,
+        })
+        .await;
+    if let Err(e) = user_result {
+        return internal_error(e);
+    }
+
+    match user_rx.await {
+        Ok(Ok(statement)) => (StatusCode::OK, Json(json!(statement))).into_response(),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not generate statement",
+                "details": service_error.to_string()
+            })),
+        )
+            .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Bonuses paid to the referrer, newest first, with the payout txid.
+pub async fn get_referrer_payments(
+    State(state): State<super::AppState>,
+    headers: HeaderMap,
+) -> Response {
+    let referrer_id = match authenticate(&state, &headers).await {
+        Ok(referrer_id) => referrer_id,
+        Err(response) => return response,
+    };
+
+    let (user_tx, user_rx) = oneshot::channel();
+    let user_result = state
+        .user_channel
+        .send(UserRequest::GetReferrerPayments {
+            id: referrer_id,
+            response: user_tx,
+        })
+        .await;
+    if let Err(e) = user_result {
+        return internal_error(e);
+    }
+
+    match user_rx.await {
+        Ok(Ok(payments)) => (StatusCode::OK, Json(json!({ "payments": payments }))).into_response(),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not get payments",
+                "details": service_error.to_string()
+            })),
+        )
+            .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Issues the statement key of a referrer, replacing any they had.
+pub async fn issue_referrer_key(
+    State(state): State<super::AppState>,
+    Path(user_id): Path<String>,
+) -> Response {
+    let (user_tx, user_rx) = oneshot::channel();
+    let user_result = state
+        .user_channel
+        .send(UserRequest::IssueReferrerKey {
+            id: user_id,
+            response: user_tx,
+        })
+        .await;
+    if let Err(e) = user_result {
+        return internal_error(e);
+    }
+
+    match user_rx.await {
+        Ok(Ok(Some(credentials))) => {
+            (StatusCode::CREATED, Json(json!(credentials))).into_response()
+        }
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Referrer not found"
+            })),
+        )
+            .into_response(),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not issue key",
+                "details": service_error.to_string()
+            })),
+        )
+            .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Referral rollups of every referrer in a month, as a CSV download for
+/// finance.
+pub async fn get_referral_report(
+    State(state): State<super::AppState>,
+    Query(query): Query<ReferralReportQuery>,
+) -> Response {
+    let Some(month) = parse_month(&query.month) else {
+        return invalid_month();
+    };
+
+    let (report_tx, report_rx) = oneshot::channel();
+    let report_result = state
+        .report_channel
+        .send(ReportRequest::ReferralRollups {
+            month,
+            response: report_tx,
+        })
+        .await;
+    if let Err(e) = report_result {
+        return internal_error(e);
+    }
+
+    match report_rx.await {
+        Ok(Ok(report)) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!(
+                        "attachment; filename=\"referrals-{}.csv\"",
+                        month.format("%Y-%m")
+                    ),
+                ),
+            ],
+            report,
+        )
+            .into_response(),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not generate report",
+                "details": service_error.to_string()
+            })),
+        )
+            .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
@@ -0,0 +1,129 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use super::validation::ValidJson;
+use crate::models::refunds::{RefundDecision, RefundDecisionRequest, RefundQuery};
+use crate::services::pix::PixServiceRequest;
+
+/// Surplus of overpaid PIX charges, newest first: the ones still `due`
+/// unless `status` asks for another.
+pub async fn get_refunds(
+    State(state): State<super::AppState>,
+    Query(query): Query<RefundQuery>,
+) -> impl IntoResponse {
+    let statuses = vec![query.status.unwrap_or_else(|| "due".to_string())];
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+
+    let (pix_tx, pix_rx) = oneshot::channel();
+    let pix_result = state
+        .pix_channel
+        .send(PixServiceRequest::GetRefunds {
+            statuses,
+            limit,
+            response: pix_tx,
+        })
+        .await;
+    if let Err(e) = pix_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match pix_rx.await {
+        Ok(Ok(refunds)) => (StatusCode::OK, Json(json!(refunds))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not list refunds",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Marks a refund paid back to the payer, outside the dealer.
+pub async fn mark_refunded(
+    State(state): State<super::AppState>,
+    Path(refund_id): Path<String>,
+    ValidJson(req): ValidJson<RefundDecisionRequest>,
+) -> impl IntoResponse {
+    resolve_refund(state, refund_id, RefundDecision::Refunded, req).await
+}
+
+pub async fn dismiss_refund(
+    State(state): State<super::AppState>,
+    Path(refund_id): Path<String>,
+    ValidJson(req): ValidJson<RefundDecisionRequest>,
+) -> impl IntoResponse {
+    resolve_refund(state, refund_id, RefundDecision::Dismissed, req).await
+}
+
+async fn resolve_refund(
+    state: super::AppState,
+    id: String,
+    decision: RefundDecision,
+    req: RefundDecisionRequest,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let (pix_tx, pix_rx) = oneshot::channel();
+
+    let pix_result = state
+        .pix_channel
+        .send(PixServiceRequest::ResolveRefund {
+            id,
+            decision,
+            decided_by: req.decided_by,
+            note: req.note,
+            response: pix_tx,
+        })
+        .await;
+    if let Err(e) = pix_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match pix_rx.await {
+        Ok(Ok(Some(refund))) => (StatusCode::OK, Json(json!(refund))),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "No due refund with this id"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not resolve refund",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
@@ -0,0 +1,38 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::json;
+
+use crate::services::sideswap;
+
+/// Lets the replay tool check it is not about to feed captured traffic to a
+/// dealer that broadcasts.
+pub async fn get_replay_mode(State(state): State<super::AppState>) -> impl IntoResponse {
+    (StatusCode::OK, Json(json!({"dry_run": state.dry_run})))
+}
+
+/// Sideswap notifications arrive over the websocket, so replays are posted
+/// here instead. Only accepted in dry-run mode.
+pub async fn replay_sideswap_notification(
+    State(state): State<super::AppState>,
+    Json(notification): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    if !state.dry_run {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Dry run disabled",
+                "details": "Notificações só podem ser reenviadas em modo dry-run."
+            })),
+        );
+    }
+
+    match sideswap::replay_notification(notification, &state.sideswap_channel).await {
+        Ok(()) => (StatusCode::OK, Json(json!({"status": "processed"}))),
+        Err(e) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": "Could not process notification",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
@@ -0,0 +1,203 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use super::validation::ValidJson;
+use crate::models::reports::TaxReportQuery;
+use crate::models::reserves::{ReserveProof, ReserveProofRequest};
+use crate::services::reports::ReportRequest;
+
+/// A stored proof exactly as it was hashed, so the hash can be checked
+/// against the body.
+fn proof_response(status: StatusCode, proof: ReserveProof) -> Response {
+    (
+        status,
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::HeaderName::from_static("x-proof-sha256"),
+                proof.hash,
+            ),
+        ],
+        proof.content,
+    )
+        .into_response()
+}
+
+/// IN RFB 1888 report of a month, generated on the first request and stored.
+pub async fn get_in1888_report(
+    State(state): State<super::AppState>,
+    Query(query): Query<TaxReportQuery>,
+) -> Response {
+    let Ok(month) = chrono::NaiveDate::parse_from_str(&format!("{}-01", query.month), "%Y-%m-%d")
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Invalid month",
+                "details": "Pass the month as YYYY-MM"
+            })),
+        )
+            .into_response();
+    };
+    let (report_tx, report_rx) = oneshot::channel();
+
+    let report_result = state
+        .report_channel
+        .send(ReportRequest::In1888 {
+            month,
+            regenerate: query.regenerate,
+            response: report_tx,
+        })
+        .await;
+    if let Err(e) = report_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response();
+    }
+
+    match report_rx.await {
+        Ok(Ok(report)) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!(
+                        "attachment; filename=\"in1888-{}.csv\"",
+                        month.format("%Y-%m")
+                    ),
+                ),
+            ],
+            report,
+        )
+            .into_response(),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not generate report",
+                "details": service_error.to_string()
+            })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// Signs every wallet address holding funds and publishes the balances with
+/// what is owed to users.
+pub async fn create_reserve_proof(
+    State(state): State<super::AppState>,
+    ValidJson(request): ValidJson<ReserveProofRequest>,
+) -> Response {
+    let (report_tx, report_rx) = oneshot::channel();
+
+    let report_result = state
+        .report_channel
+        .send(ReportRequest::CreateReserveProof {
+            challenge: request.challenge,
+            response: report_tx,
+        })
+        .await;
+    if let Err(e) = report_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response();
+    }
+
+    match report_rx.await {
+        Ok(Ok(proof)) => proof_response(StatusCode::CREATED, proof),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not generate proof of reserves",
+                "details": service_error.to_string()
+            })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// Published proof of reserves by its hash.
+pub async fn get_reserve_proof(
+    State(state): State<super::AppState>,
+    Path(hash): Path<String>,
+) -> Response {
+    let (report_tx, report_rx) = oneshot::channel();
+
+    let report_result = state
+        .report_channel
+        .send(ReportRequest::GetReserveProof {
+            hash,
+            response: report_tx,
+        })
+        .await;
+    if let Err(e) = report_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response();
+    }
+
+    match report_rx.await {
+        Ok(Ok(Some(proof))) => proof_response(StatusCode::OK, proof),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Proof not found",
+                "details": "Prova de reservas não encontrada."
+            })),
+        )
+            .into_response(),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not get proof of reserves",
+                "details": service_error.to_string()
+            })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response(),
+    }
+}
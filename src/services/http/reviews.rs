@@ -0,0 +1,119 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use super::validation::ValidJson;
+use crate::models::reviews::{ReviewDecision, ReviewDecisionRequest};
+use crate::services::transactions::TransactionServiceRequest;
+
+pub async fn get_held_transactions(State(state): State<super::AppState>) -> impl IntoResponse {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::GetHeldTransactions {
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(held)) => (StatusCode::OK, Json(json!(held))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not list held transactions",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn approve_transaction(
+    State(state): State<super::AppState>,
+    Path(transaction_id): Path<String>,
+    ValidJson(req): ValidJson<ReviewDecisionRequest>,
+) -> impl IntoResponse {
+    resolve_review(state, transaction_id, ReviewDecision::Approve, req).await
+}
+
+pub async fn reject_transaction(
+    State(state): State<super::AppState>,
+    Path(transaction_id): Path<String>,
+    ValidJson(req): ValidJson<ReviewDecisionRequest>,
+) -> impl IntoResponse {
+    resolve_review(state, transaction_id, ReviewDecision::Reject, req).await
+}
+
+async fn resolve_review(
+    state: super::AppState,
+    transaction_id: String,
+    decision: ReviewDecision,
+    req: ReviewDecisionRequest,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::ResolveReview {
+            transaction_id,
+            decision,
+            decided_by: req.decided_by,
+            note: req.note,
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match transaction_rx.await {
+        Ok(Ok(true)) => (StatusCode::OK, Json(json!({"resolved": true}))),
+        Ok(Ok(false)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "No pending review for this transaction"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not resolve review",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
@@ -0,0 +1,172 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use super::validation::ValidJson;
+use crate::models::schedules::{NewSchedule, ScheduleAction};
+use crate::services::scheduler::SchedulerRequest;
+
+pub async fn create_schedule(
+    State(state): State<super::AppState>,
+    ValidJson(req): ValidJson<NewSchedule>,
+) -> impl IntoResponse {
+    let (scheduler_tx, scheduler_rx) = oneshot::channel();
+
+    let scheduler_result = state
+        .scheduler_channel
+        .send(SchedulerRequest::CreateSchedule {
+            schedule: req,
+            response: scheduler_tx,
+        })
+        .await;
+    if let Err(e) = scheduler_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match scheduler_rx.await {
+        Ok(Ok(schedule)) => (StatusCode::CREATED, Json(json!(schedule))),
+        Ok(Err(service_error)) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Could not create schedule",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn get_user_schedules(
+    State(state): State<super::AppState>,
+    Path(user_id): Path<String>,
+) -> impl IntoResponse {
+    let (scheduler_tx, scheduler_rx) = oneshot::channel();
+
+    let scheduler_result = state
+        .scheduler_channel
+        .send(SchedulerRequest::GetUserSchedules {
+            user_id,
+            response: scheduler_tx,
+        })
+        .await;
+    if let Err(e) = scheduler_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match scheduler_rx.await {
+        Ok(Ok(schedules)) => (StatusCode::OK, Json(json!(schedules))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Database error",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn pause_schedule(
+    State(state): State<super::AppState>,
+    Path(schedule_id): Path<String>,
+    ValidJson(req): ValidJson<ScheduleAction>,
+) -> impl IntoResponse {
+    set_schedule_status(state, schedule_id, req.user_id, "paused").await
+}
+
+pub async fn resume_schedule(
+    State(state): State<super::AppState>,
+    Path(schedule_id): Path<String>,
+    ValidJson(req): ValidJson<ScheduleAction>,
+) -> impl IntoResponse {
+    set_schedule_status(state, schedule_id, req.user_id, "active").await
+}
+
+pub async fn cancel_schedule(
+    State(state): State<super::AppState>,
+    Path(schedule_id): Path<String>,
+    ValidJson(req): ValidJson<ScheduleAction>,
+) -> impl IntoResponse {
+    set_schedule_status(state, schedule_id, req.user_id, "cancelled").await
+}
+
+async fn set_schedule_status(
+    state: super::AppState,
+    schedule_id: String,
+    user_id: String,
+    status: &str,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let (scheduler_tx, scheduler_rx) = oneshot::channel();
+
+    let scheduler_result = state
+        .scheduler_channel
+        .send(SchedulerRequest::SetScheduleStatus {
+            schedule_id,
+            user_id,
+            status: status.to_string(),
+            response: scheduler_tx,
+        })
+        .await;
+    if let Err(e) = scheduler_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match scheduler_rx.await {
+        Ok(Ok(Some(schedule))) => (StatusCode::OK, Json(json!(schedule))),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Schedule not found"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Database error",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
@@ -0,0 +1,190 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use crate::models::operator::{ArchiveExportQuery, Cursor, TransactionSearch};
+use crate::services::pix::PixServiceRequest;
+use crate::services::transactions::TransactionServiceRequest;
+
+/// Cursor and page size of a listing; a cursor we didn't hand out is refused.
+fn page_bounds(
+    cursor: Option<&str>,
+    limit: Option<i64>,
+) -> Result<(Option<Cursor>, i64), impl IntoResponse> {
+    let limit = limit.unwrap_or(50).clamp(1, 500);
+
+    match cursor.map(Cursor::decode) {
+        None => Ok((None, limit)),
+        Some(Some(cursor)) => Ok((Some(cursor), limit)),
+        Some(None) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Invalid cursor",
+                "details": "Pass the next_cursor of the previous page"
+            })),
+        )),
+    }
+}
+
+/// Transactions matching every filter given, newest first.
+pub async fn search_transactions(
+    State(state): State<super::AppState>,
+    Query(search): Query<TransactionSearch>,
+) -> impl IntoResponse {
+    let (after, limit) = match page_bounds(search.cursor.as_deref(), search.limit) {
+        Ok(bounds) => bounds,
+        Err(response) => return response.into_response(),
+    };
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::SearchTransactions {
+            search,
+            after,
+            limit,
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response();
+    }
+
+    let response = match transaction_rx.await {
+        Ok(Ok(page)) => (StatusCode::OK, Json(json!(page))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not search transactions",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    };
+
+    response.into_response()
+}
+
+/// PIX charges matching every filter given, newest first. `asset` and
+/// `user_id` match the deposit the charge pays.
+pub async fn search_pix_transactions(
+    State(state): State<super::AppState>,
+    Query(search): Query<TransactionSearch>,
+) -> impl IntoResponse {
+    let (after, limit) = match page_bounds(search.cursor.as_deref(), search.limit) {
+        Ok(bounds) => bounds,
+        Err(response) => return response.into_response(),
+    };
+    let (pix_tx, pix_rx) = oneshot::channel();
+
+    let pix_result = state
+        .pix_channel
+        .send(PixServiceRequest::SearchPixTransactions {
+            search,
+            after,
+            limit,
+            response: pix_tx,
+        })
+        .await;
+    if let Err(e) = pix_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response();
+    }
+
+    let response = match pix_rx.await {
+        Ok(Ok(page)) => (StatusCode::OK, Json(json!(page))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not search PIX transactions",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    };
+
+    response.into_response()
+}
+
+/// Archived transactions with their PIX charges, oldest first, for export.
+pub async fn export_archive(
+    State(state): State<super::AppState>,
+    Query(query): Query<ArchiveExportQuery>,
+) -> impl IntoResponse {
+    let (after, limit) = match page_bounds(query.cursor.as_deref(), query.limit) {
+        Ok(bounds) => bounds,
+        Err(response) => return response.into_response(),
+    };
+    let (transaction_tx, transaction_rx) = oneshot::channel();
+
+    let transaction_result = state
+        .transaction_channel
+        .send(TransactionServiceRequest::GetArchivedTransactions {
+            since: query.since,
+            until: query.until,
+            after,
+            limit,
+            response: transaction_tx,
+        })
+        .await;
+    if let Err(e) = transaction_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response();
+    }
+
+    let response = match transaction_rx.await {
+        Ok(Ok(page)) => (StatusCode::OK, Json(json!(page))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not export archived transactions",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    };
+
+    response.into_response()
+}
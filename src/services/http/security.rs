@@ -0,0 +1,88 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::settings;
+
+/// CORS for the endpoints called by the web clients, answering their
+/// preflight requests. None when no origin is allowed.
+pub fn cors_layer(settings: &settings::Http) -> Option<CorsLayer> {
+    if settings.allowed_origins.is_empty() {
+        return None;
+    }
+
+    let allow_origin = if settings.allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        // Checked when the settings are loaded
+        AllowOrigin::list(
+            settings
+                .allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST, Method::PUT])
+            .allow_headers([header::CONTENT_TYPE, header::IF_NONE_MATCH])
+            .expose_headers([header::ETAG, header::RETRY_AFTER])
+            .max_age(Duration::from_secs(settings.cors_max_age_secs)),
+    )
+}
+
+/// Headers added to every response that does not set them itself.
+#[derive(Clone, Default)]
+pub struct SecurityHeaders(Arc<Vec<(HeaderName, HeaderValue)>>);
+
+impl SecurityHeaders {
+    pub fn new(settings: &settings::Http) -> Self {
+        if !settings.security_headers {
+            return Self::default();
+        }
+
+        let mut headers = vec![
+            (
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ),
+            (header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY")),
+            (
+                header::REFERRER_POLICY,
+                HeaderValue::from_static("no-referrer"),
+            ),
+        ];
+        if settings.hsts_max_age_secs > 0 {
+            let hsts = format!("max-age={}", settings.hsts_max_age_secs);
+            if let Ok(value) = HeaderValue::from_str(&hsts) {
+                headers.push((header::STRICT_TRANSPORT_SECURITY, value));
+            }
+        }
+
+        Self(Arc::new(headers))
+    }
+}
+
+pub async fn add_security_headers(
+    State(security_headers): State<SecurityHeaders>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+    for (name, value) in security_headers.0.iter() {
+        if !headers.contains_key(name) {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+
+    response
+}
@@ -0,0 +1,184 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use std::collections::VecDeque;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Mutex};
+
+use crate::models::health::PublicStatus;
+use crate::services::transactions::TransactionServiceRequest;
+use crate::utils::metrics;
+
+/// How long a computed status is served before it is computed again.
+const STATUS_TTL: Duration = Duration::from_secs(30);
+/// Period the average delivery time is taken over.
+const DELIVERY_WINDOW: Duration = Duration::from_secs(60 * 60);
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+/// A service that does not answer within this counts as unavailable.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Totals of `payout_delivery_seconds` at some point in time.
+#[derive(Clone, Copy)]
+struct DeliverySample {
+    at: Instant,
+    count: u64,
+    sum: f64,
+}
+
+impl DeliverySample {
+    fn now() -> Self {
+        let (count, sum) = metrics::histogram_totals("payout_delivery_seconds");
+        Self {
+            at: Instant::now(),
+            count,
+            sum,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Cached {
+    status: Option<(Instant, PublicStatus)>,
+    /// One sample a minute, back to the start of the delivery window.
+    deliveries: VecDeque<DeliverySample>,
+}
+
+/// Last public status, so a busy status page does not reach the services on
+/// every request.
+#[derive(Default)]
+pub struct StatusCache {
+    cached: Mutex<Cached>,
+}
+
+impl StatusCache {
+    /// Samples the delivery times every minute until the cache is dropped.
+    pub fn start() -> Arc<Self> {
+        let cache = Arc::new(Self::default());
+        let weak = Arc::downgrade(&cache);
+        tokio::spawn(sample_deliveries(weak));
+        cache
+    }
+
+    /// Average delivery over the window, from the oldest sample in it.
+    fn average_delivery_secs(deliveries: &VecDeque<DeliverySample>) -> Option<u64> {
+        let oldest = deliveries.front()?;
+        let latest = DeliverySample::now();
+        let count = latest.count.checked_sub(oldest.count)?;
+        if count == 0 {
+            return None;
+        }
+
+        Some(((latest.sum - oldest.sum) / count as f64).round() as u64)
+    }
+}
+
+async fn sample_deliveries(cache: Weak<StatusCache>) {
+    let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let Some(cache) = cache.upgrade() else {
+            return;
+        };
+
+        let mut cached = cache.cached.lock().await;
+        let sample = DeliverySample::now();
+        while cached
+            .deliveries
+            .front()
+            .is_some_and(|oldest| sample.at.duration_since(oldest.at) > DELIVERY_WINDOW)
+        {
+            cached.deliveries.pop_front();
+        }
+        cached.deliveries.push_back(sample);
+    }
+}
+
+/// Availability of the dealer for a public status page. Cached for
+/// `STATUS_TTL`.
+pub async fn get_status(State(state): State<super::AppState>) -> impl IntoResponse {
+    let mut cached = state.status_cache.cached.lock().await;
+    let status = match &cached.status {
+        Some((computed_at, status)) if computed_at.elapsed() < STATUS_TTL => status.clone(),
+        _ => {
+            let status = compute_status(&state, &cached.deliveries).await;
+            cached.status = Some((Instant::now(), status.clone()));
+            status
+        }
+    };
+
+    (
+        StatusCode::OK,
+        [(
+            header::CACHE_CONTROL,
+            format!("public, max-age={}", STATUS_TTL.as_secs()),
+        )],
+        Json(status),
+    )
+}
+
+async fn compute_status(
+    state: &super::AppState,
+    deliveries: &VecDeque<DeliverySample>,
+) -> PublicStatus {
+    // A service that does not answer takes no deposits either
+    let paused = request(state, |response| TransactionServiceRequest::IsPaused {
+        response,
+    })
+    .await
+    .unwrap_or(true);
+    let closure = request(state, |response| {
+        TransactionServiceRequest::GetDepositClosure { response }
+    })
+    .await
+    .flatten();
+    let assets = request(state, |response| {
+        TransactionServiceRequest::GetAssetAvailability { response }
+    })
+    .await
+    .unwrap_or_default();
+
+    let pix = if !state.health.is_available("eulen_webhooks") {
+        "unavailable"
+    } else if metrics::gauge("eulen_circuit_open", &[]) == Some(1.0) {
+        "degraded"
+    } else {
+        "operational"
+    };
+
+    PublicStatus {
+        accepting_deposits: !paused && closure.is_none() && state.health.is_available("deposits"),
+        deposits_reopen_at: closure.and_then(|closure| closure.reopens_at),
+        assets,
+        pix,
+        average_delivery_secs: StatusCache::average_delivery_secs(deliveries),
+        updated_at: chrono::Utc::now(),
+    }
+}
+
+/// Asks the transaction service, or None when it does not answer in time.
+async fn request<T>(
+    state: &super::AppState,
+    build: impl FnOnce(oneshot::Sender<T>) -> TransactionServiceRequest,
+) -> Option<T> {
+    let (response_tx, response_rx) = oneshot::channel();
+    let exchange = async {
+        state
+            .transaction_channel
+            .send(build(response_tx))
+            .await
+            .ok()?;
+        response_rx.await.ok()
+    };
+
+    match tokio::time::timeout(REQUEST_TIMEOUT, exchange).await {
+        Ok(Some(response)) => Some(response),
+        _ => {
+            log::warn!("Transaction service did not answer for the public status");
+            None
+        }
+    }
+}
@@ -1,18 +1,140 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use tokio::sync::oneshot;
 
+use super::validation::ValidJson;
+use crate::models::receipts::{NotificationPreferences, NotificationPreferencesUpdate};
+use crate::models::users::{DataSubjectRequest, StatementQuery};
 use crate::services::users::UserRequest;
+use crate::services::ServiceError;
 
-pub async fn get_user_details(
+/// Whether the request carries the `Authorization: Bearer` API key of the
+/// user it is about.
+async fn authenticate(
+    state: &super::AppState,
+    headers: &HeaderMap,
+    user_id: &str,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let unauthorized = || {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"error": "Unauthorized"})),
+        )
+    };
+
+    let api_key = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|token| !token.is_empty())
+        .ok_or_else(unauthorized)?;
+
+    let (user_tx, user_rx) = oneshot::channel();
+    let user_result = state
+        .user_channel
+        .send(UserRequest::AuthenticateUser {
+            api_key: api_key.to_string(),
+            response: user_tx,
+        })
+        .await;
+    if let Err(e) = user_result {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ));
+    }
+
+    match user_rx.await {
+        Ok(Ok(Some(authenticated))) if authenticated == user_id => Ok(()),
+        Ok(Ok(_)) => Err(unauthorized()),
+        Ok(Err(service_error)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Database error",
+                "details": service_error.to_string()
+            })),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )),
+    }
+}
+
+/// Issues the API key of a user, replacing any they had.
+pub(super) async fn new_user_key(
+    state: &super::AppState,
+    user_id: String,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let (user_tx, user_rx) = oneshot::channel();
+    let user_result = state
+        .user_channel
+        .send(UserRequest::IssueUserKey {
+            id: user_id,
+            response: user_tx,
+        })
+        .await;
+    if let Err(e) = user_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match user_rx.await {
+        Ok(Ok(Some(credentials))) => (StatusCode::CREATED, Json(json!(credentials))),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "User not found"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not issue key",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Issues a new API key for a user registered before keys were handed out,
+/// or who lost theirs.
+pub async fn issue_user_key(
     State(state): State<super::AppState>,
     Path(user_id): Path<String>,
 ) -> impl IntoResponse {
+    new_user_key(&state, user_id).await
+}
+
+pub async fn get_user_details(
+    State(state): State<super::AppState>,
+    Path(user_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
     let (user_tx, user_rx) = oneshot::channel();
 
     let user_result = state
@@ -29,50 +151,425 @@ pub async fn get_user_details(
                 "error": "Internal server error",
                 "details": e.to_string()
             })),
-        );
+        )
+            .into_response();
     }
 
     match user_rx.await {
-        Ok(Ok(user)) => {
-            match user {
-                Some(user) => {
-                    return (
-                        StatusCode::OK,
-                        Json(json!({
-                        "user_id": user.id,
-                        "daily_spending": user.daily_spending,
-                        "allowed_spending": user.allowed_spending,
-                        "verified": user.is_verified
-                    })),
-                );
-            }
-            None => {
-                return (
-                    StatusCode::NOT_FOUND,
-                    Json(json!({
-                        "error": "User not found"
-                    })),
-                );
-            }
+        Ok(Ok(user)) => match user {
+            Some(user) => {
+                let body = json!({
+                    "user_id": user.id,
+                    "daily_spending": user.daily_spending,
+                    "allowed_spending": user.allowed_spending,
+                    "verified": user.is_verified
+                });
+
+                with_etag(&headers, body)
             }
-        }
-        Ok(Err(service_error)) => {
-            return (
+            None => (
                 StatusCode::NOT_FOUND,
                 Json(json!({
-                    "error": "Database error",
-                    "details": service_error.to_string()
+                    "error": "User not found"
                 })),
-            );
-        }
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "error": "Internal server error",
-                    "details": e.to_string()
-                })),
-            );
-        }
+            )
+                .into_response(),
+        },
+        Ok(Err(service_error)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Database error",
+                "details": service_error.to_string()
+            })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response(),
+    }
+}
+
+pub async fn export_user_data(
+    State(state): State<super::AppState>,
+    Path(user_id): Path<String>,
+    ValidJson(req): ValidJson<DataSubjectRequest>,
+) -> impl IntoResponse {
+    let (user_tx, user_rx) = oneshot::channel();
+
+    let user_result = state
+        .user_channel
+        .send(UserRequest::ExportUserData {
+            id: user_id,
+            request: req,
+            response: user_tx,
+        })
+        .await;
+    if let Err(e) = user_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match user_rx.await {
+        Ok(Ok(Some(export))) => (StatusCode::OK, Json(json!(export))),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "User not found"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not export user data",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn anonymize_user(
+    State(state): State<super::AppState>,
+    Path(user_id): Path<String>,
+    ValidJson(req): ValidJson<DataSubjectRequest>,
+) -> impl IntoResponse {
+    let (user_tx, user_rx) = oneshot::channel();
+
+    let user_result = state
+        .user_channel
+        .send(UserRequest::AnonymizeUser {
+            id: user_id,
+            request: req,
+            response: user_tx,
+        })
+        .await;
+    if let Err(e) = user_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match user_rx.await {
+        Ok(Ok(true)) => (StatusCode::OK, Json(json!({"anonymized": true}))),
+        Ok(Ok(false)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "User not found"
+            })),
+        ),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "UnsettledTransactions" => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "User has transactions not settled yet; anonymize once they are"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not anonymize user",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Risk score of a user with the factors behind it, for operators.
+pub async fn get_risk_score(
+    State(state): State<super::AppState>,
+    Path(user_id): Path<String>,
+) -> impl IntoResponse {
+    let (user_tx, user_rx) = oneshot::channel();
+
+    let user_result = state
+        .user_channel
+        .send(UserRequest::GetRiskScore {
+            id: user_id,
+            response: user_tx,
+        })
+        .await;
+    if let Err(e) = user_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match user_rx.await {
+        Ok(Ok(Some(score))) => (StatusCode::OK, Json(json!(score))),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "User not found"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not score user",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+pub async fn get_notification_preferences(
+    State(state): State<super::AppState>,
+    Path(user_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(response) = authenticate(&state, &headers, &user_id).await {
+        return response;
+    }
+    let (user_tx, user_rx) = oneshot::channel();
+
+    let user_result = state
+        .user_channel
+        .send(UserRequest::GetNotificationPreferences {
+            id: user_id,
+            response: user_tx,
+        })
+        .await;
+    if let Err(e) = user_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    preferences_response(user_rx.await)
+}
+
+/// Replaces the receipt preferences of a user.
+pub async fn set_notification_preferences(
+    State(state): State<super::AppState>,
+    Path(user_id): Path<String>,
+    headers: HeaderMap,
+    ValidJson(req): ValidJson<NotificationPreferencesUpdate>,
+) -> impl IntoResponse {
+    if let Err(response) = authenticate(&state, &headers, &user_id).await {
+        return response;
+    }
+    let (user_tx, user_rx) = oneshot::channel();
+
+    let user_result = state
+        .user_channel
+        .send(UserRequest::SetNotificationPreferences {
+            id: user_id,
+            preferences: req,
+            response: user_tx,
+        })
+        .await;
+    if let Err(e) = user_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
     }
+
+    preferences_response(user_rx.await)
+}
+
+fn preferences_response(
+    result: Result<
+        Result<Option<NotificationPreferences>, ServiceError>,
+        oneshot::error::RecvError,
+    >,
+) -> (StatusCode, Json<serde_json::Value>) {
+    match result {
+        Ok(Ok(Some(preferences))) => (StatusCode::OK, Json(json!(preferences))),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "User not found"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not load notification preferences",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Signed JSON statement of a user's month, to keep as proof of purchase.
+/// The signature covers the exact body, with the key in the headers.
+pub async fn get_statement(
+    State(state): State<super::AppState>,
+    Path(user_id): Path<String>,
+    Query(query): Query<StatementQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(response) = authenticate(&state, &headers, &user_id).await {
+        return response.into_response();
+    }
+    let Ok(month) = chrono::NaiveDate::parse_from_str(&format!("{}-01", query.month), "%Y-%m-%d")
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Invalid month",
+                "details": "Informe o mês no formato AAAA-MM."
+            })),
+        )
+            .into_response();
+    };
+    let (user_tx, user_rx) = oneshot::channel();
+
+    let user_result = state
+        .user_channel
+        .send(UserRequest::GetStatement {
+            id: user_id,
+            month,
+            response: user_tx,
+        })
+        .await;
+    if let Err(e) = user_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response();
+    }
+
+    match user_rx.await {
+        Ok(Ok(Some(statement))) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "application/json".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!(
+                        "attachment; filename=\"statement-{}.json\"",
+                        month.format("%Y-%m")
+                    ),
+                ),
+                (
+                    header::HeaderName::from_static("x-statement-sha256"),
+                    statement.signature.sha256,
+                ),
+                (
+                    header::HeaderName::from_static("x-statement-signature"),
+                    statement.signature.signature,
+                ),
+                (
+                    header::HeaderName::from_static("x-statement-public-key"),
+                    statement.signature.public_key,
+                ),
+            ],
+            statement.body,
+        )
+            .into_response(),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "User not found"
+            })),
+        )
+            .into_response(),
+        Ok(Err(ServiceError::Internal(reason))) if reason == "StatementsUnavailable" => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "Statements unavailable",
+                "details": "Extratos indisponíveis no momento."
+            })),
+        )
+            .into_response(),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not generate statement",
+                "details": service_error.to_string()
+            })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// Answers 304 when the client already holds the current representation.
+fn with_etag(headers: &HeaderMap, body: serde_json::Value) -> Response {
+    let etag = format!("\"{:x}\"", Sha256::digest(body.to_string().as_bytes()));
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*"))
+        .unwrap_or(false);
+
+    if not_modified {
+        return (
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag), (header::CACHE_CONTROL, "no-cache".to_string())],
+        )
+            .into_response();
+    }
+
+    (
+        StatusCode::OK,
+        [(header::ETAG, etag), (header::CACHE_CONTROL, "no-cache".to_string())],
+        Json(body),
+    )
+        .into_response()
 }
@@ -0,0 +1,52 @@
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+use crate::utils::validation::Validate;
+
+/// JSON body that is rejected unless every field passes its checks. Bodies
+/// that can't be parsed get 400, invalid fields 422 with the fields at fault.
+pub struct ValidJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for ValidJson<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(invalid_body)?;
+
+        match value.validate() {
+            Ok(()) => Ok(Self(value)),
+            Err(fields) => Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({
+                    "error": "Invalid request",
+                    "details": "Dados inválidos, verifique os campos informados.",
+                    "fields": fields
+                })),
+            )
+                .into_response()),
+        }
+    }
+}
+
+fn invalid_body(rejection: JsonRejection) -> Response {
+    (
+        rejection.status(),
+        Json(json!({
+            "error": "Invalid request body",
+            "details": rejection.body_text()
+        })),
+    )
+        .into_response()
+}
@@ -0,0 +1,263 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use tokio::sync::oneshot;
+
+use crate::models::wallet::{DerivedAddressPage, DerivedAddressQuery, FeeAddressReceiptQuery};
+use crate::services::liquid::LiquidRequest;
+
+/// CT descriptor of the hot wallet, for watch-only monitoring. The mnemonic
+/// never leaves the dealer.
+pub async fn get_wallet_descriptor(State(state): State<super::AppState>) -> impl IntoResponse {
+    let (liquid_tx, liquid_rx) = oneshot::channel();
+
+    let liquid_result = state
+        .liquid_channel
+        .send(LiquidRequest::GetWatchOnlyDescriptor {
+            response: liquid_tx,
+        })
+        .await;
+    if let Err(e) = liquid_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match liquid_rx.await {
+        Ok(Ok(descriptor)) => (StatusCode::OK, Json(json!(descriptor))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not export wallet descriptor",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Addresses derived from the wallet descriptor, so an auditor can check
+/// them against the ones the dealer handed out.
+pub async fn get_derived_addresses(
+    State(state): State<super::AppState>,
+    Query(query): Query<DerivedAddressQuery>,
+) -> impl IntoResponse {
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let (liquid_tx, liquid_rx) = oneshot::channel();
+
+    let liquid_result = state
+        .liquid_channel
+        .send(LiquidRequest::GetDerivedAddresses {
+            change: query.change,
+            offset,
+            limit,
+            response: liquid_tx,
+        })
+        .await;
+    if let Err(e) = liquid_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match liquid_rx.await {
+        Ok(Ok(addresses)) => (
+            StatusCode::OK,
+            Json(json!(DerivedAddressPage {
+                change: query.change,
+                offset,
+                limit,
+                addresses,
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not derive addresses",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Derives the address recorded for a transaction again and checks it
+/// matches, e.g. after restoring the wallet.
+pub async fn verify_transaction_address(
+    State(state): State<super::AppState>,
+    Path(transaction_id): Path<String>,
+) -> impl IntoResponse {
+    let (liquid_tx, liquid_rx) = oneshot::channel();
+
+    let liquid_result = state
+        .liquid_channel
+        .send(LiquidRequest::VerifyTransactionAddress {
+            transaction_id,
+            response: liquid_tx,
+        })
+        .await;
+    if let Err(e) = liquid_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match liquid_rx.await {
+        Ok(Ok(Some(verification))) => (StatusCode::OK, Json(json!(verification))),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Address not recorded",
+                "details": "No wallet address was recorded for this transaction"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not verify address",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// DEPIX received on fee addresses, newest first: the unexpected ones,
+/// `late`, `repeat` and `unassigned`, unless `status` asks for another.
+pub async fn get_fee_address_receipts(
+    State(state): State<super::AppState>,
+    Query(query): Query<FeeAddressReceiptQuery>,
+) -> impl IntoResponse {
+    let statuses = match query.status {
+        Some(status) => vec![status],
+        None => vec![
+            "late".to_string(),
+            "repeat".to_string(),
+            "unassigned".to_string(),
+        ],
+    };
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let (liquid_tx, liquid_rx) = oneshot::channel();
+
+    let liquid_result = state
+        .liquid_channel
+        .send(LiquidRequest::GetFeeAddressReceipts {
+            statuses,
+            limit,
+            response: liquid_tx,
+        })
+        .await;
+    if let Err(e) = liquid_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match liquid_rx.await {
+        Ok(Ok(receipts)) => (StatusCode::OK, Json(json!(receipts))),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not list fee address receipts",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
+
+/// Network, Electrum server and chain tip a payout or swap was broadcast
+/// with, for transfers reported as not received.
+pub async fn get_broadcast(
+    State(state): State<super::AppState>,
+    Path(txid): Path<String>,
+) -> impl IntoResponse {
+    let (liquid_tx, liquid_rx) = oneshot::channel();
+
+    let liquid_result = state
+        .liquid_channel
+        .send(LiquidRequest::GetBroadcast {
+            txid,
+            response: liquid_tx,
+        })
+        .await;
+    if let Err(e) = liquid_result {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        );
+    }
+
+    match liquid_rx.await {
+        Ok(Ok(Some(broadcast))) => (StatusCode::OK, Json(json!(broadcast))),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Broadcast not recorded",
+                "details": "No broadcast was recorded for this txid"
+            })),
+        ),
+        Ok(Err(service_error)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Could not get broadcast",
+                "details": service_error.to_string()
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": "Internal server error",
+                "details": e.to_string()
+            })),
+        ),
+    }
+}
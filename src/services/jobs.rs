@@ -0,0 +1,258 @@
+use crate::models::jobs::JobStatus;
+use crate::utils::{cron::Cron, metrics};
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Notify;
+use tokio::task::AbortHandle;
+use tokio::time::{Duration, Instant};
+
+/// When a job runs.
+#[derive(Clone, Debug)]
+pub enum JobSchedule {
+    /// Right away, then once the period passed since the last start.
+    Every(Duration),
+    Cron(Cron),
+}
+
+impl JobSchedule {
+    pub fn every_secs(secs: u64) -> Self {
+        Self::Every(Duration::from_secs(secs))
+    }
+
+    /// A cron expression, or an interval such as `30s`, `5m`, `1h` or `1d`.
+    pub fn parse(value: &str) -> Result<Self, anyhow::Error> {
+        let value = value.trim();
+        if value.contains(char::is_whitespace) {
+            return Ok(Self::Cron(Cron::parse(value)?));
+        }
+
+        let unit_at = value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len());
+        let (amount, unit) = value.split_at(unit_at);
+        let unit_secs = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => bail!("Invalid job schedule: {}", value),
+        };
+
+        match amount.parse::<u64>() {
+            Ok(amount) if amount > 0 => Ok(Self::every_secs(amount * unit_secs)),
+            _ => bail!("Invalid job schedule: {}", value),
+        }
+    }
+
+    fn next_run(&self, last_started_at: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+        match (self, last_started_at) {
+            (Self::Every(_), None) => Some(Utc::now()),
+            (Self::Every(period), Some(last)) => {
+                Some(last + chrono::Duration::from_std(*period).ok()?)
+            }
+            (Self::Cron(cron), _) => cron.next_after(Utc::now()),
+        }
+    }
+}
+
+impl std::fmt::Display for JobSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Every(period) => write!(f, "{}s", period.as_secs()),
+            Self::Cron(cron) => write!(f, "{}", cron),
+        }
+    }
+}
+
+struct Job {
+    status: JobStatus,
+    trigger: Arc<Notify>,
+    task: Option<AbortHandle>,
+}
+
+#[derive(Default)]
+struct Registry {
+    jobs: DashMap<&'static str, Job>,
+    /// Schedules from `[jobs]`, replacing the ones jobs are registered with.
+    overrides: OnceLock<HashMap<String, JobSchedule>>,
+}
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(Registry::default)
+}
+
+/// Checks the schedules of `[jobs]` before any job is registered.
+pub fn configure(schedules: &HashMap<String, String>) -> Result<(), anyhow::Error> {
+    let overrides = schedules
+        .iter()
+        .map(|(name, schedule)| {
+            let schedule =
+                JobSchedule::parse(schedule).map_err(|e| anyhow!("jobs.{}: {}", name, e))?;
+            Ok((name.clone(), schedule))
+        })
+        .collect::<Result<HashMap<_, _>, anyhow::Error>>()?;
+
+    let _ = registry().overrides.set(overrides);
+    Ok(())
+}
+
+/// Runs `run` on `schedule`, or on the schedule configured for `name`. A
+/// service registering its jobs again after a restart replaces them; the
+/// run counts carry over. Runs of a job never overlap.
+pub fn register<F, Fut>(name: &'static str, schedule: JobSchedule, run: F)
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), anyhow::Error>> + Send + 'static,
+{
+    let schedule = registry()
+        .overrides
+        .get()
+        .and_then(|overrides| overrides.get(name))
+        .cloned()
+        .unwrap_or(schedule);
+    let trigger = Arc::new(Notify::new());
+
+    let status = match registry().jobs.remove(name) {
+        Some((_, previous)) => {
+            if let Some(task) = previous.task {
+                task.abort();
+            }
+            JobStatus {
+                schedule: schedule.to_string(),
+                running: false,
+                next_run_at: None,
+                ..previous.status
+            }
+        }
+        None => JobStatus {
+            name,
+            schedule: schedule.to_string(),
+            running: false,
+            next_run_at: None,
+            last_started_at: None,
+            last_finished_at: None,
+            last_duration_ms: None,
+            last_error: None,
+            runs: 0,
+            failures: 0,
+            consecutive_failures: 0,
+        },
+    };
+    registry().jobs.insert(
+        name,
+        Job {
+            status,
+            trigger: trigger.clone(),
+            task: None,
+        },
+    );
+
+    let task = tokio::spawn(run_job(name, schedule, trigger, run));
+    if let Some(mut job) = registry().jobs.get_mut(name) {
+        job.task = Some(task.abort_handle());
+    }
+}
+
+/// Every registered job, by name.
+pub fn statuses() -> Vec<JobStatus> {
+    let mut statuses: Vec<JobStatus> = registry()
+        .jobs
+        .iter()
+        .map(|job| job.status.clone())
+        .collect();
+    statuses.sort_by_key(|status| status.name);
+    statuses
+}
+
+/// Runs a job now, or right after its current run. False for an unknown job.
+pub fn trigger(name: &str) -> bool {
+    match registry().jobs.get(name) {
+        Some(job) => {
+            job.trigger.notify_one();
+            true
+        }
+        None => false,
+    }
+}
+
+fn update(name: &'static str, f: impl FnOnce(&mut JobStatus)) {
+    if let Some(mut job) = registry().jobs.get_mut(name) {
+        f(&mut job.status);
+    }
+}
+
+async fn run_job<F, Fut>(name: &'static str, schedule: JobSchedule, trigger: Arc<Notify>, run: F)
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), anyhow::Error>> + Send + 'static,
+{
+    let labels = [("job", name)];
+    let mut last_started_at = None;
+
+    loop {
+        let next_run_at = schedule.next_run(last_started_at);
+        update(name, |status| status.next_run_at = next_run_at);
+
+        match next_run_at {
+            Some(next_run_at) => {
+                let wait = (next_run_at - Utc::now()).to_std().unwrap_or_default();
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = trigger.notified() => {}
+                }
+            }
+            None => trigger.notified().await,
+        }
+
+        let started_at = Utc::now();
+        last_started_at = Some(started_at);
+        update(name, |status| {
+            status.running = true;
+            status.next_run_at = None;
+            status.last_started_at = Some(started_at);
+        });
+
+        // In a task of its own, so a panicking run is a failed run
+        let timer = Instant::now();
+        let result = match tokio::spawn(run()).await {
+            Ok(result) => result,
+            Err(e) => Err(anyhow!("Job panicked: {}", e)),
+        };
+        let elapsed = timer.elapsed();
+
+        metrics::observe("job_duration_seconds", &labels, elapsed.as_secs_f64());
+        let outcome = match &result {
+            Ok(()) => "success",
+            Err(e) => {
+                log::error!("Job {} failed: {}", name, e);
+                "failure"
+            }
+        };
+        metrics::increment("job_runs_total", &[("job", name), ("outcome", outcome)]);
+
+        update(name, |status| {
+            status.running = false;
+            status.last_finished_at = Some(Utc::now());
+            status.last_duration_ms = Some(elapsed.as_millis() as u64);
+            status.runs += 1;
+            match result {
+                Ok(()) => {
+                    status.last_error = None;
+                    status.consecutive_failures = 0;
+                }
+                Err(e) => {
+                    status.last_error = Some(e.to_string());
+                    status.failures += 1;
+                    status.consecutive_failures += 1;
+                }
+            }
+        });
+    }
+}
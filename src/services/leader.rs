@@ -0,0 +1,96 @@
+use crate::settings::Leader;
+use crate::utils::metrics;
+
+use sqlx::{PgConnection, PgPool};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Whether this instance is the leader, shared with the services and jobs
+/// that move money and only run there.
+#[derive(Clone)]
+pub struct Leadership {
+    is_leader: watch::Receiver<bool>,
+}
+
+impl Leadership {
+    /// Leader from the start, when instances are not coordinated.
+    pub(super) fn sole() -> Self {
+        let (_, is_leader) = watch::channel(true);
+        Self { is_leader }
+    }
+
+    pub fn is_leader(&self) -> bool {
+        *self.is_leader.borrow()
+    }
+
+    /// Waits until this instance is the leader. Leadership is never given
+    /// up while the process runs, so there is nothing to wait for after.
+    pub async fn acquired(&self) {
+        let mut is_leader = self.is_leader.clone();
+        if is_leader.wait_for(|is_leader| *is_leader).await.is_err() {
+            // The election stopped without making us leader
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Campaigns for leadership by taking a session-level advisory lock on a
+/// connection of its own. Postgres releases the lock when that connection
+/// ends, so another instance takes over once the leader dies; a leader that
+/// can no longer reach the database exits, since it can't tell whether it
+/// still holds the lock.
+pub fn start_election(pool: PgPool, settings: Leader) -> Leadership {
+    if !settings.enabled {
+        metrics::set_gauge("leader", &[], 1.0);
+        return Leadership::sole();
+    }
+
+    let (sender, is_leader) = watch::channel(false);
+    metrics::set_gauge("leader", &[], 0.0);
+
+    tokio::spawn(async move {
+        let retry = Duration::from_secs(settings.retry_secs);
+
+        let mut conn = loop {
+            match try_lock(&pool, settings.lock_id).await {
+                Ok(Some(conn)) => break conn,
+                Ok(None) => {}
+                Err(e) => log::warn!("Could not campaign for leadership: {}", e),
+            }
+            tokio::time::sleep(retry).await;
+        };
+
+        log::info!("Took leadership, starting the services that move money");
+        metrics::set_gauge("leader", &[], 1.0);
+        sender.send_replace(true);
+
+        let heartbeat = Duration::from_secs(settings.heartbeat_secs);
+        loop {
+            tokio::time::sleep(heartbeat).await;
+
+            let alive =
+                tokio::time::timeout(heartbeat, sqlx::query("SELECT 1").execute(&mut conn)).await;
+            if !matches!(alive, Ok(Ok(_))) {
+                log::error!(
+                    "Lost the leadership connection, exiting before another instance takes over"
+                );
+                std::process::exit(1);
+            }
+        }
+    });
+
+    Leadership { is_leader }
+}
+
+/// The connection holding the lock, or `None` when another instance has it.
+async fn try_lock(pool: &PgPool, lock_id: i64) -> Result<Option<PgConnection>, anyhow::Error> {
+    // Out of the pool, so the lock isn't handed to another query with it
+    let mut conn = pool.acquire().await?.detach();
+
+    let locked = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock($1)")
+        .bind(lock_id)
+        .fetch_one(&mut conn)
+        .await?;
+
+    Ok(locked.then_some(conn))
+}
@@ -1,11 +1,27 @@
+use super::jobs::{self, JobSchedule};
 use super::{liquidity::LiquidityRequest, RequestHandler, Service, ServiceError};
+use crate::models::reserves::ReserveAddress;
+use crate::models::transactions::Assets;
+use crate::models::wallet::{
+    AddressVerification, BroadcastRecord, BroadcastTransaction, DerivedAddress, FeeAddressReceipt,
+    FeePriority, IncomingTransfer, PsetBalance, ReceivedOutput, WatchOnlyDescriptor,
+};
+use crate::repositories::addresses::AddressRepository;
+use crate::repositories::broadcasts::BroadcastRepository;
 use crate::repositories::liquid::LiquidRepository;
+use crate::settings::NetworkFees;
+use crate::utils::metrics;
 
 use async_trait::async_trait;
 use log::{error, info};
 use lwk_wollet::{elements::pset::PartiallySignedTransaction, UnvalidatedRecipient, WalletTxOut};
+use sqlx::PgPool;
 use std::sync::Arc;
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+/// Unused addresses a wallet restored from the mnemonic alone would look
+/// past before giving up.
+const GAP_LIMIT: u32 = 20;
 
 pub enum LiquidRequest {
     GetNewAddress {
@@ -24,6 +40,10 @@ pub enum LiquidRequest {
     },
     BuildTransaction {
         recipients: Vec<UnvalidatedRecipient>,
+        priority: FeePriority,
+        /// Spend the L-BTC coins leaving the least change, when only L-BTC
+        /// is paid.
+        avoid_change: bool,
         response: oneshot::Sender<Result<PartiallySignedTransaction, ServiceError>>,
     },
     SignTransaction {
@@ -34,68 +54,129 @@ pub enum LiquidRequest {
         pset: PartiallySignedTransaction,
         response: oneshot::Sender<Result<String, ServiceError>>,
     },
+    /// What the PSET does to the wallet, to check it before signing.
+    GetPsetBalance {
+        pset: PartiallySignedTransaction,
+        response: oneshot::Sender<Result<PsetBalance, ServiceError>>,
+    },
     FinalizeTransaction {
         pset: PartiallySignedTransaction,
-        response: oneshot::Sender<Result<String, ServiceError>>,
+        response: oneshot::Sender<Result<BroadcastTransaction, ServiceError>>,
+    },
+    HasTransaction {
+        txid: String,
+        response: oneshot::Sender<Result<bool, ServiceError>>,
+    },
+    /// Network, server and chain tip a transaction was broadcast with.
+    GetBroadcast {
+        txid: String,
+        response: oneshot::Sender<Result<Option<BroadcastRecord>, ServiceError>>,
+    },
+    /// Transactions that added the asset to the wallet since `since`.
+    GetIncomingTransfers {
+        asset_id: String,
+        since: chrono::DateTime<chrono::Utc>,
+        response: oneshot::Sender<Result<Vec<IncomingTransfer>, ServiceError>>,
+    },
+    /// Outputs of the asset received on receive addresses since `since`.
+    GetReceivedOutputs {
+        asset_id: String,
+        since: chrono::DateTime<chrono::Utc>,
+        response: oneshot::Sender<Result<Vec<ReceivedOutput>, ServiceError>>,
+    },
+    /// DEPIX receipts on receive addresses in `statuses`, newest first.
+    GetFeeAddressReceipts {
+        statuses: Vec<String>,
+        limit: i64,
+        response: oneshot::Sender<Result<Vec<FeeAddressReceipt>, ServiceError>>,
+    },
+    GetWatchOnlyDescriptor {
+        response: oneshot::Sender<Result<WatchOnlyDescriptor, ServiceError>>,
+    },
+    GetDerivedAddresses {
+        change: bool,
+        offset: u32,
+        limit: u32,
+        response: oneshot::Sender<Result<Vec<DerivedAddress>, ServiceError>>,
+    },
+    VerifyTransactionAddress {
+        transaction_id: String,
+        response: oneshot::Sender<Result<Option<AddressVerification>, ServiceError>>,
+    },
+    /// Addresses holding unspent outputs, each with a signature of `message`.
+    SignReserves {
+        message: String,
+        response: oneshot::Sender<Result<Vec<ReserveAddress>, ServiceError>>,
     },
 }
 
 #[derive(Clone)]
 pub struct LiquidRequestHandler {
     liquid_repository: Arc<LiquidRepository>,
+    address_repository: AddressRepository,
+    broadcast_repository: BroadcastRepository,
     liquidity_channel: mpsc::Sender<LiquidityRequest>,
+    network_fees: NetworkFees,
+    /// Serializes address allocation, so no index is handed out twice.
+    allocation: Arc<Mutex<()>>,
 }
 
 impl LiquidRequestHandler {
-    pub fn new(
+    pub async fn new(
+        sql_conn: PgPool,
         liquidity_channel: mpsc::Sender<LiquidityRequest>,
         mnemonic: String,
         electrum_url: String,
         is_mainnet: bool,
-    ) -> Self {
-        let liquid_repository = LiquidRepository::new(&mnemonic, electrum_url, is_mainnet)
-            .expect("Could not instantiate Liquid Repository");
+        network_fees: NetworkFees,
+        dry_run: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let address_repository = AddressRepository::new(sql_conn.clone());
+        let broadcast_repository = BroadcastRepository::new(sql_conn);
 
-        Self {
+        // Scan up to every address handed out, even those past the gap limit
+        let external = address_repository.get_max_index("external").await?;
+        let internal = address_repository.get_max_index("internal").await?;
+        let scan_to_index = external.max(internal).unwrap_or(0);
+
+        let liquid_repository =
+            LiquidRepository::new(&mnemonic, electrum_url, is_mainnet, scan_to_index, dry_run)?;
+
+        Ok(Self {
             liquid_repository,
+            address_repository,
+            broadcast_repository,
             liquidity_channel,
-        }
+            network_fees,
+            allocation: Arc::new(Mutex::new(())),
+        })
     }
 
-    pub async fn start(&self) -> tokio::task::JoinHandle<()> {
+    pub fn start(&self) {
         let repository = self.liquid_repository.clone();
         let liquidity_channel = self.liquidity_channel.clone();
 
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
-            loop {
-                interval.tick().await;
+        jobs::register("wallet_sync", JobSchedule::every_secs(60), move || {
+            let repository = repository.clone();
+            let liquidity_channel = liquidity_channel.clone();
+            async move {
+                repository.update_wallet().await?;
+                info!("Wallet updated successfully");
 
-                match repository.update_wallet().await {
-                    Ok(_) => info!("Wallet updated successfully"),
-                    Err(e) => error!("Error updating wallet: {}", e),
-                };
-
-                let depix_amount = repository
-                    .get_asset_balance(
-                        "02f22f8d9c76ab41661a2729e4752e2c5d1a263012141b86ea98af5472df5189",
-                    )
-                    .await;
-
-                match depix_amount {
-                    Ok(amount) => {
-                        let _ = liquidity_channel
-                            .send(LiquidityRequest::UpdateAssetAmount{
-                                asset_id: "02f22f8d9c76ab41661a2729e4752e2c5d1a263012141b86ea98af5472df5189".to_string(),
-                                amount,
-                            }
-                            )
-                            .await;
-                    }
-                    Err(e) => error!("Error getting DEPIX balance: {}", e),
-                };
+                for asset in [Assets::DEPIX, Assets::LBTC, Assets::USDT] {
+                    let asset_id = asset.hex();
+                    match repository.get_asset_balance(&asset_id).await {
+                        Ok(amount) => {
+                            let _ = liquidity_channel
+                                .send(LiquidityRequest::UpdateAssetAmount { asset_id, amount })
+                                .await;
+                        }
+                        Err(e) => error!("Error getting {:?} balance: {}", asset, e),
+                    };
+                }
+                Ok(())
             }
-        })
+        });
     }
 
     async fn get_asset_balance(&self, asset_id: &String) -> Result<u64, ServiceError> {
@@ -106,17 +187,97 @@ impl LiquidRequestHandler {
     }
 
     async fn get_new_address(&self) -> Result<String, ServiceError> {
-        self.liquid_repository
-            .generate_address()
-            .await
-            .map_err(|e| ServiceError::Repository(String::from("Liquid"), e.to_string()))
+        self.allocate_address(false).await
     }
 
     async fn get_new_change_address(&self) -> Result<String, ServiceError> {
-        self.liquid_repository
-            .generate_change_address()
+        self.allocate_address(true).await
+    }
+
+    /// Hands out the address after both the last one seen on chain and the
+    /// last one recorded, and records it. Unpaid deposits leave unused
+    /// addresses behind, so the index can run past the gap limit; wallet
+    /// updates scan up to it anyway.
+    async fn allocate_address(&self, change: bool) -> Result<String, ServiceError> {
+        let map_err =
+            |e: anyhow::Error| ServiceError::Repository("Liquid".to_string(), e.to_string());
+        let chain = if change { "internal" } else { "external" };
+
+        let _allocation = self.allocation.lock().await;
+
+        let unused = self
+            .liquid_repository
+            .last_unused_index(change)
             .await
-            .map_err(|e| ServiceError::Repository(String::from("Liquid"), e.to_string()))
+            .map_err(map_err)?;
+        let index = match self
+            .address_repository
+            .get_max_index(chain)
+            .await
+            .map_err(map_err)?
+        {
+            Some(recorded) => unused.max(recorded + 1),
+            None => unused,
+        };
+
+        let address = self
+            .liquid_repository
+            .derive_address(change, index)
+            .await
+            .map_err(map_err)?;
+        self.address_repository
+            .insert_address(&address, chain, index)
+            .await
+            .map_err(map_err)?;
+        self.liquid_repository.extend_scan_to(index);
+
+        let gap = index - unused;
+        metrics::set_gauge("wallet_address_gap", &[("chain", chain)], gap as f64);
+        if gap >= GAP_LIMIT {
+            log::warn!(
+                "Handed out {} address {}, {} past the last used one; restoring from the mnemonic alone needs a scan up to it",
+                chain,
+                index,
+                gap
+            );
+        }
+
+        Ok(address)
+    }
+
+    async fn verify_transaction_address(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<AddressVerification>, ServiceError> {
+        let map_err =
+            |e: anyhow::Error| ServiceError::Repository("Liquid".to_string(), e.to_string());
+
+        let Some(recorded) = self
+            .address_repository
+            .get_transaction_address(transaction_id)
+            .await
+            .map_err(map_err)?
+        else {
+            return Ok(None);
+        };
+
+        let derived_address = self
+            .liquid_repository
+            .derive_address(
+                recorded.chain == "internal",
+                recorded.derivation_index as u32,
+            )
+            .await
+            .map_err(map_err)?;
+
+        Ok(Some(AddressVerification {
+            transaction_id: transaction_id.to_string(),
+            matches: derived_address == recorded.address,
+            address: recorded.address,
+            chain: recorded.chain,
+            derivation_index: recorded.derivation_index,
+            derived_address,
+        }))
     }
 
     async fn get_utxos(&self, asset: Option<String>) -> Result<Vec<WalletTxOut>, ServiceError> {
@@ -129,10 +290,18 @@ impl LiquidRequestHandler {
     async fn build_liquid_transaction(
         &self,
         recipients: Vec<UnvalidatedRecipient>,
+        priority: FeePriority,
+        avoid_change: bool,
     ) -> Result<PartiallySignedTransaction, ServiceError> {
+        let sat_vb = match priority {
+            FeePriority::Normal => self.network_fees.normal_sat_vb,
+            FeePriority::High => self.network_fees.high_sat_vb,
+        };
+
+        // lwk takes the fee rate in sat/kvB
         let tx = self
             .liquid_repository
-            .build_transaction(recipients)
+            .build_transaction(recipients, sat_vb * 1000.0, avoid_change)
             .await
             .map_err(|e| ServiceError::Repository(String::from("Liquid"), e.to_string()))?;
 
@@ -158,13 +327,102 @@ impl LiquidRequestHandler {
             .map_err(|e| ServiceError::Repository(String::from("Liquid"), e.to_string()))
     }
 
+    async fn pset_balance(
+        &self,
+        pset: &PartiallySignedTransaction,
+    ) -> Result<PsetBalance, ServiceError> {
+        self.liquid_repository
+            .pset_balance(pset)
+            .await
+            .map_err(|e| ServiceError::Repository(String::from("Liquid"), e.to_string()))
+    }
+
     async fn finalize_transaction(
         &self,
         pset: PartiallySignedTransaction,
-    ) -> Result<String, ServiceError> {
-        self.liquid_repository
+    ) -> Result<BroadcastTransaction, ServiceError> {
+        let broadcast = self
+            .liquid_repository
             .finalize_and_broadcast_transaction(pset)
             .await
+            .map_err(|e| ServiceError::Repository(String::from("Liquid"), e.to_string()))?;
+
+        // The transaction is out either way
+        if let Err(e) = self.broadcast_repository.insert_broadcast(&broadcast).await {
+            error!(
+                "Could not record the broadcast of {}: {}",
+                broadcast.txid, e
+            );
+        }
+
+        Ok(broadcast)
+    }
+
+    async fn has_transaction(&self, txid: &str) -> Result<bool, ServiceError> {
+        self.liquid_repository
+            .has_transaction(txid)
+            .await
+            .map_err(|e| ServiceError::Repository(String::from("Liquid"), e.to_string()))
+    }
+
+    async fn get_incoming_transfers(
+        &self,
+        asset_id: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<IncomingTransfer>, ServiceError> {
+        self.liquid_repository
+            .incoming_transfers(asset_id, since)
+            .await
+            .map_err(|e| ServiceError::Repository(String::from("Liquid"), e.to_string()))
+    }
+
+    async fn get_watch_only_descriptor(&self) -> Result<WatchOnlyDescriptor, ServiceError> {
+        let map_err =
+            |e: anyhow::Error| ServiceError::Repository("Liquid".to_string(), e.to_string());
+
+        let mut descriptor = self
+            .liquid_repository
+            .watch_only_descriptor()
+            .await
+            .map_err(map_err)?;
+
+        // Addresses handed out but not paid yet are not seen on chain
+        if let Some(index) = self
+            .address_repository
+            .get_max_index("external")
+            .await
+            .map_err(map_err)?
+        {
+            descriptor.next_address_index = descriptor.next_address_index.max(index + 1);
+        }
+        if let Some(index) = self
+            .address_repository
+            .get_max_index("internal")
+            .await
+            .map_err(map_err)?
+        {
+            descriptor.next_change_index = descriptor.next_change_index.max(index + 1);
+        }
+
+        Ok(descriptor)
+    }
+
+    async fn get_derived_addresses(
+        &self,
+        change: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<DerivedAddress>, ServiceError> {
+        self.liquid_repository
+            .derive_addresses(change, offset, limit)
+            .await
+            .map_err(|e| ServiceError::Repository(String::from("Liquid"), e.to_string()))
+    }
+
+    async fn sign_reserves(&self, message: &str) -> Result<Vec<ReserveAddress>, ServiceError> {
+        self.liquid_repository
+            .sign_reserves(message)
+            .await
             .map_err(|e| ServiceError::Repository(String::from("Liquid"), e.to_string()))
     }
 }
@@ -191,9 +449,13 @@ impl RequestHandler<LiquidRequest> for LiquidRequestHandler {
             }
             LiquidRequest::BuildTransaction {
                 recipients,
+                priority,
+                avoid_change,
                 response,
             } => {
-                let tx = self.build_liquid_transaction(recipients).await;
+                let tx = self
+                    .build_liquid_transaction(recipients, priority, avoid_change)
+                    .await;
                 let _ = response.send(tx);
             }
             LiquidRequest::SignTransaction { pset, response } => {
@@ -208,6 +470,78 @@ impl RequestHandler<LiquidRequest> for LiquidRequestHandler {
                 let signed_pset = self.sign_with_extra_details(pset).await;
                 let _ = response.send(signed_pset);
             }
+            LiquidRequest::GetPsetBalance { pset, response } => {
+                let balance = self.pset_balance(&pset).await;
+                let _ = response.send(balance);
+            }
+            LiquidRequest::HasTransaction { txid, response } => {
+                let known = self.has_transaction(&txid).await;
+                let _ = response.send(known);
+            }
+            LiquidRequest::GetBroadcast { txid, response } => {
+                let broadcast = self
+                    .broadcast_repository
+                    .get_broadcast(&txid)
+                    .await
+                    .map_err(|e| ServiceError::Repository("Broadcasts".to_string(), e.to_string()));
+                let _ = response.send(broadcast);
+            }
+            LiquidRequest::GetIncomingTransfers {
+                asset_id,
+                since,
+                response,
+            } => {
+                let transfers = self.get_incoming_transfers(&asset_id, since).await;
+                let _ = response.send(transfers);
+            }
+            LiquidRequest::GetReceivedOutputs {
+                asset_id,
+                since,
+                response,
+            } => {
+                let outputs = self
+                    .liquid_repository
+                    .received_outputs(&asset_id, since)
+                    .await
+                    .map_err(|e| ServiceError::Repository("Liquid".to_string(), e.to_string()));
+                let _ = response.send(outputs);
+            }
+            LiquidRequest::GetFeeAddressReceipts {
+                statuses,
+                limit,
+                response,
+            } => {
+                let receipts = self
+                    .address_repository
+                    .get_receipts(&statuses, limit)
+                    .await
+                    .map_err(|e| ServiceError::Repository("Addresses".to_string(), e.to_string()));
+                let _ = response.send(receipts);
+            }
+            LiquidRequest::GetWatchOnlyDescriptor { response } => {
+                let descriptor = self.get_watch_only_descriptor().await;
+                let _ = response.send(descriptor);
+            }
+            LiquidRequest::VerifyTransactionAddress {
+                transaction_id,
+                response,
+            } => {
+                let verification = self.verify_transaction_address(&transaction_id).await;
+                let _ = response.send(verification);
+            }
+            LiquidRequest::GetDerivedAddresses {
+                change,
+                offset,
+                limit,
+                response,
+            } => {
+                let addresses = self.get_derived_addresses(change, offset, limit).await;
+                let _ = response.send(addresses);
+            }
+            LiquidRequest::SignReserves { message, response } => {
+                let addresses = self.sign_reserves(&message).await;
+                let _ = response.send(addresses);
+            }
         }
     }
 }
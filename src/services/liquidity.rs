@@ -1,11 +1,17 @@
 
 use super::{
-    sideswap::SideswapRequest,
+    notifications::NotificationRequest,
+    venues::SwapRouter,
     RequestHandler, Service,
 };
+use crate::models::transactions::Assets;
+use crate::settings::LiquiditySwap;
+use crate::utils::metrics;
 
 use async_trait::async_trait;
-use tokio::sync::{mpsc, oneshot};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
 
 pub enum LiquidityRequest {
     UpdateAssetAmount { asset_id: String, amount: u64 },
@@ -13,49 +19,138 @@ pub enum LiquidityRequest {
 
 #[derive(Clone)]
 pub struct LiquidityHandler {
-    sideswap_channel: mpsc::Sender<SideswapRequest>,
-    depix_max_amount: u64,
+    swap_router: SwapRouter,
+    notification_channel: mpsc::Sender<NotificationRequest>,
+    /// Swap policies by sold asset id.
+    swaps: Arc<HashMap<String, LiquiditySwap>>,
+    low_water_marks: Arc<HashMap<String, u64>>,
+    below_low_water: Arc<Mutex<HashSet<String>>>,
 }
 
 impl LiquidityHandler {
-    pub fn new(depix_max_amount: u64, sideswap_channel: mpsc::Sender<SideswapRequest>) -> Self {
+    pub fn new(
+        depix_max_amount: u64,
+        swaps: Vec<LiquiditySwap>,
+        low_water_marks: HashMap<String, u64>,
+        swap_router: SwapRouter,
+        notification_channel: mpsc::Sender<NotificationRequest>,
+    ) -> Self {
+        let mut policies = HashMap::new();
+        for swap in swaps {
+            if policies.contains_key(&swap.sell_asset) {
+                log::warn!(
+                    "Ignoring duplicate liquidity swap policy for {}",
+                    swap.sell_asset
+                );
+                continue;
+            }
+            policies.insert(swap.sell_asset.clone(), swap);
+        }
+
+        // DEPIX is sold into LBTC unless configured otherwise
+        policies
+            .entry(Assets::DEPIX.hex())
+            .or_insert_with(|| LiquiditySwap {
+                sell_asset: Assets::DEPIX.hex(),
+                receive_asset: Assets::LBTC.hex(),
+                max_amount: depix_max_amount,
+                min_amount: 0,
+            });
+
         Self {
-            sideswap_channel,
-            depix_max_amount,
+            swap_router,
+            notification_channel,
+            swaps: Arc::new(policies),
+            low_water_marks: Arc::new(low_water_marks),
+            below_low_water: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 
     async fn manage_asset_liquidity(&self, asset_id: String, balance: u64) {
-        match asset_id.as_str() {
-            "02f22f8d9c76ab41661a2729e4752e2c5d1a263012141b86ea98af5472df5189" => {
-                self.manage_depix_liquidity(balance).await;
-            }
-            _ => {
-                log::warn!("Unsupported asset ID: {}", asset_id);
+        metrics::set_gauge("wallet_balance", &[("asset", &asset_id)], balance as f64);
+        self.check_low_water_mark(&asset_id, balance).await;
+
+        match self.swaps.get(&asset_id) {
+            Some(policy) => self.rebalance(policy, balance).await,
+            None => {
+                log::debug!("No liquidity management for asset ID: {}", asset_id);
             }
         }
     }
 
-    async fn manage_depix_liquidity(&self, current_balance: u64) {
-        if current_balance > self.depix_max_amount {
-            let (swap_tx, swap_rx) = oneshot::channel();
-
-            // Sends and forgets. If swap fails, the error is logged and liquidity will be handled in the next minute.
-            let _ = self
-                .sideswap_channel
-                .send(SideswapRequest::Swap {
-                    sell_asset: "02f22f8d9c76ab41661a2729e4752e2c5d1a263012141b86ea98af5472df5189"
-                        .to_string(),
-                    receive_asset:
-                        "6f0279e9ed041c3d710a9f57d0c02928416460c4b722ae3457a11eec381c526d"
-                            .to_string(),
-                    amount: (current_balance - self.depix_max_amount) as i64,
-                    response: swap_tx,
+    /// Alerts once when the float of an asset drops below its configured
+    /// low-water mark, and again when it recovers.
+    async fn check_low_water_mark(&self, asset_id: &String, balance: u64) {
+        let Some(low_water_mark) = self.low_water_marks.get(asset_id) else {
+            return;
+        };
+
+        let below = balance < *low_water_mark;
+        metrics::set_gauge(
+            "wallet_below_low_water",
+            &[("asset", asset_id)],
+            if below { 1.0 } else { 0.0 },
+        );
+
+        let mut below_low_water = self.below_low_water.lock().await;
+        let alert = if below && below_low_water.insert(asset_id.clone()) {
+            Some((
+                "Low wallet balance",
+                format!(
+                    "Balance of {} is {}, below the low-water mark of {}",
+                    asset_id, balance, low_water_mark
+                ),
+            ))
+        } else if !below && below_low_water.remove(asset_id) {
+            Some((
+                "Wallet balance recovered",
+                format!(
+                    "Balance of {} is back to {} (low-water mark {})",
+                    asset_id, balance, low_water_mark
+                ),
+            ))
+        } else {
+            None
+        };
+        drop(below_low_water);
+
+        if let Some((title, message)) = alert {
+            if let Err(e) = self
+                .notification_channel
+                .send(NotificationRequest::Alert {
+                    title: title.to_string(),
+                    message,
                 })
                 .await
-                .map_err(|e| {
-                    log::warn!("Failed to send swap request: {}", e);
-                });
+            {
+                log::error!("Failed to send alert: {}", e);
+            }
+        }
+    }
+
+    /// Sells the balance of an asset above its policy's maximum.
+    async fn rebalance(&self, policy: &LiquiditySwap, current_balance: u64) {
+        let excess = current_balance.saturating_sub(policy.max_amount);
+        if excess == 0 {
+            return;
+        }
+        if excess < policy.min_amount {
+            log::debug!(
+                "Excess of {} ({}) below the minimum swap of {}",
+                policy.sell_asset,
+                excess,
+                policy.min_amount
+            );
+            return;
+        }
+
+        // If the swap fails, the error is logged and liquidity will be handled in the next minute.
+        if let Err(e) = self
+            .swap_router
+            .swap(&policy.sell_asset, &policy.receive_asset, excess as i64)
+            .await
+        {
+            log::warn!("Could not start liquidity swap: {}", e);
         }
     }
 }
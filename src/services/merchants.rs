@@ -0,0 +1,389 @@
+use super::{
+    jobs::{self, JobSchedule},
+    notifications::NotificationRequest,
+    transactions::TransactionServiceRequest,
+    users::UserRequest,
+    RequestHandler, Service, ServiceError,
+};
+use crate::models::merchants::{
+    FeeInvoice, Merchant, MerchantCredentials, NewMerchant, NewPaymentLink, PaymentLink,
+};
+use crate::models::transactions::Assets;
+use crate::repositories::invoicing::InvoicingRepository;
+use crate::repositories::merchants::MerchantRepository;
+use crate::settings::Invoicing;
+use crate::utils::metrics;
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+use tokio::sync::{mpsc, oneshot};
+use uuid::Uuid;
+
+/// Invoices sent per run of the invoicing job.
+const INVOICE_BATCH_SIZE: i64 = 50;
+
+pub enum MerchantRequest {
+    RegisterMerchant {
+        merchant: NewMerchant,
+        response: oneshot::Sender<Result<MerchantCredentials, ServiceError>>,
+    },
+    AuthenticateMerchant {
+        api_key: String,
+        response: oneshot::Sender<Result<Option<Merchant>, ServiceError>>,
+    },
+    CreatePaymentLink {
+        merchant: Merchant,
+        link: NewPaymentLink,
+        response: oneshot::Sender<Result<PaymentLink, ServiceError>>,
+    },
+    GetPaymentLink {
+        token: String,
+        response: oneshot::Sender<Result<Option<PaymentLink>, ServiceError>>,
+    },
+    GetMerchantPaymentLinks {
+        merchant_id: String,
+        response: oneshot::Sender<Result<Vec<PaymentLink>, ServiceError>>,
+    },
+    /// Invoices the fee of a link payment whose payout finished. Ignored for
+    /// consumer transactions.
+    InvoiceFee { transaction_id: String },
+}
+
+#[derive(Clone)]
+pub struct MerchantRequestHandler {
+    repository: MerchantRepository,
+    /// None when no invoicing API is configured.
+    invoicing_repository: Option<InvoicingRepository>,
+    user_channel: mpsc::Sender<UserRequest>,
+    transaction_channel: mpsc::Sender<TransactionServiceRequest>,
+    notification_channel: mpsc::Sender<NotificationRequest>,
+    default_fee_bps: i32,
+    public_url: String,
+    invoicing: Invoicing,
+}
+
+impl MerchantRequestHandler {
+    pub fn new(
+        sql_conn: PgPool,
+        default_fee_bps: i32,
+        public_url: String,
+        invoicing: Invoicing,
+        user_channel: mpsc::Sender<UserRequest>,
+        transaction_channel: mpsc::Sender<TransactionServiceRequest>,
+        notification_channel: mpsc::Sender<NotificationRequest>,
+    ) -> Self {
+        let invoicing_repository =
+            (!invoicing.url.is_empty()).then(|| InvoicingRepository::new(&invoicing));
+
+        let handler = Self {
+            repository: MerchantRepository::new(sql_conn),
+            invoicing_repository,
+            user_channel,
+            transaction_channel,
+            notification_channel,
+            default_fee_bps,
+            public_url: public_url.trim_end_matches('/').to_string(),
+            invoicing,
+        };
+
+        if handler.invoicing_repository.is_some() {
+            handler.start_invoicing();
+        }
+
+        handler
+    }
+
+    /// Retries the invoices whose last attempt failed.
+    fn start_invoicing(&self) {
+        let handler = self.clone();
+
+        jobs::register(
+            "merchant_invoices",
+            JobSchedule::every_secs(self.invoicing.interval_secs),
+            move || {
+                let handler = handler.clone();
+                async move { handler.issue_due_invoices().await }
+            },
+        );
+    }
+
+    async fn register_merchant(
+        &self,
+        merchant: NewMerchant,
+    ) -> Result<MerchantCredentials, ServiceError> {
+        if merchant.payout_asset != Assets::DEPIX.hex() && merchant.payout_asset != Assets::LBTC.hex()
+        {
+            return Err(ServiceError::Internal("Invalid asset".to_string()));
+        }
+        let fee_bps = merchant.fee_bps.unwrap_or(self.default_fee_bps);
+        if !(0..10000).contains(&fee_bps) {
+            return Err(ServiceError::Internal("Invalid fee".to_string()));
+        }
+
+        // Link payments are recorded under an account of their own
+        let (user_tx, user_rx) = oneshot::channel();
+        self.user_channel
+            .send(UserRequest::CreateUser {
+                referral_code: None,
+                installation_id: None,
+                response: user_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Merchant => User".to_string(), e.to_string())
+            })?;
+        let user = user_rx.await.map_err(|e| {
+            ServiceError::Communication("Merchant => User".to_string(), e.to_string())
+        })??;
+
+        let api_key = format!("mzm_{}", Uuid::new_v4().simple());
+        let merchant = self
+            .repository
+            .insert_merchant(&user.id, &merchant, fee_bps, &api_key)
+            .await
+            .map_err(|e| ServiceError::Repository("Merchants".to_string(), e.to_string()))?;
+
+        log::info!("Registered merchant {} ({})", merchant.id, merchant.name);
+
+        Ok(MerchantCredentials { merchant, api_key })
+    }
+
+    async fn authenticate_merchant(&self, api_key: &str) -> Result<Option<Merchant>, ServiceError> {
+        self.repository
+            .get_merchant_by_api_key(api_key)
+            .await
+            .map_err(|e| ServiceError::Repository("Merchants".to_string(), e.to_string()))
+    }
+
+    async fn create_payment_link(
+        &self,
+        merchant: Merchant,
+        link: NewPaymentLink,
+    ) -> Result<PaymentLink, ServiceError> {
+        if link.amount_in_cents <= 0 {
+            return Err(ServiceError::Internal("Invalid amount".to_string()));
+        }
+
+        let (transaction_tx, transaction_rx) = oneshot::channel();
+        self.transaction_channel
+            .send(TransactionServiceRequest::NewMerchantTransaction {
+                user_id: merchant.user_id.clone(),
+                address: merchant.payout_address.clone(),
+                amount_in_cents: link.amount_in_cents,
+                asset: merchant.payout_asset.clone(),
+                network: merchant.network.clone(),
+                fee_bps: merchant.fee_bps,
+                response: transaction_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Merchant => Transaction".to_string(), e.to_string())
+            })?;
+        let deposit = transaction_rx.await.map_err(|e| {
+            ServiceError::Communication("Merchant => Transaction".to_string(), e.to_string())
+        })??;
+
+        let token = self
+            .repository
+            .insert_payment_link(&merchant.id, link.description.as_deref(), &deposit)
+            .await
+            .map_err(|e| ServiceError::Repository("Merchants".to_string(), e.to_string()))?;
+
+        self.get_payment_link(&token).await?.ok_or_else(|| {
+            ServiceError::Internal(format!("Payment link {} not found after insert", token))
+        })
+    }
+
+    async fn get_payment_link(&self, token: &str) -> Result<Option<PaymentLink>, ServiceError> {
+        let link = self
+            .repository
+            .get_payment_link(token)
+            .await
+            .map_err(|e| ServiceError::Repository("Merchants".to_string(), e.to_string()))?;
+
+        Ok(link.map(|link| self.with_url(link)))
+    }
+
+    async fn get_merchant_payment_links(
+        &self,
+        merchant_id: &str,
+    ) -> Result<Vec<PaymentLink>, ServiceError> {
+        let links = self
+            .repository
+            .get_merchant_payment_links(merchant_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Merchants".to_string(), e.to_string()))?;
+
+        Ok(links.into_iter().map(|link| self.with_url(link)).collect())
+    }
+
+    async fn invoice_fee(&self, transaction_id: &str) {
+        if self.invoicing_repository.is_none() {
+            return;
+        }
+
+        match self.repository.create_fee_invoice(transaction_id).await {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                log::error!("Could not queue fee invoice of {}: {}", transaction_id, e);
+                return;
+            }
+        }
+
+        if let Err(e) = self.issue_due_invoices().await {
+            log::error!("{}", e);
+        }
+    }
+
+    async fn issue_due_invoices(&self) -> Result<(), anyhow::Error> {
+        let Some(invoicing_repository) = &self.invoicing_repository else {
+            return Ok(());
+        };
+
+        let invoices = self
+            .repository
+            .claim_due_invoices(INVOICE_BATCH_SIZE, self.invoicing.retry_base_secs)
+            .await
+            .map_err(|e| anyhow::anyhow!("Could not load due fee invoices: {}", e))?;
+
+        for invoice in invoices {
+            let result = match self.repository.get_merchant(&invoice.merchant_id).await {
+                Ok(Some(merchant)) => {
+                    invoicing_repository
+                        .issue_invoice(&invoice, &merchant)
+                        .await
+                }
+                Ok(None) => Err(anyhow::anyhow!(
+                    "Merchant {} not found",
+                    invoice.merchant_id
+                )),
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(invoice_id) => self.record_invoice_issued(&invoice, &invoice_id).await,
+                Err(e) => self.record_invoice_failure(&invoice, &e.to_string()).await,
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn record_invoice_issued(&self, invoice: &FeeInvoice, invoice_id: &str) {
+        log::info!(
+            "Issued invoice {} for the fee of {}",
+            invoice_id,
+            invoice.transaction_id
+        );
+        metrics::increment("merchant_invoices_total", &[("outcome", "issued")]);
+
+        if let Err(e) = self
+            .repository
+            .record_invoice_issued(&invoice.transaction_id, invoice_id)
+            .await
+        {
+            log::error!(
+                "Could not record invoice {} of {}: {}",
+                invoice_id,
+                invoice.transaction_id,
+                e
+            );
+        }
+    }
+
+    async fn record_invoice_failure(&self, invoice: &FeeInvoice, error: &str) {
+        log::warn!(
+            "Attempt {} to invoice the fee of {} failed: {}",
+            invoice.attempts,
+            invoice.transaction_id,
+            error
+        );
+        metrics::increment("merchant_invoices_total", &[("outcome", "failed")]);
+
+        let gave_up = match self
+            .repository
+            .record_invoice_failure(&invoice.transaction_id, error, self.invoicing.max_attempts)
+            .await
+        {
+            Ok(gave_up) => gave_up,
+            Err(e) => {
+                log::error!("Could not record failed invoice attempt: {}", e);
+                return;
+            }
+        };
+        if !gave_up {
+            return;
+        }
+
+        if let Err(e) = self
+            .notification_channel
+            .send(NotificationRequest::Alert {
+                title: "Fee invoice failed".to_string(),
+                message: format!(
+                    "Could not invoice the fee of transaction {} after {} attempts: {}",
+                    invoice.transaction_id, invoice.attempts, error
+                ),
+            })
+            .await
+        {
+            log::error!("Failed to send alert: {}", e);
+        }
+    }
+
+    fn with_url(&self, link: PaymentLink) -> PaymentLink {
+        PaymentLink {
+            url: format!("{}/pay/{}", self.public_url, link.token),
+            ..link
+        }
+    }
+}
+
+#[async_trait]
+impl RequestHandler<MerchantRequest> for MerchantRequestHandler {
+    async fn handle_request(&self, request: MerchantRequest) {
+        match request {
+            MerchantRequest::RegisterMerchant { merchant, response } => {
+                let result = self.register_merchant(merchant).await;
+                let _ = response.send(result);
+            }
+            MerchantRequest::AuthenticateMerchant { api_key, response } => {
+                let merchant = self.authenticate_merchant(&api_key).await;
+                let _ = response.send(merchant);
+            }
+            MerchantRequest::CreatePaymentLink {
+                merchant,
+                link,
+                response,
+            } => {
+                let result = self.create_payment_link(merchant, link).await;
+                let _ = response.send(result);
+            }
+            MerchantRequest::GetPaymentLink { token, response } => {
+                let link = self.get_payment_link(&token).await;
+                let _ = response.send(link);
+            }
+            MerchantRequest::GetMerchantPaymentLinks {
+                merchant_id,
+                response,
+            } => {
+                let links = self.get_merchant_payment_links(&merchant_id).await;
+                let _ = response.send(links);
+            }
+            MerchantRequest::InvoiceFee { transaction_id } => {
+                self.invoice_fee(&transaction_id).await;
+            }
+        }
+    }
+}
+
+pub struct MerchantService;
+
+impl MerchantService {
+    pub fn new() -> Self {
+        MerchantService {}
+    }
+}
+
+#[async_trait]
+impl Service<MerchantRequest, MerchantRequestHandler> for MerchantService {}
@@ -0,0 +1,168 @@
+use super::{RequestHandler, Service};
+use crate::models::receipts::RECEIPT_SUBJECT;
+use crate::repositories::notifications::NotificationRepository;
+use crate::repositories::receipts::ReceiptRepository;
+use crate::settings::Notifications;
+use crate::utils::metrics;
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+pub enum NotificationRequest {
+    Alert {
+        title: String,
+        message: String,
+    },
+    UserNotice {
+        user_id: String,
+        title: String,
+        message: String,
+        data: serde_json::Value,
+    },
+    /// Sends the receipt of a broadcast payout on the channels the user
+    /// opted into.
+    Receipt {
+        transaction_id: String,
+    },
+}
+
+#[derive(Clone)]
+pub struct NotificationRequestHandler {
+    repository: NotificationRepository,
+    receipt_repository: ReceiptRepository,
+    explorer_url: String,
+}
+
+impl NotificationRequestHandler {
+    pub fn new(sql_conn: PgPool, settings: Notifications) -> Self {
+        Self {
+            explorer_url: settings.explorer_url.clone(),
+            repository: NotificationRepository::new(settings),
+            receipt_repository: ReceiptRepository::new(sql_conn),
+        }
+    }
+
+    async fn send_alert(&self, title: String, message: String) {
+        log::warn!("[ALERT] {}: {}", title, message);
+
+        if let Err(e) = self.repository.send_alert(&title, &message).await {
+            log::error!("Failed to deliver alert '{}': {}", title, e);
+        }
+    }
+
+    async fn send_user_notice(
+        &self,
+        user_id: String,
+        title: String,
+        message: String,
+        data: serde_json::Value,
+    ) {
+        log::info!("Notifying user {}: {}", user_id, title);
+
+        if let Err(e) = self
+            .repository
+            .send_user_notice(&user_id, &title, &message, &data)
+            .await
+        {
+            log::error!("Failed to notify user {}: {}", user_id, e);
+        }
+    }
+
+    async fn send_receipt(&self, transaction_id: String) {
+        let details = match self
+            .receipt_repository
+            .get_receipt_details(&transaction_id)
+            .await
+        {
+            Ok(Some(details)) => details,
+            Ok(None) => {
+                log::warn!("No broadcast payout to receipt for {}", transaction_id);
+                return;
+            }
+            Err(e) => {
+                log::error!("Could not load receipt of {}: {}", transaction_id, e);
+                return;
+            }
+        };
+        let text = details.text(&self.explorer_url);
+
+        for (channel, recipient) in details.channels() {
+            if !self.repository.has_provider(channel) {
+                log::debug!("No {} provider, not sending receipt", channel);
+                continue;
+            }
+
+            match self
+                .receipt_repository
+                .claim_receipt(&details, channel, &recipient)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    log::error!("Could not record receipt of {}: {}", transaction_id, e);
+                    continue;
+                }
+            }
+
+            let error = self
+                .repository
+                .send_receipt(channel, &recipient, RECEIPT_SUBJECT, &text)
+                .await
+                .err()
+                .map(|e| e.to_string());
+            let status = match &error {
+                Some(e) => {
+                    log::error!("Failed to send {} receipt: {}", channel, e);
+                    "failed"
+                }
+                None => "sent",
+            };
+            metrics::increment(
+                "receipts_total",
+                &[("channel", channel), ("status", status)],
+            );
+
+            if let Err(e) = self
+                .receipt_repository
+                .record_delivery(&transaction_id, channel, error)
+                .await
+            {
+                log::error!("Could not record receipt delivery: {}", e);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl RequestHandler<NotificationRequest> for NotificationRequestHandler {
+    async fn handle_request(&self, request: NotificationRequest) {
+        match request {
+            NotificationRequest::Alert { title, message } => {
+                self.send_alert(title, message).await;
+            }
+            NotificationRequest::UserNotice {
+                user_id,
+                title,
+                message,
+                data,
+            } => {
+                self.send_user_notice(user_id, title, message, data).await;
+            }
+            NotificationRequest::Receipt { transaction_id } => {
+                self.send_receipt(transaction_id).await;
+            }
+        }
+    }
+}
+
+pub struct NotificationService;
+
+impl NotificationService {
+    pub fn new() -> Self {
+        NotificationService {}
+    }
+}
+
+#[async_trait]
+impl Service<NotificationRequest, NotificationRequestHandler> for NotificationService {}
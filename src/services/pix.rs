@@ -1,9 +1,24 @@
+use super::notifications::NotificationRequest;
 use super::transactions::TransactionServiceRequest;
 use super::{RequestHandler, Service, ServiceError};
 
+use crate::models::disputes::{
+    DisputeDecision, DisputeExposure, EulenDisputeNotice, NewDispute, PixDispute,
+};
+use crate::models::operator::{Cursor, Page, TransactionSearch};
 use crate::models::pix;
-use crate::repositories::pix::PixRepository;
+use crate::models::reconciliation::DepixSettlement;
+use crate::models::refunds::{PixRefund, RefundDecision};
+use crate::models::webhooks::{RawWebhook, WebhookEvent};
+use crate::repositories::disputes::DisputeRepository;
+use crate::repositories::pix::{EulenUnavailable, PixRepository};
+use crate::repositories::reconciliation::ReconciliationRepository;
+use crate::repositories::refunds::RefundRepository;
+use crate::repositories::webhooks::WebhookRepository;
+use crate::settings::Eulen;
+use crate::utils::metrics;
 
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -18,29 +33,109 @@ pub enum PixServiceRequest {
         response: oneshot::Sender<Result<pix::Deposit, ServiceError>>,
     },
     UpdateEulenStatus {
-        eulen_status: pix::EulenDepositStatus,
+        webhook: RawWebhook,
         response: oneshot::Sender<Result<(), ServiceError>>,
     },
+    UpdateEulenStatusBatch {
+        webhook: RawWebhook,
+        response: oneshot::Sender<Result<Vec<pix::EulenBatchItemResult>, ServiceError>>,
+    },
+    SearchWebhookEvents {
+        bank_tx_id: Option<String>,
+        transaction_id: Option<String>,
+        response: oneshot::Sender<Result<Vec<WebhookEvent>, ServiceError>>,
+    },
+    SearchPixTransactions {
+        search: TransactionSearch,
+        after: Option<Cursor>,
+        limit: i64,
+        response: oneshot::Sender<Result<Page<pix::PixTransaction>, ServiceError>>,
+    },
+    /// Settlements of paid charges in `statuses`, newest first.
+    GetDepixSettlements {
+        statuses: Vec<String>,
+        limit: i64,
+        response: oneshot::Sender<Result<Vec<DepixSettlement>, ServiceError>>,
+    },
+    /// Overpayments in `statuses`, newest first.
+    GetRefunds {
+        statuses: Vec<String>,
+        limit: i64,
+        response: oneshot::Sender<Result<Vec<PixRefund>, ServiceError>>,
+    },
+    /// Closes a due refund. None when there is no due refund with this id.
+    ResolveRefund {
+        id: String,
+        decision: RefundDecision,
+        decided_by: String,
+        note: Option<String>,
+        response: oneshot::Sender<Result<Option<PixRefund>, ServiceError>>,
+    },
+    ReceiveDisputeNotice {
+        webhook: RawWebhook,
+        response: oneshot::Sender<Result<PixDispute, ServiceError>>,
+    },
+    /// Records a dispute an operator learned of outside the webhook.
+    ReportDispute {
+        dispute: NewDispute,
+        response: oneshot::Sender<Result<PixDispute, ServiceError>>,
+    },
+    /// Disputes in `statuses`, newest first.
+    GetDisputes {
+        statuses: Vec<String>,
+        limit: i64,
+        response: oneshot::Sender<Result<Vec<PixDispute>, ServiceError>>,
+    },
+    /// Closes an open dispute. None when there is no open dispute with this
+    /// id.
+    ResolveDispute {
+        id: String,
+        decision: DisputeDecision,
+        decided_by: String,
+        note: Option<String>,
+        response: oneshot::Sender<Result<Option<PixDispute>, ServiceError>>,
+    },
+    GetDisputeExposure {
+        response: oneshot::Sender<Result<Vec<DisputeExposure>, ServiceError>>,
+    },
 }
 
 #[derive(Clone)]
 pub struct PixRequestHandler {
     repository: Arc<PixRepository>,
+    webhook_repository: Arc<WebhookRepository>,
+    reconciliation_repository: ReconciliationRepository,
+    refund_repository: Arc<RefundRepository>,
+    dispute_repository: DisputeRepository,
     transaction_channel: mpsc::Sender<TransactionServiceRequest>,
+    notification_channel: mpsc::Sender<NotificationRequest>,
 }
 
 impl PixRequestHandler {
     pub fn new(
         eulen_auth_token: String,
         eulen_url: String,
+        eulen_settings: Eulen,
         pool: PgPool,
         transaction_channel: mpsc::Sender<TransactionServiceRequest>,
+        notification_channel: mpsc::Sender<NotificationRequest>,
     ) -> Self {
-        let repository = Arc::new(PixRepository::new(eulen_auth_token, eulen_url, pool));
+        let repository = Arc::new(PixRepository::new(
+            eulen_auth_token,
+            eulen_url,
+            eulen_settings,
+            pool.clone(),
+        ));
+        let webhook_repository = Arc::new(WebhookRepository::new(pool.clone()));
 
         PixRequestHandler {
             repository,
+            webhook_repository,
+            reconciliation_repository: ReconciliationRepository::new(pool.clone()),
+            refund_repository: Arc::new(RefundRepository::new(pool.clone())),
+            dispute_repository: DisputeRepository::new(pool),
             transaction_channel,
+            notification_channel,
         }
     }
 
@@ -54,7 +149,10 @@ impl PixRequestHandler {
             .repository
             .new_pix_deposit(&transaction_id, amount_in_cents, &address)
             .await
-            .map_err(|e| ServiceError::Repository("Pix".to_string(), e.to_string()))?;
+            .map_err(|e| match e.downcast_ref::<EulenUnavailable>() {
+                Some(_) => ServiceError::Internal("PixUnavailable".to_string()),
+                None => ServiceError::Repository("Pix".to_string(), e.to_string()),
+            })?;
 
         Ok(deposit)
     }
@@ -62,35 +160,377 @@ impl PixRequestHandler {
     async fn update_deposit_status(
         &self,
         eulen_deposit: pix::EulenDepositStatus,
-    ) -> Result<(), ServiceError> {
-        let transaction_id = self
+    ) -> Result<pix::DepositStatusUpdate, ServiceError> {
+        let update = self.record_deposit_status(&eulen_deposit).await?;
+
+        if let pix::DepositStatusUpdate::Applied(transaction_id) = &update {
+            self.forward_deposit_status(transaction_id.clone(), &eulen_deposit);
+        }
+
+        Ok(update)
+    }
+
+    /// Stores the webhook as received, before it is parsed, so even payloads
+    /// we reject are kept. Returns the event id and the parsed payload.
+    async fn store_webhook<T: serde::de::DeserializeOwned>(
+        &self,
+        source: &str,
+        webhook: &RawWebhook,
+    ) -> Result<(String, T), ServiceError> {
+        let (bank_tx_ids, qr_ids) = webhook_identifiers(&webhook.payload);
+        let event_id = self
+            .webhook_repository
+            .insert_event(source, webhook, &bank_tx_ids, &qr_ids)
+            .await
+            .map_err(|e| ServiceError::Repository("Webhooks".to_string(), e.to_string()))?;
+
+        match serde_json::from_str(&webhook.payload) {
+            Ok(parsed) => Ok((event_id, parsed)),
+            Err(e) => {
+                log::warn!("Rejected invalid {} webhook {}: {}", source, event_id, e);
+                self.record_webhook_outcome(&event_id, &format!("invalid: {}", e))
+                    .await;
+                Err(ServiceError::Internal("InvalidWebhookPayload".to_string()))
+            }
+        }
+    }
+
+    async fn record_webhook_outcome(&self, event_id: &str, outcome: &str) {
+        if let Err(e) = self
+            .webhook_repository
+            .record_outcome(event_id, outcome)
+            .await
+        {
+            log::error!("Could not record outcome of webhook {}: {}", event_id, e);
+        }
+    }
+
+    async fn receive_deposit_status(&self, webhook: RawWebhook) -> Result<(), ServiceError> {
+        let (event_id, eulen_deposit) = self.store_webhook("eulen_status", &webhook).await?;
+
+        let update = self.update_deposit_status(eulen_deposit).await;
+        let outcome = match &update {
+            Ok(pix::DepositStatusUpdate::Applied(transaction_id)) => {
+                format!("applied to transaction {}", transaction_id)
+            }
+            Ok(pix::DepositStatusUpdate::Stale(transaction_id)) => {
+                format!("stale for transaction {}", transaction_id)
+            }
+            Ok(pix::DepositStatusUpdate::Unmatched) => "ignored".to_string(),
+            Err(e) => format!("failed: {}", e),
+        };
+        self.record_webhook_outcome(&event_id, &outcome).await;
+
+        update.map(|_| ())
+    }
+
+    async fn receive_deposit_status_batch(
+        &self,
+        webhook: RawWebhook,
+    ) -> Result<Vec<pix::EulenBatchItemResult>, ServiceError> {
+        let (event_id, eulen_statuses): (_, Vec<pix::EulenDepositStatus>) =
+            self.store_webhook("eulen_status_batch", &webhook).await?;
+        log::info!(
+            "Received batch of {} Eulen status updates",
+            eulen_statuses.len()
+        );
+
+        let results = self.update_deposit_status_batch(eulen_statuses).await;
+        let outcome = serde_json::to_string(&results).unwrap_or_default();
+        self.record_webhook_outcome(&event_id, &outcome).await;
+
+        Ok(results)
+    }
+
+    async fn search_webhook_events(
+        &self,
+        bank_tx_id: Option<String>,
+        transaction_id: Option<String>,
+    ) -> Result<Vec<WebhookEvent>, ServiceError> {
+        self.webhook_repository
+            .search_events(bank_tx_id.as_deref(), transaction_id.as_deref())
+            .await
+            .map_err(|e| ServiceError::Repository("Webhooks".to_string(), e.to_string()))
+    }
+
+    async fn record_deposit_status(
+        &self,
+        eulen_deposit: &pix::EulenDepositStatus,
+    ) -> Result<pix::DepositStatusUpdate, ServiceError> {
+        let update = self
             .repository
-            .update_eulen_deposit_status(&eulen_deposit)
+            .update_eulen_deposit_status(eulen_deposit)
             .await
             .map_err(|e| ServiceError::Repository("Pix".to_string(), e.to_string()))?;
 
-        if transaction_id.is_none() {
-            log::info!(
+        match &update {
+            // Matched against the wallet by the reconciliation job
+            pix::DepositStatusUpdate::Applied(transaction_id)
+                if eulen_deposit.status == "depix_sent" =>
+            {
+                if let Err(e) = self
+                    .reconciliation_repository
+                    .record_settlement(transaction_id, eulen_deposit)
+                    .await
+                {
+                    log::error!("Could not record DEPIX settlement: {}", e);
+                }
+            }
+            pix::DepositStatusUpdate::Applied(_) => {}
+            pix::DepositStatusUpdate::Stale(transaction_id) => {
+                log::warn!(
+                    "Skipping {} update of transaction {}, its charge is already past it",
+                    eulen_deposit.status,
+                    transaction_id
+                );
+                metrics::increment(
+                    "stale_deposit_updates_total",
+                    &[("status", &eulen_deposit.status)],
+                );
+            }
+            pix::DepositStatusUpdate::Unmatched => log::info!(
                 "Received chat deposit. Ignoring. {}",
                 eulen_deposit.bank_tx_id
-            );
-            return Ok(());
+            ),
         }
 
-        let transaction_channel = self.transaction_channel.clone();
-        let transaction_id_clone = transaction_id.clone().unwrap();
-        let eulen_deposit_clone = eulen_deposit.clone();
+        Ok(update)
+    }
+
+    /// Payments short of the charge are held for review instead of being
+    /// paid out. Payments above it are paid out for the charge, and the
+    /// surplus is queued to be refunded to the payer.
+    fn forward_deposit_status(&self, transaction_id: String, eulen_deposit: &pix::EulenDepositStatus) {
+        let handler = self.clone();
+        let eulen_deposit = eulen_deposit.clone();
+        let status = format!("eulen_{}", eulen_deposit.status);
 
         tokio::spawn(async move {
-            let _ = transaction_channel
+            if eulen_deposit.status == "depix_sent" {
+                match handler
+                    .repository
+                    .get_expected_amount(&eulen_deposit.qr_id)
+                    .await
+                {
+                    Ok(Some(expected)) if expected > eulen_deposit.value_in_cents => {
+                        let _ = handler
+                            .transaction_channel
+                            .send(TransactionServiceRequest::HoldTransaction {
+                                transaction_id,
+                                source: "amount_mismatch".to_string(),
+                                reason: format!(
+                                    "Charged {} cents, received {} cents",
+                                    expected, eulen_deposit.value_in_cents
+                                ),
+                            })
+                            .await;
+                        return;
+                    }
+                    Ok(Some(expected)) if expected < eulen_deposit.value_in_cents => {
+                        handler
+                            .record_overpayment(&transaction_id, &eulen_deposit, expected)
+                            .await;
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::error!("Could not check paid amount: {}", e),
+                }
+            }
+
+            let _ = handler
+                .transaction_channel
                 .send(TransactionServiceRequest::UpdateTransactionStatus {
-                    transaction_id: transaction_id_clone,
-                    status: format!("eulen_{}", eulen_deposit_clone.status),
+                    transaction_id,
+                    status,
                 })
                 .await;
         });
+    }
 
-        Ok(())
+    async fn record_overpayment(
+        &self,
+        transaction_id: &str,
+        eulen_deposit: &pix::EulenDepositStatus,
+        expected: i32,
+    ) {
+        let refund = match self
+            .refund_repository
+            .record_overpayment(transaction_id, eulen_deposit, expected)
+            .await
+        {
+            Ok(Some(refund)) => refund,
+            Ok(None) => return,
+            Err(e) => {
+                log::error!(
+                    "Could not record overpayment of transaction {}: {}",
+                    transaction_id,
+                    e
+                );
+                return;
+            }
+        };
+
+        metrics::increment("pix_overpayments_total", &[]);
+        let message = format!(
+            "Transaction {} ({}) was charged {} cents but paid {} cents. Refund {} of {} cents is due to {}",
+            transaction_id,
+            refund.eulen_id,
+            refund.charged_in_cents,
+            refund.paid_in_cents,
+            refund.id,
+            refund.amount_in_cents,
+            refund.payer_name
+        );
+        log::warn!("{}", message);
+        let _ = self
+            .notification_channel
+            .send(NotificationRequest::Alert {
+                title: "PIX overpayment".to_string(),
+                message,
+            })
+            .await;
+    }
+
+    async fn receive_dispute_notice(
+        &self,
+        webhook: RawWebhook,
+    ) -> Result<PixDispute, ServiceError> {
+        let (event_id, notice): (_, EulenDisputeNotice) =
+            self.store_webhook("eulen_dispute", &webhook).await?;
+
+        let recorded = self.record_dispute(notice.into()).await;
+        let outcome = match &recorded {
+            Ok(dispute) => match &dispute.transaction_id {
+                Some(transaction_id) => format!(
+                    "dispute {} {} for transaction {}",
+                    dispute.id, dispute.status, transaction_id
+                ),
+                None => format!("dispute {} {}, unlinked", dispute.id, dispute.status),
+            },
+            Err(e) => format!("failed: {}", e),
+        };
+        self.record_webhook_outcome(&event_id, &outcome).await;
+
+        recorded
+    }
+
+    /// Records the dispute and alerts the operators when it is new or its
+    /// status changed.
+    async fn record_dispute(&self, dispute: NewDispute) -> Result<PixDispute, ServiceError> {
+        let (dispute, previous) = self
+            .dispute_repository
+            .record_dispute(&dispute)
+            .await
+            .map_err(|e| ServiceError::Repository("Disputes".to_string(), e.to_string()))?;
+
+        if previous.as_deref() == Some(dispute.status.as_str()) {
+            return Ok(dispute);
+        }
+
+        metrics::increment("pix_disputes_total", &[("status", &dispute.status)]);
+        let message = format!(
+            "Dispute {} ({}) of {} cents is {}. Transaction: {}, user: {}. Reason: {}",
+            dispute.id,
+            dispute.external_id,
+            dispute.amount_in_cents,
+            dispute.status,
+            dispute.transaction_id.as_deref().unwrap_or("none matched"),
+            dispute.user_id.as_deref().unwrap_or("unknown"),
+            dispute.reason
+        );
+        log::warn!("{}", message);
+        let _ = self
+            .notification_channel
+            .send(NotificationRequest::Alert {
+                title: "PIX dispute".to_string(),
+                message,
+            })
+            .await;
+
+        Ok(dispute)
+    }
+
+    /// Applies a backlog of status updates sent by Eulen after an outage.
+    /// Updates are deduplicated by bank_tx_id (the most advanced status wins),
+    /// recorded per charge in lifecycle order, and only the final status of
+    /// each charge is forwarded to the transaction service.
+    async fn update_deposit_status_batch(
+        &self,
+        eulen_statuses: Vec<pix::EulenDepositStatus>,
+    ) -> Vec<pix::EulenBatchItemResult> {
+        let mut results: Vec<pix::EulenBatchItemResult> = eulen_statuses
+            .iter()
+            .enumerate()
+            .map(|(index, status)| pix::EulenBatchItemResult {
+                index,
+                bank_tx_id: status.bank_tx_id.clone(),
+                qr_id: status.qr_id.clone(),
+                status: status.status.clone(),
+                result: "duplicate".to_string(),
+                error: None,
+            })
+            .collect();
+
+        let mut latest_by_bank_tx: HashMap<String, usize> = HashMap::new();
+        for (index, status) in eulen_statuses.iter().enumerate() {
+            let key = if status.bank_tx_id.is_empty() {
+                format!("{}:{}", status.qr_id, status.status)
+            } else {
+                status.bank_tx_id.clone()
+            };
+
+            match latest_by_bank_tx.get(&key).copied() {
+                Some(previous) if eulen_statuses[previous].status == status.status => {}
+                Some(previous) => {
+                    if status.status_rank() > eulen_statuses[previous].status_rank() {
+                        results[previous].result = "superseded".to_string();
+                        latest_by_bank_tx.insert(key, index);
+                    } else {
+                        results[index].result = "superseded".to_string();
+                    }
+                }
+                None => {
+                    latest_by_bank_tx.insert(key, index);
+                }
+            }
+        }
+
+        let mut by_charge: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for index in latest_by_bank_tx.into_values() {
+            by_charge
+                .entry(eulen_statuses[index].qr_id.clone())
+                .or_default()
+                .push(index);
+        }
+
+        for (_, mut indexes) in by_charge {
+            indexes.sort_by_key(|&index| (eulen_statuses[index].status_rank(), index));
+
+            let mut last_applied = None;
+            for index in indexes {
+                match self.record_deposit_status(&eulen_statuses[index]).await {
+                    Ok(pix::DepositStatusUpdate::Applied(transaction_id)) => {
+                        results[index].result = "applied".to_string();
+                        last_applied = Some((transaction_id, index));
+                    }
+                    Ok(pix::DepositStatusUpdate::Stale(_)) => {
+                        results[index].result = "stale".to_string();
+                    }
+                    Ok(pix::DepositStatusUpdate::Unmatched) => {
+                        results[index].result = "ignored".to_string();
+                    }
+                    Err(e) => {
+                        results[index].result = "failed".to_string();
+                        results[index].error = Some(e.to_string());
+                    }
+                }
+            }
+
+            if let Some((transaction_id, index)) = last_applied {
+                self.forward_deposit_status(transaction_id, &eulen_statuses[index]);
+            }
+        }
+
+        results
     }
 }
 
@@ -107,24 +547,149 @@ impl RequestHandler<PixServiceRequest> for PixRequestHandler {
                 let deposit = self
                     .new_pix_deposit(amount_in_cents, address, transaction_id)
                     .await
-                    .map_err(|e| {
-                        ServiceError::Repository("PixRepository".to_string(), e.to_string())
+                    .map_err(|e| match e {
+                        ServiceError::Internal(reason) if reason == "PixUnavailable" => {
+                            ServiceError::Internal(reason)
+                        }
+                        e => ServiceError::Repository("PixRepository".to_string(), e.to_string()),
                     });
                 let _ = response.send(deposit);
             }
-            PixServiceRequest::UpdateEulenStatus {
-                eulen_status,
+            PixServiceRequest::UpdateEulenStatus { webhook, response } => {
+                let update = self.receive_deposit_status(webhook).await;
+                let _ = response.send(update);
+            }
+            PixServiceRequest::UpdateEulenStatusBatch { webhook, response } => {
+                let results = self.receive_deposit_status_batch(webhook).await;
+                let _ = response.send(results);
+            }
+            PixServiceRequest::SearchWebhookEvents {
+                bank_tx_id,
+                transaction_id,
                 response,
             } => {
-                let update = self.update_deposit_status(eulen_status).await.map_err(|e| {
-                    ServiceError::Repository("PixRepository".to_string(), e.to_string())
-                });
-                let _ = response.send(update);
+                let events = self.search_webhook_events(bank_tx_id, transaction_id).await;
+                let _ = response.send(events);
+            }
+            PixServiceRequest::SearchPixTransactions {
+                search,
+                after,
+                limit,
+                response,
+            } => {
+                let page = self
+                    .repository
+                    .search_pix_transactions(&search, after.as_ref(), limit)
+                    .await
+                    .map_err(|e| ServiceError::Repository("Pix".to_string(), e.to_string()));
+                let _ = response.send(page);
+            }
+            PixServiceRequest::GetDepixSettlements {
+                statuses,
+                limit,
+                response,
+            } => {
+                let settlements = self
+                    .reconciliation_repository
+                    .get_settlements(&statuses, limit)
+                    .await
+                    .map_err(|e| {
+                        ServiceError::Repository("Reconciliation".to_string(), e.to_string())
+                    });
+                let _ = response.send(settlements);
+            }
+            PixServiceRequest::GetRefunds {
+                statuses,
+                limit,
+                response,
+            } => {
+                let refunds = self
+                    .refund_repository
+                    .get_refunds(&statuses, limit)
+                    .await
+                    .map_err(|e| ServiceError::Repository("Refunds".to_string(), e.to_string()));
+                let _ = response.send(refunds);
+            }
+            PixServiceRequest::ResolveRefund {
+                id,
+                decision,
+                decided_by,
+                note,
+                response,
+            } => {
+                let refund = self
+                    .refund_repository
+                    .resolve_refund(&id, decision, &decided_by, note.as_deref())
+                    .await
+                    .map_err(|e| ServiceError::Repository("Refunds".to_string(), e.to_string()));
+                let _ = response.send(refund);
+            }
+            PixServiceRequest::ReceiveDisputeNotice { webhook, response } => {
+                let dispute = self.receive_dispute_notice(webhook).await;
+                let _ = response.send(dispute);
+            }
+            PixServiceRequest::ReportDispute { dispute, response } => {
+                let dispute = self.record_dispute(dispute).await;
+                let _ = response.send(dispute);
+            }
+            PixServiceRequest::GetDisputes {
+                statuses,
+                limit,
+                response,
+            } => {
+                let disputes = self
+                    .dispute_repository
+                    .get_disputes(&statuses, limit)
+                    .await
+                    .map_err(|e| ServiceError::Repository("Disputes".to_string(), e.to_string()));
+                let _ = response.send(disputes);
+            }
+            PixServiceRequest::ResolveDispute {
+                id,
+                decision,
+                decided_by,
+                note,
+                response,
+            } => {
+                let dispute = self
+                    .dispute_repository
+                    .resolve_dispute(&id, decision, &decided_by, note.as_deref())
+                    .await
+                    .map_err(|e| ServiceError::Repository("Disputes".to_string(), e.to_string()));
+                let _ = response.send(dispute);
+            }
+            PixServiceRequest::GetDisputeExposure { response } => {
+                let exposure =
+                    self.dispute_repository.get_exposure().await.map_err(|e| {
+                        ServiceError::Repository("Disputes".to_string(), e.to_string())
+                    });
+                let _ = response.send(exposure);
             }
         }
     }
 }
 
+/// Bank transaction and charge ids mentioned by a webhook payload, read
+/// leniently so they are indexed even when the payload fails to parse.
+fn webhook_identifiers(payload: &str) -> (Vec<String>, Vec<String>) {
+    let value: serde_json::Value = serde_json::from_str(payload).unwrap_or_default();
+    let items = match value {
+        serde_json::Value::Array(items) => items,
+        item => vec![item],
+    };
+
+    let field = |name: &str| -> Vec<String> {
+        items
+            .iter()
+            .filter_map(|item| item.get(name).and_then(|v| v.as_str()))
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    (field("bankTxId"), field("qrId"))
+}
+
 pub struct PixService;
 
 impl PixService {
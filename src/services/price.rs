@@ -1,7 +1,12 @@
 use crate::{
-    models::transactions::Assets, repositories::price::PriceRepository,
+    models::{
+        price::{AssetPrice, PriceAggregation, QuoteCurrency},
+        transactions::Assets,
+    },
+    repositories::price::{PriceRepository, FETCH_INTERVAL_SECS},
 };
 
+use super::jobs::{self, JobSchedule};
 use super::{RequestHandler, Service, ServiceError};
 
 use async_trait::async_trait;
@@ -10,7 +15,8 @@ use tokio::sync::oneshot;
 pub enum PriceRequest {
     GetPrice {
         asset: Assets,
-        response: oneshot::Sender<Result<Option<f64>, ServiceError>>,
+        currency: QuoteCurrency,
+        response: oneshot::Sender<Result<Option<AssetPrice>, ServiceError>>,
     },
 }
 
@@ -20,19 +26,36 @@ pub struct PriceRequestHandler {
 }
 
 impl PriceRequestHandler {
-    pub fn new(binance_url: String, coingecko_url: String) -> Self {
-        let price_repository = PriceRepository::new(binance_url, coingecko_url);
+    pub fn new(binance_url: String, coingecko_url: String, aggregation: PriceAggregation) -> Self {
+        let price_repository = PriceRepository::new(binance_url, coingecko_url, aggregation);
 
         Self { price_repository }
     }
 
-    pub async fn start_price_fetch_task(&self) {
-        self.price_repository.start_price_fetch_task().await
+    pub fn start_price_fetch_task(&self) {
+        let repository = self.price_repository.clone();
+
+        jobs::register(
+            "price_fetch",
+            JobSchedule::every_secs(FETCH_INTERVAL_SECS as u64),
+            move || {
+                let repository = repository.clone();
+                async move {
+                    repository.fetch_best_prices().await?;
+                    log::info!("Fetched prices.");
+                    Ok(())
+                }
+            },
+        );
     }
 
-    async fn get_price(&self, asset: Assets) -> Result<Option<f64>, ServiceError> {
+    async fn get_price(
+        &self,
+        asset: Assets,
+        currency: QuoteCurrency,
+    ) -> Result<Option<AssetPrice>, ServiceError> {
         self.price_repository
-            .get_asset_price_with_spread(asset)
+            .get_asset_price_with_spread(asset, currency)
             .await
             .map_err(|e| ServiceError::Repository("Prices".to_string(), e.to_string()))
     }
@@ -42,8 +65,12 @@ impl PriceRequestHandler {
 impl RequestHandler<PriceRequest> for PriceRequestHandler {
     async fn handle_request(&self, request: PriceRequest) {
         match request {
-            PriceRequest::GetPrice { asset, response } => {
-                let price = self.get_price(asset).await;
+            PriceRequest::GetPrice {
+                asset,
+                currency,
+                response,
+            } => {
+                let price = self.get_price(asset, currency).await;
                 let _ = response.send(price);
             }
         }
@@ -0,0 +1,202 @@
+use super::jobs::{self, JobSchedule};
+use super::liquid::LiquidRequest;
+use super::notifications::NotificationRequest;
+use crate::models::reconciliation::DepixSettlement;
+use crate::models::transactions::Assets;
+use crate::models::wallet::IncomingTransfer;
+use crate::repositories::reconciliation::ReconciliationRepository;
+use crate::settings::Reconciliation;
+use crate::utils::metrics;
+
+use anyhow::anyhow;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+/// Every `interval_secs`, matches the DEPIX Eulen reported minting against
+/// the DEPIX the wallet received, and flags settlements that arrived short
+/// or not at all.
+pub fn start_depix_reconciliation(
+    pool: PgPool,
+    liquid_channel: mpsc::Sender<LiquidRequest>,
+    notification_channel: mpsc::Sender<NotificationRequest>,
+    settings: Reconciliation,
+) {
+    let repository = Arc::new(ReconciliationRepository::new(pool));
+    jobs::register(
+        "depix_reconciliation",
+        JobSchedule::every_secs(settings.interval_secs),
+        move || {
+            let repository = repository.clone();
+            let liquid_channel = liquid_channel.clone();
+            let notification_channel = notification_channel.clone();
+            let settings = settings.clone();
+            async move {
+                reconcile(
+                    &repository,
+                    &liquid_channel,
+                    &notification_channel,
+                    &settings,
+                )
+                .await
+            }
+        },
+    );
+}
+
+async fn reconcile(
+    repository: &ReconciliationRepository,
+    liquid_channel: &mpsc::Sender<LiquidRequest>,
+    notification_channel: &mpsc::Sender<NotificationRequest>,
+    settings: &Reconciliation,
+) -> Result<(), anyhow::Error> {
+    let settlements = repository
+        .get_open_settlements(settings.lookback_days)
+        .await?;
+    let Some(oldest) = settlements.first() else {
+        return Ok(());
+    };
+
+    let since = oldest.reported_at - chrono::Duration::seconds(settings.match_window_secs);
+    let transfers = incoming_depix(liquid_channel, since).await?;
+    let mut claimed: HashSet<String> = repository.get_matched_txids().await?.into_iter().collect();
+
+    for settlement in settlements {
+        match find_transfer(&settlement, &transfers, &claimed, settings) {
+            Some((transfer, matched_by)) => {
+                let status = if transfer.amount as i64 >= settlement.expected_amount() {
+                    "matched"
+                } else {
+                    "short"
+                };
+                repository
+                    .record_match(
+                        &settlement.eulen_id,
+                        status,
+                        &transfer.txid,
+                        matched_by,
+                        transfer.amount as i64,
+                    )
+                    .await?;
+                claimed.insert(transfer.txid.clone());
+                metrics::increment(
+                    "depix_settlements_total",
+                    &[("status", status), ("matched_by", matched_by)],
+                );
+
+                if status == "short" {
+                    alert(
+                        notification_channel,
+                        format!(
+                            "Eulen reported {} cents of DEPIX for transaction {} ({}), but {} received only {} base units",
+                            settlement.value_in_cents,
+                            settlement.transaction_id,
+                            settlement.eulen_id,
+                            transfer.txid,
+                            transfer.amount
+                        ),
+                    )
+                    .await;
+                } else if settlement.status == "missing" {
+                    log::info!(
+                        "DEPIX of transaction {} arrived late in {}",
+                        settlement.transaction_id,
+                        transfer.txid
+                    );
+                }
+            }
+            None => {
+                let waiting = chrono::Utc::now() - settlement.reported_at;
+                if waiting < chrono::Duration::seconds(settings.grace_secs) {
+                    repository
+                        .record_unmatched(&settlement.eulen_id, "unmatched")
+                        .await?;
+                    continue;
+                }
+
+                repository
+                    .record_unmatched(&settlement.eulen_id, "missing")
+                    .await?;
+                if settlement.status != "missing" {
+                    metrics::increment(
+                        "depix_settlements_total",
+                        &[("status", "missing"), ("matched_by", "none")],
+                    );
+                    alert(
+                        notification_channel,
+                        format!(
+                            "No DEPIX arrived for transaction {} ({}) {} minutes after Eulen reported sending {} cents",
+                            settlement.transaction_id,
+                            settlement.eulen_id,
+                            waiting.num_minutes(),
+                            settlement.value_in_cents
+                        ),
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The transfer Eulen reported by txid, or else an unclaimed one of the
+/// exact amount closest in time to the report.
+fn find_transfer<'a>(
+    settlement: &DepixSettlement,
+    transfers: &'a [IncomingTransfer],
+    claimed: &HashSet<String>,
+    settings: &Reconciliation,
+) -> Option<(&'a IncomingTransfer, &'static str)> {
+    if let Some(txid) = &settlement.blockchain_tx_id {
+        if let Some(transfer) = transfers.iter().find(|transfer| &transfer.txid == txid) {
+            return Some((transfer, "txid"));
+        }
+    }
+
+    let window = chrono::Duration::seconds(settings.match_window_secs);
+    let now = chrono::Utc::now();
+    transfers
+        .iter()
+        .filter(|transfer| !claimed.contains(&transfer.txid))
+        .filter(|transfer| transfer.amount as i64 == settlement.expected_amount())
+        .map(|transfer| {
+            let distance = (transfer.timestamp.unwrap_or(now) - settlement.reported_at).abs();
+            (transfer, distance)
+        })
+        .filter(|(_, distance)| *distance <= window)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(transfer, _)| (transfer, "amount"))
+}
+
+async fn incoming_depix(
+    liquid_channel: &mpsc::Sender<LiquidRequest>,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<IncomingTransfer>, anyhow::Error> {
+    let (liquid_tx, liquid_rx) = oneshot::channel();
+    liquid_channel
+        .send(LiquidRequest::GetIncomingTransfers {
+            asset_id: Assets::DEPIX.hex(),
+            since,
+            response: liquid_tx,
+        })
+        .await
+        .map_err(|e| anyhow!("Could not reach the Liquid service: {}", e))?;
+
+    liquid_rx
+        .await
+        .map_err(|e| anyhow!("Liquid service dropped the request: {}", e))?
+        .map_err(|e| anyhow!("Could not list incoming DEPIX: {}", e))
+}
+
+async fn alert(notification_channel: &mpsc::Sender<NotificationRequest>, message: String) {
+    log::warn!("{}", message);
+    let _ = notification_channel
+        .send(NotificationRequest::Alert {
+            title: "DEPIX settlement mismatch".to_string(),
+            message,
+        })
+        .await;
+}
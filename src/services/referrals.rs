@@ -0,0 +1,32 @@
+use super::jobs::{self, JobSchedule};
+use crate::repositories::referrals::ReferralRepository;
+
+use chrono::Datelike;
+use sqlx::PgPool;
+use std::sync::Arc;
+
+const ROLLUP_INTERVAL_SECS: u64 = 60 * 60;
+
+/// Rebuilds the referral rollups of the current and the previous month
+/// every hour, so bonuses paid after their month closed still land in it.
+pub fn start_rollups(pool: PgPool) {
+    let repository = Arc::new(ReferralRepository::new(pool));
+    jobs::register(
+        "referral_rollups",
+        JobSchedule::every_secs(ROLLUP_INTERVAL_SECS),
+        move || {
+            let repository = repository.clone();
+            async move {
+                let today = chrono::Utc::now().date_naive();
+                let since = today
+                    .with_day(1)
+                    .and_then(|day| day.checked_sub_months(chrono::Months::new(1)))
+                    .ok_or_else(|| anyhow::anyhow!("No month before {}", today))?;
+
+                let written = repository.rebuild_rollups(since).await?;
+                log::debug!("Rebuilt {} referral rollups since {}", written, since);
+                Ok(())
+            }
+        },
+    );
+}
@@ -0,0 +1,400 @@
+use super::jobs::{self, JobSchedule};
+use super::{liquid::LiquidRequest, RequestHandler, Service, ServiceError};
+use crate::models::referrals::ReferralRollup;
+use crate::models::reports::{Payer, TaxableSale};
+use crate::models::reserves::{ProofOfReserves, ReserveProof};
+use crate::models::transactions::Assets;
+use crate::repositories::referrals::ReferralRepository;
+use crate::repositories::reports::ReportRepository;
+use crate::settings::TaxReports;
+use crate::utils::money::decimal;
+
+use async_trait::async_trait;
+use chrono::Datelike;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tokio::sync::{mpsc, oneshot};
+
+const IN1888: &str = "in1888";
+
+pub enum ReportRequest {
+    /// IN RFB 1888 report of the month starting on `month`, the stored one
+    /// unless `regenerate`.
+    In1888 {
+        month: chrono::NaiveDate,
+        regenerate: bool,
+        response: oneshot::Sender<Result<String, ServiceError>>,
+    },
+    /// Signs the wallet's addresses and publishes them with what is owed to
+    /// users.
+    CreateReserveProof {
+        challenge: Option<String>,
+        response: oneshot::Sender<Result<ReserveProof, ServiceError>>,
+    },
+    GetReserveProof {
+        hash: String,
+        response: oneshot::Sender<Result<Option<ReserveProof>, ServiceError>>,
+    },
+    /// Referral rollups of every referrer in the month starting on `month`,
+    /// as CSV.
+    ReferralRollups {
+        month: chrono::NaiveDate,
+        response: oneshot::Sender<Result<String, ServiceError>>,
+    },
+}
+
+#[derive(Clone)]
+pub struct ReportRequestHandler {
+    repository: ReportRepository,
+    referral_repository: ReferralRepository,
+    liquid_channel: mpsc::Sender<LiquidRequest>,
+    settings: TaxReports,
+}
+
+impl ReportRequestHandler {
+    pub fn new(
+        sql_conn: PgPool,
+        liquid_channel: mpsc::Sender<LiquidRequest>,
+        settings: TaxReports,
+    ) -> Self {
+        Self {
+            repository: ReportRepository::new(sql_conn.clone()),
+            referral_repository: ReferralRepository::new(sql_conn),
+            liquid_channel,
+            settings,
+        }
+    }
+
+    async fn get_in1888(
+        &self,
+        month: chrono::NaiveDate,
+        regenerate: bool,
+    ) -> Result<String, ServiceError> {
+        if !regenerate {
+            let stored = self
+                .repository
+                .get_report(IN1888, month)
+                .await
+                .map_err(|e| ServiceError::Repository("Reports".to_string(), e.to_string()))?;
+            if let Some(content) = stored {
+                return Ok(content);
+            }
+        }
+
+        let next_month = month
+            .checked_add_months(chrono::Months::new(1))
+            .ok_or(ServiceError::Internal("InvalidMonth".to_string()))?;
+        let sales = self
+            .repository
+            .get_taxable_sales(
+                month.and_time(chrono::NaiveTime::MIN).and_utc(),
+                next_month.and_time(chrono::NaiveTime::MIN).and_utc(),
+            )
+            .await
+            .map_err(|e| ServiceError::Repository("Reports".to_string(), e.to_string()))?;
+        let payers = self.get_payers(&sales).await?;
+
+        let content = in1888(&self.settings, month, &sales, &payers);
+        self.repository
+            .save_report(IN1888, month, &content, sales.len())
+            .await
+            .map_err(|e| ServiceError::Repository("Reports".to_string(), e.to_string()))?;
+
+        log::info!(
+            "Generated IN 1888 report of {} with {} operations",
+            month.format("%Y-%m"),
+            sales.len()
+        );
+
+        Ok(content)
+    }
+
+    async fn create_reserve_proof(
+        &self,
+        challenge: Option<String>,
+    ) -> Result<ReserveProof, ServiceError> {
+        let generated_at = chrono::Utc::now();
+        let message = match challenge {
+            Some(challenge) => format!(
+                "Mooze proof of reserves {} {}",
+                generated_at.to_rfc3339(),
+                challenge
+            ),
+            None => format!("Mooze proof of reserves {}", generated_at.to_rfc3339()),
+        };
+
+        let (liquid_tx, liquid_rx) = oneshot::channel();
+        self.liquid_channel
+            .send(LiquidRequest::SignReserves {
+                message: message.clone(),
+                response: liquid_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Report => Liquid".to_string(), e.to_string())
+            })?;
+        let addresses = liquid_rx.await.map_err(|e| {
+            ServiceError::Communication("Report => Liquid".to_string(), e.to_string())
+        })??;
+
+        let obligations = self
+            .repository
+            .get_obligations()
+            .await
+            .map_err(|e| ServiceError::Repository("Reports".to_string(), e.to_string()))?;
+
+        let mut totals = BTreeMap::new();
+        for (asset, amount) in addresses.iter().flat_map(|address| &address.balances) {
+            *totals.entry(asset.clone()).or_default() += amount;
+        }
+        let proof = ProofOfReserves {
+            generated_at,
+            message,
+            addresses,
+            totals,
+            obligations,
+        };
+
+        let content =
+            serde_json::to_string(&proof).map_err(|e| ServiceError::Internal(e.to_string()))?;
+        let hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+
+        log::info!("Published proof of reserves {}", hash);
+
+        self.repository
+            .save_proof(&hash, &content)
+            .await
+            .map_err(|e| ServiceError::Repository("Reports".to_string(), e.to_string()))
+    }
+
+    async fn get_referral_rollups(&self, month: chrono::NaiveDate) -> Result<String, ServiceError> {
+        let rollups = self
+            .referral_repository
+            .get_rollups(month, None)
+            .await
+            .map_err(|e| ServiceError::Repository("Referrals".to_string(), e.to_string()))?;
+
+        Ok(referral_rollups(&rollups))
+    }
+
+    async fn get_reserve_proof(&self, hash: &str) -> Result<Option<ReserveProof>, ServiceError> {
+        self.repository
+            .get_proof(hash)
+            .await
+            .map_err(|e| ServiceError::Repository("Reports".to_string(), e.to_string()))
+    }
+
+    /// Payers of the PIX charges of `sales`, by Eulen id, from the webhooks
+    /// that reported them.
+    async fn get_payers(
+        &self,
+        sales: &[TaxableSale],
+    ) -> Result<HashMap<String, Payer>, ServiceError> {
+        let eulen_ids: Vec<String> = sales
+            .iter()
+            .flat_map(|sale| sale.eulen_ids.iter().cloned())
+            .collect();
+        let payloads = self
+            .repository
+            .get_webhook_payloads(&eulen_ids)
+            .await
+            .map_err(|e| ServiceError::Repository("Reports".to_string(), e.to_string()))?;
+
+        let wanted: HashSet<&String> = eulen_ids.iter().collect();
+        let mut payers = HashMap::new();
+        for payload in payloads {
+            let value: serde_json::Value = serde_json::from_str(&payload).unwrap_or_default();
+            let items = match value {
+                serde_json::Value::Array(items) => items,
+                item => vec![item],
+            };
+
+            for item in items {
+                let field = |name: &str| {
+                    item.get(name)
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                let (qr_id, tax_number) = (field("qrId"), field("payerTaxNumber"));
+                if tax_number.is_empty() || !wanted.contains(&qr_id) {
+                    continue;
+                }
+
+                payers.insert(
+                    qr_id,
+                    Payer {
+                        name: field("payerName"),
+                        tax_number,
+                    },
+                );
+            }
+        }
+
+        Ok(payers)
+    }
+}
+
+/// Report in the layout of IN RFB 1888/2019: a `0000` header with the
+/// reporting exchange, a `0110` purchase and sale record per operation and a
+/// `9999` trailer with the number of records, fields separated by `|`.
+fn in1888(
+    settings: &TaxReports,
+    month: chrono::NaiveDate,
+    sales: &[TaxableSale],
+    payers: &HashMap<String, Payer>,
+) -> String {
+    let mut lines = vec![format!(
+        "0000|{}|{}|{}",
+        field(&settings.cnpj),
+        field(&settings.name),
+        month.format("%m%Y")
+    )];
+
+    for sale in sales {
+        let payer = sale
+            .eulen_ids
+            .iter()
+            .find_map(|id| payers.get(id))
+            .cloned()
+            .unwrap_or_default();
+        let symbol = Assets::from_hex(&sale.asset)
+            .map(|asset| asset.ticker())
+            .unwrap_or_default();
+        // The layout asks for 10 decimals, more than any asset has
+        let quantity_scale = 10_i64.pow(10 - u32::from(Assets::precision_of(&sale.asset)));
+
+        lines.push(format!(
+            "0110|{}|{}|{}|{}|{}|{}|BR|{}|{}|{}|BR|CNPJ|{}|{}",
+            sale.paid_at.format("%d%m%Y"),
+            sale.transaction_id,
+            decimal(i64::from(sale.amount_in_cents), 2),
+            decimal(sale.fee_in_cents(), 2),
+            symbol,
+            decimal(sale.quantity() * quantity_scale, 10),
+            document_type(&payer.tax_number),
+            field(&payer.tax_number),
+            field(&payer.name),
+            field(&settings.cnpj),
+            field(&settings.name),
+        ));
+    }
+
+    lines.push(format!("9999|{}", lines.len() + 1));
+    lines.join("\r\n") + "\r\n"
+}
+
+/// One line per referrer and asset. Amounts are base units of the asset and
+/// cents, as stored.
+fn referral_rollups(rollups: &[ReferralRollup]) -> String {
+    let mut lines = vec![
+        "month,referrer_id,asset,earned_count,earned,earned_in_cents,paid_count,paid,paid_in_cents"
+            .to_string(),
+    ];
+    for rollup in rollups {
+        lines.push(format!(
+            "{},{},{},{},{},{},{},{},{}",
+            rollup.month.format("%Y-%m"),
+            rollup.referrer_id,
+            rollup.asset,
+            rollup.earned_count,
+            rollup.earned,
+            rollup.earned_in_cents,
+            rollup.paid_count,
+            rollup.paid,
+            rollup.paid_in_cents
+        ));
+    }
+
+    lines.join("\r\n") + "\r\n"
+}
+
+fn document_type(tax_number: &str) -> &'static str {
+    match tax_number.chars().filter(char::is_ascii_digit).count() {
+        11 => "CPF",
+        14 => "CNPJ",
+        _ => "",
+    }
+}
+
+/// Text without the field separator or line breaks.
+fn field(text: &str) -> String {
+    text.replace(['|', '\r', '\n'], " ")
+}
+
+#[async_trait]
+impl RequestHandler<ReportRequest> for ReportRequestHandler {
+    async fn handle_request(&self, request: ReportRequest) {
+        match request {
+            ReportRequest::In1888 {
+                month,
+                regenerate,
+                response,
+            } => {
+                let report = self.get_in1888(month, regenerate).await;
+                let _ = response.send(report);
+            }
+            ReportRequest::CreateReserveProof {
+                challenge,
+                response,
+            } => {
+                let proof = self.create_reserve_proof(challenge).await;
+                let _ = response.send(proof);
+            }
+            ReportRequest::GetReserveProof { hash, response } => {
+                let proof = self.get_reserve_proof(&hash).await;
+                let _ = response.send(proof);
+            }
+            ReportRequest::ReferralRollups { month, response } => {
+                let report = self.get_referral_rollups(month).await;
+                let _ = response.send(report);
+            }
+        }
+    }
+}
+
+/// Generates the report of the previous month once it has closed, checking
+/// every `interval_secs`.
+pub fn start_monthly_reports(report_channel: mpsc::Sender<ReportRequest>, settings: TaxReports) {
+    if !settings.enabled {
+        return;
+    }
+
+    jobs::register(
+        "tax_reports",
+        JobSchedule::every_secs(settings.interval_secs),
+        move || {
+            let report_channel = report_channel.clone();
+            async move {
+                let today = chrono::Utc::now().date_naive();
+                let month = today
+                    .with_day(1)
+                    .and_then(|day| day.checked_sub_months(chrono::Months::new(1)))
+                    .ok_or_else(|| anyhow::anyhow!("No month before {}", today))?;
+
+                let (report_tx, report_rx) = oneshot::channel();
+                report_channel
+                    .send(ReportRequest::In1888 {
+                        month,
+                        regenerate: false,
+                        response: report_tx,
+                    })
+                    .await?;
+                report_rx.await??;
+                Ok(())
+            }
+        },
+    );
+}
+
+pub struct ReportService;
+
+impl ReportService {
+    pub fn new() -> Self {
+        ReportService {}
+    }
+}
+
+#[async_trait]
+impl Service<ReportRequest, ReportRequestHandler> for ReportService {}
@@ -0,0 +1,245 @@
+use super::jobs::{self, JobSchedule};
+use super::{
+    notifications::NotificationRequest, transactions::TransactionServiceRequest, RequestHandler,
+    Service, ServiceError,
+};
+use crate::models::schedules::{NewSchedule, Schedule};
+use crate::models::transactions::Assets;
+use crate::repositories::schedules::ScheduleRepository;
+use crate::utils::money;
+
+use async_trait::async_trait;
+use serde_json::json;
+use sqlx::PgPool;
+use tokio::sync::{mpsc, oneshot};
+
+pub enum SchedulerRequest {
+    CreateSchedule {
+        schedule: NewSchedule,
+        response: oneshot::Sender<Result<Schedule, ServiceError>>,
+    },
+    GetUserSchedules {
+        user_id: String,
+        response: oneshot::Sender<Result<Vec<Schedule>, ServiceError>>,
+    },
+    SetScheduleStatus {
+        schedule_id: String,
+        user_id: String,
+        status: String,
+        response: oneshot::Sender<Result<Option<Schedule>, ServiceError>>,
+    },
+}
+
+#[derive(Clone)]
+pub struct SchedulerRequestHandler {
+    repository: ScheduleRepository,
+    transaction_channel: mpsc::Sender<TransactionServiceRequest>,
+    notification_channel: mpsc::Sender<NotificationRequest>,
+}
+
+impl SchedulerRequestHandler {
+    pub fn new(
+        sql_conn: PgPool,
+        transaction_channel: mpsc::Sender<TransactionServiceRequest>,
+        notification_channel: mpsc::Sender<NotificationRequest>,
+    ) -> Self {
+        let handler = Self {
+            repository: ScheduleRepository::new(sql_conn),
+            transaction_channel,
+            notification_channel,
+        };
+
+        handler.start_scheduler();
+
+        handler
+    }
+
+    fn start_scheduler(&self) {
+        let handler = self.clone();
+
+        jobs::register("scheduled_buys", JobSchedule::every_secs(60), move || {
+            let handler = handler.clone();
+            async move { handler.run_due_schedules().await }
+        });
+    }
+
+    /// Schedules buy any asset sold, given by its id or its ticker; the id
+    /// is stored.
+    async fn create_schedule(&self, mut schedule: NewSchedule) -> Result<Schedule, ServiceError> {
+        let asset = Assets::from_hex(&schedule.asset)
+            .or_else(|_| Assets::from_ticker(&schedule.asset))
+            .map_err(|_| ServiceError::Internal("Invalid asset".to_string()))?;
+        schedule.asset = asset.hex();
+        if schedule.frequency != "weekly" && schedule.frequency != "monthly" {
+            return Err(ServiceError::Internal("Invalid frequency".to_string()));
+        }
+        if schedule.amount_in_cents <= 0 {
+            return Err(ServiceError::Internal("Invalid amount".to_string()));
+        }
+
+        self.repository
+            .insert_schedule(&schedule)
+            .await
+            .map_err(|e| ServiceError::Repository("Schedules".to_string(), e.to_string()))
+    }
+
+    async fn get_user_schedules(&self, user_id: &str) -> Result<Vec<Schedule>, ServiceError> {
+        self.repository
+            .get_user_schedules(user_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Schedules".to_string(), e.to_string()))
+    }
+
+    async fn set_schedule_status(
+        &self,
+        schedule_id: &str,
+        user_id: &str,
+        status: &str,
+    ) -> Result<Option<Schedule>, ServiceError> {
+        self.repository
+            .set_status(schedule_id, user_id, status)
+            .await
+            .map_err(|e| ServiceError::Repository("Schedules".to_string(), e.to_string()))
+    }
+
+    async fn run_due_schedules(&self) -> Result<(), anyhow::Error> {
+        let schedules = self.repository.get_due_schedules().await?;
+
+        for schedule in schedules {
+            self.run_schedule(schedule).await;
+        }
+
+        Ok(())
+    }
+
+    /// Creates the charge for one run through the regular deposit flow, so
+    /// limits and liquidity checks apply as for any other deposit.
+    async fn run_schedule(&self, schedule: Schedule) {
+        let Some(next_run_at) = schedule.next_run_after(schedule.next_run_at) else {
+            log::error!(
+                "Schedule {} has an invalid frequency: {}",
+                schedule.id,
+                schedule.frequency
+            );
+            return;
+        };
+        // Runs missed while the dealer was down are skipped, not replayed
+        let mut next_run_at = next_run_at;
+        while next_run_at <= chrono::Utc::now() {
+            match schedule.next_run_after(next_run_at) {
+                Some(next) => next_run_at = next,
+                None => return,
+            }
+        }
+
+        match self.repository.claim_run(&schedule, next_run_at).await {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                log::error!("Could not claim schedule {}: {}", schedule.id, e);
+                return;
+            }
+        }
+
+        log::info!("Running schedule {} for user {}", schedule.id, schedule.user_id);
+
+        let (transaction_tx, transaction_rx) = oneshot::channel();
+        let result = match self
+            .transaction_channel
+            .send(TransactionServiceRequest::NewTransaction {
+                user_id: schedule.user_id.clone(),
+                address: schedule.address.clone(),
+                amount_in_cents: schedule.amount_in_cents,
+                asset: schedule.asset.clone(),
+                network: schedule.network.clone(),
+                quote_id: None,
+                scheduled_delivery_at: None,
+                response: transaction_tx,
+            })
+            .await
+        {
+            Ok(_) => match transaction_rx.await {
+                Ok(Ok(deposit)) => Ok(deposit),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+            Err(e) => Err(e.to_string()),
+        };
+
+        if let Err(e) = self.repository.record_run(&schedule.id, &result).await {
+            log::error!("Could not record run of schedule {}: {}", schedule.id, e);
+        }
+
+        let (title, message, data) = match &result {
+            Ok(deposit) => (
+                "Compra programada",
+                format!(
+                    "Sua compra programada de {} está pronta para pagamento.",
+                    money::format_brl(schedule.amount_in_cents.into())
+                ),
+                json!({
+                    "schedule_id": schedule.id,
+                    "transaction_id": deposit.transaction_id,
+                    "qr_copy_paste": deposit.qr_copy_paste,
+                    "qr_image_url": deposit.qr_image_url,
+                }),
+            ),
+            Err(e) => {
+                log::warn!("Schedule {} run failed: {}", schedule.id, e);
+                (
+                    "Compra programada",
+                    "Não foi possível gerar sua compra programada desta vez.".to_string(),
+                    json!({ "schedule_id": schedule.id }),
+                )
+            }
+        };
+
+        let _ = self
+            .notification_channel
+            .send(NotificationRequest::UserNotice {
+                user_id: schedule.user_id.clone(),
+                title: title.to_string(),
+                message,
+                data,
+            })
+            .await;
+    }
+}
+
+#[async_trait]
+impl RequestHandler<SchedulerRequest> for SchedulerRequestHandler {
+    async fn handle_request(&self, request: SchedulerRequest) {
+        match request {
+            SchedulerRequest::CreateSchedule { schedule, response } => {
+                let result = self.create_schedule(schedule).await;
+                let _ = response.send(result);
+            }
+            SchedulerRequest::GetUserSchedules { user_id, response } => {
+                let result = self.get_user_schedules(&user_id).await;
+                let _ = response.send(result);
+            }
+            SchedulerRequest::SetScheduleStatus {
+                schedule_id,
+                user_id,
+                status,
+                response,
+            } => {
+                let result = self
+                    .set_schedule_status(&schedule_id, &user_id, &status)
+                    .await;
+                let _ = response.send(result);
+            }
+        }
+    }
+}
+
+pub struct SchedulerService;
+
+impl SchedulerService {
+    pub fn new() -> Self {
+        SchedulerService {}
+    }
+}
+
+#[async_trait]
+impl Service<SchedulerRequest, SchedulerRequestHandler> for SchedulerService {}
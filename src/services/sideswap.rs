@@ -1,14 +1,37 @@
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
 use super::{liquid::LiquidRequest, RequestHandler, Service, ServiceError};
+use super::hedging::HedgingRequest;
+use super::jobs::{self, JobSchedule};
+use super::price::PriceRequest;
+use super::supervisor::ServiceHealth;
+use super::transactions::TransactionServiceRequest;
 
-use crate::models::sideswap::{AssetType, QuoteStatus};
-use crate::models::sideswap::{QuoteRequest, SideswapUtxo, TradeDir};
+use crate::models::price::QuoteCurrency;
+use crate::models::sideswap::{
+    ActiveQuote, AssetPair, AssetType, Maintenance, Market, QuoteStatus,
+};
+use crate::models::sideswap::{QuoteRequest, ServerStatus, SideswapUtxo, TradeDir, VenueStatus};
+use crate::models::transactions::Assets;
+use crate::models::treasury::{SwapExecution, SwapQuote, TreasuryReport};
+use crate::repositories::psets::PsetRepository;
+use crate::repositories::treasury::TreasuryRepository;
+use crate::repositories::webhooks::WebhookRepository;
+use crate::settings::Sideswap;
+use crate::utils::metrics;
+use crate::utils::money;
 use async_trait::async_trait;
 use lwk_wollet::elements::pset::PartiallySignedTransaction;
-use tokio::sync::{mpsc, oneshot};
+use sqlx::PgPool;
+use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
 
 mod client;
+mod pset;
+
+pub use client::SideswapError;
 
 pub enum SideswapMessage {
     Request(SideswapRequest),
@@ -28,31 +51,357 @@ pub enum SideswapRequest {
         quote_sub_id: i64,
         status: QuoteStatus,
     },
+    GetActiveQuotes {
+        response: oneshot::Sender<Vec<ActiveQuote>>,
+    },
+    /// Latest price of a market chart, in quote asset per base asset.
+    MarketPrice { asset_pair: AssetPair, price: f64 },
+    /// Units of `receive_asset` the latest chart price gives for `amount`,
+    /// none when Sideswap has no market for the pair.
+    Estimate {
+        sell_asset: String,
+        receive_asset: String,
+        amount: i64,
+        response: oneshot::Sender<Result<Option<u64>, ServiceError>>,
+    },
+    GetTreasuryReport {
+        days: i64,
+        response: oneshot::Sender<Result<TreasuryReport, ServiceError>>,
+    },
+    /// Fees and announced maintenance, from a `server_status` notification.
+    ServerStatus { status: ServerStatus },
+    GetVenueStatus {
+        response: oneshot::Sender<VenueStatus>,
+    },
 }
 
+/// A server status and when it came in.
+type TimedServerStatus = (ServerStatus, chrono::DateTime<chrono::Utc>);
+
 #[derive(Clone)]
 pub struct SideswapRequestHandler {
     client: client::SideswapClient,
     liquid_channel: mpsc::Sender<LiquidRequest>,
+    transaction_channel: mpsc::Sender<TransactionServiceRequest>,
+    hedging_channel: mpsc::Sender<HedgingRequest>,
+    price_channel: mpsc::Sender<PriceRequest>,
+    treasury_repository: TreasuryRepository,
+    pset_repository: PsetRepository,
+    active_quotes: Arc<Mutex<HashMap<i64, ActiveQuote>>>,
+    /// Market list, refreshed in the background instead of on every swap.
+    markets: Arc<RwLock<Vec<Market>>>,
+    /// Markets whose chart we are subscribed to, as (base, quote).
+    charts: Arc<Mutex<HashSet<(String, String)>>>,
+    /// Latest chart price per (base, quote).
+    prices: Arc<RwLock<HashMap<(String, String), f64>>>,
+    /// Latest server status and when it came in.
+    server_status: Arc<RwLock<Option<TimedServerStatus>>>,
+    /// Swaps still being quoted both ways, by swap id.
+    probes: Arc<Mutex<HashMap<i64, QuoteProbe>>>,
+    /// Subscriptions stopped to quote a swap the other way, with its swap id.
+    /// Their late quotes are ignored.
+    superseded: Arc<Mutex<HashMap<i64, i64>>>,
+    dual_quotes: bool,
+    /// How far the PSET of a swap may stray from its quote, in basis points.
+    pset_tolerance_bps: u64,
+    /// Accepted quotes are not signed.
+    dry_run: bool,
+}
+
+/// Services the Sideswap service talks to, and the channel its client
+/// forwards notifications on.
+#[derive(Clone)]
+pub struct SideswapChannels {
+    pub liquid: mpsc::Sender<LiquidRequest>,
+    pub transaction: mpsc::Sender<TransactionServiceRequest>,
+    pub hedging: mpsc::Sender<HedgingRequest>,
+    pub price: mpsc::Sender<PriceRequest>,
+    pub client: mpsc::Sender<SideswapRequest>,
+}
+
+/// A swap quoted with the amount in one asset, waiting to be quoted with
+/// it in the other.
+struct QuoteProbe {
+    current: QuoteRequest,
+    remaining: Vec<QuoteRequest>,
+    /// Best priced request so far, with its price.
+    best: Option<(QuoteRequest, f64)>,
 }
 
 impl SideswapRequestHandler {
     pub async fn new(
-        sideswap_url: &str,
-        sideswap_api_key: &str,
-        liquid_channel: mpsc::Sender<LiquidRequest>,
-        client_channel: mpsc::Sender<SideswapRequest>,
-    ) -> Self {
-        let mut client =
-            client::SideswapClient::new(sideswap_url, sideswap_api_key.to_string(), client_channel)
-                .await;
-
-        let _ = client.start().await;
-        client.start_notification_listener().await;
-
-        Self {
+        sql_conn: PgPool,
+        settings: &Sideswap,
+        channels: SideswapChannels,
+        dry_run: bool,
+    ) -> Result<Self, anyhow::Error> {
+        let mut client = client::SideswapClient::new(
+            &settings.url,
+            settings.api_key.clone(),
+            Duration::from_secs(settings.call_timeout_secs.unwrap_or(30)),
+            Duration::from_secs(settings.ping_interval_secs.unwrap_or(15)),
+            channels.client,
+            Arc::new(WebhookRepository::new(sql_conn.clone())),
+        )
+        .await?;
+
+        client.start().await?;
+
+        let handler = Self {
             client,
-            liquid_channel,
+            liquid_channel: channels.liquid,
+            transaction_channel: channels.transaction,
+            hedging_channel: channels.hedging,
+            price_channel: channels.price,
+            treasury_repository: TreasuryRepository::new(sql_conn.clone()),
+            pset_repository: PsetRepository::new(sql_conn),
+            active_quotes: Arc::new(Mutex::new(HashMap::new())),
+            markets: Arc::new(RwLock::new(Vec::new())),
+            charts: Arc::new(Mutex::new(HashSet::new())),
+            prices: Arc::new(RwLock::new(HashMap::new())),
+            server_status: Arc::new(RwLock::new(None)),
+            probes: Arc::new(Mutex::new(HashMap::new())),
+            superseded: Arc::new(Mutex::new(HashMap::new())),
+            dual_quotes: settings.dual_quotes.unwrap_or(true),
+            pset_tolerance_bps: settings.pset_tolerance_bps.unwrap_or(10),
+            dry_run,
+        };
+
+        handler.refresh_markets().await?;
+        handler.start_market_refresh(Duration::from_secs(
+            settings.market_refresh_secs.unwrap_or(300),
+        ));
+
+        Ok(handler)
+    }
+
+    fn start_market_refresh(&self, every: Duration) {
+        let handler = self.clone();
+
+        jobs::register("sideswap_markets", JobSchedule::Every(every), move || {
+            let handler = handler.clone();
+            async move { handler.refresh_markets().await }
+        });
+    }
+
+    /// Updates the cached market list and subscribes to the chart of every
+    /// new market between assets we trade.
+    async fn refresh_markets(&self) -> Result<(), anyhow::Error> {
+        let markets = self.client.get_markets().await?.markets;
+        log::debug!("Cached {} Sideswap markets", markets.len());
+        *self.markets.write().await = markets.clone();
+
+        for market in markets {
+            let pair = &market.asset_pair;
+            if Assets::from_hex(&pair.base).is_err() || Assets::from_hex(&pair.quote).is_err() {
+                continue;
+            }
+
+            let key = (pair.base.clone(), pair.quote.clone());
+            if self.charts.lock().await.contains(&key) {
+                continue;
+            }
+
+            match self.client.subscribe_chart(pair).await {
+                Ok(price) => {
+                    self.charts.lock().await.insert(key);
+                    if let Some(price) = price {
+                        self.record_market_price(pair, price).await;
+                    }
+                }
+                Err(e) => log::warn!(
+                    "Could not subscribe to Sideswap chart {}/{}: {}",
+                    pair.base,
+                    pair.quote,
+                    e
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Exposes the Sideswap price of a market and its spread against the
+    /// price service, in basis points.
+    async fn record_market_price(&self, asset_pair: &AssetPair, price: f64) {
+        let labels = [
+            ("base", asset_pair.base.as_str()),
+            ("quote", asset_pair.quote.as_str()),
+        ];
+        metrics::set_gauge("sideswap_price", &labels, price);
+        self.prices.write().await.insert(
+            (asset_pair.base.clone(), asset_pair.quote.clone()),
+            price,
+        );
+
+        let base = self.request_price(&asset_pair.base).await;
+        let quote = self.request_price(&asset_pair.quote).await;
+        match (base, quote) {
+            (Ok(base), Ok(quote)) => {
+                let oracle = base / quote;
+                metrics::set_gauge("sideswap_oracle_price", &labels, oracle);
+                metrics::set_gauge(
+                    "sideswap_spread_bps",
+                    &labels,
+                    (price - oracle) / oracle * 10_000.0,
+                );
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                log::debug!("No oracle price to compare Sideswap market with: {}", e)
+            }
+        }
+    }
+
+    async fn record_server_status(&self, status: ServerStatus) {
+        let previous = self
+            .server_status
+            .write()
+            .await
+            .replace((status.clone(), chrono::Utc::now()));
+        let was_paused = previous
+            .and_then(|(previous, _)| previous.maintenance)
+            .is_some_and(|maintenance| maintenance.is_active(chrono::Utc::now()));
+
+        match &status.maintenance {
+            Some(maintenance) if maintenance.is_active(chrono::Utc::now()) && !was_paused => {
+                log::warn!(
+                    "Sideswap is under maintenance ({}), pausing swaps",
+                    maintenance.message.as_deref().unwrap_or("no details")
+                )
+            }
+            // Already paused for it
+            Some(maintenance) if maintenance.is_active(chrono::Utc::now()) => {}
+            Some(maintenance) => log::info!(
+                "Sideswap announced maintenance from {:?} to {:?}",
+                maintenance.start_time,
+                maintenance.end_time
+            ),
+            None if was_paused => log::info!("Sideswap maintenance is over, resuming swaps"),
+            None => {}
+        }
+
+        if let Some(fee_rate) = status.elements_fee_rate {
+            metrics::set_gauge("sideswap_network_fee_rate", &[], fee_rate);
+        }
+        let paused = self.maintenance().await.is_some();
+        metrics::set_gauge("sideswap_maintenance", &[], if paused { 1.0 } else { 0.0 });
+    }
+
+    /// Maintenance Sideswap announced that is going on now.
+    async fn maintenance(&self) -> Option<Maintenance> {
+        self.server_status
+            .read()
+            .await
+            .as_ref()
+            .and_then(|(status, _)| status.maintenance.clone())
+            .filter(|maintenance| maintenance.is_active(chrono::Utc::now()))
+    }
+
+    async fn venue_status(&self) -> VenueStatus {
+        let server_status = self.server_status.read().await.clone();
+        let connected = *self.client.connection().borrow();
+
+        VenueStatus {
+            connected,
+            api_version: self.client.api_version(),
+            paused: self.maintenance().await.is_some(),
+            updated_at: server_status.as_ref().map(|(_, updated_at)| *updated_at),
+            server_status: server_status.map(|(status, _)| status),
+        }
+    }
+
+    /// None during maintenance, so swaps are routed to the other venues.
+    async fn estimate(
+        &self,
+        sell_asset: &str,
+        receive_asset: &str,
+        amount: i64,
+    ) -> Result<Option<u64>, ServiceError> {
+        if self.maintenance().await.is_some() {
+            return Ok(None);
+        }
+
+        let Some(market) = self.find_market(sell_asset, receive_asset).await else {
+            return Ok(None);
+        };
+        let pair = market.asset_pair;
+
+        let price = self
+            .prices
+            .read()
+            .await
+            .get(&(pair.base.clone(), pair.quote.clone()))
+            .copied()
+            .filter(|price| *price > 0.0)
+            .ok_or_else(|| ServiceError::Internal("NoMarketPrice".to_string()))?;
+
+        // The price is in whole units, scaled to the precision of each side
+        let scale = Assets::unit_of(&pair.quote) as f64 / Assets::unit_of(&pair.base) as f64;
+        let received = match pair.base == sell_asset {
+            true => amount as f64 * price * scale,
+            false => amount as f64 / price / scale,
+        };
+
+        Ok(Some(received as u64))
+    }
+
+    /// Books an executed swap with the fee asset of its market and the oracle
+    /// prices at execution.
+    async fn record_swap(&self, mut swap: SwapExecution) {
+        swap.fee_asset = self
+            .find_market(&swap.sell_asset, &swap.receive_asset)
+            .await
+            .map(|market| market.fee_asset);
+
+        let price_in_cents = |price: Result<f64, ServiceError>| price.ok().map(money::to_cents);
+        swap.sell_price_in_cents = price_in_cents(self.request_price(&swap.sell_asset).await);
+        swap.receive_price_in_cents = price_in_cents(self.request_price(&swap.receive_asset).await);
+        if let Some(fee_asset) = &swap.fee_asset {
+            swap.fee_price_in_cents = price_in_cents(self.request_price(fee_asset).await);
+        }
+
+        if let Err(e) = self.treasury_repository.record_swap(&swap).await {
+            log::error!("Could not record swap {}: {}", swap.quote_sub_id, e);
+        }
+    }
+
+    async fn treasury_report(&self, days: i64) -> Result<TreasuryReport, ServiceError> {
+        let daily = self
+            .treasury_repository
+            .get_daily_summaries(days)
+            .await
+            .map_err(|e| ServiceError::Repository("Treasury".to_string(), e.to_string()))?;
+
+        Ok(TreasuryReport {
+            days,
+            total_fees_brl_cents: daily.iter().filter_map(|day| day.fees_brl_cents).sum(),
+            total_pnl_cents: daily.iter().filter_map(|day| day.pnl_cents).sum(),
+            daily,
+        })
+    }
+
+    async fn request_price(&self, asset: &str) -> Result<f64, ServiceError> {
+        let asset = Assets::from_hex(asset).map_err(ServiceError::Internal)?;
+
+        let (price_tx, price_rx) = oneshot::channel();
+        self.price_channel
+            .send(PriceRequest::GetPrice {
+                asset,
+                currency: QuoteCurrency::Brl,
+                response: price_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Sideswap => Price".to_string(), e.to_string())
+            })?;
+
+        let price = price_rx.await.map_err(|e| {
+            ServiceError::Communication("Price => Sideswap".to_string(), e.to_string())
+        })??;
+
+        match price {
+            Some(price) if price.price > 0.0 => Ok(price.price),
+            _ => Err(ServiceError::Internal("Asset price not found".to_string())),
         }
     }
 
@@ -110,6 +459,22 @@ impl SideswapRequestHandler {
     ) -> Result<i64, ServiceError> {
         log::info!("Starting quotes for sell_asset={sell_asset}, receive_asset={receive_asset}, amount={amount}");
 
+        if let Some(maintenance) = self.maintenance().await {
+            log::warn!(
+                "Not swapping during Sideswap maintenance: {}",
+                maintenance.message.as_deref().unwrap_or("no details")
+            );
+            metrics::increment("sideswap_swaps_paused_total", &[]);
+            return Err(ServiceError::Internal("SideswapMaintenance".to_string()));
+        }
+
+        // Checked first, so no addresses are handed out for a pair Sideswap
+        // does not trade
+        let Some(market) = self.find_market(&sell_asset, &receive_asset).await else {
+            log::error!("Market not found for sell_asset={sell_asset}, receive_asset={receive_asset}, amount={amount}");
+            return Err(ServiceError::Internal("MarketNotFound".to_string()));
+        };
+
         let receive_address = self.request_address().await?;
         let change_address = self.request_change_address().await?;
 
@@ -167,61 +532,270 @@ impl SideswapRequestHandler {
 
         log::info!("Found {} utxos for sell_asset={sell_asset}, receive_asset={receive_asset}, amount={amount}", sideswap_utxos.len());
 
-        let markets = self.client.get_markets().await.map_err(|e| {
-            ServiceError::Communication(
-                "Sideswap".to_string(),
-                format!("Could not fetch markets: {}", e),
-            )
-        })?;
-        log::info!("Found {} markets", markets.markets.len());
-        let asset_pair = markets
-            .markets
-            .into_iter()
-            .filter(|market| {
-                (market.asset_pair.base == sell_asset && market.asset_pair.quote == receive_asset)
-                    || (market.asset_pair.base == receive_asset
-                        && market.asset_pair.quote == sell_asset)
-            })
-            .next();
-
-        match asset_pair {
-            Some(pair) => {
-                log::info!("Found asset pair: {:?}", pair);
-                let quote_request = QuoteRequest {
-                    asset_pair: pair.asset_pair,
-                    asset_type: if pair.asset_type == "Quote" {
-                        AssetType::Base
-                    } else {
-                        AssetType::Quote
-                    },
-                    trade_dir: TradeDir::Sell,
-                    amount,
-                    utxos: sideswap_utxos,
-                    receive_address,
-                    change_address,
-                };
+        log::info!("Found market: {:?}", market);
+        let sell_is_base = market.asset_pair.base == sell_asset;
+        let (sell_side, receive_side) = match sell_is_base {
+            true => (AssetType::Base, AssetType::Quote),
+            false => (AssetType::Quote, AssetType::Base),
+        };
+        // Selling the quote asset is buying the base
+        let trade_dir = match sell_is_base {
+            true => TradeDir::Sell,
+            false => TradeDir::Buy,
+        };
+        let quote_request = QuoteRequest {
+            asset_pair: market.asset_pair,
+            asset_type: sell_side,
+            trade_dir,
+            amount,
+            utxos: sideswap_utxos,
+            receive_address,
+            change_address,
+        };
+
+        // The same swap with the amount in the received asset, at the chart
+        // price, sometimes quotes better
+        let mut remaining = Vec::new();
+        if self.dual_quotes {
+            if let Ok(Some(received)) = self.estimate(&sell_asset, &receive_asset, amount).await {
+                remaining.push(QuoteRequest {
+                    asset_type: receive_side,
+                    amount: received as i64,
+                    ..quote_request.clone()
+                });
+            }
+        }
+
+        log::debug!("Quote request: {:?}", quote_request);
+
+        let quote = self
+            .client
+            .start_quotes(quote_request.clone())
+            .await
+            .map_err(sideswap_error)?;
+
+        log::debug!("Quote ID: {}", quote.quote_sub_id);
+        self.active_quotes.lock().await.insert(
+            quote.quote_sub_id,
+            ActiveQuote {
+                quote_sub_id: quote.quote_sub_id,
+                swap_id: None,
+                sell_asset,
+                receive_asset,
+                amount,
+                amount_is_received: false,
+                sell_is_base,
+                started_at: chrono::Utc::now(),
+            },
+        );
+        if !remaining.is_empty() {
+            self.probes.lock().await.insert(
+                quote.quote_sub_id,
+                QuoteProbe {
+                    current: quote_request,
+                    remaining,
+                    best: None,
+                },
+            );
+        }
 
-                log::debug!("Quote request: {:?}", quote_request);
+        Ok(quote.quote_sub_id)
+    }
+
+    /// While a swap is quoted both ways, takes its quote instead of letting
+    /// it be accepted, and moves on to the other way or back to the better
+    /// one. Returns whether it took the quote.
+    async fn probe_quote(&self, active_quote: &ActiveQuote, status: &QuoteStatus) -> bool {
+        let swap_id = active_quote.swap_id();
+        let mut probes = self.probes.lock().await;
+        let Some(probe) = probes.get_mut(&swap_id) else {
+            return false;
+        };
+
+        let quoted = match status {
+            QuoteStatus::Success {
+                quote_id,
+                base_amount,
+                quote_amount,
+                server_fee,
+                fixed_fee,
+                ..
+            } => {
+                let (sold, received) = active_quote.sold_and_received(*base_amount, *quote_amount);
+                (sold > 0).then(|| SwapQuote {
+                    swap_id,
+                    quote_sub_id: active_quote.quote_sub_id,
+                    quote_id: *quote_id as i64,
+                    sell_asset: active_quote.sell_asset.clone(),
+                    receive_asset: active_quote.receive_asset.clone(),
+                    quoted_in: quoted_in(active_quote),
+                    requested_amount: active_quote.amount,
+                    sold_amount: sold as i64,
+                    received_amount: received as i64,
+                    server_fee: *server_fee as i64,
+                    fixed_fee: *fixed_fee as i64,
+                    price: received as f64 / sold as f64,
+                    chosen: false,
+                })
+            }
+            _ => None,
+        };
 
-                let quote =
-                    self.client.start_quotes(quote_request).await.map_err(|e| {
-                        ServiceError::Repository("Sideswap".to_string(), e.to_string())
-                    })?;
+        let best_price = probe.best.as_ref().map(|(_, price)| *price);
+        let current_is_best = match &quoted {
+            Some(quote) if best_price.is_none_or(|best| quote.price > best) => {
+                probe.best = Some((probe.current.clone(), quote.price));
+                true
+            }
+            _ => false,
+        };
+
+        // When deciding, the next quote is taken as is
+        let (next, deciding) = if !probe.remaining.is_empty() {
+            // Nothing to compare the other way with
+            let deciding = probe.best.is_none();
+            (probe.remaining.remove(0), deciding)
+        } else {
+            match probe.best.take() {
+                // Quoted every way: take this quote if it is the best, or
+                // fail as usual if none was good
+                None => {
+                    probes.remove(&swap_id);
+                    return false;
+                }
+                Some(_) if current_is_best => {
+                    probes.remove(&swap_id);
+                    metrics::increment(
+                        "sideswap_quote_comparisons_total",
+                        &[("quoted_in", quoted_in(active_quote))],
+                    );
+                    return false;
+                }
+                // Back to the better one
+                Some((best, _)) => {
+                    let other_way = match quoted_in(active_quote) {
+                        "sell" => "receive",
+                        _ => "sell",
+                    };
+                    metrics::increment(
+                        "sideswap_quote_comparisons_total",
+                        &[("quoted_in", other_way)],
+                    );
+                    (best, true)
+                }
+            }
+        };
 
-                log::debug!("Quote ID: {}", quote.quote_sub_id);
-                Ok(quote.quote_sub_id)
+        if let Some(quote) = &quoted {
+            if let Err(e) = self.treasury_repository.record_quote(quote).await {
+                log::error!("Could not record quote {}: {}", quote.quote_id, e);
             }
-            None => {
-                log::error!("Market not found for sell_asset={sell_asset}, receive_asset={receive_asset}, amount={amount}");
-                Err(ServiceError::Repository(
-                    "Sideswap".to_string(),
-                    "Market not found".to_string(),
-                ))
+        }
+
+        self.client.stop_quotes().await;
+        self.active_quotes
+            .lock()
+            .await
+            .remove(&active_quote.quote_sub_id);
+        self.superseded
+            .lock()
+            .await
+            .insert(active_quote.quote_sub_id, swap_id);
+
+        let next_quote = match self.client.start_quotes(next.clone()).await {
+            Ok(next_quote) => next_quote,
+            Err(e) => {
+                log::error!("Could not quote swap {} again: {:#}", swap_id, e);
+                probes.remove(&swap_id);
+                return true;
             }
+        };
+
+        let sell_is_base = active_quote.sell_is_base;
+        let amount_is_received = (next.asset_type == AssetType::Base) != sell_is_base;
+        let next_active = ActiveQuote {
+            quote_sub_id: next_quote.quote_sub_id,
+            swap_id: Some(swap_id),
+            amount: next.amount,
+            amount_is_received,
+            ..active_quote.clone()
+        };
+        log::info!(
+            "Quoting swap {} with the amount in the {} asset",
+            swap_id,
+            quoted_in(&next_active)
+        );
+        if deciding {
+            probes.remove(&swap_id);
+        } else {
+            probe.current = next;
+        }
+        self.active_quotes
+            .lock()
+            .await
+            .insert(next_quote.quote_sub_id, next_active);
+
+        true
+    }
+
+    /// Quote that will be accepted, for the comparison with the other way.
+    async fn record_chosen_quote(&self, active_quote: &ActiveQuote, status: &QuoteStatus) {
+        let QuoteStatus::Success {
+            quote_id,
+            base_amount,
+            quote_amount,
+            server_fee,
+            fixed_fee,
+            ..
+        } = status
+        else {
+            return;
+        };
+
+        let (sold, received) = active_quote.sold_and_received(*base_amount, *quote_amount);
+        let quote = SwapQuote {
+            swap_id: active_quote.swap_id(),
+            quote_sub_id: active_quote.quote_sub_id,
+            quote_id: *quote_id as i64,
+            sell_asset: active_quote.sell_asset.clone(),
+            receive_asset: active_quote.receive_asset.clone(),
+            quoted_in: quoted_in(active_quote),
+            requested_amount: active_quote.amount,
+            sold_amount: sold as i64,
+            received_amount: received as i64,
+            server_fee: *server_fee as i64,
+            fixed_fee: *fixed_fee as i64,
+            price: match sold {
+                0 => 0.0,
+                sold => received as f64 / sold as f64,
+            },
+            chosen: true,
+        };
+        if let Err(e) = self.treasury_repository.record_quote(&quote).await {
+            log::error!("Could not record quote {}: {}", quote.quote_id, e);
         }
     }
 
-    async fn proceed_with_quote(&self, quote: QuoteStatus) {
+    /// Cached Sideswap market trading the two assets, in either direction.
+    async fn find_market(&self, sell_asset: &str, receive_asset: &str) -> Option<Market> {
+        self.markets
+            .read()
+            .await
+            .iter()
+            .find(|market| {
+                (market.asset_pair.base == sell_asset && market.asset_pair.quote == receive_asset)
+                    || (market.asset_pair.base == receive_asset
+                        && market.asset_pair.quote == sell_asset)
+            })
+            .cloned()
+    }
+
+    /// Returns the swap txid once the quote was accepted and signed.
+    async fn proceed_with_quote(
+        &self,
+        quote: QuoteStatus,
+        active_quote: Option<&ActiveQuote>,
+    ) -> Option<String> {
         log::debug!("Proceeding with quote: {:?}", quote);
 
         match quote {
@@ -239,10 +813,12 @@ impl SideswapRequestHandler {
                     "
                 );
                 self.client.stop_quotes().await;
+                None
             }
             QuoteStatus::Error { error_msg } => {
                 log::warn!("Sideswap error: {error_msg}");
                 self.client.stop_quotes().await;
+                None
             }
             QuoteStatus::Success {
                 quote_id,
@@ -253,16 +829,31 @@ impl SideswapRequestHandler {
                 ttl,
             } => {
                 log::info!("Received quote: id={quote_id}, base_amount={base_amount}, quote_amount={quote_amount}, server_fee={server_fee}, fixed_fee={fixed_fee}, ttl={ttl}");
+                let Some(active_quote) = active_quote else {
+                    // Nothing to check its PSET against
+                    log::error!("Not accepting quote {quote_id} of an unknown subscription");
+                    self.client.stop_quotes().await;
+                    return None;
+                };
+                let (sold, received) = active_quote.sold_and_received(base_amount, quote_amount);
                 let txid = self
-                    .finish_swap(quote_id, base_amount, quote_amount, fixed_fee, ttl)
+                    .finish_swap(
+                        quote_id,
+                        active_quote,
+                        sold,
+                        received,
+                        server_fee + fixed_fee,
+                    )
                     .await;
 
                 match txid {
                     Ok(txid) => {
                         log::info!("Swap completed successfully: txid={txid}");
+                        Some(txid)
                     }
                     Err(err) => {
                         log::error!("Failed to complete swap: {}", err);
+                        None
                     }
                 }
             }
@@ -272,19 +863,21 @@ impl SideswapRequestHandler {
     async fn finish_swap(
         &self,
         quote_id: u64,
-        base_amount: u64,
-        quote_amount: u64,
-        fixed_fee: u64,
-        ttl: u64,
+        active_quote: &ActiveQuote,
+        sold: u64,
+        received: u64,
+        fees: u64,
     ) -> Result<String, ServiceError> {
+        if self.dry_run {
+            log::info!("Dry run, not accepting quote {quote_id}");
+            self.client.stop_quotes().await;
+            return Err(ServiceError::Internal("DryRun".to_string()));
+        }
+
         let (liquid_tx, liquid_rx) = oneshot::channel();
         let quote_pset = self.client.get_quote_pset(quote_id).await.map_err(|e| {
             log::error!("Failed to get quote pset: {}", e);
-            ServiceError::ExternalService(
-                "Sideswap".to_string(),
-                "wss://api.sideswap.io/".to_string(),
-                e.to_string(),
-            )
+            sideswap_error(e)
         })?;
 
         let pset: PartiallySignedTransaction =
@@ -293,6 +886,23 @@ impl SideswapRequestHandler {
                 ServiceError::Repository("Sideswap".to_string(), e.to_string())
             })?;
 
+        let market = self
+            .find_market(&active_quote.sell_asset, &active_quote.receive_asset)
+            .await
+            .ok_or_else(|| ServiceError::Internal("MarketNotFound".to_string()))?;
+        let terms = pset::SwapTerms {
+            sell_asset: active_quote.sell_asset.clone(),
+            receive_asset: active_quote.receive_asset.clone(),
+            sold,
+            received,
+            fee_asset: match market.fee_asset.as_str() {
+                "Base" => market.asset_pair.base,
+                _ => market.asset_pair.quote,
+            },
+            fees,
+        };
+        self.check_pset(quote_id, &pset, &terms).await?;
+
         self.liquid_channel
             .send(LiquidRequest::SignWithExtraDetails {
                 pset,
@@ -315,17 +925,83 @@ impl SideswapRequestHandler {
             .await
             .map_err(|e| {
                 log::error!("Failed to sign quote: {}", e);
-                ServiceError::ExternalService(
-                    "Sideswap".to_string(),
-                    "wss://api.sideswap.io/".to_string(),
-                    e.to_string(),
-                )
+                sideswap_error(e)
             })?;
 
         self.client.stop_quotes().await;
 
         Ok(txid.txid)
     }
+
+    /// Refuses, and records, a PSET that does not do to the wallet what the
+    /// quote said.
+    async fn check_pset(
+        &self,
+        quote_id: u64,
+        pset: &PartiallySignedTransaction,
+        terms: &pset::SwapTerms,
+    ) -> Result<(), ServiceError> {
+        let (liquid_tx, liquid_rx) = oneshot::channel();
+        self.liquid_channel
+            .send(LiquidRequest::GetPsetBalance {
+                pset: pset.clone(),
+                response: liquid_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Sideswap => Liquid".to_string(), e.to_string())
+            })?;
+        let balance = liquid_rx.await.map_err(|e| {
+            ServiceError::Communication("Sideswap => Liquid".to_string(), e.to_string())
+        })??;
+
+        let Err(reason) = pset::check_swap(&balance, terms, self.pset_tolerance_bps) else {
+            return Ok(());
+        };
+
+        log::error!(
+            "Refusing to sign the PSET of quote {}: {}",
+            quote_id,
+            reason
+        );
+        metrics::increment("rejected_psets_total", &[("kind", "swap")]);
+        self.client.stop_quotes().await;
+        if let Err(e) = self
+            .pset_repository
+            .insert_rejected(
+                "swap",
+                &quote_id.to_string(),
+                &pset.to_string(),
+                &reason,
+                &balance,
+            )
+            .await
+        {
+            log::error!("Could not record the rejected PSET of quote {}: {}", quote_id, e);
+        }
+
+        Err(ServiceError::Internal("PsetMismatch".to_string()))
+    }
+}
+
+fn quoted_in(active_quote: &ActiveQuote) -> &'static str {
+    match active_quote.amount_is_received {
+        true => "receive",
+        false => "sell",
+    }
+}
+
+/// Errors Sideswap answered with keep their code; anything else is a failed
+/// call.
+fn sideswap_error(e: anyhow::Error) -> ServiceError {
+    match e.downcast::<SideswapError>() {
+        Ok(error) => ServiceError::Sideswap(error),
+        Err(e) => ServiceError::ExternalService(
+            "Sideswap".to_string(),
+            "wss://api.sideswap.io/".to_string(),
+            format!("{:#}", e),
+        ),
+    }
 }
 
 #[async_trait]
@@ -345,19 +1021,166 @@ impl RequestHandler<SideswapRequest> for SideswapRequestHandler {
                 quote_sub_id,
                 status,
             } => {
-                self.proceed_with_quote(status).await;
+                if self.superseded.lock().await.contains_key(&quote_sub_id) {
+                    log::debug!("Ignoring quote of stopped subscription {}", quote_sub_id);
+                    return;
+                }
+
+                let active_quote = self.active_quotes.lock().await.get(&quote_sub_id).cloned();
+                if let Some(active_quote) = &active_quote {
+                    if self.probe_quote(active_quote, &status).await {
+                        return;
+                    }
+                    self.record_chosen_quote(active_quote, &status).await;
+                }
+                // Known outside this service by the id it started with
+                let swap_id = active_quote
+                    .as_ref()
+                    .map_or(quote_sub_id, |active_quote| active_quote.swap_id());
+
+                let fill = match (&status, &active_quote) {
+                    (
+                        QuoteStatus::Success {
+                            quote_id,
+                            base_amount,
+                            quote_amount,
+                            server_fee,
+                            fixed_fee,
+                            ..
+                        },
+                        Some(active_quote),
+                    ) => {
+                        let (sold_amount, received_amount) =
+                            active_quote.sold_and_received(*base_amount, *quote_amount);
+                        Some(SwapExecution {
+                            quote_sub_id: swap_id,
+                            quote_id: *quote_id as i64,
+                            txid: String::new(),
+                            sell_asset: active_quote.sell_asset.clone(),
+                            receive_asset: active_quote.receive_asset.clone(),
+                            sold_amount: sold_amount as i64,
+                            received_amount: received_amount as i64,
+                            fee_asset: None,
+                            server_fee: *server_fee as i64,
+                            fixed_fee: *fixed_fee as i64,
+                            sell_price_in_cents: None,
+                            receive_price_in_cents: None,
+                            fee_price_in_cents: None,
+                        })
+                    }
+                    _ => None,
+                };
+
+                let txid = self.proceed_with_quote(status, active_quote.as_ref()).await;
+                self.active_quotes.lock().await.remove(&quote_sub_id);
+                self.superseded
+                    .lock()
+                    .await
+                    .retain(|_, superseded_swap| *superseded_swap != swap_id);
+
+                if let Some(txid) = txid {
+                    if let Err(e) = self
+                        .transaction_channel
+                        .send(TransactionServiceRequest::SwapCompleted {
+                            quote_sub_id: swap_id,
+                            txid: txid.clone(),
+                        })
+                        .await
+                    {
+                        log::error!("Failed to notify transaction service of swap: {:?}", e);
+                    }
+
+                    if let Some(swap) = fill {
+                        if let Err(e) = self
+                            .hedging_channel
+                            .send(HedgingRequest::SwapFilled {
+                                quote_sub_id: swap_id,
+                                sold_amount: swap.sold_amount as u64,
+                                received_amount: swap.received_amount as u64,
+                            })
+                            .await
+                        {
+                            log::error!("Failed to notify hedging service of swap: {:?}", e);
+                        }
+
+                        self.record_swap(SwapExecution { txid, ..swap }).await;
+                    }
+                }
+            }
+            SideswapRequest::GetActiveQuotes { response } => {
+                let quotes = self.active_quotes.lock().await.values().cloned().collect();
+                let _ = response.send(quotes);
+            }
+            SideswapRequest::MarketPrice { asset_pair, price } => {
+                self.record_market_price(&asset_pair, price).await;
+            }
+            SideswapRequest::Estimate {
+                sell_asset,
+                receive_asset,
+                amount,
+                response,
+            } => {
+                let _ = response.send(self.estimate(&sell_asset, &receive_asset, amount).await);
+            }
+            SideswapRequest::GetTreasuryReport { days, response } => {
+                let _ = response.send(self.treasury_report(days).await);
+            }
+            SideswapRequest::ServerStatus { status } => {
+                self.record_server_status(status).await;
+            }
+            SideswapRequest::GetVenueStatus { response } => {
+                let _ = response.send(self.venue_status().await);
             }
         }
     }
 }
 
-pub struct SideswapService {}
+/// Feeds a captured Sideswap notification through the same path as a live
+/// one.
+pub async fn replay_notification(
+    notification: serde_json::Value,
+    channel: &mpsc::Sender<SideswapRequest>,
+) -> Result<(), anyhow::Error> {
+    client::process_notification(notification, channel).await
+}
+
+pub struct SideswapService {
+    health: ServiceHealth,
+}
 
 impl SideswapService {
-    pub fn new() -> Self {
-        SideswapService {}
+    pub fn new(health: ServiceHealth) -> Self {
+        SideswapService { health }
     }
 }
 
 #[async_trait]
-impl Service<SideswapRequest, SideswapRequestHandler> for SideswapService {}
+impl Service<SideswapRequest, SideswapRequestHandler> for SideswapService {
+    /// Stops once the websocket is lost, so the supervisor connects again
+    /// instead of leaving swaps to fail on a dead connection.
+    async fn run(
+        &mut self,
+        handler: SideswapRequestHandler,
+        receiver: &mut mpsc::Receiver<SideswapRequest>,
+    ) {
+        let mut connection = handler.client.connection();
+        self.health.set_connected("sideswap", true);
+
+        tokio::select! {
+            _ = async {
+                while let Some(request) = receiver.recv().await {
+                    let handler = handler.clone();
+
+                    tokio::spawn(async move {
+                        handler.handle_request(request).await;
+                    });
+                }
+            } => {}
+            _ = connection.wait_for(|connected| !connected) => {
+                log::warn!("Lost the Sideswap connection, reconnecting");
+            }
+        }
+
+        self.health.set_connected("sideswap", false);
+    }
+}
@@ -1,105 +1,113 @@
 use super::SideswapRequest;
-use crate::models::sideswap::ListMarkets;
-use crate::utils::json_rpc::JsonRpcClient;
 use crate::models::sideswap;
-
-use anyhow::anyhow;
-use serde_json::json;
+use crate::models::webhooks::RawWebhook;
+use crate::repositories::webhooks::WebhookRepository;
+use crate::utils::json_rpc::{self, JsonRpcClient, Notifications};
+use crate::utils::latency::{self, Dependency};
+
+use anyhow::{anyhow, Context};
+use mooze_sideswap::{ApiVersion, Notification, Request};
+use serde::{de::DeserializeOwned, Serialize};
 use std::sync::Arc;
-use tokio::sync::mpsc;
-
-macro_rules! call_sideswap_api {
-    ($self:expr, $method:expr, $params:expr, $result_key:expr, $return_type:ty) => {{
-        let response = $self
-            .client
-            .call_method($method, Some($params))
-            .await
-            .map_err(|e| anyhow!(concat!("Failed to call", stringify!($method), ": {}"), e))?;
-
-        let result = response.get("result").unwrap();
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
 
-        match result.get($result_key) {
-            Some(r) => {
-                let data: $return_type = serde_json::from_value(r.clone()).map_err(|e| {
-                    anyhow!(
-                        concat!("Failed to deserialize", stringify!($return_type), ": {}"),
-                        e
-                    )
-                })?;
-                Ok(data)
-            }
-            None => Err(anyhow!("Missing result key: {}", $result_key)),
-        }
-    }};
-}
+pub use mooze_sideswap::SideswapError;
 
 #[derive(Clone)]
 pub struct SideswapClient {
     client: Arc<JsonRpcClient>,
     api_key: String,
-    sideswap_channel: mpsc::Sender<SideswapRequest>,
+    /// Announced by the server at login.
+    api_version: ApiVersion,
 }
 
 impl SideswapClient {
     pub async fn new(
         url: &str,
         api_key: String,
+        call_timeout: Duration,
+        ping_interval: Duration,
         sideswap_channel: mpsc::Sender<SideswapRequest>,
-    ) -> Self {
-        let client = Arc::new(JsonRpcClient::new(url).await);
+        webhooks: Arc<WebhookRepository>,
+    ) -> Result<Self, anyhow::Error> {
+        let (client, notifications) = json_rpc::connect(url, call_timeout, ping_interval).await?;
+        listen_for_notifications(notifications, sideswap_channel, webhooks);
 
-        Self {
-            client,
+        Ok(Self {
+            client: Arc::new(client),
             api_key,
-            sideswap_channel,
-        }
+            api_version: ApiVersion::V1,
+        })
+    }
+
+    /// Changes to false once the websocket is lost.
+    pub fn connection(&self) -> watch::Receiver<bool> {
+        self.client.connection()
+    }
+
+    pub fn api_version(&self) -> ApiVersion {
+        self.api_version
     }
 
     pub async fn start(&mut self) -> Result<(), anyhow::Error> {
-        self.login().await?;
+        self.api_version = self.login().await?;
+        if self.api_version.is_supported() {
+            log::info!("Logged in to Sideswap, API {}", self.api_version);
+        } else {
+            // Unknown fields are ignored, so a newer API mostly still works
+            let supported: Vec<String> = ApiVersion::SUPPORTED
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            log::warn!(
+                "Sideswap speaks API {}, which is not supported (supported: {})",
+                self.api_version,
+                supported.join(", ")
+            );
+        }
         self.get_markets().await?;
 
         Ok(())
     }
 
-    async fn login(&self) -> Result<(), anyhow::Error> {
-        let params = json!({
-            "api_key": self.api_key,
-            "user-agent": "mooze-dealer",
-            "version": "0.1.0"
-        });
+    async fn login(&self) -> Result<ApiVersion, anyhow::Error> {
+        let login = Request::Login {
+            api_key: self.api_key.clone(),
+            user_agent: "mooze-dealer".to_string(),
+            version: "0.1.0".to_string(),
+        };
 
-        self.client.call_method("login", Some(params)).await?;
-        Ok(())
-    }
+        let response = self.send(&login).await?;
+        mooze_sideswap::check_response(&response).context("Failed to log in to Sideswap")?;
 
-    pub async fn start_notification_listener(&self) {
-        let client = self.client.clone();
-        let tx = self.sideswap_channel.clone();
+        Ok(ApiVersion::from_login(&response))
+    }
 
-        tokio::spawn(async move {
-            loop {
-                let notification = client.wait_for_notification().await;
-                log::debug!("Received notification: {:?}", notification);
+    async fn send(&self, request: &Request) -> Result<serde_json::Value, anyhow::Error> {
+        latency::track(
+            Dependency::Sideswap,
+            self.client
+                .call_method(request.method(), Some(request.params())),
+        )
+        .await
+        .with_context(|| format!("Failed to call {}", request.name()))
+    }
 
-                if let Err(e) = process_notification(notification, &tx).await {
-                    log::error!("Error handling notification: {}", e);
-                }
-            }
-        });
+    async fn call<T: DeserializeOwned + Serialize>(
+        &self,
+        request: Request,
+    ) -> Result<T, anyhow::Error> {
+        let response = self.send(&request).await?;
+        request.parse(&response)
     }
 
-    pub async fn get_markets(&self) -> Result<ListMarkets, anyhow::Error> {
+    pub async fn get_markets(&self) -> Result<sideswap::ListMarkets, anyhow::Error> {
         log::debug!("Requesting markets from Sideswap");
-        let result = call_sideswap_api!(
-            self,
-            "market",
-            json!({"list_markets": {}}),
-            "list_markets",
-            sideswap::ListMarkets
-        );
-
-        match result {
+        match self
+            .call::<sideswap::ListMarkets>(Request::ListMarkets)
+            .await
+        {
             Ok(markets) => {
                 log::debug!("Successfully retrieved {} markets", markets.markets.len());
                 Ok(markets)
@@ -111,64 +119,45 @@ impl SideswapClient {
         }
     }
 
+    /// Subscribes to the chart of a market. Returns the latest close, updates
+    /// arrive as `chart_update` notifications.
+    pub async fn subscribe_chart(
+        &self,
+        asset_pair: &sideswap::AssetPair,
+    ) -> Result<Option<f64>, anyhow::Error> {
+        let chart: sideswap::ChartSub = self
+            .call(Request::ChartSub {
+                asset_pair: asset_pair.clone(),
+            })
+            .await?;
+
+        Ok(chart.data.last().map(|point| point.close))
+    }
+
     pub async fn start_quotes(
         &self,
         quote_request: sideswap::QuoteRequest,
     ) -> Result<sideswap::StartQuotes, anyhow::Error> {
         log::debug!("Starting quotes with request: {:?}", quote_request);
-        
-        let request_json = json!({
-            "id": 1,
-            "method": "market",
-            "params": {
-                "start_quotes": quote_request
-            }
-        });
-        
-        log::info!("Sending request to Sideswap: {}", serde_json::to_string_pretty(&request_json).unwrap());
-        
-        let result = call_sideswap_api!(
-            self,
-            "market",
-            json!({
-                "start_quotes": quote_request
-            }),
-            "start_quotes",
-            sideswap::StartQuotes
-        );
 
-        match result {
+        match self.call(Request::StartQuotes(quote_request)).await {
             Ok(start_quotes) => {
                 log::debug!("Successfully started quotes: {:?}", start_quotes);
                 Ok(start_quotes)
             }
             Err(e) => {
                 log::error!("Failed to start quotes: {}", e);
-                Err(anyhow!("Failed to start quotes: {}", e))
+                Err(e)
             }
         }
     }
 
     pub async fn stop_quotes(&self) {
-        let _ = self
-            .client
-            .call_method("market", Some(json!({"stop_quotes": {}})))
-            .await;
+        let _ = self.send(&Request::StopQuotes).await;
     }
 
     pub async fn get_quote_pset(&self, quote_id: u64) -> Result<sideswap::Quote, anyhow::Error> {
-        let result: Result<sideswap::Quote, anyhow::Error> = call_sideswap_api!(
-            self,
-            "market",
-            json!({"get_quote": {"quote_id": quote_id}}),
-            "get_quote",
-            sideswap::Quote
-        );
-
-        match result {
-            Ok(quote) => Ok(quote),
-            Err(e) => Err(anyhow!("Failed to get quote: {}", e)),
-        }
+        self.call(Request::GetQuote { quote_id }).await
     }
 
     pub async fn sign_quote(
@@ -176,124 +165,75 @@ impl SideswapClient {
         quote_id: u64,
         pset: String,
     ) -> Result<sideswap::TakerSign, anyhow::Error> {
-        let result = call_sideswap_api!(
-            self,
-            "market",
-            json!({
-                "taker_sign": {
-                    "quote_id": quote_id,
-                    "pset": pset
+        self.call(Request::TakerSign { quote_id, pset }).await
+    }
+}
+
+/// Notifications are stored in `webhook_events` as received, so they can be
+/// replayed later.
+fn listen_for_notifications(
+    mut notifications: Notifications,
+    tx: mpsc::Sender<SideswapRequest>,
+    webhooks: Arc<WebhookRepository>,
+) {
+    tokio::spawn(async move {
+        while let Some(notification) = notifications.next().await {
+            log::debug!("Received notification: {:?}", notification);
+
+            let raw = RawWebhook {
+                payload: notification.to_string(),
+                headers: "{}".to_string(),
+            };
+            let event_id = match webhooks.insert_event("sideswap", &raw, &[], &[]).await {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    log::error!("Could not store Sideswap notification: {}", e);
+                    None
+                }
+            };
+
+            let outcome = match process_notification(notification, &tx).await {
+                Ok(()) => "processed".to_string(),
+                Err(e) => {
+                    log::error!("Error handling notification: {}", e);
+                    format!("failed: {}", e)
                 }
-            }),
-            "taker_sign",
-            sideswap::TakerSign
-        );
+            };
 
-        match result {
-            Ok(taker_sign) => Ok(taker_sign),
-            Err(e) => Err(anyhow!("Failed to sign quote: {}", e)),
+            if let Some(event_id) = event_id {
+                if let Err(e) = webhooks.record_outcome(&event_id, &outcome).await {
+                    log::error!(
+                        "Could not record outcome of notification {}: {}",
+                        event_id,
+                        e
+                    );
+                }
+            }
         }
-    }
+    });
 }
 
-// Static function to process notifications without requiring &self
-async fn process_notification(
+/// Hands what a notification tells to the Sideswap service.
+pub(super) async fn process_notification(
     notification: serde_json::Value,
     tx: &mpsc::Sender<SideswapRequest>,
 ) -> Result<(), anyhow::Error> {
-    match notification.get("method") {
-        Some(method) => match method.as_str() {
-            Some("market") => {
-                log::debug!("Received market notification: {:?}", notification);
-                process_market_notification(&notification["params"], tx).await?;
-                Ok(())
+    for notification in mooze_sideswap::parse_notification(&notification)? {
+        let request = match notification {
+            Notification::Quote {
+                quote_sub_id,
+                status,
+            } => SideswapRequest::Quote {
+                quote_sub_id,
+                status,
+            },
+            Notification::MarketPrice { asset_pair, price } => {
+                SideswapRequest::MarketPrice { asset_pair, price }
             }
-            _ => {
-                log::warn!("Received unknown notification type: {}", method);
-                Ok(())
-            }
-        },
-        None => {
-            log::warn!("Received notification without method.");
-            Ok(())
-        }
+            Notification::ServerStatus(status) => SideswapRequest::ServerStatus { status },
+        };
+        tx.send(request).await?;
     }
-}
 
-async fn process_market_notification(
-    params: &serde_json::Value,
-    tx: &mpsc::Sender<SideswapRequest>,
-) -> Result<(), anyhow::Error> {
-    if let Some(quote) = params.get("quote") {
-        process_quote(quote, tx).await?;
-    }
     Ok(())
 }
-
-async fn process_quote(
-    quote: &serde_json::Value,
-    tx: &mpsc::Sender<SideswapRequest>,
-) -> Result<(), anyhow::Error> {
-    let quote_sub_id = quote["quote_sub_id"].as_i64().unwrap_or(0);
-    log::debug!("Received quote: {:?}", quote);
-
-    match quote.get("status") {
-        Some(status) => {
-            if let Some(low_balance) = status.get("LowBalance") {
-                let quote = sideswap::QuoteStatus::LowBalance {
-                    base_amount: low_balance["base_amount"].as_u64().unwrap_or(0),
-                    quote_amount: low_balance["quote_amount"].as_u64().unwrap_or(0),
-                    server_fee: low_balance["server_fee"].as_u64().unwrap_or(0),
-                    fixed_fee: low_balance["fixed_fee"].as_u64().unwrap_or(0),
-                    available: low_balance["available"].as_u64().unwrap_or(0),
-                };
-
-                tx.send(SideswapRequest::Quote {
-                    quote_sub_id,
-                    status: quote,
-                })
-                .await?;
-            }
-
-            if let Some(error) = status.get("Error") {
-                let quote = sideswap::QuoteStatus::Error {
-                    error_msg: error["error_msg"]
-                        .as_str()
-                        .unwrap_or("Unknown error")
-                        .to_owned(),
-                };
-
-                tx.send(SideswapRequest::Quote {
-                    quote_sub_id,
-                    status: quote,
-                })
-                .await?;
-            }
-
-            if let Some(success) = status.get("Success") {
-                let quote = sideswap::QuoteStatus::Success {
-                    quote_id: success["quote_id"].as_u64().unwrap_or(0),
-                    base_amount: success["base_amount"].as_u64().unwrap_or(0),
-                    quote_amount: success["quote_amount"].as_u64().unwrap_or(0),
-                    server_fee: success["server_fee"].as_u64().unwrap_or(0),
-                    fixed_fee: success["fixed_fee"].as_u64().unwrap_or(0),
-                    ttl: success["ttl"].as_u64().unwrap_or(0),
-                };
-
-                log::debug!("Got successful quote.");
-
-                let _ = tx.send(SideswapRequest::Quote {
-                    quote_sub_id,
-                    status: quote,
-                })
-                .await?;
-            }
-
-            Ok(())
-        }
-        None => {
-            log::warn!("Received quote without status.");
-            Ok(())
-        }
-    }
-}
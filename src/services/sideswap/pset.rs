@@ -0,0 +1,141 @@
+//! Checks of the PSET Sideswap sends for an accepted quote, before it is
+//! signed: the wallet must receive what was quoted and pay no more than was
+//! quoted, fees included.
+
+use crate::models::wallet::PsetBalance;
+
+/// What the wallet agreed to by accepting a quote.
+#[derive(Clone, Debug)]
+pub struct SwapTerms {
+    pub sell_asset: String,
+    pub receive_asset: String,
+    pub sold: u64,
+    pub received: u64,
+    /// Asset the server and fixed fees are paid in.
+    pub fee_asset: String,
+    pub fees: u64,
+}
+
+/// Whether `balance` honours `terms`, allowing amounts to differ by
+/// `tolerance_bps` of what was quoted. The fees may be taken from either
+/// side of the fee asset, so they are allowed off the received amount or on
+/// top of the sold one.
+pub fn check_swap(
+    balance: &PsetBalance,
+    terms: &SwapTerms,
+    tolerance_bps: u64,
+) -> Result<(), String> {
+    let tolerance = |amount: u64| (amount as u128 * tolerance_bps as u128 / 10_000) as u64;
+    let fees_in = |asset: &str| match asset == terms.fee_asset {
+        true => terms.fees,
+        false => 0,
+    };
+    let change = |asset: &str| balance.balances.get(asset).copied().unwrap_or(0);
+
+    let received = change(&terms.receive_asset);
+    let min_received = terms
+        .received
+        .saturating_sub(fees_in(&terms.receive_asset))
+        .saturating_sub(tolerance(terms.received));
+    if received < min_received as i64 {
+        return Err(format!(
+            "Receives {} of {}, quoted {}",
+            received, terms.receive_asset, terms.received
+        ));
+    }
+
+    let sold = -change(&terms.sell_asset);
+    let max_sold = terms.sold + fees_in(&terms.sell_asset) + tolerance(terms.sold);
+    if sold > max_sold as i64 {
+        return Err(format!(
+            "Pays {} of {}, quoted {}",
+            sold, terms.sell_asset, terms.sold
+        ));
+    }
+
+    let unexpected = balance.balances.iter().find(|(asset_id, amount)| {
+        **amount < 0 && **asset_id != terms.sell_asset && **asset_id != terms.receive_asset
+    });
+    if let Some((asset_id, amount)) = unexpected {
+        return Err(format!(
+            "Pays {} of {}, which is not sold",
+            -amount, asset_id
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms() -> SwapTerms {
+        SwapTerms {
+            sell_asset: "depix".to_string(),
+            receive_asset: "lbtc".to_string(),
+            sold: 1_000_000,
+            received: 20_000,
+            fee_asset: "depix".to_string(),
+            fees: 2_000,
+        }
+    }
+
+    fn balance(changes: &[(&str, i64)]) -> PsetBalance {
+        PsetBalance {
+            balances: changes
+                .iter()
+                .map(|(asset_id, amount)| (asset_id.to_string(), *amount))
+                .collect(),
+            fee: 30,
+            recipients: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn accepts_the_quoted_swap() {
+        let pset = balance(&[("depix", -1_002_000), ("lbtc", 20_000)]);
+        assert_eq!(check_swap(&pset, &terms(), 10), Ok(()));
+
+        // Fees taken from the received asset instead
+        let terms = SwapTerms {
+            fee_asset: "lbtc".to_string(),
+            fees: 40,
+            ..terms()
+        };
+        let pset = balance(&[("depix", -1_000_000), ("lbtc", 19_960)]);
+        assert_eq!(check_swap(&pset, &terms, 0), Ok(()));
+    }
+
+    #[test]
+    fn allows_the_tolerance_only() {
+        // 10 bps of 20_000 is 20
+        let pset = balance(&[("depix", -1_000_000), ("lbtc", 19_980)]);
+        assert_eq!(check_swap(&pset, &terms(), 10), Ok(()));
+
+        let pset = balance(&[("depix", -1_000_000), ("lbtc", 19_979)]);
+        assert!(check_swap(&pset, &terms(), 10).is_err());
+    }
+
+    #[test]
+    fn refuses_paying_more_than_quoted() {
+        let pset = balance(&[("depix", -1_002_001), ("lbtc", 20_000)]);
+        assert!(check_swap(&pset, &terms(), 0).is_err());
+    }
+
+    #[test]
+    fn refuses_spending_other_assets() {
+        let pset = balance(&[("depix", -1_000_000), ("lbtc", 20_000), ("usdt", -1)]);
+        assert!(check_swap(&pset, &terms(), 10).is_err());
+
+        // Receiving them is harmless
+        let pset = balance(&[("depix", -1_000_000), ("lbtc", 20_000), ("usdt", 5)]);
+        assert_eq!(check_swap(&pset, &terms(), 10), Ok(()));
+    }
+
+    #[test]
+    fn refuses_a_pset_receiving_nothing() {
+        let pset = balance(&[("depix", -1_000_000)]);
+        assert!(check_swap(&pset, &terms(), 10).is_err());
+    }
+}
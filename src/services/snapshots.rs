@@ -0,0 +1,240 @@
+use super::{
+    liquid::LiquidRequest, sideswap::SideswapRequest, transactions::TransactionServiceRequest,
+    RequestHandler, Service, ServiceError,
+};
+use crate::models::snapshots::{DealerSnapshot, SnapshotSummary, UtxoSnapshot};
+use crate::repositories::snapshots::SnapshotRepository;
+
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+pub enum SnapshotRequest {
+    CreateSnapshot {
+        response: oneshot::Sender<Result<SnapshotSummary, ServiceError>>,
+    },
+    RestoreSnapshot {
+        name: String,
+        response: oneshot::Sender<Result<usize, ServiceError>>,
+    },
+}
+
+#[derive(Clone)]
+pub struct SnapshotRequestHandler {
+    repository: Arc<SnapshotRepository>,
+    transaction_channel: mpsc::Sender<TransactionServiceRequest>,
+    liquid_channel: mpsc::Sender<LiquidRequest>,
+    sideswap_channel: mpsc::Sender<SideswapRequest>,
+}
+
+impl SnapshotRequestHandler {
+    pub fn new(
+        storage_url: String,
+        storage_token: Option<String>,
+        encryption_key: String,
+        transaction_channel: mpsc::Sender<TransactionServiceRequest>,
+        liquid_channel: mpsc::Sender<LiquidRequest>,
+        sideswap_channel: mpsc::Sender<SideswapRequest>,
+    ) -> Self {
+        let repository = Arc::new(SnapshotRepository::new(
+            storage_url,
+            storage_token,
+            encryption_key,
+        ));
+
+        Self {
+            repository,
+            transaction_channel,
+            liquid_channel,
+            sideswap_channel,
+        }
+    }
+
+    async fn collect_snapshot(&self) -> Result<DealerSnapshot, ServiceError> {
+        let (pending_tx, pending_rx) = oneshot::channel();
+        self.transaction_channel
+            .send(TransactionServiceRequest::GetPendingTransactions {
+                response: pending_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Snapshot => Transaction".to_string(), e.to_string())
+            })?;
+        let pending_transactions = pending_rx.await.map_err(|e| {
+            ServiceError::Communication("Snapshot => Transaction".to_string(), e.to_string())
+        })??;
+
+        let (unreconciled_tx, unreconciled_rx) = oneshot::channel();
+        self.transaction_channel
+            .send(TransactionServiceRequest::GetUnreconciledTransactions {
+                response: unreconciled_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Snapshot => Transaction".to_string(), e.to_string())
+            })?;
+        let unreconciled_transactions = unreconciled_rx.await.map_err(|e| {
+            ServiceError::Communication("Snapshot => Transaction".to_string(), e.to_string())
+        })??;
+
+        let (quotes_tx, quotes_rx) = oneshot::channel();
+        self.sideswap_channel
+            .send(SideswapRequest::GetActiveQuotes {
+                response: quotes_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Snapshot => Sideswap".to_string(), e.to_string())
+            })?;
+        let active_quotes = quotes_rx.await.map_err(|e| {
+            ServiceError::Communication("Snapshot => Sideswap".to_string(), e.to_string())
+        })?;
+
+        let (utxo_tx, utxo_rx) = oneshot::channel();
+        self.liquid_channel
+            .send(LiquidRequest::GetUtxos {
+                asset: None,
+                response: utxo_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Snapshot => Liquid".to_string(), e.to_string())
+            })?;
+        let utxos = utxo_rx
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Snapshot => Liquid".to_string(), e.to_string())
+            })??
+            .iter()
+            .map(|utxo| UtxoSnapshot {
+                txid: utxo.outpoint.txid.to_string(),
+                vout: utxo.outpoint.vout,
+                asset: utxo.unblinded.asset.to_string(),
+                value: utxo.unblinded.value,
+            })
+            .collect();
+
+        let mut reserved_in_cents = BTreeMap::new();
+        for transaction in &unreconciled_transactions {
+            *reserved_in_cents
+                .entry(transaction.asset.clone())
+                .or_insert(0) += transaction.amount_in_cents as i64;
+        }
+
+        Ok(DealerSnapshot {
+            created_at: chrono::Utc::now(),
+            pending_transactions,
+            unreconciled_transactions,
+            active_quotes,
+            utxos,
+            reserved_in_cents,
+        })
+    }
+
+    async fn create_snapshot(&self) -> Result<SnapshotSummary, ServiceError> {
+        let snapshot = self.collect_snapshot().await?;
+        let name = format!("dealer-{}.snapshot", snapshot.created_at.format("%Y%m%dT%H%M%SZ"));
+
+        let (bundle, sealed) = self
+            .repository
+            .seal(snapshot)
+            .map_err(|e| ServiceError::Internal(e.to_string()))?;
+
+        self.repository.store(&name, sealed).await.map_err(|e| {
+            ServiceError::Repository("Snapshot".to_string(), e.to_string())
+        })?;
+
+        log::info!("Stored dealer snapshot {} ({})", name, bundle.checksum);
+
+        Ok(SnapshotSummary {
+            name,
+            checksum: bundle.checksum,
+            created_at: bundle.snapshot.created_at,
+            pending_transactions: bundle.snapshot.pending_transactions.len(),
+            unreconciled_transactions: bundle.snapshot.unreconciled_transactions.len(),
+            active_quotes: bundle.snapshot.active_quotes.len(),
+            utxos: bundle.snapshot.utxos.len(),
+        })
+    }
+
+    /// Verifies a stored snapshot and puts every transaction it recorded as
+    /// paid-but-undelivered back into the pending queue. Transactions that were
+    /// delivered in the meantime are skipped by the transaction service.
+    async fn restore_snapshot(&self, name: &str) -> Result<usize, ServiceError> {
+        let data = self.repository.load(name).await.map_err(|e| {
+            ServiceError::Repository("Snapshot".to_string(), e.to_string())
+        })?;
+        let bundle = self
+            .repository
+            .open(&data)
+            .map_err(|e| ServiceError::Internal(e.to_string()))?;
+
+        let mut transaction_ids: Vec<String> = bundle
+            .snapshot
+            .pending_transactions
+            .iter()
+            .map(|entry| entry.transaction_id.clone())
+            .collect();
+        for transaction in &bundle.snapshot.unreconciled_transactions {
+            if !transaction_ids.contains(&transaction.id) {
+                transaction_ids.push(transaction.id.clone());
+            }
+        }
+
+        let mut restored = 0;
+        for transaction_id in transaction_ids {
+            let (requeue_tx, requeue_rx) = oneshot::channel();
+            self.transaction_channel
+                .send(TransactionServiceRequest::RequeueTransaction {
+                    transaction_id: transaction_id.clone(),
+                    response: requeue_tx,
+                })
+                .await
+                .map_err(|e| {
+                    ServiceError::Communication(
+                        "Snapshot => Transaction".to_string(),
+                        e.to_string(),
+                    )
+                })?;
+
+            match requeue_rx.await {
+                Ok(Ok(true)) => restored += 1,
+                Ok(Ok(false)) => {}
+                Ok(Err(e)) => log::warn!("Could not requeue {}: {}", transaction_id, e),
+                Err(e) => log::warn!("Could not requeue {}: {}", transaction_id, e),
+            }
+        }
+
+        log::info!("Restored {} transactions from snapshot {}", restored, name);
+
+        Ok(restored)
+    }
+}
+
+#[async_trait]
+impl RequestHandler<SnapshotRequest> for SnapshotRequestHandler {
+    async fn handle_request(&self, request: SnapshotRequest) {
+        match request {
+            SnapshotRequest::CreateSnapshot { response } => {
+                let summary = self.create_snapshot().await;
+                let _ = response.send(summary);
+            }
+            SnapshotRequest::RestoreSnapshot { name, response } => {
+                let restored = self.restore_snapshot(&name).await;
+                let _ = response.send(restored);
+            }
+        }
+    }
+}
+
+pub struct SnapshotService;
+
+impl SnapshotService {
+    pub fn new() -> Self {
+        SnapshotService {}
+    }
+}
+
+#[async_trait]
+impl Service<SnapshotRequest, SnapshotRequestHandler> for SnapshotService {}
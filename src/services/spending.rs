@@ -0,0 +1,40 @@
+use super::jobs::{self, JobSchedule};
+use crate::repositories::spending::SpendingRepository;
+use crate::settings::Spending;
+use crate::utils::metrics;
+
+use sqlx::PgPool;
+use std::sync::Arc;
+
+/// Rebuilds the last `verify_days` of the daily spending projection every
+/// `interval_secs`, fixing any row that drifted from the transactions.
+pub fn start_verification(pool: PgPool, settings: Spending) {
+    let repository = Arc::new(SpendingRepository::new(pool));
+    jobs::register(
+        "spending_verification",
+        JobSchedule::every_secs(settings.interval_secs),
+        move || {
+            let repository = repository.clone();
+            let verify_days = settings.verify_days;
+            async move {
+                let since = match verify_days {
+                    0 => None,
+                    days => chrono::Utc::now()
+                        .date_naive()
+                        .checked_sub_days(chrono::Days::new(u64::from(days) - 1)),
+                };
+
+                let repaired = repository.rebuild_daily_spending(since).await?;
+                if repaired > 0 {
+                    log::warn!(
+                        "Repaired {} daily spending rows since {:?}",
+                        repaired,
+                        since
+                    );
+                    metrics::add("spending_projection_repairs_total", &[], repaired);
+                }
+                Ok(())
+            }
+        },
+    );
+}
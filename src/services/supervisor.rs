@@ -0,0 +1,247 @@
+use super::leader::Leadership;
+use crate::models::health::{HealthReport, ServiceState};
+use crate::utils::metrics;
+
+use dashmap::DashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::time::{Duration, Instant};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// The backoff resets once a service stayed up this long.
+const STABLE_AFTER: Duration = Duration::from_secs(300);
+
+struct Entry {
+    state: ServiceState,
+    affects: &'static [&'static str],
+}
+
+/// State of the supervised services, shared with the health endpoint.
+#[derive(Clone)]
+pub struct ServiceHealth {
+    services: Arc<DashMap<&'static str, Entry>>,
+    /// Whether the long-lived connections of the services are up.
+    connections: Arc<DashMap<&'static str, bool>>,
+    leadership: Leadership,
+}
+
+impl ServiceHealth {
+    pub fn new(leadership: Leadership) -> Self {
+        Self {
+            services: Arc::new(DashMap::new()),
+            connections: Arc::new(DashMap::new()),
+            leadership,
+        }
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.leadership.is_leader()
+    }
+
+    fn register(&self, name: &'static str, affects: &'static [&'static str]) {
+        self.services.insert(
+            name,
+            Entry {
+                state: ServiceState {
+                    status: "starting",
+                    restarts: 0,
+                    last_error: None,
+                    since: chrono::Utc::now(),
+                },
+                affects,
+            },
+        );
+    }
+
+    fn set_running(&self, name: &'static str) {
+        if let Some(mut entry) = self.services.get_mut(name) {
+            entry.state.status = "running";
+            entry.state.since = chrono::Utc::now();
+        }
+    }
+
+    /// Leader-only service waiting on a follower.
+    pub fn set_standby(&self, name: &'static str) {
+        if let Some(mut entry) = self.services.get_mut(name) {
+            entry.state.status = "standby";
+            entry.state.since = chrono::Utc::now();
+        }
+    }
+
+    pub fn set_connected(&self, name: &'static str, connected: bool) {
+        self.connections.insert(name, connected);
+        metrics::set_gauge(
+            "connection_up",
+            &[("connection", name)],
+            if connected { 1.0 } else { 0.0 },
+        );
+    }
+
+    /// Whether the connection reported itself down. One that never reported
+    /// isn't, as when its service is off.
+    pub fn is_disconnected(&self, name: &str) -> bool {
+        self.connections
+            .get(name)
+            .is_some_and(|connected| !*connected)
+    }
+
+    /// A service that was running crashed; one that never got ready keeps
+    /// its status.
+    fn set_down(&self, name: &'static str, error: String, crashed: bool) {
+        if let Some(mut entry) = self.services.get_mut(name) {
+            if crashed {
+                entry.state.status = "restarting";
+                entry.state.restarts += 1;
+                entry.state.since = chrono::Utc::now();
+            }
+            entry.state.last_error = Some(error);
+        }
+    }
+
+    /// Whether every service the functionality depends on is running.
+    pub fn is_available(&self, functionality: &str) -> bool {
+        self.services
+            .iter()
+            .all(|entry| entry.state.status == "running" || !entry.affects.contains(&functionality))
+    }
+
+    pub fn report(&self) -> HealthReport {
+        let mut services = BTreeMap::new();
+        let mut degraded = BTreeSet::new();
+
+        // Services on standby run on the leader, a follower is healthy
+        // without them
+        for entry in self.services.iter() {
+            if entry.state.status != "running" && entry.state.status != "standby" {
+                degraded.extend(entry.affects.iter().copied());
+            }
+            services.insert(*entry.key(), entry.state.clone());
+        }
+
+        let has_status = |status| services.values().any(|state| state.status == status);
+        let status = if has_status("restarting") {
+            "degraded"
+        } else if has_status("starting") {
+            "starting"
+        } else {
+            "ok"
+        };
+
+        let role = if self.is_leader() {
+            "leader"
+        } else {
+            "follower"
+        };
+
+        let connections = self
+            .connections
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect();
+
+        HealthReport {
+            status,
+            role,
+            ready: degraded.is_empty(),
+            services,
+            connections,
+            degraded: degraded.into_iter().collect(),
+        }
+    }
+}
+
+/// Handed to a supervised service, which marks itself ready once its
+/// dependencies answered.
+#[derive(Clone)]
+pub struct Readiness {
+    health: ServiceHealth,
+    name: &'static str,
+    ready: Arc<AtomicBool>,
+}
+
+impl Readiness {
+    pub fn set_ready(&self) {
+        self.ready.store(true, Ordering::SeqCst);
+        self.health.set_running(self.name);
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+}
+
+/// Runs a service, starting it again with exponential backoff when it fails
+/// to start, panics or returns. `start` builds the handler again every time;
+/// the service's receiver must outlive it, so the senders held by other
+/// services keep working and requests sent meanwhile wait in the channel.
+pub fn supervise<F, Fut>(
+    health: &ServiceHealth,
+    name: &'static str,
+    affects: &'static [&'static str],
+    start: F,
+) where
+    F: Fn(Readiness) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), anyhow::Error>> + Send + 'static,
+{
+    let health = health.clone();
+    health.register(name, affects);
+
+    tokio::spawn(async move {
+        let mut backoff = INITIAL_BACKOFF;
+
+        loop {
+            let readiness = Readiness {
+                health: health.clone(),
+                name,
+                ready: Arc::new(AtomicBool::new(false)),
+            };
+
+            let started_at = Instant::now();
+            let error = match tokio::spawn(start(readiness.clone())).await {
+                Ok(Ok(())) => "stopped".to_string(),
+                Ok(Err(e)) => e.to_string(),
+                Err(e) if e.is_panic() => {
+                    let panic = e.into_panic();
+                    match panic.downcast_ref::<&str>() {
+                        Some(message) => message.to_string(),
+                        None => panic
+                            .downcast_ref::<String>()
+                            .cloned()
+                            .unwrap_or_else(|| "panicked".to_string()),
+                    }
+                }
+                Err(e) => e.to_string(),
+            };
+
+            if started_at.elapsed() >= STABLE_AFTER {
+                backoff = INITIAL_BACKOFF;
+            }
+
+            let crashed = readiness.is_ready();
+            if crashed {
+                log::error!(
+                    "Service {} crashed ({}), restarting in {}s",
+                    name,
+                    error,
+                    backoff.as_secs()
+                );
+                metrics::increment("service_restarts_total", &[("service", name)]);
+            } else {
+                log::warn!(
+                    "Service {} could not start ({}), retrying in {}s",
+                    name,
+                    error,
+                    backoff.as_secs()
+                );
+                metrics::increment("service_start_failures_total", &[("service", name)]);
+            }
+            health.set_down(name, error, crashed);
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+}
@@ -1,24 +1,104 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 
+use super::analytics::AnalyticsRequest;
+use super::compliance::ComplianceRequest;
+use super::events::EventRequest;
+use super::hedging::HedgingRequest;
+use super::jobs::{self, JobSchedule};
 use super::liquid::LiquidRequest;
+use super::merchants::MerchantRequest;
+use super::notifications::NotificationRequest;
 use super::pix::PixServiceRequest;
 use super::price::PriceRequest;
-use super::sideswap::SideswapRequest;
 use super::users::UserRequest;
+use super::venues::SwapRouter;
+use crate::models::analytics::amount_tier;
+use crate::models::campaigns::{Campaign, NewCampaign};
+use crate::models::compliance::ScreeningAction;
+use crate::models::events::{DomainEvent, EventKind};
+use crate::models::operator::{
+    AssetAvailability, Cursor, DepositClosure, MaintenanceWindow, NewMaintenanceWindow, Page,
+    TransactionSearch,
+};
+use crate::models::payouts::PartialPayout;
 use crate::models::pix::Deposit;
+use crate::models::price::{AssetPrice, QuoteCurrency};
+use crate::models::quotes::PriceQuote;
+use crate::models::reviews::{HeldTransaction, ReviewDecision};
+use crate::models::risk::RiskBand;
+use crate::models::sagas::{PayoutStep, StepStatus};
+use crate::models::timeline::{TimelineEvent, TransactionTimeline};
 use crate::models::transactions;
-use crate::models::transactions::Assets;
+use crate::models::transactions::{ArchivedTransaction, Assets, DustPolicy};
+use crate::models::wallet::{BroadcastTransaction, FeePriority, PsetBalance};
+use crate::repositories::archive::ArchiveRepository;
+use crate::repositories::campaigns::CampaignRepository;
+use crate::repositories::deliveries::DeliveryRepository;
+use crate::repositories::disputes::DisputeRepository;
+use crate::repositories::merchants::MerchantRepository;
+use crate::repositories::operator::OperatorRepository;
+use crate::repositories::payouts::{PayoutClaim, PayoutRepository};
+use crate::repositories::psets::PsetRepository;
+use crate::repositories::quotes::QuoteRepository;
+use crate::repositories::referrals::ReferralRepository;
+use crate::repositories::reviews::ReviewRepository;
+use crate::repositories::sagas::SagaRepository;
+use crate::repositories::timeline::TimelineRepository;
 use crate::repositories::transactions::TransactionRepository;
+use crate::settings::{Disputes, Quotes};
+use crate::utils::clock::SharedClock;
+use crate::utils::liquid_uri::PaymentUri;
+use crate::utils::metrics;
+use crate::utils::money;
+use crate::utils::validation;
 use async_trait::async_trait;
 use lwk_wollet::elements::pset::PartiallySignedTransaction;
 use lwk_wollet::UnvalidatedRecipient;
+use serde_json::json;
 use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
 
 use super::RequestHandler;
 use super::Service;
 use super::ServiceError;
+use super::WorkerPool;
+
+/// Operator flag stopping new deposits and payouts.
+const KILL_SWITCH_FLAG: &str = "kill_switch";
+/// Prefix of the operator flags disabling an asset, followed by its id.
+const ASSET_DISABLED_FLAG: &str = "asset_disabled:";
+/// Job retrying the queued payouts, also run when payouts are resumed.
+const PENDING_PAYOUTS_JOB: &str = "pending_payouts";
+/// L-BTC left out of a partial L-BTC payout for its network fee, in sats.
+const PARTIAL_FEE_RESERVE_SATS: u64 = 1_000;
+/// How far the estimated payout may be from the amount of a payment URI, in
+/// basis points, as prices move between the wallet and us.
+const URI_AMOUNT_TOLERANCE_BPS: u64 = 100;
+/// Furthest ahead a user may schedule the delivery of a payout.
+const MAX_DELIVERY_DELAY_DAYS: i64 = 7;
+/// Statuses of a deposit whose PIX charge wasn't paid yet.
+const UNPAID_STATUSES: &[&str] = &["pending", "eulen_under_review"];
+
+mod availability;
+mod exposure;
+mod fees;
+mod floor;
+mod pricing;
+mod priority;
+#[cfg(test)]
+mod properties;
+mod verify;
+
+pub use availability::DepositHours;
+pub use exposure::PayoutExposurePolicy;
+pub use floor::PayoutFloorPolicy;
+pub use pricing::PricingWatchdog;
+pub use priority::PayoutPriorityPolicy;
+use priority::PayoutTier;
+
+type DepositResponse = oneshot::Sender<Result<Deposit, ServiceError>>;
 
 pub enum TransactionServiceRequest {
     NewTransaction {
@@ -27,6 +107,21 @@ pub enum TransactionServiceRequest {
         amount_in_cents: i32,
         asset: String,
         network: String,
+        /// Price quote the payout should be computed with.
+        quote_id: Option<String>,
+        /// When to pay out once paid, instead of right away.
+        scheduled_delivery_at: Option<chrono::DateTime<chrono::Utc>>,
+        response: oneshot::Sender<Result<Deposit, ServiceError>>,
+    },
+    /// Deposit paying a merchant through a payment link. Consumer and device
+    /// limits don't apply.
+    NewMerchantTransaction {
+        user_id: String,
+        address: String,
+        amount_in_cents: i32,
+        asset: String,
+        network: String,
+        fee_bps: i32,
         response: oneshot::Sender<Result<Deposit, ServiceError>>,
     },
     UpdateTransactionStatus {
@@ -37,6 +132,166 @@ pub enum TransactionServiceRequest {
         transaction_id: String,
         fee_collected: i32,
     },
+    GetPendingTransactions {
+        response: oneshot::Sender<Result<Vec<transactions::PendingEntry>, ServiceError>>,
+    },
+    GetTransaction {
+        transaction_id: String,
+        response: oneshot::Sender<Result<Option<transactions::Transaction>, ServiceError>>,
+    },
+    /// Cancels a deposit of the user before its PIX charge is paid, or
+    /// before its scheduled delivery, leaving the payment to be refunded.
+    /// None when the user has no such deposit.
+    CancelTransaction {
+        transaction_id: String,
+        user_id: String,
+        response: oneshot::Sender<Result<Option<transactions::Transaction>, ServiceError>>,
+    },
+    /// Parts of the payout sent before the wallet covered all of it.
+    GetPartialPayouts {
+        transaction_id: String,
+        response: oneshot::Sender<Result<Vec<PartialPayout>, ServiceError>>,
+    },
+    /// Locks the current price of the asset for a short time.
+    CreateQuote {
+        asset: String,
+        response: oneshot::Sender<Result<PriceQuote, ServiceError>>,
+    },
+    GetRecentTransactions {
+        limit: i64,
+        response: oneshot::Sender<Result<Vec<transactions::Transaction>, ServiceError>>,
+    },
+    SearchTransactions {
+        search: TransactionSearch,
+        after: Option<Cursor>,
+        limit: i64,
+        response: oneshot::Sender<Result<Page<transactions::Transaction>, ServiceError>>,
+    },
+    /// Transactions moved out of the live tables, oldest first.
+    GetArchivedTransactions {
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        after: Option<Cursor>,
+        limit: i64,
+        response: oneshot::Sender<Result<Page<ArchivedTransaction>, ServiceError>>,
+    },
+    IsPaused {
+        response: oneshot::Sender<bool>,
+    },
+    /// Kill switch: while paused no deposit is created and no payout is
+    /// sent. Paid transactions wait in the pending queue.
+    SetPaused {
+        paused: bool,
+        requested_by: String,
+        reason: Option<String>,
+        response: oneshot::Sender<Result<(), ServiceError>>,
+    },
+    GetAssetAvailability {
+        response: oneshot::Sender<Vec<AssetAvailability>>,
+    },
+    /// Stops or resumes deposits and payouts of one asset. False if the
+    /// asset is unknown.
+    SetAssetEnabled {
+        asset_id: String,
+        enabled: bool,
+        requested_by: String,
+        reason: Option<String>,
+        response: oneshot::Sender<Result<bool, ServiceError>>,
+    },
+    /// Why new deposits are closed right now, or None while they are open.
+    GetDepositClosure {
+        response: oneshot::Sender<Option<DepositClosure>>,
+    },
+    /// Maintenance windows not over yet, configured and scheduled, soonest
+    /// first.
+    GetMaintenanceWindows {
+        response: oneshot::Sender<Vec<MaintenanceWindow>>,
+    },
+    ScheduleMaintenance {
+        window: NewMaintenanceWindow,
+        response: oneshot::Sender<Result<MaintenanceWindow, ServiceError>>,
+    },
+    /// False if no scheduled window has this id.
+    CancelMaintenance {
+        id: String,
+        requested_by: String,
+        reason: Option<String>,
+        response: oneshot::Sender<Result<bool, ServiceError>>,
+    },
+    GetUnreconciledTransactions {
+        response: oneshot::Sender<Result<Vec<transactions::Transaction>, ServiceError>>,
+    },
+    RequeueTransaction {
+        transaction_id: String,
+        response: oneshot::Sender<Result<bool, ServiceError>>,
+    },
+    /// Retries a queued payout now. None if it is not queued, otherwise
+    /// whether it is still queued after the attempt.
+    RetryPending {
+        transaction_id: String,
+        requested_by: String,
+        response: oneshot::Sender<Result<Option<bool>, ServiceError>>,
+    },
+    /// Removes a payout from the queue and marks it failed, to be refunded.
+    /// False if it is not queued.
+    DropPending {
+        transaction_id: String,
+        requested_by: String,
+        reason: String,
+        response: oneshot::Sender<Result<bool, ServiceError>>,
+    },
+    SwapCompleted {
+        quote_sub_id: i64,
+        txid: String,
+    },
+    HoldTransaction {
+        transaction_id: String,
+        source: String,
+        reason: String,
+    },
+    GetHeldTransactions {
+        response: oneshot::Sender<Result<Vec<HeldTransaction>, ServiceError>>,
+    },
+    GetTimeline {
+        transaction_id: String,
+        response: oneshot::Sender<Result<Option<TransactionTimeline>, ServiceError>>,
+    },
+    CreateCampaign {
+        campaign: NewCampaign,
+        response: oneshot::Sender<Result<Campaign, ServiceError>>,
+    },
+    GetCampaigns {
+        response: oneshot::Sender<Result<Vec<Campaign>, ServiceError>>,
+    },
+    SetCampaignActive {
+        campaign_id: String,
+        active: bool,
+        response: oneshot::Sender<Result<bool, ServiceError>>,
+    },
+    ResolveReview {
+        transaction_id: String,
+        decision: ReviewDecision,
+        decided_by: String,
+        note: Option<String>,
+        response: oneshot::Sender<Result<bool, ServiceError>>,
+    },
+}
+
+/// What a deposit buys and where its payout goes.
+struct DepositOrder {
+    user_id: String,
+    address: String,
+    amount_in_cents: i32,
+    asset: String,
+    network: String,
+}
+
+/// Amounts of a payout before the parts already sent are deducted.
+struct PayoutAmounts {
+    fee_in_asset: u64,
+    referral_addr: Option<String>,
+    referral_bonus: u64,
+    amount_to_send_user: u64,
 }
 
 #[derive(Clone, Debug)]
@@ -44,60 +299,584 @@ struct PendingTransaction {
     transaction: transactions::Transaction,
     attempts: u32,
     last_attempt: chrono::DateTime<chrono::Utc>,
+    queued_at: chrono::DateTime<chrono::Utc>,
+    /// Sideswap quote subscription started to refill this payout's asset.
+    swap_id: Option<i64>,
+    last_error: Option<String>,
+}
+
+impl PendingTransaction {
+    fn failed(self, error: String) -> Self {
+        Self {
+            last_error: Some(error),
+            ..self
+        }
+    }
+}
+
+/// Services the transaction service talks to.
+#[derive(Clone)]
+pub struct TransactionChannels {
+    pub liquid: mpsc::Sender<LiquidRequest>,
+    pub pix: mpsc::Sender<PixServiceRequest>,
+    pub price: mpsc::Sender<PriceRequest>,
+    pub user: mpsc::Sender<UserRequest>,
+    pub swap_router: SwapRouter,
+    pub compliance: mpsc::Sender<ComplianceRequest>,
+    pub notification: mpsc::Sender<NotificationRequest>,
+    pub hedging: mpsc::Sender<HedgingRequest>,
+    pub event: mpsc::Sender<EventRequest>,
+    pub merchant: mpsc::Sender<MerchantRequest>,
+    pub analytics: mpsc::Sender<AnalyticsRequest>,
+}
+
+/// Payout policies and settings of the transaction service.
+#[derive(Clone)]
+pub struct TransactionConfig {
+    pub priority_policy: PayoutPriorityPolicy,
+    pub floor_policy: PayoutFloorPolicy,
+    pub exposure_policy: PayoutExposurePolicy,
+    pub swap_buffer_bps: u64,
+    pub block_unfundable_deposits: bool,
+    pub partial_payouts: bool,
+    pub avoid_change: bool,
+    /// Asset ids whose deposits and payouts start stopped.
+    pub disabled_assets: Vec<String>,
+    pub deposit_hours: DepositHours,
+    pub stale_payout_after_secs: u64,
+    pub pricing_watchdog: PricingWatchdog,
+    pub disputes: Disputes,
+    pub quotes: Quotes,
+    pub quote_currency: QuoteCurrency,
 }
 
 #[derive(Clone)]
 pub struct TransactionRequestHandler {
     repository: TransactionRepository,
+    review_repository: ReviewRepository,
+    merchant_repository: MerchantRepository,
+    campaign_repository: CampaignRepository,
+    operator_repository: OperatorRepository,
+    payout_repository: PayoutRepository,
+    saga_repository: SagaRepository,
+    pset_repository: PsetRepository,
+    referral_repository: ReferralRepository,
+    delivery_repository: DeliveryRepository,
+    dispute_repository: DisputeRepository,
+    quote_repository: QuoteRepository,
+    timeline_repository: TimelineRepository,
+    archive_repository: ArchiveRepository,
     liquid_channel: mpsc::Sender<LiquidRequest>,
     pix_channel: mpsc::Sender<PixServiceRequest>,
     price_channel: mpsc::Sender<PriceRequest>,
     user_channel: mpsc::Sender<UserRequest>,
-    sideswap_channel: mpsc::Sender<SideswapRequest>,
+    swap_router: SwapRouter,
+    compliance_channel: mpsc::Sender<ComplianceRequest>,
+    notification_channel: mpsc::Sender<NotificationRequest>,
+    hedging_channel: mpsc::Sender<HedgingRequest>,
+    event_channel: mpsc::Sender<EventRequest>,
+    merchant_channel: mpsc::Sender<MerchantRequest>,
+    analytics_channel: mpsc::Sender<AnalyticsRequest>,
     pending_transactions: Arc<Mutex<VecDeque<PendingTransaction>>>,
+    paused: Arc<AtomicBool>,
+    /// Asset ids whose deposits and payouts are stopped.
+    disabled_assets: Arc<RwLock<HashSet<String>>>,
+    deposit_hours: DepositHours,
+    /// Windows scheduled from the admin API.
+    maintenance_windows: Arc<RwLock<Vec<MaintenanceWindow>>>,
+    priority_policy: PayoutPriorityPolicy,
+    floor_policy: PayoutFloorPolicy,
+    exposure_policy: PayoutExposurePolicy,
+    swap_buffer_bps: u64,
+    block_unfundable_deposits: bool,
+    partial_payouts: bool,
+    avoid_change: bool,
+    stale_payout_after_secs: u64,
+    pricing_watchdog: PricingWatchdog,
+    disputes: Disputes,
+    quotes: Quotes,
+    quote_currency: QuoteCurrency,
+    clock: SharedClock,
 }
 
 impl TransactionRequestHandler {
-    pub fn new(
+    pub async fn new(
         sql_conn: PgPool,
-        liquid_channel: mpsc::Sender<LiquidRequest>,
-        pix_channel: mpsc::Sender<PixServiceRequest>,
-        price_channel: mpsc::Sender<PriceRequest>,
-        user_channel: mpsc::Sender<UserRequest>,
-        sideswap_channel: mpsc::Sender<SideswapRequest>,
+        channels: TransactionChannels,
+        config: TransactionConfig,
+        clock: SharedClock,
     ) -> Self {
-        let repository = TransactionRepository::new(sql_conn);
+        let repository = TransactionRepository::new(sql_conn.clone(), clock.clone());
+        let review_repository = ReviewRepository::new(sql_conn.clone());
+        let merchant_repository = MerchantRepository::new(sql_conn.clone());
+        let campaign_repository = CampaignRepository::new(sql_conn.clone());
+        let operator_repository = OperatorRepository::new(sql_conn.clone());
+        let payout_repository = PayoutRepository::new(sql_conn.clone());
+        let saga_repository = SagaRepository::new(sql_conn.clone());
+        let pset_repository = PsetRepository::new(sql_conn.clone());
+        let referral_repository = ReferralRepository::new(sql_conn.clone());
+        let delivery_repository = DeliveryRepository::new(sql_conn.clone());
+        let dispute_repository = DisputeRepository::new(sql_conn.clone());
+        let quote_repository = QuoteRepository::new(sql_conn.clone());
+        let timeline_repository = TimelineRepository::new(sql_conn.clone());
+        let archive_repository = ArchiveRepository::new(sql_conn);
         let pending_transactions = Arc::new(Mutex::new(VecDeque::new()));
 
         let handler = TransactionRequestHandler {
             repository,
-            liquid_channel,
-            pix_channel,
-            price_channel,
-            user_channel,
-            sideswap_channel,
+            review_repository,
+            merchant_repository,
+            campaign_repository,
+            operator_repository,
+            payout_repository,
+            saga_repository,
+            pset_repository,
+            referral_repository,
+            delivery_repository,
+            dispute_repository,
+            quote_repository,
+            timeline_repository,
+            archive_repository,
+            liquid_channel: channels.liquid,
+            pix_channel: channels.pix,
+            price_channel: channels.price,
+            user_channel: channels.user,
+            swap_router: channels.swap_router,
+            compliance_channel: channels.compliance,
+            notification_channel: channels.notification,
+            hedging_channel: channels.hedging,
+            event_channel: channels.event,
+            merchant_channel: channels.merchant,
+            analytics_channel: channels.analytics,
             pending_transactions,
+            paused: Arc::new(AtomicBool::new(false)),
+            disabled_assets: Arc::new(RwLock::new(config.disabled_assets.into_iter().collect())),
+            deposit_hours: config.deposit_hours,
+            maintenance_windows: Arc::new(RwLock::new(Vec::new())),
+            priority_policy: config.priority_policy,
+            floor_policy: config.floor_policy,
+            exposure_policy: config.exposure_policy,
+            swap_buffer_bps: config.swap_buffer_bps,
+            block_unfundable_deposits: config.block_unfundable_deposits,
+            partial_payouts: config.partial_payouts,
+            avoid_change: config.avoid_change,
+            stale_payout_after_secs: config.stale_payout_after_secs,
+            pricing_watchdog: config.pricing_watchdog,
+            disputes: config.disputes,
+            quotes: config.quotes,
+            quote_currency: config.quote_currency,
+            clock,
         };
 
+        // Loaded before any job could pay out
+        handler.load_kill_switch().await;
+        handler.load_asset_flags();
+        handler.load_maintenance_windows();
         handler.start_pending_transaction_processor();
+        handler.start_recovery_scan();
+        handler.start_delivery_release();
+        handler.start_pricing_recovery();
 
         handler
     }
 
-    fn start_pending_transaction_processor(&self) {
-        let handler_clone = self.clone();
+    /// Starts paused when the flag can't be read, until an operator turns
+    /// the kill switch off.
+    async fn load_kill_switch(&self) {
+        match self.operator_repository.get_flag(KILL_SWITCH_FLAG).await {
+            Ok(true) => {
+                log::warn!("Kill switch is on: deposits and payouts are paused");
+                self.paused.store(true, Ordering::SeqCst);
+            }
+            Ok(false) => {}
+            Err(e) => {
+                log::error!("Could not load kill switch, pausing: {}", e);
+                self.paused.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// Asset flags set from the admin API override `disabled_assets`.
+    fn load_asset_flags(&self) {
+        let repository = self.operator_repository.clone();
+        let disabled_assets = self.disabled_assets.clone();
 
         tokio::spawn(async move {
-            let mut check_interval = tokio::time::interval(tokio::time::Duration::from_secs(60)); // Check every minute
+            for asset in [Assets::DEPIX, Assets::USDT, Assets::LBTC] {
+                let asset_id = asset.hex();
+                let flag = format!("{}{}", ASSET_DISABLED_FLAG, asset_id);
+                match repository.find_flag(&flag).await {
+                    Ok(Some(true)) => {
+                        log::warn!("{} deposits and payouts are disabled", asset.ticker());
+                        disabled_assets.write().await.insert(asset_id);
+                    }
+                    Ok(Some(false)) => {
+                        disabled_assets.write().await.remove(&asset_id);
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::error!("Could not load flag {}: {}", flag, e),
+                }
+            }
+        });
+    }
+
+    fn load_maintenance_windows(&self) {
+        let repository = self.operator_repository.clone();
+        let maintenance_windows = self.maintenance_windows.clone();
+        let now = self.clock.now();
 
-            loop {
-                check_interval.tick().await;
-                handler_clone.process_pending_transactions().await;
+        tokio::spawn(async move {
+            match repository.get_maintenance_windows(now).await {
+                Ok(windows) => *maintenance_windows.write().await = windows,
+                Err(e) => log::error!("Could not load maintenance windows: {}", e),
             }
         });
     }
 
+    async fn deposit_closure(&self) -> Option<DepositClosure> {
+        let now = self.clock.now();
+        let mut scheduled = self.maintenance_windows.write().await;
+        scheduled.retain(|window| window.ends_at > now);
+        self.deposit_hours.closure(now, &scheduled)
+    }
+
+    async fn get_maintenance_windows(&self) -> Vec<MaintenanceWindow> {
+        let now = self.clock.now();
+        let scheduled = self.maintenance_windows.read().await;
+        let mut windows: Vec<MaintenanceWindow> = self
+            .deposit_hours
+            .configured_windows()
+            .iter()
+            .chain(scheduled.iter())
+            .filter(|window| window.ends_at > now)
+            .cloned()
+            .collect();
+        windows.sort_by_key(|window| window.starts_at);
+        windows
+    }
+
+    async fn schedule_maintenance(
+        &self,
+        window: NewMaintenanceWindow,
+    ) -> Result<MaintenanceWindow, ServiceError> {
+        let scheduled = self
+            .operator_repository
+            .schedule_maintenance(&window)
+            .await
+            .map_err(|e| ServiceError::Repository("Operator".to_string(), e.to_string()))?;
+
+        log::warn!(
+            "Deposits closed from {} to {} by {}: {}",
+            scheduled.starts_at,
+            scheduled.ends_at,
+            scheduled.scheduled_by,
+            scheduled.reason
+        );
+        self.maintenance_windows
+            .write()
+            .await
+            .push(scheduled.clone());
+
+        Ok(scheduled)
+    }
+
+    async fn cancel_maintenance(
+        &self,
+        id: &str,
+        requested_by: &str,
+        reason: Option<&str>,
+    ) -> Result<bool, ServiceError> {
+        let cancelled = self
+            .operator_repository
+            .cancel_maintenance(id, requested_by, reason)
+            .await
+            .map_err(|e| ServiceError::Repository("Operator".to_string(), e.to_string()))?;
+
+        if cancelled {
+            log::warn!("Maintenance window {} cancelled by {}", id, requested_by);
+            self.maintenance_windows
+                .write()
+                .await
+                .retain(|window| window.id != id);
+        }
+
+        Ok(cancelled)
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    async fn is_asset_enabled(&self, asset_id: &str) -> bool {
+        !self.disabled_assets.read().await.contains(asset_id)
+    }
+
+    async fn get_asset_availability(&self) -> Vec<AssetAvailability> {
+        let disabled_assets = self.disabled_assets.read().await;
+        [Assets::DEPIX, Assets::USDT, Assets::LBTC]
+            .into_iter()
+            .map(|asset| {
+                let asset_id = asset.hex();
+                AssetAvailability {
+                    enabled: !disabled_assets.contains(&asset_id),
+                    asset_id,
+                    ticker: asset.ticker().to_string(),
+                }
+            })
+            .collect()
+    }
+
+    async fn set_asset_enabled(
+        &self,
+        asset_id: &str,
+        enabled: bool,
+        requested_by: &str,
+        reason: Option<&str>,
+    ) -> Result<bool, ServiceError> {
+        let Ok(asset) = Assets::from_hex(asset_id) else {
+            return Ok(false);
+        };
+
+        self.operator_repository
+            .set_flag(
+                &format!("{}{}", ASSET_DISABLED_FLAG, asset_id),
+                !enabled,
+                requested_by,
+                reason,
+            )
+            .await
+            .map_err(|e| ServiceError::Repository("Operator".to_string(), e.to_string()))?;
+
+        if enabled {
+            self.disabled_assets.write().await.remove(asset_id);
+            log::warn!("{} enabled by {}", asset.ticker(), requested_by);
+            jobs::trigger(PENDING_PAYOUTS_JOB);
+        } else {
+            self.disabled_assets
+                .write()
+                .await
+                .insert(asset_id.to_string());
+            log::warn!("{} disabled by {}", asset.ticker(), requested_by);
+        }
+
+        Ok(true)
+    }
+
+    async fn set_paused(
+        &self,
+        paused: bool,
+        requested_by: &str,
+        reason: Option<&str>,
+    ) -> Result<(), ServiceError> {
+        self.operator_repository
+            .set_flag(KILL_SWITCH_FLAG, paused, requested_by, reason)
+            .await
+            .map_err(|e| ServiceError::Repository("Operator".to_string(), e.to_string()))?;
+        self.paused.store(paused, Ordering::SeqCst);
+
+        if paused {
+            log::warn!("Kill switch turned on by {}", requested_by);
+        } else {
+            log::warn!("Kill switch turned off by {}", requested_by);
+            jobs::trigger(PENDING_PAYOUTS_JOB);
+        }
+
+        Ok(())
+    }
+
+    fn start_pending_transaction_processor(&self) {
+        let handler = self.clone();
+
+        jobs::register(
+            PENDING_PAYOUTS_JOB,
+            JobSchedule::every_secs(60),
+            move || {
+                let handler = handler.clone();
+                async move {
+                    handler.process_pending_transactions().await;
+                    Ok(())
+                }
+            },
+        );
+    }
+
+    /// Runs once at startup and then periodically.
+    fn start_recovery_scan(&self) {
+        let handler = self.clone();
+        let period = self.stale_payout_after_secs.max(60);
+
+        jobs::register(
+            "payout_recovery",
+            JobSchedule::every_secs(period),
+            move || {
+                let handler = handler.clone();
+                async move { handler.recover_stale_transactions().await }
+            },
+        );
+    }
+
+    fn start_delivery_release(&self) {
+        let handler = self.clone();
+
+        jobs::register(
+            "scheduled_deliveries",
+            JobSchedule::every_secs(60),
+            move || {
+                let handler = handler.clone();
+                async move { handler.release_due_deliveries().await }
+            },
+        );
+    }
+
+    fn start_pricing_recovery(&self) {
+        let handler = self.clone();
+
+        jobs::register("pricing_recovery", JobSchedule::every_secs(30), move || {
+            let handler = handler.clone();
+            async move { handler.resume_priced_payouts().await }
+        });
+    }
+
+    /// Finds paid transactions whose payout stopped halfway (e.g. after a
+    /// crash) and resumes them, marks them finished when the payout is found
+    /// on chain, or holds them for review when that can't be decided.
+    async fn recover_stale_transactions(&self) -> Result<(), anyhow::Error> {
+        let stale_before = self.clock.now()
+            - chrono::Duration::seconds(self.stale_payout_after_secs as i64);
+
+        let stale = self
+            .repository
+            .get_stale_transactions("eulen_depix_sent", stale_before)
+            .await?;
+
+        let queued: HashSet<String> = self
+            .pending_transactions
+            .lock()
+            .await
+            .iter()
+            .map(|pending_tx| pending_tx.transaction.id.clone())
+            .collect();
+
+        for transaction in stale {
+            if queued.contains(&transaction.id) {
+                continue;
+            }
+
+            // Payouts being paid right now are not stale
+            let _claim = match self.claim_payout(&transaction.id).await {
+                Ok(Some(claim)) => claim,
+                Ok(None) => continue,
+                Err(e) => {
+                    log::error!("Could not claim transaction {}: {}", transaction.id, e);
+                    continue;
+                }
+            };
+
+            let action = match self.recover_transaction(&transaction).await {
+                Ok(action) => action,
+                Err(e) => {
+                    log::error!("Could not recover transaction {}: {}", transaction.id, e);
+                    continue;
+                }
+            };
+
+            log::warn!("Recovered stale transaction {}: {}", transaction.id, action);
+            metrics::increment("payout_recovery_total", &[("action", action)]);
+        }
+
+        Ok(())
+    }
+
+    async fn recover_transaction(
+        &self,
+        transaction: &transactions::Transaction,
+    ) -> Result<&'static str, ServiceError> {
+        let payout = self
+            .payout_repository
+            .get_payout(&transaction.id)
+            .await
+            .map_err(|e| ServiceError::Repository("Payouts".to_string(), e.to_string()))?;
+
+        let partials = self
+            .payout_repository
+            .get_partial_payouts(&transaction.id)
+            .await
+            .map_err(|e| ServiceError::Repository("Payouts".to_string(), e.to_string()))?;
+        if partials.iter().any(|partial| partial.txid.is_none()) {
+            self.hold_transaction(
+                &transaction.id,
+                "recovery",
+                "Partial payout broadcast was interrupted; check the chain before approving",
+            )
+            .await?;
+            return Ok("held");
+        }
+
+        let Some(payout) = payout else {
+            self.requeue_transaction(&transaction.id).await?;
+            return Ok("requeued");
+        };
+
+        // The broadcast step keeps the txid when the payout row missed it
+        let txid = match payout.txid {
+            Some(txid) => Some(txid),
+            None => self.saga_txid(&transaction.id).await?,
+        };
+
+        match txid {
+            Some(txid) if self.is_on_chain(&txid).await? => {
+                self.update_transaction_status(&transaction.id, &"finished".to_string())
+                    .await?;
+                self.record_exposure(&transaction.id).await;
+                self.publish_payout_sent(transaction, &txid).await;
+                self.track("payout_delivered", transaction);
+                self.send_receipt(&transaction.id).await;
+                self.invoice_fee(&transaction.id).await;
+                Ok("finished")
+            }
+            Some(txid) => {
+                self.hold_transaction(
+                    &transaction.id,
+                    "recovery",
+                    &format!("Payout {} was broadcast but is not in the wallet", txid),
+                )
+                .await?;
+                Ok("held")
+            }
+            None => {
+                self.hold_transaction(
+                    &transaction.id,
+                    "recovery",
+                    "Payout broadcast was interrupted; check the chain before approving",
+                )
+                .await?;
+                Ok("held")
+            }
+        }
+    }
+
+    async fn is_on_chain(&self, txid: &str) -> Result<bool, ServiceError> {
+        let (liquid_tx, liquid_rx) = oneshot::channel();
+        self.liquid_channel
+            .send(LiquidRequest::HasTransaction {
+                txid: txid.to_string(),
+                response: liquid_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Transaction => Liquid".to_string(), e.to_string())
+            })?;
+
+        liquid_rx.await.map_err(|e| {
+            ServiceError::Communication("Transaction => Liquid".to_string(), e.to_string())
+        })?
+    }
+
     async fn process_pending_transactions(&self) {
+        if self.is_paused() {
+            return;
+        }
+
         let mut pending_txs = self.pending_transactions.lock().await;
 
         if pending_txs.is_empty() {
@@ -107,27 +886,102 @@ impl TransactionRequestHandler {
         log::info!("Processing {} pending transactions", pending_txs.len());
 
         // Take transactions from the queue to process
-        let mut transactions_to_process = Vec::new();
-        while let Some(pending_tx) = pending_txs.pop_front() {
-            transactions_to_process.push(pending_tx);
-        }
+        let transactions_to_process: Vec<PendingTransaction> = pending_txs.drain(..).collect();
 
         // Release the lock before processing
         drop(pending_txs);
 
+        self.process_pending_batch(transactions_to_process).await;
+    }
+
+    /// Retries only the payouts that were waiting on the given swap, instead of
+    /// leaving them for the next timer tick.
+    async fn process_swap_completion(&self, quote_sub_id: i64, txid: &str) {
+        if let Err(e) = self.payout_repository.complete_swap(quote_sub_id, txid).await {
+            log::error!("Could not record completion of swap {}: {}", quote_sub_id, e);
+        }
+        self.publish_event(
+            EventKind::SwapCompleted,
+            json!({ "quote_sub_id": quote_sub_id, "txid": txid }),
+        )
+        .await;
+
+        if self.is_paused() {
+            return;
+        }
+
+        let mut pending_txs = self.pending_transactions.lock().await;
+
+        let (linked, remaining): (Vec<PendingTransaction>, Vec<PendingTransaction>) = pending_txs
+            .drain(..)
+            .partition(|pending_tx| pending_tx.swap_id == Some(quote_sub_id));
+        pending_txs.extend(remaining);
+        drop(pending_txs);
+
+        if linked.is_empty() {
+            log::debug!("No pending transactions linked to swap {}", quote_sub_id);
+            return;
+        }
+
+        log::info!(
+            "Swap {} completed (txid: {}), retrying {} linked pending transactions",
+            quote_sub_id,
+            txid,
+            linked.len()
+        );
+
+        self.process_pending_batch(linked).await;
+    }
+
+    async fn process_pending_batch(&self, mut transactions_to_process: Vec<PendingTransaction>) {
+        let now = self.clock.now();
+        self.priority_policy.sort(&mut transactions_to_process, now);
+
+        // Assets whose balance is held for a starving payout this round
+        let mut held_assets: HashSet<String> = HashSet::new();
+
         for pending_tx in transactions_to_process {
+            if !self.is_asset_enabled(&pending_tx.transaction.asset).await {
+                self.requeue_pending(pending_tx, false).await;
+                continue;
+            }
+
+            let tier = self.priority_policy.tier(&pending_tx, now);
+
+            if tier != PayoutTier::Starving && held_assets.contains(&pending_tx.transaction.asset) {
+                log::debug!(
+                    "Deferring pending transaction {}: balance held for older payouts",
+                    pending_tx.transaction.id
+                );
+                metrics::increment(
+                    "payout_queue_deferred_total",
+                    &[("asset", &pending_tx.transaction.asset)],
+                );
+                self.requeue_pending(pending_tx, false).await;
+                continue;
+            }
+
             log::info!(
-                "Attempting to process pending transaction {} (attempt: {})",
+                "Attempting to process pending transaction {} (attempt: {}, tier: {:?})",
                 pending_tx.transaction.id,
-                pending_tx.attempts + 1
+                pending_tx.attempts + 1,
+                tier
             );
 
             // Check if we can now process this transaction
+            // Users who waited for a swap or past the starvation threshold
+            // get their payout confirmed faster
+            let priority = if pending_tx.swap_id.is_some() || tier == PayoutTier::Starving {
+                FeePriority::High
+            } else {
+                FeePriority::Normal
+            };
+
             match self.check_asset_balance(&pending_tx.transaction).await {
                 Ok(true) => {
                     // We have sufficient balance, try to process the transaction
                     match self
-                        .finish_transaction(pending_tx.transaction.clone())
+                        .finish_transaction(pending_tx.transaction.clone(), priority)
                         .await
                     {
                         Ok(_) => {
@@ -135,6 +989,26 @@ impl TransactionRequestHandler {
                                 "Successfully processed pending transaction {}",
                                 pending_tx.transaction.id
                             );
+                            let waited = self.clock.now() - pending_tx.queued_at;
+                            metrics::observe(
+                                "payout_queue_wait_seconds",
+                                &[("asset", &pending_tx.transaction.asset)],
+                                waited.num_milliseconds() as f64 / 1000.0,
+                            );
+                        }
+                        // Requeued, or parked until priced, by finish_transaction,
+                        // or claimed by the recovery scan which queues it if needed
+                        Err(ServiceError::Internal(reason))
+                            if reason == "DealerPaused"
+                                || reason == "AssetUnavailable"
+                                || reason == "PricingUnavailable"
+                                || reason == "PayoutClaimed" => {}
+                        Err(e) if is_compliance_stop(&e) => {
+                            log::warn!(
+                                "Pending transaction {} stopped by compliance: {}",
+                                pending_tx.transaction.id,
+                                e
+                            );
                         }
                         Err(e) => {
                             log::error!(
@@ -143,23 +1017,21 @@ impl TransactionRequestHandler {
                                 e
                             );
                             // Put it back in the queue with increased attempt count
-                            let mut pending_txs = self.pending_transactions.lock().await;
-                            pending_txs.push_back(PendingTransaction {
-                                transaction: pending_tx.transaction,
-                                attempts: pending_tx.attempts + 1,
-                                last_attempt: chrono::Utc::now(),
-                            });
+                            self.requeue_pending(pending_tx.failed(e.to_string()), true)
+                                .await;
                         }
                     }
                 }
                 Ok(false) => {
                     // Still insufficient balance, put it back in the queue
-                    let mut pending_txs = self.pending_transactions.lock().await;
-                    pending_txs.push_back(PendingTransaction {
-                        transaction: pending_tx.transaction,
-                        attempts: pending_tx.attempts + 1,
-                        last_attempt: chrono::Utc::now(),
-                    });
+                    if tier == PayoutTier::Starving {
+                        held_assets.insert(pending_tx.transaction.asset.clone());
+                    }
+                    self.requeue_pending(
+                        pending_tx.failed("Insufficient balance".to_string()),
+                        true,
+                    )
+                    .await;
                 }
                 Err(e) => {
                     log::error!(
@@ -168,48 +1040,269 @@ impl TransactionRequestHandler {
                         e
                     );
                     // Put it back in the queue
-                    let mut pending_txs = self.pending_transactions.lock().await;
-                    pending_txs.push_back(PendingTransaction {
-                        transaction: pending_tx.transaction,
-                        attempts: pending_tx.attempts + 1,
-                        last_attempt: chrono::Utc::now(),
-                    });
+                    self.requeue_pending(pending_tx.failed(e.to_string()), true)
+                        .await;
                 }
             }
         }
+
+        let queue_length = self.pending_transactions.lock().await.len();
+        metrics::set_gauge("payout_queue_length", &[], queue_length as f64);
+    }
+
+    async fn requeue_pending(&self, pending_tx: PendingTransaction, attempted: bool) {
+        let mut pending_txs = self.pending_transactions.lock().await;
+        let (attempts, last_attempt) = if attempted {
+            (pending_tx.attempts + 1, self.clock.now())
+        } else {
+            (pending_tx.attempts, pending_tx.last_attempt)
+        };
+        let pending_tx = PendingTransaction {
+            attempts,
+            last_attempt,
+            ..pending_tx
+        };
+
+        // A failed payout was already put back by its compensation
+        match pending_txs
+            .iter_mut()
+            .find(|queued| queued.transaction.id == pending_tx.transaction.id)
+        {
+            Some(queued) => *queued = pending_tx,
+            None => pending_txs.push_back(pending_tx),
+        }
     }
 
     async fn check_asset_balance(
         &self,
         transaction: &transactions::Transaction,
     ) -> Result<bool, ServiceError> {
+        Ok(self.asset_shortfall(transaction).await? == 0)
+    }
+
+    /// How much of the payout asset is missing from the wallet, in the
+    /// asset's base units.
+    async fn asset_shortfall(
+        &self,
+        transaction: &transactions::Transaction,
+    ) -> Result<u64, ServiceError> {
         let asset_price_in_cents = self.request_asset_price(&transaction.asset).await?;
+        let total_needed = fees::asset_amount(
+            transaction.amount_in_cents,
+            asset_price_in_cents,
+            Assets::precision_of(&transaction.asset),
+        )
+        .map_err(fee_error)?;
 
-        // Calculate asset amount with precision already included
-        let asset_amount =
-            (transaction.amount_in_cents as u64 * 10_u64.pow(8)) / asset_price_in_cents;
+        // Check current balance
+        let balance = self.request_asset_balance(&transaction.asset).await?;
+        let partial_total = self.partial_total(&transaction.id).await?;
 
-        let referral_addr = self.check_for_referral(&transaction.user_id).await?;
-        let fee_in_asset = self.calculate_fee_amount(
-            transaction.amount_in_cents as u64,
+        Ok(total_needed
+            .saturating_sub(partial_total)
+            .saturating_sub(balance))
+    }
+
+    async fn partial_total(&self, transaction_id: &str) -> Result<u64, ServiceError> {
+        self.payout_repository
+            .get_partial_total(transaction_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Payouts".to_string(), e.to_string()))
+    }
+
+    /// Applies limits across every account bound to the same device as the user.
+    async fn check_device_limits(
+        &self,
+        user_id: &String,
+        amount_in_cents: i32,
+    ) -> Result<(), ServiceError> {
+        let (user_tx, user_rx) = oneshot::channel();
+        self.user_channel
+            .send(UserRequest::GetDeviceLimits {
+                id: user_id.clone(),
+                response: user_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Transaction => User".to_string(), e.to_string())
+            })?;
+
+        let limits = user_rx.await.map_err(|e| {
+            ServiceError::Communication("Transaction => User".to_string(), e.to_string())
+        })??;
+
+        if let Some(limits) = limits {
+            if limits.daily_spending + amount_in_cents as i64 > limits.allowed_spending {
+                log::warn!(
+                    "Rejecting deposit of {} cents for user {}: {} accounts on the same device spent {} of {} today",
+                    amount_in_cents,
+                    user_id,
+                    limits.linked_accounts.len(),
+                    limits.daily_spending,
+                    limits.allowed_spending
+                );
+                return Err(ServiceError::Internal("DeviceLimitExceeded".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Refuses deposits from users with an open MED dispute.
+    async fn check_open_disputes(&self, user_id: &str) -> Result<(), ServiceError> {
+        if !self.disputes.block_deposits {
+            return Ok(());
+        }
+
+        let open = self.open_disputes(user_id).await?;
+        if open > 0 {
+            log::warn!(
+                "Rejecting deposit for user {}: {} open PIX disputes",
+                user_id,
+                open
+            );
+            return Err(ServiceError::Internal("OpenDispute".to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn open_disputes(&self, user_id: &str) -> Result<i64, ServiceError> {
+        self.dispute_repository
+            .count_open(user_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Disputes".to_string(), e.to_string()))
+    }
+
+    /// Band the user's limits are scaled to.
+    async fn risk_band(&self, user_id: &str) -> Result<RiskBand, ServiceError> {
+        let (user_tx, user_rx) = oneshot::channel();
+        self.user_channel
+            .send(UserRequest::GetRiskScore {
+                id: user_id.to_string(),
+                response: user_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Transaction => User".to_string(), e.to_string())
+            })?;
+
+        let score = user_rx.await.map_err(|e| {
+            ServiceError::Communication("Transaction => User".to_string(), e.to_string())
+        })??;
+
+        Ok(score.map(|score| score.band).unwrap_or(RiskBand::Standard))
+    }
+
+    /// Net payout of a deposit at the current price, in base units.
+    async fn estimate_payout(
+        &self,
+        amount_in_cents: i32,
+        asset: &String,
+        merchant_fee_bps: Option<i32>,
+    ) -> Result<u64, ServiceError> {
+        let asset_price_in_cents = self.request_asset_price(asset).await?;
+        let precision = Assets::precision_of(asset);
+        let asset_amount = fees::asset_amount(amount_in_cents, asset_price_in_cents, precision)
+            .map_err(fee_error)?;
+        let fee_in_asset = match merchant_fee_bps {
+            Some(fee_bps) => {
+                fees::merchant_fee(amount_in_cents, asset_price_in_cents, precision, fee_bps)
+            }
+            None => fees::consumer_fee(amount_in_cents, asset_price_in_cents, precision, false),
+        }
+        .map_err(fee_error)?;
+
+        Ok(asset_amount.saturating_sub(fee_in_asset))
+    }
+
+    /// Rejects deposits whose payout, after fees, would be below the asset's
+    /// minimum payout.
+    async fn ensure_payout_above_minimum(
+        &self,
+        amount_in_cents: i32,
+        asset: &String,
+        merchant_fee_bps: Option<i32>,
+    ) -> Result<(), ServiceError> {
+        let payout = self
+            .estimate_payout(amount_in_cents, asset, merchant_fee_bps)
+            .await?;
+        let min_payout = self.floor_policy.min_payout(asset);
+        if payout < min_payout {
+            log::warn!(
+                "Rejecting deposit of {} cents in {}: payout of {} is below the minimum of {}",
+                amount_in_cents,
+                asset,
+                payout,
+                min_payout
+            );
+            return Err(ServiceError::Internal("PayoutBelowMinimum".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Rejects deposits whose payout is not the amount the payment URI asks
+    /// for.
+    async fn ensure_payout_matches_uri(
+        &self,
+        amount_in_cents: i32,
+        asset: &String,
+        merchant_fee_bps: Option<i32>,
+        requested: u64,
+    ) -> Result<(), ServiceError> {
+        let payout = self
+            .estimate_payout(amount_in_cents, asset, merchant_fee_bps)
+            .await?;
+        let tolerance = requested * URI_AMOUNT_TOLERANCE_BPS / 10_000;
+        if payout.abs_diff(requested) > tolerance {
+            log::warn!(
+                "Rejecting deposit of {} cents in {}: payout of {} does not match the {} asked by the payment URI",
+                amount_in_cents,
+                asset,
+                payout,
+                requested
+            );
+            return Err(ServiceError::Internal("PaymentAmountMismatch".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Rejects deposits whose payout could not be honored from the current float.
+    async fn ensure_deposit_is_fundable(
+        &self,
+        amount_in_cents: i32,
+        asset: &String,
+    ) -> Result<(), ServiceError> {
+        let asset_price_in_cents = self.request_asset_price(asset).await?;
+        let asset_amount = fees::asset_amount(
+            amount_in_cents,
             asset_price_in_cents,
-            referral_addr.is_some(),
-        );
+            Assets::precision_of(asset),
+        )
+        .map_err(fee_error)?;
+        let balance = self.request_asset_balance(asset).await?;
 
-        let referral_bonus = if let Some(_) = &referral_addr {
-            (transaction.amount_in_cents as u64 * 50 * 10_u64.pow(8)) / 10000 / asset_price_in_cents
-        } else {
-            0
-        };
+        if asset_amount > balance {
+            log::warn!(
+                "Rejecting deposit of {} cents in {}: float is {}, payout needs {}",
+                amount_in_cents,
+                asset,
+                balance,
+                asset_amount
+            );
+            return Err(ServiceError::Internal("InsufficientLiquidity".to_string()));
+        }
 
-        // Calculate total amount needed
-        let total_needed = asset_amount;
+        Ok(())
+    }
 
-        // Check current balance
+    async fn request_asset_balance(&self, asset: &str) -> Result<u64, ServiceError> {
         let (liquid_tx, liquid_rx) = oneshot::channel();
         self.liquid_channel
             .send(LiquidRequest::GetAssetBalance {
-                asset_id: transaction.asset.clone(),
+                asset_id: asset.to_string(),
                 response: liquid_tx,
             })
             .await
@@ -217,27 +1310,30 @@ impl TransactionRequestHandler {
                 ServiceError::Communication("Transaction => Liquid".to_string(), e.to_string())
             })?;
 
-        let balance = liquid_rx.await.map_err(|e| {
+        liquid_rx.await.map_err(|e| {
             ServiceError::Communication("Transaction => Liquid".to_string(), e.to_string())
-        })??;
-
-        Ok(balance >= total_needed)
+        })?
     }
 
     async fn new_transaction(
         &self,
-        user_id: String,
-        address: String,
-        amount_in_cents: i32,
-        asset: String,
-        network: String,
+        order: DepositOrder,
+        quote_id: Option<String>,
+        scheduled_delivery_at: Option<chrono::DateTime<chrono::Utc>>,
+        response: &DepositResponse,
     ) -> Result<Deposit, ServiceError> {
-        let (liquid_tx, liquid_rx) = oneshot::channel();
-        let (pix_tx, pix_rx) = oneshot::channel();
-        
+        if let Some(deliver_at) = scheduled_delivery_at {
+            let now = self.clock.now();
+            if deliver_at <= now
+                || deliver_at > now + chrono::Duration::days(MAX_DELIVERY_DELAY_DAYS)
+            {
+                return Err(ServiceError::Internal("InvalidDeliveryTime".to_string()));
+            }
+        }
+
         let (user_tx, user_rx) = oneshot::channel();
         self.user_channel.send(
-            UserRequest::GetUser { id: user_id.clone(), response: user_tx }
+            UserRequest::GetUser { id: order.user_id.clone(), response: user_tx }
         ).await.map_err(|e| {
             log::error!("Failed to get user: {:?}", e);
             ServiceError::Communication("Transaction => User".to_string(), e.to_string())
@@ -248,6 +1344,239 @@ impl TransactionRequestHandler {
             ServiceError::Communication("Transaction => User".to_string(), e.to_string())
         })??;
 
+        self.check_device_limits(&order.user_id, order.amount_in_cents)
+            .await?;
+        self.check_open_disputes(&order.user_id).await?;
+        let risk_band = self.risk_band(&order.user_id).await?;
+
+        let quote = match quote_id {
+            Some(quote_id) => Some(self.get_valid_quote(&quote_id, &order.asset).await?),
+            None => None,
+        };
+
+        let deposit = self
+            .open_deposit(order, None, risk_band, scheduled_delivery_at, response)
+            .await?;
+
+        if let Some(quote) = quote {
+            self.attach_quote(&deposit.transaction_id, &quote).await;
+        }
+
+        Ok(deposit)
+    }
+
+    async fn create_quote(&self, asset: String) -> Result<PriceQuote, ServiceError> {
+        let asset_price = self.request_price(&asset).await?;
+        let expires_at = self.clock.now() + chrono::Duration::seconds(self.quotes.ttl_secs);
+
+        let quote = self
+            .quote_repository
+            .insert_quote(
+                &asset,
+                self.quote_currency.code(),
+                price_in_cents(&asset_price) as i64,
+                asset_price.aggregation.map(|a| a.as_str()),
+                expires_at,
+            )
+            .await
+            .map_err(|e| ServiceError::Repository("Quotes".to_string(), e.to_string()))?;
+
+        metrics::increment("price_quotes_total", &[("outcome", "created")]);
+        Ok(quote)
+    }
+
+    /// Unused, unexpired quote for the asset of the deposit.
+    async fn get_valid_quote(
+        &self,
+        quote_id: &str,
+        asset: &str,
+    ) -> Result<PriceQuote, ServiceError> {
+        let quote = self
+            .quote_repository
+            .get_unused_quote(quote_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Quotes".to_string(), e.to_string()))?;
+
+        match quote {
+            Some(quote) if quote.is_valid_for(asset, self.clock.now()) => Ok(quote),
+            _ => {
+                metrics::increment("price_quotes_total", &[("outcome", "rejected")]);
+                Err(ServiceError::Internal("InvalidQuote".to_string()))
+            }
+        }
+    }
+
+    /// The deposit already exists, so a quote that can't be attached only
+    /// means the payout is computed at the market price.
+    async fn attach_quote(&self, transaction_id: &str, quote: &PriceQuote) {
+        match self
+            .quote_repository
+            .attach_to_transaction(transaction_id, &quote.id)
+            .await
+        {
+            Ok(true) => metrics::increment("price_quotes_total", &[("outcome", "used")]),
+            Ok(false) => log::warn!(
+                "Quote {} was used by another deposit, transaction {} pays at the market price",
+                quote.id,
+                transaction_id
+            ),
+            Err(e) => log::error!(
+                "Failed to attach quote {} to transaction {}: {}",
+                quote.id,
+                transaction_id,
+                e
+            ),
+        }
+    }
+
+    /// Price the payout is computed with: the quoted one, unless the asset
+    /// got more expensive since the quote than the risk limit allows.
+    async fn payout_price(
+        &self,
+        transaction_id: &str,
+        current: &AssetPrice,
+    ) -> Result<(u64, Option<String>), ServiceError> {
+        let current_in_cents = price_in_cents(current);
+        let market = (
+            current_in_cents,
+            current.aggregation.map(|a| a.as_str().to_string()),
+        );
+
+        let quote = self
+            .quote_repository
+            .get_transaction_quote(transaction_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Quotes".to_string(), e.to_string()))?;
+        let Some(quote) = quote else {
+            return Ok(market);
+        };
+
+        let locked_in_cents = quote.price_in_cents.max(0) as u64;
+        let limit =
+            locked_in_cents as u128 * (10_000 + self.quotes.max_deviation_bps as u128) / 10_000;
+        if current_in_cents as u128 > limit {
+            log::warn!(
+                "Price of {} moved from {} to {} cents since quote {}, paying transaction {} at the market price",
+                quote.asset,
+                locked_in_cents,
+                current_in_cents,
+                quote.id,
+                transaction_id
+            );
+            return Ok(market);
+        }
+
+        Ok((locked_in_cents, quote.aggregation))
+    }
+
+    async fn new_merchant_transaction(
+        &self,
+        order: DepositOrder,
+        fee_bps: i32,
+        response: &DepositResponse,
+    ) -> Result<Deposit, ServiceError> {
+        self.open_deposit(order, Some(fee_bps), RiskBand::Standard, None, response)
+            .await
+    }
+
+    /// Whether whoever asked for a deposit stopped waiting for it, so the
+    /// steps left would create a charge nobody pays.
+    fn deposit_abandoned(response: &DepositResponse, stage: &str) -> bool {
+        if !response.is_closed() {
+            return false;
+        }
+
+        log::warn!("Deposit request abandoned before {}", stage);
+        metrics::increment(
+            "abandoned_requests_total",
+            &[("request", "deposit"), ("stage", stage)],
+        );
+        true
+    }
+
+    /// Cancels a deposit recorded for a request nobody waits for anymore.
+    /// The deposit is left as is when it can't be, its charge expires unpaid.
+    async fn abandon_deposit(&self, transaction: &transactions::Transaction, stage: &str) {
+        match self
+            .repository
+            .abandon_transaction(&transaction.id, stage)
+            .await
+        {
+            Ok(true) => {
+                log::info!("Abandoned transaction {} cancelled", transaction.id);
+                self.invalidate_user_details(&transaction.user_id).await;
+            }
+            Ok(false) => {}
+            Err(e) => log::error!(
+                "Failed to cancel abandoned transaction {}: {}",
+                transaction.id,
+                e
+            ),
+        }
+    }
+
+    /// Records the transaction, with the delivery the user asked for if any,
+    /// and creates its PIX charge. Consumer limits, scaled to the user's risk
+    /// band, apply unless the deposit pays a merchant. The request is given
+    /// up between steps once its response can't be delivered.
+    async fn open_deposit(
+        &self,
+        order: DepositOrder,
+        merchant_fee_bps: Option<i32>,
+        risk_band: RiskBand,
+        deliver_at: Option<chrono::DateTime<chrono::Utc>>,
+        response: &DepositResponse,
+    ) -> Result<Deposit, ServiceError> {
+        let DepositOrder {
+            user_id,
+            address,
+            amount_in_cents,
+            asset,
+            network,
+        } = order;
+        if self.is_paused() {
+            return Err(ServiceError::Internal("DealerPaused".to_string()));
+        }
+        if self.deposit_closure().await.is_some() {
+            return Err(ServiceError::Internal("DepositsClosed".to_string()));
+        }
+        if !self.is_asset_enabled(&asset).await {
+            return Err(ServiceError::Internal("AssetUnavailable".to_string()));
+        }
+
+        // Requests are validated over HTTP too, this covers every other caller
+        if let Err(fields) = validation::collect(validation::deposit(
+            &address,
+            amount_in_cents,
+            &asset,
+            &network,
+        )) {
+            log::warn!("Refusing deposit for {}: invalid {:?}", user_id, fields);
+            return Err(ServiceError::Internal("InvalidRequest".to_string()));
+        }
+        // Payment URIs are stored and paid as their bare address
+        let payment_uri = PaymentUri::parse(&address, Assets::precision_of(&asset))
+            .map_err(|_| ServiceError::Internal("InvalidRequest".to_string()))?;
+        let address = payment_uri.address;
+
+        let (liquid_tx, liquid_rx) = oneshot::channel();
+        let (pix_tx, pix_rx) = oneshot::channel();
+
+        self.ensure_payout_above_minimum(amount_in_cents, &asset, merchant_fee_bps)
+            .await?;
+        if let Some(requested) = payment_uri.amount {
+            self.ensure_payout_matches_uri(amount_in_cents, &asset, merchant_fee_bps, requested)
+                .await?;
+        }
+
+        if self.block_unfundable_deposits {
+            self.ensure_deposit_is_fundable(amount_in_cents, &asset).await?;
+        }
+
+        if Self::deposit_abandoned(response, "address") {
+            return Err(ServiceError::Internal("RequestAbandoned".to_string()));
+        }
+
         self.liquid_channel
             .send(LiquidRequest::GetNewAddress {
                 response: liquid_tx,
@@ -266,20 +1595,32 @@ impl TransactionRequestHandler {
             )
         })??;
 
-        let transaction = self
-            .repository
-            .new_transaction(
-                &user_id,
-                &address,
-                &fee_address,
-                amount_in_cents,
-                &asset,
-                &network,
-            )
-            .await
-            .map_err(|e| {
-                ServiceError::Repository("TransactionService".to_string(), e.to_string())
-            })?;
+        let row = transactions::NewTransactionRow {
+            user_id: user_id.clone(),
+            address: address.clone(),
+            fee_address: fee_address.clone(),
+            amount_in_cents,
+            asset: asset.clone(),
+            network: network.clone(),
+        };
+        let transaction = if merchant_fee_bps.is_none() {
+            self.repository.new_transaction(&row, risk_band).await
+        } else {
+            self.repository.insert_transaction(&row).await
+        }
+        .map_err(|e| ServiceError::Repository("TransactionService".to_string(), e.to_string()))?;
+
+        if let Some(deliver_at) = deliver_at {
+            self.delivery_repository
+                .schedule(&transaction.id, deliver_at)
+                .await
+                .map_err(|e| ServiceError::Repository("Deliveries".to_string(), e.to_string()))?;
+        }
+
+        if Self::deposit_abandoned(response, "charge") {
+            self.abandon_deposit(&transaction, "charge").await;
+            return Err(ServiceError::Internal("RequestAbandoned".to_string()));
+        }
 
         self.pix_channel
             .send(PixServiceRequest::Deposit {
@@ -307,14 +1648,37 @@ impl TransactionRequestHandler {
                     e.to_string(),
                 )
             })?
-            .map_err(|e| {
-                ServiceError::ExternalService(
+            .map_err(|e| match e {
+                ServiceError::Internal(reason) if reason == "PixUnavailable" => {
+                    ServiceError::Internal(reason)
+                }
+                e => ServiceError::ExternalService(
                     "TransactionService".to_string(),
                     "PixService".to_string(),
                     e.to_string(),
-                )
+                ),
             })?;
 
+        // The charge can't be cancelled, but the deposit shouldn't count
+        // against the user's limits
+        if Self::deposit_abandoned(response, "response") {
+            self.abandon_deposit(&transaction, "response").await;
+            return Err(ServiceError::Internal("RequestAbandoned".to_string()));
+        }
+
+        self.publish_event(
+            EventKind::TransactionCreated,
+            json!({
+                "transaction_id": transaction.id,
+                "user_id": transaction.user_id,
+                "asset": transaction.asset,
+                "network": transaction.network,
+                "amount_in_cents": transaction.amount_in_cents,
+                "merchant_fee_bps": merchant_fee_bps,
+            }),
+        )
+        .await;
+
         Ok(pix_deposit)
     }
 
@@ -323,15 +1687,52 @@ impl TransactionRequestHandler {
         transaction_id: &String,
         status: &String,
     ) -> Result<String, ServiceError> {
-        let _ = self
+        if self
+            .stop_if_cancelled(transaction_id, status == "eulen_depix_sent")
+            .await?
+        {
+            return Ok(transaction_id.clone());
+        }
+
+        // Eulen statuses only apply to deposits that weren't paid yet, a
+        // replayed depix_sent must not start a second payout
+        let updated = self
             .repository
-            .update_transaction_status(transaction_id, status)
+            .transition_status(transaction_id, UNPAID_STATUSES, status)
             .await
             .map_err(|e| {
                 ServiceError::Repository("TransactionService".to_string(), e.to_string())
             })?;
+        let Some(updated) = updated else {
+            log::warn!(
+                "Skipping {} update of transaction {}, it is no longer unpaid",
+                status,
+                transaction_id
+            );
+            metrics::increment("stale_transaction_updates_total", &[("status", status)]);
+            return Ok(transaction_id.clone());
+        };
+        self.invalidate_user_details(&updated.user_id).await;
+
+        match status.as_str() {
+            "eulen_depix_sent" => self.track("deposit_paid", &updated),
+            "eulen_expired" | "eulen_canceled" | "eulen_error" => {
+                self.track("deposit_expired", &updated)
+            }
+            _ => {}
+        }
 
         if status == "eulen_depix_sent" {
+            self.publish_event(
+                EventKind::PixPaid,
+                json!({
+                    "transaction_id": updated.id,
+                    "asset": updated.asset,
+                    "amount_in_cents": updated.amount_in_cents,
+                }),
+            )
+            .await;
+
             let transaction = self
                 .repository
                 .get_transaction(&transaction_id)
@@ -354,22 +1755,8 @@ impl TransactionRequestHandler {
                     )));
                 }
                 Some(transaction) => {
-                    match self.finish_transaction(transaction).await {
-                        Ok(_) => {}
-                        Err(e) => {
-                            // If the error is due to insufficient balance, we'll just log it
-                            // The transaction was already added to the pending queue in finish_transaction
-                            if let ServiceError::Internal(msg) = &e {
-                                if msg == "InsufficientBalance" {
-                                    log::warn!(
-                                        "Transaction {} queued due to insufficient balance",
-                                        transaction_id
-                                    );
-                                    return Ok(transaction_id.clone());
-                                }
-                            }
-                            return Err(e);
-                        }
+                    if !self.park_until_delivery(&transaction).await? {
+                        self.start_payout(transaction).await?;
                     }
                 }
             }
@@ -378,233 +1765,2090 @@ impl TransactionRequestHandler {
         Ok(transaction_id.clone())
     }
 
-    async fn update_fee_collected(
+    /// Pays out a paid transaction. A payout that was queued or stopped
+    /// isn't an error, it's picked up from there.
+    async fn start_payout(
         &self,
-        transaction_id: &String,
-        fee_collected: i32,
-    ) -> Result<String, ServiceError> {
-        let _ = self
-            .repository
-            .update_fee_collected(transaction_id, fee_collected)
+        transaction: transactions::Transaction,
+    ) -> Result<(), ServiceError> {
+        let transaction_id = transaction.id.clone();
+        match self
+            .finish_transaction(transaction, FeePriority::Normal)
             .await
-            .map_err(|e| {
-                ServiceError::Repository("TransactionService".to_string(), e.to_string())
-            })?;
+        {
+            Ok(_) => {}
+            Err(e) => {
+                // If the error is due to insufficient balance, we'll just log it
+                // The transaction was already added to the pending queue in finish_transaction
+                if let ServiceError::Internal(msg) = &e {
+                    if msg == "DealerPaused" {
+                        log::warn!(
+                            "Transaction {} queued while payouts are paused",
+                            transaction_id
+                        );
+                        return Ok(());
+                    }
+                    if msg == "AssetUnavailable" {
+                        log::warn!(
+                            "Transaction {} queued while its asset is disabled",
+                            transaction_id
+                        );
+                        return Ok(());
+                    }
+                    if msg == "InsufficientBalance" {
+                        log::warn!(
+                            "Transaction {} queued due to insufficient balance",
+                            transaction_id
+                        );
+                        return Ok(());
+                    }
+                    if msg == "PricingUnavailable" {
+                        log::warn!(
+                            "Transaction {} parked until its price is fresh",
+                            transaction_id
+                        );
+                        return Ok(());
+                    }
+                    if msg == "PayoutClaimed" {
+                        log::warn!("Transaction {} is already being paid out", transaction_id);
+                        return Ok(());
+                    }
+                }
+                if is_compliance_stop(&e) {
+                    log::warn!("Transaction {} stopped: {}", transaction_id, e);
+                    return Ok(());
+                }
+                return Err(e);
+            }
+        }
 
-        Ok(transaction_id.clone())
+        Ok(())
     }
 
-    async fn finish_transaction(
+    /// Parks a paid transaction in `scheduled` when the user asked to receive
+    /// it later. True when it was; a delivery whose time already came is
+    /// released and paid out now.
+    async fn park_until_delivery(
         &self,
-        transaction: transactions::Transaction,
-    ) -> Result<(), ServiceError> {
-        let pset = self.continue_with_transaction(transaction.clone()).await?;
-        let signed_pset = self
-            .sign_transaction(pset)
+        transaction: &transactions::Transaction,
+    ) -> Result<bool, ServiceError> {
+        let delivery = self
+            .delivery_repository
+            .get_scheduled(&transaction.id)
             .await
-            .map_err(|e| {
-                log::error!("Could not sign transaction: {:?}", e);
-                ServiceError::Internal(format!("Could not sign transaction: {}", e))
-            })?;
-
-        log::info!("Signed transaction: {:?}", signed_pset);
+            .map_err(|e| ServiceError::Repository("Deliveries".to_string(), e.to_string()))?;
+        let Some(delivery) = delivery else {
+            return Ok(false);
+        };
 
-        self.finalize_transaction(signed_pset).await?;
+        if delivery.deliver_at <= self.clock.now() {
+            self.delivery_repository
+                .release(&transaction.id)
+                .await
+                .map_err(|e| ServiceError::Repository("Deliveries".to_string(), e.to_string()))?;
+            metrics::increment("scheduled_deliveries_total", &[("outcome", "released")]);
+            return Ok(false);
+        }
 
         self.repository
-            .update_transaction_status(&transaction.id, &"finished".to_string())
+            .update_transaction_status(&transaction.id, &"scheduled".to_string())
             .await
             .map_err(|e| {
-                log::error!("Could not update transaction status: {:?}", e);
-                ServiceError::Database(format!("Could not update transaction status: {}", e))
+                ServiceError::Repository("TransactionService".to_string(), e.to_string())
+            })?;
+        self.invalidate_user_details(&transaction.user_id).await;
+        log::info!(
+            "Transaction {} scheduled for delivery at {}",
+            transaction.id,
+            delivery.deliver_at
+        );
+        metrics::increment("scheduled_deliveries_total", &[("outcome", "parked")]);
+
+        Ok(true)
+    }
+
+    /// Pays out the parked transactions whose delivery time came.
+    async fn release_due_deliveries(&self) -> Result<(), anyhow::Error> {
+        let due = self.delivery_repository.get_due(self.clock.now()).await?;
+
+        for transaction_id in due {
+            if !self.delivery_repository.release(&transaction_id).await? {
+                continue;
+            }
+            metrics::increment("scheduled_deliveries_total", &[("outcome", "released")]);
+
+            let Some(transaction) = self.repository.get_transaction(&transaction_id).await? else {
+                continue;
+            };
+            self.invalidate_user_details(&transaction.user_id).await;
+            log::info!(
+                "Releasing scheduled delivery of transaction {}",
+                transaction_id
+            );
+            if let Err(e) = self.start_payout(transaction).await {
+                log::error!(
+                    "Payout of scheduled transaction {} failed: {}",
+                    transaction_id,
+                    e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parks a paid transaction in `pricing_unavailable`, out of the queue,
+    /// until its price is fresh again. False when it wasn't awaiting payout.
+    async fn park_until_priced(&self, transaction_id: &str) -> Result<bool, ServiceError> {
+        let parked = self
+            .repository
+            .transition_status(transaction_id, &["eulen_depix_sent"], "pricing_unavailable")
+            .await
+            .map_err(|e| {
+                ServiceError::Repository("TransactionService".to_string(), e.to_string())
+            })?;
+        let Some(transaction) = parked else {
+            return Ok(false);
+        };
+
+        self.take_pending(transaction_id).await;
+        self.invalidate_user_details(&transaction.user_id).await;
+        metrics::increment("payout_pricing_total", &[("outcome", "parked")]);
+
+        Ok(true)
+    }
+
+    /// Pays out the transactions parked in `pricing_unavailable` whose asset
+    /// can be priced again. The price is checked once more when the payout
+    /// is priced.
+    async fn resume_priced_payouts(&self) -> Result<(), anyhow::Error> {
+        let parked = self
+            .repository
+            .get_transactions_by_status("pricing_unavailable")
+            .await?;
+
+        let mut priced: HashMap<String, bool> = HashMap::new();
+        for transaction in parked {
+            let is_priced = match priced.get(&transaction.asset) {
+                Some(is_priced) => *is_priced,
+                None => {
+                    let is_priced = match self.request_price(&transaction.asset).await {
+                        Ok(price) => self
+                            .pricing_watchdog
+                            .check(&price, self.clock.now())
+                            .is_none(),
+                        Err(e) => {
+                            log::warn!("Could not price {}: {}", transaction.asset, e);
+                            false
+                        }
+                    };
+                    priced.insert(transaction.asset.clone(), is_priced);
+                    is_priced
+                }
+            };
+            if !is_priced {
+                continue;
+            }
+
+            let Some(transaction) = self
+                .repository
+                .transition_status(
+                    &transaction.id,
+                    &["pricing_unavailable"],
+                    "eulen_depix_sent",
+                )
+                .await?
+            else {
+                continue;
+            };
+            metrics::increment("payout_pricing_total", &[("outcome", "resumed")]);
+            self.invalidate_user_details(&transaction.user_id).await;
+            log::info!(
+                "Resuming payout of transaction {} now that it can be priced",
+                transaction.id
+            );
+
+            let transaction_id = transaction.id.clone();
+            if let Err(e) = self.start_payout(transaction).await {
+                log::error!(
+                    "Payout of transaction {} failed after pricing recovered: {}",
+                    transaction_id,
+                    e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn update_fee_collected(
+        &self,
+        transaction_id: &String,
+        fee_collected: i32,
+    ) -> Result<String, ServiceError> {
+        let _ = self
+            .repository
+            .update_fee_collected(transaction_id, fee_collected)
+            .await
+            .map_err(|e| {
+                ServiceError::Repository("TransactionService".to_string(), e.to_string())
+            })?;
+
+        Ok(transaction_id.clone())
+    }
+
+    async fn get_pending_transactions(&self) -> Vec<transactions::PendingEntry> {
+        let pending_txs = self.pending_transactions.lock().await;
+
+        pending_txs
+            .iter()
+            .map(|pending_tx| transactions::PendingEntry {
+                transaction_id: pending_tx.transaction.id.clone(),
+                user_id: pending_tx.transaction.user_id.clone(),
+                asset: pending_tx.transaction.asset.clone(),
+                amount_in_cents: pending_tx.transaction.amount_in_cents,
+                attempts: pending_tx.attempts,
+                last_attempt: pending_tx.last_attempt,
+                swap_id: pending_tx.swap_id,
+                last_error: pending_tx.last_error.clone(),
+            })
+            .collect()
+    }
+
+    async fn get_unreconciled_transactions(
+        &self,
+    ) -> Result<Vec<transactions::Transaction>, ServiceError> {
+        self.repository
+            .get_transactions_by_status("eulen_depix_sent")
+            .await
+            .map_err(|e| ServiceError::Repository("TransactionService".to_string(), e.to_string()))
+    }
+
+    /// Puts a paid but undelivered transaction back into the pending queue.
+    /// Returns false if it is already queued or no longer awaiting payout.
+    async fn requeue_transaction(&self, transaction_id: &String) -> Result<bool, ServiceError> {
+        let transaction = self
+            .repository
+            .get_transaction(transaction_id)
+            .await
+            .map_err(|e| ServiceError::Repository("TransactionService".to_string(), e.to_string()))?
+            .ok_or(ServiceError::Database(format!(
+                "Transaction not found: {}.",
+                transaction_id
+            )))?;
+
+        if transaction.status != "eulen_depix_sent" {
+            return Ok(false);
+        }
+
+        let mut pending_txs = self.pending_transactions.lock().await;
+        if pending_txs
+            .iter()
+            .any(|pending_tx| &pending_tx.transaction.id == transaction_id)
+        {
+            return Ok(false);
+        }
+
+        pending_txs.push_back(PendingTransaction {
+            transaction,
+            attempts: 0,
+            last_attempt: self.clock.now(),
+            queued_at: self.clock.now(),
+            swap_id: None,
+            last_error: None,
+        });
+
+        Ok(true)
+    }
+
+    async fn take_pending(&self, transaction_id: &str) -> Option<PendingTransaction> {
+        let mut pending_txs = self.pending_transactions.lock().await;
+        let position = pending_txs
+            .iter()
+            .position(|pending_tx| pending_tx.transaction.id == transaction_id)?;
+        pending_txs.remove(position)
+    }
+
+    async fn retry_pending(
+        &self,
+        transaction_id: &str,
+        requested_by: &str,
+    ) -> Result<Option<bool>, ServiceError> {
+        if self.is_paused() {
+            return Err(ServiceError::Internal("DealerPaused".to_string()));
+        }
+
+        let Some(pending_tx) = self.take_pending(transaction_id).await else {
+            return Ok(None);
+        };
+
+        if let Err(e) = self
+            .repository
+            .record_payout_retry(transaction_id, requested_by)
+            .await
+        {
+            self.requeue_pending(pending_tx, false).await;
+            return Err(ServiceError::Repository(
+                "TransactionService".to_string(),
+                e.to_string(),
+            ));
+        }
+
+        log::info!(
+            "Pending transaction {} retried by {}",
+            transaction_id,
+            requested_by
+        );
+        self.process_pending_batch(vec![pending_tx]).await;
+
+        let still_queued = self
+            .pending_transactions
+            .lock()
+            .await
+            .iter()
+            .any(|pending_tx| pending_tx.transaction.id == transaction_id);
+        Ok(Some(still_queued))
+    }
+
+    async fn drop_pending(
+        &self,
+        transaction_id: &str,
+        requested_by: &str,
+        reason: &str,
+    ) -> Result<bool, ServiceError> {
+        let Some(pending_tx) = self.take_pending(transaction_id).await else {
+            return Ok(false);
+        };
+
+        let transaction = match self
+            .repository
+            .fail_transaction(transaction_id, requested_by, reason)
+            .await
+        {
+            Ok(Some(transaction)) => transaction,
+            // Paid out or otherwise settled meanwhile, nothing left to drop
+            Ok(None) => return Ok(false),
+            Err(e) => {
+                self.requeue_pending(pending_tx, false).await;
+                return Err(ServiceError::Repository(
+                    "TransactionService".to_string(),
+                    e.to_string(),
+                ));
+            }
+        };
+
+        let queue_length = self.pending_transactions.lock().await.len();
+        metrics::set_gauge("payout_queue_length", &[], queue_length as f64);
+        self.invalidate_user_details(&transaction.user_id).await;
+        self.release_campaign(transaction_id).await;
+
+        log::warn!(
+            "Pending transaction {} dropped by {}: {}",
+            transaction_id,
+            requested_by,
+            reason
+        );
+        self.send_alert(
+            "Payout dropped",
+            format!(
+                "Payout of transaction {} ({} cents) was dropped by {}: {}; refund the PIX payment",
+                transaction_id, transaction.amount_in_cents, requested_by, reason
+            ),
+        )
+        .await;
+
+        Ok(true)
+    }
+
+    /// Pays a transaction out as a saga: each step is recorded as it
+    /// completes, and a failed step is recorded and compensated, so the
+    /// payout is left either finished, queued for a retry or held.
+    async fn finish_transaction(
+        &self,
+        transaction: transactions::Transaction,
+        priority: FeePriority,
+    ) -> Result<(), ServiceError> {
+        if self.is_paused() {
+            self.requeue_transaction(&transaction.id).await?;
+            return Err(ServiceError::Internal("DealerPaused".to_string()));
+        }
+        if !self.is_asset_enabled(&transaction.asset).await {
+            self.requeue_transaction(&transaction.id).await?;
+            return Err(ServiceError::Internal("AssetUnavailable".to_string()));
+        }
+
+        // Held until the payout is done, so the recovery scan leaves it alone
+        let Some(_claim) = self.claim_payout(&transaction.id).await? else {
+            return Err(ServiceError::Internal("PayoutClaimed".to_string()));
+        };
+
+        // Screen the payout address before anything moves
+        self.screen_payout_address(&transaction).await?;
+        self.check_exposure(&transaction).await?;
+        self.check_disputes(&transaction).await?;
+        // Dust payouts are stopped before a swap is started for them
+        if let Err(e) = self.check_dust(&transaction).await {
+            return Err(self
+                .fail_step(&transaction.id, PayoutStep::PriceLocked, e)
+                .await);
+        }
+        self.ensure_payout_liquidity(&transaction, priority).await?;
+
+        let broadcast = match self.run_payout(&transaction, priority).await {
+            Ok(broadcast) => broadcast,
+            Err((step, e)) => return Err(self.fail_step(&transaction.id, step, e).await),
+        };
+
+        let txid = broadcast.txid;
+        metrics::add(
+            "payout_network_fees_total",
+            &[("priority", priority.as_str())],
+            broadcast.network_fee,
+        );
+
+        self.repository
+            .update_transaction_status(&transaction.id, &"finished".to_string())
+            .await
+            .map_err(|e| {
+                log::error!("Could not update transaction status: {:?}", e);
+                ServiceError::Database(format!("Could not update transaction status: {}", e))
             })?;
+        self.invalidate_user_details(&transaction.user_id).await;
+        // Last updated when the PIX payment was confirmed
+        let delivery = self.clock.now() - transaction.updated_at;
+        metrics::observe(
+            "payout_delivery_seconds",
+            &[("asset", &transaction.asset)],
+            delivery.num_milliseconds() as f64 / 1000.0,
+        );
+        self.record_exposure(&transaction.id).await;
+        self.publish_payout_sent(&transaction, &txid).await;
+        self.track("payout_delivered", &transaction);
+        self.send_receipt(&transaction.id).await;
+        self.invoice_fee(&transaction.id).await;
+
+        Ok(())
+    }
+
+    /// Records a failed step of the payout and compensates it.
+    async fn fail_step(
+        &self,
+        transaction_id: &String,
+        step: PayoutStep,
+        error: ServiceError,
+    ) -> ServiceError {
+        log::error!(
+            "Payout of {} failed at {}: {}",
+            transaction_id,
+            step.as_str(),
+            error
+        );
+        metrics::increment("payout_steps_failed_total", &[("step", step.as_str())]);
+        self.record_step(
+            transaction_id,
+            step,
+            StepStatus::Failed,
+            json!({ "error": error.to_string() }),
+        )
+        .await;
+        self.compensate_payout(transaction_id, step, &error).await;
+
+        error
+    }
+
+    /// Stops a payout the dust floor would hold or refund, at the current
+    /// price, before the wallet is refilled for it.
+    async fn check_dust(
+        &self,
+        transaction: &transactions::Transaction,
+    ) -> Result<(), ServiceError> {
+        let (asset_price_in_cents, _) = self.lock_payout_price(transaction).await?;
+        self.payout_amounts(transaction, asset_price_in_cents)
+            .await
+            .map(|_| ())
+    }
+
+    /// Locks the payout of a transaction still waiting for it. None when
+    /// another worker or the recovery scan holds it, or it was settled since.
+    async fn claim_payout(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<PayoutClaim>, ServiceError> {
+        let claim = self
+            .payout_repository
+            .claim(transaction_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Payouts".to_string(), e.to_string()))?;
+        let Some(claim) = claim else {
+            log::debug!("Payout of {} is claimed elsewhere", transaction_id);
+            return Ok(None);
+        };
+
+        let status = self
+            .repository
+            .get_transaction(&transaction_id.to_string())
+            .await
+            .map_err(|e| ServiceError::Repository("TransactionService".to_string(), e.to_string()))?
+            .map(|transaction| transaction.status);
+        if status.as_deref() != Some("eulen_depix_sent") {
+            return Ok(None);
+        }
+
+        Ok(Some(claim))
+    }
+
+    /// Steps of the payout from the price to the broadcast. Errors come with
+    /// the step they stopped at.
+    async fn run_payout(
+        &self,
+        transaction: &transactions::Transaction,
+        priority: FeePriority,
+    ) -> Result<BroadcastTransaction, (PayoutStep, ServiceError)> {
+        let step = PayoutStep::PriceLocked;
+        let (asset_price_in_cents, aggregation) = self
+            .lock_payout_price(transaction)
+            .await
+            .map_err(|e| (step, e))?;
+        self.record_step(
+            &transaction.id,
+            step,
+            StepStatus::Completed,
+            json!({ "price_in_cents": asset_price_in_cents, "aggregation": aggregation }),
+        )
+        .await;
+
+        let step = PayoutStep::FeeComputed;
+        let recipients = self
+            .compute_payout(transaction, asset_price_in_cents, aggregation.as_deref())
+            .await
+            .map_err(|e| (step, e))?;
+        let amounts: Vec<serde_json::Value> = recipients
+            .iter()
+            .map(|recipient| json!({ "address": recipient.address, "amount": recipient.satoshi }))
+            .collect();
+        self.record_step(
+            &transaction.id,
+            step,
+            StepStatus::Completed,
+            json!({ "recipients": amounts }),
+        )
+        .await;
+
+        let step = PayoutStep::PsetBuilt;
+        let pset = self
+            .build_payout(&transaction.id, recipients.clone(), priority)
+            .await
+            .map_err(|e| (step, e))?;
+        self.record_step(&transaction.id, step, StepStatus::Completed, json!({}))
+            .await;
+
+        let step = PayoutStep::PsetVerified;
+        self.verify_payout(transaction, verify::PayoutKind::Final, &recipients, &pset)
+            .await
+            .map_err(|e| (step, e))?;
+        self.record_step(&transaction.id, step, StepStatus::Completed, json!({}))
+            .await;
+
+        let step = PayoutStep::Signed;
+        let signed_pset = self.sign_transaction(pset).await.map_err(|e| {
+            log::error!("Could not sign transaction: {:?}", e);
+            (
+                step,
+                ServiceError::Internal(format!("Could not sign transaction: {}", e)),
+            )
+        })?;
+        self.record_step(&transaction.id, step, StepStatus::Completed, json!({}))
+            .await;
+
+        log::info!("Signed transaction: {:?}", signed_pset);
+
+        let step = PayoutStep::Broadcast;
+        let broadcast = self
+            .broadcast_payout(&transaction.id, signed_pset)
+            .await
+            .map_err(|e| (step, e))?;
+        self.record_step(
+            &transaction.id,
+            step,
+            StepStatus::Completed,
+            json!({ "txid": broadcast.txid, "network_fee": broadcast.network_fee }),
+        )
+        .await;
+
+        Ok(broadcast)
+    }
+
+    /// Records a payout step. A step that could not be recorded already
+    /// happened, so the failure is only logged; the payout and transaction
+    /// rows are what recovery falls back on.
+    async fn record_step(
+        &self,
+        transaction_id: &str,
+        step: PayoutStep,
+        status: StepStatus,
+        details: serde_json::Value,
+    ) {
+        if let Err(e) = self
+            .saga_repository
+            .record_step(transaction_id, step, status, details)
+            .await
+        {
+            log::error!(
+                "Could not record step {} of payout {}: {}",
+                step.as_str(),
+                transaction_id,
+                e
+            );
+        }
+    }
+
+    /// Undoes what a failed step left behind and queues the payout for a
+    /// retry. Nothing has left the wallet before the broadcast, so earlier
+    /// steps only need the retry; a broadcast that failed is forgotten first.
+    /// Payouts stopped for review stay where they are. Unless it may have
+    /// been broadcast, the payout gives up its campaign redemption, which
+    /// the next attempt takes again if the campaign is still available.
+    async fn compensate_payout(
+        &self,
+        transaction_id: &String,
+        step: PayoutStep,
+        error: &ServiceError,
+    ) {
+        if step != PayoutStep::Broadcast {
+            self.release_campaign(transaction_id).await;
+        }
+        if is_compliance_stop(error) {
+            return;
+        }
+
+        // Resumed by the pricing recovery job instead of the queue
+        if matches!(error, ServiceError::Internal(msg) if msg == "PricingUnavailable") {
+            match self.park_until_priced(transaction_id).await {
+                Ok(true) => {
+                    self.record_step(
+                        transaction_id,
+                        step,
+                        StepStatus::Compensated,
+                        json!({ "action": "parked" }),
+                    )
+                    .await;
+                }
+                Ok(false) => {}
+                Err(e) => log::error!("Could not park payout {}: {}", transaction_id, e),
+            }
+            return;
+        }
+
+        // Electrum may have taken the transaction before the error, so the
+        // payout row stays and only a reviewer checking the chain retries it
+        if step == PayoutStep::Broadcast {
+            let reason = format!(
+                "Payout broadcast failed and may be on chain ({}); check the chain before approving",
+                error
+            );
+            match self
+                .hold_transaction(transaction_id, "broadcast", &reason)
+                .await
+            {
+                Ok(()) => {
+                    self.record_step(
+                        transaction_id,
+                        step,
+                        StepStatus::Compensated,
+                        json!({ "action": "held" }),
+                    )
+                    .await;
+                }
+                // The recovery scan holds it for review instead
+                Err(e) => log::error!("Could not hold payout {}: {}", transaction_id, e),
+            }
+            return;
+        }
+
+        match self.requeue_transaction(transaction_id).await {
+            Ok(_) => {
+                self.record_step(
+                    transaction_id,
+                    step,
+                    StepStatus::Compensated,
+                    json!({ "action": "requeued" }),
+                )
+                .await;
+            }
+            Err(e) => log::error!("Could not requeue payout {}: {}", transaction_id, e),
+        }
+    }
+
+    /// Drops the campaign redemption of a payout that wasn't sent. One with
+    /// a part sent already keeps it, since the part was paid with it.
+    async fn release_campaign(&self, transaction_id: &str) {
+        match self.partial_total(transaction_id).await {
+            Ok(0) => {}
+            Ok(_) => return,
+            Err(e) => {
+                log::error!(
+                    "Could not check the partial payouts of {}: {}",
+                    transaction_id,
+                    e
+                );
+                return;
+            }
+        }
+
+        match self.campaign_repository.release(transaction_id).await {
+            Ok(true) => log::info!("Released the campaign redemption of {}", transaction_id),
+            Ok(false) => {}
+            Err(e) => log::error!(
+                "Could not release the campaign redemption of {}: {}",
+                transaction_id,
+                e
+            ),
+        }
+    }
+
+    /// Txid of the broadcast step of the latest attempt, when the payout
+    /// row missed it.
+    async fn saga_txid(&self, transaction_id: &str) -> Result<Option<String>, ServiceError> {
+        let steps = self
+            .saga_repository
+            .get_latest_attempt(transaction_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Sagas".to_string(), e.to_string()))?;
+
+        Ok(steps
+            .iter()
+            .filter(|step| {
+                PayoutStep::parse(&step.step) == Some(PayoutStep::Broadcast)
+                    && step.status == StepStatus::Completed.as_str()
+            })
+            .filter_map(|step| serde_json::from_str::<serde_json::Value>(&step.details).ok())
+            .find_map(|details| details["txid"].as_str().map(str::to_string)))
+    }
+
+    async fn record_exposure(&self, transaction_id: &str) {
+        if let Err(e) = self
+            .hedging_channel
+            .send(HedgingRequest::RecordPayout {
+                transaction_id: transaction_id.to_string(),
+            })
+            .await
+        {
+            log::warn!("Failed to notify hedging service of payout: {:?}", e);
+        }
+    }
+
+    async fn send_receipt(&self, transaction_id: &str) {
+        if let Err(e) = self
+            .notification_channel
+            .send(NotificationRequest::Receipt {
+                transaction_id: transaction_id.to_string(),
+            })
+            .await
+        {
+            log::warn!("Failed to queue receipt of {}: {:?}", transaction_id, e);
+        }
+    }
+
+    async fn invoice_fee(&self, transaction_id: &str) {
+        if let Err(e) = self
+            .merchant_channel
+            .send(MerchantRequest::InvoiceFee {
+                transaction_id: transaction_id.to_string(),
+            })
+            .await
+        {
+            log::warn!("Failed to queue fee invoice of {}: {:?}", transaction_id, e);
+        }
+    }
+
+    async fn publish_event(&self, kind: EventKind, data: serde_json::Value) {
+        if let Err(e) = self
+            .event_channel
+            .send(EventRequest::Publish {
+                event: DomainEvent::new(kind, data),
+            })
+            .await
+        {
+            log::warn!("Failed to queue {} event: {:?}", kind.as_str(), e);
+        }
+    }
+
+    async fn publish_payout_sent(&self, transaction: &transactions::Transaction, txid: &str) {
+        self.publish_event(
+            EventKind::PayoutSent,
+            json!({
+                "transaction_id": transaction.id,
+                "asset": transaction.asset,
+                "address": transaction.address,
+                "txid": txid,
+            }),
+        )
+        .await;
+    }
+
+    /// Funnel step of a deposit, for product analytics. Dropped rather than
+    /// waited for when the analytics service falls behind.
+    fn track(&self, name: &'static str, transaction: &transactions::Transaction) {
+        let request = AnalyticsRequest::Track {
+            name,
+            user_id: transaction.user_id.clone(),
+            transaction_id: Some(transaction.id.clone()),
+            properties: json!({
+                "asset": transaction.asset,
+                "network": transaction.network,
+                "amount_tier": amount_tier(transaction.amount_in_cents),
+            }),
+        };
+        if self.analytics_channel.try_send(request).is_err() {
+            metrics::increment("analytics_events_dropped_total", &[("event", name)]);
+        }
+    }
+
+    /// Only deposits whose charge wasn't paid yet can be cancelled. The PIX
+    /// charge is left to expire at Eulen, which can't cancel it.
+    async fn cancel_transaction(
+        &self,
+        transaction_id: &String,
+        user_id: &String,
+    ) -> Result<Option<transactions::Transaction>, ServiceError> {
+        let cancelled = self
+            .repository
+            .cancel_transaction(transaction_id, user_id)
+            .await
+            .map_err(|e| {
+                ServiceError::Repository("TransactionService".to_string(), e.to_string())
+            })?;
+
+        if let Some(transaction) = cancelled {
+            log::info!("Transaction {} cancelled by its user", transaction_id);
+            metrics::increment("transactions_cancelled_total", &[]);
+            self.track("deposit_cancelled", &transaction);
+            self.invalidate_user_details(user_id).await;
+            return Ok(Some(transaction));
+        }
+
+        // Paid but waiting for its delivery, the payment goes back
+        let cancelled = self
+            .delivery_repository
+            .cancel(transaction_id, user_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Deliveries".to_string(), e.to_string()))?;
+        if let Some(transaction) = cancelled {
+            log::info!(
+                "Scheduled delivery of transaction {} cancelled by its user",
+                transaction_id
+            );
+            metrics::increment("scheduled_deliveries_total", &[("outcome", "cancelled")]);
+            self.track("deposit_cancelled", &transaction);
+            self.invalidate_user_details(user_id).await;
+            self.send_alert(
+                "Refund requested",
+                format!(
+                    "Transaction {} of {} cents was cancelled before its scheduled delivery; refund the PIX payment",
+                    transaction_id, transaction.amount_in_cents
+                ),
+            )
+            .await;
+            return Ok(Some(transaction));
+        }
+
+        let transaction = self
+            .repository
+            .get_transaction(transaction_id)
+            .await
+            .map_err(|e| {
+                ServiceError::Repository("TransactionService".to_string(), e.to_string())
+            })?;
+        match transaction {
+            Some(transaction) if &transaction.user_id == user_id => Err(ServiceError::Internal(
+                "TransactionNotCancellable".to_string(),
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    /// Status updates arriving after the user cancelled the deposit leave it
+    /// cancelled. True when it was; a payment that still went through is
+    /// flagged for refund.
+    async fn stop_if_cancelled(
+        &self,
+        transaction_id: &String,
+        paid: bool,
+    ) -> Result<bool, ServiceError> {
+        let transaction = self
+            .repository
+            .get_transaction(transaction_id)
+            .await
+            .map_err(|e| {
+                ServiceError::Repository("TransactionService".to_string(), e.to_string())
+            })?;
+        let Some(transaction) = transaction.filter(|transaction| transaction.status == "cancelled")
+        else {
+            return Ok(false);
+        };
+
+        if !paid {
+            log::info!(
+                "Ignoring update of cancelled transaction {}",
+                transaction_id
+            );
+            return Ok(true);
+        }
+
+        log::warn!("Cancelled transaction {} was paid", transaction_id);
+        metrics::increment("cancelled_transactions_paid_total", &[]);
+        self.repository
+            .update_transaction_status(transaction_id, &"refund_requested".to_string())
+            .await
+            .map_err(|e| {
+                ServiceError::Repository("TransactionService".to_string(), e.to_string())
+            })?;
+        self.invalidate_user_details(&transaction.user_id).await;
+        self.send_alert(
+            "Refund requested",
+            format!(
+                "Transaction {} of {} cents was paid after being cancelled; refund the PIX payment",
+                transaction_id, transaction.amount_in_cents
+            ),
+        )
+        .await;
+
+        Ok(true)
+    }
+
+    async fn invalidate_user_details(&self, user_id: &str) {
+        if let Err(e) = self
+            .user_channel
+            .send(UserRequest::InvalidateUserDetails {
+                id: user_id.to_string(),
+            })
+            .await
+        {
+            log::warn!("Failed to invalidate user details cache: {:?}", e);
+        }
+    }
+
+    async fn request_asset_price(&self, asset: &String) -> Result<u64, ServiceError> {
+        let asset_price = self.request_price(asset).await?;
+        Ok(price_in_cents(&asset_price))
+    }
+
+    async fn request_price(&self, asset: &str) -> Result<AssetPrice, ServiceError> {
+        let (price_tx, price_rx) = oneshot::channel();
+        let asset_object = Assets::from_hex(asset)
+            .map_err(|e| {
+                log::error!("Invalid asset: {:?}", e);
+                ServiceError::Internal("Invalid asset".to_string())
+            })?;
+        self.price_channel
+            .send(PriceRequest::GetPrice {
+                asset: asset_object,
+                currency: self.quote_currency,
+                response: price_tx,
+            })
+            .await
+            .map_err(|err| {
+                ServiceError::Communication("Transactions => Price".to_string(), err.to_string())
+            })?;
+
+        let asset_price = price_rx.await.map_err(|e| {
+            ServiceError::ExternalService(
+                "TransactionService".to_string(),
+                "PriceService".to_string(),
+                e.to_string(),
+            )
+        })??;
+
+        asset_price.ok_or(ServiceError::Internal("Asset price not found".to_string()))
+    }
+
+    async fn check_for_referral(&self, user_id: &String) -> Result<Option<String>, ServiceError> {
+        let (referral_addr_tx, referral_addr_rx) = oneshot::channel();
+        self.user_channel
+            .send(UserRequest::GetUserReferrerAddress {
+                id: user_id.clone(),
+                response: referral_addr_tx,
+            })
+            .await
+            .map_err(|e| {
+                log::error!("Failed to send request to user service: {:?}", e);
+                ServiceError::Communication("Transaction => User".to_string(), e.to_string())
+            })?;
+
+        match referral_addr_rx.await {
+            Ok(Ok(addr)) => Ok(addr),
+            Ok(Err(addr)) => {
+                log::error!("Failed to get user referrer address: {}", addr);
+                Ok(None)
+            }
+            Err(e) => {
+                log::error!("Failed to get user referrer address: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Discounts the fee by the best campaign the purchase is eligible for
+    /// and records the redemption. A payout resumed after a restart keeps the
+    /// discount it was given.
+    async fn apply_campaign(
+        &self,
+        transaction: &transactions::Transaction,
+        fee_in_asset: u64,
+    ) -> Result<u64, ServiceError> {
+        let repository_error =
+            |e: anyhow::Error| ServiceError::Repository("Campaigns".to_string(), e.to_string());
+
+        if let Some(redemption) = self
+            .campaign_repository
+            .get_redemption(&transaction.id)
+            .await
+            .map_err(repository_error)?
+        {
+            return Ok(fee_in_asset.saturating_sub(redemption.fee_discount as u64));
+        }
+
+        let campaigns = self
+            .campaign_repository
+            .get_available_campaigns(&transaction.user_id)
+            .await
+            .map_err(repository_error)?;
+
+        let mut has_previous_purchase = None;
+        for campaign in campaigns {
+            if !campaign.applies_to(transaction.amount_in_cents, &transaction.asset) {
+                continue;
+            }
+
+            if campaign.first_purchase_only {
+                let previous = match has_previous_purchase {
+                    Some(previous) => previous,
+                    None => {
+                        let previous = self
+                            .campaign_repository
+                            .has_previous_purchase(&transaction.user_id, &transaction.id)
+                            .await
+                            .map_err(repository_error)?;
+                        has_previous_purchase = Some(previous);
+                        previous
+                    }
+                };
+                if previous {
+                    continue;
+                }
+            }
+
+            let discount = fees::campaign_discount(fee_in_asset, campaign.fee_discount_bps)
+                .map_err(fee_error)?;
+
+            // Another purchase of the user may have redeemed it meanwhile
+            let redeemed = self
+                .campaign_repository
+                .redeem(
+                    &campaign.id,
+                    &transaction.user_id,
+                    &transaction.id,
+                    discount,
+                )
+                .await
+                .map_err(repository_error)?;
+            if redeemed {
+                log::info!(
+                    "Campaign {} waived {} of the fee of transaction {}",
+                    campaign.name,
+                    discount,
+                    transaction.id
+                );
+                metrics::increment(
+                    "campaign_redemptions_total",
+                    &[("campaign", &campaign.name)],
+                );
+                return Ok(fee_in_asset - discount);
+            }
+        }
+
+        Ok(fee_in_asset)
+    }
+
+    async fn create_campaign(&self, campaign: NewCampaign) -> Result<Campaign, ServiceError> {
+        if campaign.name.trim().is_empty()
+            || !(0..=10_000).contains(&campaign.fee_discount_bps)
+            || campaign.ends_at <= campaign.starts_at
+            || campaign.max_amount_in_cents.is_some_and(|max| max <= 0)
+        {
+            return Err(ServiceError::Internal("Invalid campaign".to_string()));
+        }
+
+        self.campaign_repository
+            .insert_campaign(&campaign)
+            .await
+            .map_err(|e| ServiceError::Repository("Campaigns".to_string(), e.to_string()))
+    }
+
+    /// Queues the payout and starts a swap to refill its asset when the
+    /// wallet is short on it, after paying the part it covers when partial
+    /// payouts are on.
+    async fn ensure_payout_liquidity(
+        &self,
+        transaction: &transactions::Transaction,
+        priority: FeePriority,
+    ) -> Result<(), ServiceError> {
+        if let Ok(has_sufficient_balance) = self.check_asset_balance(transaction).await {
+            if !has_sufficient_balance {
+                if self.partial_payouts {
+                    match self.pay_partial(transaction, priority).await {
+                        Ok(()) => {}
+                        Err(e) if is_compliance_stop(&e) => return Err(e),
+                        Err(e) => log::error!(
+                            "Could not pay part of transaction {}: {}",
+                            transaction.id,
+                            e
+                        ),
+                    }
+                }
+
+                log::warn!(
+                    "Insufficient balance for transaction {}, adding to pending queue",
+                    transaction.id
+                );
+
+                // Add to pending transactions queue
+                self.pending_transactions
+                    .lock()
+                    .await
+                    .push_back(PendingTransaction {
+                        transaction: transaction.clone(),
+                        attempts: 0,
+                        last_attempt: self.clock.now(),
+                        queued_at: self.clock.now(),
+                        swap_id: None,
+                        last_error: Some("Insufficient balance".to_string()),
+                    });
+
+                // Initiate swap through the dedicated method
+                if let Some(quote_sub_id) = self.send_to_swap(transaction.clone()).await {
+                    self.link_swap(&transaction.id, quote_sub_id).await;
+                }
+
+                return Err(ServiceError::Internal("InsufficientBalance".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends what the wallet covers of the payout, once per transaction, and
+    /// tells the user the rest follows. The rest is the final payout, sent
+    /// when a swap refilled the asset; the part sent is never so large that
+    /// the rest would be dust.
+    async fn pay_partial(
+        &self,
+        transaction: &transactions::Transaction,
+        priority: FeePriority,
+    ) -> Result<(), ServiceError> {
+        if self.partial_total(&transaction.id).await? > 0 {
+            return Ok(());
+        }
+
+        let (asset_price_in_cents, aggregation) = self.lock_payout_price(transaction).await?;
+        let recipients = self
+            .compute_payout(transaction, asset_price_in_cents, aggregation.as_deref())
+            .await?;
+        // The user's output comes first
+        let owed = recipients.first().map_or(0, |recipient| recipient.satoshi);
+
+        let balance = self.request_asset_balance(&transaction.asset).await?;
+        let spendable = if transaction.asset == Assets::LBTC.hex() {
+            balance.saturating_sub(PARTIAL_FEE_RESERVE_SATS)
+        } else {
+            balance
+        };
+        let amount = spendable.min(owed.saturating_sub(self.floor_policy.dust_threshold()));
+        if self.floor_policy.is_dust(amount) {
+            return Ok(());
+        }
+
+        // Claimed before anything is built, so only one part is ever sent
+        let partial_id = self
+            .payout_repository
+            .start_partial(&transaction.id, amount)
+            .await
+            .map_err(|e| ServiceError::Repository("Payouts".to_string(), e.to_string()))?;
+        let Some(partial_id) = partial_id else {
+            return Ok(());
+        };
+
+        let recipient = UnvalidatedRecipient {
+            address: transaction.address.clone(),
+            satoshi: amount,
+            asset: transaction.asset.clone(),
+        };
+        let signed_pset = match self.sign_partial(transaction, recipient, priority).await {
+            Ok(signed_pset) => signed_pset,
+            Err(e) => {
+                // Nothing left the wallet yet
+                if let Err(clear_error) = self.payout_repository.clear_partial(partial_id).await {
+                    log::error!("Could not clear failed partial payout: {}", clear_error);
+                }
+                return Err(e);
+            }
+        };
+
+        // Electrum may have taken the part before the error, so it counts as
+        // sent and a reviewer checks the chain
+        let broadcast = match self.finalize_transaction(signed_pset).await {
+            Ok(broadcast) => broadcast,
+            Err(e) => {
+                self.hold_transaction(
+                    &transaction.id,
+                    "partial_payout",
+                    &format!(
+                        "Partial payout broadcast failed and may be on chain ({}); check the chain before approving",
+                        e
+                    ),
+                )
+                .await?;
+                return Err(ServiceError::Internal("TransactionHeld".to_string()));
+            }
+        };
+
+        if let Err(e) = self
+            .payout_repository
+            .record_partial_txid(partial_id, &broadcast.txid, broadcast.network_fee)
+            .await
+        {
+            log::error!(
+                "Could not record partial payout txid {}: {}",
+                broadcast.txid,
+                e
+            );
+        }
+        metrics::increment("partial_payouts_total", &[("asset", &transaction.asset)]);
+        metrics::add(
+            "payout_network_fees_total",
+            &[("priority", priority.as_str())],
+            broadcast.network_fee,
+        );
+
+        log::info!(
+            "Sent {} of {} owed for transaction {} (txid: {}), the rest follows the swap",
+            amount,
+            owed,
+            transaction.id,
+            broadcast.txid
+        );
+
+        let _ = self
+            .notification_channel
+            .send(NotificationRequest::UserNotice {
+                user_id: transaction.user_id.clone(),
+                title: "Pagamento parcial".to_string(),
+                message: "Enviamos parte da sua compra. O restante será enviado em instantes."
+                    .to_string(),
+                data: json!({
+                    "transaction_id": transaction.id,
+                    "txid": broadcast.txid,
+                    "amount": amount,
+                    "remaining": owed - amount,
+                }),
+            })
+            .await;
 
         Ok(())
     }
 
-    async fn request_asset_price(&self, asset: &String) -> Result<u64, ServiceError> {
-        let (price_tx, price_rx) = oneshot::channel();
-        let asset_object = Assets::from_hex(asset)
+    async fn sign_partial(
+        &self,
+        transaction: &transactions::Transaction,
+        recipient: UnvalidatedRecipient,
+        priority: FeePriority,
+    ) -> Result<PartiallySignedTransaction, ServiceError> {
+        let pset = self
+            .build_payout(&transaction.id, vec![recipient.clone()], priority)
+            .await?;
+        self.verify_payout(
+            transaction,
+            verify::PayoutKind::Partial,
+            &[recipient],
+            &pset,
+        )
+        .await?;
+        self.sign_transaction(pset)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("Could not sign partial payout: {}", e)))
+    }
+
+    async fn lock_payout_price(
+        &self,
+        transaction: &transactions::Transaction,
+    ) -> Result<(u64, Option<String>), ServiceError> {
+        log::debug!("Continuing with transaction: {}", transaction.id);
+
+        let asset_price = self.request_price(&transaction.asset).await?;
+        if let Some(input) = self.pricing_watchdog.check(&asset_price, self.clock.now()) {
+            log::warn!(
+                "Not pricing the payout of transaction {}: {}",
+                transaction.id,
+                input
+            );
+            metrics::increment("payout_pricing_blocked_total", &[("input", input.as_str())]);
+            return Err(ServiceError::Internal("PricingUnavailable".to_string()));
+        }
+
+        self.payout_price(&transaction.id, &asset_price).await
+    }
+
+    /// Fee and recipients of the payout at the locked price. The fee and the
+    /// price are stored with the transaction.
+    async fn compute_payout(
+        &self,
+        transaction: &transactions::Transaction,
+        asset_price_in_cents: u64,
+        aggregation: Option<&str>,
+    ) -> Result<Vec<UnvalidatedRecipient>, ServiceError> {
+        let PayoutAmounts {
+            fee_in_asset,
+            referral_addr,
+            referral_bonus,
+            mut amount_to_send_user,
+        } = self
+            .payout_amounts(transaction, asset_price_in_cents)
+            .await?;
+
+        // Parts already sent count towards what the user is owed. The last
+        // one is never dust, even when the price moved in the user's favour
+        let partial_total = self.partial_total(&transaction.id).await?;
+        if partial_total > 0 {
+            amount_to_send_user = amount_to_send_user
+                .saturating_sub(partial_total)
+                .max(self.floor_policy.dust_threshold());
+        }
+
+        // Update the fee_collected field in the database
+        self.repository
+            .update_fee_collected(&transaction.id, fee_in_asset as i32)
+            .await
             .map_err(|e| {
-                log::error!("Invalid asset: {:?}", e);
-                ServiceError::Internal("Invalid asset".to_string())
+                ServiceError::Repository("TransactionService".to_string(), e.to_string())
             })?;
-        self.price_channel
-            .send(PriceRequest::GetPrice {
-                asset: asset_object,
-                response: price_tx,
+
+        self.repository
+            .record_price(
+                &transaction.id,
+                self.quote_currency,
+                asset_price_in_cents,
+                aggregation,
+                fee_in_asset,
+            )
+            .await
+            .map_err(|e| {
+                ServiceError::Repository("TransactionService".to_string(), e.to_string())
+            })?;
+
+        let user_recipient = UnvalidatedRecipient {
+            address: transaction.address.clone(),
+            satoshi: amount_to_send_user,
+            asset: transaction.asset.clone(),
+        };
+
+        // Referrers see their bonuses in their statements
+        let recorded = match referral_addr {
+            Some(_) => {
+                self.referral_repository
+                    .record_bonus(
+                        &transaction.id,
+                        &transaction.user_id,
+                        &transaction.asset,
+                        referral_bonus,
+                        fees::referral_bonus_in_cents(transaction.amount_in_cents),
+                    )
+                    .await
+            }
+            None => {
+                self.referral_repository
+                    .discard_bonus(&transaction.id)
+                    .await
+            }
+        };
+        recorded.map_err(|e| ServiceError::Repository("Referrals".to_string(), e.to_string()))?;
+
+        let recipients = match referral_addr {
+            Some(referral_addr) => {
+                let referral_recipient = UnvalidatedRecipient {
+                    address: referral_addr,
+                    satoshi: referral_bonus,
+                    asset: transaction.asset.clone(),
+                };
+                vec![user_recipient, referral_recipient]
+            }
+            None => vec![user_recipient],
+        };
+
+        Ok(recipients)
+    }
+
+    /// What the payout sends at the price, once the dust floor is applied.
+    /// A payout the fees push below dust is held for review, and approving
+    /// it pays it without the fee. One that is dust even without the fee
+    /// can't be paid at all: approving it refunds the PIX payment instead.
+    async fn payout_amounts(
+        &self,
+        transaction: &transactions::Transaction,
+        asset_price_in_cents: u64,
+    ) -> Result<PayoutAmounts, ServiceError> {
+        let precision = Assets::precision_of(&transaction.asset);
+        let asset_amount =
+            fees::asset_amount(transaction.amount_in_cents, asset_price_in_cents, precision)
+                .map_err(fee_error)?;
+
+        // Merchant payments have their own fee schedule and no referral bonus
+        let merchant_fee_bps = self
+            .merchant_repository
+            .get_fee_bps_for_transaction(&transaction.id)
+            .await
+            .map_err(|e| ServiceError::Repository("Merchants".to_string(), e.to_string()))?;
+
+        let (mut referral_addr, mut fee_in_asset) = match merchant_fee_bps {
+            Some(fee_bps) => (
+                None,
+                fees::merchant_fee(
+                    transaction.amount_in_cents,
+                    asset_price_in_cents,
+                    precision,
+                    fee_bps,
+                )
+                .map_err(fee_error)?,
+            ),
+            None => {
+                let referral_addr = self.check_for_referral(&transaction.user_id).await?;
+                let fee_in_asset = fees::consumer_fee(
+                    transaction.amount_in_cents,
+                    asset_price_in_cents,
+                    precision,
+                    referral_addr.is_some(),
+                )
+                .map_err(fee_error)?;
+                let fee_in_asset = self.apply_campaign(transaction, fee_in_asset).await?;
+                (referral_addr, fee_in_asset)
+            }
+        };
+
+        let mut referral_bonus = match &referral_addr {
+            Some(_) => {
+                fees::referral_bonus(transaction.amount_in_cents, asset_price_in_cents, precision)
+                    .map_err(fee_error)?
+            }
+            None => 0,
+        };
+
+        // A referral bonus too small for its own output goes to the user
+        if referral_addr.is_some() && self.floor_policy.is_dust(referral_bonus) {
+            log::info!(
+                "Referral bonus of {} for transaction {} is dust, paying it to the user",
+                referral_bonus,
+                transaction.id
+            );
+            referral_addr = None;
+            referral_bonus = 0;
+        }
+
+        // Fees that eat the whole payout are handled like a dust payout
+        let mut amount_to_send_user =
+            match fees::user_payout(asset_amount, fee_in_asset, referral_bonus) {
+                Ok(amount) => amount,
+                Err(fees::FeeError::NoPayoutLeft { .. }) => 0,
+                Err(e) => return Err(fee_error(e)),
+            };
+        if self.floor_policy.is_dust(amount_to_send_user) {
+            let approved = self
+                .review_repository
+                .is_approved(&transaction.id)
+                .await
+                .map_err(|e| ServiceError::Repository("Reviews".to_string(), e.to_string()))?;
+            let gross_is_dust = self.floor_policy.is_dust(asset_amount);
+            if gross_is_dust && approved {
+                return self.refund_dust(transaction, asset_amount).await;
+            }
+
+            // Approving a dust hold means paying the payout without the fee
+            let waive_fee = !gross_is_dust
+                && (self.floor_policy.below_dust() == DustPolicy::WaiveFee || approved);
+            if !waive_fee {
+                metrics::increment("payout_dust_total", &[("action", "held")]);
+                let reason = if gross_is_dust {
+                    format!(
+                        "Payout of {} is below the dust threshold even without the fee; approving refunds the PIX payment",
+                        asset_amount
+                    )
+                } else {
+                    format!(
+                        "Payout of {} after a fee of {} is below the dust threshold",
+                        amount_to_send_user, fee_in_asset
+                    )
+                };
+                self.hold_transaction(&transaction.id, "dust", &reason)
+                    .await?;
+                return Err(ServiceError::Internal("TransactionHeld".to_string()));
+            }
+
+            log::warn!(
+                "Payout of {} for transaction {} is dust after fees, waiving the fee",
+                amount_to_send_user,
+                transaction.id
+            );
+            metrics::increment("payout_dust_total", &[("action", "fee_waived")]);
+            referral_addr = None;
+            fee_in_asset = 0;
+            amount_to_send_user = asset_amount;
+        }
+
+        Ok(PayoutAmounts {
+            fee_in_asset,
+            referral_addr,
+            referral_bonus,
+            amount_to_send_user,
+        })
+    }
+
+    /// Settles a payout too small to send even without the fee: the
+    /// transaction is moved to `refund_requested` and operators are alerted.
+    async fn refund_dust(
+        &self,
+        transaction: &transactions::Transaction,
+        asset_amount: u64,
+    ) -> Result<PayoutAmounts, ServiceError> {
+        log::warn!(
+            "Payout of {} for transaction {} is dust even without the fee, refunding it",
+            asset_amount,
+            transaction.id
+        );
+        metrics::increment("payout_dust_total", &[("action", "refunded")]);
+        self.repository
+            .update_transaction_status(&transaction.id, &"refund_requested".to_string())
+            .await
+            .map_err(|e| {
+                ServiceError::Repository("TransactionService".to_string(), e.to_string())
+            })?;
+        self.invalidate_user_details(&transaction.user_id).await;
+        self.send_alert(
+            "Refund requested",
+            format!(
+                "Payout of transaction {} ({} cents) is below the dust threshold and can't be sent; refund the PIX payment",
+                transaction.id, transaction.amount_in_cents
+            ),
+        )
+        .await;
+
+        Err(ServiceError::Internal("TransactionRefunded".to_string()))
+    }
+
+    async fn build_payout(
+        &self,
+        transaction_id: &str,
+        recipients: Vec<UnvalidatedRecipient>,
+        priority: FeePriority,
+    ) -> Result<PartiallySignedTransaction, ServiceError> {
+        log::debug!("Building transaction for: {}", transaction_id);
+
+        let (liquid_tx, liquid_rx) = oneshot::channel();
+        self.liquid_channel
+            .send(LiquidRequest::BuildTransaction {
+                recipients,
+                priority,
+                avoid_change: self.avoid_change,
+                response: liquid_tx,
             })
             .await
-            .map_err(|err| {
-                ServiceError::Communication("Transactions => Price".to_string(), err.to_string())
+            .map_err(|e| {
+                log::error!("{:?}", e);
+                ServiceError::Communication("Transaction => Liquid".to_string(), e.to_string())
             })?;
 
-        let asset_price = price_rx.await.map_err(|e| {
-            ServiceError::ExternalService(
-                "TransactionService".to_string(),
-                "PriceService".to_string(),
-                e.to_string(),
-            )
+        let pset = liquid_rx.await.map_err(|e| {
+            log::error!("{:?}", e);
+            ServiceError::Communication("Transaction => Liquid".to_string(), e.to_string())
+        })??;
+
+        log::debug!("Transaction built for: {}", transaction_id);
+
+        Ok(pset)
+    }
+
+    /// Checks the PSET against the transaction before it is signed. A
+    /// mismatch is a bug rather than a passing failure, so the payout is
+    /// held for review instead of retried.
+    async fn verify_payout(
+        &self,
+        transaction: &transactions::Transaction,
+        kind: verify::PayoutKind,
+        recipients: &[UnvalidatedRecipient],
+        pset: &PartiallySignedTransaction,
+    ) -> Result<(), ServiceError> {
+        let recorded = self.recorded_payout(transaction).await?;
+        if let Err(reason) = verify::check_recipients(transaction, &recorded, kind, recipients) {
+            return self
+                .reject_payout_pset(transaction, pset, &reason, &PsetBalance::default())
+                .await;
+        }
+
+        let (liquid_tx, liquid_rx) = oneshot::channel();
+        self.liquid_channel
+            .send(LiquidRequest::GetPsetBalance {
+                pset: pset.clone(),
+                response: liquid_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Transaction => Liquid".to_string(), e.to_string())
+            })?;
+        let balance = liquid_rx.await.map_err(|e| {
+            ServiceError::Communication("Transaction => Liquid".to_string(), e.to_string())
         })??;
 
-        match asset_price {
-            Some(price) => {
-                let asset_price_in_cents = price * 100.0;
-                return Ok(asset_price_in_cents as u64)
+        let checked = verify::expected_outputs(recipients)
+            .and_then(|expected| verify::check_pset(&balance, &expected, &Assets::LBTC.hex()));
+        match checked {
+            Ok(()) => {
+                Self::record_change_outputs(transaction, pset, &balance);
+                Ok(())
+            }
+            Err(reason) => {
+                self.reject_payout_pset(transaction, pset, &reason, &balance)
+                    .await
             }
-            None => Err(ServiceError::Internal("Asset price not found".to_string())),
         }
     }
 
-    async fn check_for_referral(&self, user_id: &String) -> Result<Option<String>, ServiceError> {
-        let (referral_addr_tx, referral_addr_rx) = oneshot::channel();
-        self.user_channel
-            .send(UserRequest::GetUserReferrerAddress {
-                id: user_id.clone(),
-                response: referral_addr_tx,
+    /// Reads back what computing the payout of a transaction recorded: its
+    /// price, fee, referral bonus and parts, rather than trusting the
+    /// amounts the payout was built from.
+    async fn recorded_payout(
+        &self,
+        transaction: &transactions::Transaction,
+    ) -> Result<verify::RecordedPayout, ServiceError> {
+        let (price_in_cents, fee) = self
+            .repository
+            .get_recorded_price(&transaction.id)
+            .await
+            .map_err(|e| ServiceError::Repository("TransactionService".to_string(), e.to_string()))?
+            .ok_or_else(|| {
+                ServiceError::Internal(format!("No price recorded for {}", transaction.id))
+            })?;
+
+        let gross_amount = fees::asset_amount(
+            transaction.amount_in_cents,
+            price_in_cents,
+            Assets::precision_of(&transaction.asset),
+        )
+        .map_err(fee_error)?;
+        let bonus = self
+            .referral_repository
+            .get_bonus_amount(&transaction.id)
+            .await
+            .map_err(|e| ServiceError::Repository("Referrals".to_string(), e.to_string()))?;
+        let referral = match bonus {
+            Some(bonus) => {
+                let address = self
+                    .check_for_referral(&transaction.user_id)
+                    .await?
+                    .ok_or_else(|| {
+                        ServiceError::Internal(format!(
+                            "Bonus recorded for {} without a referrer",
+                            transaction.id
+                        ))
+                    })?;
+                Some((address, bonus))
+            }
+            None => None,
+        };
+
+        Ok(verify::RecordedPayout {
+            gross_amount,
+            fee,
+            referral,
+            partial_total: self.partial_total(&transaction.id).await?,
+            dust_threshold: self.floor_policy.dust_threshold(),
+        })
+    }
+
+    /// Counts the inputs of a payout and the change outputs it creates, every
+    /// output back to the wallet. The builder always adds one in L-BTC, so a
+    /// payout in another asset whose inputs matched it exactly has one.
+    fn record_change_outputs(
+        transaction: &transactions::Transaction,
+        pset: &PartiallySignedTransaction,
+        balance: &PsetBalance,
+    ) {
+        let change_outputs = pset
+            .n_outputs()
+            .saturating_sub(balance.recipients.len() + 1);
+        let ticker = Assets::from_hex(&transaction.asset)
+            .map(|asset| asset.ticker())
+            .unwrap_or("unknown");
+
+        metrics::increment("payout_psets_total", &[("asset", ticker)]);
+        metrics::add(
+            "payout_change_outputs_total",
+            &[("asset", ticker)],
+            change_outputs as u64,
+        );
+        metrics::add(
+            "payout_inputs_total",
+            &[("asset", ticker)],
+            pset.n_inputs() as u64,
+        );
+    }
+
+    async fn reject_payout_pset(
+        &self,
+        transaction: &transactions::Transaction,
+        pset: &PartiallySignedTransaction,
+        reason: &str,
+        balance: &PsetBalance,
+    ) -> Result<(), ServiceError> {
+        log::error!(
+            "Refusing to sign the payout of {}: {}",
+            transaction.id,
+            reason
+        );
+        metrics::increment("rejected_psets_total", &[("kind", "payout")]);
+        if let Err(e) = self
+            .pset_repository
+            .insert_rejected(
+                "payout",
+                &transaction.id,
+                &pset.to_string(),
+                reason,
+                balance,
+            )
+            .await
+        {
+            log::error!(
+                "Could not record the rejected PSET of {}: {}",
+                transaction.id,
+                e
+            );
+        }
+
+        self.hold_transaction(
+            &transaction.id,
+            "pset",
+            &format!("Payout PSET refused: {}", reason),
+        )
+        .await?;
+
+        Err(ServiceError::Internal("TransactionHeld".to_string()))
+    }
+
+    /// Records the broadcast before sending it, so a crash in between is
+    /// told apart from a payout that never started.
+    async fn broadcast_payout(
+        &self,
+        transaction_id: &str,
+        signed_pset: PartiallySignedTransaction,
+    ) -> Result<BroadcastTransaction, ServiceError> {
+        self.payout_repository
+            .start_broadcast(transaction_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Payouts".to_string(), e.to_string()))?;
+
+        let broadcast = self.finalize_transaction(signed_pset).await?;
+
+        if let Err(e) = self
+            .payout_repository
+            .record_txid(transaction_id, &broadcast.txid, broadcast.network_fee)
+            .await
+        {
+            log::error!("Could not record payout txid {}: {}", broadcast.txid, e);
+        }
+        if let Err(e) = self
+            .referral_repository
+            .mark_paid(transaction_id, &broadcast.txid)
+            .await
+        {
+            log::error!(
+                "Could not mark the referral bonus of {} paid: {}",
+                transaction_id,
+                e
+            );
+        }
+
+        Ok(broadcast)
+    }
+
+    /// Held and blocked payouts leave the payout flow with their status set;
+    /// flagged ones continue.
+    async fn screen_payout_address(
+        &self,
+        transaction: &transactions::Transaction,
+    ) -> Result<(), ServiceError> {
+        // A reviewer already cleared this payout
+        let approved = self
+            .review_repository
+            .is_approved(&transaction.id)
+            .await
+            .map_err(|e| ServiceError::Repository("Reviews".to_string(), e.to_string()))?;
+        if approved {
+            return Ok(());
+        }
+
+        let (compliance_tx, compliance_rx) = oneshot::channel();
+        self.compliance_channel
+            .send(ComplianceRequest::ScreenAddress {
+                transaction_id: transaction.id.clone(),
+                address: transaction.address.clone(),
+                response: compliance_tx,
             })
             .await
             .map_err(|e| {
-                log::error!("Failed to send request to user service: {:?}", e);
-                ServiceError::Communication("Transaction => User".to_string(), e.to_string())
+                ServiceError::Communication("Transaction => Compliance".to_string(), e.to_string())
             })?;
 
-        match referral_addr_rx.await {
-            Ok(Ok(addr)) => Ok(addr),
-            Ok(Err(addr)) => {
-                log::error!("Failed to get user referrer address: {}", addr);
-                Ok(None)
+        let screening = compliance_rx.await.map_err(|e| {
+            ServiceError::Communication("Transaction => Compliance".to_string(), e.to_string())
+        })??;
+
+        match screening.action {
+            ScreeningAction::Allow | ScreeningAction::Flag => Ok(()),
+            ScreeningAction::Hold => {
+                let reason = screening
+                    .reason
+                    .unwrap_or_else(|| "Payout address screening".to_string());
+                self.hold_transaction(&transaction.id, "compliance", &reason)
+                    .await?;
+
+                Err(ServiceError::Internal("TransactionHeld".to_string()))
             }
-            Err(e) => {
-                log::error!("Failed to get user referrer address: {}", e);
-                Ok(None)
+            ScreeningAction::Block => {
+                let updated = self
+                    .repository
+                    .update_transaction_status(&transaction.id, &"blocked".to_string())
+                    .await
+                    .map_err(|e| {
+                        ServiceError::Repository("TransactionService".to_string(), e.to_string())
+                    })?;
+                self.invalidate_user_details(&updated.user_id).await;
+
+                Err(ServiceError::Internal("TransactionBlocked".to_string()))
             }
         }
     }
 
-    fn calculate_fee_amount(
+    /// Holds payouts past the exposure caps for manual approval, unless a
+    /// reviewer already approved them.
+    async fn check_exposure(
+        &self,
+        transaction: &transactions::Transaction,
+    ) -> Result<(), ServiceError> {
+        let paid_last_hour = if self.exposure_policy.has_hourly_cap() {
+            self.payout_repository
+                .get_paid_since(
+                    self.clock.now() - chrono::Duration::hours(1),
+                    &transaction.id,
+                )
+                .await
+                .map_err(|e| ServiceError::Repository("Payouts".to_string(), e.to_string()))?
+        } else {
+            0
+        };
+
+        let Some(reason) = self
+            .exposure_policy
+            .exceeded(transaction.amount_in_cents, paid_last_hour)
+        else {
+            return Ok(());
+        };
+
+        let approved = self
+            .review_repository
+            .is_approved(&transaction.id)
+            .await
+            .map_err(|e| ServiceError::Repository("Reviews".to_string(), e.to_string()))?;
+        if approved {
+            return Ok(());
+        }
+
+        metrics::increment("payout_exposure_holds_total", &[]);
+        self.hold_transaction(&transaction.id, "exposure", &reason)
+            .await?;
+
+        Err(ServiceError::Internal("TransactionHeld".to_string()))
+    }
+
+    /// Holds payouts of users with an open MED dispute for manual approval,
+    /// unless a reviewer already approved them.
+    async fn check_disputes(
         &self,
-        fiat_amount_in_cents: u64,
-        asset_price_in_cents: u64,
-        has_referral: bool,
-    ) -> u64 {
-        // Calculate fee in asset terms with precision already adjusted
-        let fee_in_asset = if fiat_amount_in_cents < 55 * 100 {
-            (2 * 100 * 10_u64.pow(8)) / asset_price_in_cents
-        } else if fiat_amount_in_cents < 500 * 100 {
-            (fiat_amount_in_cents * 350 * 10_u64.pow(8)) / 10000 / asset_price_in_cents
-        } else if fiat_amount_in_cents < 5000 * 100 {
-            (fiat_amount_in_cents * 325 * 10_u64.pow(8)) / 10000 / asset_price_in_cents
-        } else {
-            (fiat_amount_in_cents * 275 * 10_u64.pow(8)) / 10000 / asset_price_in_cents
-        };
+        transaction: &transactions::Transaction,
+    ) -> Result<(), ServiceError> {
+        if !self.disputes.hold_payouts {
+            return Ok(());
+        }
 
-        // If there's a referral, reduce the fee by 0.5% of the total transaction amount
-        if has_referral {
-            let referral_discount =
-                (fiat_amount_in_cents * 50 * 10_u64.pow(8)) / 10000 / asset_price_in_cents;
-            fee_in_asset - referral_discount
-        } else {
-            fee_in_asset
+        let open = self.open_disputes(&transaction.user_id).await?;
+        if open == 0 {
+            return Ok(());
+        }
+
+        let approved = self
+            .review_repository
+            .is_approved(&transaction.id)
+            .await
+            .map_err(|e| ServiceError::Repository("Reviews".to_string(), e.to_string()))?;
+        if approved {
+            return Ok(());
         }
+
+        metrics::increment("payout_dispute_holds_total", &[]);
+        let reason = format!("User has {} open PIX disputes", open);
+        self.hold_transaction(&transaction.id, "dispute", &reason)
+            .await?;
+
+        Err(ServiceError::Internal("TransactionHeld".to_string()))
     }
 
-    async fn continue_with_transaction(
+    async fn hold_transaction(
         &self,
-        transaction: transactions::Transaction,
-    ) -> Result<PartiallySignedTransaction, ServiceError> {
-        // First check if we have sufficient balance
-        if let Ok(has_sufficient_balance) = self.check_asset_balance(&transaction).await {
-            if !has_sufficient_balance {
-                log::warn!(
-                    "Insufficient balance for transaction {}, adding to pending queue",
-                    transaction.id
-                );
+        transaction_id: &String,
+        source: &str,
+        reason: &str,
+    ) -> Result<(), ServiceError> {
+        // Holds of a cancelled deposit come from a payment of the wrong amount
+        if self.stop_if_cancelled(transaction_id, true).await? {
+            return Ok(());
+        }
 
-                // Add to pending transactions queue
-                let mut pending_txs = self.pending_transactions.lock().await;
-                pending_txs.push_back(PendingTransaction {
-                    transaction: transaction.clone(),
-                    attempts: 0,
-                    last_attempt: chrono::Utc::now(),
-                });
+        let review = self
+            .review_repository
+            .hold(transaction_id, source, reason)
+            .await
+            .map_err(|e| ServiceError::Repository("Reviews".to_string(), e.to_string()))?;
 
-                // Initiate swap through the dedicated method
-                self.send_to_swap(transaction).await;
+        if let Some(review) = review {
+            log::warn!(
+                "Transaction {} held for review ({}): {}",
+                transaction_id,
+                review.source,
+                review.reason
+            );
+            self.send_alert(
+                "Transaction held for review",
+                format!("Transaction {} ({}): {}", transaction_id, source, reason),
+            )
+            .await;
+        }
 
-                return Err(ServiceError::Internal("InsufficientBalance".to_string()));
-            }
+        if let Ok(Some(transaction)) = self.repository.get_transaction(transaction_id).await {
+            self.invalidate_user_details(&transaction.user_id).await;
         }
 
-        log::debug!("Continuing with transaction: {}", transaction.id);
+        Ok(())
+    }
 
-        let asset_price_in_cents = self.request_asset_price(&transaction.asset).await?;
+    async fn get_timeline(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<TransactionTimeline>, ServiceError> {
+        let mut timeline = self
+            .timeline_repository
+            .get_transaction_timeline(transaction_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Timeline".to_string(), e.to_string()))?;
 
-        let asset_amount =
-            (transaction.amount_in_cents as u64 * 10_u64.pow(8)) / asset_price_in_cents;
+        // Queue position is only known in memory
+        if let Some(timeline) = timeline.as_mut() {
+            let pending_txs = self.pending_transactions.lock().await;
+            if let Some(pending_tx) = pending_txs
+                .iter()
+                .find(|pending_tx| pending_tx.transaction.id == transaction_id)
+            {
+                timeline.events.push(TimelineEvent {
+                    at: pending_tx.last_attempt,
+                    source: "payout".to_string(),
+                    event: "waiting_for_liquidity".to_string(),
+                    details: serde_json::json!({
+                        "attempts": pending_tx.attempts,
+                        "queued_at": pending_tx.queued_at,
+                        "swap_id": pending_tx.swap_id,
+                    }),
+                });
+                timeline.events.sort_by_key(|event| event.at);
+            }
+        }
 
-        let referral_addr = self.check_for_referral(&transaction.user_id).await?;
-        let fee_in_asset = self.calculate_fee_amount(
-            transaction.amount_in_cents as u64,
-            asset_price_in_cents,
-            referral_addr.is_some(),
-        );
+        Ok(timeline)
+    }
 
-        // Update the fee_collected field in the database
-        self.repository
-            .update_fee_collected(&transaction.id, fee_in_asset as i32)
+    async fn get_held_transactions(&self) -> Result<Vec<HeldTransaction>, ServiceError> {
+        self.review_repository
+            .get_held_transactions()
             .await
-            .map_err(|e| {
-                ServiceError::Repository("TransactionService".to_string(), e.to_string())
-            })?;
+            .map_err(|e| ServiceError::Repository("Reviews".to_string(), e.to_string()))
+    }
 
-        let referral_bonus = if let Some(addr) = &referral_addr {
-            (transaction.amount_in_cents as u64 * 50 * 10_u64.pow(8)) / 10000 / asset_price_in_cents
-        } else {
-            0
-        };
+    /// Approving resumes the payout; rejecting asks operators to refund the
+    /// PIX payment.
+    async fn resolve_review(
+        &self,
+        transaction_id: &String,
+        decision: ReviewDecision,
+        decided_by: &str,
+        note: Option<&str>,
+    ) -> Result<bool, ServiceError> {
+        let review = self
+            .review_repository
+            .resolve(transaction_id, decision, decided_by, note)
+            .await
+            .map_err(|e| ServiceError::Repository("Reviews".to_string(), e.to_string()))?;
 
-        let amount_to_send_user = asset_amount - fee_in_asset - referral_bonus;
-        let user_recipient = UnvalidatedRecipient {
-            address: transaction.address,
-            satoshi: amount_to_send_user,
-            asset: transaction.asset.clone(),
-        };
+        if review.is_none() {
+            return Ok(false);
+        }
 
-        let recipients = match referral_addr {
-            Some(referral_addr) => {
-                let referral_recipient = UnvalidatedRecipient {
-                    address: referral_addr,
-                    satoshi: referral_bonus,
-                    asset: transaction.asset.clone(),
-                };
-                vec![user_recipient, referral_recipient]
+        match decision {
+            ReviewDecision::Approve => {
+                log::info!("Review of {} approved by {}", transaction_id, decided_by);
+                // The reviewer found an interrupted broadcast did not go out
+                self.payout_repository
+                    .clear_broadcast(transaction_id)
+                    .await
+                    .map_err(|e| ServiceError::Repository("Payouts".to_string(), e.to_string()))?;
+                self.update_transaction_status(transaction_id, &"eulen_depix_sent".to_string())
+                    .await?;
             }
-            None => vec![user_recipient],
-        };
+            ReviewDecision::Reject => {
+                log::info!("Review of {} rejected by {}", transaction_id, decided_by);
+                self.release_campaign(transaction_id).await;
+                if let Ok(Some(transaction)) =
+                    self.repository.get_transaction(transaction_id).await
+                {
+                    self.invalidate_user_details(&transaction.user_id).await;
+                    self.send_alert(
+                        "Refund requested",
+                        format!(
+                            "Transaction {} of {} cents was rejected by {}; refund the PIX payment",
+                            transaction_id, transaction.amount_in_cents, decided_by
+                        ),
+                    )
+                    .await;
+                }
+            }
+        }
 
-        log::debug!("Building transaction for: {}", transaction.id);
+        Ok(true)
+    }
 
-        let (liquid_tx, liquid_rx) = oneshot::channel();
-        self.liquid_channel
-            .send(LiquidRequest::BuildTransaction {
-                recipients,
-                response: liquid_tx,
+    async fn send_alert(&self, title: &str, message: String) {
+        if let Err(e) = self
+            .notification_channel
+            .send(NotificationRequest::Alert {
+                title: title.to_string(),
+                message,
             })
             .await
-            .map_err(|e| {
-                log::error!("{:?}", e);
-                ServiceError::Communication("Transaction => Liquid".to_string(), e.to_string())
-            })?;
-
-        let pset = liquid_rx.await.map_err(|e| {
-            log::error!("{:?}", e);
-            ServiceError::Communication("Transaction => Liquid".to_string(), e.to_string())
-        })??;
-
-        log::debug!("Transaction built for: {}", transaction.id);
-
-        Ok(pset)
+        {
+            log::error!("Failed to send alert: {}", e);
+        }
     }
 
     async fn sign_transaction(
@@ -637,7 +3881,7 @@ impl TransactionRequestHandler {
     async fn finalize_transaction(
         &self,
         pset: PartiallySignedTransaction,
-    ) -> Result<(), ServiceError> {
+    ) -> Result<BroadcastTransaction, ServiceError> {
         let (liquid_tx, liquid_rx) = oneshot::channel();
         log::debug!("Finalizing transaction.");
         self.liquid_channel
@@ -651,29 +3895,149 @@ impl TransactionRequestHandler {
                 ServiceError::Communication("Transaction => Liquid".to_string(), e.to_string())
             })?;
 
-        let txid = liquid_rx.await.map_err(|e| {
+        let broadcast = liquid_rx.await.map_err(|e| {
             log::error!("{:?}", e);
             ServiceError::Communication("Transaction => Liquid".to_string(), e.to_string())
         })??;
 
-        log::info!("Finished transaction: {}", txid);
+        log::info!(
+            "Finished transaction: {} (network fee: {})",
+            broadcast.txid,
+            broadcast.network_fee
+        );
 
-        Ok(())
+        Ok(broadcast)
     }
 
-    async fn send_to_swap(&self, transaction: transactions::Transaction) {
-        let (sideswap_tx, sideswap_rx) = oneshot::channel();
-        if let Err(e) = self.sideswap_channel.send(
-            SideswapRequest::Swap {
-                sell_asset: "02f22f8d9c76ab41661a2729e4752e2c5d1a263012141b86ea98af5472df5189".to_string(),
-                receive_asset: transaction.asset.clone(),
-                amount: ((transaction.amount_in_cents - 100) as u64 * 10_u64.pow(6)) as i64,
-                response: sideswap_tx,
+    async fn send_to_swap(&self, transaction: transactions::Transaction) -> Option<i64> {
+        let depix = Assets::DEPIX.hex();
+        if transaction.asset == depix {
+            log::warn!(
+                "Transaction {} is short on DEPIX, which cannot be refilled by a swap",
+                transaction.id
+            );
+            return None;
+        }
+
+        let sell_amount = match self.swap_amount_for_shortfall(&transaction, &depix).await {
+            Ok(0) => return None,
+            Ok(sell_amount) => sell_amount,
+            Err(e) => {
+                log::error!(
+                    "Could not size swap for transaction {}: {}",
+                    transaction.id,
+                    e
+                );
+                return None;
+            }
+        };
+
+        log::info!(
+            "Requesting swap of {} DEPIX units into {} for transaction {}",
+            sell_amount,
+            transaction.asset,
+            transaction.id
+        );
+
+        match self
+            .swap_router
+            .swap(&depix, &transaction.asset, sell_amount as i64)
+            .await
+        {
+            Ok(quote_sub_id) => Some(quote_sub_id),
+            Err(e) => {
+                log::error!(
+                    "Swap for transaction {} was not started: {}",
+                    transaction.id,
+                    e
+                );
+                None
             }
-        ).await {
-            log::error!("Failed to send sideswap request: {:?}", e);
         }
     }
+
+    async fn link_swap(&self, transaction_id: &String, quote_sub_id: i64) {
+        if let Err(e) = self
+            .payout_repository
+            .record_swap(transaction_id, quote_sub_id)
+            .await
+        {
+            log::error!("Could not record swap {}: {}", quote_sub_id, e);
+        }
+        self.record_step(
+            transaction_id,
+            PayoutStep::SwapRequested,
+            StepStatus::Completed,
+            json!({ "quote_sub_id": quote_sub_id }),
+        )
+        .await;
+
+        let mut pending_txs = self.pending_transactions.lock().await;
+        if let Some(pending_tx) = pending_txs
+            .iter_mut()
+            .find(|pending_tx| &pending_tx.transaction.id == transaction_id)
+        {
+            log::info!(
+                "Linked swap {} to pending transaction {}",
+                quote_sub_id,
+                transaction_id
+            );
+            pending_tx.swap_id = Some(quote_sub_id);
+        }
+    }
+
+    /// DEPIX to sell so the wallet receives exactly the missing amount of the
+    /// payout asset, plus the configured buffer for price movement and fees.
+    async fn swap_amount_for_shortfall(
+        &self,
+        transaction: &transactions::Transaction,
+        sell_asset: &String,
+    ) -> Result<u64, ServiceError> {
+        let shortfall = self.asset_shortfall(transaction).await?;
+        if shortfall == 0 {
+            return Ok(0);
+        }
+
+        let receive_price_in_cents = self.request_asset_price(&transaction.asset).await?;
+        let sell_price_in_cents = self.request_asset_price(sell_asset).await?;
+        if sell_price_in_cents == 0 {
+            return Err(ServiceError::Internal("Asset price not found".to_string()));
+        }
+
+        // Base units convert through the price ratio, scaled between the
+        // precisions of the two assets
+        let sell_amount = shortfall as u128
+            * receive_price_in_cents as u128
+            * Assets::unit_of(sell_asset) as u128
+            / Assets::unit_of(&transaction.asset) as u128
+            / sell_price_in_cents as u128;
+        let sell_amount = sell_amount * (10_000 + self.swap_buffer_bps as u128) / 10_000;
+
+        log::debug!(
+            "Shortfall for transaction {}: {} units of {}, selling {} units of {}",
+            transaction.id,
+            shortfall,
+            transaction.asset,
+            sell_amount,
+            sell_asset
+        );
+
+        u64::try_from(sell_amount)
+            .map_err(|_| ServiceError::Internal("Swap amount overflow".to_string()))
+    }
+}
+
+fn price_in_cents(asset_price: &AssetPrice) -> u64 {
+    money::to_cents(asset_price.price).max(0) as u64
+}
+
+fn fee_error(error: fees::FeeError) -> ServiceError {
+    ServiceError::Internal(format!("Fee calculation failed: {}", error))
+}
+
+/// Payouts stopped for review or for good, which are not retried.
+fn is_compliance_stop(error: &ServiceError) -> bool {
+    matches!(error, ServiceError::Internal(msg) if msg == "TransactionHeld" || msg == "TransactionBlocked" || msg == "TransactionRefunded")
 }
 
 #[async_trait]
@@ -686,10 +4050,44 @@ impl RequestHandler<TransactionServiceRequest> for TransactionRequestHandler {
                 amount_in_cents,
                 asset,
                 network,
+                quote_id,
+                scheduled_delivery_at,
+                response,
+            } => {
+                let result = self
+                    .new_transaction(
+                        DepositOrder {
+                            user_id,
+                            address,
+                            amount_in_cents,
+                            asset,
+                            network,
+                        },
+                        quote_id,
+                        scheduled_delivery_at,
+                        &response,
+                    )
+                    .await;
+                let _ = response.send(result);
+            }
+            TransactionServiceRequest::NewMerchantTransaction {
+                user_id,
+                address,
+                amount_in_cents,
+                asset,
+                network,
+                fee_bps,
                 response,
             } => {
+                let order = DepositOrder {
+                    user_id,
+                    address,
+                    amount_in_cents,
+                    asset,
+                    network,
+                };
                 let result = self
-                    .new_transaction(user_id, address, amount_in_cents, asset, network)
+                    .new_merchant_transaction(order, fee_bps, &response)
                     .await;
                 let _ = response.send(result);
             }
@@ -709,17 +4107,241 @@ impl RequestHandler<TransactionServiceRequest> for TransactionRequestHandler {
                     .update_fee_collected(&transaction_id, fee_collected)
                     .await;
             }
+            TransactionServiceRequest::GetPendingTransactions { response } => {
+                let pending = self.get_pending_transactions().await;
+                let _ = response.send(Ok(pending));
+            }
+            TransactionServiceRequest::GetTransaction {
+                transaction_id,
+                response,
+            } => {
+                let transaction = self
+                    .repository
+                    .get_transaction(&transaction_id)
+                    .await
+                    .map_err(|e| {
+                        ServiceError::Repository("TransactionService".to_string(), e.to_string())
+                    });
+                let _ = response.send(transaction);
+            }
+            TransactionServiceRequest::CancelTransaction {
+                transaction_id,
+                user_id,
+                response,
+            } => {
+                let result = self.cancel_transaction(&transaction_id, &user_id).await;
+                let _ = response.send(result);
+            }
+            TransactionServiceRequest::GetPartialPayouts {
+                transaction_id,
+                response,
+            } => {
+                let partials = self
+                    .payout_repository
+                    .get_partial_payouts(&transaction_id)
+                    .await
+                    .map_err(|e| ServiceError::Repository("Payouts".to_string(), e.to_string()));
+                let _ = response.send(partials);
+            }
+            TransactionServiceRequest::CreateQuote { asset, response } => {
+                let quote = self.create_quote(asset).await;
+                let _ = response.send(quote);
+            }
+            TransactionServiceRequest::GetRecentTransactions { limit, response } => {
+                let transactions = self
+                    .repository
+                    .get_recent_transactions(limit)
+                    .await
+                    .map_err(|e| {
+                        ServiceError::Repository("TransactionService".to_string(), e.to_string())
+                    });
+                let _ = response.send(transactions);
+            }
+            TransactionServiceRequest::SearchTransactions {
+                search,
+                after,
+                limit,
+                response,
+            } => {
+                let page = self
+                    .repository
+                    .search_transactions(&search, after.as_ref(), limit)
+                    .await
+                    .map_err(|e| {
+                        ServiceError::Repository("TransactionService".to_string(), e.to_string())
+                    });
+                let _ = response.send(page);
+            }
+            TransactionServiceRequest::GetArchivedTransactions {
+                since,
+                until,
+                after,
+                limit,
+                response,
+            } => {
+                let page = self
+                    .archive_repository
+                    .get_archived(since, until, after.as_ref(), limit)
+                    .await
+                    .map_err(|e| ServiceError::Repository("Archive".to_string(), e.to_string()));
+                let _ = response.send(page);
+            }
+            TransactionServiceRequest::IsPaused { response } => {
+                let _ = response.send(self.is_paused());
+            }
+            TransactionServiceRequest::SetPaused {
+                paused,
+                requested_by,
+                reason,
+                response,
+            } => {
+                let result = self
+                    .set_paused(paused, &requested_by, reason.as_deref())
+                    .await;
+                let _ = response.send(result);
+            }
+            TransactionServiceRequest::GetAssetAvailability { response } => {
+                let _ = response.send(self.get_asset_availability().await);
+            }
+            TransactionServiceRequest::SetAssetEnabled {
+                asset_id,
+                enabled,
+                requested_by,
+                reason,
+                response,
+            } => {
+                let result = self
+                    .set_asset_enabled(&asset_id, enabled, &requested_by, reason.as_deref())
+                    .await;
+                let _ = response.send(result);
+            }
+            TransactionServiceRequest::GetDepositClosure { response } => {
+                let _ = response.send(self.deposit_closure().await);
+            }
+            TransactionServiceRequest::GetMaintenanceWindows { response } => {
+                let _ = response.send(self.get_maintenance_windows().await);
+            }
+            TransactionServiceRequest::ScheduleMaintenance { window, response } => {
+                let _ = response.send(self.schedule_maintenance(window).await);
+            }
+            TransactionServiceRequest::CancelMaintenance {
+                id,
+                requested_by,
+                reason,
+                response,
+            } => {
+                let result = self
+                    .cancel_maintenance(&id, &requested_by, reason.as_deref())
+                    .await;
+                let _ = response.send(result);
+            }
+            TransactionServiceRequest::GetUnreconciledTransactions { response } => {
+                let transactions = self.get_unreconciled_transactions().await;
+                let _ = response.send(transactions);
+            }
+            TransactionServiceRequest::RequeueTransaction {
+                transaction_id,
+                response,
+            } => {
+                let result = self.requeue_transaction(&transaction_id).await;
+                let _ = response.send(result);
+            }
+            TransactionServiceRequest::RetryPending {
+                transaction_id,
+                requested_by,
+                response,
+            } => {
+                let result = self.retry_pending(&transaction_id, &requested_by).await;
+                let _ = response.send(result);
+            }
+            TransactionServiceRequest::DropPending {
+                transaction_id,
+                requested_by,
+                reason,
+                response,
+            } => {
+                let result = self
+                    .drop_pending(&transaction_id, &requested_by, &reason)
+                    .await;
+                let _ = response.send(result);
+            }
+            TransactionServiceRequest::SwapCompleted { quote_sub_id, txid } => {
+                self.process_swap_completion(quote_sub_id, &txid).await;
+            }
+            TransactionServiceRequest::HoldTransaction {
+                transaction_id,
+                source,
+                reason,
+            } => {
+                if let Err(e) = self.hold_transaction(&transaction_id, &source, &reason).await {
+                    log::error!("Could not hold transaction {}: {}", transaction_id, e);
+                }
+            }
+            TransactionServiceRequest::GetHeldTransactions { response } => {
+                let held = self.get_held_transactions().await;
+                let _ = response.send(held);
+            }
+            TransactionServiceRequest::GetTimeline {
+                transaction_id,
+                response,
+            } => {
+                let timeline = self.get_timeline(&transaction_id).await;
+                let _ = response.send(timeline);
+            }
+            TransactionServiceRequest::CreateCampaign { campaign, response } => {
+                let _ = response.send(self.create_campaign(campaign).await);
+            }
+            TransactionServiceRequest::GetCampaigns { response } => {
+                let campaigns =
+                    self.campaign_repository.get_campaigns().await.map_err(|e| {
+                        ServiceError::Repository("Campaigns".to_string(), e.to_string())
+                    });
+                let _ = response.send(campaigns);
+            }
+            TransactionServiceRequest::SetCampaignActive {
+                campaign_id,
+                active,
+                response,
+            } => {
+                let updated = self
+                    .campaign_repository
+                    .set_active(&campaign_id, active)
+                    .await
+                    .map_err(|e| ServiceError::Repository("Campaigns".to_string(), e.to_string()));
+                let _ = response.send(updated);
+            }
+            TransactionServiceRequest::ResolveReview {
+                transaction_id,
+                decision,
+                decided_by,
+                note,
+                response,
+            } => {
+                let result = self
+                    .resolve_review(&transaction_id, decision, &decided_by, note.as_deref())
+                    .await;
+                let _ = response.send(result);
+            }
         }
     }
 }
 
-pub struct TransactionService;
+pub struct TransactionService {
+    workers: usize,
+}
 
 impl TransactionService {
-    pub fn new() -> Self {
-        TransactionService {}
+    pub fn new(workers: usize) -> Self {
+        TransactionService { workers }
     }
 }
 
 #[async_trait]
-impl Service<TransactionServiceRequest, TransactionRequestHandler> for TransactionService {}
+impl Service<TransactionServiceRequest, TransactionRequestHandler> for TransactionService {
+    fn worker_pool(&self) -> Option<WorkerPool> {
+        Some(WorkerPool {
+            name: "transaction",
+            size: self.workers,
+        })
+    }
+}
@@ -0,0 +1,229 @@
+use chrono::{Datelike, Offset, TimeZone};
+
+use crate::models::operator::{DepositClosure, MaintenanceWindow};
+use crate::settings;
+
+/// Closed periods chained back to back are followed at most this far.
+const MAX_CLOSED_PERIODS: usize = 64;
+
+#[derive(Clone)]
+struct OpeningHours {
+    days: Vec<chrono::Weekday>,
+    opens_at: chrono::NaiveTime,
+    closes_at: chrono::NaiveTime,
+}
+
+/// When new deposits are taken: within the business hours, if any, and
+/// outside of maintenance windows. Paid transactions are paid out anyway.
+#[derive(Clone)]
+pub struct DepositHours {
+    offset: chrono::FixedOffset,
+    hours: Option<OpeningHours>,
+    /// Windows from the settings, which can't be cancelled.
+    windows: Vec<MaintenanceWindow>,
+}
+
+impl DepositHours {
+    /// Settings are checked when loaded, so none of them fail here.
+    pub fn new(settings: &settings::Availability) -> Self {
+        let offset = chrono::FixedOffset::east_opt(settings.utc_offset_hours * 3600)
+            .unwrap_or_else(|| chrono::Utc.fix());
+        let business_hours = &settings.business_hours;
+        let hours = match (business_hours.weekdays(), business_hours.hours()) {
+            (Ok(days), Ok((opens_at, closes_at))) if business_hours.enabled => Some(OpeningHours {
+                days,
+                opens_at,
+                closes_at,
+            }),
+            _ => None,
+        };
+        let windows = settings
+            .maintenance
+            .iter()
+            .enumerate()
+            .map(|(index, window)| MaintenanceWindow {
+                id: format!("settings-{}", index),
+                starts_at: window.starts_at,
+                ends_at: window.ends_at,
+                reason: window.reason.clone(),
+                scheduled_by: "settings".to_string(),
+            })
+            .collect();
+
+        Self {
+            offset,
+            hours,
+            windows,
+        }
+    }
+
+    pub(super) fn configured_windows(&self) -> &[MaintenanceWindow] {
+        &self.windows
+    }
+
+    /// Why deposits are closed at `now`, given the windows scheduled besides
+    /// the configured ones. None while they are open.
+    pub(super) fn closure(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        scheduled: &[MaintenanceWindow],
+    ) -> Option<DepositClosure> {
+        let windows: Vec<&MaintenanceWindow> = self.windows.iter().chain(scheduled).collect();
+
+        let (closed_for, note) = match windows.iter().find(|window| window.contains(now)) {
+            Some(window) => ("maintenance", Some(window.reason.clone())),
+            None if self.next_opening(now) != Some(now) => ("business_hours", None),
+            None => return None,
+        };
+
+        Some(DepositClosure {
+            closed_for,
+            note,
+            reopens_at: self
+                .reopens_at(now, &windows)
+                .map(|at| at.with_timezone(&self.offset)),
+        })
+    }
+
+    /// First time from `now` that is in business hours and out of every
+    /// window.
+    fn reopens_at(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        windows: &[&MaintenanceWindow],
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        let mut at = now;
+        for _ in 0..MAX_CLOSED_PERIODS {
+            let window_end = windows
+                .iter()
+                .filter(|window| window.contains(at))
+                .map(|window| window.ends_at)
+                .max();
+            if let Some(window_end) = window_end {
+                at = window_end;
+                continue;
+            }
+
+            match self.next_opening(at)? {
+                opening if opening == at => return Some(at),
+                opening => at = opening,
+            }
+        }
+
+        None
+    }
+
+    /// `at` itself when within business hours, else when they next begin.
+    /// None when no day is open.
+    fn next_opening(
+        &self,
+        at: chrono::DateTime<chrono::Utc>,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        let Some(hours) = &self.hours else {
+            return Some(at);
+        };
+
+        let local = at.with_timezone(&self.offset).naive_local();
+        (0..=7)
+            .map(|days| local.date() + chrono::Duration::days(days))
+            .filter(|date| hours.days.contains(&date.weekday()))
+            .find_map(|date| {
+                let opens_at = date.and_time(hours.opens_at);
+                if local < opens_at {
+                    Some(opens_at)
+                } else if local < date.and_time(hours.closes_at) {
+                    Some(local)
+                } else {
+                    None
+                }
+            })
+            .and_then(|opening| self.offset.from_local_datetime(&opening).single())
+            .map(|opening| opening.with_timezone(&chrono::Utc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn business_hours() -> DepositHours {
+        DepositHours::new(&settings::Availability {
+            utc_offset_hours: -3,
+            business_hours: settings::BusinessHours {
+                enabled: true,
+                days: ["mon", "tue", "wed", "thu", "fri"]
+                    .map(str::to_string)
+                    .to_vec(),
+                opens_at: "08:00".to_string(),
+                closes_at: "20:00".to_string(),
+            },
+            maintenance: Vec::new(),
+        })
+    }
+
+    fn window(
+        starts_at: chrono::DateTime<Utc>,
+        ends_at: chrono::DateTime<Utc>,
+    ) -> MaintenanceWindow {
+        MaintenanceWindow {
+            id: "w1".to_string(),
+            starts_at,
+            ends_at,
+            reason: "Eulen maintenance".to_string(),
+            scheduled_by: "ops".to_string(),
+        }
+    }
+
+    #[test]
+    fn closed_outside_business_hours_until_the_next_open_day() {
+        let hours = business_hours();
+        // Friday 2025-07-11, 19:59 and 20:00 in Brasília
+        let open = Utc.with_ymd_and_hms(2025, 7, 11, 22, 59, 0).unwrap();
+        let closing = Utc.with_ymd_and_hms(2025, 7, 11, 23, 0, 0).unwrap();
+
+        assert!(hours.closure(open, &[]).is_none());
+        let closure = hours.closure(closing, &[]).unwrap();
+        assert_eq!(closure.closed_for, "business_hours");
+        assert_eq!(
+            closure.reopens_at.unwrap().to_rfc3339(),
+            "2025-07-14T08:00:00-03:00"
+        );
+    }
+
+    #[test]
+    fn maintenance_reopens_at_its_end_or_the_next_opening() {
+        let hours = business_hours();
+        let now = Utc.with_ymd_and_hms(2025, 7, 10, 15, 0, 0).unwrap();
+        let scheduled = [window(
+            now - chrono::Duration::hours(1),
+            now + chrono::Duration::hours(2),
+        )];
+
+        let closure = hours.closure(now, &scheduled).unwrap();
+        assert_eq!(closure.closed_for, "maintenance");
+        assert_eq!(closure.note.as_deref(), Some("Eulen maintenance"));
+        assert_eq!(
+            closure.reopens_at.unwrap().to_rfc3339(),
+            "2025-07-10T14:00:00-03:00"
+        );
+
+        // Ending after hours, it reopens with them the next morning
+        let late = [window(
+            now,
+            Utc.with_ymd_and_hms(2025, 7, 11, 0, 0, 0).unwrap(),
+        )];
+        assert_eq!(
+            hours
+                .closure(now, &late)
+                .unwrap()
+                .reopens_at
+                .unwrap()
+                .to_rfc3339(),
+            "2025-07-11T08:00:00-03:00"
+        );
+        assert!(hours
+            .closure(now + chrono::Duration::hours(2), &scheduled)
+            .is_none());
+    }
+}
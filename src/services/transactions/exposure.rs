@@ -0,0 +1,43 @@
+/// Risk caps on what the dealer pays out, on top of the users' own limits.
+/// Payouts past them wait for manual approval.
+#[derive(Clone, Debug)]
+pub struct PayoutExposurePolicy {
+    max_payout_cents: i32,
+    max_hourly_payout_cents: i64,
+}
+
+impl PayoutExposurePolicy {
+    /// A cap of 0 is off.
+    pub fn new(max_payout_cents: i32, max_hourly_payout_cents: i64) -> Self {
+        Self {
+            max_payout_cents,
+            max_hourly_payout_cents,
+        }
+    }
+
+    pub(super) fn has_hourly_cap(&self) -> bool {
+        self.max_hourly_payout_cents > 0
+    }
+
+    /// Why a payout of `amount_in_cents` may not go out on its own, given
+    /// the cents paid out in the last hour.
+    pub(super) fn exceeded(&self, amount_in_cents: i32, paid_last_hour: i64) -> Option<String> {
+        if self.max_payout_cents > 0 && amount_in_cents > self.max_payout_cents {
+            return Some(format!(
+                "Payout of {} cents is above the cap of {} cents per transaction",
+                amount_in_cents, self.max_payout_cents
+            ));
+        }
+
+        if self.has_hourly_cap()
+            && paid_last_hour + amount_in_cents as i64 > self.max_hourly_payout_cents
+        {
+            return Some(format!(
+                "Payout of {} cents would take the last hour's payouts from {} past the cap of {} cents",
+                amount_in_cents, paid_last_hour, self.max_hourly_payout_cents
+            ));
+        }
+
+        None
+    }
+}
@@ -0,0 +1,405 @@
+//! Fee and payout arithmetic. Fiat amounts are cents of the quote currency,
+//! asset amounts are base units (`precision` decimals, as registered for the
+//! asset) and prices are cents per whole unit of the asset in that same
+//! currency. The fee tiers are set in BRL.
+
+const BPS_DENOMINATOR: u128 = 10_000;
+const REFERRAL_BPS: u128 = 50;
+/// Flat fee charged below the first tier.
+const FLAT_FEE_CENTS: u128 = 2 * 100;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum FeeError {
+    #[error("asset price must be positive")]
+    InvalidPrice,
+    #[error("amount must be positive, got {0} cents")]
+    InvalidAmount(i32),
+    #[error("fee rate must not be negative, got {0} bps")]
+    InvalidFeeRate(i32),
+    #[error("fee arithmetic overflow")]
+    Overflow,
+    #[error("referral discount of {discount} exceeds the fee of {fee}")]
+    NegativeFee { fee: u64, discount: u64 },
+    #[error("fee of {fee} and referral bonus of {referral_bonus} leave nothing of {asset_amount}")]
+    NoPayoutLeft {
+        asset_amount: u64,
+        fee: u64,
+        referral_bonus: u64,
+    },
+}
+
+fn checked_amount(amount_in_cents: i32) -> Result<u128, FeeError> {
+    if amount_in_cents <= 0 {
+        return Err(FeeError::InvalidAmount(amount_in_cents));
+    }
+
+    Ok(amount_in_cents as u128)
+}
+
+/// Base units in one whole unit of an asset with `precision` decimals.
+fn base_units(precision: u8) -> Result<u128, FeeError> {
+    10u128
+        .checked_pow(u32::from(precision))
+        .ok_or(FeeError::Overflow)
+}
+
+/// Converts `bps` basis points of an amount in cents to base units of the
+/// asset, rounding down.
+fn bps_in_asset(
+    cents: u128,
+    bps: u128,
+    asset_price_in_cents: u64,
+    precision: u8,
+) -> Result<u64, FeeError> {
+    if asset_price_in_cents == 0 {
+        return Err(FeeError::InvalidPrice);
+    }
+
+    let units = cents
+        .checked_mul(bps)
+        .and_then(|v| v.checked_mul(base_units(precision).ok()?))
+        .ok_or(FeeError::Overflow)?
+        / BPS_DENOMINATOR
+        / asset_price_in_cents as u128;
+
+    u64::try_from(units).map_err(|_| FeeError::Overflow)
+}
+
+/// Gross amount of the asset bought by a deposit.
+pub fn asset_amount(
+    amount_in_cents: i32,
+    asset_price_in_cents: u64,
+    precision: u8,
+) -> Result<u64, FeeError> {
+    bps_in_asset(
+        checked_amount(amount_in_cents)?,
+        BPS_DENOMINATOR,
+        asset_price_in_cents,
+        precision,
+    )
+}
+
+/// Consumer fee in the asset: a flat fee for small deposits, then a rate
+/// decreasing with the amount. Referred users get 0.5% off.
+pub fn consumer_fee(
+    amount_in_cents: i32,
+    asset_price_in_cents: u64,
+    precision: u8,
+    has_referral: bool,
+) -> Result<u64, FeeError> {
+    let amount = checked_amount(amount_in_cents)?;
+    let in_asset = |cents, bps| bps_in_asset(cents, bps, asset_price_in_cents, precision);
+
+    let fee = if amount < 55 * 100 {
+        in_asset(FLAT_FEE_CENTS, BPS_DENOMINATOR)?
+    } else if amount < 500 * 100 {
+        in_asset(amount, 350)?
+    } else if amount < 5000 * 100 {
+        in_asset(amount, 325)?
+    } else {
+        in_asset(amount, 275)?
+    };
+
+    if !has_referral {
+        return Ok(fee);
+    }
+
+    let discount = in_asset(amount, REFERRAL_BPS)?;
+    discount_fee(fee, discount)
+}
+
+fn discount_fee(fee: u64, discount: u64) -> Result<u64, FeeError> {
+    fee.checked_sub(discount)
+        .ok_or(FeeError::NegativeFee { fee, discount })
+}
+
+/// Bonus paid to the referrer: 0.5% of the deposit, in the asset.
+pub fn referral_bonus(
+    amount_in_cents: i32,
+    asset_price_in_cents: u64,
+    precision: u8,
+) -> Result<u64, FeeError> {
+    bps_in_asset(
+        checked_amount(amount_in_cents)?,
+        REFERRAL_BPS,
+        asset_price_in_cents,
+        precision,
+    )
+}
+
+/// What the referral bonus of a deposit is worth, in cents.
+pub fn referral_bonus_in_cents(amount_in_cents: i32) -> i64 {
+    (amount_in_cents.max(0) as u128 * REFERRAL_BPS / BPS_DENOMINATOR) as i64
+}
+
+/// Merchant fee in the asset, at the merchant's rate.
+pub fn merchant_fee(
+    amount_in_cents: i32,
+    asset_price_in_cents: u64,
+    precision: u8,
+    fee_bps: i32,
+) -> Result<u64, FeeError> {
+    if fee_bps < 0 {
+        return Err(FeeError::InvalidFeeRate(fee_bps));
+    }
+
+    bps_in_asset(
+        checked_amount(amount_in_cents)?,
+        fee_bps as u128,
+        asset_price_in_cents,
+        precision,
+    )
+}
+
+/// Part of the fee waived by a campaign.
+pub fn campaign_discount(fee: u64, discount_bps: i32) -> Result<u64, FeeError> {
+    if !(0..=BPS_DENOMINATOR as i32).contains(&discount_bps) {
+        return Err(FeeError::InvalidFeeRate(discount_bps));
+    }
+
+    let discount = fee as u128 * discount_bps as u128 / BPS_DENOMINATOR;
+    Ok(discount as u64)
+}
+
+/// What is left for the user once the fee and the referral bonus are taken
+/// out. The user always gets something and the outputs never exceed the
+/// amount bought.
+pub fn user_payout(asset_amount: u64, fee: u64, referral_bonus: u64) -> Result<u64, FeeError> {
+    let no_payout_left = FeeError::NoPayoutLeft {
+        asset_amount,
+        fee,
+        referral_bonus,
+    };
+
+    let deductions = fee.checked_add(referral_bonus).ok_or(FeeError::Overflow)?;
+    let user_amount = match asset_amount.checked_sub(deductions) {
+        Some(user_amount) if user_amount > 0 => user_amount,
+        _ => return Err(no_payout_left),
+    };
+
+    debug_assert!(user_amount + referral_bonus + fee <= asset_amount);
+
+    Ok(user_amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // R$ 5,00 per unit keeps the numbers readable: 1 cent = 200_000 base units
+    const PRICE: u64 = 500;
+    const PRECISION: u8 = 8;
+
+    /// Value in cents of an amount of the asset, rounding down.
+    fn fiat_value(asset_amount: u64, asset_price_in_cents: u64, precision: u8) -> Option<u64> {
+        let cents =
+            asset_amount as u128 * asset_price_in_cents as u128 / base_units(precision).ok()?;
+        u64::try_from(cents).ok()
+    }
+
+    #[test]
+    fn asset_amount_converts_cents_to_base_units() {
+        assert_eq!(asset_amount(100, PRICE, PRECISION), Ok(20_000_000));
+        assert_eq!(asset_amount(1, PRICE, PRECISION), Ok(200_000));
+    }
+
+    #[test]
+    fn rejects_invalid_inputs() {
+        assert_eq!(
+            asset_amount(0, PRICE, PRECISION),
+            Err(FeeError::InvalidAmount(0))
+        );
+        assert_eq!(
+            asset_amount(-1, PRICE, PRECISION),
+            Err(FeeError::InvalidAmount(-1))
+        );
+        assert_eq!(asset_amount(100, 0, PRECISION), Err(FeeError::InvalidPrice));
+        assert_eq!(
+            consumer_fee(100, 0, PRECISION, false),
+            Err(FeeError::InvalidPrice)
+        );
+        assert_eq!(
+            merchant_fee(100, PRICE, PRECISION, -1),
+            Err(FeeError::InvalidFeeRate(-1))
+        );
+    }
+
+    #[test]
+    fn consumer_fee_tier_boundaries() {
+        // Flat R$ 2,00 below R$ 55,00
+        assert_eq!(consumer_fee(5_499, PRICE, PRECISION, false), Ok(40_000_000));
+        // 3.5% from R$ 55,00
+        assert_eq!(consumer_fee(5_500, PRICE, PRECISION, false), Ok(38_500_000));
+        assert_eq!(
+            consumer_fee(49_999, PRICE, PRECISION, false),
+            Ok(349_993_000)
+        );
+        // 3.25% from R$ 500,00
+        assert_eq!(
+            consumer_fee(50_000, PRICE, PRECISION, false),
+            Ok(325_000_000)
+        );
+        assert_eq!(
+            consumer_fee(499_999, PRICE, PRECISION, false),
+            Ok(3_249_993_500)
+        );
+        // 2.75% from R$ 5.000,00
+        assert_eq!(
+            consumer_fee(500_000, PRICE, PRECISION, false),
+            Ok(2_750_000_000)
+        );
+    }
+
+    #[test]
+    fn referral_discount_is_half_a_percent() {
+        assert_eq!(consumer_fee(10_000, PRICE, PRECISION, true), Ok(60_000_000));
+        assert_eq!(referral_bonus(10_000, PRICE, PRECISION), Ok(10_000_000));
+        // The flat fee is discounted too
+        assert_eq!(
+            consumer_fee(5_499, PRICE, PRECISION, true),
+            Ok(40_000_000 - 5_499_000)
+        );
+    }
+
+    #[test]
+    fn referral_discount_larger_than_fee_is_an_error() {
+        assert_eq!(discount_fee(100, 100), Ok(0));
+        assert_eq!(
+            discount_fee(10, 11),
+            Err(FeeError::NegativeFee {
+                fee: 10,
+                discount: 11
+            })
+        );
+    }
+
+    #[test]
+    fn merchant_fee_uses_the_merchant_rate() {
+        assert_eq!(merchant_fee(10_000, PRICE, PRECISION, 150), Ok(30_000_000));
+        assert_eq!(merchant_fee(10_000, PRICE, PRECISION, 0), Ok(0));
+    }
+
+    #[test]
+    fn campaign_discount_never_exceeds_the_fee() {
+        assert_eq!(campaign_discount(1_000, 10_000), Ok(1_000));
+        assert_eq!(campaign_discount(1_000, 5_000), Ok(500));
+        assert_eq!(campaign_discount(u64::MAX, 10_000), Ok(u64::MAX));
+        assert_eq!(campaign_discount(1_000, 0), Ok(0));
+        assert_eq!(
+            campaign_discount(1_000, 10_001),
+            Err(FeeError::InvalidFeeRate(10_001))
+        );
+    }
+
+    #[test]
+    fn large_amounts_do_not_overflow() {
+        assert_eq!(
+            asset_amount(i32::MAX, 1, PRECISION),
+            Ok(214_748_364_700_000_000)
+        );
+        assert_eq!(
+            consumer_fee(i32::MAX, 1, PRECISION, true),
+            Ok(4_831_838_205_750_000)
+        );
+        assert_eq!(
+            merchant_fee(i32::MAX, 1, PRECISION, i32::MAX),
+            Err(FeeError::Overflow)
+        );
+    }
+
+    #[test]
+    fn user_payout_takes_fee_and_bonus_out() {
+        assert_eq!(user_payout(1_000, 100, 50), Ok(850));
+        assert_eq!(user_payout(1_000, 0, 0), Ok(1_000));
+    }
+
+    #[test]
+    fn user_payout_never_goes_negative_or_zero() {
+        let no_payout_left = |fee, referral_bonus| FeeError::NoPayoutLeft {
+            asset_amount: 1_000,
+            fee,
+            referral_bonus,
+        };
+
+        assert_eq!(user_payout(1_000, 1_000, 0), Err(no_payout_left(1_000, 0)));
+        assert_eq!(user_payout(1_000, 900, 200), Err(no_payout_left(900, 200)));
+        assert_eq!(user_payout(1_000, u64::MAX, 1), Err(FeeError::Overflow));
+    }
+
+    #[test]
+    fn small_deposits_keep_outputs_within_the_amount_bought() {
+        for amount_in_cents in [1, 99, 200, 5_499, 5_500, 49_999, 50_000, 500_000] {
+            for has_referral in [false, true] {
+                let asset_amount = asset_amount(amount_in_cents, PRICE, PRECISION).unwrap();
+                let fee = consumer_fee(amount_in_cents, PRICE, PRECISION, has_referral).unwrap();
+                let bonus = if has_referral {
+                    referral_bonus(amount_in_cents, PRICE, PRECISION).unwrap()
+                } else {
+                    0
+                };
+
+                match user_payout(asset_amount, fee, bonus) {
+                    Ok(user_amount) => assert!(user_amount + fee + bonus <= asset_amount),
+                    Err(FeeError::NoPayoutLeft { .. }) => assert!(fee + bonus >= asset_amount),
+                    Err(e) => panic!("unexpected error: {}", e),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn amounts_scale_with_the_asset_precision() {
+        // R$ 1,00 buys a fifth of a unit
+        assert_eq!(asset_amount(100, PRICE, 2), Ok(20));
+        assert_eq!(asset_amount(100, PRICE, 6), Ok(200_000));
+        assert_eq!(asset_amount(100, PRICE, 8), Ok(20_000_000));
+        // 3.5%, 1.5% and 0.5% of R$ 100,00
+        assert_eq!(consumer_fee(10_000, PRICE, 2, false), Ok(70));
+        assert_eq!(merchant_fee(10_000, PRICE, 6, 150), Ok(300_000));
+        assert_eq!(referral_bonus(10_000, PRICE, 2), Ok(10));
+        assert_eq!(
+            fiat_value(10, PRICE, 2),
+            Some(referral_bonus_in_cents(10_000) as u64)
+        );
+        assert_eq!(asset_amount(100, PRICE, 39), Err(FeeError::Overflow));
+    }
+
+    #[test]
+    fn amounts_round_trip_through_cents() {
+        for precision in [2, 6, 8] {
+            // At R$ 1,00 per unit every cent is a whole number of base units
+            for amount_in_cents in [1, 99, 5_500, 500_000, i32::MAX] {
+                let bought = asset_amount(amount_in_cents, 100, precision).unwrap();
+                assert_eq!(
+                    fiat_value(bought, 100, precision),
+                    Some(amount_in_cents as u64)
+                );
+            }
+
+            // Otherwise the deposit buys the most base units it can pay for
+            for price in [1, 550, 33_333, 60_000_000] {
+                for amount_in_cents in [1, 99, 5_500, 500_000] {
+                    let bought = asset_amount(amount_in_cents, price, precision).unwrap();
+                    let value = fiat_value(bought, price, precision).unwrap();
+                    let one_more = fiat_value(bought + 1, price, precision).unwrap();
+                    assert!(value <= amount_in_cents as u64);
+                    assert!(one_more >= amount_in_cents as u64);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn payouts_add_up_at_every_precision() {
+        for precision in [2, 6, 8] {
+            let bought = asset_amount(10_000, PRICE, precision).unwrap();
+            let fee = consumer_fee(10_000, PRICE, precision, true).unwrap();
+            let bonus = referral_bonus(10_000, PRICE, precision).unwrap();
+            let user_amount = user_payout(bought, fee, bonus).unwrap();
+
+            assert_eq!(user_amount + fee + bonus, bought);
+            // R$ 100,00 less a 3% fee and a 0,5% bonus
+            assert_eq!(fiat_value(user_amount, PRICE, precision), Some(9_650));
+        }
+    }
+}
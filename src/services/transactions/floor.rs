@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::models::transactions::DustPolicy;
+
+#[derive(Clone, Debug)]
+pub struct PayoutFloorPolicy {
+    min_payouts: Arc<HashMap<String, u64>>,
+    dust_threshold: u64,
+    below_dust: DustPolicy,
+}
+
+impl PayoutFloorPolicy {
+    pub fn new(
+        min_payouts: HashMap<String, u64>,
+        dust_threshold: u64,
+        below_dust: DustPolicy,
+    ) -> Self {
+        Self {
+            min_payouts: Arc::new(min_payouts),
+            dust_threshold,
+            below_dust,
+        }
+    }
+
+    /// Smallest payout accepted at deposit time, in base units of the asset.
+    /// Never below the dust threshold.
+    pub(super) fn min_payout(&self, asset: &str) -> u64 {
+        self.min_payouts
+            .get(asset)
+            .copied()
+            .unwrap_or(0)
+            .max(self.dust_threshold)
+    }
+
+    pub(super) fn dust_threshold(&self) -> u64 {
+        self.dust_threshold
+    }
+
+    pub(super) fn is_dust(&self, amount: u64) -> bool {
+        amount < self.dust_threshold
+    }
+
+    pub(super) fn below_dust(&self) -> DustPolicy {
+        self.below_dust
+    }
+}
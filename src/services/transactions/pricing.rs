@@ -0,0 +1,132 @@
+use std::fmt;
+
+use crate::models::price::AssetPrice;
+use crate::services::supervisor::ServiceHealth;
+
+/// Input a payout can't be priced without.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum StaleInput {
+    /// The price wasn't refetched for this many seconds.
+    Price { age_secs: i64 },
+    /// The asset paid out can't be swapped back at the price given.
+    Sideswap,
+}
+
+impl StaleInput {
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            StaleInput::Price { .. } => "stale_price",
+            StaleInput::Sideswap => "sideswap_down",
+        }
+    }
+}
+
+impl fmt::Display for StaleInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StaleInput::Price { age_secs } => write!(f, "price is {}s old", age_secs),
+            StaleInput::Sideswap => write!(f, "Sideswap is disconnected"),
+        }
+    }
+}
+
+/// Refuses to price payouts while their inputs stopped updating. Checked
+/// when the payout is priced, since the price fetch and the Sideswap
+/// connection can fail long after the deposit was quoted.
+#[derive(Clone)]
+pub struct PricingWatchdog {
+    max_price_age: Option<chrono::Duration>,
+    health: ServiceHealth,
+}
+
+impl PricingWatchdog {
+    /// A maximum age of 0 turns the price check off.
+    pub fn new(max_price_age_secs: u64, health: ServiceHealth) -> Self {
+        Self {
+            max_price_age: (max_price_age_secs > 0)
+                .then(|| chrono::Duration::seconds(max_price_age_secs as i64)),
+            health,
+        }
+    }
+
+    pub(super) fn check(
+        &self,
+        price: &AssetPrice,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Option<StaleInput> {
+        stale_input(
+            price,
+            now,
+            self.max_price_age,
+            !self.health.is_disconnected("sideswap"),
+        )
+    }
+}
+
+fn stale_input(
+    price: &AssetPrice,
+    now: chrono::DateTime<chrono::Utc>,
+    max_price_age: Option<chrono::Duration>,
+    sideswap_connected: bool,
+) -> Option<StaleInput> {
+    // Pegged prices have no aggregation and depend on neither input
+    price.aggregation?;
+
+    let age = now - price.updated_at;
+    if max_price_age.is_some_and(|max_price_age| age > max_price_age) {
+        return Some(StaleInput::Price {
+            age_secs: age.num_seconds(),
+        });
+    }
+    if !sideswap_connected {
+        return Some(StaleInput::Sideswap);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::price::PriceAggregation;
+    use chrono::TimeZone;
+
+    fn price(updated_at: chrono::DateTime<chrono::Utc>, pegged: bool) -> AssetPrice {
+        AssetPrice {
+            price: 100.0,
+            aggregation: (!pegged).then_some(PriceAggregation::Median),
+            updated_at,
+            expires_at: updated_at + chrono::Duration::seconds(60),
+        }
+    }
+
+    #[test]
+    fn stale_inputs_block_pricing() {
+        let now = chrono::Utc.with_ymd_and_hms(2025, 7, 8, 12, 0, 0).unwrap();
+        let max_age = Some(chrono::Duration::seconds(180));
+        let fresh = price(now - chrono::Duration::seconds(180), false);
+        let old = price(now - chrono::Duration::seconds(181), false);
+
+        assert_eq!(stale_input(&fresh, now, max_age, true), None);
+        assert_eq!(
+            stale_input(&old, now, max_age, true),
+            Some(StaleInput::Price { age_secs: 181 })
+        );
+        assert_eq!(
+            stale_input(&fresh, now, max_age, false),
+            Some(StaleInput::Sideswap)
+        );
+        assert_eq!(stale_input(&old, now, None, true), None);
+    }
+
+    #[test]
+    fn pegged_prices_are_never_stale() {
+        let now = chrono::Utc.with_ymd_and_hms(2025, 7, 8, 12, 0, 0).unwrap();
+        let pegged = price(now - chrono::Duration::days(1), true);
+
+        assert_eq!(
+            stale_input(&pegged, now, Some(chrono::Duration::seconds(180)), false),
+            None
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use super::PendingTransaction;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PayoutTier {
+    /// Waited longer than the starvation threshold. Goes first and holds the
+    /// balance of its asset until it is paid.
+    Starving,
+    /// Small enough to usually be paid straight from the float.
+    Small,
+    Regular,
+}
+
+#[derive(Clone, Debug)]
+pub struct PayoutPriorityPolicy {
+    small_payout_cents: i32,
+    starvation_after: chrono::Duration,
+}
+
+impl PayoutPriorityPolicy {
+    pub fn new(small_payout_cents: i32, starvation_after_secs: u64) -> Self {
+        Self {
+            small_payout_cents,
+            starvation_after: chrono::Duration::seconds(starvation_after_secs as i64),
+        }
+    }
+
+    pub(super) fn tier(
+        &self,
+        pending_tx: &PendingTransaction,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> PayoutTier {
+        if now - pending_tx.queued_at >= self.starvation_after {
+            PayoutTier::Starving
+        } else if pending_tx.transaction.amount_in_cents <= self.small_payout_cents {
+            PayoutTier::Small
+        } else {
+            PayoutTier::Regular
+        }
+    }
+
+    /// Orders the queue by tier, then oldest first within each tier.
+    pub(super) fn sort(
+        &self,
+        pending_txs: &mut [PendingTransaction],
+        now: chrono::DateTime<chrono::Utc>,
+    ) {
+        pending_txs.sort_by_key(|pending_tx| (self.tier(pending_tx, now), pending_tx.queued_at));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transactions::Transaction;
+    use crate::utils::clock::{Clock, MockClock};
+    use chrono::TimeZone;
+
+    fn pending(clock: &MockClock, id: &str, amount_in_cents: i32) -> PendingTransaction {
+        PendingTransaction {
+            transaction: Transaction {
+                id: id.to_string(),
+                user_id: "user".to_string(),
+                address: "address".to_string(),
+                amount_in_cents,
+                asset: "depix".to_string(),
+                fee_collected: None,
+                network: "liquid".to_string(),
+                status: "eulen_depix_sent".to_string(),
+                created_at: clock.now(),
+                updated_at: clock.now(),
+            },
+            attempts: 0,
+            last_attempt: clock.now(),
+            queued_at: clock.now(),
+            swap_id: None,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn payout_starves_once_it_waited_past_the_threshold() {
+        let clock = MockClock::at(chrono::Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap());
+        let policy = PayoutPriorityPolicy::new(10_000, 600);
+        let pending_tx = pending(&clock, "regular", 100_000);
+        assert_eq!(policy.tier(&pending_tx, clock.now()), PayoutTier::Regular);
+
+        clock.advance(chrono::Duration::seconds(599));
+        assert_eq!(policy.tier(&pending_tx, clock.now()), PayoutTier::Regular);
+
+        clock.advance(chrono::Duration::seconds(1));
+        assert_eq!(policy.tier(&pending_tx, clock.now()), PayoutTier::Starving);
+    }
+
+    #[test]
+    fn starving_payouts_go_before_newer_small_ones() {
+        let clock = MockClock::at(chrono::Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap());
+        let policy = PayoutPriorityPolicy::new(10_000, 600);
+        let old = pending(&clock, "old", 100_000);
+
+        clock.advance(chrono::Duration::minutes(15));
+        let mut queue = vec![pending(&clock, "small", 5_000), old];
+        policy.sort(&mut queue, clock.now());
+
+        let order: Vec<&str> = queue
+            .iter()
+            .map(|pending_tx| pending_tx.transaction.id.as_str())
+            .collect();
+        assert_eq!(order, ["old", "small"]);
+    }
+}
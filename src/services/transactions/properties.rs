@@ -0,0 +1,182 @@
+//! Property tests of the fee and limit engine: random deposits, prices,
+//! referral states and fee schedules run through the same arithmetic the
+//! payout flow uses.
+
+use proptest::prelude::*;
+
+use super::fees::{
+    asset_amount, campaign_discount, consumer_fee, merchant_fee, referral_bonus, user_payout,
+    FeeError,
+};
+use crate::models::risk::RiskBand;
+use crate::repositories::transactions::{
+    check_deposit_limits, DAILY_LIMIT_CENTS, FIRST_DEPOSIT_LIMITS_CENTS,
+};
+
+/// Fee schedule of a deposit: the consumer tiers, with or without a
+/// referral, or a merchant rate.
+#[derive(Clone, Debug)]
+enum Schedule {
+    Consumer { has_referral: bool },
+    Merchant { fee_bps: i32 },
+}
+
+fn schedule() -> impl Strategy<Value = Schedule> {
+    prop_oneof![
+        any::<bool>().prop_map(|has_referral| Schedule::Consumer { has_referral }),
+        (0..=10_000i32).prop_map(|fee_bps| Schedule::Merchant { fee_bps }),
+    ]
+}
+
+/// From a cent per unit up to R$ 10 billion per unit.
+fn price() -> impl Strategy<Value = u64> {
+    1..=1_000_000_000_000u64
+}
+
+/// Registry precisions of Liquid assets.
+fn precision() -> impl Strategy<Value = u8> {
+    0..=8u8
+}
+
+fn amount() -> impl Strategy<Value = i32> {
+    prop_oneof![1..=1_000_000i32, 1..=i32::MAX]
+}
+
+/// `bps` of an asset amount, rounded up; fees computed from the fiat
+/// amount may round one unit above the same rate taken from the asset.
+fn upper_bound(asset_amount: u64, bps: u128) -> u128 {
+    (asset_amount as u128 * bps).div_ceil(10_000) + 1
+}
+
+proptest! {
+    #[test]
+    fn fee_functions_never_panic(
+        amount_in_cents in any::<i32>(),
+        price in any::<u64>(),
+        precision in any::<u8>(),
+        has_referral in any::<bool>(),
+        fee_bps in any::<i32>(),
+        discount_bps in any::<i32>(),
+        bought in any::<u64>(),
+        fee in any::<u64>(),
+        bonus in any::<u64>(),
+    ) {
+        let _ = asset_amount(amount_in_cents, price, precision);
+        let _ = consumer_fee(amount_in_cents, price, precision, has_referral);
+        let _ = referral_bonus(amount_in_cents, price, precision);
+        let _ = merchant_fee(amount_in_cents, price, precision, fee_bps);
+        let _ = campaign_discount(fee, discount_bps);
+        let _ = user_payout(bought, fee, bonus);
+    }
+
+    #[test]
+    fn consumer_fee_stays_within_its_tier(
+        amount_in_cents in amount(),
+        price in price(),
+        precision in precision(),
+        has_referral in any::<bool>(),
+    ) {
+        let bought = asset_amount(amount_in_cents, price, precision).unwrap();
+        let fee = consumer_fee(amount_in_cents, price, precision, false).unwrap();
+
+        if amount_in_cents >= 5_500 {
+            prop_assert!(fee as u128 <= upper_bound(bought, 350));
+            prop_assert!(fee as u128 + 1 >= bought as u128 * 275 / 10_000);
+        }
+
+        // A referral only ever lowers the fee
+        let discounted = consumer_fee(amount_in_cents, price, precision, has_referral).unwrap();
+        prop_assert!(discounted <= fee);
+    }
+
+    #[test]
+    fn merchant_fee_stays_within_the_merchant_rate(
+        amount_in_cents in amount(),
+        price in price(),
+        precision in precision(),
+        fee_bps in 0..=10_000i32,
+    ) {
+        let bought = asset_amount(amount_in_cents, price, precision).unwrap();
+        let fee = merchant_fee(amount_in_cents, price, precision, fee_bps).unwrap();
+
+        prop_assert!(fee as u128 <= upper_bound(bought, fee_bps as u128));
+        prop_assert!(fee <= bought + 1);
+    }
+
+    #[test]
+    fn campaign_discount_never_exceeds_the_fee(
+        fee in any::<u64>(),
+        discount_bps in 0..=10_000i32,
+    ) {
+        let discount = campaign_discount(fee, discount_bps).unwrap();
+        prop_assert!(discount <= fee);
+    }
+
+    #[test]
+    fn limits_are_never_exceeded(
+        transaction_count in 0..10i64,
+        daily_spending in 0..=DAILY_LIMIT_CENTS * 2,
+        amount_in_cents in any::<i32>(),
+        band in prop::sample::select(vec![RiskBand::Trusted, RiskBand::Standard, RiskBand::Risky]),
+    ) {
+        if check_deposit_limits(transaction_count, daily_spending, amount_in_cents, band).is_ok() {
+            let amount = amount_in_cents as i64;
+            prop_assert!(amount + daily_spending <= band.scale(DAILY_LIMIT_CENTS));
+            if let Some(limit) = FIRST_DEPOSIT_LIMITS_CENTS.get(transaction_count as usize) {
+                prop_assert!(amount <= band.scale(*limit));
+            }
+            if band == RiskBand::Risky {
+                prop_assert!(amount + daily_spending <= DAILY_LIMIT_CENTS);
+            }
+        }
+    }
+
+    #[test]
+    fn accepted_deposits_pay_out_exactly_what_was_bought(
+        transaction_count in 0..10i64,
+        daily_spending in 0..=DAILY_LIMIT_CENTS,
+        amount_in_cents in 1..=DAILY_LIMIT_CENTS as i32,
+        price in price(),
+        precision in precision(),
+        schedule in schedule(),
+        discount_bps in 0..=10_000i32,
+    ) {
+        // Merchant deposits skip the consumer limits
+        if let Schedule::Consumer { .. } = schedule {
+            prop_assume!(
+                check_deposit_limits(
+                    transaction_count,
+                    daily_spending,
+                    amount_in_cents,
+                    RiskBand::Standard,
+                )
+                .is_ok()
+            );
+        }
+
+        let bought = asset_amount(amount_in_cents, price, precision).unwrap();
+        let (fee, bonus) = match schedule {
+            Schedule::Consumer { has_referral } => {
+                let fee = consumer_fee(amount_in_cents, price, precision, has_referral).unwrap();
+                let bonus = match has_referral {
+                    true => referral_bonus(amount_in_cents, price, precision).unwrap(),
+                    false => 0,
+                };
+                (fee, bonus)
+            }
+            Schedule::Merchant { fee_bps } => {
+                (merchant_fee(amount_in_cents, price, precision, fee_bps).unwrap(), 0)
+            }
+        };
+        let fee = fee - campaign_discount(fee, discount_bps).unwrap();
+
+        match user_payout(bought, fee, bonus) {
+            Ok(user_amount) => {
+                prop_assert!(user_amount > 0);
+                prop_assert_eq!(user_amount + fee + bonus, bought);
+            }
+            Err(FeeError::NoPayoutLeft { .. }) => prop_assert!(fee + bonus >= bought),
+            Err(e) => return Err(TestCaseError::fail(e.to_string())),
+        }
+    }
+}
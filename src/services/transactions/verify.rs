@@ -0,0 +1,428 @@
+//! Checks of a payout between building its PSET and signing it. The
+//! recipients are checked against what was recorded for the transaction
+//! they pay, and the PSET against the recipients, so a bug in either
+//! cannot misdirect funds.
+
+use lwk_wollet::elements::Address;
+use lwk_wollet::UnvalidatedRecipient;
+use std::str::FromStr;
+
+use crate::models::transactions::Transaction;
+use crate::models::wallet::PsetBalance;
+
+/// An output a payout PSET must have, as built from its recipient.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct ExpectedOutput {
+    pub script_pubkey: String,
+    pub asset_id: String,
+    pub value: u64,
+}
+
+/// What was recorded for a transaction when its payout was computed, from
+/// which the payout is derived again.
+pub(super) struct RecordedPayout {
+    /// Bought at the recorded price, before the fee.
+    pub gross_amount: u64,
+    /// Fee recorded with the price.
+    pub fee: u64,
+    /// The referrer's address, with the bonus recorded for it.
+    pub referral: Option<(String, u64)>,
+    /// Parts of the payout recorded so far.
+    pub partial_total: u64,
+    pub dust_threshold: u64,
+}
+
+/// Whether a payout is the part sent ahead of a swap or what is left.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum PayoutKind {
+    Partial,
+    Final,
+}
+
+/// Whether the recipients pay exactly what the records of the transaction
+/// add up to: the user's share of the amount bought after the fee, bonus
+/// and parts already sent, then the referrer's bonus, all in the bought
+/// asset. A partial payout pays the user the part recorded for it, leaving
+/// more than dust to the final one.
+pub(super) fn check_recipients(
+    transaction: &Transaction,
+    recorded: &RecordedPayout,
+    kind: PayoutKind,
+    recipients: &[UnvalidatedRecipient],
+) -> Result<(), String> {
+    let bonus = recorded.referral.as_ref().map_or(0, |(_, bonus)| *bonus);
+    let owed = recorded
+        .gross_amount
+        .checked_sub(recorded.fee)
+        .and_then(|owed| owed.checked_sub(bonus))
+        .ok_or_else(|| {
+            format!(
+                "Fee of {} and bonus of {} exceed the {} bought",
+                recorded.fee, bonus, recorded.gross_amount
+            )
+        })?;
+
+    let expected = match kind {
+        PayoutKind::Partial => {
+            if recorded
+                .partial_total
+                .saturating_add(recorded.dust_threshold)
+                > owed
+            {
+                return Err(format!(
+                    "Part of {} leaves less than dust of the {} owed",
+                    recorded.partial_total, owed
+                ));
+            }
+            vec![(transaction.address.as_str(), recorded.partial_total)]
+        }
+        PayoutKind::Final => {
+            let user_amount = match recorded.partial_total {
+                0 => owed,
+                sent => owed.saturating_sub(sent).max(recorded.dust_threshold),
+            };
+            let mut expected = vec![(transaction.address.as_str(), user_amount)];
+            expected.extend(
+                recorded
+                    .referral
+                    .as_ref()
+                    .map(|(address, bonus)| (address.as_str(), *bonus)),
+            );
+            expected
+        }
+    };
+
+    if recipients.len() != expected.len() {
+        return Err(format!(
+            "Pays {} recipients instead of {}",
+            recipients.len(),
+            expected.len()
+        ));
+    }
+    for (recipient, (address, amount)) in recipients.iter().zip(expected) {
+        if recipient.address != address {
+            return Err(format!("Pays {} instead of {}", recipient.address, address));
+        }
+        if recipient.asset != transaction.asset {
+            return Err(format!(
+                "Pays {} instead of {}",
+                recipient.asset, transaction.asset
+            ));
+        }
+        if recipient.satoshi != amount {
+            return Err(format!(
+                "Pays {} to {}, the records add up to {}",
+                recipient.satoshi, address, amount
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Outputs the recipients should have turned into.
+pub(super) fn expected_outputs(
+    recipients: &[UnvalidatedRecipient],
+) -> Result<Vec<ExpectedOutput>, String> {
+    recipients
+        .iter()
+        .map(|recipient| {
+            let address = Address::from_str(&recipient.address)
+                .map_err(|e| format!("Invalid address {}: {}", recipient.address, e))?;
+            Ok(ExpectedOutput {
+                script_pubkey: format!("{:x}", address.script_pubkey()),
+                asset_id: recipient.asset.clone(),
+                value: recipient.satoshi,
+            })
+        })
+        .collect()
+}
+
+/// Whether the PSET pays exactly the expected outputs, sends everything
+/// else back to the wallet and spends no coins but the wallet's.
+pub(super) fn check_pset(
+    balance: &PsetBalance,
+    expected: &[ExpectedOutput],
+    policy_asset: &str,
+) -> Result<(), String> {
+    let mut missing = expected.to_vec();
+    for recipient in &balance.recipients {
+        let (Some(asset_id), Some(value)) = (&recipient.asset_id, recipient.value) else {
+            return Err(format!(
+                "Output {} hides its asset or value",
+                recipient.vout
+            ));
+        };
+
+        let position = missing.iter().position(|output| {
+            output.script_pubkey == recipient.script_pubkey
+                && output.asset_id == *asset_id
+                && output.value == value
+        });
+        match position {
+            Some(position) => {
+                missing.remove(position);
+            }
+            None => {
+                return Err(format!(
+                    "Output {} pays {} of {} to {}, which the payout does not",
+                    recipient.vout, value, asset_id, recipient.script_pubkey
+                ))
+            }
+        }
+    }
+
+    if let Some(output) = missing.first() {
+        return Err(format!(
+            "No output pays {} of {} to {}",
+            output.value, output.asset_id, output.script_pubkey
+        ));
+    }
+
+    // With only the wallet's coins spent, the wallet loses exactly what
+    // the recipients and the network get
+    let mut spent = std::collections::BTreeMap::<&str, i64>::new();
+    for output in expected {
+        *spent.entry(&output.asset_id).or_default() += output.value as i64;
+    }
+    *spent.entry(policy_asset).or_default() += balance.fee as i64;
+    for asset_id in balance.balances.keys() {
+        spent.entry(asset_id).or_default();
+    }
+    for (asset_id, amount) in spent {
+        let change = balance.balances.get(asset_id).copied().unwrap_or(0);
+        if change != -amount {
+            return Err(format!(
+                "Wallet balance of {} changes by {}, expected -{}",
+                asset_id, change, amount
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::wallet::PsetRecipient;
+
+    const USER: &str = "user-address";
+    const REFERRER: &str = "referrer-address";
+
+    fn transaction() -> Transaction {
+        Transaction {
+            id: "tx".to_string(),
+            user_id: "user".to_string(),
+            address: USER.to_string(),
+            amount_in_cents: 10_000,
+            asset: "depix".to_string(),
+            fee_collected: None,
+            network: "liquid".to_string(),
+            status: "eulen_depix_sent".to_string(),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn recipient(address: &str, satoshi: u64) -> UnvalidatedRecipient {
+        UnvalidatedRecipient {
+            address: address.to_string(),
+            satoshi,
+            asset: "depix".to_string(),
+        }
+    }
+
+    fn recorded(referral: Option<(&str, u64)>, partial_total: u64) -> RecordedPayout {
+        RecordedPayout {
+            gross_amount: 10_000,
+            fee: 400,
+            referral: referral.map(|(address, bonus)| (address.to_string(), bonus)),
+            partial_total,
+            dust_threshold: 500,
+        }
+    }
+
+    #[test]
+    fn recipients_pay_the_user_and_referrer_only() {
+        let referred = recorded(Some((REFERRER, 100)), 0);
+        let recipients = [recipient(USER, 9_500), recipient(REFERRER, 100)];
+        assert_eq!(
+            check_recipients(&transaction(), &referred, PayoutKind::Final, &recipients),
+            Ok(())
+        );
+
+        let misdirected = [recipient("someone-else", 9_600)];
+        let not_referred = recorded(None, 0);
+        assert!(check_recipients(
+            &transaction(),
+            &not_referred,
+            PayoutKind::Final,
+            &misdirected
+        )
+        .is_err());
+
+        // The referrer's output without a referrer
+        assert!(check_recipients(
+            &transaction(),
+            &not_referred,
+            PayoutKind::Final,
+            &recipients
+        )
+        .is_err());
+
+        let wrong_asset = [UnvalidatedRecipient {
+            asset: "lbtc".to_string(),
+            ..recipient(USER, 9_600)
+        }];
+        assert!(check_recipients(
+            &transaction(),
+            &not_referred,
+            PayoutKind::Final,
+            &wrong_asset
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn recipients_pay_exactly_what_the_records_add_up_to() {
+        let referred = recorded(Some((REFERRER, 100)), 0);
+
+        // Short of what the user is owed as well as above it
+        for amount in [9_499, 9_501] {
+            let recipients = [recipient(USER, amount), recipient(REFERRER, 100)];
+            assert!(
+                check_recipients(&transaction(), &referred, PayoutKind::Final, &recipients)
+                    .is_err()
+            );
+        }
+
+        let bonus_moved = [recipient(USER, 9_450), recipient(REFERRER, 150)];
+        assert!(
+            check_recipients(&transaction(), &referred, PayoutKind::Final, &bonus_moved).is_err()
+        );
+    }
+
+    #[test]
+    fn final_payout_deducts_the_parts_sent() {
+        let sent = recorded(None, 6_000);
+        let rest = [recipient(USER, 3_600)];
+        assert_eq!(
+            check_recipients(&transaction(), &sent, PayoutKind::Final, &rest),
+            Ok(())
+        );
+
+        // What is left is never dust
+        let most_sent = recorded(None, 9_500);
+        let floor = [recipient(USER, 500)];
+        assert_eq!(
+            check_recipients(&transaction(), &most_sent, PayoutKind::Final, &floor),
+            Ok(())
+        );
+
+        let whole = [recipient(USER, 9_600)];
+        assert!(check_recipients(&transaction(), &sent, PayoutKind::Final, &whole).is_err());
+    }
+
+    #[test]
+    fn partial_payout_pays_the_recorded_part() {
+        let part = recorded(None, 6_000);
+        assert_eq!(
+            check_recipients(
+                &transaction(),
+                &part,
+                PayoutKind::Partial,
+                &[recipient(USER, 6_000)]
+            ),
+            Ok(())
+        );
+        assert!(check_recipients(
+            &transaction(),
+            &part,
+            PayoutKind::Partial,
+            &[recipient(USER, 6_500)]
+        )
+        .is_err());
+
+        // The rest would be dust
+        let too_large = recorded(None, 9_200);
+        assert!(check_recipients(
+            &transaction(),
+            &too_large,
+            PayoutKind::Partial,
+            &[recipient(USER, 9_200)]
+        )
+        .is_err());
+    }
+
+    fn expected(script_pubkey: &str, value: u64) -> ExpectedOutput {
+        ExpectedOutput {
+            script_pubkey: script_pubkey.to_string(),
+            asset_id: "depix".to_string(),
+            value,
+        }
+    }
+
+    fn balance(recipients: &[(&str, u64)], changes: &[(&str, i64)]) -> PsetBalance {
+        PsetBalance {
+            balances: changes
+                .iter()
+                .map(|(asset_id, amount)| (asset_id.to_string(), *amount))
+                .collect(),
+            fee: 30,
+            recipients: recipients
+                .iter()
+                .enumerate()
+                .map(|(vout, (script_pubkey, value))| PsetRecipient {
+                    vout: vout as u32,
+                    script_pubkey: script_pubkey.to_string(),
+                    asset_id: Some("depix".to_string()),
+                    value: Some(*value),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn pset_pays_the_expected_outputs() {
+        let outputs = [expected("0014aa", 9_500), expected("0014bb", 100)];
+        let pset = balance(
+            &[("0014bb", 100), ("0014aa", 9_500)],
+            &[("depix", -9_600), ("lbtc", -30)],
+        );
+        assert_eq!(check_pset(&pset, &outputs, "lbtc"), Ok(()));
+    }
+
+    #[test]
+    fn pset_with_other_outputs_is_refused() {
+        let outputs = [expected("0014aa", 9_500)];
+
+        let extra = balance(
+            &[("0014aa", 9_500), ("0014cc", 1_000)],
+            &[("depix", -10_500), ("lbtc", -30)],
+        );
+        assert!(check_pset(&extra, &outputs, "lbtc").is_err());
+
+        let changed = balance(&[("0014aa", 9_000)], &[("depix", -9_000), ("lbtc", -30)]);
+        assert!(check_pset(&changed, &outputs, "lbtc").is_err());
+
+        let missing = balance(&[], &[("lbtc", -30)]);
+        assert!(check_pset(&missing, &outputs, "lbtc").is_err());
+    }
+
+    #[test]
+    fn pset_keeps_the_change_in_the_wallet() {
+        let outputs = [expected("0014aa", 9_500)];
+
+        // Change of more than the fee missing from the wallet
+        let pset = balance(&[("0014aa", 9_500)], &[("depix", -9_500), ("lbtc", -1_030)]);
+        assert!(check_pset(&pset, &outputs, "lbtc").is_err());
+
+        // Another asset spent
+        let pset = balance(
+            &[("0014aa", 9_500)],
+            &[("depix", -9_500), ("lbtc", -30), ("usdt", -5)],
+        );
+        assert!(check_pset(&pset, &outputs, "lbtc").is_err());
+    }
+}
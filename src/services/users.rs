@@ -1,13 +1,31 @@
 use async_trait::async_trait;
+use dashmap::DashMap;
 use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::oneshot;
+use uuid::Uuid;
 
-use super::{RequestHandler, Service, ServiceError};
-use crate::{models::users, repositories::users::UserRepository};
+use super::{RequestHandler, Service, ServiceError, WorkerPool};
+use crate::{
+    models::{
+        receipts,
+        referrals::{ReferralBonus, ReferrerCredentials, ReferrerStatement},
+        risk::{RiskBand, RiskScore},
+        users,
+    },
+    repositories::{
+        referrals::ReferralRepository,
+        risk::RiskRepository,
+        users::{UnsettledTransactions, UserRepository},
+    },
+    utils::{clock::SharedClock, metrics, money, signing::DocumentSigner},
+};
 
 pub enum UserRequest {
     CreateUser {
         referral_code: Option<String>,
+        installation_id: Option<String>,
         response: oneshot::Sender<Result<users::User, ServiceError>>,
     },
     GetUser {
@@ -30,31 +48,146 @@ pub enum UserRequest {
         id: String,
         response: oneshot::Sender<Result<Option<String>, ServiceError>>,
     },
+    InvalidateUserDetails {
+        id: String,
+    },
+    GetDeviceLimits {
+        id: String,
+        response: oneshot::Sender<Result<Option<users::DeviceLimits>, ServiceError>>,
+    },
+    ExportUserData {
+        id: String,
+        request: users::DataSubjectRequest,
+        response: oneshot::Sender<Result<Option<users::UserDataExport>, ServiceError>>,
+    },
+    AnonymizeUser {
+        id: String,
+        request: users::DataSubjectRequest,
+        response: oneshot::Sender<Result<bool, ServiceError>>,
+    },
+    GetNotificationPreferences {
+        id: String,
+        response: oneshot::Sender<Result<Option<receipts::NotificationPreferences>, ServiceError>>,
+    },
+    SetNotificationPreferences {
+        id: String,
+        preferences: receipts::NotificationPreferencesUpdate,
+        response: oneshot::Sender<Result<Option<receipts::NotificationPreferences>, ServiceError>>,
+    },
+    /// Signed statement of the month starting on `month`.
+    GetStatement {
+        id: String,
+        month: chrono::NaiveDate,
+        response: oneshot::Sender<Result<Option<users::SignedStatement>, ServiceError>>,
+    },
+    /// Risk score of the user, computed again when it's older than a day.
+    GetRiskScore {
+        id: String,
+        response: oneshot::Sender<Result<Option<RiskScore>, ServiceError>>,
+    },
+    /// New API key for the user's own routes, replacing any they had. None
+    /// when there's no such user.
+    IssueUserKey {
+        id: String,
+        response: oneshot::Sender<Result<Option<users::UserCredentials>, ServiceError>>,
+    },
+    /// User holding the key.
+    AuthenticateUser {
+        api_key: String,
+        response: oneshot::Sender<Result<Option<String>, ServiceError>>,
+    },
+    /// New API key for a referrer to read their statements with. None when
+    /// the user has no referral code.
+    IssueReferrerKey {
+        id: String,
+        response: oneshot::Sender<Result<Option<ReferrerCredentials>, ServiceError>>,
+    },
+    /// Referrer holding the key.
+    AuthenticateReferrer {
+        api_key: String,
+        response: oneshot::Sender<Result<Option<String>, ServiceError>>,
+    },
+    /// Referral statement of the month starting on `month`.
+    GetReferrerStatement {
+        id: String,
+        month: chrono::NaiveDate,
+        response: oneshot::Sender<Result<ReferrerStatement, ServiceError>>,
+    },
+    GetReferrerPayments {
+        id: String,
+        response: oneshot::Sender<Result<Vec<ReferralBonus>, ServiceError>>,
+    },
 }
 
+// The app polls /user/{id} aggressively; details only change when one of the
+// user's transactions changes status, which invalidates the entry explicitly.
+const DETAILS_CACHE_TTL: Duration = Duration::from_secs(15);
+
+// Scores are refreshed when something about the user changes, and daily for
+// the signals that change on their own, like the account's age
+const RISK_SCORE_TTL_HOURS: i64 = 24;
+
+/// Most bonus payments handed to a referrer at once.
+const REFERRER_PAYMENTS_LIMIT: i64 = 500;
+
 #[derive(Clone)]
 pub struct UserRequestHandler {
     repository: UserRepository,
+    risk_repository: RiskRepository,
+    referral_repository: ReferralRepository,
+    clock: SharedClock,
+    details_cache: Arc<DashMap<String, (Instant, users::UserDetails)>>,
+    statement_signer: Option<DocumentSigner>,
 }
 
 impl UserRequestHandler {
-    pub fn new(sql_conn: PgPool) -> Self {
-        let repository = UserRepository::new(sql_conn);
+    pub fn new(
+        sql_conn: PgPool,
+        statement_signer: Option<DocumentSigner>,
+        clock: SharedClock,
+    ) -> Self {
+        let repository = UserRepository::new(sql_conn.clone(), clock.clone());
+        let risk_repository = RiskRepository::new(sql_conn.clone(), clock.clone());
+        let referral_repository = ReferralRepository::new(sql_conn);
 
-        UserRequestHandler { repository }
+        UserRequestHandler {
+            repository,
+            risk_repository,
+            referral_repository,
+            clock,
+            details_cache: Arc::new(DashMap::new()),
+            statement_signer,
+        }
     }
 
     async fn create_user(
         &self,
         referral_code: Option<String>,
+        installation_id: Option<String>,
     ) -> Result<users::User, ServiceError> {
-        self.repository
-            .insert_user(referral_code)
+        let user = self
+            .repository
+            .insert_user(referral_code, installation_id)
             .await
             .map_err(|e| {
                 log::error!("Failed to create user: {:?}", e);
                 ServiceError::Database(e.to_string())
-            })
+            })?;
+
+        // Accounts on the same device look riskier now
+        let linked_accounts = match self.repository.get_device_linked_users(&user.id).await {
+            Ok(linked_accounts) => linked_accounts,
+            Err(e) => {
+                log::error!("Failed to get accounts linked to {}: {}", user.id, e);
+                vec![user.id.clone()]
+            }
+        };
+        for user_id in linked_accounts {
+            self.refresh_risk_score_logged(&user_id).await;
+            self.invalidate_user_details(&user_id);
+        }
+
+        Ok(user)
     }
 
     async fn get_user(&self, id: &str) -> Result<Option<users::User>, ServiceError> {
@@ -68,7 +201,83 @@ impl UserRequestHandler {
         self.repository
             .verify_user(id)
             .await
-            .map_err(|e| ServiceError::Database(e.to_string()))
+            .map_err(|e| ServiceError::Database(e.to_string()))?;
+
+        self.refresh_risk_score_logged(id).await;
+        self.invalidate_user_details(id);
+        Ok(())
+    }
+
+    /// Scores the user from their signals as they are now and stores it.
+    /// None when there's no such user.
+    async fn refresh_risk_score(&self, user_id: &str) -> Result<Option<RiskScore>, ServiceError> {
+        let signals = self
+            .risk_repository
+            .get_signals(user_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Risk".to_string(), e.to_string()))?;
+        let Some(signals) = signals else {
+            return Ok(None);
+        };
+
+        let (score, factors) = signals.score();
+        let risk_score = RiskScore {
+            user_id: user_id.to_string(),
+            score,
+            band: RiskBand::of(score),
+            factors,
+            computed_at: self.clock.now(),
+        };
+
+        let previous = self
+            .risk_repository
+            .get_score(user_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Risk".to_string(), e.to_string()))?;
+        if previous.is_some_and(|previous| previous.band != risk_score.band) {
+            log::info!(
+                "User {} is now {} with a risk score of {}",
+                user_id,
+                risk_score.band.as_str(),
+                score
+            );
+            metrics::increment(
+                "risk_band_changes_total",
+                &[("band", risk_score.band.as_str())],
+            );
+        }
+
+        self.risk_repository
+            .save_score(&risk_score)
+            .await
+            .map_err(|e| ServiceError::Repository("Risk".to_string(), e.to_string()))?;
+
+        Ok(Some(risk_score))
+    }
+
+    /// A score that can't be refreshed is refreshed again when read.
+    async fn refresh_risk_score_logged(&self, user_id: &str) {
+        if let Err(e) = self.refresh_risk_score(user_id).await {
+            log::error!("Failed to refresh risk score of {}: {}", user_id, e);
+        }
+    }
+
+    async fn get_risk_score(&self, user_id: &str) -> Result<Option<RiskScore>, ServiceError> {
+        let stored = self
+            .risk_repository
+            .get_score(user_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Risk".to_string(), e.to_string()))?;
+
+        match stored {
+            Some(score)
+                if self.clock.now() - score.computed_at
+                    < chrono::Duration::hours(RISK_SCORE_TTL_HOURS) =>
+            {
+                Ok(Some(score))
+            }
+            _ => self.refresh_risk_score(user_id).await,
+        }
     }
 
     async fn get_user_daily_spending(&self, user_id: &str) -> Result<i64, ServiceError> {
@@ -79,13 +288,26 @@ impl UserRequestHandler {
     }
 
     async fn get_allowed_spending(&self, user_id: &str) -> Result<i64, ServiceError> {
+        let band = self
+            .get_risk_score(user_id)
+            .await?
+            .map(|score| score.band)
+            .unwrap_or(RiskBand::Standard);
+
         self.repository
-            .get_user_allowed_spending(user_id)
+            .get_user_allowed_spending(user_id, band)
             .await
             .map_err(|e| ServiceError::Database(e.to_string()))
     }
 
     async fn get_user_details(&self, user_id: &str) -> Result<Option<users::UserDetails>, ServiceError> {
+        if let Some(entry) = self.details_cache.get(user_id) {
+            let (cached_at, details) = entry.value();
+            if cached_at.elapsed() < DETAILS_CACHE_TTL {
+                return Ok(Some(details.clone()));
+            }
+        }
+
         let user = self.get_user(user_id).await?;
         if let None = user {
             log::debug!("User not found");
@@ -95,14 +317,269 @@ impl UserRequestHandler {
         let daily_spending = self.get_user_daily_spending(user_id).await?;
         let allowed_spending = self.get_allowed_spending(user_id).await?;
 
-        Ok(Some(users::UserDetails {
+        let details = users::UserDetails {
             id: user_id.to_string(),
             daily_spending,
             allowed_spending,
             is_verified: false,
+        };
+        self.details_cache
+            .insert(user_id.to_string(), (Instant::now(), details.clone()));
+
+        Ok(Some(details))
+    }
+
+    fn invalidate_user_details(&self, user_id: &str) {
+        self.details_cache.remove(user_id);
+    }
+
+    async fn get_device_limits(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<users::DeviceLimits>, ServiceError> {
+        self.repository
+            .get_device_limits(user_id)
+            .await
+            .map_err(|e| ServiceError::Database(e.to_string()))
+    }
+
+    async fn export_user_data(
+        &self,
+        user_id: &str,
+        request: users::DataSubjectRequest,
+    ) -> Result<Option<users::UserDataExport>, ServiceError> {
+        log::info!(
+            "Exporting data of user {} (requested by {})",
+            user_id,
+            request.requested_by
+        );
+
+        self.repository
+            .export_user_data(user_id, &request.requested_by, request.reference.as_deref())
+            .await
+            .map_err(|e| ServiceError::Repository("Users".to_string(), e.to_string()))
+    }
+
+    async fn anonymize_user(
+        &self,
+        user_id: &str,
+        request: users::DataSubjectRequest,
+    ) -> Result<bool, ServiceError> {
+        log::info!(
+            "Anonymizing user {} (requested by {})",
+            user_id,
+            request.requested_by
+        );
+
+        let anonymized = self
+            .repository
+            .anonymize_user(user_id, &request.requested_by, request.reference.as_deref())
+            .await
+            .map_err(|e| match e.downcast_ref::<UnsettledTransactions>() {
+                Some(_) => ServiceError::Internal("UnsettledTransactions".to_string()),
+                None => ServiceError::Repository("Users".to_string(), e.to_string()),
+            })?;
+
+        self.invalidate_user_details(user_id);
+
+        Ok(anonymized)
+    }
+
+    /// Preferences of a user, all receipts off when never set. None when the
+    /// user does not exist.
+    async fn get_notification_preferences(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<receipts::NotificationPreferences>, ServiceError> {
+        let Some(user) = self.get_user(user_id).await? else {
+            return Ok(None);
+        };
+
+        let preferences = self
+            .repository
+            .get_notification_preferences(user_id)
+            .await
+            .map_err(|e| ServiceError::Repository("Users".to_string(), e.to_string()))?;
+
+        let preferences = preferences.unwrap_or(receipts::NotificationPreferences {
+            user_id: user.id,
+            email: None,
+            phone: None,
+            email_receipts: false,
+            sms_receipts: false,
+            updated_at: user.created_at,
+        });
+
+        Ok(Some(preferences))
+    }
+
+    async fn set_notification_preferences(
+        &self,
+        user_id: &str,
+        preferences: receipts::NotificationPreferencesUpdate,
+    ) -> Result<Option<receipts::NotificationPreferences>, ServiceError> {
+        if self.get_user(user_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        self.repository
+            .set_notification_preferences(user_id, &preferences)
+            .await
+            .map(Some)
+            .map_err(|e| ServiceError::Repository("Users".to_string(), e.to_string()))
+    }
+
+    async fn get_statement(
+        &self,
+        user_id: &str,
+        month: chrono::NaiveDate,
+    ) -> Result<Option<users::SignedStatement>, ServiceError> {
+        let Some(signer) = &self.statement_signer else {
+            return Err(ServiceError::Internal("StatementsUnavailable".to_string()));
+        };
+        if self.get_user(user_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        let next_month = month
+            .checked_add_months(chrono::Months::new(1))
+            .ok_or(ServiceError::Internal("InvalidMonth".to_string()))?;
+        let entries = self
+            .repository
+            .get_statement_entries(
+                user_id,
+                month.and_time(chrono::NaiveTime::MIN).and_utc(),
+                next_month.and_time(chrono::NaiveTime::MIN).and_utc(),
+            )
+            .await
+            .map_err(|e| ServiceError::Repository("Users".to_string(), e.to_string()))?;
+
+        let total_paid_in_cents: i64 = entries
+            .iter()
+            .filter(|entry| {
+                matches!(
+                    entry.status.as_str(),
+                    "eulen_depix_sent" | "scheduled" | "pricing_unavailable" | "finished"
+                )
+            })
+            .map(|entry| i64::from(entry.amount_in_cents))
+            .sum();
+        let statement = users::Statement {
+            user_id: user_id.to_string(),
+            month: month.format("%Y-%m").to_string(),
+            entries,
+            total_paid_in_cents,
+            total_paid: money::format_brl(total_paid_in_cents),
+            generated_at: chrono::Utc::now(),
+        };
+
+        let body =
+            serde_json::to_string(&statement).map_err(|e| ServiceError::Internal(e.to_string()))?;
+        let signature = signer.sign(body.as_bytes());
+
+        Ok(Some(users::SignedStatement { body, signature }))
+    }
+
+    async fn issue_user_key(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<users::UserCredentials>, ServiceError> {
+        let api_key = format!("mzu_{}", Uuid::new_v4().simple());
+        let issued = self
+            .repository
+            .set_api_key(user_id, &api_key)
+            .await
+            .map_err(|e| ServiceError::Database(e.to_string()))?;
+        if !issued {
+            return Ok(None);
+        }
+
+        Ok(Some(users::UserCredentials {
+            user_id: user_id.to_string(),
+            api_key,
         }))
     }
 
+    async fn authenticate_user(&self, api_key: &str) -> Result<Option<String>, ServiceError> {
+        self.repository
+            .get_user_by_api_key(api_key)
+            .await
+            .map_err(|e| ServiceError::Database(e.to_string()))
+    }
+
+    async fn issue_referrer_key(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<ReferrerCredentials>, ServiceError> {
+        let api_key = format!("mzr_{}", Uuid::new_v4().simple());
+        let issued = self
+            .referral_repository
+            .set_api_key(user_id, &api_key)
+            .await
+            .map_err(|e| ServiceError::Repository("Referrals".to_string(), e.to_string()))?;
+        if !issued {
+            return Ok(None);
+        }
+
+        log::info!("Issued a statement key to referrer {}", user_id);
+        Ok(Some(ReferrerCredentials {
+            user_id: user_id.to_string(),
+            api_key,
+        }))
+    }
+
+    async fn authenticate_referrer(&self, api_key: &str) -> Result<Option<String>, ServiceError> {
+        self.referral_repository
+            .get_referrer_by_api_key(api_key)
+            .await
+            .map_err(|e| ServiceError::Repository("Referrals".to_string(), e.to_string()))
+    }
+
+    /// Rollups of the month as of the last `referral_rollups` run, with the
+    /// bonuses earned in it.
+    async fn get_referrer_statement(
+        &self,
+        referrer_id: &str,
+        month: chrono::NaiveDate,
+    ) -> Result<ReferrerStatement, ServiceError> {
+        let next_month = month
+            .checked_add_months(chrono::Months::new(1))
+            .ok_or(ServiceError::Internal("InvalidMonth".to_string()))?;
+
+        let rollups = self
+            .referral_repository
+            .get_rollups(month, Some(referrer_id))
+            .await
+            .map_err(|e| ServiceError::Repository("Referrals".to_string(), e.to_string()))?;
+        let bonuses = self
+            .referral_repository
+            .get_bonuses(
+                referrer_id,
+                month.and_time(chrono::NaiveTime::MIN).and_utc(),
+                next_month.and_time(chrono::NaiveTime::MIN).and_utc(),
+            )
+            .await
+            .map_err(|e| ServiceError::Repository("Referrals".to_string(), e.to_string()))?;
+
+        Ok(ReferrerStatement {
+            referrer_id: referrer_id.to_string(),
+            month: month.format("%Y-%m").to_string(),
+            rollups,
+            bonuses,
+            generated_at: self.clock.now(),
+        })
+    }
+
+    async fn get_referrer_payments(
+        &self,
+        referrer_id: &str,
+    ) -> Result<Vec<ReferralBonus>, ServiceError> {
+        self.referral_repository
+            .get_payments(referrer_id, REFERRER_PAYMENTS_LIMIT)
+            .await
+            .map_err(|e| ServiceError::Repository("Referrals".to_string(), e.to_string()))
+    }
+
     async fn get_user_referrer_address(
         &self,
         user_id: &str,
@@ -131,9 +608,10 @@ impl RequestHandler<UserRequest> for UserRequestHandler {
         match request {
             UserRequest::CreateUser {
                 referral_code,
+                installation_id,
                 response,
             } => {
-                let user = self.create_user(referral_code).await;
+                let user = self.create_user(referral_code, installation_id).await;
                 let _ = response.send(user);
             }
             UserRequest::GetUser { id, response } => {
@@ -156,17 +634,102 @@ impl RequestHandler<UserRequest> for UserRequestHandler {
                 let referrer = self.get_user_referrer_address(&id).await;
                 let _ = response.send(referrer);
             }
+            UserRequest::InvalidateUserDetails { id } => {
+                self.refresh_risk_score_logged(&id).await;
+                self.invalidate_user_details(&id);
+            }
+            UserRequest::GetDeviceLimits { id, response } => {
+                let limits = self.get_device_limits(&id).await;
+                let _ = response.send(limits);
+            }
+            UserRequest::ExportUserData {
+                id,
+                request,
+                response,
+            } => {
+                let export = self.export_user_data(&id, request).await;
+                let _ = response.send(export);
+            }
+            UserRequest::AnonymizeUser {
+                id,
+                request,
+                response,
+            } => {
+                let result = self.anonymize_user(&id, request).await;
+                let _ = response.send(result);
+            }
+            UserRequest::GetNotificationPreferences { id, response } => {
+                let preferences = self.get_notification_preferences(&id).await;
+                let _ = response.send(preferences);
+            }
+            UserRequest::SetNotificationPreferences {
+                id,
+                preferences,
+                response,
+            } => {
+                let preferences = self.set_notification_preferences(&id, preferences).await;
+                let _ = response.send(preferences);
+            }
+            UserRequest::GetStatement {
+                id,
+                month,
+                response,
+            } => {
+                let statement = self.get_statement(&id, month).await;
+                let _ = response.send(statement);
+            }
+            UserRequest::GetRiskScore { id, response } => {
+                let score = self.get_risk_score(&id).await;
+                let _ = response.send(score);
+            }
+            UserRequest::IssueUserKey { id, response } => {
+                let credentials = self.issue_user_key(&id).await;
+                let _ = response.send(credentials);
+            }
+            UserRequest::AuthenticateUser { api_key, response } => {
+                let user = self.authenticate_user(&api_key).await;
+                let _ = response.send(user);
+            }
+            UserRequest::IssueReferrerKey { id, response } => {
+                let credentials = self.issue_referrer_key(&id).await;
+                let _ = response.send(credentials);
+            }
+            UserRequest::AuthenticateReferrer { api_key, response } => {
+                let referrer = self.authenticate_referrer(&api_key).await;
+                let _ = response.send(referrer);
+            }
+            UserRequest::GetReferrerStatement {
+                id,
+                month,
+                response,
+            } => {
+                let statement = self.get_referrer_statement(&id, month).await;
+                let _ = response.send(statement);
+            }
+            UserRequest::GetReferrerPayments { id, response } => {
+                let payments = self.get_referrer_payments(&id).await;
+                let _ = response.send(payments);
+            }
         }
     }
 }
 
-pub struct UserService;
+pub struct UserService {
+    workers: usize,
+}
 
 impl UserService {
-    pub fn new() -> Self {
-        UserService {}
+    pub fn new(workers: usize) -> Self {
+        UserService { workers }
     }
 }
 
 #[async_trait]
-impl Service<UserRequest, UserRequestHandler> for UserService {}
+impl Service<UserRequest, UserRequestHandler> for UserService {
+    fn worker_pool(&self) -> Option<WorkerPool> {
+        Some(WorkerPool {
+            name: "user",
+            size: self.workers,
+        })
+    }
+}
@@ -0,0 +1,241 @@
+//! Swap venues the dealer sources liquidity from. Swaps are routed to the
+//! healthy venue giving the most of the received asset; venues that keep
+//! failing are skipped for a while.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+use super::{sideswap::SideswapRequest, ServiceError};
+use crate::utils::metrics;
+
+pub mod otc;
+
+/// Consecutive failures after which a venue is skipped.
+const FAILURE_THRESHOLD: u32 = 3;
+/// How long an unhealthy venue is skipped before it is tried again.
+const COOLDOWN: Duration = Duration::from_secs(300);
+
+/// Where liquidity swaps are executed. A started swap is reported to the
+/// transaction and hedging services with `SwapCompleted` and `SwapFilled`
+/// under the id `swap` returned, whatever the venue.
+#[async_trait]
+pub trait SwapVenue: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Units of `receive_asset` the venue would give for `amount`, none when
+    /// it does not trade the pair.
+    async fn estimate(
+        &self,
+        sell_asset: &str,
+        receive_asset: &str,
+        amount: i64,
+    ) -> Result<Option<u64>, ServiceError>;
+
+    /// Starts a swap and returns its id.
+    async fn swap(
+        &self,
+        sell_asset: &str,
+        receive_asset: &str,
+        amount: i64,
+    ) -> Result<i64, ServiceError>;
+}
+
+#[derive(Default)]
+struct VenueHealth {
+    consecutive_failures: u32,
+    unhealthy_since: Option<Instant>,
+}
+
+impl VenueHealth {
+    fn is_available(&self) -> bool {
+        self.unhealthy_since
+            .is_none_or(|since| since.elapsed() >= COOLDOWN)
+    }
+}
+
+#[derive(Clone)]
+pub struct SwapRouter {
+    venues: Arc<Vec<Arc<dyn SwapVenue>>>,
+    health: Arc<Mutex<HashMap<&'static str, VenueHealth>>>,
+}
+
+impl SwapRouter {
+    pub fn new(venues: Vec<Arc<dyn SwapVenue>>) -> Self {
+        for venue in &venues {
+            metrics::set_gauge("swap_venue_healthy", &[("venue", venue.name())], 1.0);
+        }
+
+        Self {
+            venues: Arc::new(venues),
+            health: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Starts the swap on the venue with the best estimate, falling back to
+    /// the next one when it fails. Venues that could not estimate are tried
+    /// last.
+    pub async fn swap(
+        &self,
+        sell_asset: &str,
+        receive_asset: &str,
+        amount: i64,
+    ) -> Result<i64, ServiceError> {
+        let mut candidates = Vec::new();
+        for venue in self.venues.iter() {
+            if !self.is_available(venue.name()).await {
+                log::debug!("Skipping unhealthy swap venue {}", venue.name());
+                continue;
+            }
+
+            match venue.estimate(sell_asset, receive_asset, amount).await {
+                Ok(Some(estimate)) => candidates.push((venue.clone(), Some(estimate))),
+                Ok(None) => {}
+                Err(e) => {
+                    log::warn!("Swap venue {} could not estimate: {}", venue.name(), e);
+                    candidates.push((venue.clone(), None));
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            return Err(ServiceError::Internal("NoSwapVenue".to_string()));
+        }
+
+        // Stable, so venues without an estimate keep their configured order
+        candidates.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let mut last_error = None;
+        for (venue, estimate) in candidates {
+            log::info!(
+                "Routing swap of {} {} into {} to {} (estimate: {:?})",
+                amount,
+                sell_asset,
+                receive_asset,
+                venue.name(),
+                estimate
+            );
+
+            match venue.swap(sell_asset, receive_asset, amount).await {
+                Ok(swap_id) => {
+                    self.record_success(venue.name()).await;
+                    metrics::increment("swaps_routed_total", &[("venue", venue.name())]);
+                    return Ok(swap_id);
+                }
+                Err(e) => {
+                    log::warn!("Swap on {} failed: {}", venue.name(), e);
+                    self.record_failure(venue.name()).await;
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| ServiceError::Internal("NoSwapVenue".to_string())))
+    }
+
+    async fn is_available(&self, venue: &'static str) -> bool {
+        self.health
+            .lock()
+            .await
+            .get(venue)
+            .is_none_or(|health| health.is_available())
+    }
+
+    async fn record_success(&self, venue: &'static str) {
+        let mut health = self.health.lock().await;
+        let health = health.entry(venue).or_default();
+        if health.unhealthy_since.take().is_some() {
+            log::info!("Swap venue {} recovered", venue);
+        }
+        health.consecutive_failures = 0;
+        metrics::set_gauge("swap_venue_healthy", &[("venue", venue)], 1.0);
+    }
+
+    async fn record_failure(&self, venue: &'static str) {
+        let mut health = self.health.lock().await;
+        let health = health.entry(venue).or_default();
+        health.consecutive_failures += 1;
+        metrics::increment("swap_venue_failures_total", &[("venue", venue)]);
+
+        if health.consecutive_failures >= FAILURE_THRESHOLD {
+            log::warn!(
+                "Swap venue {} failed {} times in a row, skipping it for {:?}",
+                venue,
+                health.consecutive_failures,
+                COOLDOWN
+            );
+            health.unhealthy_since = Some(Instant::now());
+            metrics::set_gauge("swap_venue_healthy", &[("venue", venue)], 0.0);
+        }
+    }
+}
+
+/// Sideswap, through the Sideswap service. Estimates come from the market
+/// charts, so they do not include Sideswap's fees.
+pub struct SideswapVenue {
+    sideswap_channel: mpsc::Sender<SideswapRequest>,
+}
+
+impl SideswapVenue {
+    pub fn new(sideswap_channel: mpsc::Sender<SideswapRequest>) -> Self {
+        Self { sideswap_channel }
+    }
+}
+
+#[async_trait]
+impl SwapVenue for SideswapVenue {
+    fn name(&self) -> &'static str {
+        "sideswap"
+    }
+
+    async fn estimate(
+        &self,
+        sell_asset: &str,
+        receive_asset: &str,
+        amount: i64,
+    ) -> Result<Option<u64>, ServiceError> {
+        let (sideswap_tx, sideswap_rx) = oneshot::channel();
+        self.sideswap_channel
+            .send(SideswapRequest::Estimate {
+                sell_asset: sell_asset.to_string(),
+                receive_asset: receive_asset.to_string(),
+                amount,
+                response: sideswap_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Router => Sideswap".to_string(), e.to_string())
+            })?;
+
+        sideswap_rx.await.map_err(|e| {
+            ServiceError::Communication("Sideswap => Router".to_string(), e.to_string())
+        })?
+    }
+
+    async fn swap(
+        &self,
+        sell_asset: &str,
+        receive_asset: &str,
+        amount: i64,
+    ) -> Result<i64, ServiceError> {
+        let (sideswap_tx, sideswap_rx) = oneshot::channel();
+        self.sideswap_channel
+            .send(SideswapRequest::Swap {
+                sell_asset: sell_asset.to_string(),
+                receive_asset: receive_asset.to_string(),
+                amount,
+                response: sideswap_tx,
+            })
+            .await
+            .map_err(|e| {
+                ServiceError::Communication("Router => Sideswap".to_string(), e.to_string())
+            })?;
+
+        sideswap_rx.await.map_err(|e| {
+            ServiceError::Communication("Sideswap => Router".to_string(), e.to_string())
+        })?
+    }
+}
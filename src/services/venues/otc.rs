@@ -0,0 +1,304 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use lwk_wollet::elements::pset::PartiallySignedTransaction;
+use lwk_wollet::UnvalidatedRecipient;
+use tokio::sync::{mpsc, oneshot};
+
+use super::SwapVenue;
+use crate::models::otc::OtcSwap;
+use crate::models::wallet::FeePriority;
+use crate::repositories::otc::OtcRepository;
+use crate::services::{
+    hedging::HedgingRequest,
+    jobs::{self, JobSchedule},
+    liquid::LiquidRequest,
+    transactions::TransactionServiceRequest,
+    ServiceError,
+};
+
+/// OTC desk quoting over HTTP. An accepted quote is paid by sending the sold
+/// asset to the deposit address the desk gives; the desk then pays the
+/// received asset to a fresh wallet address.
+#[derive(Clone)]
+pub struct OtcVenue {
+    repository: OtcRepository,
+    liquid_channel: mpsc::Sender<LiquidRequest>,
+    transaction_channel: mpsc::Sender<TransactionServiceRequest>,
+    hedging_channel: mpsc::Sender<HedgingRequest>,
+    /// Quotes are never accepted.
+    dry_run: bool,
+}
+
+impl OtcVenue {
+    pub fn new(
+        repository: OtcRepository,
+        liquid_channel: mpsc::Sender<LiquidRequest>,
+        transaction_channel: mpsc::Sender<TransactionServiceRequest>,
+        hedging_channel: mpsc::Sender<HedgingRequest>,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            repository,
+            liquid_channel,
+            transaction_channel,
+            hedging_channel,
+            dry_run,
+        }
+    }
+
+    /// Polls the desk for swaps whose deposit was sent until they settle.
+    pub fn start_settlement_check(&self, every: Duration) {
+        let venue = self.clone();
+
+        jobs::register("otc_settlement", JobSchedule::Every(every), move || {
+            let venue = venue.clone();
+            async move {
+                for swap in venue.repository.get_deposited_swaps().await? {
+                    venue.check_settlement(swap).await;
+                }
+                Ok(())
+            }
+        });
+    }
+
+    async fn check_settlement(&self, swap: OtcSwap) {
+        let status = match self.repository.get_swap_status(&swap.external_id).await {
+            Ok(status) => status,
+            Err(e) => {
+                log::warn!("Could not check OTC swap {}: {}", swap.id, e);
+                return;
+            }
+        };
+
+        match status.status.as_str() {
+            "completed" => {
+                let Some(txid) = status.txid else {
+                    log::warn!("OTC swap {} completed without a txid", swap.id);
+                    return;
+                };
+
+                match self
+                    .repository
+                    .close_swap(swap.id, "completed", Some(&txid))
+                    .await
+                {
+                    Ok(true) => {}
+                    Ok(false) => return,
+                    Err(e) => {
+                        log::error!("Could not close OTC swap {}: {}", swap.id, e);
+                        return;
+                    }
+                }
+
+                log::info!("OTC swap {} settled: txid={}", swap.id, txid);
+                let received_amount = status
+                    .received_amount
+                    .unwrap_or(swap.expected_amount as u64);
+
+                if let Err(e) = self
+                    .transaction_channel
+                    .send(TransactionServiceRequest::SwapCompleted {
+                        quote_sub_id: swap.id,
+                        txid,
+                    })
+                    .await
+                {
+                    log::error!("Failed to notify transaction service of swap: {:?}", e);
+                }
+
+                if let Err(e) = self
+                    .hedging_channel
+                    .send(HedgingRequest::SwapFilled {
+                        quote_sub_id: swap.id,
+                        sold_amount: swap.sold_amount as u64,
+                        received_amount,
+                    })
+                    .await
+                {
+                    log::error!("Failed to notify hedging service of swap: {:?}", e);
+                }
+            }
+            "failed" => {
+                log::error!(
+                    "OTC swap {} ({}) of {} into {} failed after its deposit was sent, {} units to recover",
+                    swap.id,
+                    swap.external_id,
+                    swap.sell_asset,
+                    swap.receive_asset,
+                    swap.sold_amount
+                );
+                if let Err(e) = self.repository.close_swap(swap.id, "failed", None).await {
+                    log::error!("Could not close OTC swap {}: {}", swap.id, e);
+                }
+            }
+            _ => log::debug!("OTC swap {} still pending", swap.id),
+        }
+    }
+
+    async fn request_address(&self) -> Result<String, ServiceError> {
+        let (liquid_tx, liquid_rx) = oneshot::channel();
+        self.liquid_channel
+            .send(LiquidRequest::GetNewAddress {
+                response: liquid_tx,
+            })
+            .await
+            .map_err(|e| ServiceError::Communication("OTC => Liquid".to_string(), e.to_string()))?;
+
+        liquid_rx
+            .await
+            .map_err(|e| ServiceError::Communication("Liquid => OTC".to_string(), e.to_string()))?
+    }
+
+    /// Pays the deposit of an accepted quote and returns its txid.
+    async fn send_deposit(
+        &self,
+        address: String,
+        asset: &str,
+        amount: i64,
+    ) -> Result<String, ServiceError> {
+        let recipient = UnvalidatedRecipient {
+            address,
+            satoshi: amount as u64,
+            asset: asset.to_string(),
+        };
+
+        let (liquid_tx, liquid_rx) = oneshot::channel();
+        self.liquid_channel
+            .send(LiquidRequest::BuildTransaction {
+                recipients: vec![recipient],
+                priority: FeePriority::Normal,
+                avoid_change: false,
+                response: liquid_tx,
+            })
+            .await
+            .map_err(|e| ServiceError::Communication("OTC => Liquid".to_string(), e.to_string()))?;
+        let pset: PartiallySignedTransaction = liquid_rx.await.map_err(|e| {
+            ServiceError::Communication("Liquid => OTC".to_string(), e.to_string())
+        })??;
+
+        let (liquid_tx, liquid_rx) = oneshot::channel();
+        self.liquid_channel
+            .send(LiquidRequest::SignTransaction {
+                pset,
+                response: liquid_tx,
+            })
+            .await
+            .map_err(|e| ServiceError::Communication("OTC => Liquid".to_string(), e.to_string()))?;
+        let pset = liquid_rx.await.map_err(|e| {
+            ServiceError::Communication("Liquid => OTC".to_string(), e.to_string())
+        })??;
+
+        let (liquid_tx, liquid_rx) = oneshot::channel();
+        self.liquid_channel
+            .send(LiquidRequest::FinalizeTransaction {
+                pset,
+                response: liquid_tx,
+            })
+            .await
+            .map_err(|e| ServiceError::Communication("OTC => Liquid".to_string(), e.to_string()))?;
+        let broadcast = liquid_rx.await.map_err(|e| {
+            ServiceError::Communication("Liquid => OTC".to_string(), e.to_string())
+        })??;
+
+        Ok(broadcast.txid)
+    }
+}
+
+#[async_trait]
+impl SwapVenue for OtcVenue {
+    fn name(&self) -> &'static str {
+        "otc"
+    }
+
+    async fn estimate(
+        &self,
+        sell_asset: &str,
+        receive_asset: &str,
+        amount: i64,
+    ) -> Result<Option<u64>, ServiceError> {
+        let quote = self
+            .repository
+            .request_quote(sell_asset, receive_asset, amount)
+            .await
+            .map_err(|e| {
+                ServiceError::ExternalService(
+                    "OTC".to_string(),
+                    "OTC desk".to_string(),
+                    e.to_string(),
+                )
+            })?;
+
+        Ok(Some(quote.receive_amount))
+    }
+
+    async fn swap(
+        &self,
+        sell_asset: &str,
+        receive_asset: &str,
+        amount: i64,
+    ) -> Result<i64, ServiceError> {
+        if self.dry_run {
+            log::info!("Dry run, not accepting OTC quote for {amount} {sell_asset}");
+            return Err(ServiceError::Internal("DryRun".to_string()));
+        }
+
+        let external_error = |e: anyhow::Error| {
+            ServiceError::ExternalService("OTC".to_string(), "OTC desk".to_string(), e.to_string())
+        };
+
+        let quote = self
+            .repository
+            .request_quote(sell_asset, receive_asset, amount)
+            .await
+            .map_err(external_error)?;
+        let receive_address = self.request_address().await?;
+        let acceptance = self
+            .repository
+            .accept_quote(&quote.quote_id, &receive_address)
+            .await
+            .map_err(external_error)?;
+
+        let id = self
+            .repository
+            .insert_swap(
+                &acceptance.swap_id,
+                sell_asset,
+                receive_asset,
+                amount,
+                quote.receive_amount as i64,
+            )
+            .await
+            .map_err(|e| ServiceError::Repository("OTC".to_string(), e.to_string()))?;
+
+        let txid = match self
+            .send_deposit(acceptance.deposit_address, sell_asset, amount)
+            .await
+        {
+            Ok(txid) => txid,
+            Err(e) => {
+                if let Err(e) = self.repository.close_swap(id, "failed", None).await {
+                    log::error!("Could not close OTC swap {}: {}", id, e);
+                }
+                return Err(e);
+            }
+        };
+
+        self.repository
+            .record_deposit(id, &txid)
+            .await
+            .map_err(|e| ServiceError::Repository("OTC".to_string(), e.to_string()))?;
+
+        log::info!(
+            "OTC swap {} started: sent {} {} for {} {} (deposit txid={})",
+            id,
+            amount,
+            sell_asset,
+            quote.receive_amount,
+            receive_asset,
+            txid
+        );
+
+        Ok(id)
+    }
+}
@@ -1,62 +1,1050 @@
 use config::{Config, ConfigError, File};
 use serde::Deserialize;
+use std::collections::HashMap;
 
-#[derive(Debug, Deserialize)]
+use crate::models::compliance::ScreeningAction;
+use crate::models::price::PriceAggregation;
+use crate::models::transactions::{AssetIds, DustPolicy, MAX_PRECISION};
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    pub fn is_mainnet(&self) -> bool {
+        *self == Network::Mainnet
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
 pub struct Postgres {
     pub url: String,
     pub port: u32,
     pub user: String,
     pub password: String,
     pub database: String,
+    /// Connections kept by the pool, 5 when unset.
+    #[serde(default)]
+    pub max_connections: Option<u32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct Electrum {
     pub url: String,
     pub port: u32,
     pub tls: bool,
-    pub testnet: bool,
+    /// Derived from `network`; starting fails if it contradicts it.
+    #[serde(default)]
+    pub testnet: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct Depix {
     pub url: String,
     pub auth_token: String,
     pub tls: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct Sideswap {
     pub url: String,
     pub api_key: String,
+    /// Seconds between refreshes of the cached market list, 300 by default.
+    pub market_refresh_secs: Option<u64>,
+    /// Seconds a call waits for Sideswap to answer, 30 by default.
+    pub call_timeout_secs: Option<u64>,
+    /// Seconds between websocket pings, 15 by default. The connection is
+    /// reopened when nothing came back for two intervals.
+    pub ping_interval_secs: Option<u64>,
+    /// Quotes each swap with the amount in the sold and in the received
+    /// asset and takes the better price, true by default.
+    pub dual_quotes: Option<bool>,
+    /// How far the PSET of an accepted quote may stray from the quoted
+    /// amounts before it is refused, in basis points; 10 by default.
+    pub pset_tolerance_bps: Option<u64>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct Wallet {
     pub mnemonic: String,
-    pub mainnet: bool,
+    /// Derived from `network`; starting fails if it contradicts it.
+    #[serde(default)]
+    pub mainnet: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct PriceProviders {
     pub binance_url: String,
     pub coingecko_url: String,
+    /// How the quotes of the providers are combined.
+    #[serde(default)]
+    pub aggregation: PriceAggregation,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct Liquidity {
+    /// DEPIX above this balance is sold into LBTC, unless `swaps` has a
+    /// policy selling DEPIX.
     pub max_depix_amount: u64,
+    /// Swap policies, at most one per sold asset.
+    #[serde(default)]
+    pub swaps: Vec<LiquiditySwap>,
+    /// Minimum float per asset id, in base units. Dropping below it raises an alert.
+    #[serde(default)]
+    pub low_water_marks: HashMap<String, u64>,
+    /// Reject deposits whose payout is larger than the current float of the asset.
+    #[serde(default)]
+    pub block_unfundable_deposits: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LiquiditySwap {
+    /// Asset id sold when its balance goes above `max_amount`.
+    pub sell_asset: String,
+    pub receive_asset: String,
+    /// Balance of `sell_asset` kept, in base units. The excess is sold.
+    pub max_amount: u64,
+    /// Smaller excesses are left for later, in base units of `sell_asset`.
+    #[serde(default)]
+    pub min_amount: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Notifications {
+    /// Chat webhook receiving operator alerts. Alerts are only logged when empty.
+    pub webhook_url: String,
+    /// Push relay receiving notices meant for users (e.g. scheduled charges).
+    pub user_webhook_url: String,
+    /// Provider of e-mailed payout receipts.
+    pub email: ReceiptProvider,
+    /// Provider of payout receipts by SMS.
+    pub sms: ReceiptProvider,
+    /// Receipts link the payout at this URL followed by its txid.
+    pub explorer_url: String,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            webhook_url: String::new(),
+            user_webhook_url: String::new(),
+            email: ReceiptProvider::default(),
+            sms: ReceiptProvider::default(),
+            explorer_url: "https://blockstream.info/liquid/tx/".to_string(),
+        }
+    }
+}
+
+/// HTTP API that delivers receipts on one channel. Receipts are not sent on
+/// the channel when `url` is empty.
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct ReceiptProvider {
+    pub url: String,
+    /// Sent as a bearer token.
+    pub api_key: String,
+    /// Sender address or number.
+    pub from: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct Admin {
+    /// Bearer token required by the /admin API. Admin routes are disabled when empty.
+    pub api_key: String,
+}
+
+/// Browser access to the public API and the headers sent with every
+/// response.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Http {
+    /// Addresses the API is served on, such as `0.0.0.0:8080`, or Unix
+    /// sockets as `unix:/run/mooze/api.sock`.
+    pub listen: Vec<String>,
+    /// Where the admin API is served instead, in the same form. When empty
+    /// it is served on `listen` along with the public API.
+    pub admin_listen: Vec<String>,
+    /// PEM certificate chain and private key to terminate TLS on the TCP
+    /// listeners. Both or neither must be set.
+    pub tls_cert_path: Option<String>,
+    pub tls_key_path: Option<String>,
+    /// Origins of the web clients allowed to call the public API, such as
+    /// `https://app.mooze.app`, or `*` for any. CORS is off when empty.
+    pub allowed_origins: Vec<String>,
+    /// Seconds browsers may cache a preflight response.
+    pub cors_max_age_secs: u64,
+    /// Sends nosniff, frame, referrer and HSTS headers.
+    pub security_headers: bool,
+    /// max-age of Strict-Transport-Security, left out when 0.
+    pub hsts_max_age_secs: u64,
+    pub logging: RequestLogging,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Self {
+            listen: vec!["0.0.0.0:8080".to_string()],
+            admin_listen: Vec::new(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            allowed_origins: Vec::new(),
+            cors_max_age_secs: 10 * 60,
+            security_headers: true,
+            hsts_max_age_secs: 365 * 24 * 60 * 60,
+            logging: RequestLogging::default(),
+        }
+    }
+}
+
+/// How a logged field is redacted.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Redaction {
+    /// Replaced altogether.
+    Hide,
+    /// Only the last 4 characters are kept.
+    Mask,
+    /// Replaced with a short hash, so requests with the same value can be
+    /// matched without revealing it.
+    Hash,
+}
+
+/// Requests served, logged as one JSON line each under the `http_requests`
+/// target for settling disputes.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RequestLogging {
+    pub enabled: bool,
+    /// Share of the requests logged, from 0 to 1. Failed requests are
+    /// logged either way, without their body when not sampled.
+    pub sample_rate: f64,
+    /// Sample rates of routes, such as `"/price" = 0.01`, in place of
+    /// `sample_rate`.
+    pub route_sample_rates: HashMap<String, f64>,
+    /// Logs JSON request and response bodies.
+    pub bodies: bool,
+    /// Bodies longer than this are left out.
+    pub max_body_bytes: usize,
+    /// Redaction of path parameters, query parameters and JSON body fields
+    /// by name, at any depth, ignoring case and underscores so `pix_key`
+    /// matches `pixKey` too. `*_address` matches every name ending in
+    /// `_address` or `Address`. Replaces the defaults when set.
+    pub redact: HashMap<String, Redaction>,
+}
+
+impl Default for RequestLogging {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_rate: 1.0,
+            route_sample_rates: HashMap::new(),
+            bodies: true,
+            max_body_bytes: 16 * 1024,
+            redact: HashMap::from(
+                [
+                    ("pix_key", Redaction::Hide),
+                    ("api_key", Redaction::Hide),
+                    ("token", Redaction::Hide),
+                    ("signature", Redaction::Hide),
+                    ("qr_copy_paste", Redaction::Hide),
+                    ("address", Redaction::Mask),
+                    ("*_address", Redaction::Mask),
+                    ("email", Redaction::Mask),
+                    ("phone", Redaction::Mask),
+                    ("tax_number", Redaction::Mask),
+                    ("payer_tax_number", Redaction::Mask),
+                    ("payer_name", Redaction::Hash),
+                ]
+                .map(|(field, redaction)| (field.to_string(), redaction)),
+            ),
+        }
+    }
+}
+
+/// Monthly reports of the assets sold to users, filed with Receita Federal
+/// under IN RFB 1888/2019.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct TaxReports {
+    /// Generates the report of each month once it closes. Reports can be
+    /// generated through the admin API either way.
+    pub enabled: bool,
+    /// CNPJ of the reporting exchange.
+    pub cnpj: String,
+    /// Name of the reporting exchange.
+    pub name: String,
+    /// Seconds between checks for a closed month without a report.
+    pub interval_secs: u64,
+}
+
+impl Default for TaxReports {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cnpj: String::new(),
+            name: String::new(),
+            interval_secs: 60 * 60,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct Statements {
+    /// secp256k1 secret key in hex signing user statements. Statements are
+    /// unavailable when empty.
+    pub signing_key: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct Snapshots {
+    /// Directory (plain path or file://) or object storage base URL (http/https).
+    pub storage_url: String,
+    pub storage_token: Option<String>,
+    pub encryption_key: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Payouts {
+    /// Payouts up to this amount are tried before larger ones of the same age.
+    pub small_payout_cents: i32,
+    /// After waiting this long a payout goes first and holds its asset's balance.
+    pub starvation_after_secs: u64,
+    /// Extra DEPIX sold on top of the exact shortfall, in basis points.
+    pub swap_buffer_bps: u64,
+    /// Paid transactions untouched for this long are picked up by the
+    /// recovery scan, which also runs at this interval.
+    pub stale_payout_after_secs: u64,
+    /// Minimum net payout per asset id, in base units, checked at deposit time.
+    pub min_payouts: HashMap<String, u64>,
+    /// Outputs below this many base units are never created.
+    pub dust_threshold: u64,
+    /// Applied when fees push a payout below the dust threshold.
+    pub below_dust: DustPolicy,
+    /// Pays the part of a payout the wallet covers right away, and the rest
+    /// once the swap refilling its asset completes.
+    pub partial_payouts: bool,
+    /// Payouts in L-BTC spend the coins leaving the least change, instead
+    /// of every L-BTC coin of the wallet.
+    pub avoid_change: bool,
+    /// Payouts of more than this many cents wait for manual approval. 0
+    /// turns the cap off.
+    pub max_payout_cents: i32,
+    /// Payouts that would take the cents paid out by the dealer in the last
+    /// hour past this wait for manual approval. 0 turns the cap off.
+    pub max_hourly_payout_cents: i64,
+    /// Asset ids neither sold nor paid out, until enabled from the admin API.
+    pub disabled_assets: Vec<String>,
+    /// Payouts wait in `pricing_unavailable` while the price is older than
+    /// this, or Sideswap is disconnected. 0 turns the price check off.
+    pub max_price_age_secs: u64,
+}
+
+impl Default for Payouts {
+    fn default() -> Self {
+        Self {
+            small_payout_cents: 500 * 100,
+            starvation_after_secs: 30 * 60,
+            swap_buffer_bps: 100,
+            stale_payout_after_secs: 10 * 60,
+            min_payouts: HashMap::new(),
+            dust_threshold: 1000,
+            below_dust: DustPolicy::Hold,
+            partial_payouts: false,
+            avoid_change: false,
+            max_payout_cents: 0,
+            max_hourly_payout_cents: 0,
+            disabled_assets: Vec::new(),
+            max_price_age_secs: 3 * 60,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Compliance {
+    /// Screen payout addresses before building the transaction.
+    pub enabled: bool,
+    /// Chainalysis-style risk API. Only the local denylist is used when empty.
+    pub provider_url: String,
+    pub provider_api_key: String,
+    /// Lowest provider risk (low, medium, high, severe) that triggers `provider_action`.
+    pub risk_threshold: String,
+    pub denylist_action: ScreeningAction,
+    pub provider_action: ScreeningAction,
+    /// Hold payouts instead of flagging them when the provider is unavailable.
+    pub fail_closed: bool,
+}
+
+impl Default for Compliance {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider_url: String::new(),
+            provider_api_key: String::new(),
+            risk_threshold: "high".to_string(),
+            denylist_action: ScreeningAction::Block,
+            provider_action: ScreeningAction::Hold,
+            fail_closed: false,
+        }
+    }
+}
+
+/// What users with an open MED dispute of a PIX payment may still do.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Disputes {
+    /// Refuse new deposits from the user until the dispute is decided.
+    pub block_deposits: bool,
+    /// Hold the user's payouts for review until the dispute is decided.
+    pub hold_payouts: bool,
+    /// Shared secret Eulen sends in the `X-Webhook-Secret` header of dispute
+    /// and batch status webhooks. Both are refused while it is empty.
+    pub webhook_secret: String,
+}
+
+impl Default for Disputes {
+    fn default() -> Self {
+        Self {
+            block_deposits: true,
+            hold_payouts: true,
+            webhook_secret: String::new(),
+        }
+    }
+}
+
+/// When new deposits are taken. Payouts of paid transactions and webhooks
+/// go on regardless.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Availability {
+    /// Hours local time is off UTC, for the business hours and the
+    /// reopening time shown to users. Brasília is -3.
+    pub utc_offset_hours: i32,
+    pub business_hours: BusinessHours,
+    /// Known in advance, on top of the windows scheduled from the admin API.
+    pub maintenance: Vec<MaintenanceWindow>,
+}
+
+impl Default for Availability {
+    fn default() -> Self {
+        Self {
+            utc_offset_hours: -3,
+            business_hours: BusinessHours::default(),
+            maintenance: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct BusinessHours {
+    /// Take deposits only from `opens_at` to `closes_at` on `days`.
+    pub enabled: bool,
+    /// `mon` to `sun`.
+    pub days: Vec<String>,
+    /// `HH:MM` local time.
+    pub opens_at: String,
+    pub closes_at: String,
+}
+
+impl Default for BusinessHours {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            days: ["mon", "tue", "wed", "thu", "fri", "sat", "sun"]
+                .map(str::to_string)
+                .to_vec(),
+            opens_at: "08:00".to_string(),
+            closes_at: "22:00".to_string(),
+        }
+    }
+}
+
+impl BusinessHours {
+    pub fn weekdays(&self) -> Result<Vec<chrono::Weekday>, String> {
+        self.days
+            .iter()
+            .map(|day| {
+                day.parse()
+                    .map_err(|_| format!("{} is not a day of the week", day))
+            })
+            .collect()
+    }
+
+    /// Opening and closing times, the first before the second.
+    pub fn hours(&self) -> Result<(chrono::NaiveTime, chrono::NaiveTime), String> {
+        let parse = |time: &str| {
+            chrono::NaiveTime::parse_from_str(time, "%H:%M")
+                .map_err(|_| format!("{} is not a HH:MM time", time))
+        };
+        let (opens_at, closes_at) = (parse(&self.opens_at)?, parse(&self.closes_at)?);
+        if opens_at >= closes_at {
+            return Err(format!(
+                "opens_at {} is not before closes_at {}",
+                self.opens_at, self.closes_at
+            ));
+        }
+
+        Ok((opens_at, closes_at))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct MaintenanceWindow {
+    pub starts_at: chrono::DateTime<chrono::Utc>,
+    pub ends_at: chrono::DateTime<chrono::Utc>,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Merchants {
+    /// Fee on payment link payments, in basis points, unless set per merchant.
+    pub default_fee_bps: i32,
+    /// Public base URL of the dealer, used to build payment link URLs.
+    pub public_url: String,
+    pub invoicing: Invoicing,
+}
+
+impl Default for Merchants {
+    fn default() -> Self {
+        Self {
+            default_fee_bps: 150,
+            public_url: String::new(),
+            invoicing: Invoicing::default(),
+        }
+    }
+}
+
+/// Invoicing API (e.g. an NFS-e gateway) issuing service invoices for the
+/// fees of link payments. No invoices are issued when `url` is empty.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Invoicing {
+    pub url: String,
+    /// Sent as a bearer token.
+    pub api_key: String,
+    pub timeout_secs: u64,
+    /// How often invoices due for a retry are sent again.
+    pub interval_secs: u64,
+    /// First retry delay, doubled on each failed attempt up to a day.
+    pub retry_base_secs: u64,
+    /// Failed attempts after which the invoice is left to operators.
+    pub max_attempts: i32,
+}
+
+impl Default for Invoicing {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            api_key: String::new(),
+            timeout_secs: 10,
+            interval_secs: 60,
+            retry_base_secs: 60,
+            max_attempts: 12,
+        }
+    }
+}
+
+/// Resilience of the Eulen API client.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Eulen {
+    pub timeout_secs: u64,
+    /// Retries after a timeout, connection error or 5xx. Retries reuse the
+    /// request nonce, so Eulen does not create the charge twice.
+    pub max_retries: u32,
+    /// First backoff delay, doubled on each retry and jittered.
+    pub retry_base_ms: u64,
+    /// Consecutive failed requests after which calls fail fast.
+    pub breaker_threshold: u32,
+    pub breaker_cooldown_secs: u64,
+}
+
+impl Default for Eulen {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 10,
+            max_retries: 2,
+            retry_base_ms: 250,
+            breaker_threshold: 5,
+            breaker_cooldown_secs: 30,
+        }
+    }
+}
+
+/// Prices locked between the app's preview and the deposit.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Quotes {
+    /// How long a deposit can be created at the quoted price.
+    pub ttl_secs: i64,
+    /// The locked price is dropped for the current one when the asset got
+    /// more expensive than this since the quote.
+    pub max_deviation_bps: u64,
+}
+
+impl Default for Quotes {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 30,
+            max_deviation_bps: 200,
+        }
+    }
+}
+
+/// Offsetting swaps of DEPIX into the assets paid out.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Hedging {
+    pub enabled: bool,
+    /// Open exposure per asset id, in base units, above which a hedge is
+    /// placed. Assets without a threshold are not hedged.
+    pub thresholds: HashMap<String, u64>,
+    pub check_interval_secs: u64,
+    /// Open hedges that did not fill by then are dropped.
+    pub hedge_timeout_secs: i64,
+}
+
+impl Default for Hedging {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            thresholds: HashMap::new(),
+            check_interval_secs: 60,
+            hedge_timeout_secs: 600,
+        }
+    }
+}
+
+/// Second swap venue next to Sideswap: an OTC desk quoting over HTTP.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Otc {
+    /// Base URL of the desk API. The venue is disabled when empty.
+    pub url: String,
+    pub api_key: String,
+    /// Seconds between checks of unsettled swaps.
+    pub poll_interval_secs: u64,
+}
+
+impl Default for Otc {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            api_key: String::new(),
+            poll_interval_secs: 30,
+        }
+    }
+}
+
+/// Moves settled transactions and their PIX charges out of the live tables.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Archive {
+    pub enabled: bool,
+    /// Transactions created this many months ago or earlier are archived.
+    pub after_months: u32,
+    /// Transactions moved per database transaction.
+    pub batch_size: i64,
+    /// Seconds between archival runs.
+    pub interval_secs: u64,
+}
+
+impl Default for Archive {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            after_months: 12,
+            batch_size: 1000,
+            interval_secs: 24 * 60 * 60,
+        }
+    }
+}
+
+/// Checks of the per-user daily spending projection against the transactions.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Spending {
+    /// Days, counting today, checked on each run; 0 checks the whole history.
+    pub verify_days: u32,
+    /// Seconds between checks.
+    pub interval_secs: u64,
+}
+
+impl Default for Spending {
+    fn default() -> Self {
+        Self {
+            verify_days: 2,
+            interval_secs: 60 * 60,
+        }
+    }
+}
+
+/// Matching of the DEPIX Eulen reports sending against what the wallet
+/// received.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Reconciliation {
+    /// Seconds between runs.
+    pub interval_secs: u64,
+    /// How far apart in time a wallet transaction and Eulen's report may be
+    /// to be matched by amount.
+    pub match_window_secs: i64,
+    /// Seconds after the report before a settlement without DEPIX is
+    /// flagged missing.
+    pub grace_secs: i64,
+    /// Days missing settlements keep being looked for.
+    pub lookback_days: i32,
+}
+
+impl Default for Reconciliation {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10 * 60,
+            match_window_secs: 60 * 60,
+            grace_secs: 2 * 60 * 60,
+            lookback_days: 7,
+        }
+    }
+}
+
+/// Watching of the DEPIX that arrives on the fee addresses handed out per
+/// transaction.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct FeeAddresses {
+    /// Seconds between runs.
+    pub interval_secs: u64,
+    /// Seconds after an address is handed out during which DEPIX arriving on
+    /// it is expected.
+    pub expected_within_secs: i64,
+    /// Days of wallet history looked at.
+    pub lookback_days: i64,
+}
+
+impl Default for FeeAddresses {
+    fn default() -> Self {
+        Self {
+            interval_secs: 5 * 60,
+            expected_within_secs: 24 * 60 * 60,
+            lookback_days: 7,
+        }
+    }
+}
+
+/// Coordination of dealer instances sharing a database. The instance holding
+/// the lock is the leader and the only one moving money.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Leader {
+    /// Without it the instance leads on its own; only one may run.
+    pub enabled: bool,
+    /// Key of the Postgres advisory lock held by the leader.
+    pub lock_id: i64,
+    /// Seconds between attempts to take the lock.
+    pub retry_secs: u64,
+    /// Seconds between checks that the lock connection is alive.
+    pub heartbeat_secs: u64,
+}
+
+impl Default for Leader {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            lock_id: 0x6d6f_6f7a_6564,
+            retry_secs: 5,
+            heartbeat_secs: 5,
+        }
+    }
+}
+
+/// Latency objectives of the external dependencies, reported by
+/// GET /admin/status.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Latency {
+    /// Requests older than this are left out of the summary.
+    pub window_secs: u64,
+    /// Target p95 latency per dependency (`eulen`, `sideswap`, `electrum`,
+    /// `price_providers` and `postgres`), in milliseconds.
+    pub p95_targets_ms: HashMap<String, u64>,
+    /// Share of requests to each dependency that must succeed.
+    pub min_success_rate: f64,
+}
+
+impl Default for Latency {
+    fn default() -> Self {
+        Self {
+            window_secs: 15 * 60,
+            p95_targets_ms: HashMap::from([
+                ("eulen".to_string(), 3_000),
+                ("sideswap".to_string(), 2_000),
+                ("electrum".to_string(), 5_000),
+                ("price_providers".to_string(), 3_000),
+                ("postgres".to_string(), 250),
+            ]),
+            min_success_rate: 0.99,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct Jobs {
+    /// Schedule per job name, replacing its default: a cron expression
+    /// (`minute hour day month weekday`, in UTC) or an interval such as
+    /// `30s`, `5m`, `1h` or `1d`.
+    pub schedules: HashMap<String, String>,
+}
+
+/// Fee rates of the transactions the dealer builds, in sat/vB. Liquid's
+/// minimum relay fee is 0.1 sat/vB.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct NetworkFees {
+    /// Regular payouts.
+    pub normal_sat_vb: f32,
+    /// Payouts that waited for a swap or past the starvation threshold.
+    pub high_sat_vb: f32,
+}
+
+impl Default for NetworkFees {
+    fn default() -> Self {
+        Self {
+            normal_sat_vb: 0.1,
+            high_sat_vb: 0.25,
+        }
+    }
+}
+
+/// Requests handled at once by the services doing most database work.
+/// Requests beyond it wait in the service's channel.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Workers {
+    pub transactions: usize,
+    pub users: usize,
+}
+
+impl Default for Workers {
+    fn default() -> Self {
+        Self {
+            transactions: 4,
+            users: 4,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BrokerKind {
+    Nats,
+    Rabbitmq,
+}
+
+/// Publishing of domain events to an external message broker.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Events {
+    /// Events are not published without a broker.
+    pub broker: Option<BrokerKind>,
+    /// `nats://host:4222`, or the RabbitMQ AMQP listener,
+    /// `amqp://host:5672` (`amqps://` over TLS).
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Connections without TLS are refused.
+    pub tls: bool,
+    /// PEM file of the CA that signed the broker's certificate, when it is
+    /// not one of the system's.
+    pub tls_ca_path: Option<String>,
+    /// NATS subject, or RabbitMQ routing key, of an event is
+    /// `<prefix>.<event kind>`.
+    pub subject_prefix: String,
+    pub exchange: String,
+    pub vhost: String,
+    /// Events kept while the broker is unreachable; the oldest are dropped
+    /// beyond it.
+    pub buffer_size: usize,
+    pub flush_interval_secs: u64,
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        Self {
+            broker: None,
+            url: String::new(),
+            username: None,
+            password: None,
+            tls: false,
+            tls_ca_path: None,
+            subject_prefix: "mooze.dealer".to_string(),
+            exchange: "amq.topic".to_string(),
+            vhost: "/".to_string(),
+            buffer_size: 10_000,
+            flush_interval_secs: 1,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalyticsSink {
+    Posthog,
+    Segment,
+}
+
+/// Product analytics, exported to a PostHog or Segment compatible batch API.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Analytics {
+    /// Events are not tracked without a sink.
+    pub sink: Option<AnalyticsSink>,
+    /// Batch endpoint, e.g. `https://eu.i.posthog.com/batch/` or
+    /// `https://api.segment.io/v1/batch`.
+    pub url: String,
+    /// PostHog project API key, or Segment write key.
+    pub api_key: String,
+    /// Secret mixed into the hashes that replace user and transaction ids.
+    pub id_salt: String,
+    /// Share of users whose events are tracked, from 0 to 1. Users are kept
+    /// or left out as a whole, so their funnels stay complete.
+    pub sample_rate: f64,
+    /// Events kept while the sink is unreachable; the oldest are dropped
+    /// beyond it.
+    pub buffer_size: usize,
+    pub flush_interval_secs: u64,
+}
+
+impl Default for Analytics {
+    fn default() -> Self {
+        Self {
+            sink: None,
+            url: String::new(),
+            api_key: String::new(),
+            id_salt: String::new(),
+            sample_rate: 1.0,
+            buffer_size: 10_000,
+            flush_interval_secs: 10,
+        }
+    }
+}
+
+/// Endpoints and asset ids used when `network = "testnet"`. They replace the
+/// values of the [electrum], [sideswap] and [depix] sections.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Testnet {
+    pub electrum_url: String,
+    pub sideswap_url: String,
+    /// Eulen sandbox API.
+    pub depix_url: String,
+    pub depix_auth_token: Option<String>,
+    pub depix_asset_id: String,
+    pub usdt_asset_id: String,
+    pub lbtc_asset_id: String,
+}
+
+impl Default for Testnet {
+    fn default() -> Self {
+        Self {
+            electrum_url: "blockstream.info:465".to_string(),
+            sideswap_url: "wss://api-testnet.sideswap.io/json-rpc-ws".to_string(),
+            depix_url: String::new(),
+            depix_auth_token: None,
+            depix_asset_id: String::new(),
+            usdt_asset_id: "b612eb46313a2cd6ebabd8b7a8eed5696e29898b87a43bff41c94f51acef9d73"
+                .to_string(),
+            lbtc_asset_id: "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49"
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
 pub struct Settings {
+    /// Liquid network the whole stack runs against.
+    #[serde(default)]
+    pub network: Network,
+    /// Builds and signs payouts and swaps without broadcasting them, so
+    /// captured traffic can be replayed safely.
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub testnet: Testnet,
+    /// Decimals of assets registered with a precision other than 8, by
+    /// asset id.
+    #[serde(default)]
+    pub asset_precisions: HashMap<String, u8>,
     pub postgres: Postgres,
     pub electrum: Electrum,
     pub depix: Depix,
+    #[serde(default)]
+    pub eulen: Eulen,
     pub liquidity: Liquidity,
     pub price_providers: PriceProviders,
+    #[serde(default)]
+    pub quotes: Quotes,
+    #[serde(default)]
+    pub hedging: Hedging,
+    #[serde(default)]
+    pub events: Events,
+    #[serde(default)]
+    pub analytics: Analytics,
+    #[serde(default)]
+    pub workers: Workers,
     pub sideswap: Sideswap,
+    #[serde(default)]
+    pub otc: Otc,
     pub wallet: Wallet,
+    #[serde(default)]
+    pub admin: Admin,
+    #[serde(default)]
+    pub http: Http,
+    #[serde(default)]
+    pub snapshots: Snapshots,
+    #[serde(default)]
+    pub payouts: Payouts,
+    #[serde(default)]
+    pub network_fees: NetworkFees,
+    #[serde(default)]
+    pub notifications: Notifications,
+    #[serde(default)]
+    pub compliance: Compliance,
+    #[serde(default)]
+    pub disputes: Disputes,
+    #[serde(default)]
+    pub availability: Availability,
+    #[serde(default)]
+    pub merchants: Merchants,
+    #[serde(default)]
+    pub archive: Archive,
+    #[serde(default)]
+    pub spending: Spending,
+    #[serde(default)]
+    pub reconciliation: Reconciliation,
+    #[serde(default)]
+    pub fee_addresses: FeeAddresses,
+    #[serde(default)]
+    pub statements: Statements,
+    #[serde(default)]
+    pub tax_reports: TaxReports,
+    #[serde(default)]
+    pub leader: Leader,
+    #[serde(default)]
+    pub jobs: Jobs,
+    #[serde(default)]
+    pub latency: Latency,
 }
 
 impl Settings {
@@ -65,6 +1053,233 @@ impl Settings {
             .add_source(File::with_name(path))
             .build()?;
 
-        config.try_deserialize()
+        let mut settings: Settings = config.try_deserialize()?;
+        settings.apply_network()?;
+        settings.check_asset_precisions()?;
+        settings.check_allowed_origins()?;
+        settings.check_listeners()?;
+        settings.check_request_logging()?;
+        settings.check_availability()?;
+        settings.check_events()?;
+
+        Ok(settings)
+    }
+
+    pub fn asset_ids(&self) -> AssetIds {
+        let asset_ids = match self.network {
+            Network::Mainnet => AssetIds::mainnet(),
+            Network::Testnet => AssetIds {
+                depix: self.testnet.depix_asset_id.clone(),
+                usdt: self.testnet.usdt_asset_id.clone(),
+                lbtc: self.testnet.lbtc_asset_id.clone(),
+                precisions: HashMap::new(),
+            },
+        };
+
+        AssetIds {
+            precisions: self.asset_precisions.clone(),
+            ..asset_ids
+        }
+    }
+
+    /// Liquid assets have at most 8 decimals.
+    fn check_asset_precisions(&self) -> Result<(), ConfigError> {
+        match self
+            .asset_precisions
+            .iter()
+            .find(|(_, precision)| **precision > MAX_PRECISION)
+        {
+            Some((asset_id, precision)) => Err(ConfigError::Message(format!(
+                "asset_precisions.{} = {} exceeds the maximum of {} decimals",
+                asset_id, precision, MAX_PRECISION
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Origins are compared as browsers send them: scheme and host, with
+    /// no path or trailing slash.
+    fn check_allowed_origins(&self) -> Result<(), ConfigError> {
+        let invalid = self.http.allowed_origins.iter().find(|origin| {
+            let origin = origin.as_str();
+            let host = origin
+                .strip_prefix("https://")
+                .or_else(|| origin.strip_prefix("http://"));
+            origin != "*" && host.is_none_or(|host| host.is_empty() || host.contains('/'))
+        });
+
+        match invalid {
+            Some(origin) => Err(ConfigError::Message(format!(
+                "http.allowed_origins: {} is not an origin such as https://app.example.com",
+                origin
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Listeners are TCP addresses or `unix:` socket paths.
+    fn check_listeners(&self) -> Result<(), ConfigError> {
+        if self.http.listen.is_empty() {
+            return Err(ConfigError::Message(
+                "http.listen needs at least one address".to_string(),
+            ));
+        }
+
+        let mut binds = self.http.listen.iter().chain(&self.http.admin_listen);
+        let invalid = binds.find(|bind| match bind.strip_prefix("unix:") {
+            Some(path) => path.is_empty(),
+            None => bind.parse::<std::net::SocketAddr>().is_err(),
+        });
+        if let Some(bind) = invalid {
+            return Err(ConfigError::Message(format!(
+                "http: {} is neither an address such as 0.0.0.0:8080 nor unix:<path>",
+                bind
+            )));
+        }
+
+        if self.http.tls_cert_path.is_some() != self.http.tls_key_path.is_some() {
+            return Err(ConfigError::Message(
+                "http.tls_cert_path and http.tls_key_path must be set together".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// RabbitMQ is reached over AMQP, and over TLS only with `amqps://`.
+    fn check_events(&self) -> Result<(), ConfigError> {
+        let events = &self.events;
+        let schemes: &[&str] = match events.broker {
+            None => return Ok(()),
+            Some(BrokerKind::Nats) => &["nats://", "tls://"],
+            Some(BrokerKind::Rabbitmq) if events.tls => &["amqps://"],
+            Some(BrokerKind::Rabbitmq) => &["amqp://", "amqps://"],
+        };
+        if !schemes.iter().any(|scheme| events.url.starts_with(scheme)) {
+            return Err(ConfigError::Message(format!(
+                "events.url: {} does not start with {}",
+                events.url,
+                schemes.join(" or ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Sample rates are shares of the requests.
+    fn check_request_logging(&self) -> Result<(), ConfigError> {
+        let logging = &self.http.logging;
+        let rates = std::iter::once(("sample_rate", &logging.sample_rate)).chain(
+            logging
+                .route_sample_rates
+                .iter()
+                .map(|(route, rate)| (route.as_str(), rate)),
+        );
+        for (name, rate) in rates {
+            if !(0.0..=1.0).contains(rate) {
+                return Err(ConfigError::Message(format!(
+                    "http.logging: {} = {} is not between 0 and 1",
+                    name, rate
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_availability(&self) -> Result<(), ConfigError> {
+        let availability = &self.availability;
+        if !(-12..=14).contains(&availability.utc_offset_hours) {
+            return Err(ConfigError::Message(format!(
+                "availability: utc_offset_hours = {} is not between -12 and 14",
+                availability.utc_offset_hours
+            )));
+        }
+
+        let business_hours = &availability.business_hours;
+        if business_hours.enabled {
+            let checked = business_hours.weekdays().and_then(|days| {
+                if days.is_empty() {
+                    return Err("no days open".to_string());
+                }
+                business_hours.hours().map(|_| ())
+            });
+            if let Err(e) = checked {
+                return Err(ConfigError::Message(format!(
+                    "availability.business_hours: {}",
+                    e
+                )));
+            }
+        }
+
+        for window in &availability.maintenance {
+            if window.ends_at <= window.starts_at {
+                return Err(ConfigError::Message(format!(
+                    "availability.maintenance: window \"{}\" ends before it starts",
+                    window.reason
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Points every component at the selected network, refusing
+    /// configurations that mix mainnet and testnet settings.
+    fn apply_network(&mut self) -> Result<(), ConfigError> {
+        let is_mainnet = self.network.is_mainnet();
+        let mixed = |setting: &str| {
+            ConfigError::Message(format!(
+                "{} contradicts network = \"{}\"",
+                setting,
+                if is_mainnet { "mainnet" } else { "testnet" }
+            ))
+        };
+
+        if self.wallet.mainnet.is_some_and(|mainnet| mainnet != is_mainnet) {
+            return Err(mixed("wallet.mainnet"));
+        }
+        if self.electrum.testnet.is_some_and(|testnet| testnet == is_mainnet) {
+            return Err(mixed("electrum.testnet"));
+        }
+        self.wallet.mainnet = Some(is_mainnet);
+        self.electrum.testnet = Some(!is_mainnet);
+
+        if is_mainnet {
+            return Ok(());
+        }
+
+        let mainnet_ids = AssetIds::mainnet();
+        let mainnet_ids = [mainnet_ids.depix, mainnet_ids.usdt, mainnet_ids.lbtc];
+        for (name, asset_id) in [
+            ("testnet.depix_asset_id", &self.testnet.depix_asset_id),
+            ("testnet.usdt_asset_id", &self.testnet.usdt_asset_id),
+            ("testnet.lbtc_asset_id", &self.testnet.lbtc_asset_id),
+        ] {
+            if asset_id.is_empty() {
+                return Err(ConfigError::Message(format!("{} is required on testnet", name)));
+            }
+            if mainnet_ids.contains(asset_id) {
+                return Err(mixed(name));
+            }
+        }
+        for (name, url) in [
+            ("testnet.electrum_url", &self.testnet.electrum_url),
+            ("testnet.sideswap_url", &self.testnet.sideswap_url),
+            ("testnet.depix_url", &self.testnet.depix_url),
+        ] {
+            if url.is_empty() {
+                return Err(ConfigError::Message(format!("{} is required on testnet", name)));
+            }
+        }
+
+        self.electrum.url = self.testnet.electrum_url.clone();
+        self.sideswap.url = self.testnet.sideswap_url.clone();
+        self.depix.url = self.testnet.depix_url.clone();
+        if let Some(auth_token) = &self.testnet.depix_auth_token {
+            self.depix.auth_token = auth_token.clone();
+        }
+
+        Ok(())
     }
 }
@@ -1 +1,9 @@
+pub mod clock;
+pub mod cron;
 pub mod json_rpc;
+pub mod latency;
+pub mod liquid_uri;
+pub mod metrics;
+pub mod money;
+pub mod signing;
+pub mod validation;
@@ -0,0 +1,100 @@
+//! Source of the current time for logic that depends on it: daily limits,
+//! quote expiry, payout retries. Services and repositories hold a
+//! `SharedClock`, the system clock outside of tests, so tests can move time
+//! forward instead of waiting.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use std::sync::Arc;
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Day of `now` in UTC.
+    fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// Clock standing still until moved, for tests.
+#[cfg(test)]
+#[derive(Debug)]
+pub struct MockClock {
+    now: std::sync::Mutex<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+impl MockClock {
+    pub fn at(now: DateTime<Utc>) -> Arc<Self> {
+        Arc::new(Self {
+            now: std::sync::Mutex::new(now),
+        })
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    pub fn advance(&self, by: chrono::Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn mock_clock_only_moves_when_told() {
+        let start = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+        let clock = MockClock::at(start);
+        assert_eq!(clock.now(), start);
+        assert_eq!(clock.now(), start);
+
+        clock.advance(chrono::Duration::seconds(90));
+        assert_eq!(clock.now(), start + chrono::Duration::seconds(90));
+
+        clock.set(start);
+        assert_eq!(clock.now(), start);
+    }
+
+    #[test]
+    fn today_changes_at_midnight() {
+        let clock = MockClock::at(Utc.with_ymd_and_hms(2025, 7, 1, 23, 59, 59).unwrap());
+        assert_eq!(clock.today(), NaiveDate::from_ymd_opt(2025, 7, 1).unwrap());
+
+        clock.advance(chrono::Duration::seconds(1));
+        assert_eq!(clock.today(), NaiveDate::from_ymd_opt(2025, 7, 2).unwrap());
+    }
+
+    #[test]
+    fn shared_clocks_see_the_same_time() {
+        let mock = MockClock::at(Utc.with_ymd_and_hms(2025, 7, 1, 0, 0, 0).unwrap());
+        let shared: SharedClock = mock.clone();
+
+        mock.advance(chrono::Duration::days(1));
+        assert_eq!(shared.today(), NaiveDate::from_ymd_opt(2025, 7, 2).unwrap());
+    }
+}
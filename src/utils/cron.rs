@@ -0,0 +1,141 @@
+//! Cron expressions of the job schedules: `minute hour day month weekday`,
+//! evaluated in UTC. Fields take `*`, numbers, ranges `1-5`, steps `*/15` or
+//! `0-30/10`, and comma separated lists of those. Weekdays go from 0
+//! (Sunday) to 6, and 7 is Sunday too.
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+
+/// Matching times are searched this many years ahead, so an expression that
+/// never matches (e.g. February 30th) fails instead of looping.
+const SEARCH_YEARS: i32 = 5;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cron {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// With both restricted, either the day or the weekday matching is
+    /// enough, as in crontab.
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl Cron {
+    pub fn parse(expression: &str) -> Result<Self, anyhow::Error> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            bail!("Cron expression needs 5 fields: {}", expression);
+        };
+
+        let mut weekday_bits = field(weekdays, 0, 7)?;
+        if weekday_bits & (1 << 7) != 0 {
+            weekday_bits = (weekday_bits | 1) & !(1 << 7);
+        }
+
+        let cron = Self {
+            expression: expression.to_string(),
+            minutes: field(minutes, 0, 59)?,
+            hours: field(hours, 0, 23)?,
+            days: field(days, 1, 31)?,
+            months: field(months, 1, 12)?,
+            weekdays: weekday_bits,
+            days_restricted: days != "*",
+            weekdays_restricted: weekdays != "*",
+        };
+
+        if cron.next_after(Utc::now()).is_none() {
+            bail!("Cron expression never matches: {}", expression);
+        }
+
+        Ok(cron)
+    }
+
+    /// First matching minute strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let until = after.year() + SEARCH_YEARS;
+
+        while time.year() <= until {
+            if !matches(self.months, time.month()) {
+                let next_month = time.date_naive().with_day(1)? + chrono::Months::new(1);
+                time = next_month.and_hms_opt(0, 0, 0)?.and_utc();
+            } else if !self.matches_day(time) {
+                time = (time.date_naive() + chrono::Days::new(1))
+                    .and_hms_opt(0, 0, 0)?
+                    .and_utc();
+            } else if !matches(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !matches(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+
+        None
+    }
+
+    fn matches_day(&self, time: DateTime<Utc>) -> bool {
+        let day = matches(self.days, time.day());
+        let weekday = matches(self.weekdays, time.weekday().num_days_from_sunday());
+
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, true) => weekday,
+            (false, false) => true,
+        }
+    }
+}
+
+impl std::fmt::Display for Cron {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+fn matches(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+/// Values of a field as bits, checked against `min..=max`.
+fn field(field: &str, min: u32, max: u32) -> Result<u64, anyhow::Error> {
+    let mut bits = 0;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, number(step)?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            bail!("Invalid step in cron field: {}", field);
+        }
+
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (number(start)?, number(end)?),
+            // `5/15` runs from 5 to the end of the range
+            None if step > 1 => (number(range)?, max),
+            None => (number(range)?, number(range)?),
+        };
+        if start < min || end > max || start > end {
+            bail!("Cron field out of range {}-{}: {}", min, max, field);
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+fn number(value: &str) -> Result<u32, anyhow::Error> {
+    value
+        .parse()
+        .map_err(|_| anyhow!("Invalid number in cron field: {}", value))
+}
@@ -0,0 +1,186 @@
+//! Latency and success rate of the services the dealer depends on. Every
+//! request is observed in `dependency_request_duration_seconds` and counted
+//! in `dependency_requests_total`; the most recent ones are kept to compare
+//! against the objectives of `[latency]`.
+
+use crate::models::health::DependencyLatency;
+use crate::settings;
+use crate::utils::metrics;
+
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Requests kept per dependency, however short the window.
+const MAX_SAMPLES: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dependency {
+    Eulen,
+    Sideswap,
+    Electrum,
+    PriceProviders,
+    Postgres,
+}
+
+impl Dependency {
+    pub const ALL: [Dependency; 5] = [
+        Dependency::Eulen,
+        Dependency::Sideswap,
+        Dependency::Electrum,
+        Dependency::PriceProviders,
+        Dependency::Postgres,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dependency::Eulen => "eulen",
+            Dependency::Sideswap => "sideswap",
+            Dependency::Electrum => "electrum",
+            Dependency::PriceProviders => "price_providers",
+            Dependency::Postgres => "postgres",
+        }
+    }
+}
+
+struct Sample {
+    at: Instant,
+    elapsed: Duration,
+    ok: bool,
+}
+
+#[derive(Default)]
+struct Tracker {
+    settings: OnceLock<settings::Latency>,
+    samples: DashMap<&'static str, VecDeque<Sample>>,
+}
+
+static TRACKER: OnceLock<Tracker> = OnceLock::new();
+
+fn tracker() -> &'static Tracker {
+    TRACKER.get_or_init(Tracker::default)
+}
+
+fn settings() -> &'static settings::Latency {
+    tracker().settings.get_or_init(settings::Latency::default)
+}
+
+/// Sets the objectives of `[latency]`. Called once at startup, before any
+/// request is tracked; the defaults are used otherwise.
+pub fn configure(settings: settings::Latency) {
+    let _ = tracker().settings.set(settings);
+}
+
+/// Runs `request` against `dependency`, recording how long it took and
+/// whether it succeeded.
+pub async fn track<T, E>(
+    dependency: Dependency,
+    request: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let started = Instant::now();
+    let result = request.await;
+    record(dependency, started.elapsed(), result.is_ok());
+    result
+}
+
+/// Like `track`, for clients that block, such as Electrum's.
+pub fn track_blocking<T, E>(
+    dependency: Dependency,
+    request: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let started = Instant::now();
+    let result = request();
+    record(dependency, started.elapsed(), result.is_ok());
+    result
+}
+
+fn record(dependency: Dependency, elapsed: Duration, ok: bool) {
+    let name = dependency.as_str();
+    let outcome = if ok { "success" } else { "failure" };
+    metrics::observe(
+        "dependency_request_duration_seconds",
+        &[("dependency", name)],
+        elapsed.as_secs_f64(),
+    );
+    metrics::increment(
+        "dependency_requests_total",
+        &[("dependency", name), ("outcome", outcome)],
+    );
+
+    let now = Instant::now();
+    let window = Duration::from_secs(settings().window_secs);
+    let mut samples = tracker().samples.entry(name).or_default();
+    while samples.len() >= MAX_SAMPLES
+        || samples
+            .front()
+            .is_some_and(|sample| now.duration_since(sample.at) > window)
+    {
+        samples.pop_front();
+    }
+    samples.push_back(Sample {
+        at: now,
+        elapsed,
+        ok,
+    });
+}
+
+/// Latency and success rate of every dependency over the window, against
+/// its objective.
+pub fn summaries() -> Vec<DependencyLatency> {
+    let settings = settings();
+    let window = Duration::from_secs(settings.window_secs);
+    let now = Instant::now();
+
+    Dependency::ALL
+        .iter()
+        .map(|dependency| {
+            let name = dependency.as_str();
+            let (mut latencies, successes) = match tracker().samples.get(name) {
+                Some(samples) => {
+                    let recent: Vec<&Sample> = samples
+                        .iter()
+                        .filter(|sample| now.duration_since(sample.at) <= window)
+                        .collect();
+                    let successes = recent.iter().filter(|sample| sample.ok).count();
+                    let latencies: Vec<Duration> =
+                        recent.iter().map(|sample| sample.elapsed).collect();
+                    (latencies, successes)
+                }
+                None => (Vec::new(), 0),
+            };
+            latencies.sort();
+
+            let requests = latencies.len();
+            let success_rate = (requests > 0).then(|| successes as f64 / requests as f64);
+            let p95_ms = percentile_ms(&latencies, 95);
+            let p95_target_ms = settings.p95_targets_ms.get(name).copied();
+            let within_slo = success_rate.map(|success_rate| {
+                success_rate >= settings.min_success_rate
+                    && p95_target_ms.is_none_or(|target| p95_ms.is_some_and(|p95| p95 <= target))
+            });
+
+            DependencyLatency {
+                dependency: name,
+                requests,
+                success_rate,
+                p50_ms: percentile_ms(&latencies, 50),
+                p95_ms,
+                max_ms: latencies.last().map(|max| max.as_millis() as u64),
+                p95_target_ms,
+                within_slo,
+            }
+        })
+        .collect()
+}
+
+/// Nearest-rank percentile of sorted latencies.
+fn percentile_ms(sorted: &[Duration], percentile: usize) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let rank = (sorted.len() * percentile).div_ceil(100).max(1);
+    Some(sorted[rank - 1].as_millis() as u64)
+}
@@ -0,0 +1,92 @@
+//! Liquid BIP21 payment URIs, e.g.
+//! `liquidnetwork:<address>?amount=0.001&assetid=<asset id>`, as copied from
+//! other wallets into the payout address.
+
+use super::money;
+
+const SCHEMES: [&str; 2] = ["liquidnetwork", "liquidtestnet"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentUri {
+    pub address: String,
+    pub asset_id: Option<String>,
+    /// In base units of the asset.
+    pub amount: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum UriError {
+    #[error("Unknown URI scheme")]
+    Scheme,
+    #[error("Missing address")]
+    Address,
+    #[error("Invalid amount")]
+    Amount,
+    #[error("Invalid asset id")]
+    AssetId,
+    #[error("Unsupported required parameter {0}")]
+    Required(String),
+}
+
+impl PaymentUri {
+    /// Parses a payment URI, or takes `value` as a bare address when it has
+    /// no scheme. The amount is converted with `precision`, the decimals of
+    /// the asset paid.
+    pub fn parse(value: &str, precision: u8) -> Result<Self, UriError> {
+        let value = value.trim();
+        let Some((scheme, rest)) = value.split_once(':') else {
+            return Ok(Self {
+                address: value.to_string(),
+                asset_id: None,
+                amount: None,
+            });
+        };
+        if !SCHEMES
+            .iter()
+            .any(|known| scheme.eq_ignore_ascii_case(known))
+        {
+            return Err(UriError::Scheme);
+        }
+
+        let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+        if address.is_empty() {
+            return Err(UriError::Address);
+        }
+
+        let mut uri = Self {
+            address: address.to_string(),
+            asset_id: None,
+            amount: None,
+        };
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            match key {
+                "amount" => uri.amount = Some(parse_amount(value, precision.into())?),
+                "assetid" => uri.asset_id = Some(parse_asset_id(value)?),
+                // Parameters a wallet must understand to pay
+                key if key.starts_with("req-") => return Err(UriError::Required(key.to_string())),
+                // label, message and the like
+                _ => {}
+            }
+        }
+
+        Ok(uri)
+    }
+}
+
+/// Decimal amount of whole units, converted without going through floats.
+fn parse_amount(value: &str, decimals: u32) -> Result<u64, UriError> {
+    match money::parse_decimal(value, decimals) {
+        Some(amount) if amount > 0 => Ok(amount),
+        _ => Err(UriError::Amount),
+    }
+}
+
+fn parse_asset_id(value: &str) -> Result<String, UriError> {
+    let asset_id = value.to_ascii_lowercase();
+    if asset_id.len() != 64 || !asset_id.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(UriError::AssetId);
+    }
+
+    Ok(asset_id)
+}
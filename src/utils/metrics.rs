@@ -0,0 +1,162 @@
+use dashmap::DashMap;
+use std::fmt::Write;
+use std::sync::OnceLock;
+
+/// Upper bounds used by every histogram. Values are in seconds for latencies
+/// and wait times, which is all we observe today.
+const BUCKETS: [f64; 12] = [
+    0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 15.0, 60.0, 300.0, 900.0, 3600.0, 14400.0,
+];
+
+type Key = (String, String);
+
+#[derive(Clone, Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Default)]
+struct Registry {
+    counters: DashMap<Key, u64>,
+    gauges: DashMap<Key, f64>,
+    histograms: DashMap<Key, Histogram>,
+}
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(Registry::default)
+}
+
+fn key(name: &str, labels: &[(&str, &str)]) -> Key {
+    let labels = labels
+        .iter()
+        .map(|(label, value)| format!("{}=\"{}\"", label, value.replace('"', "\\\"")))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    (name.to_string(), labels)
+}
+
+pub fn increment(name: &str, labels: &[(&str, &str)]) {
+    add(name, labels, 1);
+}
+
+pub fn add(name: &str, labels: &[(&str, &str)], value: u64) {
+    *registry().counters.entry(key(name, labels)).or_insert(0) += value;
+}
+
+pub fn set_gauge(name: &str, labels: &[(&str, &str)], value: f64) {
+    registry().gauges.insert(key(name, labels), value);
+}
+
+pub fn observe(name: &str, labels: &[(&str, &str)], value: f64) {
+    let mut histogram = registry()
+        .histograms
+        .entry(key(name, labels))
+        .or_default();
+
+    for (index, bound) in BUCKETS.iter().enumerate() {
+        if value <= *bound {
+            histogram.buckets[index] += 1;
+        }
+    }
+    histogram.sum += value;
+    histogram.count += 1;
+}
+
+/// Value of a gauge, if it was ever set.
+pub fn gauge(name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+    registry()
+        .gauges
+        .get(&key(name, labels))
+        .map(|value| *value)
+}
+
+/// Count and sum of a histogram's observations across every label set.
+pub fn histogram_totals(name: &str) -> (u64, f64) {
+    registry()
+        .histograms
+        .iter()
+        .filter(|entry| entry.key().0 == name)
+        .fold((0, 0.0), |(count, sum), entry| {
+            (count + entry.value().count, sum + entry.value().sum)
+        })
+}
+
+/// Renders every metric in the Prometheus text exposition format.
+pub fn render() -> String {
+    let mut output = String::new();
+
+    let mut counters: Vec<(Key, u64)> = registry()
+        .counters
+        .iter()
+        .map(|entry| (entry.key().clone(), *entry.value()))
+        .collect();
+    counters.sort_by(|a, b| a.0.cmp(&b.0));
+    for ((name, labels), value) in counters {
+        let _ = writeln!(output, "{} {}", series(&name, &labels, None), value);
+    }
+
+    let mut gauges: Vec<(Key, f64)> = registry()
+        .gauges
+        .iter()
+        .map(|entry| (entry.key().clone(), *entry.value()))
+        .collect();
+    gauges.sort_by(|a, b| a.0.cmp(&b.0));
+    for ((name, labels), value) in gauges {
+        let _ = writeln!(output, "{} {}", series(&name, &labels, None), value);
+    }
+
+    let mut histograms: Vec<(Key, Histogram)> = registry()
+        .histograms
+        .iter()
+        .map(|entry| (entry.key().clone(), entry.value().clone()))
+        .collect();
+    histograms.sort_by(|a, b| a.0.cmp(&b.0));
+    for ((name, labels), histogram) in histograms {
+        let bucket_name = format!("{}_bucket", name);
+        for (index, bound) in BUCKETS.iter().enumerate() {
+            let le = format!("le=\"{}\"", bound);
+            let _ = writeln!(
+                output,
+                "{} {}",
+                series(&bucket_name, &labels, Some(&le)),
+                histogram.buckets[index]
+            );
+        }
+        let _ = writeln!(
+            output,
+            "{} {}",
+            series(&bucket_name, &labels, Some("le=\"+Inf\"")),
+            histogram.count
+        );
+        let _ = writeln!(
+            output,
+            "{} {}",
+            series(&format!("{}_sum", name), &labels, None),
+            histogram.sum
+        );
+        let _ = writeln!(
+            output,
+            "{} {}",
+            series(&format!("{}_count", name), &labels, None),
+            histogram.count
+        );
+    }
+
+    output
+}
+
+fn series(name: &str, labels: &str, extra: Option<&str>) -> String {
+    let labels = match (labels.is_empty(), extra) {
+        (true, None) => return name.to_string(),
+        (true, Some(extra)) => extra.to_string(),
+        (false, None) => labels.to_string(),
+        (false, Some(extra)) => format!("{},{}", labels, extra),
+    };
+
+    format!("{}{{{}}}", name, labels)
+}
@@ -0,0 +1,116 @@
+//! Amounts as people read them. Money is kept in BRL cents and assets in
+//! base units everywhere; these convert at the edges, without going through
+//! floats except for prices, which arrive as floats.
+//!
+//! Amounts shown to users are in the Brazilian format: `.` groups the
+//! thousands and `,` marks the decimals, as in `R$ 1.234,56`.
+
+/// `cents` as shown to users, e.g. `R$ 1.234,56` or `-R$ 0,50`.
+pub fn format_brl(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+
+    format!(
+        "{}R$ {},{:02}",
+        sign,
+        group_thousands(cents / 100),
+        cents % 100
+    )
+}
+
+/// `units` with `decimals` decimal places and a decimal comma, without
+/// grouping, as files and reports take them.
+pub fn decimal(units: i64, decimals: u32) -> String {
+    let scale = 10_u64.pow(decimals);
+    let sign = if units < 0 { "-" } else { "" };
+    let units = units.unsigned_abs();
+
+    if decimals == 0 {
+        return format!("{}{}", sign, units);
+    }
+    format!(
+        "{}{},{:0width$}",
+        sign,
+        units / scale,
+        units % scale,
+        width = decimals as usize
+    )
+}
+
+/// Decimal amount with a `.` separator, such as `12.5`, in units of
+/// `decimals` decimal places. None when it isn't a plain positive decimal or
+/// has more places than that.
+pub fn parse_decimal(value: &str, decimals: u32) -> Option<u64> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || fraction.len() > decimals as usize || !digits(whole) || !digits(fraction)
+    {
+        return None;
+    }
+
+    format!("{}{:0<width$}", whole, fraction, width = decimals as usize)
+        .parse()
+        .ok()
+}
+
+/// A price or amount in reais to cents, rounded to the nearest cent with
+/// halves away from zero.
+pub fn to_cents(reais: f64) -> i64 {
+    (reais * 100.0).round() as i64
+}
+
+fn group_thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brl_groups_thousands_with_a_decimal_comma() {
+        assert_eq!(format_brl(0), "R$ 0,00");
+        assert_eq!(format_brl(5), "R$ 0,05");
+        assert_eq!(format_brl(99_999), "R$ 999,99");
+        assert_eq!(format_brl(123_456), "R$ 1.234,56");
+        assert_eq!(format_brl(100_000_000), "R$ 1.000.000,00");
+        assert_eq!(format_brl(-50), "-R$ 0,50");
+        assert_eq!(format_brl(i64::MIN), "-R$ 92.233.720.368.547.758,08");
+    }
+
+    #[test]
+    fn decimals_keep_every_place() {
+        assert_eq!(decimal(123_456, 2), "1234,56");
+        assert_eq!(decimal(-5, 2), "-0,05");
+        assert_eq!(decimal(12_000, 8), "0,00012000");
+        assert_eq!(decimal(42, 0), "42");
+    }
+
+    #[test]
+    fn decimals_parse_to_units() {
+        assert_eq!(parse_decimal("12.5", 2), Some(1250));
+        assert_eq!(parse_decimal("0.00000001", 8), Some(1));
+        assert_eq!(parse_decimal("7", 0), Some(7));
+        assert_eq!(parse_decimal("1.234", 2), None);
+        assert_eq!(parse_decimal(".5", 2), None);
+        assert_eq!(parse_decimal("1,5", 2), None);
+        assert_eq!(parse_decimal("-1", 2), None);
+    }
+
+    #[test]
+    fn cents_round_half_away_from_zero() {
+        assert_eq!(to_cents(5.994_9), 599);
+        assert_eq!(to_cents(0.125), 13);
+        assert_eq!(to_cents(-0.125), -13);
+        // 0.29 * 100 is 28.999... in floating point
+        assert_eq!(to_cents(0.29), 29);
+    }
+}
@@ -0,0 +1,49 @@
+//! Signatures of documents the dealer hands out, such as user statements, so
+//! anyone holding the dealer's public key can check they were not altered.
+
+use std::str::FromStr;
+
+use anyhow::Context;
+use lwk_wollet::elements::hashes::{sha256, Hash};
+use lwk_wollet::elements::secp256k1_zkp::{All, Message, PublicKey, Secp256k1, SecretKey};
+
+#[derive(Clone)]
+pub struct DocumentSigner {
+    secp: Secp256k1<All>,
+    secret_key: SecretKey,
+}
+
+/// ECDSA signature, DER encoded in hex, over the SHA-256 of a document.
+pub struct DocumentSignature {
+    pub sha256: String,
+    pub signature: String,
+    pub public_key: String,
+}
+
+impl DocumentSigner {
+    /// Signer of a secp256k1 secret key in hex.
+    pub fn from_hex(secret_key: &str) -> Result<Self, anyhow::Error> {
+        let secret_key = SecretKey::from_str(secret_key).context("Invalid signing key")?;
+
+        Ok(Self {
+            secp: Secp256k1::new(),
+            secret_key,
+        })
+    }
+
+    pub fn public_key(&self) -> String {
+        PublicKey::from_secret_key(&self.secp, &self.secret_key).to_string()
+    }
+
+    pub fn sign(&self, document: &[u8]) -> DocumentSignature {
+        let digest = sha256::Hash::hash(document);
+        let message = Message::from_digest(digest.to_byte_array());
+        let signature = self.secp.sign_ecdsa(&message, &self.secret_key);
+
+        DocumentSignature {
+            sha256: digest.to_string(),
+            signature: signature.to_string(),
+            public_key: self.public_key(),
+        }
+    }
+}
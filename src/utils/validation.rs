@@ -0,0 +1,191 @@
+//! Field checks shared by the HTTP layer, which rejects invalid bodies with
+//! the fields at fault, and the services, which check again before acting.
+
+use serde::Serialize;
+
+use super::liquid_uri::PaymentUri;
+use crate::models::transactions::Assets;
+
+/// Largest amount a single deposit, payment link or schedule may ask for,
+/// R$ 1,000,000. Lower limits are applied per user by the services.
+pub const MAX_AMOUNT_IN_CENTS: i32 = 100_000_000;
+const MAX_TEXT_LEN: usize = 256;
+const MAX_ADDRESS_LEN: usize = 128;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: &'static str,
+}
+
+pub trait Validate {
+    fn validate(&self) -> Result<(), Vec<FieldError>>;
+}
+
+/// Fails with every check that did not pass.
+pub fn collect(
+    checks: impl IntoIterator<Item = Option<FieldError>>,
+) -> Result<(), Vec<FieldError>> {
+    let errors: Vec<FieldError> = checks.into_iter().flatten().collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn error(field: &'static str, message: &'static str) -> Option<FieldError> {
+    Some(FieldError { field, message })
+}
+
+pub fn amount(field: &'static str, value: i32) -> Option<FieldError> {
+    if value <= 0 {
+        error(field, "O valor deve ser maior que zero.")
+    } else if value > MAX_AMOUNT_IN_CENTS {
+        error(field, "Valor acima do máximo permitido.")
+    } else {
+        None
+    }
+}
+
+/// Asset ids are 32 bytes in lowercase hex.
+pub fn asset(field: &'static str, value: &str) -> Option<FieldError> {
+    let is_hex = value
+        .bytes()
+        .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    if value.len() != 64 || !is_hex {
+        error(field, "Ativo inválido.")
+    } else {
+        None
+    }
+}
+
+/// An asset id, or the ticker of an asset sold.
+pub fn asset_or_ticker(field: &'static str, value: &str) -> Option<FieldError> {
+    if Assets::from_ticker(value).is_ok() {
+        None
+    } else {
+        asset(field, value)
+    }
+}
+
+pub fn address(field: &'static str, value: &str) -> Option<FieldError> {
+    if value.is_empty() {
+        error(field, "Endereço obrigatório.")
+    } else if value.len() > MAX_ADDRESS_LEN || !value.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        error(field, "Endereço inválido.")
+    } else {
+        None
+    }
+}
+
+/// A bare address or a Liquid payment URI, whose asset must be `asset`.
+pub fn payment_address(field: &'static str, value: &str, asset: &str) -> Option<FieldError> {
+    if value.len() > MAX_TEXT_LEN {
+        return error(field, "Endereço inválido.");
+    }
+
+    match PaymentUri::parse(value, Assets::precision_of(asset)) {
+        Err(_) => error(field, "URI de pagamento inválida."),
+        Ok(uri)
+            if uri
+                .asset_id
+                .as_deref()
+                .is_some_and(|asset_id| asset_id != asset) =>
+        {
+            error(
+                field,
+                "O ativo da URI de pagamento não corresponde ao ativo do depósito.",
+            )
+        }
+        Ok(uri) => address(field, &uri.address),
+    }
+}
+
+/// Payouts are only made on Liquid.
+pub fn network(field: &'static str, value: &str) -> Option<FieldError> {
+    if value != "liquid" {
+        error(field, "Rede não suportada.")
+    } else {
+        None
+    }
+}
+
+pub fn required(field: &'static str, value: &str) -> Option<FieldError> {
+    if value.trim().is_empty() {
+        error(field, "Campo obrigatório.")
+    } else {
+        optional(field, Some(value))
+    }
+}
+
+pub fn optional(field: &'static str, value: Option<&str>) -> Option<FieldError> {
+    match value {
+        Some(value) if value.len() > MAX_TEXT_LEN => error(field, "Texto muito longo."),
+        _ => None,
+    }
+}
+
+pub fn email(field: &'static str, value: &str) -> Option<FieldError> {
+    let valid = match value.split_once('@') {
+        Some((user, domain)) => !user.is_empty() && domain.contains('.') && !domain.contains('@'),
+        None => false,
+    };
+    if !valid || value.len() > MAX_TEXT_LEN || value.chars().any(char::is_whitespace) {
+        error(field, "E-mail inválido.")
+    } else {
+        None
+    }
+}
+
+/// Phone numbers in E.164, e.g. +5511999999999.
+pub fn phone(field: &'static str, value: &str) -> Option<FieldError> {
+    let valid = match value.strip_prefix('+') {
+        Some(digits) => {
+            (8..=15).contains(&digits.len()) && digits.bytes().all(|b| b.is_ascii_digit())
+        }
+        None => false,
+    };
+    if !valid {
+        error(field, "Telefone inválido, use o formato +5511999999999.")
+    } else {
+        None
+    }
+}
+
+/// Periods end after they start.
+pub fn period(
+    field: &'static str,
+    starts_at: chrono::DateTime<chrono::Utc>,
+    ends_at: chrono::DateTime<chrono::Utc>,
+) -> Option<FieldError> {
+    if ends_at <= starts_at {
+        error(field, "O fim deve ser depois do início.")
+    } else {
+        None
+    }
+}
+
+pub fn bps(field: &'static str, value: i32) -> Option<FieldError> {
+    if !(0..=10_000).contains(&value) {
+        error(field, "Deve estar entre 0 e 10000.")
+    } else {
+        None
+    }
+}
+
+/// Checks of a deposit paying out `amount_in_cents` of `asset` to `address`,
+/// which may be a Liquid payment URI.
+pub fn deposit(
+    address: &str,
+    amount_in_cents: i32,
+    asset: &str,
+    network: &str,
+) -> [Option<FieldError>; 4] {
+    [
+        payment_address("address", address, asset),
+        amount("amount_in_cents", amount_in_cents),
+        self::asset("asset", asset),
+        self::network("network", network),
+    ]
+}